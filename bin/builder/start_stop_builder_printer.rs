@@ -1,7 +1,6 @@
 use std::time::Instant;
 
 use syact::prelude::*;
-use syact::tests::SimulatedController;
 
 pub fn prompt<T : core::str::FromStr + Copy>(msg : &str, default_opt : Option<T>) -> T {
     let mut input_string = String::new();
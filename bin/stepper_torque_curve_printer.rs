@@ -23,6 +23,16 @@ pub fn prompt<T : core::str::FromStr + Copy>(msg : &str, default_opt : Option<T>
     }
 }
 
+pub fn prompt_opt<T : core::str::FromStr + Copy>(msg : &str) -> Option<T> {
+    let mut input_string = String::new();
+
+    print!("{}", msg);
+    std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    std::io::stdin().read_line(&mut input_string).unwrap();
+
+    T::from_str(input_string.trim()).ok()
+}
+
 fn main() {
     // Print out header
     println!();
@@ -49,7 +59,9 @@ fn main() {
 
     let config = StepperConfig {
         voltage: prompt("Voltage (V - default: 12V): ", Some(12.0)),
-        overload_current: None
+        overload_current: None,
+        drive_current: prompt_opt("Driver current (Amp - optional): "),
+        velocity_margin: VelocityMargin::None
     };
 
     // Print out data
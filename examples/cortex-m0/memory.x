@@ -0,0 +1,6 @@
+/* STM32F030K6, 32 KB of flash and 4 KB of RAM */
+MEMORY
+{
+  FLASH : ORIGIN = 0x08000000, LENGTH = 32K
+  RAM : ORIGIN = 0x20000000, LENGTH = 4K
+}
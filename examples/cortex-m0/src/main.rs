@@ -0,0 +1,129 @@
+//! Drives a stepper motor back and forth on an STM32F030K6 (Cortex-M0, 32 KB flash, 4 KB RAM) with the minimal motion path
+//! of syact, no allocator required
+//!
+//! - PA0: Direction pin of the driver
+//! - PA1: Step pin of the driver
+//!
+//! Build with `cargo build --release` inside this directory, `scripts/size_check.sh` checks the size of the binary.
+
+#![no_std]
+#![no_main]
+
+use core::convert::Infallible;
+use core::ptr::{read_volatile, write_volatile};
+
+use cortex_m::delay::Delay;
+use cortex_m_rt::entry;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, OutputPin, PinState};
+use panic_halt as _;
+
+use syact::prelude::*;
+
+// Registers
+const RCC_AHBENR : *mut u32 = 0x4002_1014 as *mut u32;
+const GPIOA_MODER : *mut u32 = 0x4800_0000 as *mut u32;
+const GPIOA_BSRR : *mut u32 = 0x4800_0018 as *mut u32;
+
+/// The clock after reset, the internal 8 MHz oscillator
+const SYSCLK : u32 = 8_000_000;
+
+// ###############
+// #    GpioA    #
+// ###############
+    /// A pin of the GPIO port A, configured as push-pull output
+    struct GpioA {
+        pin : u8
+    }
+
+    impl GpioA {
+        fn output(pin : u8) -> Self {
+            unsafe {
+                // Enable the clock of the port and switch the pin to output mode
+                write_volatile(RCC_AHBENR, read_volatile(RCC_AHBENR) | (1 << 17));
+                write_volatile(GPIOA_MODER, (read_volatile(GPIOA_MODER) & !(0b11 << (pin * 2))) | (0b01 << (pin * 2)));
+            }
+
+            Self { pin }
+        }
+    }
+
+    impl ErrorType for GpioA {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for GpioA {
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            unsafe { write_volatile(GPIOA_BSRR, 1 << self.pin) };
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            unsafe { write_volatile(GPIOA_BSRR, 1 << (self.pin + 16)) };
+            Ok(())
+        }
+    }
+//
+
+// #######################
+// #    PinController    #
+// #######################
+    /// Bitbangs the step signal, timed with the SysTick delay
+    struct PinController {
+        pin_dir : GpioA,
+        pin_step : GpioA,
+        delay : Delay,
+
+        direction : Direction
+    }
+
+    impl StepperController for PinController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            let half_ns = (time.0 * 0.5e9) as u32;
+
+            self.pin_step.set_high().map_err(|_| ActuatorError::IOError)?;
+            self.delay.delay_ns(half_ns);
+            self.pin_step.set_low().map_err(|_| ActuatorError::IOError)?;
+            self.delay.delay_ns(half_ns);
+
+            Ok(())
+        }
+
+        fn direction(&self) -> Direction {
+            self.direction
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.pin_dir.set_state(PinState::from(dir.as_bool())).map_err(|_| ActuatorError::IOError)?;
+            self.direction = dir;
+            Ok(())
+        }
+
+        fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            self.delay.delay_ns((time.0 * 1e9) as u32);
+            Ok(())
+        }
+    }
+//
+
+#[entry]
+fn main() -> ! {
+    let core = cortex_m::Peripherals::take().unwrap();
+
+    let ctrl = PinController {
+        pin_dir: GpioA::output(0),
+        pin_step: GpioA::output(1),
+        delay: Delay::new(core.SYST, SYSCLK),
+
+        direction: Direction::default()
+    };
+
+    let builder = StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    let mut stepper = MinimalStepper::new(builder, ctrl);
+
+    loop {
+        // One revolution forward and back again
+        stepper.drive_rel(Radians(6.283), Factor::MAX).unwrap();
+        stepper.drive_rel(Radians(-6.283), Factor::MAX).unwrap();
+    }
+}
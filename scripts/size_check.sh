@@ -0,0 +1,15 @@
+# Builds the Cortex-M0 example and fails if the flashed image (text + data) exceeds 32 KB
+#
+# Requires the `thumbv6m-none-eabi` target and `cargo-binutils` (`cargo size`)
+LIMIT=32768
+
+cd examples/cortex-m0
+cargo build --release || exit 1
+
+SIZE=$(cargo size --release -- -A | awk '$1 ~ /^\.(vector_table|text|rodata|data)$/ { sum += $2 } END { print sum }')
+echo "Flash usage of the Cortex-M0 example: $SIZE of $LIMIT bytes"
+
+if [ "$SIZE" -ge "$LIMIT" ]; then
+    echo "The minimal motion path does not fit into 32 KB of flash anymore"
+    exit 1
+fi
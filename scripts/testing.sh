@@ -1,4 +1,5 @@
 cargo test --verbose --features testing
+cargo test --verbose --no-default-features --features testing
 
 cd syact_std
 cargo test --verbose 
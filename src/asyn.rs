@@ -2,6 +2,13 @@ use syunit::*;
 
 use crate::ActuatorError;
 
+// ####################
+// #    SUBMODULES    #
+// ####################
+    mod pwm;
+    pub use pwm::PwmDevice;
+//
+
 /// A component which is asynchronous because of its hardware properties, e.g. a simple DC-Motors
 pub trait AsyncActuator<U : UnitSet> {
     /// Starts the movement process of the component in the given direction with a given `speed` factor
@@ -9,4 +16,4 @@ pub trait AsyncActuator<U : UnitSet> {
 
     /// Start the movement process of the component with the given velocity `speed`, positive values for `speed` mean CW movement
     fn drive_speed(&mut self, speed : U::Velocity) -> Result<(), ActuatorError<U>>;
-}
\ No newline at end of file
+}
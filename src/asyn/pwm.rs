@@ -0,0 +1,191 @@
+use alloc::boxed::Box;
+
+use syunit::*;
+
+use crate::{ActuatorError, AsyncActuator};
+use crate::io::{DigitalOutput, PwmOutput};
+
+/// A device driven by a PWM output, e.g. a coolant pump, a fan or a spindle without step and direction signals
+///
+/// Without feedback the device is driven open-loop, the duty cycle is proportional to the requested velocity. With a 
+/// tachometer attached (see [PwmDevice::with_tach]), velocities requested with [AsyncActuator::drive_speed] are held 
+/// closed-loop by a PI controller, compensating loads and supply variations.
+///
+/// The device has to be polled periodically with the time passed since the last poll to update the closed-loop control.
+pub struct PwmDevice<O : PwmOutput, U : UnitSet = Rotary> {
+    output : O,
+    dir_output : Option<Box<dyn DigitalOutput + Send>>,
+    tach : Option<Box<dyn FnMut() -> Option<U::Velocity> + Send>>,
+
+    /// The velocity of the device at full duty cycle without any load
+    pub velocity_max : U::Velocity,
+    /// Proportional gain of the velocity control, duty cycle per relative velocity error
+    pub gain_p : f32,
+    /// Integral gain of the velocity control, duty cycle per relative velocity error and second
+    pub gain_i : f32,
+
+    _duty : Factor,
+    _direction : Direction,
+    _target : Option<U::Velocity>,
+    _velocity : Option<U::Velocity>,
+    _integral : f32
+}
+
+impl<O : PwmOutput, U : UnitSet> PwmDevice<O, U> {
+    /// Creates a new open-loop device driving only in CW direction, reaching `velocity_max` at full duty cycle
+    pub fn new(output : O, velocity_max : U::Velocity) -> Self {
+        Self {
+            output,
+            dir_output: None,
+            tach: None,
+
+            velocity_max: velocity_max.abs(),
+            gain_p: 0.5,
+            gain_i: 2.0,
+
+            _duty: Factor::MIN,
+            _direction: Direction::CW,
+            _target: None,
+            _velocity: None,
+            _integral: 0.0
+        }
+    }
+
+    /// Adds a `tach` closure delivering the measured velocity of the device, enabling the closed-loop velocity hold.
+    /// `None` values are skipped
+    pub fn with_tach(mut self, tach : Box<dyn FnMut() -> Option<U::Velocity> + Send>) -> Self {
+        self.tach = Some(tach);
+        self
+    }
+
+    /// Adds an output selecting the direction of the device, CW being a high signal. Devices without it only drive CW
+    pub fn with_dir_output(mut self, dir_output : Box<dyn DigitalOutput + Send>) -> Self {
+        self.dir_output = Some(dir_output);
+        self
+    }
+
+    // State
+        /// The current duty cycle of the output
+        pub fn duty(&self) -> Factor {
+            self._duty
+        }
+
+        /// The current direction of the device
+        pub fn direction(&self) -> Direction {
+            self._direction
+        }
+
+        /// The velocity held closed-loop, `None` if the device is driven open-loop
+        pub fn target(&self) -> Option<U::Velocity> {
+            self._target
+        }
+
+        /// The last velocity measured by the tachometer
+        pub fn velocity(&self) -> Option<U::Velocity> {
+            self._velocity
+        }
+
+        /// Returns `true` if a tachometer is attached
+        pub fn has_tach(&self) -> bool {
+            self.tach.is_some()
+        }
+    //
+
+    /// Stops the device, ending any closed-loop control
+    pub fn stop(&mut self) -> Result<(), ActuatorError<U>> {
+        self._target = None;
+        self.apply_duty(Factor::MIN)
+    }
+
+    /// Reads the tachometer and updates the duty cycle of the closed-loop control, `elapsed` is the time passed since 
+    /// the last poll
+    ///
+    /// Returns the duty cycle the device is driven with
+    pub fn poll(&mut self, elapsed : Seconds) -> Result<Factor, ActuatorError<U>> {
+        if let Some(velocity) = self.tach.as_mut().and_then(|tach| tach()) {
+            self._velocity = Some(velocity);
+
+            if let Some(target) = self._target {
+                let error = (target.abs() - velocity.abs()) / self.velocity_max;
+                let feed_forward = target.abs() / self.velocity_max;
+
+                // The integral is frozen while the output saturates, preventing wind-up
+                let integral = self._integral + error * elapsed.0;
+                let duty = feed_forward + self.gain_p * error + self.gain_i * integral;
+
+                if (0.0 ..= 1.0).contains(&duty) {
+                    self._integral = integral;
+                }
+
+                self.apply_duty(Factor::new(duty.clamp(0.0, 1.0)))?;
+            }
+        }
+
+        Ok(self._duty)
+    }
+
+    // Helpers
+        fn set_direction(&mut self, direction : Direction) -> Result<(), ActuatorError<U>> {
+            match &mut self.dir_output {
+                Some(dir_output) => dir_output.set(direction.as_bool()).map_err(|_| ActuatorError::IOError)?,
+                None if direction == Direction::CCW => return Err(ActuatorError::InvalidVelocity(-self.velocity_max)),
+                None => { }
+            }
+
+            self._direction = direction;
+            Ok(())
+        }
+
+        fn apply_duty(&mut self, duty : Factor) -> Result<(), ActuatorError<U>> {
+            self.output.set_duty(duty).map_err(|_| ActuatorError::IOError)?;
+            self._duty = duty;
+            Ok(())
+        }
+    //
+}
+
+impl<O : PwmOutput, U : UnitSet> AsyncActuator<U> for PwmDevice<O, U> {
+    fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError<U>> {
+        self.set_direction(direction)?;
+
+        self._target = None;
+        self.apply_duty(speed)
+    }
+
+    fn drive_speed(&mut self, speed : U::Velocity) -> Result<(), ActuatorError<U>> {
+        if !speed.is_finite() {
+            return Err(ActuatorError::InvalidVelocity(speed));
+        }
+
+        if speed.abs() > self.velocity_max {
+            return Err(ActuatorError::VelocityTooHigh(speed, self.velocity_max));
+        }
+
+        self.set_direction(speed.get_direction())?;
+
+        let duty = Factor::new(speed.abs() / self.velocity_max);
+
+        // Held closed-loop if a tachometer is attached, starting with the open-loop duty cycle
+        if self.tach.is_some() && (speed != U::Velocity::ZERO) {
+            self._target = Some(speed);
+            self._integral = 0.0;
+        } else {
+            self._target = None;
+        }
+
+        self.apply_duty(duty)
+    }
+}
+
+impl<O : PwmOutput + core::fmt::Debug, U : UnitSet> core::fmt::Debug for PwmDevice<O, U> {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PwmDevice")
+            .field("output", &self.output)
+            .field("velocity_max", &self.velocity_max)
+            .field("duty", &self._duty)
+            .field("direction", &self._direction)
+            .field("target", &self._target)
+            .field("velocity", &self._velocity)
+            .finish()
+    }
+}
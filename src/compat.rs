@@ -0,0 +1,114 @@
+//! Adapter shims that allow pins implementing the traits of `embedded-hal` 0.2 to be used wherever the library 
+//! expects the `embedded-hal` 1.0 traits
+//! 
+//! Components using pins provide an additional constructor with the suffix `_02`, wrapping the pins automatically
+//! 
+//! ```rust,ignore
+//! use syact::prelude::*;
+//! 
+//! // `pin_02` implements `embedded_hal::digital::v2::InputPin` (version 0.2)
+//! let endstop = EndStop::new_02(true, None, pin_02);
+//! ```
+
+use embedded_hal::digital::{Error, ErrorKind, ErrorType, InputPin, OutputPin};
+use embedded_hal_02::digital::v2 as hal02;
+
+/// Wraps an error of an `embedded-hal` 0.2 pin, as these do not implement the `embedded-hal` 1.0 [Error] trait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compat02Error<E>(pub E);
+
+impl<E : core::fmt::Debug> Error for Compat02Error<E> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+// Input pins
+    /// Wraps an input pin implementing the `embedded-hal` 0.2 traits, implementing the `embedded-hal` 1.0 traits for it
+    #[derive(Debug)]
+    pub struct CompatInput02<P> {
+        pin : P
+    }
+
+    impl<P> CompatInput02<P> {
+        /// Wraps the given `embedded-hal` 0.2 input pin
+        pub fn new(pin : P) -> Self {
+            Self { pin }
+        }
+
+        /// Returns a reference to the wrapped pin
+        pub fn inner(&self) -> &P {
+            &self.pin
+        }
+
+        /// Returns the wrapped pin, consuming the adapter
+        pub fn into_inner(self) -> P {
+            self.pin
+        }
+    }
+
+    impl<P : hal02::InputPin> ErrorType for CompatInput02<P> 
+    where
+        P::Error : core::fmt::Debug
+    {
+        type Error = Compat02Error<P::Error>;
+    }
+
+    impl<P : hal02::InputPin> InputPin for CompatInput02<P> 
+    where
+        P::Error : core::fmt::Debug
+    {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            self.pin.is_high().map_err(Compat02Error)
+        }
+
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            self.pin.is_low().map_err(Compat02Error)
+        }
+    }
+// 
+
+// Output pins
+    /// Wraps an output pin implementing the `embedded-hal` 0.2 traits, implementing the `embedded-hal` 1.0 traits for it
+    #[derive(Debug)]
+    pub struct CompatOutput02<P> {
+        pin : P
+    }
+
+    impl<P> CompatOutput02<P> {
+        /// Wraps the given `embedded-hal` 0.2 output pin
+        pub fn new(pin : P) -> Self {
+            Self { pin }
+        }
+
+        /// Returns a reference to the wrapped pin
+        pub fn inner(&self) -> &P {
+            &self.pin
+        }
+
+        /// Returns the wrapped pin, consuming the adapter
+        pub fn into_inner(self) -> P {
+            self.pin
+        }
+    }
+
+    impl<P : hal02::OutputPin> ErrorType for CompatOutput02<P> 
+    where
+        P::Error : core::fmt::Debug
+    {
+        type Error = Compat02Error<P::Error>;
+    }
+
+    impl<P : hal02::OutputPin> OutputPin for CompatOutput02<P> 
+    where
+        P::Error : core::fmt::Debug
+    {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.pin.set_low().map_err(Compat02Error)
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.pin.set_high().map_err(Compat02Error)
+        }
+    }
+// 
@@ -1,12 +1,26 @@
 // ####################
 // #    SUBMODULES    #
 // ####################
+    mod backlash;
+    pub use backlash::BacklashCompensator;
+
     mod conveyor;
     pub use conveyor::Conveyor;
+    #[cfg(feature = "meas")]
+    pub use conveyor::ConveyorRegistration;
+
+    mod gantry;
+    pub use gantry::{Gantry, GantrySquaring};
+
+    mod indexing_table;
+    pub use indexing_table::IndexingTable;
 
     mod gear;
     pub use gear::Gear;
 
+    mod gear_train;
+    pub use gear_train::{GearStage, GearTrain, StageDiagnostics};
+
     mod linear_axis;
     pub use linear_axis::LinearAxis;
 // 
\ No newline at end of file
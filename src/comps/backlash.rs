@@ -0,0 +1,532 @@
+use core::sync::atomic::Ordering;
+
+use alloc::sync::Arc;
+
+use atomic_float::AtomicF32;
+use syunit::*;
+
+use crate::{ActuatorError, AdvancedActuator, LimitBehavior, MotionMode, SyncActuator, SyncActuatorBlocking, SyncActuatorState};
+use crate::data::MicroSteps;
+use crate::parent::{ActuatorParent, LayerPos, PositionLayers};
+use crate::sync::stepper::{HoldPolicy, StepperActuator};
+
+/// The state handle of a [BacklashCompensator], removing the distance taken up by the backlash from the positions of its child
+struct BacklashState<S : ?Sized> {
+    child : Arc<S>,
+    offset : Arc<AtomicF32>
+}
+
+impl<S : ?Sized> BacklashState<S> {
+    fn offset<U : UnitSet>(&self) -> U::Distance {
+        U::Distance::from(self.offset.load(Ordering::Relaxed))
+    }
+}
+
+impl<S : ?Sized> core::fmt::Debug for BacklashState<S> {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BacklashState")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+impl<U : UnitSet, S : SyncActuatorState<U> + ?Sized> SyncActuatorState<U> for BacklashState<S> {
+    fn pos(&self) -> U::Position {
+        self.child.pos() - self.offset::<U>()
+    }
+
+    fn moving(&self) -> bool {
+        self.child.moving()
+    }
+
+    fn motion_mode(&self) -> MotionMode {
+        self.child.motion_mode()
+    }
+
+    fn predicted_pos(&self, now : U::Time) -> U::Position {
+        self.child.predicted_pos(now) - self.offset::<U>()
+    }
+
+    fn simulated(&self) -> bool {
+        self.child.simulated()
+    }
+
+    fn halt(&self) {
+        self.child.halt()
+    }
+
+    fn interrupt(&self) {
+        self.child.interrupt()
+    }
+
+    fn heartbeat(&self) {
+        self.child.heartbeat()
+    }
+}
+
+/// A parent component compensating the backlash (play) of a gearbox or lead screw between its child and the driven load
+///
+/// The compensator tracks the direction of the last movement. Whenever the direction reverses, the child is driven by the
+/// extra distance required to take up the backlash before the load starts moving, so the load ends up at the requested
+/// position. The distance taken up is removed from the position of the child, [SyncActuator::pos] returns the position of
+/// the load. Continuous movements (see [SyncActuatorBlocking::drive_factor]) take up the backlash before they start.
+///
+/// ```rust
+/// use syact::prelude::*;
+///
+/// let mut stepper = BacklashCompensator::new(Stepper::simulated(), Radians(0.2));
+///
+/// stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+/// stepper.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+///
+/// // The load moved back by one radian, the motor had to take up the backlash first
+/// assert!((stepper.pos() - PositionRad(1.0)).abs() < Radians(0.05));
+/// assert!((stepper.actuator.pos() - PositionRad(0.8)).abs() < Radians(0.05));
+/// ```
+///
+/// # Direction at startup
+///
+/// The side of the backlash the drive train rests on is unknown until the first movement, the first movement is therefore
+/// never compensated. Reference the actuator with a movement in the direction of the later positioning moves, or call
+/// [BacklashCompensator::set_backlash] after homing to restart the tracking.
+///
+/// # Limits
+///
+/// Positional limits and all other settings are forwarded to the child unchanged, limits are therefore checked against the
+/// position of the child, which differs from the position of the load by at most the backlash.
+#[derive(Debug)]
+pub struct BacklashCompensator<C : SyncActuatorBlocking<U>, U : UnitSet + 'static = Rotary> {
+    /// The child actuator driving the load
+    pub actuator : C,
+
+    backlash : U::Distance,
+    /// The position of the child inside the backlash, `ZERO` if the drive train rests against the negative side and
+    /// `backlash` if it rests against the positive side, `None` if no movement has been executed yet
+    _slack : Option<U::Distance>,
+    _state : Arc<BacklashState<dyn SyncActuatorState<U>>>
+}
+
+impl<C : SyncActuatorBlocking<U>, U : UnitSet + 'static> BacklashCompensator<C, U> {
+    /// Creates a new backlash compensator for the `actuator`, inserting the extra distance `backlash` on every direction
+    /// reversal
+    ///
+    /// # Panics
+    ///
+    /// Panics if `backlash` is negative or not finite
+    pub fn new(actuator : C, backlash : U::Distance) -> Self {
+        Self::assert_backlash(backlash);
+
+        Self {
+            _state: Arc::new(BacklashState {
+                child: actuator.clone_state(),
+                offset: Arc::new(AtomicF32::new(0.0))
+            }),
+            actuator,
+            backlash,
+            _slack: None
+        }
+    }
+
+    fn assert_backlash(backlash : U::Distance) {
+        let backlash_f : f32 = backlash.into();
+        assert!(backlash_f.is_finite() && (backlash_f >= 0.0), "The backlash must be positive and finite! (backlash: {})", backlash_f);
+    }
+
+    // Backlash
+        /// The extra distance inserted on every direction reversal
+        pub fn backlash(&self) -> U::Distance {
+            self.backlash
+        }
+
+        /// Sets the extra distance inserted on every direction reversal, the tracking of the direction is restarted, so the
+        /// next movement is not compensated
+        ///
+        /// # Panics
+        ///
+        /// Panics if `backlash` is negative or not finite
+        pub fn set_backlash(&mut self, backlash : U::Distance) {
+            Self::assert_backlash(backlash);
+            self.backlash = backlash;
+            self._slack = None;
+        }
+
+        /// The direction the drive train currently rests against, `None` if no movement has been executed yet or if the
+        /// drive train has been stopped inside the backlash
+        pub fn engaged_dir(&self) -> Option<Direction> {
+            let slack = self._slack?;
+
+            if slack >= self.backlash {
+                Some(Direction::CW)
+            } else if slack <= U::Distance::default() {
+                Some(Direction::CCW)
+            } else {
+                None
+            }
+        }
+
+        /// The distance the position of the child is ahead of the position of the load
+        pub fn offset(&self) -> U::Distance {
+            self._state.offset::<U>()
+        }
+
+        fn set_offset(&self, offset : U::Distance) {
+            self._state.offset.store(offset.into(), Ordering::Relaxed);
+        }
+    //
+
+    /// The extra distance the child has to move before the load starts moving in the direction of `rel_dist`
+    fn take_up_for(&self, rel_dist : U::Distance) -> U::Distance {
+        let zero = U::Distance::default();
+
+        if rel_dist > zero {
+            self.take_up_towards(Direction::CW)
+        } else if rel_dist < zero {
+            self.take_up_towards(Direction::CCW)
+        } else {
+            zero
+        }
+    }
+
+    /// The extra distance the child has to move before the load starts moving in the given `direction`
+    fn take_up_towards(&self, direction : Direction) -> U::Distance {
+        let zero = U::Distance::default();
+
+        match self._slack {
+            Some(slack) if direction.as_bool() => self.backlash - slack,
+            Some(slack) => zero - slack,
+            None => zero
+        }
+    }
+
+    /// Takes up the backlash in the given `direction` before a continuous movement, the load does not move
+    fn take_up(&mut self, direction : Direction, speed : Factor) -> Result<(), ActuatorError<U>> {
+        let take_up = self.take_up_towards(direction);
+
+        if take_up == U::Distance::default() {
+            return Ok(());
+        }
+
+        let pos_start = self.actuator.pos();
+        let result = self.actuator.drive_rel_blocking(take_up, speed);
+
+        self.track(self.actuator.pos() - pos_start);
+        result
+    }
+
+    /// Updates the slack and the offset after a continuous movement in the commanded `direction`, once the child moved the 
+    /// drive train rests against the side of the direction
+    fn track_dir(&mut self, direction : Direction, moved : U::Distance) {
+        let zero = U::Distance::default();
+
+        if moved == zero {
+            return;
+        }
+
+        let slack_new = if direction.as_bool() { self.backlash } else { zero };
+
+        if let Some(slack) = self._slack {
+            self.set_offset(self.offset() + (slack_new - slack));
+        }
+
+        self._slack = Some(slack_new);
+    }
+
+    /// Updates the slack and the offset with the distance `moved` the child actually moved
+    fn track(&mut self, moved : U::Distance) {
+        let zero = U::Distance::default();
+
+        let Some(slack) = self._slack else {
+            // The first movement defines the side the drive train rests against
+            if moved > zero {
+                self._slack = Some(self.backlash);
+            } else if moved < zero {
+                self._slack = Some(zero);
+            }
+
+            return;
+        };
+
+        let mut slack_new = slack + moved;
+
+        if slack_new > self.backlash {
+            slack_new = self.backlash;
+        } else if slack_new < zero {
+            slack_new = zero;
+        }
+
+        // The part of the movement spent inside the backlash does not move the load
+        self.set_offset(self.offset() + (slack_new - slack));
+        self._slack = Some(slack_new);
+    }
+}
+
+// Parent
+    impl<C : SyncActuatorBlocking<U>, U : UnitSet + 'static> ActuatorParent for BacklashCompensator<C, U> {
+        type Child = C;
+
+        fn child(&self) -> &Self::Child {
+            &self.actuator
+        }
+
+        fn child_mut(&mut self) -> &mut Self::Child {
+            &mut self.actuator
+        }
+    }
+
+    impl<C : SyncActuatorBlocking<U> + PositionLayers, U : UnitSet + 'static> PositionLayers for BacklashCompensator<C, U>
+    where
+        U::Position : Into<LayerPos>
+    {
+        fn layer_count(&self) -> usize {
+            1 + self.actuator.layer_count()
+        }
+
+        fn layer_name(&self, index : usize) -> Option<&'static str> {
+            match index {
+                0 => Some("backlash"),
+                _ => self.actuator.layer_name(index - 1)
+            }
+        }
+
+        fn layer_pos(&self, index : usize) -> Option<LayerPos> {
+            match index {
+                0 => Some(self.pos().into()),
+                _ => self.actuator.layer_pos(index - 1)
+            }
+        }
+    }
+//
+
+// Actuator traits
+    impl<C : SyncActuatorBlocking<U>, U : UnitSet + 'static> SyncActuator<U> for BacklashCompensator<C, U> {
+        // Position
+            fn pos(&self) -> U::Position {
+                self.actuator.pos() - self.offset()
+            }
+
+            fn overwrite_abs_pos(&mut self, abs_pos : U::Position) {
+                self.actuator.overwrite_abs_pos(abs_pos + self.offset())
+            }
+        //
+
+        // Velocity
+            fn velocity_max(&self) -> Option<U::Velocity> {
+                self.actuator.velocity_max()
+            }
+
+            fn set_velocity_max(&mut self, velocity_opt : Option<U::Velocity>) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_velocity_max(velocity_opt)
+            }
+
+            fn velocity_min(&self) -> Option<U::Velocity> {
+                self.actuator.velocity_min()
+            }
+
+            fn set_velocity_min(&mut self, velocity_opt : Option<U::Velocity>) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_velocity_min(velocity_opt)
+            }
+        //
+
+        // Acceleration
+            fn acceleration_max(&self) -> Option<U::Acceleration> {
+                self.actuator.acceleration_max()
+            }
+
+            fn set_acceleration_max(&mut self, acceleration_opt : Option<U::Acceleration>) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_acceleration_max(acceleration_opt)
+            }
+
+            fn deceleration_max(&self) -> Option<U::Acceleration> {
+                self.actuator.deceleration_max()
+            }
+
+            fn set_deceleration_max(&mut self, deceleration_opt : Option<U::Acceleration>) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_deceleration_max(deceleration_opt)
+            }
+        //
+
+        // Jolt
+            fn jolt_max(&self) -> Option<U::Jolt> {
+                self.actuator.jolt_max()
+            }
+
+            fn set_jolt_max(&mut self, jolt_opt : Option<U::Jolt>) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_jolt_max(jolt_opt)
+            }
+        //
+
+        // Positional limits
+            fn limit_max(&self) -> Option<U::Position> {
+                self.actuator.limit_max()
+            }
+
+            fn limit_min(&self) -> Option<U::Position> {
+                self.actuator.limit_min()
+            }
+
+            fn resolve_pos_limits_for_abs_pos(&self, abs_pos : U::Position) -> U::Distance {
+                self.actuator.resolve_pos_limits_for_abs_pos(abs_pos)
+            }
+
+            fn set_pos_limits(&mut self, min : Option<U::Position>, max : Option<U::Position>) {
+                self.actuator.set_pos_limits(min, max)
+            }
+
+            fn set_endpos(&mut self, overwrite_abs_pos : U::Position) {
+                self.actuator.set_endpos(overwrite_abs_pos)
+            }
+
+            fn overwrite_pos_limits(&mut self, min : Option<U::Position>, max : Option<U::Position>) {
+                self.actuator.overwrite_pos_limits(min, max)
+            }
+
+            fn limit_behavior(&self) -> LimitBehavior {
+                self.actuator.limit_behavior()
+            }
+
+            fn set_limit_behavior(&mut self, behavior : LimitBehavior) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_limit_behavior(behavior)
+            }
+        //
+    }
+
+    impl<C : SyncActuatorBlocking<U>, U : UnitSet + 'static> SyncActuatorBlocking<U> for BacklashCompensator<C, U> {
+        // State
+            fn state(&self) -> &dyn SyncActuatorState<U> {
+                self._state.as_ref()
+            }
+
+            fn clone_state(&self) -> Arc<dyn SyncActuatorState<U>> {
+                self._state.clone()
+            }
+        //
+
+        fn drive_rel_blocking(&mut self, rel_dist : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>> {
+            let pos_start = self.actuator.pos();
+            let take_up = self.take_up_for(rel_dist);
+
+            let result = self.actuator.drive_rel_blocking(rel_dist + take_up, speed);
+
+            self.track(self.actuator.pos() - pos_start);
+            result
+        }
+
+        fn drive_rel_blocking_with<F>(&mut self, rel_dist : U::Distance, speed : Factor, observer : F) -> Result<(), ActuatorError<U>> 
+        where
+            F : FnOnce(Arc<dyn SyncActuatorState<U> + Send + Sync>)
+        {
+            let pos_start = self.actuator.pos();
+            let take_up = self.take_up_for(rel_dist);
+            let offset = self._state.offset.clone();
+
+            let result = self.actuator.drive_rel_blocking_with(rel_dist + take_up, speed, |child| {
+                observer(Arc::new(BacklashState { child, offset }))
+            });
+
+            self.track(self.actuator.pos() - pos_start);
+            result
+        }
+
+        fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError<U>> {
+            self.take_up(direction, speed)?;
+
+            let pos_start = self.actuator.pos();
+            let result = self.actuator.drive_factor(speed, direction);
+
+            self.track_dir(direction, self.actuator.pos() - pos_start);
+            result
+        }
+
+        fn drive_speed(&mut self, speed : U::Velocity) -> Result<(), ActuatorError<U>> {
+            let zero = U::Velocity::default();
+            let direction = if speed >= zero { Direction::CW } else { Direction::CCW };
+
+            // The backlash is taken up with the commanded velocity, relative to the maximum velocity of the child
+            if speed != zero {
+                let speed_f = self.actuator.velocity_max()
+                    .and_then(|velocity_max| Factor::try_new(speed.into().abs() / velocity_max.into()))
+                    .unwrap_or(Factor::MAX);
+
+                self.take_up(direction, speed_f)?;
+            }
+
+            let pos_start = self.actuator.pos();
+            let result = self.actuator.drive_speed(speed);
+
+            self.track_dir(direction, self.actuator.pos() - pos_start);
+            result
+        }
+    }
+
+    impl<C : SyncActuatorBlocking<U> + AdvancedActuator<U>, U : UnitSet + 'static> AdvancedActuator<U> for BacklashCompensator<C, U> {
+        // Loads
+            fn force_gen(&self) -> U::Force {
+                self.actuator.force_gen()
+            }
+
+            fn force_dir(&self) -> U::Force {
+                self.actuator.force_dir()
+            }
+
+            fn apply_gen_force(&mut self, force : U::Force) -> Result<(), ActuatorError<U>> {
+                self.actuator.apply_gen_force(force)
+            }
+
+            fn apply_dir_force(&mut self, force : U::Force) -> Result<(), ActuatorError<U>> {
+                self.actuator.apply_dir_force(force)
+            }
+
+            fn inertia(&self) -> U::Inertia {
+                self.actuator.inertia()
+            }
+
+            fn apply_inertia(&mut self, inertia : U::Inertia) -> Result<(), ActuatorError<U>> {
+                self.actuator.apply_inertia(inertia)
+            }
+        //
+    }
+
+    impl<C : SyncActuatorBlocking<U> + StepperActuator<U>, U : UnitSet + 'static> StepperActuator<U> for BacklashCompensator<C, U> {
+        // Microsteps
+            fn microsteps(&self) -> MicroSteps {
+                self.actuator.microsteps()
+            }
+
+            fn set_microsteps(&mut self, micro : MicroSteps) -> Result<(), ActuatorError<U>> {
+                self.actuator.set_microsteps(micro)
+            }
+        //
+
+        fn step_dist(&self) -> U::Distance {
+            self.actuator.step_dist()
+        }
+
+        // Hold policy
+            fn hold_policy(&self) -> HoldPolicy {
+                self.actuator.hold_policy()
+            }
+
+            fn set_hold_policy(&mut self, policy : HoldPolicy) {
+                self.actuator.set_hold_policy(policy)
+            }
+        //
+
+        // Heartbeat
+            fn heartbeat_timeout(&self) -> Option<Seconds> {
+                self.actuator.heartbeat_timeout()
+            }
+
+            fn set_heartbeat_timeout(&mut self, timeout : Option<Seconds>) {
+                self.actuator.set_heartbeat_timeout(timeout)
+            }
+
+            fn heartbeat_lost(&self) -> bool {
+                self.actuator.heartbeat_lost()
+            }
+
+            fn reset_heartbeat_lost(&mut self) {
+                self.actuator.reset_heartbeat_lost()
+            }
+        //
+    }
+//
@@ -2,10 +2,15 @@
 //! 
 //! A conveyor powered by a synchronous actuator, for full description see [Conveyor]
 
+#[cfg(feature = "meas")]
+use alloc::sync::Arc;
+
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
 use crate::SyncActuator;
+#[cfg(feature = "meas")]
+use crate::meas::RegistrationLatch;
 use crate::parent::{ActuatorParent, RatioActuatorParent};
 
 use syunit::*;
@@ -21,7 +26,24 @@ pub struct Conveyor<C : SyncActuator> {
     actuator : C,
 
     /// Radius of the powered conveyor roll in [Millimeter]
-    pub r_roll : Millimeters
+    pub r_roll : Millimeters,
+
+    /// Registration mark settings, see [Conveyor::apply_registration]
+    #[cfg(feature = "meas")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    registration : Option<ConveyorRegistration>
+}
+
+/// Settings for re-syncing the position of a [Conveyor] with registration marks on its belt
+#[cfg(feature = "meas")]
+#[derive(Debug, Clone)]
+pub struct ConveyorRegistration {
+    /// The latch of the sensor detecting the marks, see [RegistrationSensor](crate::meas::RegistrationSensor)
+    pub latch : Arc<RegistrationLatch>,
+    /// The nominal distance between two marks on the belt
+    pub mark_pitch : Millimeters,
+    /// The maximum correction that will be applied for a single mark
+    pub correction_max : Millimeters
 }
 
 impl<C : SyncActuator> Conveyor<C> {
@@ -32,9 +54,46 @@ impl<C : SyncActuator> Conveyor<C> {
     pub fn new(actuator : C, r_roll : Millimeters) -> Self {
         Self {
             actuator, 
-            r_roll
+            r_roll,
+
+            #[cfg(feature = "meas")]
+            registration: None
         }
     }
+
+    // Registration
+        #[cfg(feature = "meas")]
+        /// Returns the registration mark settings of the conveyor, if set
+        pub fn registration(&self) -> Option<&ConveyorRegistration> {
+            self.registration.as_ref()
+        }
+
+        #[cfg(feature = "meas")]
+        /// Sets the registration mark settings of the conveyor, `None` disables the re-sync
+        pub fn set_registration(&mut self, registration : Option<ConveyorRegistration>) {
+            self.registration = registration;
+        }
+
+        #[cfg(feature = "meas")]
+        /// Corrects the position of the conveyor if a registration mark has been detected since the last call, 
+        /// compensating slip of the belt over long runs
+        /// 
+        /// The position the mark has been detected at is moved to the closest multiple of the mark pitch, the correction 
+        /// is limited to the `correction_max` of the [ConveyorRegistration]. Returns the correction applied, `None` if no mark 
+        /// has been detected or no registration has been set.
+        pub fn apply_registration(&mut self) -> Option<Millimeters> {
+            let registration = self.registration.as_ref()?;
+            let mark_pos = self.pos_for_parent(registration.latch.take()?);
+
+            let nominal = (mark_pos.0 / registration.mark_pitch.0).round() * registration.mark_pitch.0;
+            let correction = Millimeters(
+                (nominal - mark_pos.0).clamp(-registration.correction_max.0.abs(), registration.correction_max.0.abs())
+            );
+
+            self.overwrite_abs_pos(self.pos() + correction);
+            Some(correction)
+        }
+    // 
 }
 
 // ######################################
@@ -61,5 +120,9 @@ impl<C : SyncActuator> Conveyor<C> {
         fn ratio(&self) -> Self::Ratio {
             self.r_roll
         }
+
+        fn output_name(&self) -> &'static str {
+            "conveyor"
+        }
     }
 // 
\ No newline at end of file
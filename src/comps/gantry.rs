@@ -0,0 +1,169 @@
+//! ### Gantry - General component
+//!
+//! A gantry driven by two stepper motors, one on each side, for full description see [Gantry]
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::{ActuatorError, SyncActuator};
+use crate::data::CouplingMatrix;
+#[cfg(feature = "meas")]
+use crate::InterruptReason;
+#[cfg(feature = "meas")]
+use crate::meas::SimpleMeasError;
+use crate::sync::stepper::{InterpolatedStepper, drive_rel_interpolated};
+
+/// The skew of the end switches of a [Gantry], determined by [Gantry::square]
+///
+/// Store it with the machine configuration, it is applied by [Gantry::home] on every homing afterwards
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GantrySquaring {
+    /// The distance the switch of side `b` is further away than the switch of side `a`, in motor radians
+    pub skew : Radians
+}
+
+/// ### Gantry
+///
+/// A gantry driven by two stepper motors `a` and `b`, one on each side of the gantry. Both motors are driven with a
+/// shared step schedule (see [drive_rel_interpolated]), all distances are given in motor radians.
+///
+/// One-sided loads cause the sides to travel different distances, racking the gantry. An optional [CouplingMatrix] can be
+/// applied to the commanded movements of both motors to reduce the racking without requiring closed-loop control, see
+/// [Gantry::calibrate].
+#[derive(Debug)]
+pub struct Gantry<A : InterpolatedStepper + SyncActuator, B : InterpolatedStepper + SyncActuator> {
+    /// The motor driving side `a` of the gantry
+    pub actuator_a : A,
+    /// The motor driving side `b` of the gantry
+    pub actuator_b : B,
+
+    /// Optional compensation applied to the commanded movements of both motors
+    pub coupling : Option<CouplingMatrix>,
+    /// The skew of the end switches, see [Gantry::square]
+    pub squaring : Option<GantrySquaring>
+}
+
+impl<A : InterpolatedStepper + SyncActuator, B : InterpolatedStepper + SyncActuator> Gantry<A, B> {
+    /// Creates a new gantry without compensation
+    pub fn new(actuator_a : A, actuator_b : B) -> Self {
+        Self {
+            actuator_a,
+            actuator_b,
+
+            coupling: None,
+            squaring: None
+        }
+    }
+
+    /// The position of the gantry, being the average of the positions of both motors
+    pub fn pos(&self) -> PositionRad {
+        PositionRad((self.actuator_a.pos().0 + self.actuator_b.pos().0) / 2.0)
+    }
+
+    /// Returns the movements the motors have to be commanded with in order to move both sides by `rel_dist`
+    pub fn compensated(&self, rel_dist : Radians) -> (Radians, Radians) {
+        let (dist_a, dist_b) = self.coupling.unwrap_or_default().apply(rel_dist.0, rel_dist.0);
+        (Radians(dist_a), Radians(dist_b))
+    }
+
+    /// Moves the gantry by the relative distance `rel_dist` with the speed factor `speed_f`, blocks until the movement is done
+    pub fn drive_rel_blocking(&mut self, rel_dist : Radians, speed_f : Factor) -> Result<(), ActuatorError> {
+        let (dist_a, dist_b) = self.compensated(rel_dist);
+        self.drive_sides(dist_a, dist_b, speed_f)
+    }
+
+    /// Moves both sides by the given distances without applying the compensation
+    fn drive_sides(&mut self, dist_a : Radians, dist_b : Radians, speed_f : Factor) -> Result<(), ActuatorError> {
+        drive_rel_interpolated(&mut [ &mut self.actuator_a, &mut self.actuator_b ], &[ dist_a, dist_b ], speed_f)
+    }
+
+    /// Calibrates the [CouplingMatrix] of the gantry
+    ///
+    /// Each side is moved alone by the `test_dist` and back again, while the `measure` closure delivers the actual positions
+    /// of both sides, e.g. read with dial gauges or linear encoders. The measurements can be given in any unit. Returns `None` if
+    /// the measurement failed or the measured response cannot be inverted, the coupling of the gantry is left unchanged in that case.
+    ///
+    /// The `test_dist` has to be small enough for the mechanics to tolerate the racking caused by moving one side only!
+    pub fn calibrate<F>(&mut self, test_dist : Radians, speed_f : Factor, mut measure : F) -> Result<Option<CouplingMatrix>, ActuatorError>
+    where
+        F : FnMut() -> Option<(f32, f32)>
+    {
+        let mut response = [ None, None ];
+
+        for (i, (dist_a, dist_b)) in [ (test_dist, Radians::ZERO), (Radians::ZERO, test_dist) ].into_iter().enumerate() {
+            let start = measure();
+            self.drive_sides(dist_a, dist_b, speed_f)?;
+            let end = measure();
+            self.drive_sides(-dist_a, -dist_b, speed_f)?;
+
+            if let (Some(start), Some(end)) = (start, end) {
+                response[i] = Some((end.0 - start.0, end.1 - start.1));
+            }
+        }
+
+        let coupling = match response {
+            [ Some(response_a), Some(response_b) ] => CouplingMatrix::from_response(response_a, response_b),
+            _ => None
+        };
+
+        if coupling.is_some() {
+            self.coupling = coupling;
+        }
+
+        Ok(coupling)
+    }
+
+    /// Drives both sides by `max_dist` until each one is stopped by its own end switch, returns the distances travelled
+    #[cfg(feature = "meas")]
+    fn home_sides(&mut self, max_dist : Radians, speed_f : Factor) -> Result<(Radians, Radians), SimpleMeasError<Rotary>> {
+        let (start_a, start_b) = (self.actuator_a.pos(), self.actuator_b.pos());
+        self.drive_sides(max_dist, max_dist, speed_f)?;
+
+        for reason in [ self.actuator_a.interrupted(), self.actuator_b.interrupted() ] {
+            match reason {
+                Some(InterruptReason::EndReached) => { },
+                Some(reason) => return Err(SimpleMeasError::WrongInterruptReason(reason)),
+                None => return Err(SimpleMeasError::NoInterrupt)
+            }
+        }
+
+        Ok((self.actuator_a.pos() - start_a, self.actuator_b.pos() - start_b))
+    }
+
+    /// Determines the skew of the end switches, both sides need an interruptor stopping them at their own switch
+    ///
+    /// The gantry has to be square when the procedure starts, e.g. by pushing it against a mechanical stop. Both sides are
+    /// moved together by up to `max_dist`, each one stopping at its own switch. The difference of the distances travelled is
+    /// the skew of the switches, it is stored in the gantry and returned, so it can be saved with the machine configuration.
+    #[cfg(feature = "meas")]
+    pub fn square(&mut self, max_dist : Radians, speed_f : Factor) -> Result<GantrySquaring, SimpleMeasError<Rotary>> {
+        let (travel_a, travel_b) = self.home_sides(max_dist, speed_f)?;
+
+        let squaring = GantrySquaring { skew: travel_b - travel_a };
+        self.squaring = Some(squaring);
+
+        Ok(squaring)
+    }
+
+    /// Homes both sides to their own end switch and squares the gantry with the stored [GantrySquaring]
+    ///
+    /// Both sides are moved together by up to `max_dist`, each one stopping at its own switch. Side `b` is then moved back by
+    /// the skew, leaving the gantry square, and both motors are set to `home_pos`. Returns the distances travelled by both 
+    /// sides, including the correction.
+    #[cfg(feature = "meas")]
+    pub fn home(&mut self, max_dist : Radians, speed_f : Factor, home_pos : PositionRad) -> Result<(Radians, Radians), SimpleMeasError<Rotary>> {
+        let (travel_a, mut travel_b) = self.home_sides(max_dist, speed_f)?;
+
+        if let Some(squaring) = self.squaring {
+            self.drive_sides(Radians::ZERO, -squaring.skew, speed_f)?;
+            travel_b -= squaring.skew;
+        }
+
+        self.actuator_a.overwrite_abs_pos(home_pos);
+        self.actuator_b.overwrite_abs_pos(home_pos);
+
+        Ok((travel_a, travel_b))
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 use syunit::*;
 
@@ -9,7 +10,8 @@ use crate::parent::{ActuatorParent, RatioActuatorParent};
 /// # Gears
 /// 
 /// 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Gear<C : SyncActuator> {
     /// Steppercontrol for the motor of the bearing
     pub actuator : C,
@@ -27,6 +29,20 @@ impl<C : SyncActuator> Gear<C> {
             ratio
         }
     }
+
+    /// Creates a new gear from the tooth counts of the gear on the actuator `teeth_actuator` and the driven gear 
+    /// `teeth_output`, the resulting ratio is `teeth_actuator / teeth_output` radians of the output per radian of the actuator
+    /// 
+    /// ```rust
+    /// use syact::prelude::*;
+    /// 
+    /// // A 12 tooth pinion driving a 48 tooth wheel reduces the speed by 4
+    /// let gear = Gear::from_teeth(Stepper::simulated(), 12, 48);
+    /// assert_eq!(gear.ratio, 0.25);
+    /// ```
+    pub fn from_teeth(ctrl : C, teeth_actuator : u32, teeth_output : u32) -> Self {
+        Self::new(ctrl, teeth_actuator as f32 / teeth_output as f32)
+    }
 }
 
 // Parent
@@ -40,6 +56,10 @@ impl<C : SyncActuator> Gear<C> {
         fn child_mut(&mut self) -> &mut Self::Child {
             &mut self.actuator
         }
+
+        fn intr_pos_for_parent(&self, child_pos : PositionRad) -> Option<PositionRad> {
+            Some(self.pos_for_parent(child_pos))
+        }
     }
 
     impl<C : SyncActuator> RatioActuatorParent for Gear<C> {
@@ -50,5 +70,9 @@ impl<C : SyncActuator> Gear<C> {
         fn ratio(&self) -> Self::Ratio {
             self.ratio
         }
+
+        fn output_name(&self) -> &'static str {
+            "gear"
+        }
     }
 // 
\ No newline at end of file
@@ -0,0 +1,243 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use syunit::*;
+use syunit::metric::{KgMeter2, NewtonMeters};
+
+use crate::{ActuatorError, AdvancedActuator, SyncActuator};
+use crate::parent::{ActuatorParent, RatioActuatorParent};
+
+/// A single stage of a [GearTrain], e.g. a spur gear pair or a planetary stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GearStage {
+    /// Angle ratio from the input to the output of the stage (velocity_out / velocity_in)
+    pub ratio : f32,
+    /// The efficiency of the stage, the fraction of the input torque reaching the output (`0.0 < efficiency <= 1.0`)
+    pub efficiency : f32,
+    /// The inertia of the parts rotating with the output of the stage, unit kgm^2
+    pub inertia : KgMeter2
+}
+
+impl GearStage {
+    /// Creates a new stage without losses and inertia
+    pub fn new(ratio : f32) -> Self {
+        Self {
+            ratio,
+            efficiency: 1.0,
+            inertia: KgMeter2::ZERO
+        }
+    }
+
+    /// Sets the efficiency of the stage
+    pub fn with_efficiency(mut self, efficiency : f32) -> Self {
+        self.efficiency = efficiency;
+        self
+    }
+
+    /// Sets the inertia of the parts rotating with the output of the stage
+    pub fn with_inertia(mut self, inertia : KgMeter2) -> Self {
+        self.inertia = inertia;
+        self
+    }
+}
+
+/// The diagnostics of a single stage of a [GearTrain], see [GearTrain::diagnostics]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StageDiagnostics {
+    /// The index of the stage, counted from the motor
+    pub index : usize,
+    /// The ratio from the motor to the output of the stage
+    pub ratio_total : f32,
+    /// The efficiency from the motor to the output of the stage
+    pub efficiency_total : f32,
+    /// The velocity of the output of the stage
+    pub velocity : RadPerSecond,
+    /// The torque at the output of the stage, transmitting the load torque
+    pub torque : NewtonMeters,
+    /// The torque lost in the stage
+    pub torque_loss : NewtonMeters,
+    /// The inertia of the stage as seen by the motor
+    pub inertia_reflected : KgMeter2
+}
+
+/// A gear train built from multiple stages, each one with its own ratio, efficiency and inertia
+/// 
+/// # Gear trains
+/// 
+/// Unlike a [Gear](crate::comps::Gear), the train models the losses and the rotating masses of its stages, e.g. for the 
+/// stages of a planetary gearbox. The positions, velocities and limits are converted with the total ratio like any other 
+/// [RatioActuatorParent], loads should be applied with [GearTrain::apply_load_inertia] and [GearTrain::apply_load_forces] to 
+/// include the inertias and efficiencies of the stages.
+/// 
+/// ```rust
+/// use syact::prelude::*;
+/// 
+/// // Two planetary stages reducing the speed by 4 each
+/// let mut train = GearTrain::new(Stepper::simulated(), vec![
+///     GearStage::new(0.25).with_efficiency(0.9).with_inertia(KgMeter2(0.000_1)),
+///     GearStage::new(0.25).with_efficiency(0.9).with_inertia(KgMeter2(0.000_4))
+/// ]);
+/// 
+/// assert_eq!(train.ratio_total(), 0.0625);
+/// assert!((train.efficiency_total() - 0.81).abs() < 1e-6);
+/// 
+/// // The motor sees the stages and the load reduced by the square of the ratios
+/// train.apply_load_inertia(KgMeter2(1.6)).unwrap();
+/// assert!((train.child().inertia() - KgMeter2(0.000_1 / 16.0 + 0.000_4 / 256.0 + 1.6 / 256.0)).abs() < KgMeter2(1e-9));
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GearTrain<C : SyncActuator> {
+    /// The actuator driving the first stage
+    pub actuator : C,
+
+    stages : Vec<GearStage>
+}
+
+impl<C : SyncActuator> GearTrain<C> {
+    /// Creates a new gear train, the `stages` are ordered from the motor to the output
+    /// 
+    /// ## Panics
+    /// 
+    /// Panics if a ratio is zero or not finite, or if an efficiency is outside of `0.0 < efficiency <= 1.0`
+    pub fn new(ctrl : C, stages : Vec<GearStage>) -> Self {
+        for stage in &stages {
+            if !stage.ratio.is_normal() {
+                panic!("The ratio of a gear stage must be finite and not zero! (given: {})", stage.ratio);
+            }
+
+            if !((stage.efficiency > 0.0) && (stage.efficiency <= 1.0)) {
+                panic!("The efficiency of a gear stage must be in the range 0.0 < efficiency <= 1.0! (given: {})", stage.efficiency);
+            }
+        }
+
+        Self {
+            actuator: ctrl,
+            stages
+        }
+    }
+
+    // Stages
+        /// The stages of the train, ordered from the motor to the output
+        pub fn stages(&self) -> &[GearStage] {
+            &self.stages
+        }
+
+        /// The ratio of the whole train, the product of all stage ratios
+        pub fn ratio_total(&self) -> f32 {
+            self.stages.iter().map(|stage| stage.ratio).product()
+        }
+
+        /// The efficiency of the whole train, the product of all stage efficiencies
+        pub fn efficiency_total(&self) -> f32 {
+            self.stages.iter().map(|stage| stage.efficiency).product()
+        }
+
+        /// The inertia of all stages as seen by the motor, each one reduced by the square of the ratio up to its output
+        pub fn inertia_stages(&self) -> KgMeter2 {
+            let mut ratio = 1.0;
+
+            self.stages.iter().map(|stage| {
+                ratio *= stage.ratio;
+                stage.inertia * (ratio * ratio)
+            }).fold(KgMeter2::ZERO, |sum, inertia| sum + inertia)
+        }
+    //
+
+    // Loads
+        /// The inertia seen by the motor with the `inertia` applied to the output, stages included
+        pub fn inertia_for_motor(&self, inertia : KgMeter2) -> KgMeter2 {
+            let ratio = self.ratio_total();
+            self.inertia_stages() + inertia * (ratio * ratio)
+        }
+
+        /// The torque the motor has to deliver to hold the `torque` at the output, losses included
+        pub fn torque_for_motor(&self, torque : NewtonMeters) -> NewtonMeters {
+            torque * self.ratio_total() / self.efficiency_total()
+        }
+
+        /// The per-stage diagnostics with the `torque` and `velocity` applied to the output of the train
+        pub fn diagnostics(&self, torque : NewtonMeters, velocity : RadPerSecond) -> Vec<StageDiagnostics> {
+            let ratio_total = self.ratio_total();
+            let velocity_motor = velocity / ratio_total;
+
+            let mut ratio = 1.0;
+            let mut efficiency = 1.0;
+
+            // The torque at the output of each stage, calculated from the output backwards
+            let mut torques = Vec::with_capacity(self.stages.len());
+            let mut torque_out = torque;
+
+            for stage in self.stages.iter().rev() {
+                torques.push(torque_out);
+                torque_out = torque_out * stage.ratio / stage.efficiency;
+            }
+
+            self.stages.iter().zip(torques.into_iter().rev()).enumerate().map(|(index, (stage, torque))| {
+                ratio *= stage.ratio;
+                efficiency *= stage.efficiency;
+
+                StageDiagnostics {
+                    index,
+                    ratio_total: ratio,
+                    efficiency_total: efficiency,
+                    velocity: velocity_motor * ratio,
+                    torque,
+                    torque_loss: torque * (1.0 / stage.efficiency - 1.0),
+                    inertia_reflected: stage.inertia * (ratio * ratio)
+                }
+            }).collect()
+        }
+    //
+}
+
+impl<C : SyncActuator + AdvancedActuator> GearTrain<C> {
+    /// Applies the load `inertia` at the output to the motor, the inertias of the stages included
+    pub fn apply_load_inertia(&mut self, inertia : KgMeter2) -> Result<(), ActuatorError> {
+        let inertia = self.inertia_for_motor(inertia);
+        self.actuator.apply_inertia(inertia)
+    }
+
+    /// Applies the general and directional load torques at the output to the motor, the losses of the stages included
+    pub fn apply_load_forces(&mut self, force_gen : NewtonMeters, force_dir : NewtonMeters) -> Result<(), ActuatorError> {
+        let force_gen = self.torque_for_motor(force_gen);
+        let force_dir = self.torque_for_motor(force_dir);
+
+        self.actuator.apply_gen_force(force_gen)?;
+        self.actuator.apply_dir_force(force_dir)
+    }
+}
+
+// Parent
+    impl<C : SyncActuator> ActuatorParent for GearTrain<C> {
+        type Child = C;
+
+        fn child(&self) -> &Self::Child {
+            &self.actuator
+        }
+
+        fn child_mut(&mut self) -> &mut Self::Child {
+            &mut self.actuator
+        }
+
+        fn intr_pos_for_parent(&self, child_pos : PositionRad) -> Option<PositionRad> {
+            Some(self.pos_for_parent(child_pos))
+        }
+    }
+
+    impl<C : SyncActuator> RatioActuatorParent for GearTrain<C> {
+        type Input = Rotary;
+        type Output = Rotary;
+        type Ratio = f32;
+
+        fn ratio(&self) -> Self::Ratio {
+            self.ratio_total()
+        }
+
+        fn output_name(&self) -> &'static str {
+            "gear_train"
+        }
+    }
+//
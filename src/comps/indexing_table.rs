@@ -0,0 +1,179 @@
+//! ### Indexing table - General component
+//!
+//! A rotary table moving between named stations, for full description see [IndexingTable]
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+
+use syunit::*;
+
+use crate::{ActuatorError, SyncActuatorBlocking};
+use crate::io::{DigitalInput, DigitalOutput};
+
+/// ### Indexing table
+///
+/// A rotary table with any amount of named stations at arbitrary angles. The table wraps around, so [IndexingTable::goto_station]
+/// always takes the shorter way to the station, unless the table may only be rotated in one [Direction], e.g. because of
+/// cables or a ratcheting mechanism.
+///
+/// If the table is secured by a locking pin or a brake, the `lock` output is released before and engaged again after every
+/// movement. An optional `in_position` sensor verifies the accuracy of the station after the lock has been engaged.
+///
+/// All angles are given in radians of the `actuator`, wrap the actuator with a [Gear](crate::Gear) if the table
+/// is driven with a transmission.
+pub struct IndexingTable<A : SyncActuatorBlocking> {
+    /// The actuator rotating the table
+    pub actuator : A,
+    /// The only direction the table may be rotated in, `None` if both directions are allowed
+    pub direction : Option<Direction>,
+    /// The maximum deviation of the table angle from a station to still count as being at the station
+    pub tolerance : Radians,
+
+    /// The output controlling the lock of the table
+    pub lock : Option<Box<dyn DigitalOutput + Send>>,
+    /// The state of the `lock` output that engages the lock
+    pub lock_state : bool,
+    /// The sensor reporting that the table is in position, read after each movement
+    pub in_position : Option<Box<dyn DigitalInput + Send>>,
+
+    _stations : Vec<(&'static str, PositionRad)>
+}
+
+impl<A : SyncActuatorBlocking> IndexingTable<A> {
+    /// Creates a new indexing table without any stations, lock or sensor
+    pub fn new(actuator : A) -> Self {
+        Self {
+            actuator,
+            direction: None,
+            tolerance: Radians(0.01),
+
+            lock: None,
+            lock_state: true,
+            in_position: None,
+
+            _stations: Vec::new()
+        }
+    }
+
+    /// Creates a new indexing table with one station per name, spread evenly around the table, the first one being at zero
+    pub fn evenly_spaced(actuator : A, names : &[&'static str]) -> Self {
+        let mut table = Self::new(actuator);
+        let pitch = 2.0 * PI / names.len().max(1) as f32;
+
+        for (i, name) in names.iter().enumerate() {
+            table.add_station(name, PositionRad(pitch * i as f32));
+        }
+
+        table
+    }
+
+    // Stations
+        /// Adds a station at the given table `angle`, returning its index
+        pub fn add_station(&mut self, name : &'static str, angle : PositionRad) -> usize {
+            self._stations.push((name, Self::wrap(angle)));
+            self._stations.len() - 1
+        }
+
+        /// The stations of the table with their names and angles
+        pub fn stations(&self) -> &[(&'static str, PositionRad)] {
+            &self._stations
+        }
+
+        /// The index of the station with the given `name`
+        pub fn station_index(&self, name : &str) -> Option<usize> {
+            self._stations.iter().position(|(n, _)| *n == name)
+        }
+
+        /// The current angle of the table in the range `0 .. 2 * PI`
+        pub fn angle(&self) -> PositionRad {
+            Self::wrap(self.actuator.pos())
+        }
+
+        /// The index of the station the table is currently at, `None` if it is not within the tolerance of any station
+        pub fn current_station(&self) -> Option<usize> {
+            let angle = self.angle();
+
+            self._stations.iter().position(|(_, station)|
+                Self::shortest(angle, *station).abs() <= self.tolerance
+            )
+        }
+
+        /// The relative distance required to reach the station `index` from the current angle, respecting the allowed
+        /// direction
+        ///
+        /// # Panics
+        ///
+        /// Panics if the station does not exist
+        pub fn dist_to_station(&self, index : usize) -> Radians {
+            let dist = Self::shortest(self.angle(), self._stations[index].1);
+
+            if dist.abs() <= self.tolerance {
+                return Radians::ZERO;
+            }
+
+            match self.direction {
+                Some(Direction::CW) if dist < Radians::ZERO => dist + Radians(2.0 * PI),
+                Some(Direction::CCW) if dist > Radians::ZERO => dist - Radians(2.0 * PI),
+                _ => dist
+            }
+        }
+    //
+
+    /// Rotates the table to the station `index` with the speed factor `speed`, blocks until the movement is done
+    ///
+    /// Returns [ActuatorError::StationNotReached] if the `in_position` sensor does not confirm the station
+    ///
+    /// # Panics
+    ///
+    /// Panics if the station does not exist
+    pub fn goto_station(&mut self, index : usize, speed : Factor) -> Result<(), ActuatorError> {
+        let dist = self.dist_to_station(index);
+
+        if dist != Radians::ZERO {
+            self.set_locked(false)?;
+            self.actuator.drive_rel_blocking(dist, speed)?;
+            self.set_locked(true)?;
+        }
+
+        if let Some(sensor) = &mut self.in_position {
+            if !sensor.read().map_err(|_| ActuatorError::IOError)? {
+                return Err(ActuatorError::StationNotReached(index));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Engages or releases the lock of the table, does nothing if there is no lock
+    pub fn set_locked(&mut self, locked : bool) -> Result<(), ActuatorError> {
+        if let Some(lock) = &mut self.lock {
+            lock.set(locked == self.lock_state).map_err(|_| ActuatorError::IOError)?;
+        }
+
+        Ok(())
+    }
+
+    // Helpers
+        fn wrap(angle : PositionRad) -> PositionRad {
+            PositionRad(angle.0.rem_euclid(2.0 * PI))
+        }
+
+        /// The shortest distance from `from` to `to`, in the range `-PI .. PI`
+        fn shortest(from : PositionRad, to : PositionRad) -> Radians {
+            let dist = (to.0 - from.0).rem_euclid(2.0 * PI);
+            Radians(if dist > PI { dist - 2.0 * PI } else { dist })
+        }
+    //
+}
+
+impl<A : SyncActuatorBlocking + core::fmt::Debug> core::fmt::Debug for IndexingTable<A> {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IndexingTable")
+            .field("actuator", &self.actuator)
+            .field("direction", &self.direction)
+            .field("tolerance", &self.tolerance)
+            .field("stations", &self._stations)
+            .finish()
+    }
+}
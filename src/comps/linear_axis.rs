@@ -1,13 +1,16 @@
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
-use syunit::metric::Millimeters;
+use syunit::metric::{KgMeter2, Kilogramms, Millimeters, NewtonMeters, Newtons, PositionMM};
 
 use crate::SyncActuator;
+use crate::data::CalibrationTable;
 use crate::parent::{ActuatorParent, RatioActuatorParent};
 
 use syunit::*;
 
 /// A linear axis
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LinearAxis<A : SyncActuator> {
     /// The child actuator driving the linear axis
     pub actuator : A,
@@ -18,25 +21,141 @@ pub struct LinearAxis<A : SyncActuator> {
     /// ```
     /// 
     /// is true.
-    pub effective_radius : Millimeters
+    pub effective_radius : Millimeters,
+    /// Optional calibration table compensating the position error of the axis, e.g. of a cheap lead screw
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub calibration : Option<CalibrationTable>,
+    /// The mechanical efficiency of the drive (`0.0 < efficiency <= 1.0`), the torque the actuator requires for an axial load
+    /// and the inertia of the carriage are divided by it, see [LinearAxis::with_efficiency]
+    #[cfg_attr(feature = "serde", serde(default = "efficiency_ideal"))]
+    pub efficiency : f32
+}
+
+#[cfg(feature = "serde")]
+fn efficiency_ideal() -> f32 {
+    1.0
 }
 
 impl<A : SyncActuator> LinearAxis<A> {
     /// Create a new linear axis driven by a tooth belt, with the driving gear having the `radius` given in [Millimeters]
     pub fn new_belt_axis(actuator : A, radius : Millimeters) -> Self {
-        return LinearAxis {
+        LinearAxis {
             actuator,
-            effective_radius: radius
-        };
+            effective_radius: radius,
+            calibration: None,
+            efficiency: 1.0
+        }
     }
 
     /// Create a new linear axis driven by a spindle with the given `pitch` in [Millimeters]
     pub fn new_spindle_axis(actuator : A, pitch : Millimeters) -> Self {
-        return LinearAxis {
+        LinearAxis {
             actuator,
-            effective_radius: pitch / 2.0 / core::f32::consts::PI   // Convert pitch to effective radius
+            effective_radius: pitch / 2.0 / core::f32::consts::PI,   // Convert pitch to effective radius
+            calibration: None,
+            efficiency: 1.0
         }
     }
+
+    /// Create a new linear axis driven by a tooth belt from the `teeth` of the pulley on the actuator and the `belt_pitch` 
+    /// in [Millimeters], one revolution of the actuator moves the axis by `teeth * belt_pitch` millimeters
+    /// 
+    /// ```rust
+    /// use syact::prelude::*;
+    /// 
+    /// // A 20 tooth GT2 pulley (2 mm pitch) moves the axis by 40 mm per revolution
+    /// let axis = LinearAxis::from_pulley(Stepper::simulated(), 20, Millimeters(2.0));
+    /// assert!((axis.effective_radius * 2.0 * core::f32::consts::PI - Millimeters(40.0)).abs() < Millimeters(1e-4));
+    /// ```
+    pub fn from_pulley(actuator : A, teeth : u32, belt_pitch : Millimeters) -> Self {
+        Self::new_spindle_axis(actuator, belt_pitch * teeth as f32)
+    }
+
+    /// Create a new linear axis driven by a screw with the given `lead` in [Millimeters], being the distance the nut 
+    /// moves per revolution of the screw (the pitch multiplied by the number of starts)
+    /// 
+    /// ```rust
+    /// use syact::prelude::*;
+    /// 
+    /// // A four-start T8 screw with 2 mm pitch moves the axis by 8 mm per revolution
+    /// let axis = LinearAxis::from_screw(Stepper::simulated(), Millimeters(8.0));
+    /// assert!((axis.effective_radius * 2.0 * core::f32::consts::PI - Millimeters(8.0)).abs() < Millimeters(1e-4));
+    /// ```
+    pub fn from_screw(actuator : A, lead : Millimeters) -> Self {
+        Self::new_spindle_axis(actuator, lead)
+    }
+
+    // Presets
+        /// Create a new linear axis driven by a ball or lead screw moving the nut by `pitch` in [Millimeters] per revolution, 
+        /// loads are converted with the mechanical `efficiency` of the screw (typically around `0.9` for ball screws and 
+        /// `0.3 - 0.7` for trapezoidal lead screws)
+        /// 
+        /// Axial loads applied with [AdvancedActuator::apply_gen_force](crate::AdvancedActuator::apply_gen_force) in [Newtons] 
+        /// and the mass of the carriage applied with [AdvancedActuator::apply_inertia](crate::AdvancedActuator::apply_inertia)
+        /// in [Kilogramms] are converted into the torque and the inertia the actuator has to overcome.
+        /// 
+        /// ```rust
+        /// use syact::prelude::*;
+        /// 
+        /// // A 10 mm pitch ball screw with an efficiency of 90%
+        /// let mut axis = LinearAxis::ball_screw(Stepper::simulated(), Millimeters(10.0), 0.9);
+        /// 
+        /// // 100 N axial load require 10 mm * 100 N / (2 PI * 0.9) = 0.177 Nm on the motor
+        /// axis.apply_gen_force(Newtons(100.0)).unwrap();
+        /// assert!((axis.actuator.force_gen() - NewtonMeters(0.1768)).abs() < NewtonMeters(1e-3));
+        /// ```
+        /// 
+        /// # Panics
+        /// 
+        /// Panics if the `efficiency` is not within `0.0 < efficiency <= 1.0`
+        pub fn ball_screw(actuator : A, pitch : Millimeters, efficiency : f32) -> Self {
+            Self::new_spindle_axis(actuator, pitch)
+                .with_efficiency(efficiency)
+        }
+
+        /// Create a new linear axis driven by a tooth belt from the `pulley_teeth` of the pulley on the actuator and the 
+        /// `belt_pitch` in [Millimeters], see [LinearAxis::from_pulley]
+        /// 
+        /// Belt drives are assumed to be lossless, use [LinearAxis::with_efficiency] to add the losses of the belt and the 
+        /// guides.
+        /// 
+        /// ```rust
+        /// use syact::prelude::*;
+        /// 
+        /// // A 20 tooth GT2 pulley (2 mm pitch) moving a 2 kg carriage
+        /// let mut axis = LinearAxis::belt(Stepper::simulated(), 20, Millimeters(2.0));
+        /// axis.apply_inertia(Kilogramms(2.0)).unwrap();
+        /// 
+        /// // The carriage acts on the pulley radius of 6.37 mm
+        /// assert!((axis.actuator.inertia() - KgMeter2(8.1e-5)).abs() < KgMeter2(1e-6));
+        /// ```
+        pub fn belt(actuator : A, pulley_teeth : u32, belt_pitch : Millimeters) -> Self {
+            Self::from_pulley(actuator, pulley_teeth, belt_pitch)
+        }
+
+        /// Sets the mechanical `efficiency` of the drive, see [LinearAxis::efficiency]
+        /// 
+        /// # Panics
+        /// 
+        /// Panics if the `efficiency` is not within `0.0 < efficiency <= 1.0`
+        pub fn with_efficiency(mut self, efficiency : f32) -> Self {
+            assert!((efficiency > 0.0) && (efficiency <= 1.0), "The efficiency must be within 0.0 < efficiency <= 1.0! (efficiency: {})", efficiency);
+            self.efficiency = efficiency;
+            self
+        }
+    //
+
+    // Load model
+        /// The torque the actuator has to generate to push the given axial `load`
+        pub fn torque_for_load(&self, load : Newtons) -> NewtonMeters {
+            load * self.effective_radius / self.efficiency
+        }
+
+        /// The maximum axial load the axis can push with the given actuator `torque`
+        pub fn load_max_for_torque(&self, torque : NewtonMeters) -> Newtons {
+            torque * self.efficiency / self.effective_radius
+        }
+    //
 }
 
 // Parent
@@ -60,5 +179,45 @@ impl<A : SyncActuator> LinearAxis<A> {
         fn ratio(&self) -> Self::Ratio {
             self.effective_radius
         }
+
+        fn output_name(&self) -> &'static str {
+            "axis"
+        }
+
+        // Apply the calibration to all position conversions
+        fn pos_for_child(&self, parent_abs_pos : PositionMM) -> PositionRad {
+            let nominal = match &self.calibration {
+                Some(table) => PositionMM(table.nominal_for_actual(parent_abs_pos.0)),
+                None => parent_abs_pos
+            };
+
+            nominal / self.ratio()
+        }
+
+        fn pos_for_parent(&self, child_abs_pos : PositionRad) -> PositionMM {
+            let nominal = child_abs_pos * self.ratio();
+
+            match &self.calibration {
+                Some(table) => PositionMM(table.actual_for_nominal(nominal.0)),
+                None => nominal
+            }
+        }
+
+        // Apply the efficiency of the drive to the loads
+        fn force_for_child(&self, parent_force : Newtons) -> NewtonMeters {
+            self.torque_for_load(parent_force)
+        }
+
+        fn force_for_parent(&self, child_force : NewtonMeters) -> Newtons {
+            self.load_max_for_torque(child_force)
+        }
+
+        fn inertia_for_child(&self, parent_inertia : Kilogramms) -> KgMeter2 {
+            KgMeter2(Kilogramms::reduce(parent_inertia, self.ratio()).0 / self.efficiency)
+        }
+
+        fn inertia_for_parent(&self, child_inertia : KgMeter2) -> Kilogramms {
+            Kilogramms(Kilogramms::extend(child_inertia, self.ratio()).0 * self.efficiency)
+        }
     }
 // 
\ No newline at end of file
@@ -0,0 +1,392 @@
+//! Declarative machine configurations, deserialized from any format supported by serde (TOML, JSON, ...)
+//!
+//! The specs of this module mirror the actuators of the library, a spec tree like `LinearAxisSpec<GearSpec<StepperSpec>>`
+//! is built into a `LinearAxis<Gear<Stepper>>`. The hardware of the motors is created by the factories of a
+//! [ControllerRegistry], selected by the `kind` given in the [ControllerSpec] of every motor.
+//!
+//! ```rust
+//! use syact::prelude::*;
+//! use syact::config::{ActuatorSpec, ControllerRegistry, GearSpec, LinearAxisSpec, StepperSpec};
+//! use syact::devices::SimulatedController;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Machine {
+//!     x : LinearAxisSpec<GearSpec<StepperSpec>>,
+//!     rotary : [StepperSpec; 2]
+//! }
+//!
+//! let doc = serde_json::json!({
+//!     "x": {
+//!         "effective_radius": 5.0,
+//!         "limits": { "pos_min": 0.0, "pos_max": 200.0 },
+//!         "actuator": {
+//!             "ratio": 0.5,
+//!             "actuator": { "controller": { "kind": "sim" }, "consts": "MOT_17HE15_1504S", "config": "VOLT24_NO_OVERLOAD" }
+//!         }
+//!     },
+//!     "rotary": [
+//!         { "controller": { "kind": "sim" }, "consts": "MOT_17HE15_1504S", "config": "VOLT12_NO_OVERLOAD" },
+//!         { "controller": { "kind": "sim" }, "consts": "MOT_23HS45_4204S", "config": "VOLT24_NO_OVERLOAD", "microsteps": 4 }
+//!     ]
+//! });
+//! let machine : Machine = serde_json::from_value(doc).unwrap();
+//!
+//! let mut registry = ControllerRegistry::new();
+//! registry.register("sim", |_| Ok(SimulatedController::new()));
+//!
+//! let x : LinearAxis<Gear<Stepper>> = machine.x.build(&registry).unwrap();
+//! let mut rotary : [Stepper; 2] = machine.rotary.build(&registry).unwrap();
+//!
+//! assert_eq!(x.actuator.ratio, 0.5);
+//! assert_eq!(x.limit_max(), Some(PositionMM(200.0)));
+//!
+//! assert_eq!(rotary[1].microsteps(), MicroSteps::from(4));
+//! rotary[1].drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+//! ```
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use serde::{Serialize, Deserialize};
+use syunit::*;
+use syunit::metric::Millimeters;
+
+use crate::{ActuatorError, DefinedActuator, Gear, LinearAxis, SyncActuator};
+use crate::data::{MicroSteps, StepperConfig, StepperConst};
+use crate::sync::stepper::{AdvancedStepperBuilder, StartStopBuilder, StepperActuator, StepperController, StepperMotor};
+
+// ###############
+// #    Error    #
+// ###############
+    /// Errors that can occur when instantiating a machine from its configuration
+    #[derive(Clone, Debug)]
+    pub enum ConfigError {
+        /// No controller factory has been registered for the given kind
+        UnknownController(String),
+        /// The given name does not refer to a preset of [StepperConst] or [StepperConfig]
+        UnknownPreset(String),
+        /// The controller factory failed to create the controller, e.g. because the pins given are invalid
+        /// - 0 - [String]: The kind of the controller
+        /// - 1 - [String]: A message describing the error
+        Controller(String, String),
+        /// A value given in the configuration is out of range
+        /// - 0 - `&'static str`: The name of the value
+        InvalidValue(&'static str),
+        /// A value given in the configuration has been rejected by the actuator, contains the debug output of the 
+        /// [ActuatorError], as the errors of the actuators in a tree use different units
+        Actuator(String)
+    }
+
+    impl<U : UnitSet> From<ActuatorError<U>> for ConfigError {
+        fn from(value : ActuatorError<U>) -> Self {
+            Self::Actuator(format!("{:?}", value))
+        }
+    }
+
+    impl core::fmt::Display for ConfigError {
+        fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::UnknownController(kind) => write!(f, "no controller factory registered for kind '{}'", kind),
+                Self::UnknownPreset(name) => write!(f, "unknown preset '{}'", name),
+                Self::Controller(kind, msg) => write!(f, "failed to create controller of kind '{}': {}", kind, msg),
+                Self::InvalidValue(name) => write!(f, "the value '{}' is out of range", name),
+                Self::Actuator(err) => write!(f, "invalid actuator configuration: {}", err)
+            }
+        }
+    }
+//
+
+// ############################
+// #    ControllerRegistry    #
+// ############################
+    /// The hardware description of a motor controller, interpreted by the factory registered for its `kind`
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    pub struct ControllerSpec {
+        /// The kind of the controller, selecting the factory of the [ControllerRegistry]
+        pub kind : String,
+        /// The pins used by the controller, their meaning depends on the factory (e.g. `[step, dir]`)
+        #[serde(default)]
+        pub pins : Vec<u32>,
+        /// Additional numeric parameters of the controller, e.g. a PWM frequency
+        #[serde(default)]
+        pub params : BTreeMap<String, f32>
+    }
+
+    /// A factory creating a controller from its [ControllerSpec]
+    pub type ControllerFactory<C> = Box<dyn Fn(&ControllerSpec) -> Result<C, ConfigError>>;
+
+    /// Named factories creating the controllers of the motors described in a configuration
+    pub struct ControllerRegistry<C> {
+        factories : Vec<(String, ControllerFactory<C>)>
+    }
+
+    impl<C> ControllerRegistry<C> {
+        /// Creates a new empty registry
+        pub fn new() -> Self {
+            Self { factories: Vec::new() }
+        }
+
+        /// Registers the `factory` for the given `kind`, replacing any factory with the same kind
+        pub fn register<F : Fn(&ControllerSpec) -> Result<C, ConfigError> + 'static>(&mut self, kind : &str, factory : F) {
+            self.factories.retain(|(k, _)| k != kind);
+            self.factories.push((String::from(kind), Box::new(factory)));
+        }
+
+        /// Registers the `factory` for the given `kind`, see [ControllerRegistry::register]
+        pub fn with<F : Fn(&ControllerSpec) -> Result<C, ConfigError> + 'static>(mut self, kind : &str, factory : F) -> Self {
+            self.register(kind, factory);
+            self
+        }
+
+        /// The kinds of all controllers registered
+        pub fn kinds(&self) -> impl Iterator<Item = &str> {
+            self.factories.iter().map(|(kind, _)| kind.as_str())
+        }
+
+        /// Creates the controller described by the `spec`, returns [ConfigError::UnknownController] if no factory is
+        /// registered for its kind
+        pub fn create(&self, spec : &ControllerSpec) -> Result<C, ConfigError> {
+            let (_, factory) = self.factories.iter().find(|(kind, _)| *kind == spec.kind)
+                .ok_or_else(|| ConfigError::UnknownController(spec.kind.clone()))?;
+
+            factory(spec)
+        }
+    }
+
+    impl<C> Default for ControllerRegistry<C> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<C> core::fmt::Debug for ControllerRegistry<C> {
+        fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_list().entries(self.kinds()).finish()
+        }
+    }
+//
+
+// ######################
+// #    ActuatorSpec    #
+// ######################
+    /// A deserializable description of an actuator, which can be built into the actuator itself
+    pub trait ActuatorSpec<C> {
+        /// The actuator described
+        type Actuator;
+
+        /// Builds the actuator, creating all controllers with the `registry`
+        fn build(&self, registry : &ControllerRegistry<C>) -> Result<Self::Actuator, ConfigError>;
+    }
+
+    /// Limits of an actuator, given in the units of the actuator (e.g. millimeters for a [LinearAxis])
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+    pub struct LimitsSpec {
+        /// The minimum position of the actuator
+        #[serde(default)]
+        pub pos_min : Option<f32>,
+        /// The maximum position of the actuator
+        #[serde(default)]
+        pub pos_max : Option<f32>,
+        /// The maximum velocity of the actuator
+        #[serde(default)]
+        pub velocity_max : Option<f32>,
+        /// The maximum acceleration of the actuator
+        #[serde(default)]
+        pub acceleration_max : Option<f32>
+    }
+
+    impl LimitsSpec {
+        /// Applies the limits given to the `actuator`, limits not given are left untouched
+        pub fn apply<U : UnitSet, A : SyncActuator<U> + ?Sized>(&self, actuator : &mut A) -> Result<(), ActuatorError<U>> {
+            if self.pos_min.is_some() || self.pos_max.is_some() {
+                actuator.set_pos_limits(self.pos_min.map(U::Position::from), self.pos_max.map(U::Position::from));
+            }
+
+            if let Some(velocity) = self.velocity_max {
+                actuator.set_velocity_max(Some(U::Velocity::from(velocity)))?;
+            }
+
+            if let Some(acceleration) = self.acceleration_max {
+                actuator.set_acceleration_max(Some(U::Acceleration::from(acceleration)))?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Either the name of a preset or the values themselves
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(untagged)]
+    pub enum PresetOr<T> {
+        /// The name of the preset, e.g. `"MOT_17HE15_1504S"`
+        Preset(String),
+        /// The values given explicitly
+        Custom(T)
+    }
+
+    impl PresetOr<StepperConst> {
+        /// Resolves the [StepperConst], presets are named like the constants of [StepperConst]
+        pub fn resolve(&self) -> Result<StepperConst, ConfigError> {
+            match self {
+                Self::Preset(name) => match name.as_str() {
+                    "MOT_17HE15_1504S" => Ok(StepperConst::MOT_17HE15_1504S),
+                    "MOT_23HS45_4204S" => Ok(StepperConst::MOT_23HS45_4204S),
+                    _ => Err(ConfigError::UnknownPreset(name.clone()))
+                },
+                Self::Custom(consts) => Ok(consts.clone())
+            }
+        }
+    }
+
+    impl PresetOr<StepperConfig> {
+        /// Resolves the [StepperConfig], presets are named like the constants of [StepperConfig]
+        pub fn resolve(&self) -> Result<StepperConfig, ConfigError> {
+            match self {
+                Self::Preset(name) => match name.as_str() {
+                    "VOLT12_NO_OVERLOAD" => Ok(StepperConfig::VOLT12_NO_OVERLOAD),
+                    "VOLT24_NO_OVERLOAD" => Ok(StepperConfig::VOLT24_NO_OVERLOAD),
+                    "VOLT48_NO_OVERLOAD" => Ok(StepperConfig::VOLT48_NO_OVERLOAD),
+                    _ => Err(ConfigError::UnknownPreset(name.clone()))
+                },
+                Self::Custom(config) => Ok(config.clone())
+            }
+        }
+    }
+
+    /// Description of a [StepperMotor] using the builder `B`
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct StepperSpec<B = StartStopBuilder> {
+        /// The controller driving the motor
+        pub controller : ControllerSpec,
+        /// The constants of the motor
+        pub consts : PresetOr<StepperConst>,
+        /// The configuration of the motor
+        pub config : PresetOr<StepperConfig>,
+        /// The microsteps of the motor, the default of the builder is used if not given
+        #[serde(default)]
+        pub microsteps : Option<MicroSteps>,
+        /// The limits of the motor
+        #[serde(default)]
+        pub limits : LimitsSpec,
+
+        #[serde(skip)]
+        _builder : PhantomData<B>
+    }
+
+    impl<B> StepperSpec<B> {
+        /// Creates a new spec without microsteps and limits
+        pub fn new(controller : ControllerSpec, consts : PresetOr<StepperConst>, config : PresetOr<StepperConfig>) -> Self {
+            Self {
+                controller,
+                consts,
+                config,
+                microsteps: None,
+                limits: LimitsSpec::default(),
+                _builder: PhantomData
+            }
+        }
+    }
+
+    impl<B : AdvancedStepperBuilder + DefinedActuator, C : StepperController> ActuatorSpec<C> for StepperSpec<B> {
+        type Actuator = StepperMotor<B, C>;
+
+        fn build(&self, registry : &ControllerRegistry<C>) -> Result<Self::Actuator, ConfigError> {
+            let ctrl = registry.create(&self.controller)?;
+            let mut motor = StepperMotor::new_advanced(ctrl, self.consts.resolve()?, self.config.resolve()?)?;
+
+            if let Some(microsteps) = self.microsteps {
+                motor.set_microsteps(microsteps)?;
+            }
+
+            self.limits.apply(&mut motor)?;
+            Ok(motor)
+        }
+    }
+
+    /// Description of a [Gear]
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct GearSpec<S> {
+        /// The actuator driving the gear
+        pub actuator : S,
+        /// The ratio of the gear, see [Gear::ratio](crate::Gear)
+        pub ratio : f32,
+        /// The limits of the gear output
+        #[serde(default)]
+        pub limits : LimitsSpec
+    }
+
+    impl<C, S : ActuatorSpec<C>> ActuatorSpec<C> for GearSpec<S>
+    where
+        S::Actuator : SyncActuator
+    {
+        type Actuator = Gear<S::Actuator>;
+
+        fn build(&self, registry : &ControllerRegistry<C>) -> Result<Self::Actuator, ConfigError> {
+            let mut gear = Gear::new(self.actuator.build(registry)?, self.ratio);
+            self.limits.apply(&mut gear)?;
+            Ok(gear)
+        }
+    }
+
+    /// Description of a [LinearAxis]
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct LinearAxisSpec<S> {
+        /// The actuator driving the axis
+        pub actuator : S,
+        /// The effective radius of the axis, see [LinearAxis::effective_radius]
+        pub effective_radius : Millimeters,
+        /// The mechanical efficiency of the drive, see [LinearAxis::with_efficiency]
+        #[serde(default)]
+        pub efficiency : Option<f32>,
+        /// The limits of the axis in millimeters
+        #[serde(default)]
+        pub limits : LimitsSpec
+    }
+
+    impl<C, S : ActuatorSpec<C>> ActuatorSpec<C> for LinearAxisSpec<S>
+    where
+        S::Actuator : SyncActuator
+    {
+        type Actuator = LinearAxis<S::Actuator>;
+
+        fn build(&self, registry : &ControllerRegistry<C>) -> Result<Self::Actuator, ConfigError> {
+            let mut axis = LinearAxis::new_belt_axis(self.actuator.build(registry)?, self.effective_radius);
+
+            if let Some(efficiency) = self.efficiency {
+                if !((0.0 < efficiency) && (efficiency <= 1.0)) {
+                    return Err(ConfigError::InvalidValue("efficiency"));
+                }
+
+                axis.efficiency = efficiency;
+            }
+
+            self.limits.apply(&mut axis)?;
+            Ok(axis)
+        }
+    }
+
+    impl<C, S : ActuatorSpec<C>, const N : usize> ActuatorSpec<C> for [S; N] {
+        type Actuator = [S::Actuator; N];
+
+        fn build(&self, registry : &ControllerRegistry<C>) -> Result<Self::Actuator, ConfigError> {
+            let actuators = self.iter().map(|spec| spec.build(registry)).collect::<Result<Vec<_>, _>>()?;
+
+            match actuators.try_into() {
+                Ok(actuators) => Ok(actuators),
+                Err(_) => unreachable!("The number of actuators built matches the number of specs")
+            }
+        }
+    }
+
+    impl<C, S : ActuatorSpec<C>> ActuatorSpec<C> for Vec<S> {
+        type Actuator = Vec<S::Actuator>;
+
+        fn build(&self, registry : &ControllerRegistry<C>) -> Result<Self::Actuator, ConfigError> {
+            self.iter().map(|spec| spec.build(registry)).collect()
+        }
+    }
+//
@@ -1,12 +1,33 @@
 // ####################
 // #    SUBMODULES    #
 // ####################
+    /// Position calibration tables
+    #[cfg(feature = "alloc")]
+    mod calibration;
+    #[cfg(feature = "alloc")]
+    pub use calibration::{CalibrationTable, CalibrationParseError};
+
+    /// Cross-coupling compensation of actuators driving the same mechanism
+    mod coupling;
+    pub use coupling::CouplingMatrix;
+
+    /// Capabilities of stepper drivers
+    mod driver;
+    pub use driver::{DriverLimit, DriverLimits};
+
+    /// Velocity-dependent jolt limits
+    #[cfg(feature = "alloc")]
+    mod jolt;
+    #[cfg(feature = "alloc")]
+    pub use jolt::JoltTable;
+
     /// Servo motor data
+    #[cfg(feature = "servo")]
     pub mod servo;
     
     /// All data and parameters related to stepper motors
     pub mod stepper;
-    pub use stepper::{StepperConfig, StepperConst, MicroSteps};
+    pub use stepper::{StepperConfig, StepperConst, StepperSnapshot, MicroSteps, RoundingPolicy, VelocityMargin};
 
     /// Crate for variables read and written during runtime
     mod var;
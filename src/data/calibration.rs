@@ -0,0 +1,128 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Amount of iterations used to invert the calibration curve
+const INVERSE_ITERATIONS : usize = 8;
+
+/// A position calibration table, storing the measured position error of an axis at multiple nominal positions
+///
+/// Positions and errors are given in the position unit of the actuator the table is applied to, e.g. millimeters for a
+/// [LinearAxis](crate::LinearAxis). The error is defined as `actual - nominal`.
+///
+/// Between the measured points the error is interpolated with a cubic hermite spline, so the corrected positions have no
+/// kinks that would cause jerking motion. Outside of the table the error of the closest point is used.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CalibrationTable {
+    points : Vec<(f32, f32)>
+}
+
+/// Error returned when parsing a [CalibrationTable] fails
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CalibrationParseError {
+    /// The line (starting at 1) that could not be parsed
+    pub line : usize
+}
+
+impl CalibrationTable {
+    /// Creates a new calibration table out of `(nominal position, error)` points, the points are sorted by position
+    pub fn new(mut points : Vec<(f32, f32)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { points }
+    }
+
+    /// Parses a calibration table out of a text with one `position, error` pair per line
+    ///
+    /// The values can be separated by commas, semicolons or whitespaces, empty lines and lines starting with `#` are ignored
+    pub fn parse(text : &str) -> Result<Self, CalibrationParseError> {
+        let mut points = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut values = line.split(|c : char| (c == ',') | (c == ';') | c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<f32>());
+
+            match (values.next(), values.next(), values.next()) {
+                (Some(Ok(pos)), Some(Ok(error)), None) => points.push((pos, error)),
+                _ => return Err(CalibrationParseError { line: index + 1 })
+            }
+        }
+
+        Ok(Self::new(points))
+    }
+
+    /// The `(nominal position, error)` points of the table
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Returns the interpolated error at the given `nominal` position
+    pub fn error_at(&self, nominal : f32) -> f32 {
+        let n = self.points.len();
+
+        if n == 0 {
+            return 0.0;
+        }
+
+        let (pos_first, error_first) = self.points[0];
+        let (pos_last, error_last) = self.points[n - 1];
+
+        if nominal <= pos_first {
+            return error_first;
+        } else if nominal >= pos_last {
+            return error_last;
+        }
+
+        let i = self.points.partition_point(|p| p.0 <= nominal) - 1;
+
+        let (x0, y0) = self.points[i];
+        let (x1, y1) = self.points[i + 1];
+
+        let h = x1 - x0;
+        let t = (nominal - x0) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        // Cubic hermite basis
+        (2.0*t3 - 3.0*t2 + 1.0) * y0
+            + (t3 - 2.0*t2 + t) * h * self.tangent(i)
+            + (-2.0*t3 + 3.0*t2) * y1
+            + (t3 - t2) * h * self.tangent(i + 1)
+    }
+
+    /// Tangent of the spline at the point `i`, zero at both ends of the table to join the constant error outside smoothly
+    fn tangent(&self, i : usize) -> f32 {
+        if (i == 0) || (i == self.points.len() - 1) {
+            return 0.0;
+        }
+
+        let (x_prev, y_prev) = self.points[i - 1];
+        let (x_next, y_next) = self.points[i + 1];
+
+        (y_next - y_prev) / (x_next - x_prev)
+    }
+
+    /// Returns the actual position the axis reaches when driving to the `nominal` position
+    pub fn actual_for_nominal(&self, nominal : f32) -> f32 {
+        nominal + self.error_at(nominal)
+    }
+
+    /// Returns the nominal position the axis has to drive to in order to reach the `actual` position
+    pub fn nominal_for_actual(&self, actual : f32) -> f32 {
+        let mut nominal = actual;
+
+        for _ in 0 .. INVERSE_ITERATIONS {
+            nominal = actual - self.error_at(nominal);
+        }
+
+        nominal
+    }
+}
@@ -0,0 +1,60 @@
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// A cross-coupling compensation matrix for two actuators driving the same mechanism, e.g. both sides of a gantry
+///
+/// The matrix maps the target movements `(a, b)` of both sides to the movements that have to be commanded, compensating
+/// sides that lag behind (e.g. because of a one-sided load) and motion of one side being transferred to the other one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CouplingMatrix {
+    /// The matrix values in row-major order
+    pub m : [[f32; 2]; 2]
+}
+
+impl CouplingMatrix {
+    /// A matrix that does not change the movements at all
+    pub const IDENTITY : Self = Self { m: [[1.0, 0.0], [0.0, 1.0]] };
+
+    /// Creates the compensation matrix out of the measured response of the mechanism
+    ///
+    /// - `response_a`: The travel `(a, b)` measured on both sides when commanding side `a` only
+    /// - `response_b`: The travel `(a, b)` measured on both sides when commanding side `b` only
+    ///
+    /// The response is normalized by its average direct travel, so the measurements can be given in any unit and only the
+    /// differences between the sides are compensated. Returns `None` if the response cannot be inverted.
+    pub fn from_response(response_a : (f32, f32), response_b : (f32, f32)) -> Option<Self> {
+        let scale = (response_a.0 + response_b.1) / 2.0;
+
+        // Response matrix, the columns are the normalized responses to each side
+        let (r00, r01) = (response_a.0 / scale, response_b.0 / scale);
+        let (r10, r11) = (response_a.1 / scale, response_b.1 / scale);
+
+        let det = r00 * r11 - r01 * r10;
+
+        if !det.is_normal() {
+            return None;
+        }
+
+        Some(Self {
+            m: [
+                [ r11 / det, -r01 / det ],
+                [ -r10 / det, r00 / det ]
+            ]
+        })
+    }
+
+    /// Applies the matrix to the target movements `a` and `b`, returning the movements that have to be commanded
+    pub fn apply(&self, a : f32, b : f32) -> (f32, f32) {
+        (
+            self.m[0][0] * a + self.m[0][1] * b,
+            self.m[1][0] * a + self.m[1][1] * b
+        )
+    }
+}
+
+impl Default for CouplingMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::data::{MicroSteps, StepperConfig, StepperConst};
+
+/// A limit of a stepper driver exceeded by the motor setup or a movement, see [DriverLimits]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DriverLimit {
+    /// The phase current required by the configuration exceeds the current the driver can deliver
+    /// - 0: `f32` - The current required in Ampere
+    /// - 1: `f32` - The maximum current of the driver in Ampere
+    Current(f32, f32),
+    /// The driver does not support the amount of microsteps
+    /// - 0: [MicroSteps] - The microsteps requested
+    /// - 1: [MicroSteps] - The highest microstepping of the driver
+    MicroSteps(MicroSteps, MicroSteps),
+    /// The step rate of a movement is too high for the driver
+    /// - 0: [Hertz] - The step frequency requested
+    /// - 1: [Hertz] - The maximum step frequency of the driver
+    StepFrequency(Hertz, Hertz)
+}
+
+/// The capabilities of a stepper driver, attached to a controller with
+/// [StepperController::driver_limits](crate::sync::stepper::StepperController::driver_limits)
+///
+/// Motors check their constants, configuration and microstepping against the limits when they are set up and reject
+/// movements requiring higher step rates than the driver is able to follow before their first step. Steps exceeding the 
+/// limits during a movement are slowed down to the shortest step time of the driver (see [DriverLimits::step_time_min]).
+///
+/// ```rust
+/// use syact::prelude::*;
+/// use syact::data::{DriverLimit, DriverLimits};
+///
+/// let limits = DriverLimits::A4988;
+///
+/// assert!(limits.check(&StepperConst::MOT_17HE15_1504S, &StepperConfig::VOLT12_NO_OVERLOAD, MicroSteps::from(16)).is_ok());
+/// assert_eq!(
+///     limits.check_microsteps(MicroSteps::from(32)),
+///     Err(DriverLimit::MicroSteps(MicroSteps::from(32), MicroSteps::from(16)))
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DriverLimits {
+    /// The maximum phase current of the driver in Ampere
+    pub current_max : f32,
+    /// The highest microstepping supported by the driver
+    pub microsteps_max : MicroSteps,
+    /// The maximum frequency of the step signal
+    pub step_frequency_max : Hertz
+}
+
+impl DriverLimits {
+    /// Allegro A4988, 2 A with sufficient cooling, up to 16 microsteps, 1 µs minimum high and low times of the step signal
+    pub const A4988 : Self = Self {
+        current_max: 2.0,
+        microsteps_max: MicroSteps(16),
+        step_frequency_max: Hertz(500_000.0)
+    };
+
+    /// Texas Instruments DRV8825, 2.5 A with sufficient cooling, up to 32 microsteps, 1.9 µs minimum high and low times of
+    /// the step signal
+    pub const DRV8825 : Self = Self {
+        current_max: 2.5,
+        microsteps_max: MicroSteps(32),
+        step_frequency_max: Hertz(250_000.0)
+    };
+
+    /// Creates new driver limits
+    pub fn new(current_max : f32, microsteps_max : MicroSteps, step_frequency_max : Hertz) -> Self {
+        Self { current_max, microsteps_max, step_frequency_max }
+    }
+
+    // Checks
+        /// Checks if the driver can deliver the phase current a motor with the `consts` requires with the given `config`
+        pub fn check_current(&self, consts : &StepperConst, config : &StepperConfig) -> Result<(), DriverLimit> {
+            let current = config.current().unwrap_or(consts.default_current);
+
+            if current > self.current_max {
+                Err(DriverLimit::Current(current, self.current_max))
+            } else {
+                Ok(())
+            }
+        }
+
+        /// Checks if the driver supports the given amount of `microsteps`
+        pub fn check_microsteps(&self, microsteps : MicroSteps) -> Result<(), DriverLimit> {
+            if microsteps > self.microsteps_max {
+                Err(DriverLimit::MicroSteps(microsteps, self.microsteps_max))
+            } else {
+                Ok(())
+            }
+        }
+
+        /// Checks if the driver can generate a step taking the given `step_time`
+        pub fn check_step_time(&self, step_time : Seconds) -> Result<(), DriverLimit> {
+            let frequency = Hertz(1.0 / step_time.0);
+
+            if frequency > self.step_frequency_max {
+                Err(DriverLimit::StepFrequency(frequency, self.step_frequency_max))
+            } else {
+                Ok(())
+            }
+        }
+
+        /// Checks if the driver can move a motor with the given `velocity`, each step moving the motor by `step_angle`
+        pub fn check_velocity(&self, velocity : RadPerSecond, step_angle : Radians) -> Result<(), DriverLimit> {
+            self.check_step_time(step_angle / velocity.abs())
+        }
+
+        /// Checks the whole setup of a motor, see [DriverLimits::check_current] and [DriverLimits::check_microsteps]
+        pub fn check(&self, consts : &StepperConst, config : &StepperConfig, microsteps : MicroSteps) -> Result<(), DriverLimit> {
+            self.check_current(consts, config)?;
+            self.check_microsteps(microsteps)
+        }
+    //
+
+    /// The shortest step the driver is able to generate
+    pub fn step_time_min(&self) -> Seconds {
+        Seconds(1.0 / self.step_frequency_max.0)
+    }
+
+    /// The highest velocity the driver is able to step a motor with, each step moving the motor by `step_angle`
+    pub fn velocity_max(&self, step_angle : Radians) -> RadPerSecond {
+        step_angle / self.step_time_min()
+    }
+}
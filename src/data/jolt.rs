@@ -0,0 +1,42 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+/// A table of jolt limits for velocity bands, consulted by the builders with speed levels instead of the global jolt limit
+///
+/// A single jolt limit that keeps high-speed moves smooth is often too conservative for small moves, which never leave the
+/// low velocities. Each band applies its jolt limit to all velocities up to its velocity, the bands do not have to cover the
+/// whole velocity range, above the last band the global jolt limit is used.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JoltTable {
+    bands : Vec<(RadPerSecond, RadPerSecond3)>
+}
+
+impl JoltTable {
+    /// Creates a new jolt table out of `(velocity, jolt)` bands, the bands are sorted by velocity
+    pub fn new(mut bands : Vec<(RadPerSecond, RadPerSecond3)>) -> Self {
+        for band in bands.iter_mut() {
+            *band = (band.0.abs(), band.1.abs());
+        }
+
+        bands.sort_by(|a, b| a.0.0.total_cmp(&b.0.0));
+        Self { bands }
+    }
+
+    /// The `(velocity, jolt)` bands of the table
+    pub fn bands(&self) -> &[(RadPerSecond, RadPerSecond3)] {
+        &self.bands
+    }
+
+    /// Returns the jolt limit of the band the `velocity` lies in, `None` if it lies above all bands
+    pub fn jolt_at(&self, velocity : RadPerSecond) -> Option<RadPerSecond3> {
+        let velocity = velocity.abs();
+
+        self.bands.iter()
+            .find(|(band, _)| velocity <= *band)
+            .map(|(_, jolt)| *jolt)
+    }
+}
@@ -1,10 +1,12 @@
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use syunit::*;
 use syunit::metric::*;
 
 /// A struct for storing all the constants of a servo motor that do not change during the process of the program
-#[derive(Debug, Default, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ServoConst {
     /// Maximum torque of servo motor 
     pub t_max : NewtonMeters,
@@ -37,6 +39,27 @@ impl ServoConst {
         f_pwm: Hertz(50.0)
     };
 
+    /// Returns the constants with the pulse widths `pwm_min` and `pwm_max` measured at the endpoints of the servo and the 
+    /// angle `position_max` between the endpoints
+    /// 
+    /// ## Panics
+    /// 
+    /// Panics if the pulse widths are not positive or not ordered, or if the angle is not positive
+    pub fn with_calibration(mut self, pwm_min : Seconds, pwm_max : Seconds, position_max : PositionRad) -> Self {
+        if !((Seconds::ZERO < pwm_min) && (pwm_min < pwm_max)) {
+            panic!("The pulse widths of a servo must be positive and ordered! (given: {:?} - {:?})", pwm_min, pwm_max);
+        }
+
+        if !(position_max.0.is_finite() && (position_max > PositionRad::ZERO)) {
+            panic!("The angle range of a servo must be positive! (given: {:?})", position_max);
+        }
+
+        self.pwm_min = pwm_min;
+        self.pwm_max = pwm_max;
+        self.position_max = position_max;
+        self
+    }
+
     /// The default pulse time of a servo motor (mid position)
     pub fn default_pulse(&self) -> Seconds {
         (self.pwm_min + self.pwm_max) / 2.0
@@ -61,4 +84,9 @@ impl ServoConst {
     pub fn pulse_for_angle(&self, pos : PositionRad) -> Seconds {
         self.pulse_for_factor(Factor::new(pos / self.position_max))
     }
+
+    /// The duty cycle of the PWM signal for a given [Factor], being the pulse time relative to the period time
+    pub fn duty_for_factor(&self, factor : Factor) -> Factor {
+        Factor::new((self.pulse_for_factor(factor) / self.period_time()).min(1.0))
+    }
 }
\ No newline at end of file
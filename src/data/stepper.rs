@@ -2,6 +2,7 @@ use core::f32::consts::PI;
 use core::ops::Mul;
 use core::str::FromStr;
 
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 use syunit::metric::{KgMeter2, NewtonMeters};
 use syunit::*;
@@ -9,8 +10,9 @@ use syunit::*;
 use crate::data::ActuatorVars;
 
 /// Microsteps used for stepper motors
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct MicroSteps(u8);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MicroSteps(pub(crate) u8);
 
 impl MicroSteps {
     /// Get the representing `u8` value
@@ -57,33 +59,119 @@ impl Mul<MicroSteps> for u64 {
     }
 }
 
+/// Defines how relative distances are converted into a whole number of steps
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RoundingPolicy {
+    /// Round to the closest number of steps, sub-step residue is lost
+    #[default]
+    Round,
+    /// Only drive full steps that fit into the distance (rounding towards zero), sub-step residue is lost
+    Floor,
+    /// Round to the closest number of steps, but carry the sub-step residue over to the next movement, so many small 
+    /// movements do not cause a drift of the position
+    Accumulate
+}
+
+impl RoundingPolicy {
+    /// Converts the exact (fractional) number of `steps` into a whole number of steps, `remainder` stores the residue 
+    /// carried between conversions and is only used with [RoundingPolicy::Accumulate]
+    pub fn apply(&self, steps : f32, remainder : &mut f32) -> i64 {
+        match self {
+            Self::Round => steps.round() as i64,
+            Self::Floor => steps.trunc() as i64,
+            Self::Accumulate => {
+                let steps = steps + *remainder;
+                let steps_whole = steps.round();
+
+                *remainder = steps - steps_whole;
+                steps_whole as i64
+            }
+        }
+    }
+}
+
+/// Defines the safety margin applied to the back-EMF limited maximum velocity of a stepper motor, see 
+/// [StepperConst::velocity_max_with_margin]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VelocityMargin {
+    /// The velocity of the model is used as is
+    #[default]
+    None,
+    /// The velocity of the model is reduced by the given percentage (e.g. `20.0` for 20 %)
+    Percent(f32),
+    /// The velocity of the model is reduced by the given absolute velocity
+    Absolute(RadPerSecond),
+    /// The model is replaced by the maximum velocity measured during a calibration of the machine
+    Ceiling(RadPerSecond)
+}
+
+impl VelocityMargin {
+    /// Applies the margin to the maximum `velocity` of the model, the result is never negative
+    pub fn apply(&self, velocity : RadPerSecond) -> RadPerSecond {
+        match *self {
+            Self::None => velocity,
+            Self::Percent(percent) => velocity * (1.0 - percent / 100.0),
+            Self::Absolute(margin) => velocity - margin,
+            Self::Ceiling(ceiling) => ceiling
+        }.max(RadPerSecond::ZERO)
+    }
+
+    /// Checks if the margin is valid, returns the reason if it is not
+    pub fn check(&self) -> Result<(), &'static str> {
+        match *self {
+            Self::None => Ok(()),
+            Self::Percent(percent) if !((0.0 .. 100.0).contains(&percent)) => Err("The velocity margin must be within 0 % and 100 %"),
+            Self::Absolute(margin) if !(margin.is_finite() && (margin >= RadPerSecond::ZERO)) => Err("The velocity margin must not be negative"),
+            Self::Ceiling(ceiling) if !(ceiling.is_finite() && (ceiling > RadPerSecond::ZERO)) => Err("The velocity ceiling must be positive"),
+            _ => Ok(())
+        }
+    }
+}
+
 /// Stores data for generic components 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StepperConfig {
     /// Supply voltage of the components in Volts
     pub voltage : f32,
 
     /// Overload current of the stepper, can increase torque
-    pub overload_current : Option<f32>
+    pub overload_current : Option<f32>,
+
+    /// Current set on the driver, if it is below the rated current of the motor. The torque scales proportionally
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub drive_current : Option<f32>,
+
+    /// Safety margin applied to the back-EMF limited maximum velocity
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub velocity_margin : VelocityMargin
 }
 
 impl StepperConfig {
     /// The stepper is using 12 Volts and its rated current
     pub const VOLT12_NO_OVERLOAD : Self = Self {
         voltage: 12.0,
-        overload_current: None
+        overload_current: None,
+        drive_current: None,
+        velocity_margin: VelocityMargin::None
     }; 
 
     /// The stepper is using 24 Volts and its rated current
     pub const VOLT24_NO_OVERLOAD : Self = Self {
         voltage: 24.0,
-        overload_current: None
+        overload_current: None,
+        drive_current: None,
+        velocity_margin: VelocityMargin::None
     };
 
     /// The stepper is using 48 Volts and its rated current
     pub const VOLT48_NO_OVERLOAD : Self = Self {
         voltage: 48.0,
-        overload_current: None
+        overload_current: None,
+        drive_current: None,
+        velocity_margin: VelocityMargin::None
     };
 
     /// Creates a new StepperConfig instance
@@ -91,8 +179,45 @@ impl StepperConfig {
     pub fn new(voltage : f32, overload_current : Option<f32>) -> Self {
         Self { 
             voltage,
-            overload_current
+            overload_current,
+            drive_current: None,
+            velocity_margin: VelocityMargin::None
+        }
+    }
+
+    /// The current used for torque calculations, the overload current takes priority over the drive current
+    /// 
+    /// ## Option
+    /// 
+    /// Returns `None` if neither is set, meaning the rated current of the motor is used
+    #[inline]
+    pub fn current(&self) -> Option<f32> {
+        self.overload_current.or(self.drive_current)
+    }
+
+    /// Checks if the configuration can be used with a motor having the given `consts`, returns the reason if it cannot
+    pub fn check(&self, consts : &StepperConst) -> Result<(), &'static str> {
+        if !(self.voltage.is_finite() && (self.voltage > 0.0)) {
+            return Err("The voltage must be positive");
+        }
+
+        if let Some(current) = self.overload_current {
+            if !(current.is_finite() && (current > 0.0)) {
+                return Err("The overload current must be positive");
+            }
+        }
+
+        if let Some(current) = self.drive_current {
+            if !(current.is_finite() && (current > 0.0)) {
+                return Err("The drive current must be positive");
+            }
+
+            if current > consts.default_current {
+                return Err("The drive current exceeds the rated current of the motor");
+            }
         }
+
+        self.velocity_margin.check()
     }
 }
 
@@ -104,7 +229,8 @@ impl StepperConfig {
 /// let mut data = StepperConst::MOT_17HE15_1504S;
 ///
 /// ``` 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct StepperConst {
     /// Max phase current [Unit A]
     pub default_current : f32,
@@ -179,13 +305,34 @@ impl StepperConst {
             }
             
             if velocity == RadPerSecond::ZERO {
-                return self.torque_overload(config.overload_current);
+                return self.torque_overload(config.current());
             }
 
             let time = self.full_step_time(velocity);
             let pow = core::f32::consts::E.powf(time / self.tau(config.voltage));
 
-            self.torque_overload(config.overload_current) * (pow - 1.0) / (pow + 1.0)
+            self.torque_overload(config.current()) * (pow - 1.0) / (pow + 1.0)
+        }
+
+        /// Factor of the torque that is usable when driving with the given amount of `microsteps` at the given `velocity`
+        /// 
+        /// When microstepping at low speeds, the phase currents are distributed between both coils, reducing the usable 
+        /// torque to ~70% (`1 / sqrt(2)`) of the full step torque. With rising speed the inductance prevents the currents 
+        /// from following the sine steps, the motor behaves more and more like in full step mode, so the factor rises 
+        /// linearly to `1.0` at the maximum velocity (see [StepperConst::velocity_max])
+        pub fn torque_factor_microsteps(&self, microsteps : MicroSteps, velocity : RadPerSecond, config : &StepperConfig) -> f32 {
+            if microsteps.as_u8() <= 1 {
+                return 1.0;
+            }
+
+            let ratio = (velocity.abs() / self.velocity_max(config.voltage)).clamp(0.0, 1.0);
+            core::f32::consts::FRAC_1_SQRT_2 + (1.0 - core::f32::consts::FRAC_1_SQRT_2) * ratio
+        }
+
+        /// The dynamic torque (see [StepperConst::torque_dyn]) derated for the given amount of `microsteps`
+        #[inline]
+        pub fn torque_dyn_microsteps(&self, velocity : RadPerSecond, config : &StepperConfig, microsteps : MicroSteps) -> NewtonMeters {
+            self.torque_dyn(velocity, config) * self.torque_factor_microsteps(microsteps, velocity, config)
         }
     // 
 
@@ -201,6 +348,13 @@ impl StepperConst {
         pub fn acceleration_max_for_velocity(&self, vars : &ActuatorVars, config : &StepperConfig, velocity : RadPerSecond, dir : Direction) -> Option<RadPerSecond2> {
             vars.force_after_load(self.torque_dyn(velocity , config), dir).map(|f| f / vars.inertia_after_load(self.inertia_motor))
         }
+
+        /// Returns the maximum acceleration that can be reached at the given `velocity`, considering the torque derating 
+        /// caused by `microsteps` (see [StepperConst::torque_factor_microsteps])
+        #[inline]
+        pub fn acceleration_max_for_velocity_microsteps(&self, vars : &ActuatorVars, config : &StepperConfig, velocity : RadPerSecond, dir : Direction, microsteps : MicroSteps) -> Option<RadPerSecond2> {
+            vars.force_after_load(self.torque_dyn_microsteps(velocity, config, microsteps), dir).map(|f| f / vars.inertia_after_load(self.inertia_motor))
+        }
     // 
 
     // U::Velocity & Inductance
@@ -216,9 +370,15 @@ impl StepperConst {
             RadPerSecond(PI * voltage / self.default_current / self.inductance / self.number_steps as f32)
         }
 
-        /// Returns the start-stop-velocity for a stepper motor
+        /// The maximum speed (see [StepperConst::velocity_max]) with the safety margin of the `config` applied
+        #[inline]
+        pub fn velocity_max_with_margin(&self, config : &StepperConfig) -> RadPerSecond {
+            config.velocity_margin.apply(self.velocity_max(config.voltage))
+        }
+
+        /// Returns the start-stop-velocity for a stepper motor, considering the torque derating caused by `microsteps`
         pub fn velocity_start_stop(&self, vars : &ActuatorVars, config : &StepperConfig, microsteps : MicroSteps) -> Option<RadPerSecond> {
-            vars.force_after_load_lower(self.torque_overload(config.overload_current)).map(|torque| {
+            vars.force_after_load_lower(self.torque_overload(config.current()) * self.torque_factor_microsteps(microsteps, RadPerSecond::ZERO, config)).map(|torque| {
                 RadPerSecond((torque.0 / vars.inertia_after_load(self.inertia_motor).0 * core::f32::consts::PI / (self.number_steps * microsteps) as f32).sqrt())
             })
         }
@@ -321,4 +481,19 @@ impl StepperConst {
             self.steps_from_angle(angle, microsteps) == steps
         }
     //
-}
\ No newline at end of file
+}
+
+/// An owned copy of the data of a stepper motor, taken with [ConfigurableStepper::snapshot](crate::sync::stepper::ConfigurableStepper::snapshot)
+/// 
+/// The snapshot does not borrow the motor, so it can be sent to monitoring threads or serialized for remote protocols while 
+/// the motor is moving
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StepperSnapshot {
+    /// The constants of the motor
+    pub consts : StepperConst,
+    /// The configuration of the motor
+    pub config : StepperConfig,
+    /// The loads and limits applied to the motor
+    pub vars : ActuatorVars
+}
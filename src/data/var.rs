@@ -1,7 +1,14 @@
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 use syunit::*;
 
 /// Stores distance limits for the component
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "U::Position : Serialize",
+    deserialize = "U::Position : Deserialize<'de>"
+)))]
 pub struct Limits<U : UnitSet> {
     /// Limit for minimum pos distance
     pub min : Option<U::Position>,  
@@ -16,6 +23,11 @@ impl<U : UnitSet> Limits<U> {
 
 /// Stores variables that can change in the process of the program
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "U::Force : Serialize, U::Inertia : Serialize, U::Position : Serialize",
+    deserialize = "U::Force : Deserialize<'de>, U::Inertia : Deserialize<'de>, U::Position : Deserialize<'de>"
+)))]
 pub struct ActuatorVars<U : UnitSet = Rotary> {
     /// Load torque general in both directions (e.g. friction), unit Nm
     pub force_load_gen : U::Force,
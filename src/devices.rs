@@ -0,0 +1,36 @@
+// ###############
+// #    Items    #
+// ###############
+    pub use crate::asyn::PwmDevice;
+    pub use crate::comps::{BacklashCompensator, Conveyor, Gantry, GantrySquaring, Gear, GearStage, GearTrain, IndexingTable, LinearAxis, StageDiagnostics};
+    #[cfg(feature = "meas")]
+    pub use crate::comps::ConveyorRegistration;
+    pub use crate::data::{ActuatorVars, CalibrationTable, CouplingMatrix, DriverLimit, DriverLimits, JoltTable, MicroSteps, 
+        RoundingPolicy, StepperConfig, StepperConst, StepperSnapshot, VelocityMargin};
+    #[cfg(feature = "servo")]
+    pub use crate::data::servo::ServoConst;
+    pub use crate::io::{CoolingFan, DigitalInput, DigitalOutput, Encoder, IoRegistry, PwmOutput};
+    pub use crate::parent::{ActuatorParent, LayerPos, PositionLayers, RatioActuatorParent};
+    #[cfg(feature = "servo")]
+    pub use crate::sync::MiniServo;
+    pub use crate::sync::{DcState, PidDcMotor, PidGains};
+    pub use crate::sync::stepper::{builder, AdvancedStepperBuilder, CommandPolicy, CommandQueue, Disturbance, DisturbanceProfile, 
+        DriveMode, FaultKind, FaultRecord, HoldPolicy, InPositionCheck, MinimalStepper, MoveOutcome, PulseController, PulseGenerator, SettlePolicy, SimpleStepperBuilder, 
+        SimulatedAxis, SimulatedController, StartStopBuilder, Stepper, StepperActuator, StepperBuilder, StepperController, StepperMotor, StepperState, StepperStats, StopMode, TrapezoidalBuilder, 
+        TrapezoidalStepper, VelocityConstraint};
+    #[cfg(feature = "complex-builder")]
+    pub use crate::sync::stepper::{ComplexBuilder, ComplexStepper, LoadQuantization};
+//
+
+/// The motors, their builders and data and the components built with them
+pub mod prelude {
+    pub use super::{ActuatorParent, ActuatorVars, AdvancedStepperBuilder, BacklashCompensator, CommandPolicy, Conveyor, Disturbance, DisturbanceProfile, 
+        DriveMode, Gantry, Gear, GearStage, GearTrain, HoldPolicy, IndexingTable, InPositionCheck, LayerPos, LinearAxis, MicroSteps, MinimalStepper, MoveOutcome, 
+        PidDcMotor, PositionLayers, RatioActuatorParent, RoundingPolicy, SettlePolicy, SimpleStepperBuilder, SimulatedAxis, SimulatedController, StartStopBuilder, Stepper, 
+        StepperActuator, StepperBuilder, StepperConfig, StepperConst, StepperController, StepperMotor, StepperState, StopMode, TrapezoidalBuilder, 
+        TrapezoidalStepper, VelocityConstraint, VelocityMargin};
+    #[cfg(feature = "servo")]
+    pub use super::ServoConst;
+    #[cfg(feature = "complex-builder")]
+    pub use super::{ComplexBuilder, ComplexStepper};
+}
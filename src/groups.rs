@@ -0,0 +1,17 @@
+// ###############
+// #    Items    #
+// ###############
+    pub use crate::path::{LinearMove, LinearPathPlanner, Waypoint};
+    pub use crate::sync::SyncActuatorGroup;
+    pub use crate::sync::plotter::ZHopPlotter;
+    pub use crate::sync::stepper::{CompareAction, CompareCondition, ConfigurableStepper, GroupStopPolicy, InterpolatedStepper, 
+        PositionCompare, drive_rel_interpolated, drive_rel_interpolated_compare, drive_rel_interpolated_with, set_config_all, 
+        sync_speed_factors};
+//
+
+/// Coordinated movements of multiple actuators
+pub mod prelude {
+    pub use super::{CompareAction, CompareCondition, ConfigurableStepper, GroupStopPolicy, InterpolatedStepper, LinearPathPlanner, 
+        PositionCompare, SyncActuatorGroup, drive_rel_interpolated, drive_rel_interpolated_compare, drive_rel_interpolated_with, 
+        set_config_all, sync_speed_factors};
+}
@@ -0,0 +1,287 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin, PinState};
+use embedded_hal::pwm::SetDutyCycle;
+use syunit::*;
+
+use crate::SyncActuatorState;
+
+/// The interval in which inputs are polled while waiting for them
+pub const INPUT_POLL_INTERVAL : Seconds = Seconds(0.001);
+
+// ################
+// #    Traits    #
+// ################
+    /// A digital output, e.g. driving a valve or a gripper
+    pub trait DigitalOutput {
+        /// Sets the output to the given `state`
+        fn set(&mut self, state : bool) -> Result<(), IoError>;
+    }
+
+    /// A digital input, e.g. a sensor
+    pub trait DigitalInput {
+        /// Reads the current state of the input
+        fn read(&mut self) -> Result<bool, IoError>;
+    }
+
+    impl<P : OutputPin> DigitalOutput for P {
+        fn set(&mut self, state : bool) -> Result<(), IoError> {
+            self.set_state(PinState::from(state)).map_err(|_| IoError::PinError)
+        }
+    }
+
+    impl<P : InputPin> DigitalInput for P {
+        fn read(&mut self) -> Result<bool, IoError> {
+            self.is_high().map_err(|_| IoError::PinError)
+        }
+    }
+
+    /// An output with a variable duty cycle, e.g. a fan or a heater
+    pub trait PwmOutput {
+        /// Sets the duty cycle of the output
+        fn set_duty(&mut self, duty : Factor) -> Result<(), IoError>;
+    }
+
+    impl<P : SetDutyCycle> PwmOutput for P {
+        fn set_duty(&mut self, duty : Factor) -> Result<(), IoError> {
+            self.set_duty_cycle(duty.get_duty_for(self.max_duty_cycle())).map_err(|_| IoError::PinError)
+        }
+    }
+
+    /// A quadrature encoder counting the increments of a shaft, e.g. the feedback of a DC gearmotor
+    pub trait Encoder {
+        /// Reads the current count of the encoder, rising counts mean a movement in `CW` direction
+        fn count(&mut self) -> Result<i64, IoError>;
+    }
+
+    /// Uses a [DigitalOutput] as [PwmOutput], the output is switched on for every duty cycle above zero
+    #[derive(Debug)]
+    pub struct OnOffOutput<O : DigitalOutput>(pub O);
+
+    impl<O : DigitalOutput> PwmOutput for OnOffOutput<O> {
+        fn set_duty(&mut self, duty : Factor) -> Result<(), IoError> {
+            self.0.set(duty > Factor::MIN)
+        }
+    }
+//
+
+/// Errors that can occur when using digital I/O
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IoError {
+    /// No channel with the given name has been registered
+    UnknownChannel,
+    /// The pin could not be read or written
+    PinError,
+    /// The input did not reach the expected state in time
+    Timeout
+}
+
+/// A registry of named digital inputs and outputs, so grippers, valves and sensors can be used in motion sequences through
+/// the same API as the actuators
+#[derive(Default)]
+pub struct IoRegistry {
+    outputs : Vec<(&'static str, Box<dyn DigitalOutput + Send>)>,
+    inputs : Vec<(&'static str, Box<dyn DigitalInput + Send>)>
+}
+
+impl IoRegistry {
+    /// Creates a new empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Registration
+        /// Registers an output under the given `name`, replacing any output with the same name
+        pub fn add_output(&mut self, name : &'static str, output : Box<dyn DigitalOutput + Send>) {
+            self.outputs.retain(|(n, _)| *n != name);
+            self.outputs.push((name, output));
+        }
+
+        /// Registers an input under the given `name`, replacing any input with the same name
+        pub fn add_input(&mut self, name : &'static str, input : Box<dyn DigitalInput + Send>) {
+            self.inputs.retain(|(n, _)| *n != name);
+            self.inputs.push((name, input));
+        }
+    //
+
+    // Access
+        /// The names of all outputs registered
+        pub fn output_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+            self.outputs.iter().map(|(name, _)| *name)
+        }
+
+        /// The names of all inputs registered
+        pub fn input_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+            self.inputs.iter().map(|(name, _)| *name)
+        }
+
+        /// Sets the output with the given `name` to `state`
+        pub fn set_output(&mut self, name : &str, state : bool) -> Result<(), IoError> {
+            self.outputs.iter_mut()
+                .find(|(n, _)| *n == name)
+                .ok_or(IoError::UnknownChannel)?
+                .1.set(state)
+        }
+
+        /// Reads the input with the given `name`
+        pub fn read_input(&mut self, name : &str) -> Result<bool, IoError> {
+            self.inputs.iter_mut()
+                .find(|(n, _)| *n == name)
+                .ok_or(IoError::UnknownChannel)?
+                .1.read()
+        }
+
+        /// Blocks until the input with the given `name` reaches the `state`, polling it every [INPUT_POLL_INTERVAL]
+        ///
+        /// Returns [IoError::Timeout] if the state is not reached within `timeout`
+        pub fn wait_for_input<D : DelayNs>(&mut self, name : &str, state : bool, timeout : Seconds, delay : &mut D) -> Result<(), IoError> {
+            let mut elapsed = Seconds::ZERO;
+
+            loop {
+                if self.read_input(name)? == state {
+                    return Ok(());
+                }
+
+                if elapsed >= timeout {
+                    return Err(IoError::Timeout);
+                }
+
+                delay.delay_us((INPUT_POLL_INTERVAL.0 * 1_000_000.0) as u32);
+                elapsed += INPUT_POLL_INTERVAL;
+            }
+        }
+    //
+}
+
+// ####################
+// #    CoolingFan    #
+// ####################
+    /// Controls a cooling fan (e.g. of the stepper drivers in an enclosed machine) depending on the recent activity of the axes
+    ///
+    /// The activity is the average of two low-pass filtered values: the duty, being the share of time any axis is moving, 
+    /// and the speed of the fastest axis relative to `velocity_ref`. The fan starts once the activity reaches `activity_on` 
+    /// and runs for the `run_on` time after the activity has dropped below `activity_off`. While running, the duty cycle of 
+    /// the fan scales with the activity, starting at `duty_min`.
+    ///
+    /// The fan has to be polled periodically with the time passed since the last poll.
+    pub struct CoolingFan<O : PwmOutput> {
+        output : O,
+        axes : Vec<(Arc<dyn SyncActuatorState>, PositionRad)>,
+
+        /// Time constant of the low-pass filters averaging the activity
+        pub time_constant : Seconds,
+        /// Axis speed counting as full activity
+        pub velocity_ref : RadPerSecond,
+        /// Activity at which the fan is started
+        pub activity_on : Factor,
+        /// Activity below which the fan is stopped after the run-on time, must be lower than `activity_on` for hysteresis
+        pub activity_off : Factor,
+        /// Time the fan keeps running after the activity has dropped below `activity_off`
+        pub run_on : Seconds,
+        /// Duty cycle of the fan at the lowest activity, many fans do not start below a certain duty cycle
+        pub duty_min : Factor,
+
+        _duty : f32,
+        _speed : f32,
+        _running : bool,
+        _idle : Seconds,
+        _output_duty : Option<Factor>
+    }
+
+    impl<O : PwmOutput> CoolingFan<O> {
+        /// Creates a new fan control without any axes, starting at 30 % activity, stopping 30 seconds after dropping below 
+        /// 10 % activity
+        pub fn new(output : O, velocity_ref : RadPerSecond) -> Self {
+            Self {
+                output,
+                axes: Vec::new(),
+
+                time_constant: Seconds(10.0),
+                velocity_ref: velocity_ref.abs(),
+                activity_on: Factor::new(0.3),
+                activity_off: Factor::new(0.1),
+                run_on: Seconds(30.0),
+                duty_min: Factor::new(0.3),
+
+                _duty: 0.0,
+                _speed: 0.0,
+                _running: false,
+                _idle: Seconds::ZERO,
+                _output_duty: None
+            }
+        }
+
+        /// Adds an axis to monitor, use the state of the actuator (see `SyncActuatorBlocking::clone_state`)
+        pub fn add_axis(&mut self, state : Arc<dyn SyncActuatorState>) {
+            let pos = state.pos();
+            self.axes.push((state, pos));
+        }
+
+        /// The current activity of the axes
+        pub fn activity(&self) -> Factor {
+            Factor::new(((self._duty + self._speed) / 2.0).clamp(0.0, 1.0))
+        }
+
+        /// Returns `true` if the fan is running
+        pub fn running(&self) -> bool {
+            self._running
+        }
+
+        /// Updates the activity and the fan output, `elapsed` is the time passed since the last poll
+        ///
+        /// Returns the duty cycle the fan is running with
+        pub fn poll(&mut self, elapsed : Seconds) -> Result<Factor, IoError> {
+            let mut moving = false;
+            let mut speed : f32 = 0.0;
+
+            for (state, last_pos) in self.axes.iter_mut() {
+                let pos = state.pos();
+
+                moving |= state.moving();
+                if elapsed > Seconds::ZERO {
+                    speed = speed.max(((pos - *last_pos).abs() / elapsed / self.velocity_ref).min(1.0));
+                }
+
+                *last_pos = pos;
+            }
+
+            // Low-pass filters
+            let alpha = elapsed.0 / (self.time_constant.0 + elapsed.0);
+            self._duty += alpha * (if moving { 1.0 } else { 0.0 } - self._duty);
+            self._speed += alpha * (if speed.is_finite() { speed } else { 0.0 } - self._speed);
+
+            let activity = self.activity();
+
+            if activity >= self.activity_on {
+                self._running = true;
+            }
+
+            if activity < self.activity_off {
+                self._idle += elapsed;
+
+                if self._idle >= self.run_on {
+                    self._running = false;
+                }
+            } else {
+                self._idle = Seconds::ZERO;
+            }
+
+            let duty = if self._running {
+                Factor::new(self.duty_min.as_f32() + (1.0 - self.duty_min.as_f32()) * activity.as_f32())
+            } else {
+                Factor::MIN
+            };
+
+            // Only write the output if the duty cycle changed
+            if self._output_duty != Some(duty) {
+                self.output.set_duty(duty)?;
+                self._output_duty = Some(duty);
+            }
+
+            Ok(duty)
+        }
+    }
+//
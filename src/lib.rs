@@ -7,9 +7,11 @@
 #![cfg_attr(feature = "testing", allow(unused))]
 
 // Modules
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
 // Private imports
+#[cfg(feature = "alloc")]
 use alloc::boxed::Box;
 
 use syunit::*;
@@ -19,28 +21,108 @@ use syunit::*;
 // ####################
     // Core
         /// Everything about actuators that work asynchronously
+        #[cfg(feature = "alloc")]
         pub mod asyn;
+        #[cfg(feature = "alloc")]
         pub use asyn::AsyncActuator;
 
+        /// Adapters for pins implementing the traits of `embedded-hal` 0.2
+        #[cfg(feature = "embedded-hal-02")]
+        pub mod compat;
+
+        #[cfg(feature = "alloc")]
         mod comps;
-        pub use comps::{Conveyor, Gear, LinearAxis};
+        #[cfg(feature = "alloc")]
+        pub use comps::{BacklashCompensator, Conveyor, Gantry, GantrySquaring, Gear, GearStage, GearTrain, IndexingTable, LinearAxis, StageDiagnostics};
+        #[cfg(feature = "meas")]
+        pub use comps::ConveyorRegistration;
+
+        /// Declarative machine configurations, deserialized with serde and instantiated through a registry of controllers
+        #[cfg(feature = "serde")]
+        pub mod config;
 
         /// Structs for storing characteristics of stepper motors and so on
         pub mod data;
         pub use data::{MicroSteps, StepperConst, StepperConfig};
 
+        /// Digital inputs and outputs participating in motion sequences
+        #[cfg(feature = "alloc")]
+        pub mod io;
+        #[cfg(feature = "alloc")]
+        pub use io::{CoolingFan, DigitalInput, DigitalOutput, Encoder, IoRegistry, PwmOutput};
+
+        /// Internal logging facade
+        #[cfg(feature = "alloc")]
+        mod logging;
+
+        /// Floating point precision of the internal bookkeeping
+        pub mod math;
+
         /// Functions and Structs for taking measurements with a robot for e.g. position calculation
+        #[cfg(feature = "meas")]
         pub mod meas;
 
         /// Component parent relations and their implementation
+        #[cfg(feature = "alloc")]
         pub mod parent;
-        pub use parent::{ActuatorParent, RatioActuatorParent};
+        #[cfg(feature = "alloc")]
+        pub use parent::{ActuatorParent, LayerPos, PositionLayers, RatioActuatorParent};
+
+        /// Path planning for machines moving a tool with multiple linear axes, e.g. CNC mills or 3D printers
+        #[cfg(feature = "alloc")]
+        pub mod path;
+        #[cfg(feature = "alloc")]
+        pub use path::LinearPathPlanner;
+
+        /// Reusable building blocks for typical application code
+        #[cfg(feature = "alloc")]
+        pub mod recipes;
+
+        /// Recording and playback of trajectories, e.g. for teach-in workflows
+        #[cfg(feature = "alloc")]
+        pub mod record;
+
+        /// Machine capability reports for user interfaces
+        #[cfg(feature = "alloc")]
+        pub mod report;
+
+        /// Safety facilities like emergency stops and guards for subsystems
+        #[cfg(feature = "alloc")]
+        pub mod safety;
+        #[cfg(feature = "alloc")]
+        pub use safety::{CommandSanitizer, EmergencyStop, OverspeedSupervisor, ThermalGuard};
+
+        /// Simulated actuators running on a virtual clock, for testing motion logic without hardware
+        #[cfg(feature = "testing")]
+        pub mod sim;
 
         /// Everything about actuators that work synchronously
         pub mod sync;
-        pub use sync::{SyncActuator, SyncActuatorState, SyncActuatorBlocking, SyncActuatorNB}; 
+        pub use sync::{LimitBehavior, MotionMode};
+        #[cfg(feature = "alloc")]
+        pub use sync::{SyncActuator, SyncActuatorState, SyncActuatorBlocking, SyncActuatorAsync, SyncActuatorGroup, SyncActuatorJog, JogPreset}; 
+        #[cfg(feature = "alloc")]
+        pub use sync::SyncActuatorNB;
     // 
 
+    // Capabilities
+        /// Motors, their builders and data and the components built with them
+        #[cfg(feature = "alloc")]
+        pub mod devices;
+
+        /// Coordinated movements of multiple actuators, from interpolated moves to whole paths
+        #[cfg(feature = "alloc")]
+        pub mod groups;
+
+        /// Actuator traits, movement modes, ramps and the execution of movements
+        #[cfg(feature = "alloc")]
+        pub mod motion;
+
+        /// Interruptors, pre-move checks, guards and everything else watching over the movements
+        #[cfg(feature = "alloc")]
+        pub mod supervision;
+    //
+
     /// Easy import of the functionalities
     pub mod prelude;
 
@@ -54,6 +136,7 @@ use syunit::*;
 // Macros
     // TODO: Improve docs for this macro
     /// Helper macro for merging multiple Actuator traits into one, useful for group implementation
+    #[cfg(feature = "macros")]
     #[macro_export]
     macro_rules! merge_actuator_traits {
         ($name:ident, $trait1:ident, $trait2:ident) => {
@@ -95,21 +178,97 @@ use syunit::*;
         fn set_temp_dir(&mut self, dir_opt : Option<Direction>);
 
         /// Runs a check of the movement process and Interrupts if it has a reason to
-        fn check(&mut self, pos : U::Position) -> Option<InterruptReason>;
+        /// 
+        /// The `ctx` is a snapshot of the actuator taken before the step, the interruptor must not access the actuator itself,
+        /// as it is executed by the thread driving the actuator
+        fn check(&mut self, ctx : &InterruptContext<U>) -> Option<InterruptReason>;
+
+        /// The position the interruptor has actually been triggered at, queried right after [Interruptor::check] interrupted 
+        /// the movement
+        /// 
+        /// Switches are only noticed at the next check after they became active, interruptors able to estimate the real trigger 
+        /// position (e.g. with the check interval and the velocity, see [InterruptContext::interval]) return it here. `None` 
+        /// means the position of the check is used
+        fn trigger_pos(&self) -> Option<U::Position> {
+            None
+        }
+
+        /// Called when the actuator starts a new movement from standstill, e.g. to discard values of the last movement
+        fn begin_movement(&mut self) { }
+
+        /// The kind of the interruptor, e.g. `"EndStop"`, used to describe the sensors of an actuator in reports
+        fn kind(&self) -> &'static str {
+            "Interruptor"
+        }
+
+        /// Identifies the shared object the interruptor belongs to, e.g. the [EmergencyStop](crate::safety::EmergencyStop) 
+        /// that created it, so interruptors of the same kind can be told apart (see [Interruptible::remove_interruptors_of]). 
+        /// `None` if the interruptor does not belong to one
+        fn source(&self) -> Option<usize> {
+            None
+        }
+
+        /// Reads the sensor of the interruptor outside of a movement, used by self-tests (see [SelfTest](crate::report::SelfTest))
+        /// 
+        /// Returns the reason if the interruptor is not ready, the default implementation is always ready
+        fn probe(&mut self) -> Result<(), &'static str> {
+            Ok(())
+        }
+    }
+
+    /// Snapshot of an actuator passed to every [Interruptor] check
+    /// 
+    /// Fields may be added in future versions, create contexts outside of the crate (e.g. to test an interruptor) with 
+    /// [InterruptContext::new]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[non_exhaustive]
+    pub struct InterruptContext<U : UnitSet = Rotary> {
+        /// The current absolute position of the actuator
+        pub pos : U::Position,
+        /// The current velocity of the actuator, positive values mean movement in `CW` direction
+        pub velocity : U::Velocity,
+        /// The current movement direction
+        pub direction : Direction,
+        /// The current mode of the actuator
+        pub mode : MotionMode,
+        /// The time until the next check, e.g. the time of the step about to be executed
+        pub interval : U::Time,
+        /// The time since the last check, e.g. the time of the step that has just been executed, zero for the first check of
+        /// a movement
+        pub last_interval : U::Time
+    }
+
+    impl<U : UnitSet> InterruptContext<U> {
+        /// Creates a new context out of the state of the actuator
+        pub fn new(pos : U::Position, velocity : U::Velocity, direction : Direction, mode : MotionMode, interval : U::Time, 
+        last_interval : U::Time) -> Self {
+            Self {
+                pos,
+                velocity,
+                direction,
+                mode,
+                interval,
+                last_interval
+            }
+        }
     }
 
     /// Reasons why an interrupt was triggered
     #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum InterruptReason {
         /// A virtual end or a switch has been reached
         EndReached,
         /// The component has been overloaded
         Overload,
         /// Another error has occured
-        Error
+        Error,
+        /// The movement has been halted on request, see [SyncActuatorState::halt]
+        Halted
     }
 
     /// Represents an interruptible component, meaning `Interruptors` can be attached to modify the movement process
+    #[cfg(feature = "alloc")]
     pub trait Interruptible<U : UnitSet = Rotary> {
         /// Add an interruptor to the component, often used for measurements or other processes checking the movement
         fn add_interruptor(&mut self, interruptor : Box<dyn Interruptor<U> + Send>);
@@ -123,12 +282,39 @@ use syunit::*;
             self
         }
 
+        /// Removes all interruptors of the given `kind` (see [Interruptor::kind]) from the component, returns the number of 
+        /// interruptors removed
+        /// 
+        /// Components that cannot remove interruptors keep them and return `0`
+        fn remove_interruptors(&mut self, _kind : &str) -> usize {
+            0
+        }
+
+        /// Removes all interruptors of the given `kind` that belong to the given `source` (see [Interruptor::source]) from 
+        /// the component, returns the number of interruptors removed
+        /// 
+        /// Components that cannot remove interruptors keep them and return `0`
+        fn remove_interruptors_of(&mut self, _kind : &str, _source : usize) -> usize {
+            0
+        }
+
         /// Returns the interrupt reason if there is any (returns `None` otherwise)
         /// 
         /// # Note
         /// 
-        /// Executing this function will replace the reason with `None`, so if you need to access the value multiple times, you have to store it yourself
+        /// Executing this function will replace the reason with `None`, so if you need to access the value multiple times, you have to store it yourself.
+        /// The reason only refers to the last movement, actuators may discard a reason that has not been read once the next 
+        /// movement begins
         fn intr_reason(&mut self) -> Option<InterruptReason>;
+
+        /// The position the last movement has been interrupted at by an interruptor, compensated by the interruptor if it 
+        /// supports it (see [Interruptor::trigger_pos]), `None` if the last movement has not been interrupted by one
+        /// 
+        /// Unlike the position the component stops at, this position does not depend on the latency of the checks and the stop
+        /// ramp, use it for references like homing
+        fn intr_pos(&self) -> Option<U::Position> {
+            None
+        }
     }
 //
 
@@ -148,6 +334,10 @@ use syunit::*;
             /// - 0: [U::Velocity] - The given velocity
             /// - 1: [U::Velocity] - The maximum velocity
             VelocityTooHigh(U::Velocity, U::Velocity),
+            /// The velocity given is too low, depending on the context, see the function description
+            /// - 0: [U::Velocity] - The given velocity
+            /// - 1: [U::Velocity] - The minimum velocity
+            VelocityTooLow(U::Velocity, U::Velocity),
         //
 
         // Acceleration
@@ -172,7 +362,56 @@ use syunit::*;
 
         // Load
             /// The component has been overloaded
-            Overload
+            Overload,
+        // 
+
+        // Safety
+            /// A pre-move check failed, the movement has not been started
+            /// - 0: `&'static str` - The name of the check
+            /// - 1: `&'static str` - The reason given by the check
+            PreMoveCheckFailed(&'static str, &'static str),
+            /// An axis of a group has been interrupted, all axes of the group have been stopped
+            /// - 0: `usize` - The index of the interrupted axis
+            /// - 1: [InterruptReason] - The reason of the interrupt
+            AxisInterrupted(usize, InterruptReason),
+        // 
+
+        // Positioning
+            /// The in-position sensor did not confirm the station after the movement
+            /// - 0: `usize` - The index of the station
+            StationNotReached(usize),
+            /// The actuator did not settle in position after the movement
+            /// - 0: [Seconds] - The time waited for the actuator to settle
+            SettleTimeout(Seconds),
+        // 
+
+        // Configuration
+            /// No configuration profile with the given name has been registered
+            UnknownConfigProfile,
+            /// The configuration does not fit the constants of the actuator
+            /// - 0: `&'static str` - The reason the configuration is invalid
+            InvalidConfig(&'static str),
+        // 
+
+        // Limits
+            /// The distance required by the movement does not fit between the current position and the limit
+            /// - 0: [U::Distance] - The distance required
+            /// - 1: [U::Distance] - The distance available until the limit is reached
+            StopDistanceExceedsLimit(U::Distance, U::Distance),
+            /// The motor setup or a movement exceeds the capabilities of the driver, see [DriverLimits](data::DriverLimits)
+            /// - 0: [DriverLimit](data::DriverLimit) - The limit exceeded
+            DriverLimitExceeded(data::DriverLimit),
+        // 
+
+        // Remote operation
+            /// The heartbeat has not been received within the configured interval, the actuator has been stopped
+            HeartbeatLost,
+            /// The shared clock is not synchronized precisely enough for a scheduled start
+            /// - 0: [Seconds] - The uncertainty of the clock synchronization
+            ClockSkewExceeded(Seconds),
+            /// The scheduled start time of a movement has already passed by more than the allowed skew
+            /// - 0: [Seconds] - The time the start is late
+            StartTimeMissed(Seconds)
         // 
     }
 
@@ -214,8 +453,8 @@ use syunit::*;
             /// 
             /// // Create a new gear bearing (implements AdvancedActuator)
             /// let mut gear = Gear::new(
-            ///     // Stepper Motor as subcomponent (also implements AdvancedActuator), `default()` function is only available for tests!
-            ///     Stepper::default(), 
+            ///     // Simulated stepper motor as subcomponent (also implements AdvancedActuator)
+            ///     Stepper::simulated(), 
             ///     // Ratio is set to 0.5, which means for each radian the motor moves, the bearing moves for half a radian
             ///     0.5
             /// );  
@@ -249,8 +488,8 @@ use syunit::*;
             /// 
             /// // Create a new gear bearing (implements AdvancedActuator)
             /// let mut gear = Gear::new(
-            ///     // Stepper Motor as subcomponent (also implements AdvancedActuator), `default()` function is only available for tests!
-            ///     Stepper::default(), 
+            ///     // Simulated stepper motor as subcomponent (also implements AdvancedActuator)
+            ///     Stepper::simulated(), 
             /// 0.5);    // Ratio is set to 0.5, which means for each radian the motor moves, the bearing moves for half a radian
             /// 
             /// // Applies the inertia to the gearbearing component
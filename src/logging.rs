@@ -0,0 +1,66 @@
+//! Internal logging facade, forwarding to the `log` crate (feature "log") and/or `defmt` (feature "defmt")
+//!
+//! Without any of the features the macros compile to nothing. The messages only use `{}` placeholders with primitive
+//! arguments, so they are valid for both backends.
+
+use crate::{InterruptReason, MotionMode};
+
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::$level!($($arg)*);
+
+        #[cfg(feature = "defmt")]
+        defmt::$level!($($arg)*);
+
+        #[cfg(not(any(feature = "log", feature = "defmt")))]
+        if false {
+            let _ = core::format_args!($($arg)*);
+        }
+    }};
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => { $crate::logging::log_event!(debug, $($arg)*) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => { $crate::logging::log_event!(info, $($arg)*) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => { $crate::logging::log_event!(warn, $($arg)*) };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => { $crate::logging::log_event!(error, $($arg)*) };
+}
+
+pub(crate) use {log_event, log_debug, log_info, log_warn, log_error};
+
+/// Rate limit for events that can occur with every step, only the 1st, 2nd, 4th, 8th ... occurence is logged
+pub(crate) fn rate_limited(count : usize) -> bool {
+    count.is_power_of_two()
+}
+
+/// Name of the motion mode used in log messages
+pub(crate) fn mode_name(mode : MotionMode) -> &'static str {
+    match mode {
+        MotionMode::Inactive => "Inactive",
+        MotionMode::Holding => "Holding",
+        MotionMode::ConstVelocity => "ConstVelocity",
+        MotionMode::ConstFactor => "ConstFactor",
+        MotionMode::FixedDistance => "FixedDistance",
+        MotionMode::Stopping => "Stopping"
+    }
+}
+
+/// Name of the interrupt reason used in log messages
+pub(crate) fn reason_name(reason : InterruptReason) -> &'static str {
+    match reason {
+        InterruptReason::EndReached => "EndReached",
+        InterruptReason::Overload => "Overload",
+        InterruptReason::Error => "Error",
+        InterruptReason::Halted => "Halted"
+    }
+}
@@ -0,0 +1,44 @@
+//! Floating point type used for the internal position bookkeeping
+//!
+//! Positions are accumulated step by step, with `f32` long axes at fine resolutions lose steps in the rounding errors of the
+//! accumulation. The `f64` feature switches the bookkeeping to `f64`, the public unit types stay `f32` and are converted
+//! when being read. Use the `*_precise` functions (e.g. [StepperMotor::pos_precise](crate::sync::stepper::StepperMotor::pos_precise)) 
+//! to get the full precision.
+//!
+//! The feature only covers the accumulated absolute positions of the motors and simulated axes and the step timestamps of 
+//! the position prediction. The motion math (builders, ramps, kinematic queries and limits) keeps working in `f32`, as do 
+//! all distances and velocities passed to the actuators, so a single movement is still planned with `f32` precision.
+//!
+//! The `f64` feature requires 64 bit atomics on the target platform.
+//! 
+//! Kinematic queries for planning decisions in application code are found in [kin].
+
+/// Kinematic queries for trapezoidal movements
+pub mod kin;
+
+/// The floating point type used for internal bookkeeping, `f64` with the feature "f64" enabled, `f32` otherwise
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+/// The floating point type used for internal bookkeeping, `f64` with the feature "f64" enabled, `f32` otherwise
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Atomic version of [Float]
+#[cfg(all(feature = "alloc", not(feature = "f64")))]
+pub(crate) type AtomicFloat = atomic_float::AtomicF32;
+/// Atomic version of [Float]
+#[cfg(all(feature = "alloc", feature = "f64"))]
+pub(crate) type AtomicFloat = atomic_float::AtomicF64;
+
+/// Converts an `f32` value of the public unit types into a [Float]
+#[inline]
+pub fn precise(value : f32) -> Float {
+    Float::from(value)
+}
+
+/// Converts a [Float] back into the `f32` used by the public unit types
+#[inline]
+#[allow(clippy::unnecessary_cast)]
+pub fn reduced(value : Float) -> f32 {
+    value as f32
+}
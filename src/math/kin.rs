@@ -0,0 +1,287 @@
+//! Kinematic queries for trapezoidal movements, typed with the units of the actuators
+//!
+//! The functions follow the same model the builders use to plan their movements: constant acceleration up to the maximum 
+//! velocity, optionally a phase with constant velocity and a constant deceleration down to a standstill. Use them to make 
+//! planning decisions like "can the axis still stop before the part?" in application code.
+//!
+//! ```rust
+//! use syact::math::kin;
+//! use syact::units::*;
+//!
+//! let stop = kin::stop_distance::<Rotary>(RadPerSecond(10.0), RadPerSecond2(50.0));
+//! assert_eq!(stop, Radians(1.0));
+//!
+//! let times = kin::travel_times::<Rotary>(Radians(10.0), RadPerSecond(10.0), RadPerSecond2(50.0), RadPerSecond2(100.0));
+//! assert_eq!(times.velocity_peak, RadPerSecond(10.0));
+//! assert!((times.total() - Seconds(1.15)).abs() < Seconds(1e-5));
+//! ```
+
+use core::marker::PhantomData;
+
+use syunit::*;
+
+use crate::ActuatorError;
+#[cfg(feature = "alloc")]
+use crate::SyncActuator;
+
+/// The phases of a trapezoidal movement, see [travel_times]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TravelTimes<U : UnitSet = Rotary> {
+    /// The time spent accelerating
+    pub accel : U::Time,
+    /// The time spent moving with the peak velocity
+    pub cruise : U::Time,
+    /// The time spent decelerating
+    pub decel : U::Time,
+    /// The highest velocity reached, lower than the maximum velocity if the distance is too short to reach it
+    pub velocity_peak : U::Velocity
+}
+
+impl<U : UnitSet> TravelTimes<U> {
+    /// The total time of the movement
+    pub fn total(&self) -> U::Time {
+        self.accel + self.cruise + self.decel
+    }
+}
+
+/// The distance required to change the velocity from `velocity_0` to `velocity_t` with the constant `acceleration`
+pub fn accel_distance<U : UnitSet>(velocity_0 : U::Velocity, velocity_t : U::Velocity, acceleration : U::Acceleration) -> U::Distance {
+    let v_0 : f32 = velocity_0.into();
+    let v_t : f32 = velocity_t.into();
+    let accel : f32 = acceleration.into();
+
+    U::Distance::from((v_t * v_t - v_0 * v_0).abs() / (2.0 * accel.abs()))
+}
+
+/// The distance required to come to a standstill from `velocity` with the constant `deceleration`
+pub fn stop_distance<U : UnitSet>(velocity : U::Velocity, deceleration : U::Acceleration) -> U::Distance {
+    accel_distance::<U>(velocity, U::Velocity::ZERO, deceleration)
+}
+
+/// Returns `true` if a standstill can be reached from `velocity` with the `deceleration` within the `available` distance
+pub fn can_stop_within<U : UnitSet>(velocity : U::Velocity, deceleration : U::Acceleration, available : U::Distance) -> bool {
+    stop_distance::<U>(velocity, deceleration) <= available.abs()
+}
+
+/// The phases of a movement by `rel_dist` starting and ending at a standstill, limited by `velocity_max`, `acceleration`
+/// and `deceleration`
+pub fn travel_times<U : UnitSet>(rel_dist : U::Distance, velocity_max : U::Velocity, acceleration : U::Acceleration, 
+    deceleration : U::Acceleration) -> TravelTimes<U> 
+{
+    let dist : f32 = rel_dist.into();
+    let dist = dist.abs();
+    let v_max : f32 = velocity_max.into();
+    let accel : f32 = acceleration.into();
+    let decel : f32 = deceleration.into();
+    let (v_max, accel, decel) = (v_max.abs(), accel.abs(), decel.abs());
+
+    // The distance both ramps require to reach the maximum velocity
+    let dist_ramps = v_max * v_max / (2.0 * accel) + v_max * v_max / (2.0 * decel);
+
+    let (v_peak, cruise) = if dist_ramps <= dist {
+        (v_max, (dist - dist_ramps) / v_max)
+    } else {
+        // Triangular profile, the ramps meet before the maximum velocity is reached
+        ((2.0 * dist * accel * decel / (accel + decel)).sqrt(), 0.0)
+    };
+
+    TravelTimes {
+        accel: U::Time::from(v_peak / accel),
+        cruise: U::Time::from(cruise),
+        decel: U::Time::from(v_peak / decel),
+        velocity_peak: U::Velocity::from(v_peak)
+    }
+}
+
+/// The distance the `actuator` requires to stop from `velocity`, using its deceleration limit or its acceleration limit if
+/// no separate deceleration is set. Returns `None` if the actuator has neither of them.
+#[cfg(feature = "alloc")]
+pub fn actuator_stop_distance<U : UnitSet, A : SyncActuator<U> + ?Sized>(actuator : &A, velocity : U::Velocity) -> Option<U::Distance> {
+    actuator.deceleration_max()
+        .or(actuator.acceleration_max())
+        .map(|deceleration| stop_distance::<U>(velocity, deceleration))
+}
+
+/// A trapezoidal movement defined by its distance and limits only, without any force or inertia modelling
+/// 
+/// Drive it with [StepperMotor::drive_with_profile](crate::sync::stepper::StepperMotor::drive_with_profile) for simple 
+/// point-to-point moves where the limits are known from the application, e.g. from a datasheet or a quick test. Parents 
+/// like a [LinearAxis](crate::comps::LinearAxis) can convert their profile for the motor with [RampProfile::for_child].
+/// 
+/// ```rust
+/// use syact::prelude::*;
+/// 
+/// // Move 100 mm at 200 mm/s with 500 mm/s² ramps
+/// let mut axis = LinearAxis::from_screw(Stepper::simulated(), Millimeters(8.0));
+/// let profile = RampProfile::<MetricMM>::new(Millimeters(100.0), MMPerSecond(200.0), MMPerSecond2(500.0))
+///     .for_child(axis.ratio().into());
+/// axis.child_mut().drive_with_profile(&profile).unwrap();
+/// 
+/// assert!((axis.pos() - PositionMM(100.0)).abs() < Millimeters(0.1));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RampProfile<U : UnitSet = Rotary> {
+    /// The relative distance of the movement, the sign defines the direction
+    pub distance : U::Distance,
+    /// The velocity of the constant velocity phase, not reached if the distance is too short
+    pub velocity_max : U::Velocity,
+    /// The acceleration at the start of the movement
+    pub acceleration : U::Acceleration,
+    /// The deceleration at the end of the movement
+    pub deceleration : U::Acceleration
+}
+
+impl<U : UnitSet> RampProfile<U> {
+    /// Creates a new profile moving by `distance`, accelerating and decelerating with the same `acceleration`
+    pub fn new(distance : U::Distance, velocity_max : U::Velocity, acceleration : U::Acceleration) -> Self {
+        Self { distance, velocity_max, acceleration, deceleration: acceleration }
+    }
+
+    /// Sets a separate `deceleration` for the end of the movement
+    pub fn with_deceleration(mut self, deceleration : U::Acceleration) -> Self {
+        self.deceleration = deceleration;
+        self
+    }
+
+    /// Checks that the distance is finite and all limits are finite and positive
+    pub fn check(&self) -> Result<(), ActuatorError<U>> {
+        let distance : f32 = self.distance.into();
+        let velocity_max : f32 = self.velocity_max.into();
+
+        if !distance.is_finite() {
+            return Err(ActuatorError::InvaldRelativeDistance(self.distance));
+        }
+
+        if !velocity_max.is_finite() || (velocity_max <= 0.0) {
+            return Err(ActuatorError::InvalidVelocity(self.velocity_max));
+        }
+
+        for acceleration in [ self.acceleration, self.deceleration ] {
+            let accel : f32 = acceleration.into();
+
+            if !accel.is_finite() || (accel <= 0.0) {
+                return Err(ActuatorError::InvalidAcceleration(acceleration));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The phases of the movement, see [travel_times]
+    pub fn times(&self) -> TravelTimes<U> {
+        travel_times::<U>(self.distance, self.velocity_max, self.acceleration, self.deceleration)
+    }
+
+    /// Converts the profile of a parent into the profile its child has to drive, using the `ratio` of the parent (see 
+    /// [RatioActuatorParent::ratio](crate::RatioActuatorParent::ratio))
+    pub fn for_child<V : UnitSet>(&self, ratio : f32) -> RampProfile<V> {
+        let distance : f32 = self.distance.into();
+        let velocity_max : f32 = self.velocity_max.into();
+        let acceleration : f32 = self.acceleration.into();
+        let deceleration : f32 = self.deceleration.into();
+
+        RampProfile {
+            distance: V::Distance::from(distance / ratio),
+            velocity_max: V::Velocity::from(velocity_max / ratio),
+            acceleration: V::Acceleration::from(acceleration / ratio),
+            deceleration: V::Acceleration::from(deceleration / ratio)
+        }
+    }
+
+    /// The intervals between the steps of the movement, each step covering the distance `step_dist`
+    /// 
+    /// The distance is rounded to a whole number of steps, the intervals are taken from the exact timing of the trapezoid.
+    pub fn step_times(&self, step_dist : U::Distance) -> RampSteps<U> {
+        let step : f32 = step_dist.into();
+        let distance : f32 = self.distance.into();
+        let steps = (distance / step).abs().round() as u64;
+
+        // The timing refers to the rounded distance, so the last step ends exactly at the standstill
+        let times = travel_times::<U>(U::Distance::from(step.abs() * steps as f32), self.velocity_max, 
+            self.acceleration, self.deceleration);
+
+        let velocity_peak : f32 = times.velocity_peak.into();
+        let accel : f32 = self.acceleration.into();
+        let decel : f32 = self.deceleration.into();
+
+        RampSteps {
+            step: step.abs(),
+            steps,
+            index: 0,
+            time_prev: 0.0,
+
+            dist_accel: velocity_peak * velocity_peak / (2.0 * accel.abs()),
+            dist_decel: velocity_peak * velocity_peak / (2.0 * decel.abs()),
+            velocity_peak,
+            accel: accel.abs(),
+            decel: decel.abs(),
+            time_accel: times.accel.into(),
+            time_total: times.total().into(),
+
+            _unit: PhantomData
+        }
+    }
+}
+
+/// Iterator over the step intervals of a [RampProfile], see [RampProfile::step_times]
+#[derive(Clone, Debug)]
+pub struct RampSteps<U : UnitSet = Rotary> {
+    step : f32,
+    steps : u64,
+    index : u64,
+    time_prev : f32,
+
+    dist_accel : f32,
+    dist_decel : f32,
+    velocity_peak : f32,
+    accel : f32,
+    decel : f32,
+    time_accel : f32,
+    time_total : f32,
+
+    _unit : PhantomData<U>
+}
+
+impl<U : UnitSet> RampSteps<U> {
+    /// The amount of steps left
+    pub fn remaining(&self) -> u64 {
+        self.steps - self.index
+    }
+
+    /// The time since the start of the movement at which the distance `dist` is reached
+    fn time_at(&self, dist : f32) -> f32 {
+        let dist_total = self.step * self.steps as f32;
+        let dist_decel_start = dist_total - self.dist_decel;
+
+        if dist <= self.dist_accel {
+            (2.0 * dist / self.accel).sqrt()
+        } else if dist <= dist_decel_start {
+            self.time_accel + (dist - self.dist_accel) / self.velocity_peak
+        } else {
+            self.time_total - (2.0 * (dist_total - dist).max(0.0) / self.decel).sqrt()
+        }
+    }
+}
+
+impl<U : UnitSet> Iterator for RampSteps<U> {
+    type Item = U::Time;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.steps {
+            return None;
+        }
+
+        self.index += 1;
+
+        let time = self.time_at(self.step * self.index as f32);
+        let interval = time - self.time_prev;
+        self.time_prev = time;
+
+        Some(U::Time::from(interval))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining() as usize;
+        (remaining, Some(remaining))
+    }
+}
@@ -1,5 +1,6 @@
 use alloc::vec::Vec;
 
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 use syunit::*;
 
@@ -8,6 +9,21 @@ use crate::{ActuatorError, InterruptReason, Interruptible, SyncActuatorBlocking}
 // Submodules
     mod endstop;
     pub use endstop::*;
+
+    mod filter;
+    pub use filter::{Filter, Iir, MovingAverage};
+
+    mod limit;
+    pub use limit::HardLimitSwitch;
+
+    mod load;
+    pub use load::{LoadMonitor, LoadStatus};
+
+    mod registration;
+    pub use registration::{RegistrationLatch, RegistrationSensor};
+
+    mod sensorless;
+    pub use sensorless::SensorlessEndstop;
 // 
 
 // Traits
@@ -27,15 +43,22 @@ use crate::{ActuatorError, InterruptReason, Interruptible, SyncActuatorBlocking}
 
 // Errors
     /// Error that can occur when using simple measurements
+    #[derive(Clone, Debug)]
     pub enum SimpleMeasError<U : UnitSet> {
         /// There was no interrupt triggered while driving, meaning that either
         /// - the interrupt source is out of reach (e.g. endstop is not close enough)
         /// - the interrupt source is broken, not correctly wired or similar
         NoInterrupt,
+        /// The interrupt source is still active after backing off, the switch is stuck or shorted
+        SwitchStuck,
+        /// The movement has been stopped by the position limits of the component before the interrupt source was found
+        TravelExceeded,
         /// The motor stopped because of the wrong reason, e.g. overloading it
         WrongInterruptReason(InterruptReason),
         /// There was an issue with the motor itself
-        SyncActuatorError(ActuatorError<U>)
+        SyncActuatorError(ActuatorError<U>),
+        /// All attempts of the [MeasRetryPolicy] failed, containing the error of each attempt in order
+        RetriesExhausted(Vec<SimpleMeasError<U>>)
     }
 
     impl<U : UnitSet> From<ActuatorError<U>> for SimpleMeasError<U> {
@@ -45,8 +68,35 @@ use crate::{ActuatorError, InterruptReason, Interruptible, SyncActuatorBlocking}
     }
 // 
 
+/// Defines how a failed measurement is retried, see [take_simple_meas]
+/// 
+/// Errors of the motor itself ([SimpleMeasError::SyncActuatorError]) are never retried
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "U::Distance : Serialize", deserialize = "U::Distance : Deserialize<'de>")))]
+pub struct MeasRetryPolicy<U : UnitSet> {
+    /// Number of retries after the first attempt failed
+    pub retries : usize,
+    /// Factor applied to the measurement speed with every retry, slower movements help with switches that trigger too late
+    pub retry_speed : Factor,
+    /// Distance to move in the opposite direction before every retry, releasing stuck switches and extending the search to
+    /// switches the component started behind of
+    pub search_window : Option<U::Distance>
+}
+
+impl<U : UnitSet> Default for MeasRetryPolicy<U> {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            retry_speed: Factor::HALF,
+            search_window: None
+        }
+    }
+}
+
 /// Collection of parameters required for a simple measurement
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SimpleMeasParams<U : UnitSet> {
     /// The pos value to set the component to if the measurement was successful
     pub overwrite_abs_pos : U::Position,
@@ -60,7 +110,22 @@ pub struct SimpleMeasParams<U : UnitSet> {
     /// Number of additional samples to take
     _add_samples : Option<usize>,
     /// Will take 5% of max_dist as default
-    pub sample_dist : Option<U::Distance>
+    pub sample_dist : Option<U::Distance>,
+
+    /// Retry policy used if the measurement fails, by default the measurement is not retried
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub retry : MeasRetryPolicy<U>,
+
+    /// Signed offset of the reference position to the trigger point of the switch, the trigger point is set to 
+    /// `overwrite_abs_pos + homing_offset`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub homing_offset : U::Distance,
+    /// Backlash of the drive train between the motor and the switch, if known
+    /// 
+    /// Positions are referred to the center of the backlash, as the motor runs ahead of the load by half of the backlash in 
+    /// the approach direction. The reference is therefore identical regardless of the side the switch is approached from.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub backlash : Option<U::Distance>
 }
 
 impl<U : UnitSet> SimpleMeasParams<U> {
@@ -81,7 +146,9 @@ pub struct SimpleMeasValues<U : UnitSet> {
     /// Average pos used for the set pos
     pub position_avg : U::Position,
     /// Correction value (offset of current postion and set-pos reference)
-    pub correction : U::Distance
+    pub correction : U::Distance,
+    /// The backlash compensation applied to the reference, see [SimpleMeasParams::backlash]
+    pub backlash_comp : U::Distance
 }
 
 impl<U : UnitSet> SimpleMeasValues<U> {
@@ -101,58 +168,121 @@ impl<U : UnitSet> SimpleMeasValues<U> {
     }
 }
 
-/// Simplest form of measurement by reference position
-/// - `comp`: The component to measure
-/// - `data`: The data defining the measurement
-/// - `speed_f`: The overall speed factor of the measurement
-/// 
-/// # Measurement data and its usage
-/// 
-/// Specifing a `sample_dist` is optional, as the script will replace it with 10% of the maximum distance if not specified
-pub fn take_simple_meas<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized>(comp : &mut C, data : &SimpleMeasParams<U>, speed : Factor) -> Result<SimpleMeasValues<U>, SimpleMeasError<U>> {
+/// Drives the component until it gets interrupted, returns the position the interruptor has been triggered at
+fn search_interrupt<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized>(comp : &mut C, dist : U::Distance, speed : Factor) -> Result<U::Position, SimpleMeasError<U>> {
+    let pos_start = comp.pos();
+    comp.drive_rel_blocking(dist, speed)?;
+    let travelled = comp.pos() - pos_start;
+
+    match comp.intr_reason() {
+        // The component stops behind the trigger position, as the interrupt is noticed with a delay
+        Some(InterruptReason::EndReached) => Ok(comp.intr_pos().unwrap_or(comp.pos())),
+        Some(reason) => Err(SimpleMeasError::WrongInterruptReason(reason)),
+        // The movement stopped early without an interrupt, meaning that the position limits have been reached
+        None if travelled.abs() < (dist.abs() * 0.99) => Err(SimpleMeasError::TravelExceeded),
+        // If no interrupt was triggered, return `MeasError::NoInterrupt`
+        None => Err(SimpleMeasError::NoInterrupt)
+    }
+}
+
+/// A single measurement attempt, returns the positions of all samples
+fn attempt_simple_meas<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized>(comp : &mut C, data : &SimpleMeasParams<U>, 
+    max_dist : U::Distance, speed : Factor, meas_speed : Factor) -> Result<Vec<U::Position>, SimpleMeasError<U>> 
+{
     let mut abs_poss : Vec<U::Position> = Vec::new();
+    let sample_dist = data.sample_dist.unwrap_or(data.max_dist * 0.25);
 
     // Init measurement
         // Drive full distance with optionally reduced speed
-        comp.drive_rel_blocking(data.max_dist, data.meas_speed * speed)?;
-        
-        comp.intr_reason()      // Get the interrupt reason
-            .ok_or(SimpleMeasError::NoInterrupt)
-            .and_then(|reason|
-                if reason == InterruptReason::EndReached { Ok(()) } else { Err(SimpleMeasError::WrongInterruptReason(reason)) }
-            )?;       // If no interrupt was triggered, return `MeasError::NoInterrupt`
-
-        abs_poss.push(comp.pos());
+        abs_poss.push(search_interrupt(comp, max_dist, meas_speed * speed)?);
     //
 
     // Samples
         for _ in 0 .. data.add_samples() {
             // Drive half of the sample distance back (faster)
-            comp.drive_rel_blocking(-data.sample_dist.unwrap_or(data.max_dist * 0.25) / 2.0, speed)?;
-
-            // TODO: Check for errors when moving backwards
+            comp.drive_rel_blocking(-sample_dist / 2.0, speed)?;
 
             // Drive sample distance
-            comp.drive_rel_blocking(data.sample_dist.unwrap_or(data.max_dist * 0.25), data.meas_speed * speed)?;
+            let pos_start = comp.pos();
+            let pos_trigger = search_interrupt(comp, sample_dist, meas_speed * speed)?;
 
-            // Check wheiter the component has been interrupted and if it is the correct interrupt
-            comp.intr_reason()      // Get the interrupt reason
-                .ok_or(SimpleMeasError::NoInterrupt)
-                .and_then(|reason|
-                    if reason == InterruptReason::EndReached { Ok(()) } else { Err(SimpleMeasError::WrongInterruptReason(reason)) }
-                )?;       // If no interrupt was triggered, return `MeasError::NoInterrupt`
+            // The switch must have been released when backing off, triggering right away means it is stuck
+            if (pos_trigger - pos_start).abs() < (sample_dist.abs() * 0.125) {
+                return Err(SimpleMeasError::SwitchStuck);
+            }
 
             // Add the measurement value to the list
-            abs_poss.push(comp.pos());
+            abs_poss.push(pos_trigger);
         }
     // 
 
+    Ok(abs_poss)
+}
+
+/// Simplest form of measurement by reference position
+/// - `comp`: The component to measure
+/// - `data`: The data defining the measurement
+/// - `speed_f`: The overall speed factor of the measurement
+/// 
+/// # Measurement data and its usage
+/// 
+/// Specifing a `sample_dist` is optional, as the script will replace it with 10% of the maximum distance if not specified
+/// 
+/// # Homing offset and backlash
+/// 
+/// The trigger point of the switch is set to the `overwrite_abs_pos` shifted by the signed `homing_offset`. With a known 
+/// `backlash`, half of it is added in the approach direction, so geared axes get the same zero from both sides.
+/// 
+/// The trigger point is taken from [Interruptible::intr_pos], so the stop distance of the component after the switch has 
+/// been noticed does not affect the reference. Switches supporting it (like the [EndStop] with [EndStop::with_latency]) also 
+/// compensate the latency of their checks.
+/// 
+/// # Retries
+/// 
+/// Failed attempts are retried as defined by the [MeasRetryPolicy] of the `data`. If all of them fail, the errors of all 
+/// attempts are returned with [SimpleMeasError::RetriesExhausted], without any retries configured the error is returned directly.
+pub fn take_simple_meas<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized>(comp : &mut C, data : &SimpleMeasParams<U>, speed : Factor) -> Result<SimpleMeasValues<U>, SimpleMeasError<U>> {
+    let mut errors = Vec::new();
+    let mut meas_speed = data.meas_speed;
+
+    let abs_poss = loop {
+        let mut max_dist = data.max_dist;
+
+        // Back off by the search window before retrying, searching the additional distance afterwards
+        if let Some(window) = data.retry.search_window.filter(|_| !errors.is_empty()) {
+            let window = if data.max_dist.is_sign_negative() { -window.abs() } else { window.abs() };
+
+            comp.drive_rel_blocking(-window, speed)?;
+            comp.intr_reason();
+            max_dist += window;
+        }
+
+        match attempt_simple_meas(comp, data, max_dist, speed, meas_speed) {
+            Ok(abs_poss) => break abs_poss,
+            Err(err @ SimpleMeasError::SyncActuatorError(_)) => return Err(err),
+            Err(err) => errors.push(err)
+        }
+
+        if errors.len() > data.retry.retries {
+            return Err(if errors.len() == 1 {
+                errors.remove(0)
+            } else {
+                SimpleMeasError::RetriesExhausted(errors)
+            });
+        }
+
+        meas_speed = meas_speed * data.retry.retry_speed;
+    };
+
     // The average pos of all measurements
     let abs_pos_av = U::Position::from(abs_poss.iter().map(|g| (*g).into()).sum()) / (abs_poss.len() as f32);
     // Current pos difference considering the current position and the average taken by the measurement
     let abs_pos_diff = comp.pos() - abs_pos_av;
+    // The motor runs ahead of the load by half of the backlash in the approach direction
+    let half_backlash = data.backlash.map_or(U::Distance::ZERO, |backlash| backlash.abs() * 0.5);
+    let backlash_comp = if data.max_dist.is_sign_negative() { -half_backlash } else { half_backlash };
     // The new pos to set the components pos to
-    let abs_pos_new = data.overwrite_abs_pos + abs_pos_diff;
+    let abs_pos_new = data.overwrite_abs_pos + data.homing_offset + backlash_comp + abs_pos_diff;
 
     // Set limits and write new distance value
     comp.set_endpos(abs_pos_av);
@@ -163,6 +293,68 @@ pub fn take_simple_meas<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible
 
         positions: abs_poss,
         position_avg: abs_pos_av,
-        correction: abs_pos_diff
+        correction: abs_pos_diff,
+        backlash_comp
     })
-}
\ No newline at end of file
+}
+
+/// Moves the component off an active end switch, e.g. after it has been switched on while sitting on the switch
+/// - `comp`: The component to move
+/// - `recovery`: The recovery state of the switch, see [Recoverable::recovery] and [HardLimitSwitch::recovery]
+/// - `max_dist`: Maximum distance to move until the switch is released, the sign defines the direction away from the switch
+/// - `margin`: Distance to move on after the switch has been released
+/// - `speed`: Speed factor of the movement, slow movements stop closer to the release point
+/// 
+/// The direction away from the switch is unlocked temporarily, the switch then stops the movement once it has been released.
+/// Works with every interruptor wrapped in a [Recoverable] and with a [HardLimitSwitch], whose latch is cleared once the 
+/// switch has been released.
+/// The position limits of the component are lifted during the routine, both they and the switch are restored in every case.
+/// Returns the position the component stopped at after the switch has been released.
+/// 
+/// # Errors
+/// 
+/// Returns [SimpleMeasError::SwitchStuck] if the switch is not released within `max_dist`
+pub fn move_off_switch<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized>(comp : &mut C, recovery : &SwitchRecovery, 
+    max_dist : U::Distance, margin : U::Distance, speed : Factor) -> Result<U::Position, SimpleMeasError<U>> 
+{
+    let limit_min = comp.limit_min();
+    let limit_max = comp.limit_max();
+
+    recovery.begin(Direction::from_bool(!max_dist.is_sign_negative()));
+    comp.overwrite_pos_limits(None, None);
+    // Discard the interrupt that stopped the component on the switch
+    comp.intr_reason();
+
+    let result = release_switch(comp, recovery, max_dist, margin, speed);
+
+    comp.overwrite_pos_limits(limit_min, limit_max);
+    recovery.end();
+
+    result
+}
+
+/// Drives until the switch is released and on by the margin, returns the position the release stopped the component at
+fn release_switch<U : UnitSet, C : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized>(comp : &mut C, recovery : &SwitchRecovery, 
+    max_dist : U::Distance, margin : U::Distance, speed : Factor) -> Result<U::Position, SimpleMeasError<U>> 
+{
+    comp.drive_rel_blocking(max_dist, speed)?;
+
+    match comp.intr_reason() {
+        Some(InterruptReason::EndReached) if !recovery.active() => { },
+        Some(reason) => return Err(SimpleMeasError::WrongInterruptReason(reason)),
+        None => return Err(SimpleMeasError::SwitchStuck)
+    }
+
+    // The switch has been released, it stops movements in both directions again
+    recovery.release();
+
+    let pos_release = comp.pos();
+    comp.drive_rel_blocking(if max_dist.is_sign_negative() { -margin.abs() } else { margin.abs() }, speed)?;
+
+    // Another interruptor stopped the component
+    if let Some(reason) = comp.intr_reason() {
+        return Err(SimpleMeasError::WrongInterruptReason(reason));
+    }
+
+    Ok(pos_release)
+}
@@ -1,21 +1,250 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::sync::Arc;
+
+use atomic_float::AtomicF32;
 use embedded_hal::digital::InputPin;
+#[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 use syunit::*;
 
-use crate::{Interruptor, InterruptReason};
+use crate::{InterruptContext, Interruptor, InterruptReason};
+use crate::logging;
 use crate::meas::Measurable;
 
+/// Diagnostics of an [EndStop], shared with other threads
+///
+/// Flaky switches and broken wiring cause triggers at random positions, the diagnostics count them instead of silently 
+/// stopping the movement, see [EndStop::with_diagnostics]
+#[derive(Debug, Default)]
+pub struct SwitchDiagnostics {
+    _edges : AtomicUsize,
+    _glitches : AtomicUsize,
+    _implausible : AtomicUsize,
+    _fault : AtomicBool
+}
+
+impl SwitchDiagnostics {
+    /// The amount of times the switch became active
+    pub fn edges(&self) -> usize {
+        self._edges.load(Relaxed)
+    }
+
+    /// The amount of activations that were shorter than the filter of the switch and have therefore been ignored
+    pub fn glitches(&self) -> usize {
+        self._glitches.load(Relaxed)
+    }
+
+    /// The amount of activations while moving away from the switch, which are physically impossible
+    pub fn implausible(&self) -> usize {
+        self._implausible.load(Relaxed)
+    }
+
+    /// Returns `true` if the glitches and implausible activations have reached the fault threshold of the switch, indicating
+    /// a wiring fault or a broken switch
+    pub fn fault(&self) -> bool {
+        self._fault.load(Relaxed)
+    }
+
+    /// Resets all counters and the fault state
+    pub fn reset(&self) {
+        self._edges.store(0, Relaxed);
+        self._glitches.store(0, Relaxed);
+        self._implausible.store(0, Relaxed);
+        self._fault.store(false, Relaxed);
+    }
+}
+
+/// Recovery state of a [Recoverable] interruptor or a [HardLimitSwitch](crate::meas::HardLimitSwitch), shared with the 
+/// routine moving the component off the switch, see [move_off_switch](crate::meas::move_off_switch)
+/// 
+/// Hard limit switches latch the state once they have been hit, it stays latched until the component has been moved off
+/// the switch
+#[derive(Debug, Default)]
+pub struct SwitchRecovery {
+    _active : AtomicBool,
+    _recovering : AtomicBool,
+    _away : AtomicBool,
+
+    _latched : AtomicBool,
+    _direction : AtomicBool,
+    _pos : AtomicF32
+}
+
+impl SwitchRecovery {
+    /// Returns `true` if the interruptor has been triggered during the last check
+    pub fn active(&self) -> bool {
+        self._active.load(Relaxed)
+    }
+
+    /// The direction away from the switch if a recovery is in progress
+    pub fn recovering(&self) -> Option<Direction> {
+        self._recovering.load(Relaxed).then(|| Direction::from_bool(self._away.load(Relaxed)))
+    }
+
+    /// The direction in which the switch has been hit, `None` if the state is not latched
+    pub fn latched(&self) -> Option<Direction> {
+        self._latched.load(Relaxed).then(|| Direction::from_bool(self._direction.load(Relaxed)))
+    }
+
+    /// The position at which the switch has been hit, given as `f32` in the position unit of the component. For hard limit
+    /// switches it marks the hard limit, which is tracked independently from the soft limits of the component
+    pub fn pos(&self) -> f32 {
+        self._pos.load(Relaxed)
+    }
+
+    /// Unlocks movements in the direction `away` from the switch, which are stopped once the switch has been released
+    pub(crate) fn begin(&self, away : Direction) {
+        self._away.store(away.as_bool(), Relaxed);
+        self._recovering.store(true, Relaxed);
+    }
+
+    /// Locks the switch again
+    pub(crate) fn end(&self) {
+        self._recovering.store(false, Relaxed);
+    }
+
+    /// Locks the switch again after it has been released, clearing the latch
+    pub(crate) fn release(&self) {
+        self._latched.store(false, Relaxed);
+        self.end();
+    }
+
+    pub(crate) fn latch(&self, direction : Direction, pos : f32) {
+        self._pos.store(pos, Relaxed);
+        self._direction.store(direction.as_bool(), Relaxed);
+        self._latched.store(true, Relaxed);
+    }
+
+    pub(crate) fn set_active(&self, active : bool) {
+        self._active.store(active, Relaxed);
+    }
+}
+
+/// Wraps any [Interruptor] (e.g. an [EndStop]), so the component can be moved off it with 
+/// [move_off_switch](crate::meas::move_off_switch)
+/// 
+/// Outside of a recovery the interruptor behaves as usual, including its temporary direction. During a recovery the movement 
+/// away from the switch is unlocked and the interruptor is checked as if the component moved towards the switch, the movement 
+/// is stopped once it does not trigger anymore.
+#[derive(Debug)]
+pub struct Recoverable<I> {
+    inner : I,
+    _recovery : Arc<SwitchRecovery>
+}
+
+impl<I> Recoverable<I> {
+    /// Wraps the `inner` interruptor
+    pub fn new(inner : I) -> Self {
+        Self {
+            inner,
+            _recovery: Arc::new(SwitchRecovery::default())
+        }
+    }
+
+    /// Returns the recovery state, required to move the component off the switch with 
+    /// [move_off_switch](crate::meas::move_off_switch)
+    pub fn recovery(&self) -> Arc<SwitchRecovery> {
+        self._recovery.clone()
+    }
+
+    /// The wrapped interruptor
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    /// The wrapped interruptor
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+}
+
+impl<U : UnitSet, I : Interruptor<U>> Interruptor<U> for Recoverable<I> {
+    fn dir(&self) -> Option<Direction> {
+        // The switch is checked in both directions during a recovery, so its release is noticed
+        if self._recovery.recovering().is_some() {
+            return None;
+        }
+
+        self.inner.dir()
+    }
+
+    fn set_temp_dir(&mut self, dir_opt : Option<Direction>) {
+        // The release of the switch during a recovery must not lock the direction towards it
+        if self._recovery.recovering().is_some() {
+            return;
+        }
+
+        self.inner.set_temp_dir(dir_opt)
+    }
+
+    fn check(&mut self, ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+        let Some(away) = self._recovery.recovering().filter(|&away| away == ctx.direction) else {
+            let reason = self.inner.check(ctx);
+            self._recovery._active.store(reason.is_some(), Relaxed);
+            return reason;
+        };
+
+        // The switch is still active as long as it would stop a movement towards it
+        let active = self.inner.check(&InterruptContext { direction: Direction::from_bool(!away.as_bool()), ..*ctx }).is_some();
+        self._recovery._active.store(active, Relaxed);
+
+        // A recovery is finished once the switch has been released
+        (!active).then_some(InterruptReason::EndReached)
+    }
+
+    fn trigger_pos(&self) -> Option<U::Position> {
+        self.inner.trigger_pos()
+    }
+
+    fn begin_movement(&mut self) {
+        self.inner.begin_movement()
+    }
+
+    fn kind(&self) -> &'static str {
+        self.inner.kind()
+    }
+
+    fn probe(&mut self) -> Result<(), &'static str> {
+        self.inner.probe()
+    }
+}
+
 /// A simple endswitch that can trigger when reaching a destination
-#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct EndStop<P : InputPin> {
     trigger : bool,
     _dir : Option<Direction>, 
     temp_dir : Option<Direction>,
 
-    #[serde(skip)]
+    /// Amount of consecutive checks the switch has to be active for in order to interrupt the movement
+    #[cfg_attr(feature = "serde", serde(default = "default_filter_samples"))]
+    filter_samples : usize,
+    /// Amount of glitches and implausible activations until the switch reports a fault, `None` disables the monitoring
+    #[cfg_attr(feature = "serde", serde(default))]
+    fault_threshold : Option<usize>,
+    /// Delay between the switch becoming active and its pin changing, e.g. caused by optocouplers or RC filters
+    #[cfg_attr(feature = "serde", serde(default))]
+    latency : Seconds,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _diag : Arc<SwitchDiagnostics>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _active_samples : usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _released : bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _trigger_pos : Option<PositionRad>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
     sys_pin : P
 }
 
+fn default_filter_samples() -> usize {
+    1
+}
+
 impl<P : InputPin> EndStop<P> {
     /// Creates a new end switch
     pub fn new(trigger : bool, dir : Option<Direction>, sys_pin : P) -> Self {
@@ -24,9 +253,69 @@ impl<P : InputPin> EndStop<P> {
             _dir: dir,
             temp_dir: None,
 
+            filter_samples: default_filter_samples(),
+            fault_threshold: None,
+            latency: Seconds::ZERO,
+
+            _diag: Arc::new(SwitchDiagnostics::default()),
+            _active_samples: 0,
+            _released: false,
+            _trigger_pos: None,
+
             sys_pin
         }
     }
+
+    /// Enables the chatter filter and the fault detection of the switch
+    /// - `filter_samples`: The switch has to be active for this amount of consecutive checks in order to interrupt the 
+    ///   movement, shorter activations are counted as glitches
+    /// - `fault_threshold`: Amount of glitches and implausible activations until [SwitchDiagnostics::fault] is set
+    ///
+    /// For directional switches the monitoring requires the switch to be checked in both directions, activations while moving
+    /// away from the switch are counted as implausible and never interrupt the movement.
+    pub fn with_diagnostics(mut self, filter_samples : usize, fault_threshold : usize) -> Self {
+        self.filter_samples = filter_samples.max(1);
+        self.fault_threshold = Some(fault_threshold);
+        self
+    }
+
+    /// Sets the delay between the switch becoming active and its pin changing
+    /// 
+    /// The switch became active somewhere within the step executed before the check that noticed it, the trigger position 
+    /// reported with [Interruptor::trigger_pos] is therefore moved back by half of [InterruptContext::last_interval] and the 
+    /// `latency`, using the velocity of the actuator at the check.
+    pub fn with_latency(mut self, latency : Seconds) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Returns the diagnostics of the switch
+    pub fn diagnostics(&self) -> Arc<SwitchDiagnostics> {
+        self._diag.clone()
+    }
+
+    /// Counts a glitch or implausible activation and sets the fault state once the threshold has been reached
+    fn count_anomaly(&self, counter : &AtomicUsize) {
+        counter.fetch_add(1, Relaxed);
+
+        if let Some(threshold) = self.fault_threshold {
+            if !self._diag.fault() && (self._diag.glitches() + self._diag.implausible()) >= threshold {
+                logging::log_warn!("End switch reports implausible activations, check the wiring");
+                self._diag._fault.store(true, Relaxed);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<P : embedded_hal_02::digital::v2::InputPin> EndStop<crate::compat::CompatInput02<P>> 
+where
+    P::Error : core::fmt::Debug
+{
+    /// Creates a new end switch from a pin implementing the `embedded-hal` 0.2 traits
+    pub fn new_02(trigger : bool, dir : Option<Direction>, sys_pin : P) -> Self {
+        Self::new(trigger, dir, crate::compat::CompatInput02::new(sys_pin))
+    }
 }
 
 impl<P : InputPin> Measurable<bool> for EndStop<P> {
@@ -39,17 +328,63 @@ impl<P : InputPin> Measurable<bool> for EndStop<P> {
 
 impl<P : InputPin> Interruptor<Rotary> for EndStop<P> {
     fn dir(&self) -> Option<Direction> {
-        self._dir.or(self.temp_dir)
+        // Monitored switches are checked in both directions, the direction is evaluated in the check itself
+        if self.fault_threshold.is_some() {
+            self.temp_dir
+        } else {
+            self._dir.or(self.temp_dir)
+        }
     }
 
     fn set_temp_dir(&mut self, dir_opt : Option<Direction>) {
         self.temp_dir = dir_opt;
     }
 
-    fn check(&mut self, _abs_pos : PositionRad) -> Option<InterruptReason> {
+    fn kind(&self) -> &'static str {
+        "EndStop"
+    }
+
+    fn probe(&mut self) -> Result<(), &'static str> {
+        self.sys_pin.is_high().map(|_| ()).map_err(|_| "the input of the switch cannot be read")
+    }
+
+    fn trigger_pos(&self) -> Option<PositionRad> {
+        self._trigger_pos
+    }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
         // TODO: Add errors to implementation
         // unwraping unsafe is safe, as no error can occur
-        if unsafe { self.sys_pin.is_high().unwrap_unchecked() } == self.trigger {    
+        let active = unsafe { self.sys_pin.is_high().unwrap_unchecked() } == self.trigger;
+
+        if !active {
+            if (0 < self._active_samples) && (self._active_samples < self.filter_samples) {
+                self.count_anomaly(&self._diag._glitches);
+            }
+
+            self._active_samples = 0;
+            self._released = true;
+            self._trigger_pos = None;
+            return None;
+        }
+
+        if self._active_samples == 0 {
+            self._diag._edges.fetch_add(1, Relaxed);
+            self._trigger_pos = Some(ctx.pos - ctx.velocity * (ctx.last_interval * 0.5 + self.latency));
+
+            // A switch cannot become active while the axis moves away from it, unless the axis started on the switch
+            if self._released && self.fault_threshold.is_some() && self._dir.is_some_and(|dir| dir != ctx.direction) {
+                self.count_anomaly(&self._diag._implausible);
+            }
+        }
+
+        self._active_samples = self._active_samples.saturating_add(1);
+
+        if self.fault_threshold.is_some() && self._dir.is_some_and(|dir| dir != ctx.direction) {
+            return None;
+        }
+
+        if self._active_samples >= self.filter_samples {
             Some(InterruptReason::EndReached)
         } else {
             None
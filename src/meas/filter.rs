@@ -0,0 +1,111 @@
+use alloc::collections::VecDeque;
+
+use syunit::*;
+
+/// A filter smoothing the samples of a sensor, typed in the unit of the measured value
+pub trait Filter<T : Unit> {
+    /// Adds a new `sample` and returns the filtered value
+    fn update(&mut self, sample : T) -> T;
+
+    /// The current filtered value, `None` if no samples have been added yet
+    fn value(&self) -> Option<T>;
+
+    /// Discards all samples, e.g. after the sensor has been reconnected
+    fn reset(&mut self);
+}
+
+/// Averages the last `window` samples, suppressing noise without any phase shift for constant values
+#[derive(Clone, Debug)]
+pub struct MovingAverage<T : Unit> {
+    samples : VecDeque<T>,
+    window : usize
+}
+
+impl<T : Unit> MovingAverage<T> {
+    /// Creates a new moving average over the last `window` samples
+    pub fn new(window : usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            window: window.max(1)
+        }
+    }
+
+    /// The amount of samples averaged
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+impl<T : Unit> Filter<T> for MovingAverage<T> {
+    fn update(&mut self, sample : T) -> T {
+        if self.samples.len() >= self.window {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+
+        // Recalculated each time, so no rounding errors accumulate
+        let sum : f32 = self.samples.iter().map(|s| Into::<f32>::into(*s)).sum();
+        T::from(sum / self.samples.len() as f32)
+    }
+
+    fn value(&self) -> Option<T> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let sum : f32 = self.samples.iter().map(|s| Into::<f32>::into(*s)).sum();
+        Some(T::from(sum / self.samples.len() as f32))
+    }
+
+    fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// A first order low-pass filter (exponential smoothing), the first sample initializes the filter
+#[derive(Clone, Debug)]
+pub struct Iir<T : Unit> {
+    /// The weight of each new sample, `1.0` disables the filter
+    pub alpha : Factor,
+    
+    _value : Option<T>
+}
+
+impl<T : Unit> Iir<T> {
+    /// Creates a new filter weighting each new sample with `alpha`
+    pub fn new(alpha : Factor) -> Self {
+        Self {
+            alpha,
+            _value: None
+        }
+    }
+
+    /// Creates a new filter with the given `time_constant`, updated every `sample_time`
+    pub fn from_time_constant(time_constant : Seconds, sample_time : Seconds) -> Self {
+        Self::new(Factor::try_new(sample_time.0 / (time_constant.0 + sample_time.0)).unwrap_or(Factor::MAX))
+    }
+}
+
+impl<T : Unit> Filter<T> for Iir<T> {
+    fn update(&mut self, sample : T) -> T {
+        let value = match self._value {
+            Some(value) => {
+                let value : f32 = value.into();
+                T::from(value + self.alpha.as_f32() * (Into::<f32>::into(sample) - value))
+            },
+            None => sample
+        };
+
+        self._value = Some(value);
+        value
+    }
+
+    fn value(&self) -> Option<T> {
+        self._value
+    }
+
+    fn reset(&mut self) {
+        self._value = None;
+    }
+}
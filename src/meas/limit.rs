@@ -0,0 +1,90 @@
+use alloc::sync::Arc;
+
+use embedded_hal::digital::InputPin;
+use syunit::*;
+
+use crate::{logging, InterruptContext, Interruptor, InterruptReason};
+use crate::meas::SwitchRecovery;
+
+/// A hard limit switch at one end of the travel of an actuator
+///
+/// Unlike an [EndStop](crate::meas::EndStop) used for homing, hitting the switch latches its [SwitchRecovery]. While 
+/// latched, the switch stops every movement of the actuator with its first check, in both directions, until the actuator 
+/// has been moved off the switch with [move_off_switch](crate::meas::move_off_switch).
+///
+/// The switch overrides the soft limits (see [SyncActuator::set_pos_limits](crate::SyncActuator::set_pos_limits)) of the
+/// actuator, it stops movements even within them. Errors reading the pin count as an active switch.
+pub struct HardLimitSwitch<P : InputPin> {
+    trigger : bool,
+    direction : Direction,
+    sys_pin : P,
+
+    _recovery : Arc<SwitchRecovery>
+}
+
+impl<P : InputPin> HardLimitSwitch<P> {
+    /// Creates a new hard limit switch that is active if the pin equals the `trigger` state, placed at the end of travel
+    /// in the given `direction`
+    pub fn new(trigger : bool, direction : Direction, sys_pin : P) -> Self {
+        Self {
+            trigger,
+            direction,
+            sys_pin,
+
+            _recovery: Arc::new(SwitchRecovery::default())
+        }
+    }
+
+    /// Returns the recovery state, latched once the switch has been hit and required to move the actuator off the switch
+    /// with [move_off_switch](crate::meas::move_off_switch)
+    pub fn recovery(&self) -> Arc<SwitchRecovery> {
+        self._recovery.clone()
+    }
+}
+
+impl<P : InputPin, U : UnitSet> Interruptor<U> for HardLimitSwitch<P> {
+    // The switch is checked in both directions, so the recovery can observe it being released
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn kind(&self) -> &'static str {
+        "HardLimitSwitch"
+    }
+
+    // An active limit switch at startup requires a recovery before the machine can operate
+    fn probe(&mut self) -> Result<(), &'static str> {
+        match self.sys_pin.is_high() {
+            Ok(high) if high == self.trigger => Err("the hard limit switch is active"),
+            Ok(_) => Ok(()),
+            Err(_) => Err("the input of the switch cannot be read")
+        }
+    }
+
+    fn check(&mut self, ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+        let active = self.sys_pin.is_high().map_or(true, |high| high == self.trigger);
+        self._recovery.set_active(active);
+
+        // A recovery is finished once the switch has been released
+        if self._recovery.recovering() == Some(ctx.direction) {
+            return (!active).then_some(InterruptReason::EndReached);
+        }
+
+        // Only the recovery move may run while the switch is latched
+        if self._recovery.latched().is_some() {
+            return Some(InterruptReason::EndReached);
+        }
+
+        if active && (ctx.direction == self.direction) {
+            let pos : f32 = ctx.pos.into();
+            logging::log_error!("Hard limit switch hit at {}", pos);
+
+            self._recovery.latch(self.direction, pos);
+            return Some(InterruptReason::EndReached);
+        }
+
+        None
+    }
+}
@@ -0,0 +1,215 @@
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use atomic_float::AtomicF32;
+use syunit::*;
+
+use crate::{InterruptContext, InterruptReason, Interruptor};
+
+/// Shared status of a [LoadMonitor], holding the load margin of the last move and its trend over all moves
+///
+/// A load margin of `1.0` means that the axis accelerates and decelerates exactly as predicted by the model, lower values
+/// mean that a part of the torque is consumed by friction. A falling trend over many moves indicates wear like dry rails
+/// or failing bearings, long before the axis starts losing steps.
+#[derive(Debug)]
+pub struct LoadStatus {
+    /// Smoothing factor of the average and the trend, see [LoadStatus::new]
+    pub alpha : Factor,
+
+    _margin : AtomicF32,
+    _average : AtomicF32,
+    _trend : AtomicF32,
+    _moves : AtomicUsize,
+
+    // Accumulators of the current move
+    _acc_cmd : AtomicF32,
+    _acc_meas : AtomicF32,
+    _dec_cmd : AtomicF32,
+    _dec_meas : AtomicF32
+}
+
+impl LoadStatus {
+    /// Creates a new status, the average and the trend are smoothed over roughly `1 / alpha` moves
+    pub fn new(alpha : Factor) -> Self {
+        Self {
+            alpha,
+
+            _margin: AtomicF32::new(1.0),
+            _average: AtomicF32::new(1.0),
+            _trend: AtomicF32::new(0.0),
+            _moves: AtomicUsize::new(0),
+
+            _acc_cmd: AtomicF32::new(0.0),
+            _acc_meas: AtomicF32::new(0.0),
+            _dec_cmd: AtomicF32::new(0.0),
+            _dec_meas: AtomicF32::new(0.0)
+        }
+    }
+
+    /// The load margin of the last move evaluated
+    pub fn margin(&self) -> f32 {
+        self._margin.load(Relaxed)
+    }
+
+    /// The smoothed load margin over all moves evaluated
+    pub fn average(&self) -> f32 {
+        self._average.load(Relaxed)
+    }
+
+    /// The smoothed change of the load margin per move, negative values mean the load is increasing
+    pub fn trend(&self) -> f32 {
+        self._trend.load(Relaxed)
+    }
+
+    /// The amount of moves evaluated
+    pub fn moves(&self) -> usize {
+        self._moves.load(Relaxed)
+    }
+
+    /// Evaluates the current move, returns its load margin or `None` if no samples have been taken
+    ///
+    /// Moves are evaluated automatically once the next move starts, call this function after the last move of a sequence
+    /// to get its margin right away
+    pub fn finish_move(&self) -> Option<f32> {
+        let acc_cmd = self._acc_cmd.swap(0.0, Relaxed);
+        let acc_meas = self._acc_meas.swap(0.0, Relaxed);
+        let dec_cmd = self._dec_cmd.swap(0.0, Relaxed);
+        let dec_meas = self._dec_meas.swap(0.0, Relaxed);
+
+        let ratio_acc = (acc_cmd > 0.0).then(|| acc_meas / acc_cmd);
+        let ratio_dec = (dec_cmd > 0.0).then(|| dec_meas / dec_cmd);
+
+        // Friction reduces the acceleration and increases the deceleration by the same amount
+        let margin = match (ratio_acc, ratio_dec) {
+            (Some(acc), Some(dec)) => 1.0 - (dec - acc) / 2.0,
+            (Some(acc), None) => acc,
+            (None, Some(dec)) => 2.0 - dec,
+            (None, None) => return None
+        };
+
+        let moves = self._moves.fetch_add(1, Relaxed);
+        self._margin.store(margin, Relaxed);
+
+        if moves == 0 {
+            self._average.store(margin, Relaxed);
+        } else {
+            let alpha = self.alpha.as_f32();
+            let average = self.average();
+            let average_new = average + alpha * (margin - average);
+
+            self._trend.store(self.trend() + alpha * ((average_new - average) - self.trend()), Relaxed);
+            self._average.store(average_new, Relaxed);
+        }
+
+        Some(margin)
+    }
+
+    /// Resets the status, e.g. after maintenance of the axis
+    pub fn reset(&self) {
+        self._margin.store(1.0, Relaxed);
+        self._average.store(1.0, Relaxed);
+        self._trend.store(0.0, Relaxed);
+        self._moves.store(0, Relaxed);
+
+        self._acc_cmd.store(0.0, Relaxed);
+        self._acc_meas.store(0.0, Relaxed);
+        self._dec_cmd.store(0.0, Relaxed);
+        self._dec_meas.store(0.0, Relaxed);
+    }
+
+    fn add_sample(&self, delta_cmd : f32, delta_meas : f32) {
+        if delta_cmd > 0.0 {
+            self._acc_cmd.fetch_add(delta_cmd, Relaxed);
+            self._acc_meas.fetch_add(delta_meas, Relaxed);
+        } else {
+            self._dec_cmd.fetch_add(-delta_cmd, Relaxed);
+            self._dec_meas.fetch_add(-delta_meas, Relaxed);
+        }
+    }
+
+    fn decelerated(&self) -> bool {
+        self._dec_cmd.load(Relaxed) > 0.0
+    }
+}
+
+impl Default for LoadStatus {
+    fn default() -> Self {
+        Self::new(Factor::new(0.1))
+    }
+}
+
+/// An interruptor comparing the velocity changes measured by an encoder against the ones commanded by the model of the
+/// actuator, never interrupts any movement
+///
+/// The commanded velocity profile is calculated from the torque the model expects to be available. Friction slows down the
+/// accelerations and speeds up the decelerations of the actual axis, the difference between both is reported as load
+/// margin of each move in the shared [LoadStatus].
+pub struct LoadMonitor<U : UnitSet = Rotary> {
+    encoder : Box<dyn FnMut() -> Option<U::Velocity> + Send>,
+
+    _status : Arc<LoadStatus>,
+    _last : Option<(f32, f32, bool)>
+}
+
+impl<U : UnitSet> LoadMonitor<U> {
+    /// Creates a new monitor with an `encoder` closure delivering the measured velocity, `None` values are skipped
+    pub fn new(encoder : Box<dyn FnMut() -> Option<U::Velocity> + Send>) -> Self {
+        Self {
+            encoder,
+
+            _status: Arc::new(LoadStatus::default()),
+            _last: None
+        }
+    }
+
+    /// Uses the given shared `status`, e.g. to change the smoothing of the trend
+    pub fn with_status(mut self, status : Arc<LoadStatus>) -> Self {
+        self._status = status;
+        self
+    }
+
+    /// The shared status of the monitor, can be used to read the load margins after it has been added to an actuator
+    pub fn status(&self) -> Arc<LoadStatus> {
+        self._status.clone()
+    }
+}
+
+impl<U : UnitSet> Interruptor<U> for LoadMonitor<U> {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn kind(&self) -> &'static str {
+        "LoadMonitor"
+    }
+
+    fn check(&mut self, ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+        // Samples without a measurement are skipped, `None` never interrupts
+        let measured = (self.encoder)()?;
+
+        // Velocities along the movement direction
+        let forward = ctx.direction.as_bool();
+        let speed_cmd : f32 = ctx.velocity.abs().into();
+        let speed_meas : f32 = measured.into();
+        let speed_meas = if forward { speed_meas } else { -speed_meas };
+
+        if let Some((last_cmd, last_meas, last_forward)) = self._last {
+            let delta_cmd = speed_cmd - last_cmd;
+
+            // A reversal or an acceleration after decelerating means a new move has started
+            if (last_forward != forward) || ((delta_cmd > 0.0) && self._status.decelerated()) {
+                self._status.finish_move();
+            } else if delta_cmd != 0.0 {
+                self._status.add_sample(delta_cmd, speed_meas - last_meas);
+            }
+        }
+
+        self._last = Some((speed_cmd, speed_meas, forward));
+        None
+    }
+}
@@ -0,0 +1,93 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::sync::Arc;
+
+use atomic_float::AtomicF32;
+use embedded_hal::digital::InputPin;
+use syunit::*;
+
+use crate::{InterruptContext, Interruptor, InterruptReason};
+
+/// Stores the position at which a registration mark has been detected last, shared between the sensor and the component
+#[derive(Debug, Default)]
+pub struct RegistrationLatch {
+    _pos : AtomicF32,
+    _latched : AtomicBool
+}
+
+impl RegistrationLatch {
+    /// Creates a new empty latch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the given position `pos`, overwriting previous ones that have not been taken yet
+    pub fn latch(&self, pos : PositionRad) {
+        self._pos.store(pos.0, Relaxed);
+        self._latched.store(true, Relaxed);
+    }
+
+    /// Returns the latched position if a mark has been detected since the last call, clearing the latch
+    pub fn take(&self) -> Option<PositionRad> {
+        if self._latched.swap(false, Relaxed) {
+            Some(PositionRad(self._pos.load(Relaxed)))
+        } else {
+            None
+        }
+    }
+}
+
+/// A sensor detecting registration marks (e.g. on a conveyor belt)
+/// 
+/// The sensor never interrupts a movement, it only stores the position of the actuator in its [RegistrationLatch] each time 
+/// a mark starts passing the sensor
+pub struct RegistrationSensor<P : InputPin> {
+    trigger : bool,
+    triggered : bool,
+    latch : Arc<RegistrationLatch>,
+
+    sys_pin : P
+}
+
+impl<P : InputPin> RegistrationSensor<P> {
+    /// Creates a new registration sensor, `trigger` is the pin state that signals a mark passing
+    pub fn new(trigger : bool, sys_pin : P) -> Self {
+        Self {
+            trigger,
+            triggered: false,
+            latch: Arc::new(RegistrationLatch::new()),
+
+            sys_pin
+        }
+    }
+
+    /// Returns the latch of the sensor, storing the positions of the marks detected
+    pub fn latch(&self) -> Arc<RegistrationLatch> {
+        self.latch.clone()
+    }
+}
+
+impl<P : InputPin> Interruptor<Rotary> for RegistrationSensor<P> {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn kind(&self) -> &'static str {
+        "RegistrationSensor"
+    }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        let triggered = self.sys_pin.is_high().map(|v| v == self.trigger).unwrap_or(false);
+
+        // Only latch at the beginning of the mark
+        if triggered & !self.triggered {
+            self.latch.latch(ctx.pos);
+        }
+
+        self.triggered = triggered;
+        None
+    }
+}
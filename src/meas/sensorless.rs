@@ -0,0 +1,153 @@
+use alloc::boxed::Box;
+
+use syunit::*;
+
+use crate::{InterruptContext, Interruptor, InterruptReason};
+
+/// An end switch without a switch, detecting the stall of the motor at a mechanical end through the load values reported by
+/// its driver (e.g. the StallGuard value of TMC drivers or a measured motor current)
+///
+/// The `load` closure delivers the current load value, higher values mean a higher load. A stall is detected once the value
+/// reaches the threshold for the configured amount of consecutive checks. Drivers reporting a falling value with increasing
+/// load (like StallGuard) can be used with [SensorlessEndstop::inverted].
+///
+/// Load values are only reliable above a certain velocity, checks below [SensorlessEndstop::with_velocity_min] are ignored.
+/// Home with a constant velocity above this limit, as the acceleration at the start of a movement raises the load too.
+pub struct SensorlessEndstop {
+    load : Box<dyn FnMut() -> Option<f32> + Send>,
+    threshold : f32,
+    inverted : bool,
+
+    _dir : Option<Direction>,
+    temp_dir : Option<Direction>,
+
+    /// Amount of consecutive checks the stall signature has to be present for in order to interrupt the movement
+    samples : usize,
+    /// Minimum velocity the load values are evaluated at
+    velocity_min : RadPerSecond,
+
+    _stall_samples : usize,
+    _trigger_pos : Option<PositionRad>
+}
+
+impl SensorlessEndstop {
+    /// Creates a new sensorless end switch, interrupting once the `load` value reaches the `threshold`
+    /// 
+    /// The `load` closure is called at every check, `None` values are skipped
+    pub fn new(threshold : f32, dir : Option<Direction>, load : Box<dyn FnMut() -> Option<f32> + Send>) -> Self {
+        Self {
+            load,
+            threshold,
+            inverted: false,
+
+            _dir: dir,
+            temp_dir: None,
+
+            samples: 1,
+            velocity_min: RadPerSecond::ZERO,
+
+            _stall_samples: 0,
+            _trigger_pos: None
+        }
+    }
+
+    /// Detects a stall once the load value falls to the threshold or below, e.g. for the StallGuard value of TMC drivers
+    pub fn inverted(mut self) -> Self {
+        self.inverted = true;
+        self
+    }
+
+    /// Requires the stall signature to be present for `samples` consecutive checks, filtering single load peaks
+    pub fn with_samples(mut self, samples : usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Ignores all load values below the given velocity, as the load reported by most drivers is not reliable at low speeds
+    pub fn with_velocity_min(mut self, velocity_min : RadPerSecond) -> Self {
+        self.velocity_min = velocity_min.abs();
+        self
+    }
+
+    /// The load value a stall is detected at
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Sets the load value a stall is detected at, e.g. after tuning it for the machine
+    pub fn set_threshold(&mut self, threshold : f32) {
+        self.threshold = threshold;
+    }
+
+    /// Returns `true` if the given load `value` matches the stall signature
+    fn stalled(&self, value : f32) -> bool {
+        if self.inverted {
+            value <= self.threshold
+        } else {
+            value >= self.threshold
+        }
+    }
+}
+
+impl core::fmt::Debug for SensorlessEndstop {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SensorlessEndstop")
+            .field("threshold", &self.threshold)
+            .field("inverted", &self.inverted)
+            .field("dir", &self._dir)
+            .field("samples", &self.samples)
+            .field("velocity_min", &self.velocity_min)
+            .finish()
+    }
+}
+
+impl Interruptor<Rotary> for SensorlessEndstop {
+    fn dir(&self) -> Option<Direction> {
+        self._dir.or(self.temp_dir)
+    }
+
+    fn set_temp_dir(&mut self, dir_opt : Option<Direction>) {
+        self.temp_dir = dir_opt;
+    }
+
+    fn kind(&self) -> &'static str {
+        "SensorlessEndstop"
+    }
+
+    fn probe(&mut self) -> Result<(), &'static str> {
+        (self.load)().map(|_| ()).ok_or("the load value of the driver cannot be read")
+    }
+
+    fn trigger_pos(&self) -> Option<PositionRad> {
+        self._trigger_pos
+    }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        if ctx.velocity.abs() < self.velocity_min {
+            self._stall_samples = 0;
+            return None;
+        }
+
+        // Samples without a load value are skipped
+        let value = (self.load)()?;
+
+        if !self.stalled(value) {
+            self._stall_samples = 0;
+            self._trigger_pos = None;
+            return None;
+        }
+
+        // The stall started somewhere within the check interval of the first sample
+        if self._stall_samples == 0 {
+            self._trigger_pos = Some(ctx.pos - ctx.velocity * (ctx.interval * 0.5));
+        }
+
+        self._stall_samples = self._stall_samples.saturating_add(1);
+
+        if self._stall_samples >= self.samples {
+            Some(InterruptReason::EndReached)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,18 @@
+// ###############
+// #    Items    #
+// ###############
+    pub use crate::{ActuatorError, AdvancedActuator, DefinedActuator, Interruptible, InterruptReason};
+    pub use crate::asyn::AsyncActuator;
+    pub use crate::math::kin;
+    pub use crate::math::kin::RampProfile;
+    pub use crate::sync::{AsyncMotion, ChunkedMove, ChunkOutcome, ChunkProgress, HeldJog, HeldJogPhase, JogKey, JogPreset, 
+        LimitBehavior, MotionFuture, MotionMode, MotionWorker, Segment, SegmentEvent, SegmentQueue, SimulationSwitch, StopPoint, SyncActuator, 
+        SyncActuatorAsync, SyncActuatorBlocking, SyncActuatorJog, SyncActuatorState, TimeScale};
+    pub use crate::sync::stepper::{node_stream, StreamConsumer, StreamMetrics, StreamProducer};
+//
+
+/// The traits every actuator is driven with
+pub mod prelude {
+    pub use super::{ActuatorError, AdvancedActuator, AsyncActuator, DefinedActuator, Interruptible, InterruptReason, JogPreset, 
+        LimitBehavior, MotionMode, RampProfile, SyncActuator, SyncActuatorAsync, SyncActuatorBlocking, SyncActuatorJog, SyncActuatorState};
+}
@@ -2,12 +2,14 @@ use core::ops::{Div, Mul};
 
 use alloc::boxed::Box;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use syunit::*;
+use syunit::metric::PositionMM;
 
-use crate::{SyncActuator, SyncActuatorBlocking, ActuatorError, Interruptible, AdvancedActuator, SyncActuatorState};
+use crate::{SyncActuator, SyncActuatorBlocking, ActuatorError, LimitBehavior, Interruptible, AdvancedActuator, SyncActuatorState};
 use crate::data::MicroSteps;
-use crate::sync::stepper::StepperActuator;
+use crate::sync::stepper::{HoldPolicy, StepperActuator};
 
 /// A trait that marks an actuator which acts as a parent for another actuator
 pub trait ActuatorParent {
@@ -19,8 +21,111 @@ pub trait ActuatorParent {
 
     /// Returns a mutable reference to the child
     fn child_mut(&mut self) -> &mut Self::Child; 
+
+    /// Converts the trigger position `child_pos` of the child (see [Interruptible::intr_pos]) into a position of the parent
+    /// 
+    /// Rotary parents with a ratio convert it with [RatioActuatorParent::pos_for_parent], the default returns `None` as the 
+    /// position cannot be converted
+    fn intr_pos_for_parent(&self, _child_pos : PositionRad) -> Option<PositionRad> {
+        None
+    }
 }
 
+// Position layers
+    /// The position of a single layer of a composed actuator, see [PositionLayers]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum LayerPos {
+        /// The position of a rotating layer, e.g. a motor shaft or a gearbox output
+        Rotary(PositionRad),
+        /// The position of a linear layer, e.g. the carriage of an axis
+        Linear(PositionMM)
+    }
+
+    impl LayerPos {
+        /// The plain value of the position, in radians or millimeters
+        pub fn value(&self) -> f32 {
+            match self {
+                Self::Rotary(pos) => pos.0,
+                Self::Linear(pos) => pos.0
+            }
+        }
+
+        /// The rotary position, `None` if the layer is linear
+        pub fn rotary(&self) -> Option<PositionRad> {
+            match self {
+                Self::Rotary(pos) => Some(*pos),
+                Self::Linear(_) => None
+            }
+        }
+
+        /// The linear position, `None` if the layer is rotary
+        pub fn linear(&self) -> Option<PositionMM> {
+            match self {
+                Self::Rotary(_) => None,
+                Self::Linear(pos) => Some(*pos)
+            }
+        }
+    }
+
+    impl From<PositionRad> for LayerPos {
+        fn from(pos : PositionRad) -> Self {
+            Self::Rotary(pos)
+        }
+    }
+
+    impl From<PositionMM> for LayerPos {
+        fn from(pos : PositionMM) -> Self {
+            Self::Linear(pos)
+        }
+    }
+
+    /// Access to the positions of all layers of a composed actuator, e.g. the carriage of a linear axis, the output of the gearbox
+    /// driving it and the shaft of the motor
+    /// 
+    /// The layers are indexed from the outside in, index `0` being the actuator itself and the last index the motor at the end 
+    /// of the chain. Every [RatioActuatorParent] implements the trait automatically, converting the position of its child with 
+    /// its ratio.
+    /// 
+    /// ```rust
+    /// use syact::prelude::*;
+    /// 
+    /// let axis = LinearAxis::new_belt_axis(Gear::new(Stepper::simulated(), 0.5), Millimeters(10.0));
+    /// 
+    /// assert_eq!(axis.layer_count(), 3);
+    /// assert_eq!(axis.layer_index("motor"), Some(2));
+    /// assert_eq!(axis.layer_pos_by_name("gear"), Some(LayerPos::Rotary(PositionRad::ZERO)));
+    /// ```
+    pub trait PositionLayers {
+        /// The amount of layers, including the actuator itself
+        fn layer_count(&self) -> usize;
+
+        /// The name of the layer with the given `index`, `None` if the index is out of range
+        fn layer_name(&self, index : usize) -> Option<&'static str>;
+
+        /// The current position of the layer with the given `index`, `None` if the index is out of range
+        fn layer_pos(&self, index : usize) -> Option<LayerPos>;
+
+        // Automatic implementations
+            /// The index of the outermost layer with the given `name`
+            fn layer_index(&self, name : &str) -> Option<usize> {
+                (0 .. self.layer_count()).find(|index| self.layer_name(*index) == Some(name))
+            }
+
+            /// The current position of the outermost layer with the given `name`
+            fn layer_pos_by_name(&self, name : &str) -> Option<LayerPos> {
+                self.layer_index(name).and_then(|index| self.layer_pos(index))
+            }
+
+            /// The names and current positions of all layers, from the outside in
+            fn layer_positions(&self) -> Vec<(&'static str, LayerPos)> {
+                (0 .. self.layer_count())
+                    .filter_map(|index| Some((self.layer_name(index)?, self.layer_pos(index)?)))
+                    .collect()
+            }
+        //
+    }
+// 
+
 // Relationships
     /// A parent that relates to its child through a constant `ratio`
     pub trait RatioActuatorParent : ActuatorParent 
@@ -58,6 +163,11 @@ pub trait ActuatorParent {
         /// For each radian/mm the child moves, the parent moves this distances *times the `ratio`*
         fn ratio(&self) -> Self::Ratio;
 
+        /// The name of the output of the parent, selecting its layer in [PositionLayers]
+        fn output_name(&self) -> &'static str {
+            "output"
+        }
+
         // Automatic implementations
             /// Convert a parent [UnitSet::Position] into a child one
             #[inline]
@@ -163,6 +273,8 @@ pub trait ActuatorParent {
                         ActuatorError::InvalidVelocity(self.velocity_for_parent(child_vel)),
                     ActuatorError::VelocityTooHigh(given_child_vel, max_child_vel) => 
                         ActuatorError::VelocityTooHigh(self.velocity_for_parent(given_child_vel), self.velocity_for_parent(max_child_vel)),
+                    ActuatorError::VelocityTooLow(given_child_vel, min_child_vel) => 
+                        ActuatorError::VelocityTooLow(self.velocity_for_parent(given_child_vel), self.velocity_for_parent(min_child_vel)),
                     // Convert acceleration
                     ActuatorError::InvalidAcceleration(child_accel) => 
                         ActuatorError::InvalidAcceleration(self.acceleration_for_parent(child_accel)),
@@ -176,13 +288,84 @@ pub trait ActuatorParent {
 
                     ActuatorError::IOError => ActuatorError::IOError,
 
-                    ActuatorError::Overload => ActuatorError::Overload
+                    ActuatorError::Overload => ActuatorError::Overload,
+
+                    ActuatorError::UnknownConfigProfile => ActuatorError::UnknownConfigProfile,
+                    ActuatorError::InvalidConfig(reason) => ActuatorError::InvalidConfig(reason),
+
+                    // Convert distances
+                    ActuatorError::StopDistanceExceedsLimit(child_required, child_available) => 
+                        ActuatorError::StopDistanceExceedsLimit(self.dist_for_parent(child_required), self.dist_for_parent(child_available)),
+                    ActuatorError::DriverLimitExceeded(limit) => ActuatorError::DriverLimitExceeded(limit),
+
+                    ActuatorError::PreMoveCheckFailed(name, reason) => ActuatorError::PreMoveCheckFailed(name, reason),
+                    ActuatorError::AxisInterrupted(index, reason) => ActuatorError::AxisInterrupted(index, reason),
+                    ActuatorError::StationNotReached(index) => ActuatorError::StationNotReached(index),
+                    ActuatorError::SettleTimeout(waited) => ActuatorError::SettleTimeout(waited),
+                    ActuatorError::HeartbeatLost => ActuatorError::HeartbeatLost,
+                    ActuatorError::ClockSkewExceeded(uncertainty) => ActuatorError::ClockSkewExceeded(uncertainty),
+                    ActuatorError::StartTimeMissed(late) => ActuatorError::StartTimeMissed(late)
                 }
             }
         // 
     }
 // 
 
+/// The state handle of a [RatioActuatorParent] for an observed movement, mapping the progress of its child onto the positions
+/// of the parent at the start and the target of the movement
+struct RatioState<S : ?Sized> {
+    child : Arc<S>,
+    child_start : f32,
+    child_dist : f32,
+    pos_start : f32,
+    dist : f32
+}
+
+impl<S : ?Sized> RatioState<S> {
+    fn pos_for_parent<U : UnitSet>(&self, child_pos : U::Position) -> U::Position {
+        if self.child_dist == 0.0 {
+            return U::Position::from(self.pos_start);
+        }
+
+        let progress = (child_pos.into() - self.child_start) / self.child_dist;
+        U::Position::from(self.pos_start + self.dist * progress)
+    }
+}
+
+impl<U : UnitSet, S : SyncActuatorState<U> + ?Sized> SyncActuatorState<U> for RatioState<S> {
+    fn pos(&self) -> U::Position {
+        self.pos_for_parent::<U>(self.child.pos())
+    }
+
+    fn moving(&self) -> bool {
+        self.child.moving()
+    }
+
+    fn motion_mode(&self) -> crate::MotionMode {
+        self.child.motion_mode()
+    }
+
+    fn predicted_pos(&self, now : U::Time) -> U::Position {
+        self.pos_for_parent::<U>(self.child.predicted_pos(now))
+    }
+
+    fn simulated(&self) -> bool {
+        self.child.simulated()
+    }
+
+    fn halt(&self) {
+        self.child.halt()
+    }
+
+    fn interrupt(&self) {
+        self.child.interrupt()
+    }
+
+    fn heartbeat(&self) {
+        self.child.heartbeat()
+    }
+}
+
 // ##################################
 // #    AUTOMATIC IMPLEMENTATION    #
 // ##################################
@@ -232,6 +415,17 @@ pub trait ActuatorParent {
                     self.child_mut().set_velocity_max(velocity_opt)
                         .map_err(|err| self.error_for_parent(err))
                 }
+
+                #[inline]
+                fn velocity_min(&self) -> Option<<T::Input as UnitSet>::Velocity> {
+                    self.child().velocity_min().map(|velocity| self.velocity_for_parent(velocity))
+                }
+
+                fn set_velocity_min(&mut self, velocity_opt : Option<<T::Input as UnitSet>::Velocity>) -> Result<(), ActuatorError<T::Input>> {
+                    let velocity_opt = velocity_opt.map(|velocity| self.velocity_for_child(velocity));
+                    self.child_mut().set_velocity_min(velocity_opt)
+                        .map_err(|err| self.error_for_parent(err))
+                }
             // 
 
             // Acceleration
@@ -245,6 +439,17 @@ pub trait ActuatorParent {
                     self.child_mut().set_acceleration_max(acceleration_opt)
                         .map_err(|err| self.error_for_parent(err))
                 }
+
+                #[inline]
+                fn deceleration_max(&self) -> Option<<T::Input as UnitSet>::Acceleration> {
+                    self.child().deceleration_max().map(|deceleration| self.acceleration_for_parent(deceleration))
+                }
+
+                fn set_deceleration_max(&mut self, deceleration_opt : Option<<T::Input as UnitSet>::Acceleration>) -> Result<(), ActuatorError<T::Input>> {
+                    let deceleration_opt = deceleration_opt.map(|deceleration| self.acceleration_for_child(deceleration));
+                    self.child_mut().set_deceleration_max(deceleration_opt)
+                        .map_err(|err| self.error_for_parent(err))
+                }
             // 
 
             // Jolt
@@ -293,6 +498,15 @@ pub trait ActuatorParent {
                     let max = max.map(|g| self.pos_for_child(g));
                     self.child_mut().overwrite_pos_limits(min, max)
                 }
+
+                fn limit_behavior(&self) -> LimitBehavior {
+                    self.child().limit_behavior()
+                }
+
+                fn set_limit_behavior(&mut self, behavior : LimitBehavior) -> Result<(), ActuatorError<T::Input>> {
+                    self.child_mut().set_limit_behavior(behavior)
+                        .map_err(|err| self.error_for_parent(err))
+                }
             // 
         }
     
@@ -326,9 +540,34 @@ pub trait ActuatorParent {
                 }
             //  
 
-            fn drive_rel_blocking(&mut self, mut rel_dist : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>> {
-                rel_dist = self.dist_for_child(rel_dist);
-                self.child_mut().drive_rel_blocking(rel_dist, speed)
+            fn drive_rel_blocking(&mut self, rel_dist : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>> {
+                // Convert the target position instead of the distance, so position dependent conversions are considered
+                let child_rel_dist = self.pos_for_child(self.pos() + rel_dist) - self.child().pos();
+                self.child_mut().drive_rel_blocking(child_rel_dist, speed)
+            }
+
+            fn drive_rel_blocking_blended(&mut self, rel_dist : U::Distance, speed : Factor, exit : Factor) -> Result<(), ActuatorError<U>> {
+                let child_rel_dist = self.pos_for_child(self.pos() + rel_dist) - self.child().pos();
+                self.child_mut().drive_rel_blocking_blended(child_rel_dist, speed, exit)
+            }
+
+            fn drive_rel_blocking_with<F>(&mut self, rel_dist : U::Distance, speed : Factor, observer : F) -> Result<(), ActuatorError<U>> 
+            where
+                F : FnOnce(Arc<dyn SyncActuatorState<U> + Send + Sync>),
+                U : 'static
+            {
+                let pos_start = self.pos();
+                let child_start = self.child().pos();
+                let child_dist = self.pos_for_child(pos_start + rel_dist) - child_start;
+
+                // The handle of the child reports the positions of the child, they are mapped onto the movement of the parent
+                self.child_mut().drive_rel_blocking_with(child_dist, speed, |child| observer(Arc::new(RatioState {
+                    child,
+                    child_start: child_start.into(),
+                    child_dist: child_dist.into(),
+                    pos_start: pos_start.into(),
+                    dist: rel_dist.into()
+                })))
             }
 
             fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError<U>> {
@@ -429,6 +668,34 @@ pub trait ActuatorParent {
         fn step_dist(&self) -> <T::Input as UnitSet>::Distance {
             self.dist_for_parent(self.child().step_dist())
         }
+
+        // Hold policy
+            fn hold_policy(&self) -> HoldPolicy {
+                self.child().hold_policy()
+            }
+
+            fn set_hold_policy(&mut self, policy : HoldPolicy) {
+                self.child_mut().set_hold_policy(policy)
+            }
+        // 
+
+        // Heartbeat
+            fn heartbeat_timeout(&self) -> Option<Seconds> {
+                self.child().heartbeat_timeout()
+            }
+
+            fn set_heartbeat_timeout(&mut self, timeout : Option<Seconds>) {
+                self.child_mut().set_heartbeat_timeout(timeout)
+            }
+
+            fn heartbeat_lost(&self) -> bool {
+                self.child().heartbeat_lost()
+            }
+
+            fn reset_heartbeat_lost(&mut self) {
+                self.child_mut().reset_heartbeat_lost()
+            }
+        // 
     }
 
     impl<T : ActuatorParent> Interruptible for T 
@@ -439,9 +706,61 @@ pub trait ActuatorParent {
             self.child_mut().add_interruptor(interruptor)
         }
 
+        fn remove_interruptors(&mut self, kind : &str) -> usize {
+            self.child_mut().remove_interruptors(kind)
+        }
+
+        fn remove_interruptors_of(&mut self, kind : &str, source : usize) -> usize {
+            self.child_mut().remove_interruptors_of(kind, source)
+        }
+
         fn intr_reason(&mut self) -> Option<super::InterruptReason> {
             self.child_mut().intr_reason()
         }
+
+        fn intr_pos(&self) -> Option<PositionRad> {
+            self.child().intr_pos().and_then(|pos| self.intr_pos_for_parent(pos))
+        }
+    }
+
+    impl<T : RatioActuatorParent> PositionLayers for T
+    where
+        T::Child : SyncActuator<T::Output> + PositionLayers,
+        <T::Input as UnitSet>::Position : Into<LayerPos>,
+
+        <T::Input as UnitSet>::Time : From<<T::Output as UnitSet>::Time>,
+
+        <T::Input as UnitSet>::Position : Div<T::Ratio, Output = <T::Output as UnitSet>::Position>,
+        <T::Input as UnitSet>::Velocity : Div<T::Ratio, Output = <T::Output as UnitSet>::Velocity>,
+        <T::Input as UnitSet>::Acceleration : Div<T::Ratio, Output = <T::Output as UnitSet>::Acceleration>,
+        <T::Input as UnitSet>::Jolt : Div<T::Ratio, Output = <T::Output as UnitSet>::Jolt>,
+        <T::Input as UnitSet>::Force : Mul<T::Ratio, Output = <T::Output as UnitSet>::Force>,
+        <T::Input as UnitSet>::Inertia : InertiaUnit<T::Ratio, Reduced = <T::Output as UnitSet>::Inertia>,
+
+        <T::Output as UnitSet>::Position : Mul<T::Ratio, Output = <T::Input as UnitSet>::Position>,
+        <T::Output as UnitSet>::Distance : Mul<T::Ratio, Output = <T::Input as UnitSet>::Distance>,
+        <T::Output as UnitSet>::Velocity : Mul<T::Ratio, Output = <T::Input as UnitSet>::Velocity>,
+        <T::Output as UnitSet>::Acceleration : Mul<T::Ratio, Output = <T::Input as UnitSet>::Acceleration>,
+        <T::Output as UnitSet>::Jolt : Mul<T::Ratio, Output = <T::Input as UnitSet>::Jolt>,
+        <T::Output as UnitSet>::Force : Div<T::Ratio, Output = <T::Input as UnitSet>::Force>
+    {
+        fn layer_count(&self) -> usize {
+            1 + self.child().layer_count()
+        }
+
+        fn layer_name(&self, index : usize) -> Option<&'static str> {
+            match index {
+                0 => Some(self.output_name()),
+                _ => self.child().layer_name(index - 1)
+            }
+        }
+
+        fn layer_pos(&self, index : usize) -> Option<LayerPos> {
+            match index {
+                0 => Some(self.pos_for_parent(self.child().pos()).into()),
+                _ => self.child().layer_pos(index - 1)
+            }
+        }
     }
 
     // impl<T : ActuatorParent, U : UnitSet> AsyncActuator<U> for T
@@ -0,0 +1,168 @@
+use alloc::collections::VecDeque;
+
+use syunit::*;
+use syunit::metric::{MMPerSecond, Millimeters, PositionMM};
+
+use crate::{ActuatorError, LinearAxis, SyncActuator};
+use crate::parent::RatioActuatorParent;
+use crate::sync::{SegmentEvent, SyncActuatorGroup};
+use crate::sync::stepper::{InterpolatedStepper, drive_rel_interpolated};
+
+/// A linear movement to a Cartesian waypoint, carrying a user tag
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Waypoint<T, const N : usize> {
+    /// The absolute positions of all axes at the waypoint
+    pub pos : [PositionMM; N],
+    /// The velocity along the path (feed rate), `None` to move as fast as the speed factor of the planner allows
+    pub feed : Option<MMPerSecond>,
+    /// The user tag reported back in the [SegmentEvent], e.g. a G-code line number
+    pub tag : T
+}
+
+/// A linear movement planned by a [LinearPathPlanner], see [LinearPathPlanner::plan_next]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinearMove<const N : usize> {
+    /// The relative distances of the actuators driving the axes
+    pub rel_dists : [Radians; N],
+    /// The length of the movement along the path
+    pub length : Millimeters,
+    /// The speed factor of the axis limiting the movement
+    pub speed : Factor
+}
+
+/// Plans and executes straight-line movements between Cartesian waypoints (like G-code `G1` moves) with a group of linear
+/// axes, the base of every CNC mill, laser cutter or 3D printer
+///
+/// Each movement is converted into the distances of the actuators driving the axes, calibration tables of the axes included.
+/// The step schedules of all actuators are then merged with [drive_rel_interpolated], so all axes accelerate, decelerate and
+/// arrive together and the tool follows the straight line between the waypoints.
+///
+/// Each executed waypoint is reported with a [SegmentEvent], like in a [SegmentQueue](crate::sync::SegmentQueue).
+#[derive(Clone, Debug)]
+pub struct LinearPathPlanner<T, const N : usize> {
+    /// The speed factor used for waypoints without a feed rate, the limit for all other waypoints
+    pub speed : Factor,
+
+    waypoints : VecDeque<Waypoint<T, N>>
+}
+
+impl<T, const N : usize> LinearPathPlanner<T, N> {
+    /// Creates a new planner without any waypoints
+    pub fn new(speed : Factor) -> Self {
+        Self {
+            speed,
+            waypoints: VecDeque::new()
+        }
+    }
+
+    // Path
+        /// Appends a waypoint approached as fast as the speed factor allows
+        pub fn push(&mut self, pos : [PositionMM; N], tag : T) {
+            self.waypoints.push_back(Waypoint { pos, feed: None, tag });
+        }
+
+        /// Appends a waypoint approached with the given `feed` rate along the path
+        pub fn push_feed(&mut self, pos : [PositionMM; N], feed : MMPerSecond, tag : T) {
+            self.waypoints.push_back(Waypoint { pos, feed: Some(feed), tag });
+        }
+
+        /// The amount of waypoints waiting
+        pub fn len(&self) -> usize {
+            self.waypoints.len()
+        }
+
+        /// Returns `true` if no waypoints are waiting
+        pub fn is_empty(&self) -> bool {
+            self.waypoints.is_empty()
+        }
+
+        /// Removes all waypoints waiting, returning them
+        pub fn clear(&mut self) -> VecDeque<Waypoint<T, N>> {
+            core::mem::take(&mut self.waypoints)
+        }
+    //
+
+    /// Plans the movement from the current positions of the `group` to the next waypoint, returns `None` if the path is empty
+    ///
+    /// The speed factor is reduced if the feed rate of the waypoint requires a slower movement. The feed rate is reached
+    /// exactly by builders driving with constant velocities, ramps make the movement slightly slower
+    pub fn plan_next<A, G>(&self, group : &G) -> Option<LinearMove<N>>
+    where
+        A : InterpolatedStepper + SyncActuator,
+        G : SyncActuatorGroup<LinearAxis<A>, N>
+    {
+        let waypoint = self.waypoints.front()?;
+        let axes = group.axes();
+
+        let mut rel_dists = [Radians::ZERO; N];
+        let mut length_sq = 0.0;
+
+        for ((rel_dist, axis), target) in rel_dists.iter_mut().zip(axes).zip(waypoint.pos) {
+            let dist = target - axis.pos();
+
+            *rel_dist = axis.pos_for_child(target) - axis.actuator.axis_pos();
+            length_sq += dist.0 * dist.0;
+        }
+
+        let length = Millimeters(f32::sqrt(length_sq));
+
+        let speed = match waypoint.feed {
+            Some(feed) if feed > MMPerSecond::ZERO => {
+                // The time of the movement with the slowest axis at full speed
+                let time_min = axes.iter().zip(rel_dists)
+                    .map(|(axis, rel_dist)| axis.actuator.ptp_time_for_distance(PositionRad::ZERO, PositionRad::ZERO + rel_dist))
+                    .fold(Seconds::ZERO, Seconds::max);
+
+                let time_feed = Seconds(length.0 / feed.0);
+
+                if time_feed > Seconds::ZERO {
+                    Factor::try_new((time_min / time_feed).min(self.speed.as_f32())).unwrap_or(self.speed)
+                } else {
+                    self.speed
+                }
+            },
+            _ => self.speed
+        };
+
+        Some(LinearMove { rel_dists, length, speed })
+    }
+
+    /// Executes the movement to the next waypoint with the axes of the `group`, returns `None` if the path is empty
+    pub fn run_next<A, G>(&mut self, group : &mut G) -> Option<SegmentEvent<T>>
+    where
+        A : InterpolatedStepper + SyncActuator,
+        G : SyncActuatorGroup<LinearAxis<A>, N>
+    {
+        let planned = self.plan_next(group)?;
+        let waypoint = self.waypoints.pop_front()?;
+
+        let mut actuators = group.axes_mut().map(|axis| &mut axis.actuator as &mut dyn InterpolatedStepper);
+
+        Some(match drive_rel_interpolated(&mut actuators, &planned.rel_dists, planned.speed) {
+            Ok(()) => SegmentEvent::Completed(waypoint.tag),
+            Err(err) => SegmentEvent::Failed(waypoint.tag, err)
+        })
+    }
+
+    /// Executes the whole path with the axes of the `group`, reporting each waypoint with `on_event`
+    ///
+    /// Execution stops after the first waypoint that failed, the remaining waypoints stay in the path
+    pub fn run_all<A, G, F>(&mut self, group : &mut G, mut on_event : F) -> Result<(), ActuatorError>
+    where
+        A : InterpolatedStepper + SyncActuator,
+        G : SyncActuatorGroup<LinearAxis<A>, N>,
+        F : FnMut(SegmentEvent<T>)
+    {
+        while let Some(event) = self.run_next(group) {
+            if let SegmentEvent::Failed(_, err) = &event {
+                let err = err.clone();
+                on_event(event);
+                return Err(err);
+            }
+
+            on_event(event);
+        }
+
+        Ok(())
+    }
+}
@@ -1,21 +1,76 @@
-// Simple all in one import
-pub use crate::{ActuatorError, AdvancedActuator, SyncActuator, SyncActuatorBlocking, SyncActuatorNB, AsyncActuator, DefinedActuator, merge_actuator_traits};
+// Simple all in one import, combining the preludes of all capability modules
+#[cfg(feature = "alloc")]
+pub use crate::devices::prelude::*;
+#[cfg(feature = "alloc")]
+pub use crate::groups::prelude::*;
+#[cfg(feature = "alloc")]
+pub use crate::motion::prelude::*;
+#[cfg(feature = "alloc")]
+pub use crate::supervision::prelude::*;
 
-pub use crate::comps::{Conveyor, Gear, LinearAxis};
+// The minimal motion path, the only items available without the `alloc` feature
+#[cfg(not(feature = "alloc"))]
+pub use crate::{ActuatorError, StepperConfig, StepperConst};
+#[cfg(not(feature = "alloc"))]
+pub use crate::data::{MicroSteps, VelocityMargin};
+#[cfg(not(feature = "alloc"))]
+pub use crate::sync::stepper::{AdvancedStepperBuilder, DriveMode, MinimalStepper, StartStopBuilder, StepperBuilder, StepperController};
 
-pub use crate::data::{ActuatorVars, StepperConfig, StepperConst, MicroSteps};
-pub use crate::data::servo::ServoConst;
+#[cfg(feature = "macros")]
+pub use crate::merge_actuator_traits;
 
-pub use crate::meas::{SimpleMeasParams, EndStop};
+// Access to most units
+pub use syunit::prelude::*;
 
-pub use crate::parent::{ActuatorParent, RatioActuatorParent};
+// ################
+// #    Legacy    #
+// ################
+    // Items the prelude used to export, import them from their capability module instead
+        #[cfg(feature = "alloc")]
+        pub use crate::sync::SyncActuatorNB;
 
-pub use crate::sync::stepper::*;
+        pub use crate::sync::stepper::builder;
 
-// Access to most units
-pub use syunit::prelude::*;
+        /// Shim for [crate::devices::CommandQueue]
+        #[cfg(feature = "alloc")]
+        #[deprecated(since = "0.14.0", note = "import `syact::devices::CommandQueue` instead")]
+        pub type CommandQueue = crate::devices::CommandQueue;
+
+        /// Shim for [crate::motion::StreamConsumer]
+        #[cfg(feature = "alloc")]
+        #[deprecated(since = "0.14.0", note = "import `syact::motion::StreamConsumer` instead")]
+        pub type StreamConsumer = crate::motion::StreamConsumer;
+
+        /// Shim for [crate::motion::StreamMetrics]
+        #[cfg(feature = "alloc")]
+        #[deprecated(since = "0.14.0", note = "import `syact::motion::StreamMetrics` instead")]
+        pub type StreamMetrics = crate::motion::StreamMetrics;
+
+        /// Shim for [crate::motion::StreamProducer]
+        #[cfg(feature = "alloc")]
+        #[deprecated(since = "0.14.0", note = "import `syact::motion::StreamProducer` instead")]
+        pub type StreamProducer = crate::motion::StreamProducer;
+
+        /// Shim for [crate::motion::node_stream]
+        #[cfg(feature = "alloc")]
+        #[deprecated(since = "0.14.0", note = "import `syact::motion::node_stream` instead")]
+        pub fn node_stream(capacity : usize, low_watermark : usize, high_watermark : usize) -> (crate::motion::StreamProducer, crate::motion::StreamConsumer) {
+            crate::motion::node_stream(capacity, low_watermark, high_watermark)
+        }
+
+        /// Shim for [crate::sync::stepper::SETTLE_POLL_INTERVAL]
+        #[deprecated(since = "0.14.0", note = "import `syact::sync::stepper::SETTLE_POLL_INTERVAL` instead")]
+        pub const SETTLE_POLL_INTERVAL : syunit::Seconds = crate::sync::stepper::SETTLE_POLL_INTERVAL;
 
-// Testing
-#[cfg(feature="testing")]
-pub use crate::tests::{ComplexStepper, Stepper};
+        /// Shim for [crate::sync::stepper::STATS_FAULT_HISTORY]
+        #[cfg(feature = "alloc")]
+        #[deprecated(since = "0.14.0", note = "import `syact::sync::stepper::STATS_FAULT_HISTORY` instead")]
+        pub const STATS_FAULT_HISTORY : usize = crate::sync::stepper::STATS_FAULT_HISTORY;
 
+        /// Shim for [crate::sync::stepper::profile_hash]
+        #[deprecated(since = "0.14.0", note = "import `syact::sync::stepper::profile_hash` instead")]
+        pub fn profile_hash(profile : &[syunit::Seconds]) -> u64 {
+            crate::sync::stepper::profile_hash(profile)
+        }
+    //
+//
@@ -0,0 +1,118 @@
+//! Reusable building blocks for typical application code, e.g. conveyors feeding parts through multiple stations
+//!
+//! The recipes are tested on the host with simulated actuators, compose them instead of copying the logic into every
+//! application.
+//!
+//! ```rust
+//! use embedded_hal::delay::DelayNs;
+//! use syact::prelude::*;
+//! use syact::recipes::StagedSchedule;
+//!
+//! struct NoDelay;
+//!
+//! impl DelayNs for NoDelay {
+//!     fn delay_ns(&mut self, _ns : u32) { }
+//! }
+//!
+//! // Move slowly out of the loading station, then fast to the next one
+//! let mut schedule = StagedSchedule::new();
+//! schedule.add_stage(Radians(1.0), Factor::new(0.25), Seconds(0.5));
+//! schedule.add_stage(Radians(4.0), Factor::MAX, Seconds(1.0));
+//!
+//! let mut conveyor = Stepper::simulated();
+//! schedule.run(&mut conveyor, &mut NoDelay, |_| { }).unwrap();
+//!
+//! assert!((conveyor.pos() - PositionRad(5.0)).abs() < Radians(0.05));
+//! ```
+
+use alloc::vec::Vec;
+
+use embedded_hal::delay::DelayNs;
+use syunit::*;
+
+use crate::{ActuatorError, SyncActuatorBlocking};
+
+/// A single stage of a [StagedSchedule]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeedStage<U : UnitSet = Rotary> {
+    /// The relative distance to move
+    pub rel_dist : U::Distance,
+    /// The speed factor the distance is moved with
+    pub speed : Factor,
+    /// The time to wait after the movement, e.g. for a process at the station
+    pub dwell : Seconds
+}
+
+/// Progress of a [StagedSchedule] reported after every stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StageProgress<U : UnitSet = Rotary> {
+    /// The index of the stage that has been finished
+    pub stage : usize,
+    /// The amount of stages of the schedule
+    pub stages : usize,
+    /// The position of the actuator after the stage
+    pub pos : U::Position
+}
+
+impl<U : UnitSet> StageProgress<U> {
+    /// Returns `true` if the last stage of the schedule has been finished
+    pub fn is_last(&self) -> bool {
+        (self.stage + 1) == self.stages
+    }
+}
+
+/// A sequence of movements with individual speeds and dwell times, the speed staging used by conveyors and feeders
+#[derive(Clone, Debug, Default)]
+pub struct StagedSchedule<U : UnitSet = Rotary> {
+    stages : Vec<SpeedStage<U>>
+}
+
+impl<U : UnitSet> StagedSchedule<U> {
+    /// Creates a new schedule without any stages
+    pub fn new() -> Self {
+        Self {
+            stages: Vec::new()
+        }
+    }
+
+    /// Appends a stage moving by `rel_dist` with the `speed` factor, waiting for `dwell` afterwards
+    pub fn add_stage(&mut self, rel_dist : U::Distance, speed : Factor, dwell : Seconds) {
+        self.stages.push(SpeedStage { rel_dist, speed, dwell });
+    }
+
+    /// The stages of the schedule
+    pub fn stages(&self) -> &[SpeedStage<U>] {
+        &self.stages
+    }
+
+    /// The total distance of all stages
+    pub fn total_dist(&self) -> U::Distance {
+        self.stages.iter().fold(U::Distance::ZERO, |sum, stage| sum + stage.rel_dist)
+    }
+
+    /// Runs all stages with the `actuator`, reporting the progress with `on_progress` after every stage
+    ///
+    /// The schedule stops at the first stage that failed, the progress of the stages finished is reported until then
+    pub fn run<A, D, F>(&self, actuator : &mut A, delay : &mut D, mut on_progress : F) -> Result<(), ActuatorError<U>>
+    where
+        A : SyncActuatorBlocking<U> + ?Sized,
+        D : DelayNs,
+        F : FnMut(StageProgress<U>)
+    {
+        for (index, stage) in self.stages.iter().enumerate() {
+            actuator.drive_rel_blocking(stage.rel_dist, stage.speed)?;
+
+            if stage.dwell > Seconds::ZERO {
+                delay.delay_us((stage.dwell.0 * 1_000_000.0) as u32);
+            }
+
+            on_progress(StageProgress {
+                stage: index,
+                stages: self.stages.len(),
+                pos: actuator.pos()
+            });
+        }
+
+        Ok(())
+    }
+}
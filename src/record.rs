@@ -0,0 +1,358 @@
+//! Recording and playback of trajectories, e.g. for teach-in workflows on robot arms
+//!
+//! A [TrajectoryRecorder] wraps an actuator and logs timestamped samples of every movement executed through it, a
+//! [TrajectoryPlayer] replays the recorded profile on the same or another actuator.
+//!
+//! ```rust
+//! use syact::prelude::*;
+//! use syact::record::TrajectoryRecorder;
+//!
+//! // Teach the movement ...
+//! let mut recorder = TrajectoryRecorder::new(Stepper::simulated())
+//!     .with_sample_interval(Seconds(0.01));
+//! recorder.drive_rel_blocking(Radians(2.0), Factor::HALF).unwrap();
+//! recorder.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+//!
+//! // ... and replay it on another actuator
+//! let mut stepper = Stepper::simulated();
+//! recorder.player().play(&mut stepper, Factor::MAX).unwrap();
+//!
+//! assert!((stepper.pos() - PositionRad(1.0)).abs() < Radians(0.05));
+//! ```
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use syunit::*;
+
+use crate::{ActuatorError, InterruptContext, InterruptReason, Interruptible, Interruptor, SyncActuatorBlocking};
+use crate::sync::Latch;
+
+/// A sample of a recorded trajectory
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectorySample<U : UnitSet = Rotary> {
+    /// The time of the sample, counting the time spent moving since the recording has been started
+    pub time : Seconds,
+    /// The position of the actuator
+    pub pos : U::Position,
+    /// The velocity of the actuator
+    pub velocity : U::Velocity
+}
+
+/// A raw sample taken by the [RecordingProbe], given at the innermost actuator
+#[derive(Clone, Copy, Debug)]
+struct RawSample {
+    time : Seconds,
+    pos : f32,
+    velocity : f32,
+    interval : Seconds
+}
+
+#[derive(Debug, Default)]
+struct Recording {
+    active : bool,
+    sample_interval : Seconds,
+    time : Seconds,
+    samples : Vec<RawSample>,
+    /// The last check of the movement, sampled or not
+    check_last : Option<RawSample>
+}
+
+/// The kind of the [RecordingProbe], used to remove it again
+const PROBE_KIND : &str = "TrajectoryRecorder";
+
+/// The interruptor sampling the movements of a [TrajectoryRecorder], it never interrupts a movement
+struct RecordingProbe {
+    recording : Arc<Latch<Recording>>
+}
+
+impl Interruptor for RecordingProbe {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn kind(&self) -> &'static str {
+        PROBE_KIND
+    }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        self.recording.with(|rec| {
+            if !rec.active {
+                return;
+            }
+
+            let sample = RawSample { time: rec.time, pos: ctx.pos.0, velocity: ctx.velocity.0, interval: ctx.interval };
+            let due = rec.samples.last().is_none_or(|last| (rec.time - last.time) >= rec.sample_interval);
+
+            if due {
+                rec.samples.push(sample);
+            }
+
+            rec.check_last = Some(sample);
+            rec.time += ctx.interval;
+        });
+
+        None
+    }
+}
+
+/// Wraps an actuator and records timestamped position and velocity samples of every movement executed through the recorder
+///
+/// The samples are taken by an interruptor added to the actuator, which is checked with every step. As the interruptors of
+/// composed actuators (e.g. a [LinearAxis](crate::LinearAxis)) are checked by the motor, the samples are converted into the
+/// units of the actuator using its positions before and after every movement.
+///
+/// The timestamps only count the time spent moving, pauses between the movements are not recorded. The interruptor is 
+/// removed from the actuator once it is released with [TrajectoryRecorder::into_inner].
+pub struct TrajectoryRecorder<A : SyncActuatorBlocking<U> + Interruptible, U : UnitSet = Rotary> {
+    actuator : A,
+    recording : Arc<Latch<Recording>>,
+    samples : Vec<TrajectorySample<U>>
+}
+
+impl<A : SyncActuatorBlocking<U> + Interruptible, U : UnitSet> TrajectoryRecorder<A, U> {
+    /// Wraps the `actuator`, sampling every step of its movements
+    pub fn new(mut actuator : A) -> Self {
+        let recording = Arc::new(Latch::new(Recording::default()));
+        actuator.add_interruptor(Box::new(RecordingProbe { recording: recording.clone() }));
+
+        Self {
+            actuator,
+            recording,
+            samples: Vec::new()
+        }
+    }
+
+    /// Takes a sample at most every `sample_interval`, the end of every movement is always recorded
+    pub fn with_sample_interval(self, sample_interval : Seconds) -> Self {
+        self.set_sample_interval(sample_interval);
+        self
+    }
+
+    /// The minimum time between two samples
+    pub fn sample_interval(&self) -> Seconds {
+        self.recording.with(|rec| rec.sample_interval)
+    }
+
+    /// Sets the minimum time between two samples, see [TrajectoryRecorder::with_sample_interval]
+    pub fn set_sample_interval(&self, sample_interval : Seconds) {
+        self.recording.with(|rec| rec.sample_interval = sample_interval);
+    }
+
+    // Actuator
+        /// The wrapped actuator
+        pub fn actuator(&self) -> &A {
+            &self.actuator
+        }
+
+        /// The wrapped actuator, movements executed directly are not recorded
+        pub fn actuator_mut(&mut self) -> &mut A {
+            &mut self.actuator
+        }
+
+        /// Releases the wrapped actuator and removes the interruptor sampling its movements
+        pub fn into_inner(mut self) -> A {
+            self.recording.with(|rec| rec.active = false);
+            self.actuator.remove_interruptors(PROBE_KIND);
+            self.actuator
+        }
+    //
+
+    // Movements
+        /// Moves the actuator by the relative distance `rel_dist` and records the movement
+        pub fn drive_rel_blocking(&mut self, rel_dist : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>> {
+            let pos_start = self.actuator.pos();
+
+            self.recording.with(|rec| {
+                rec.active = true;
+                rec.samples.clear();
+                rec.check_last = None;
+            });
+
+            let result = self.actuator.drive_rel_blocking(rel_dist, speed);
+
+            let (raw, check_last, time) = self.recording.with(|rec| {
+                rec.active = false;
+                (core::mem::take(&mut rec.samples), rec.check_last.take(), rec.time)
+            });
+
+            self.push_movement(pos_start, &raw, check_last, time);
+            result
+        }
+
+        /// Moves the actuator to the absolute position `pos` and records the movement
+        pub fn drive_abs_blocking(&mut self, pos : U::Position, speed : Factor) -> Result<(), ActuatorError<U>> {
+            let rel_dist = pos - self.actuator.pos();
+            self.drive_rel_blocking(rel_dist, speed)
+        }
+    //
+
+    /// Converts the raw samples of a movement into the units of the actuator
+    fn push_movement(&mut self, pos_start : U::Position, raw : &[RawSample], check_last : Option<RawSample>, time_end : Seconds) {
+        let pos_end = self.actuator.pos();
+        let dist : f32 = (pos_end - pos_start).into();
+
+        let Some((first, last)) = raw.first().zip(check_last) else {
+            return;
+        };
+
+        // The last check is executed before the last step, the step is added to get the end of the movement
+        let raw_dist = (last.pos + last.velocity * last.interval.0) - first.pos;
+        let scale = if raw_dist.abs() > f32::EPSILON { dist / raw_dist } else { 0.0 };
+
+        for sample in raw {
+            self.samples.push(TrajectorySample {
+                time: sample.time,
+                pos: pos_start + U::Distance::from((sample.pos - first.pos) * scale),
+                velocity: U::Velocity::from(sample.velocity * scale)
+            });
+        }
+
+        self.samples.push(TrajectorySample {
+            time: time_end,
+            pos: pos_end,
+            velocity: U::Velocity::default()
+        });
+    }
+
+    // Samples
+        /// All samples recorded
+        pub fn samples(&self) -> &[TrajectorySample<U>] {
+            &self.samples
+        }
+
+        /// Removes all samples and restarts the time of the recording
+        pub fn clear(&mut self) {
+            self.samples.clear();
+            self.recording.with(|rec| rec.time = Seconds::ZERO);
+        }
+
+        /// Creates a player replaying the samples recorded
+        pub fn player(&self) -> TrajectoryPlayer<U> {
+            TrajectoryPlayer::new(self.samples.clone())
+        }
+    //
+}
+
+impl<A : SyncActuatorBlocking<U> + Interruptible + core::fmt::Debug, U : UnitSet> core::fmt::Debug for TrajectoryRecorder<A, U> {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TrajectoryRecorder")
+            .field("actuator", &self.actuator)
+            .field("samples", &self.samples.len())
+            .finish()
+    }
+}
+
+/// Replays a recorded trajectory on an actuator
+///
+/// The samples are streamed as a queue of blended segments, each one driven with the average velocity the recorded actuator
+/// has moved with between the samples. The actuator only stops where the recorded trajectory reverses and at its end, 
+/// actuators that cannot blend movements (see [SyncActuatorBlocking::drive_rel_blocking_blended]) stop at every sample.
+/// 
+/// The actuator must be able to stop within every segment, the velocity between samples taken close together is therefore
+/// limited by the deceleration of the actuator.
+#[derive(Clone, Debug)]
+pub struct TrajectoryPlayer<U : UnitSet = Rotary> {
+    samples : Vec<TrajectorySample<U>>
+}
+
+impl<U : UnitSet> TrajectoryPlayer<U> {
+    /// Creates a new player for the given `samples`
+    pub fn new(samples : Vec<TrajectorySample<U>>) -> Self {
+        Self { samples }
+    }
+
+    /// The samples replayed
+    pub fn samples(&self) -> &[TrajectorySample<U>] {
+        &self.samples
+    }
+
+    /// The duration of the recorded trajectory
+    pub fn duration(&self) -> Seconds {
+        match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => Seconds::ZERO
+        }
+    }
+
+    /// Replays the trajectory at the recorded positions, the recorded velocities are scaled with `speed` and limited by the
+    /// maximum velocity of the `actuator`
+    ///
+    /// The actuator is driven to the first sample with `speed` first
+    pub fn play<A : SyncActuatorBlocking<U> + ?Sized>(&self, actuator : &mut A, speed : Factor) -> Result<(), ActuatorError<U>> {
+        let Some(first) = self.samples.first() else {
+            return Ok(());
+        };
+
+        actuator.drive_abs_blocking(first.pos, speed)?;
+        self.replay(actuator, U::Distance::default(), speed)
+    }
+
+    /// Replays the trajectory relative to the current position of the `actuator`, the first sample being the current
+    /// position, see [TrajectoryPlayer::play]
+    pub fn play_rel<A : SyncActuatorBlocking<U> + ?Sized>(&self, actuator : &mut A, speed : Factor) -> Result<(), ActuatorError<U>> {
+        let Some(first) = self.samples.first() else {
+            return Ok(());
+        };
+
+        let offset = actuator.pos() - first.pos;
+        self.replay(actuator, offset, speed)
+    }
+
+    fn replay<A : SyncActuatorBlocking<U> + ?Sized>(&self, actuator : &mut A, offset : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>> {
+        let velocity_max = actuator.velocity_max();
+        let deceleration_max = actuator.deceleration_max().or(actuator.acceleration_max());
+
+        // The target, the recorded distance and the speed factor of every segment between two samples
+        let segments : Vec<(U::Position, f32, Factor)> = self.samples.windows(2).filter_map(|pair| {
+            let dist : f32 = (pair[1].pos - pair[0].pos).into();
+            let time = pair[1].time - pair[0].time;
+
+            if dist == 0.0 {
+                return None;
+            }
+
+            // Average velocity of the recorded actuator between the samples, relative to the maximum of the replaying one
+            let factor = match velocity_max {
+                Some(velocity_max) if time > Seconds::ZERO => {
+                    let velocity_max : f32 = velocity_max.into();
+                    Factor::try_new(dist.abs() / time.0 / velocity_max).unwrap_or(Factor::MAX)
+                },
+                _ => Factor::MAX
+            };
+
+            Some((pair[1].pos + offset, dist, factor))
+        }).collect();
+
+        for (index, &(target, dist, factor)) in segments.iter().enumerate() {
+            // Segments are blended into the next one in the same direction, leaving with the slower of both velocities
+            let exit = match segments.get(index + 1) {
+                Some(&(_, dist_next, factor_next)) if dist_next.signum() == dist.signum() => {
+                    let exit = if factor_next < factor { factor_next } else { factor } * speed;
+
+                    // The next segment has to be long enough to stop within it, the margin covers the rounding to steps
+                    match (velocity_max, deceleration_max) {
+                        (Some(velocity_max), Some(deceleration)) => {
+                            let velocity_max : f32 = velocity_max.into();
+                            let deceleration : f32 = deceleration.into();
+                            let velocity_stop = (2.0 * deceleration * dist_next.abs() * 0.8).sqrt();
+
+                            Factor::try_new(velocity_stop / velocity_max).filter(|&limit| limit < exit).unwrap_or(exit)
+                        },
+                        _ => exit
+                    }
+                },
+                _ => Factor::MIN
+            };
+
+            // Driving to the target instead of the recorded distance keeps the rounding errors from adding up
+            let rel_dist = target - actuator.pos();
+            actuator.drive_rel_blocking_blended(rel_dist, factor * speed, exit)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,606 @@
+//! Machine capability reports, describing the axes of a machine for frontends
+//!
+//! The reports are generated from the live actuators, so user interfaces can build jog panels and limit-aware input fields
+//! without duplicating the configuration. All values are given as plain numbers in the unit named by the [AxisReport].
+//!
+//! ```rust
+//! use syact::prelude::*;
+//! use syact::report::MachineReport;
+//!
+//! let mut stepper = Stepper::simulated();
+//! stepper.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(5.0)));
+//!
+//! let mut report = MachineReport::new("turntable");
+//! report.add_axis("table", &stepper);
+//!
+//! assert_eq!(report.axes[0].unit, "rad");
+//! assert_eq!(report.axes[0].limit_max, Some(5.0));
+//! ```
+
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use atomic_float::AtomicF32;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use syunit::*;
+use syunit::metric::MetricMM;
+
+use crate::{InterruptContext, Interruptor, InterruptReason, MotionMode, SyncActuator};
+use crate::io::{Encoder, IoRegistry};
+use crate::parent::ActuatorParent;
+
+// ###############
+// #    Traits   #
+// ###############
+    /// Symbol of the units of a [UnitSet], used in reports
+    pub trait UnitSymbol : UnitSet {
+        /// The symbol of the distance unit, e.g. `"mm"`
+        const SYMBOL : &'static str;
+    }
+
+    impl UnitSymbol for Rotary {
+        const SYMBOL : &'static str = "rad";
+    }
+
+    impl UnitSymbol for MetricMM {
+        const SYMBOL : &'static str = "mm";
+    }
+
+    /// Capabilities of an actuator that cannot be derived from the actuator traits, used in [AxisReport]s
+    pub trait Capabilities {
+        /// The motion modes the actuator supports, by default all of them
+        fn motion_modes(&self) -> Vec<MotionMode> {
+            vec![
+                MotionMode::Inactive, MotionMode::Holding, MotionMode::ConstVelocity,
+                MotionMode::ConstFactor, MotionMode::FixedDistance, MotionMode::Stopping
+            ]
+        }
+
+        /// The kinds of the sensors attached to the actuator, see [Interruptor::kind](crate::Interruptor::kind)
+        fn sensors(&self) -> Vec<&'static str> {
+            Vec::new()
+        }
+    }
+
+    impl<T : ActuatorParent> Capabilities for T
+    where
+        T::Child : Capabilities
+    {
+        fn motion_modes(&self) -> Vec<MotionMode> {
+            self.child().motion_modes()
+        }
+
+        fn sensors(&self) -> Vec<&'static str> {
+            self.child().sensors()
+        }
+    }
+//
+
+/// Description of a single axis of a machine
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AxisReport {
+    /// The name of the axis
+    pub name : String,
+    /// The symbol of the distance unit all values are given in, velocities are given per second
+    pub unit : String,
+
+    /// The current position of the axis
+    pub pos : f32,
+    /// The minimum position of the axis, if limited
+    pub limit_min : Option<f32>,
+    /// The maximum position of the axis, if limited
+    pub limit_max : Option<f32>,
+
+    /// The maximum velocity of the axis, if set
+    pub velocity_max : Option<f32>,
+    /// The maximum acceleration of the axis, if set
+    pub acceleration_max : Option<f32>,
+    /// The maximum deceleration of the axis, if set separately from the acceleration
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deceleration_max : Option<f32>,
+    /// The maximum jolt of the axis, if set
+    pub jolt_max : Option<f32>,
+
+    /// The motion modes the axis supports
+    pub modes : Vec<MotionMode>,
+    /// The sensors attached to the axis
+    pub sensors : Vec<String>
+}
+
+impl AxisReport {
+    /// Creates the report of the given `actuator`
+    pub fn from_actuator<U, A>(name : &str, actuator : &A) -> Self
+    where
+        U : UnitSymbol,
+        A : SyncActuator<U> + Capabilities + ?Sized
+    {
+        Self {
+            name: name.to_string(),
+            unit: U::SYMBOL.to_string(),
+
+            pos: actuator.pos().into(),
+            limit_min: actuator.limit_min().map(Into::into),
+            limit_max: actuator.limit_max().map(Into::into),
+
+            velocity_max: actuator.velocity_max().map(Into::into),
+            acceleration_max: actuator.acceleration_max().map(Into::into),
+            deceleration_max: actuator.deceleration_max().map(Into::into),
+            jolt_max: actuator.jolt_max().map(Into::into),
+
+            modes: actuator.motion_modes(),
+            sensors: actuator.sensors().into_iter().map(ToString::to_string).collect()
+        }
+    }
+}
+
+/// Description of a whole machine, serialize it with any serde format to hand it to a frontend
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MachineReport {
+    /// The name of the machine
+    pub name : String,
+    /// The axes of the machine
+    pub axes : Vec<AxisReport>,
+    /// The names of the digital outputs
+    pub outputs : Vec<String>,
+    /// The names of the digital inputs
+    pub inputs : Vec<String>
+}
+
+impl MachineReport {
+    /// Creates a new report without any axes
+    pub fn new(name : &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Adds the report of the given `actuator`
+    pub fn add_axis<U, A>(&mut self, name : &str, actuator : &A)
+    where
+        U : UnitSymbol,
+        A : SyncActuator<U> + Capabilities + ?Sized
+    {
+        self.axes.push(AxisReport::from_actuator(name, actuator));
+    }
+
+    /// Adds the inputs and outputs registered in the `io` registry
+    pub fn add_io(&mut self, io : &IoRegistry) {
+        self.outputs.extend(io.output_names().map(ToString::to_string));
+        self.inputs.extend(io.input_names().map(ToString::to_string));
+    }
+}
+
+// ##########################
+// #    Motion envelopes    #
+// ##########################
+    /// The extreme values of all movements of an actuator, recorded by an [EnvelopeRecorder] and shared with other threads
+    ///
+    /// All values are given as `f32` in the units of the actuator the recorder has been attached to, velocities and 
+    /// accelerations as absolute values.
+    #[derive(Debug)]
+    pub struct MotionEnvelope {
+        _samples : AtomicUsize,
+        _pos_min : AtomicF32,
+        _pos_max : AtomicF32,
+        _velocity_peak : AtomicF32,
+        _acceleration_peak : AtomicF32,
+        _deceleration_peak : AtomicF32
+    }
+
+    impl Default for MotionEnvelope {
+        fn default() -> Self {
+            Self {
+                _samples: AtomicUsize::new(0),
+                _pos_min: AtomicF32::new(f32::INFINITY),
+                _pos_max: AtomicF32::new(f32::NEG_INFINITY),
+                _velocity_peak: AtomicF32::new(0.0),
+                _acceleration_peak: AtomicF32::new(0.0),
+                _deceleration_peak: AtomicF32::new(0.0)
+            }
+        }
+    }
+
+    impl MotionEnvelope {
+        /// The amount of steps recorded
+        pub fn samples(&self) -> usize {
+            self._samples.load(Relaxed)
+        }
+
+        /// The minimum position reached, `None` if nothing has been recorded yet
+        pub fn pos_min(&self) -> Option<f32> {
+            (self.samples() > 0).then(|| self._pos_min.load(Relaxed))
+        }
+
+        /// The maximum position reached, `None` if nothing has been recorded yet
+        pub fn pos_max(&self) -> Option<f32> {
+            (self.samples() > 0).then(|| self._pos_max.load(Relaxed))
+        }
+
+        /// The peak velocity reached
+        pub fn velocity_peak(&self) -> f32 {
+            self._velocity_peak.load(Relaxed)
+        }
+
+        /// The peak acceleration reached while speeding up
+        pub fn acceleration_peak(&self) -> f32 {
+            self._acceleration_peak.load(Relaxed)
+        }
+
+        /// The peak deceleration reached while slowing down
+        pub fn deceleration_peak(&self) -> f32 {
+            self._deceleration_peak.load(Relaxed)
+        }
+
+        /// Discards all recorded values, e.g. before a new run
+        pub fn reset(&self) {
+            self._samples.store(0, Relaxed);
+            self._pos_min.store(f32::INFINITY, Relaxed);
+            self._pos_max.store(f32::NEG_INFINITY, Relaxed);
+            self._velocity_peak.store(0.0, Relaxed);
+            self._acceleration_peak.store(0.0, Relaxed);
+            self._deceleration_peak.store(0.0, Relaxed);
+        }
+
+        fn record(&self, pos : f32, velocity : f32, acceleration : Option<f32>) {
+            self._pos_min.fetch_min(pos, Relaxed);
+            self._pos_max.fetch_max(pos, Relaxed);
+            self._velocity_peak.fetch_max(velocity.abs(), Relaxed);
+
+            match acceleration {
+                Some(acceleration) if acceleration > 0.0 => { self._acceleration_peak.fetch_max(acceleration, Relaxed); },
+                Some(acceleration) => { self._deceleration_peak.fetch_max(-acceleration, Relaxed); },
+                None => { }
+            }
+
+            self._samples.fetch_add(1, Relaxed);
+        }
+    }
+
+    /// An interruptor recording the [MotionEnvelope] of the actuator it is attached to, it never interrupts a movement
+    ///
+    /// The accelerations are derived from the velocities of consecutive steps of the same movement, the jump from standstill
+    /// to the start velocity of the motor is not considered. Interruptors are not checked during the stop ramps of interrupted
+    /// or halted movements, which are therefore not recorded.
+    #[derive(Debug, Default)]
+    pub struct EnvelopeRecorder {
+        _envelope : Arc<MotionEnvelope>,
+        /// The velocity and the interval of the last check
+        _last : Option<(f32, f32)>
+    }
+
+    impl EnvelopeRecorder {
+        /// Creates a new recorder with an empty envelope
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// The envelope recorded, can be read while the recorder is attached to an actuator
+        pub fn envelope(&self) -> Arc<MotionEnvelope> {
+            self._envelope.clone()
+        }
+    }
+
+    impl<U : UnitSet> Interruptor<U> for EnvelopeRecorder {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn kind(&self) -> &'static str {
+            "EnvelopeRecorder"
+        }
+
+        fn begin_movement(&mut self) {
+            // The velocity jumps from standstill to the start velocity of the motor
+            self._last = None;
+        }
+
+        fn check(&mut self, ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+            let velocity : f32 = ctx.velocity.into();
+            let interval : f32 = ctx.interval.into();
+
+            // The velocities of two steps are valid for their whole intervals, the change happens between their centers
+            let acceleration = self._last.map(|(velocity_last, interval_last)| {
+                (velocity.abs() - velocity_last.abs()) / ((interval + interval_last) / 2.0)
+            }).filter(|acceleration| acceleration.is_finite());
+
+            self._envelope.record(ctx.pos.into(), velocity, acceleration);
+            self._last = Some((velocity, interval));
+
+            None
+        }
+    }
+
+    /// The recorded envelope of an axis compared with the limits declared for it, see [MotionReport]
+    #[derive(Clone, Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct AxisEnvelope {
+        /// The name of the axis
+        pub name : String,
+        /// The symbol of the distance unit all values are given in, velocities are given per second
+        pub unit : String,
+        /// The amount of steps recorded
+        pub samples : usize,
+
+        /// The minimum position reached, `None` if nothing has been recorded
+        pub pos_min : Option<f32>,
+        /// The maximum position reached, `None` if nothing has been recorded
+        pub pos_max : Option<f32>,
+        /// The peak velocity reached
+        pub velocity_peak : f32,
+        /// The peak acceleration reached while speeding up
+        pub acceleration_peak : f32,
+        /// The peak deceleration reached while slowing down
+        pub deceleration_peak : f32,
+
+        /// The declared minimum position of the axis, if limited
+        pub limit_min : Option<f32>,
+        /// The declared maximum position of the axis, if limited
+        pub limit_max : Option<f32>,
+        /// The declared maximum velocity of the axis, if set
+        pub velocity_max : Option<f32>,
+        /// The declared maximum acceleration of the axis, if set
+        pub acceleration_max : Option<f32>,
+        /// The declared maximum deceleration of the axis, falls back to the acceleration if not set separately
+        pub deceleration_max : Option<f32>
+    }
+
+    /// A declared limit exceeded by the movements of an axis, see [AxisEnvelope::violations]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub enum EnvelopeViolation {
+        /// The axis moved below its minimum position
+        LimitMin,
+        /// The axis moved above its maximum position
+        LimitMax,
+        /// The peak velocity exceeded the maximum velocity
+        Velocity,
+        /// The peak acceleration exceeded the maximum acceleration
+        Acceleration,
+        /// The peak deceleration exceeded the maximum deceleration
+        Deceleration
+    }
+
+    impl AxisEnvelope {
+        /// Creates the envelope report of an axis from the recorded `envelope` and the limits declared for the `actuator`
+        ///
+        /// The `actuator` has to be the one the [EnvelopeRecorder] has been attached to, as the envelope is recorded in its 
+        /// units. Recorders added to components like a [LinearAxis](crate::LinearAxis) are attached to their motor.
+        pub fn from_recording<U, A>(name : &str, envelope : &MotionEnvelope, actuator : &A) -> Self
+        where
+            U : UnitSymbol,
+            A : SyncActuator<U> + ?Sized
+        {
+            let acceleration_max = actuator.acceleration_max().map(Into::into);
+
+            Self {
+                name: name.to_string(),
+                unit: U::SYMBOL.to_string(),
+                samples: envelope.samples(),
+
+                pos_min: envelope.pos_min(),
+                pos_max: envelope.pos_max(),
+                velocity_peak: envelope.velocity_peak(),
+                acceleration_peak: envelope.acceleration_peak(),
+                deceleration_peak: envelope.deceleration_peak(),
+
+                limit_min: actuator.limit_min().map(Into::into),
+                limit_max: actuator.limit_max().map(Into::into),
+                velocity_max: actuator.velocity_max().map(Into::into),
+                acceleration_max,
+                deceleration_max: actuator.deceleration_max().map(Into::into).or(acceleration_max)
+            }
+        }
+
+        /// All declared limits exceeded by more than the relative `tolerance`
+        ///
+        /// The accelerations are derived from discrete step times, so a small tolerance (a few percent) avoids reporting the 
+        /// rounding of the step times as violations
+        pub fn violations(&self, tolerance : f32) -> Vec<EnvelopeViolation> {
+            let exceeds = |value : f32, max : Option<f32>| max.is_some_and(|max| value > max.abs() * (1.0 + tolerance));
+            let margin = |limit : f32| limit.abs().max(1.0) * tolerance;
+
+            let mut violations = Vec::new();
+
+            if let (Some(pos_min), Some(limit_min)) = (self.pos_min, self.limit_min) {
+                if pos_min < (limit_min - margin(limit_min)) {
+                    violations.push(EnvelopeViolation::LimitMin);
+                }
+            }
+
+            if let (Some(pos_max), Some(limit_max)) = (self.pos_max, self.limit_max) {
+                if pos_max > (limit_max + margin(limit_max)) {
+                    violations.push(EnvelopeViolation::LimitMax);
+                }
+            }
+
+            if exceeds(self.velocity_peak, self.velocity_max) {
+                violations.push(EnvelopeViolation::Velocity);
+            }
+
+            if exceeds(self.acceleration_peak, self.acceleration_max) {
+                violations.push(EnvelopeViolation::Acceleration);
+            }
+
+            if exceeds(self.deceleration_peak, self.deceleration_max) {
+                violations.push(EnvelopeViolation::Deceleration);
+            }
+
+            violations
+        }
+    }
+
+    /// The recorded envelopes of all axes of a machine, e.g. to verify that a validated program never exceeded the declared 
+    /// operating limits of the machine
+    #[derive(Clone, Debug, Default, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct MotionReport {
+        /// The name of the machine
+        pub name : String,
+        /// The envelopes of the axes
+        pub axes : Vec<AxisEnvelope>
+    }
+
+    impl MotionReport {
+        /// Creates a new report without any axes
+        pub fn new(name : &str) -> Self {
+            Self {
+                name: name.to_string(),
+                ..Self::default()
+            }
+        }
+
+        /// Adds the envelope of an axis, see [AxisEnvelope::from_recording]
+        pub fn add_axis<U, A>(&mut self, name : &str, envelope : &MotionEnvelope, actuator : &A)
+        where
+            U : UnitSymbol,
+            A : SyncActuator<U> + ?Sized
+        {
+            self.axes.push(AxisEnvelope::from_recording(name, envelope, actuator));
+        }
+
+        /// Returns `true` if no axis exceeded its declared limits by more than the relative `tolerance`, see 
+        /// [AxisEnvelope::violations]
+        pub fn within_limits(&self, tolerance : f32) -> bool {
+            self.axes.iter().all(|axis| axis.violations(tolerance).is_empty())
+        }
+    }
+//
+
+// ###################
+// #    Readiness    #
+// ###################
+    /// The part of a machine checked by a [ReadinessCheck]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub enum CheckKind {
+        /// The controller of an actuator responds
+        Controller,
+        /// The configuration of an actuator is valid for the motor and its driver
+        Config,
+        /// An interruptor (endstop, limit switch, ...) can be read
+        Interruptor,
+        /// An encoder can be read
+        Encoder,
+        /// A brake can be reached
+        Brake,
+        /// A tool (spindle, gripper, ...) can be reached
+        Tool,
+        /// A digital input or output can be used
+        Io
+    }
+
+    /// The result of a single check of a [ReadinessReport]
+    #[derive(Clone, Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct ReadinessCheck {
+        /// The index of the axis checked, `None` for checks not related to an axis
+        pub axis : Option<usize>,
+        /// The part of the machine checked
+        pub kind : CheckKind,
+        /// The name of the checked part, e.g. the kind of an interruptor
+        pub name : String,
+        /// The reason the check failed, `None` if it passed
+        pub error : Option<String>
+    }
+
+    impl ReadinessCheck {
+        /// Creates a new check result
+        pub fn new(axis : Option<usize>, kind : CheckKind, name : &str, result : Result<(), &str>) -> Self {
+            Self {
+                axis,
+                kind,
+                name: name.to_string(),
+                error: result.err().map(ToString::to_string)
+            }
+        }
+
+        /// Returns `true` if the check passed
+        pub fn passed(&self) -> bool {
+            self.error.is_none()
+        }
+    }
+
+    /// An actuator that can check itself before the machine starts operating, see [ReadinessReport]
+    pub trait SelfTest {
+        /// Runs the checks of the actuator, returning one result per check
+        /// 
+        /// The checks must not move the actuator, the `axis` index is stored in the results
+        fn self_test(&mut self, axis : Option<usize>) -> Vec<ReadinessCheck>;
+    }
+
+    impl<T : ActuatorParent> SelfTest for T
+    where
+        T::Child : SelfTest
+    {
+        fn self_test(&mut self, axis : Option<usize>) -> Vec<ReadinessCheck> {
+            self.child_mut().self_test(axis)
+        }
+    }
+
+    /// The readiness of a machine, created by a self-test at startup, see 
+    /// [SyncActuatorGroup::self_test](crate::sync::SyncActuatorGroup::self_test)
+    /// 
+    /// Applications should only start operating if the machine is [ReadinessReport::ready], instead of discovering failures 
+    /// in the middle of a program. Checks of parts not belonging to an axis (brakes, tools, encoders, ...) can be added with 
+    /// the `check` functions.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct ReadinessReport {
+        /// The results of all checks
+        pub checks : Vec<ReadinessCheck>
+    }
+
+    impl ReadinessReport {
+        /// Creates a new report without any checks
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        // Checks
+            /// Adds the result of a custom check, e.g. whether a brake or a tool can be reached
+            pub fn check(&mut self, kind : CheckKind, name : &str, result : Result<(), &str>) {
+                self.checks.push(ReadinessCheck::new(None, kind, name, result));
+            }
+
+            /// Adds the checks of an `actuator` with the given `axis` index, see [SelfTest]
+            pub fn check_actuator<A : SelfTest + ?Sized>(&mut self, axis : usize, actuator : &mut A) {
+                self.checks.extend(actuator.self_test(Some(axis)));
+            }
+
+            /// Checks if the `encoder` can be read
+            pub fn check_encoder<E : Encoder + ?Sized>(&mut self, name : &str, encoder : &mut E) {
+                self.check(CheckKind::Encoder, name, encoder.count().map(|_| ()).map_err(|_| "the encoder cannot be read"));
+            }
+
+            /// Checks if all inputs of the `io` registry can be read
+            pub fn check_io(&mut self, io : &mut IoRegistry) {
+                let names : Vec<&'static str> = io.input_names().collect();
+
+                for name in names {
+                    self.check(CheckKind::Io, name, io.read_input(name).map(|_| ()).map_err(|_| "the input cannot be read"));
+                }
+            }
+        //
+
+        /// Returns `true` if all checks passed
+        pub fn ready(&self) -> bool {
+            self.checks.iter().all(ReadinessCheck::passed)
+        }
+
+        /// The checks that failed
+        pub fn failures(&self) -> impl Iterator<Item = &ReadinessCheck> {
+            self.checks.iter().filter(|check| !check.passed())
+        }
+    }
+//
@@ -0,0 +1,790 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use atomic_float::AtomicF32;
+#[cfg(feature = "meas")]
+use embedded_hal::digital::{OutputPin, PinState};
+use syunit::*;
+
+use crate::{logging, ActuatorError, InterruptContext, Interruptible, Interruptor, InterruptReason, SyncActuatorBlocking};
+use crate::sync::SyncActuatorGroup;
+#[cfg(feature = "meas")]
+use crate::meas::{Filter, Measurable};
+
+// ########################
+// #    EmergencyStop     #
+// ########################
+    /// A shared emergency stop, stopping the movements of all actuators registered on it once triggered
+    ///
+    /// Clones of the emergency stop share the same state, so it can be triggered from anywhere, e.g. from the interrupt
+    /// handler of a physical emergency stop button, as [EmergencyStop::trigger] only sets an atomic flag without taking 
+    /// any locks. The stop stays triggered until it is reset manually.
+    /// 
+    /// # Registering actuators
+    /// 
+    /// [EmergencyStop::register] and [EmergencyStop::register_group] add the interruptor of the emergency stop (see 
+    /// [EmergencyStop::interruptor]) to actuators. It is checked with every step, stepper motors switch their builder to 
+    /// [DriveMode::Stop](crate::sync::stepper::DriveMode::Stop) within one step period once the emergency stop is 
+    /// triggered. Movements started while the emergency stop is triggered are stopped after their first step. Actuators 
+    /// are registered until they are unregistered with [EmergencyStop::unregister] or [EmergencyStop::unregister_group].
+    /// 
+    /// ```rust
+    /// use syact::prelude::*;
+    /// use syact::sync::SyncActuatorGroup;
+    /// 
+    /// let estop = EmergencyStop::new();
+    /// let mut stepper = Stepper::simulated();
+    /// let mut group = [ Stepper::simulated(), Stepper::simulated() ];
+    /// 
+    /// estop.register(&mut stepper);
+    /// estop.register_group(&mut group);
+    /// assert_eq!(estop.registered(), 3);
+    /// 
+    /// // e.g. by the button
+    /// estop.trigger();
+    /// 
+    /// stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    /// assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    /// 
+    /// assert!(estop.unregister(&mut stepper));
+    /// assert_eq!(estop.registered(), 2);
+    /// ```
+    #[derive(Clone)]
+    pub struct EmergencyStop {
+        _triggered : Arc<AtomicBool>,
+        _registered : Arc<AtomicUsize>
+    }
+
+    impl EmergencyStop {
+        /// Creates a new emergency stop that has not been triggered
+        pub fn new() -> Self {
+            Self {
+                _triggered: Arc::new(AtomicBool::new(false)),
+                _registered: Arc::new(AtomicUsize::new(0))
+            }
+        }
+
+        /// Triggers the emergency stop, all registered actuators stop their movements
+        /// 
+        /// Never blocks and does not log, so it can be called from interrupt handlers. The actuators log the interruption 
+        /// of their movements themselves.
+        pub fn trigger(&self) {
+            self._triggered.store(true, Relaxed);
+        }
+
+        /// Returns `true` if the emergency stop has been triggered
+        pub fn is_triggered(&self) -> bool {
+            self._triggered.load(Relaxed)
+        }
+
+        /// Resets the emergency stop, allowing movements again
+        pub fn reset(&self) {
+            self._triggered.store(false, Relaxed);
+        }
+
+        /// Creates an interruptor that stops the movement of the actuator it is added to when the emergency stop is triggered
+        pub fn interruptor<U : UnitSet>(&self) -> Box<dyn Interruptor<U> + Send> {
+            Box::new(self.clone())
+        }
+
+        // Registration
+            /// Registers the `actuator` by adding the interruptor of the emergency stop to it, its movements are stopped while
+            /// the emergency stop is triggered
+            pub fn register<A : Interruptible<U> + ?Sized, U : UnitSet>(&self, actuator : &mut A) {
+                actuator.add_interruptor(self.interruptor());
+                self._registered.fetch_add(1, Relaxed);
+            }
+
+            /// Registers all actuators of the `group`, see [EmergencyStop::register]
+            pub fn register_group<G, T, U, const N : usize>(&self, group : &mut G)
+            where
+                G : SyncActuatorGroup<T, N>,
+                T : SyncActuatorBlocking<U> + Interruptible<U>,
+                U : UnitSet
+            {
+                group.for_each_mut(|axis, _| self.register(axis));
+            }
+
+            /// Unregisters the `actuator` by removing the interruptors of this emergency stop from it, returns `true` if the 
+            /// actuator has been registered. Interruptors of other emergency stops stay on the actuator
+            pub fn unregister<A : Interruptible<U> + ?Sized, U : UnitSet>(&self, actuator : &mut A) -> bool {
+                let removed = actuator.remove_interruptors_of(<Self as Interruptor<U>>::kind(self), self.id());
+
+                if removed > 0 {
+                    let _ = self._registered.fetch_update(Relaxed, Relaxed, |count| Some(count.saturating_sub(removed)));
+                }
+
+                removed > 0
+            }
+
+            /// Unregisters all actuators of the `group`, see [EmergencyStop::unregister]
+            pub fn unregister_group<G, T, U, const N : usize>(&self, group : &mut G)
+            where
+                G : SyncActuatorGroup<T, N>,
+                T : SyncActuatorBlocking<U> + Interruptible<U>,
+                U : UnitSet
+            {
+                group.for_each_mut(|axis, _| { self.unregister(axis); });
+            }
+
+            /// The number of actuators registered
+            pub fn registered(&self) -> usize {
+                self._registered.load(Relaxed)
+            }
+
+            /// Identity of the emergency stop shared by all of its clones, see [Interruptor::source]
+            fn id(&self) -> usize {
+                Arc::as_ptr(&self._triggered) as usize
+            }
+        //
+    }
+
+    impl Default for EmergencyStop {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl core::fmt::Debug for EmergencyStop {
+        fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("EmergencyStop")
+                .field("triggered", &self.is_triggered())
+                .field("registered", &self.registered())
+                .finish()
+        }
+    }
+
+    impl<U : UnitSet> Interruptor<U> for EmergencyStop {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        // The emergency stop blocks all directions
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn kind(&self) -> &'static str {
+            "EmergencyStop"
+        }
+
+        fn source(&self) -> Option<usize> {
+            Some(self.id())
+        }
+
+        fn check(&mut self, _ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+            if self.is_triggered() {
+                Some(InterruptReason::Error)
+            } else {
+                None
+            }
+        }
+    }
+//
+
+// ######################
+// #    ThermalGuard    #
+// ######################
+    /// Faults detected by a [ThermalGuard]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ThermalFault {
+        /// The temperature monitor did not deliver a value, e.g. because the sensor is disconnected
+        SensorFault,
+        /// The temperature has been above the maximum for longer than the grace period
+        /// - 0 - `f32`: The last temperature measured
+        OverTemperature(f32),
+        /// The temperature has been below the minimum for longer than the grace period
+        /// - 0 - `f32`: The last temperature measured
+        UnderTemperature(f32)
+    }
+
+    /// Couples a temperature monitor of a heated subsystem to an [EmergencyStop]
+    ///
+    /// The guard has to be polled periodically with the time passed since the last poll. If the temperature leaves the
+    /// allowed range for longer than the grace period, or the monitor reports no value at all, the emergency stop is
+    /// triggered and the fault is latched.
+    pub struct ThermalGuard<F : FnMut() -> Option<f32>> {
+        monitor : F,
+        estop : EmergencyStop,
+
+        /// The minimum allowed temperature
+        pub temp_min : f32,
+        /// The maximum allowed temperature
+        pub temp_max : f32,
+        /// The time the temperature is allowed to be outside of the range before the guard trips
+        pub grace_period : Seconds,
+
+        _out_of_range : Seconds,
+        _fault : Option<ThermalFault>
+    }
+
+    impl<F : FnMut() -> Option<f32>> ThermalGuard<F> {
+        /// Creates a new thermal guard with the temperature `monitor` closure, the allowed temperature range and the grace period
+        pub fn new(monitor : F, estop : EmergencyStop, temp_min : f32, temp_max : f32, grace_period : Seconds) -> Self {
+            Self {
+                monitor,
+                estop,
+
+                temp_min,
+                temp_max,
+                grace_period,
+
+                _out_of_range: Seconds::ZERO,
+                _fault: None
+            }
+        }
+
+        /// The emergency stop triggered by the guard
+        pub fn estop(&self) -> &EmergencyStop {
+            &self.estop
+        }
+
+        /// The latched fault, `None` if the guard has not tripped
+        pub fn fault(&self) -> Option<ThermalFault> {
+            self._fault
+        }
+
+        /// Reads the temperature and checks it, `elapsed` is the time passed since the last poll
+        ///
+        /// Returns the fault if the guard has tripped, in that case the emergency stop has been triggered
+        pub fn poll(&mut self, elapsed : Seconds) -> Option<ThermalFault> {
+            if self._fault.is_some() {
+                return self._fault;
+            }
+
+            let fault = match (self.monitor)() {
+                None => Some(ThermalFault::SensorFault),
+                Some(temp) if (temp > self.temp_max) | (temp < self.temp_min) => {
+                    self._out_of_range += elapsed;
+
+                    if self._out_of_range > self.grace_period {
+                        Some(if temp > self.temp_max {
+                            ThermalFault::OverTemperature(temp)
+                        } else {
+                            ThermalFault::UnderTemperature(temp)
+                        })
+                    } else {
+                        None
+                    }
+                },
+                // NaN values are treated as sensor faults as well
+                Some(temp) if temp.is_nan() => Some(ThermalFault::SensorFault),
+                Some(_) => {
+                    self._out_of_range = Seconds::ZERO;
+                    None
+                }
+            };
+
+            if fault.is_some() {
+                logging::log_error!("Thermal guard tripped");
+                self.estop.trigger();
+                self._fault = fault;
+            }
+
+            fault
+        }
+
+        /// Clears the latched fault, the emergency stop has to be reset separately
+        pub fn reset(&mut self) {
+            self._fault = None;
+            self._out_of_range = Seconds::ZERO;
+        }
+    }
+//
+
+// #############################
+// #    OverspeedSupervisor    #
+// #############################
+    /// Shared status of an [OverspeedSupervisor], stays tripped until it is reset manually
+    #[derive(Debug, Default)]
+    pub struct OverspeedStatus {
+        _tripped : AtomicBool,
+        _measured : AtomicBool,
+        _velocity : AtomicF32
+    }
+
+    impl OverspeedStatus {
+        /// Returns `true` if the supervisor has tripped, all movements are interrupted until [OverspeedStatus::reset] is called
+        pub fn tripped(&self) -> bool {
+            self._tripped.load(Relaxed)
+        }
+
+        /// Returns `true` if the supervisor has been tripped by the measured velocity, `false` if by the commanded one
+        pub fn measured(&self) -> bool {
+            self._measured.load(Relaxed)
+        }
+
+        /// The velocity that tripped the supervisor, given as `f32` in the velocity unit of the actuator
+        pub fn velocity(&self) -> f32 {
+            self._velocity.load(Relaxed)
+        }
+
+        /// Resets the status, allowing movements again
+        pub fn reset(&self) {
+            self._tripped.store(false, Relaxed);
+            self._measured.store(false, Relaxed);
+            self._velocity.store(0.0, Relaxed);
+        }
+
+        fn trip(&self, velocity : f32, measured : bool) {
+            self._velocity.store(velocity, Relaxed);
+            self._measured.store(measured, Relaxed);
+            self._tripped.store(true, Relaxed);
+        }
+    }
+
+    /// An interruptor that trips when the commanded or measured velocity of an actuator exceeds the configured ceilings 
+    /// for more than the given amount of steps in a row
+    ///
+    /// Protects the mechanics against unit mix-ups and configuration errors that would otherwise drive them at full speed. 
+    /// Once tripped, the supervisor interrupts all movements and triggers the attached [EmergencyStop], until its 
+    /// [OverspeedStatus] is reset.
+    pub struct OverspeedSupervisor<U : UnitSet = Rotary> {
+        /// The maximum commanded velocity allowed
+        pub velocity_max : U::Velocity,
+        /// The maximum velocity allowed to be measured by the encoder
+        pub measured_max : U::Velocity,
+        /// The amount of consecutive steps the ceilings may be exceeded before the supervisor trips
+        pub tolerance_steps : usize,
+
+        encoder : Option<Box<dyn FnMut() -> Option<U::Velocity> + Send>>,
+        #[cfg(feature = "meas")]
+        filter : Option<Box<dyn Filter<U::Velocity> + Send>>,
+        estop : Option<EmergencyStop>,
+
+        _status : Arc<OverspeedStatus>,
+        _exceeded : usize
+    }
+
+    impl<U : UnitSet> OverspeedSupervisor<U> {
+        /// Creates a new supervisor with the given velocity ceiling, used for both the commanded and the measured velocity
+        pub fn new(velocity_max : U::Velocity, tolerance_steps : usize) -> Self {
+            Self {
+                velocity_max: velocity_max.abs(),
+                measured_max: velocity_max.abs(),
+                tolerance_steps,
+
+                encoder: None,
+                #[cfg(feature = "meas")]
+                filter: None,
+                estop: None,
+
+                _status: Arc::new(OverspeedStatus::default()),
+                _exceeded: 0
+            }
+        }
+
+        /// Adds an `encoder` closure delivering the measured velocity, `None` values are skipped
+        pub fn with_encoder(mut self, encoder : Box<dyn FnMut() -> Option<U::Velocity> + Send>) -> Self {
+            self.encoder = Some(encoder);
+            self
+        }
+
+        #[cfg(feature = "meas")]
+        /// Smoothes the velocities measured by the encoder with the given `filter`, e.g. a [MovingAverage](crate::meas::MovingAverage) suppressing the 
+        /// quantization noise of low resolution encoders
+        pub fn with_filter(mut self, filter : Box<dyn Filter<U::Velocity> + Send>) -> Self {
+            self.filter = Some(filter);
+            self
+        }
+
+        /// Attaches an emergency stop that is triggered when the supervisor trips
+        pub fn with_estop(mut self, estop : EmergencyStop) -> Self {
+            self.estop = Some(estop);
+            self
+        }
+
+        /// The shared status of the supervisor, can be used to check and reset it after it has been added to an actuator
+        pub fn status(&self) -> Arc<OverspeedStatus> {
+            self._status.clone()
+        }
+    }
+
+    impl<U : UnitSet> Interruptor<U> for OverspeedSupervisor<U> {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        // The supervisor blocks all directions
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn kind(&self) -> &'static str {
+            "OverspeedSupervisor"
+        }
+
+        fn check(&mut self, ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+            if self._status.tripped() {
+                return Some(InterruptReason::Error);
+            }
+
+            let measured = self.encoder.as_mut().and_then(|encoder| encoder());
+            #[cfg(feature = "meas")]
+            let measured = measured.map(|velocity| match self.filter.as_mut() {
+                Some(filter) => filter.update(velocity),
+                None => velocity
+            });
+
+            // The measured velocity is checked first, as it represents the actual movement
+            let violation = match measured {
+                Some(velocity) if velocity.abs() > self.measured_max => Some((velocity, true)),
+                _ if ctx.velocity.abs() > self.velocity_max => Some((ctx.velocity, false)),
+                _ => None
+            };
+
+            if let Some((velocity, measured)) = violation {
+                self._exceeded += 1;
+
+                if self._exceeded > self.tolerance_steps {
+                    self._exceeded = 0;
+                    let velocity : f32 = velocity.into();
+                    logging::log_error!("Overspeed supervisor tripped at a velocity of {} (measured: {})", velocity, measured);
+
+                    self._status.trip(velocity, measured);
+
+                    if let Some(estop) = self.estop.as_ref() {
+                        estop.trigger();
+                    }
+
+                    return Some(InterruptReason::Error);
+                }
+            } else {
+                self._exceeded = 0;
+            }
+
+            None
+        }
+    }
+//
+
+// ###################
+// #    BrakeTest    #
+// ###################
+    #[cfg(feature = "meas")]
+    /// Parameters of a [BrakeTest]
+    #[derive(Clone, Copy, Debug)]
+    pub struct BrakeTestParams<U : UnitSet = Rotary> {
+        /// The distance the actuator is commanded to move against the engaged brake, should be a few steps only
+        pub test_dist : U::Distance,
+        /// The speed factor used for the test movement
+        pub speed : Factor,
+        /// The maximum position change measured by the encoder that is still accepted
+        pub tolerance : U::Distance,
+        /// The pin state of the brake output that engages the brake
+        pub engaged_state : bool
+    }
+
+    #[cfg(feature = "meas")]
+    /// Errors and faults of a [BrakeTest]
+    #[derive(Clone, Debug)]
+    pub enum BrakeTestError<U : UnitSet = Rotary> {
+        /// The brake slipped during the test, the fault is latched until [BrakeTest::reset] is called
+        /// - 0 - `U::Distance`: The distance measured by the encoder
+        Slipped(U::Distance),
+        /// The encoder could not be read
+        EncoderError,
+        /// The brake output could not be written
+        IOError,
+        /// The actuator failed to execute the test movement
+        ActuatorError(ActuatorError<U>)
+    }
+
+    #[cfg(feature = "meas")]
+    impl<U : UnitSet> From<ActuatorError<U>> for BrakeTestError<U> {
+        fn from(value : ActuatorError<U>) -> Self {
+            Self::ActuatorError(value)
+        }
+    }
+
+    #[cfg(feature = "meas")]
+    /// Periodic holding test of an axis brake, intended for vertical axes
+    ///
+    /// With the brake engaged, the actuator is commanded to move a tiny distance and back again. An external encoder then has 
+    /// to confirm that the axis did not move, otherwise the brake slipped and the fault is latched. 
+    pub struct BrakeTest<U : UnitSet = Rotary> {
+        /// The parameters of the test
+        pub params : BrakeTestParams<U>,
+
+        _fault : Option<U::Distance>
+    }
+
+    #[cfg(feature = "meas")]
+    impl<U : UnitSet> BrakeTest<U> {
+        /// Creates a new brake test with the given parameters
+        pub fn new(params : BrakeTestParams<U>) -> Self {
+            Self {
+                params,
+                _fault: None
+            }
+        }
+
+        /// The slip distance of the last failed test, `None` if no fault has been latched
+        pub fn fault(&self) -> Option<U::Distance> {
+            self._fault
+        }
+
+        /// Clears the latched fault
+        pub fn reset(&mut self) {
+            self._fault = None;
+        }
+
+        /// Runs the brake test, the brake stays engaged afterwards
+        /// 
+        /// Returns [BrakeTestError::Slipped] immediately if a fault has been latched already
+        pub fn run<A, B, E>(&mut self, actuator : &mut A, brake : &mut B, encoder : &mut E) -> Result<(), BrakeTestError<U>> 
+        where
+            A : SyncActuatorBlocking<U> + ?Sized,
+            B : OutputPin,
+            E : Measurable<U::Position>
+        {
+            if let Some(slip) = self._fault {
+                return Err(BrakeTestError::Slipped(slip));
+            }
+
+            brake.set_state(PinState::from(self.params.engaged_state))
+                .map_err(|_| BrakeTestError::IOError)?;
+
+            let pos_start = encoder.measure().map_err(|_| BrakeTestError::EncoderError)?;
+
+            // Move against the brake, then back to restore the position of the actuator 
+            actuator.drive_rel_blocking(self.params.test_dist, self.params.speed)?;
+            let pos_test = encoder.measure().map_err(|_| BrakeTestError::EncoderError)?;
+            actuator.drive_rel_blocking(-self.params.test_dist, self.params.speed)?;
+
+            let slip = (pos_test - pos_start).abs();
+
+            if slip > self.params.tolerance {
+                self._fault = Some(slip);
+                return Err(BrakeTestError::Slipped(slip));
+            }
+
+            Ok(())
+        }
+    }
+//
+
+// ##########################
+// #    CommandSanitizer    #
+// ##########################
+    /// The verdict of a [CommandSanitizer] on a single command
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum CommandDiagnostic {
+        /// The command has been forwarded unchanged
+        Forwarded,
+        /// The command exceeded a limit or was infinite, a clamped value has been forwarded
+        Clamped,
+        /// The velocity only differs by less than the dead-band from the last one, nothing has been forwarded
+        Coalesced,
+        /// The command arrived too early after the last one, nothing has been forwarded
+        /// - 0 - [Seconds]: The time left until the next command is accepted
+        RateLimited(Seconds),
+        /// The command is invalid (e.g. NaN), nothing has been forwarded
+        /// - 0 - `&'static str`: The reason for the rejection
+        Rejected(&'static str)
+    }
+
+    impl CommandDiagnostic {
+        /// Returns `true` if the command reached the actuator, either unchanged or clamped
+        pub fn forwarded(&self) -> bool {
+            matches!(self, Self::Forwarded | Self::Clamped)
+        }
+    }
+
+    /// Counters of all verdicts a [CommandSanitizer] has given since its creation or the last reset
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct SanitizerStats {
+        /// Commands forwarded unchanged
+        pub forwarded : usize,
+        /// Commands forwarded with a clamped value
+        pub clamped : usize,
+        /// Velocity updates dropped as redundant
+        pub coalesced : usize,
+        /// Commands dropped because of the rate limit
+        pub rate_limited : usize,
+        /// Invalid commands dropped
+        pub rejected : usize
+    }
+
+    /// An optional layer in front of an actuator for commands coming from untrusted sources like network clients or user 
+    /// interfaces, one sanitizer is used per axis
+    ///
+    /// - NaN values are rejected, infinite and excessive values are clamped to the limits (rejected if there is no limit)
+    /// - Commands arriving faster than the minimum interval are dropped, stop commands (zero velocity) are always forwarded
+    /// - Velocity updates within the dead-band of the last velocity forwarded are coalesced
+    ///
+    /// The sanitizer has to be polled with the time passed, like the [ThermalGuard].
+    pub struct CommandSanitizer<U : UnitSet = Rotary> {
+        /// The minimum time between two commands forwarded to the actuator
+        pub interval_min : Seconds,
+        /// The largest relative distance forwarded, larger distances are clamped
+        pub dist_max : Option<U::Distance>,
+        /// The largest velocity forwarded, larger velocities are clamped
+        pub velocity_max : Option<U::Velocity>,
+        /// Velocity updates differing by less than this value from the last velocity forwarded are coalesced
+        pub velocity_deadband : U::Velocity,
+
+        _since_last : Option<Seconds>,
+        _velocity_last : Option<U::Velocity>,
+        _stats : SanitizerStats
+    }
+
+    impl<U : UnitSet> CommandSanitizer<U> {
+        /// Creates a new sanitizer forwarding at most one command per `interval_min`, without any limits or dead-band
+        pub fn new(interval_min : Seconds) -> Self {
+            Self {
+                interval_min,
+                dist_max: None,
+                velocity_max: None,
+                velocity_deadband: U::Velocity::ZERO,
+
+                _since_last: None,
+                _velocity_last: None,
+                _stats: SanitizerStats::default()
+            }
+        }
+
+        /// Sets the largest relative distance forwarded
+        pub fn with_dist_max(mut self, dist_max : U::Distance) -> Self {
+            self.dist_max = Some(dist_max.abs());
+            self
+        }
+
+        /// Sets the largest velocity forwarded
+        pub fn with_velocity_max(mut self, velocity_max : U::Velocity) -> Self {
+            self.velocity_max = Some(velocity_max.abs());
+            self
+        }
+
+        /// Sets the dead-band for coalescing velocity updates
+        pub fn with_velocity_deadband(mut self, deadband : U::Velocity) -> Self {
+            self.velocity_deadband = deadband.abs();
+            self
+        }
+
+        /// Advances the time of the rate limit, `elapsed` is the time passed since the last poll
+        pub fn poll(&mut self, elapsed : Seconds) {
+            if let Some(since_last) = self._since_last.as_mut() {
+                *since_last += elapsed;
+            }
+        }
+
+        /// The counters of all verdicts given
+        pub fn stats(&self) -> SanitizerStats {
+            self._stats
+        }
+
+        /// Resets the counters, the rate limit and the last velocity
+        pub fn reset(&mut self) {
+            self._since_last = None;
+            self._velocity_last = None;
+            self._stats = SanitizerStats::default();
+        }
+
+        /// Sanitizes the relative distance `rel_dist`, returning the value to forward or the reason why it has been dropped
+        pub fn sanitize_dist(&mut self, rel_dist : U::Distance) -> Result<(U::Distance, CommandDiagnostic), CommandDiagnostic> {
+            let (rel_dist, diagnostic) = self.clamp::<U::Distance>(rel_dist, self.dist_max)?;
+            self.rate_limit(false)?;
+
+            Ok(self.forward(rel_dist, diagnostic))
+        }
+
+        /// Sanitizes the `velocity`, returning the value to forward or the reason why it has been dropped
+        pub fn sanitize_velocity(&mut self, velocity : U::Velocity) -> Result<(U::Velocity, CommandDiagnostic), CommandDiagnostic> {
+            let (velocity, diagnostic) = self.clamp::<U::Velocity>(velocity, self.velocity_max)?;
+
+            if let Some(velocity_last) = self._velocity_last {
+                // A stop is never coalesced with a slow movement
+                let redundant = if velocity == U::Velocity::ZERO {
+                    velocity_last == U::Velocity::ZERO
+                } else {
+                    (velocity - velocity_last).abs() < self.velocity_deadband
+                };
+
+                if redundant {
+                    return Err(self.drop_command(CommandDiagnostic::Coalesced));
+                }
+            }
+
+            self.rate_limit(velocity == U::Velocity::ZERO)?;
+            self._velocity_last = Some(velocity);
+
+            Ok(self.forward(velocity, diagnostic))
+        }
+
+        /// Sanitizes the relative distance `rel_dist` and moves the `actuator` by it if it has not been dropped
+        pub fn drive_rel<A : SyncActuatorBlocking<U> + ?Sized>(&mut self, actuator : &mut A, rel_dist : U::Distance, speed : Factor) 
+            -> Result<CommandDiagnostic, ActuatorError<U>> 
+        {
+            match self.sanitize_dist(rel_dist) {
+                Ok((rel_dist, diagnostic)) => actuator.drive_rel_blocking(rel_dist, speed).map(|_| diagnostic),
+                Err(diagnostic) => Ok(diagnostic)
+            }
+        }
+
+        /// Sanitizes the `velocity` and drives the `actuator` with it if it has not been dropped
+        pub fn drive_speed<A : SyncActuatorBlocking<U> + ?Sized>(&mut self, actuator : &mut A, velocity : U::Velocity) 
+            -> Result<CommandDiagnostic, ActuatorError<U>> 
+        {
+            match self.sanitize_velocity(velocity) {
+                Ok((velocity, diagnostic)) => actuator.drive_speed(velocity).map(|_| diagnostic),
+                Err(diagnostic) => Ok(diagnostic)
+            }
+        }
+
+        /// Rejects NaN values and clamps infinite or excessive values to the `limit`
+        fn clamp<V>(&mut self, value : V, limit : Option<V>) -> Result<(V, CommandDiagnostic), CommandDiagnostic> 
+        where
+            V : Copy + PartialOrd + Into<f32> + From<f32>
+        {
+            let raw : f32 = value.into();
+
+            if raw.is_nan() {
+                return Err(self.drop_command(CommandDiagnostic::Rejected("NaN value")));
+            }
+
+            match limit {
+                Some(limit) if raw.abs() > limit.into() => {
+                    let limit : f32 = limit.into();
+                    Ok((V::from(limit.copysign(raw)), CommandDiagnostic::Clamped))
+                },
+                None if raw.is_infinite() => Err(self.drop_command(CommandDiagnostic::Rejected("Infinite value without a limit"))),
+                _ => Ok((value, CommandDiagnostic::Forwarded))
+            }
+        }
+
+        /// Drops the command if it arrives before the minimum interval has passed, unless it is a `stop`
+        fn rate_limit(&mut self, stop : bool) -> Result<(), CommandDiagnostic> {
+            match self._since_last {
+                Some(since_last) if !stop && (since_last < self.interval_min) => 
+                    Err(self.drop_command(CommandDiagnostic::RateLimited(self.interval_min - since_last))),
+                _ => Ok(())
+            }
+        }
+
+        /// Counts the dropped command and logs the reason
+        fn drop_command(&mut self, diagnostic : CommandDiagnostic) -> CommandDiagnostic {
+            match diagnostic {
+                CommandDiagnostic::Coalesced => self._stats.coalesced += 1,
+                CommandDiagnostic::RateLimited(_) => self._stats.rate_limited += 1,
+                _ => {
+                    logging::log_warn!("Command rejected by the sanitizer");
+                    self._stats.rejected += 1
+                }
+            }
+
+            diagnostic
+        }
+
+        /// Counts the forwarded command and restarts the rate limit
+        fn forward<V>(&mut self, value : V, diagnostic : CommandDiagnostic) -> (V, CommandDiagnostic) {
+            if diagnostic == CommandDiagnostic::Clamped {
+                self._stats.clamped += 1;
+            } else {
+                self._stats.forwarded += 1;
+            }
+
+            self._since_last = Some(Seconds::ZERO);
+            (value, diagnostic)
+        }
+    }
+//
@@ -0,0 +1,651 @@
+use core::f32::consts::TAU;
+use core::sync::atomic::{AtomicBool, AtomicI64, AtomicU8, AtomicU16, AtomicU64};
+use core::sync::atomic::Ordering::Relaxed;
+use core::time::Duration;
+
+use std::sync::{Arc, Mutex};
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::pwm::{ErrorType, SetDutyCycle};
+use syunit::*;
+
+use crate::{ActuatorError, InterruptContext, Interruptor, InterruptReason};
+use crate::data::{MicroSteps, StepperConfig, StepperConst};
+use crate::io::{DigitalOutput, Encoder, IoError};
+use crate::sync::PidDcMotor;
+use crate::sync::clock::LocalClock;
+use crate::sync::stepper::{AdvancedStepperBuilder, StepperController, StepperMotor, StartStopBuilder};
+#[cfg(feature = "servo")]
+use crate::data::servo::ServoConst;
+#[cfg(feature = "servo")]
+use crate::sync::MiniServo;
+
+// ###############
+// #    Clock    #
+// ###############
+    /// A virtual clock shared by all simulated devices, advanced by the simulation instead of waiting in real time
+    ///
+    /// Every delay (see [DelayNs]) and every step of a [SimStepperController] advances the clock instantly, so movements of
+    /// any length are simulated in a fraction of a second. Clones share the same time.
+    #[derive(Clone, Debug, Default)]
+    pub struct VirtualClock {
+        _nanos : Arc<AtomicU64>
+    }
+
+    impl VirtualClock {
+        /// Creates a new clock starting at zero
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// The current virtual time, kept with nanosecond precision no matter how long the simulation runs
+        pub fn now(&self) -> Duration {
+            Duration::from_nanos(self._nanos.load(Relaxed))
+        }
+
+        /// Advances the clock by the given `time`, negative and invalid times are ignored
+        pub fn advance(&self, time : Seconds) {
+            if time.0.is_finite() && (time.0 > 0.0) {
+                self._nanos.fetch_add((time.0 as f64 * 1e9).round() as u64, Relaxed);
+            }
+        }
+    }
+
+    impl LocalClock for VirtualClock {
+        fn now(&mut self) -> Seconds {
+            VirtualClock::now(self).into()
+        }
+    }
+
+    impl DelayNs for VirtualClock {
+        fn delay_ns(&mut self, ns : u32) {
+            self._nanos.fetch_add(ns as u64, Relaxed);
+        }
+    }
+//
+
+// ####################
+// #    Trajectory    #
+// ####################
+    /// Values recorded over the virtual time, e.g. the commanded positions of a simulated actuator
+    ///
+    /// Clones share the same recording
+    #[derive(Debug)]
+    pub struct Trajectory<T> {
+        _points : Arc<Mutex<Vec<(Duration, T)>>>
+    }
+
+    impl<T> Clone for Trajectory<T> {
+        fn clone(&self) -> Self {
+            Self { _points: self._points.clone() }
+        }
+    }
+
+    impl<T> Default for Trajectory<T> {
+        fn default() -> Self {
+            Self { _points: Arc::new(Mutex::new(Vec::new())) }
+        }
+    }
+
+    impl<T : Clone> Trajectory<T> {
+        /// All recorded points with their virtual time
+        pub fn points(&self) -> Vec<(Duration, T)> {
+            self.lock().clone()
+        }
+
+        /// The last recorded point
+        pub fn last(&self) -> Option<(Duration, T)> {
+            self.lock().last().cloned()
+        }
+    }
+
+    impl<T> Trajectory<T> {
+        /// The amount of recorded points
+        pub fn len(&self) -> usize {
+            self.lock().len()
+        }
+
+        /// Returns `true` if no points have been recorded
+        pub fn is_empty(&self) -> bool {
+            self.lock().is_empty()
+        }
+
+        /// Removes all recorded points
+        pub fn clear(&self) {
+            self.lock().clear()
+        }
+
+        fn push(&self, time : Duration, value : T) {
+            self.lock().push((time, value))
+        }
+
+        fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(Duration, T)>> {
+            // A panic while recording does not corrupt the points
+            self._points.lock().unwrap_or_else(|err| err.into_inner())
+        }
+    }
+//
+
+// #################
+// #    Stepper    #
+// #################
+    /// A fault injected into a [SimStepperController], counted in steps since the controller has been created
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum SimFault {
+        /// The steps are executed by the controller, but the motor does not move, e.g. because of a mechanical overload
+        MissedSteps {
+            /// The index of the first step missed
+            after : u64,
+            /// The amount of steps missed
+            count : u64
+        },
+        /// The driver reports a stall at the given step, which is not executed
+        Stall {
+            /// The index of the step stalling
+            after : u64
+        }
+    }
+
+    /// The shared state of a [SimStepperController], used to inspect the simulated motor and to inject faults
+    #[derive(Debug)]
+    pub struct SimStepperState {
+        /// The clock advanced by the steps
+        pub clock : VirtualClock,
+        /// The constants of the simulated motor
+        pub consts : StepperConst,
+
+        _microsteps : AtomicU8,
+        _commanded : AtomicI64,
+        _actual : AtomicI64,
+        _steps : AtomicU64,
+        _lost : AtomicU64,
+
+        trajectory : Trajectory<PositionRad>,
+        faults : Mutex<Vec<SimFault>>
+    }
+
+    impl SimStepperState {
+        /// The microsteps of the simulated driver, set by the motor (see [StepperController::set_microsteps])
+        pub fn microsteps(&self) -> MicroSteps {
+            MicroSteps::from(self._microsteps.load(Relaxed))
+        }
+
+        /// The angle of a single step with the current microsteps
+        pub fn step_angle(&self) -> Radians {
+            self.consts.step_angle(self.microsteps())
+        }
+
+        /// Converts the `counts` of the finest microstep resolution into a position
+        fn pos_for_counts(&self, counts : i64) -> PositionRad {
+            PositionRad::ZERO + self.consts.step_angle(MicroSteps::default()) * (counts as f32 / SIM_COUNTS_PER_STEP as f32)
+        }
+
+        /// The position commanded by the steps generated
+        pub fn commanded_pos(&self) -> PositionRad {
+            self.pos_for_counts(self._commanded.load(Relaxed))
+        }
+
+        /// The actual position of the simulated motor, falling behind the commanded one if steps are missed
+        pub fn pos(&self) -> PositionRad {
+            self.pos_for_counts(self._actual.load(Relaxed))
+        }
+
+        /// The amount of steps generated
+        pub fn steps(&self) -> u64 {
+            self._steps.load(Relaxed)
+        }
+
+        /// The amount of steps lost because of injected faults
+        pub fn lost_steps(&self) -> u64 {
+            self._lost.load(Relaxed)
+        }
+
+        /// The commanded positions recorded after every step
+        pub fn trajectory(&self) -> Trajectory<PositionRad> {
+            self.trajectory.clone()
+        }
+
+        /// Injects a fault, see [SimFault]
+        pub fn inject(&self, fault : SimFault) {
+            self.lock_faults().push(fault);
+        }
+
+        /// Removes all injected faults
+        pub fn clear_faults(&self) {
+            self.lock_faults().clear();
+        }
+
+        fn lock_faults(&self) -> std::sync::MutexGuard<'_, Vec<SimFault>> {
+            self.faults.lock().unwrap_or_else(|err| err.into_inner())
+        }
+
+        /// The fault affecting the step with the given `index`
+        fn fault_at(&self, index : u64) -> Option<SimFault> {
+            self.lock_faults().iter().copied().find(|fault| match *fault {
+                SimFault::MissedSteps { after, count } => (after <= index) && (index < after.saturating_add(count)),
+                SimFault::Stall { after } => after == index
+            })
+        }
+    }
+
+    /// The positions of a [SimStepperState] are counted in the finest microstep resolution, so they stay exact if the 
+    /// microsteps change
+    const SIM_COUNTS_PER_STEP : i64 = 128;
+
+    /// A stepper controller running on a [VirtualClock], executing all steps instantly while advancing the clock by their
+    /// duration
+    ///
+    /// The controller tracks the commanded and the actual position of the motor in its shared [SimStepperState], records the
+    /// commanded trajectory and executes the faults injected, see [SimFault] and [SimEndStop].
+    #[derive(Debug)]
+    pub struct SimStepperController {
+        _dir : Direction,
+        _state : Arc<SimStepperState>
+    }
+
+    impl SimStepperController {
+        /// Creates a new controller advancing the `clock` for a motor with the given `consts`
+        pub fn new(clock : &VirtualClock, consts : StepperConst) -> Self {
+            Self {
+                _dir: Direction::default(),
+                _state: Arc::new(SimStepperState {
+                    clock: clock.clone(),
+                    consts,
+
+                    _microsteps: AtomicU8::new(MicroSteps::default().as_u8()),
+
+                    _commanded: AtomicI64::new(0),
+                    _actual: AtomicI64::new(0),
+                    _steps: AtomicU64::new(0),
+                    _lost: AtomicU64::new(0),
+
+                    trajectory: Trajectory::default(),
+                    faults: Mutex::new(Vec::new())
+                })
+            }
+        }
+
+        /// The shared state of the controller
+        pub fn state(&self) -> Arc<SimStepperState> {
+            self._state.clone()
+        }
+    }
+
+    impl StepperController for SimStepperController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            let state = &self._state;
+            let counts = SIM_COUNTS_PER_STEP / state.microsteps().as_u8() as i64;
+            let delta = if self._dir.as_bool() { counts } else { -counts };
+
+            state.clock.advance(time);
+            let index = state._steps.fetch_add(1, Relaxed);
+
+            match state.fault_at(index) {
+                Some(SimFault::Stall { .. }) => {
+                    state._lost.fetch_add(1, Relaxed);
+                    return Err(ActuatorError::Overload);
+                },
+                Some(SimFault::MissedSteps { .. }) => {
+                    state._lost.fetch_add(1, Relaxed);
+                },
+                None => {
+                    state._actual.fetch_add(delta, Relaxed);
+                }
+            }
+
+            state._commanded.fetch_add(delta, Relaxed);
+            state.trajectory.push(state.clock.now(), state.commanded_pos());
+
+            Ok(())
+        }
+
+        fn direction(&self) -> Direction {
+            self._dir
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self._dir = dir;
+            Ok(())
+        }
+
+        fn set_microsteps(&mut self, microsteps : MicroSteps) -> Result<(), ActuatorError> {
+            self._state._microsteps.store(microsteps.as_u8(), Relaxed);
+            Ok(())
+        }
+
+        fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            self._state.clock.advance(time);
+            Ok(())
+        }
+    }
+
+    /// A simulated end switch triggering once the actual position of a simulated stepper motor reaches its position
+    ///
+    /// The switch can be triggered at any time with the shared flag of [SimEndStop::forced], e.g. to simulate a broken wire
+    /// or an obstacle.
+    #[derive(Debug)]
+    pub struct SimEndStop {
+        state : Arc<SimStepperState>,
+        pos : PositionRad,
+        _dir : Direction,
+
+        _forced : Arc<AtomicBool>,
+        _trigger_pos : Option<PositionRad>
+    }
+
+    impl SimEndStop {
+        /// Creates a new switch at the position `pos`, reached when moving in the direction `dir`
+        pub fn new(state : Arc<SimStepperState>, pos : PositionRad, dir : Direction) -> Self {
+            Self {
+                state,
+                pos,
+                _dir: dir,
+
+                _forced: Arc::new(AtomicBool::new(false)),
+                _trigger_pos: None
+            }
+        }
+
+        /// The shared flag triggering the switch independent of the position while set
+        pub fn forced(&self) -> Arc<AtomicBool> {
+            self._forced.clone()
+        }
+    }
+
+    impl Interruptor for SimEndStop {
+        fn dir(&self) -> Option<Direction> {
+            Some(self._dir)
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn kind(&self) -> &'static str {
+            "SimEndStop"
+        }
+
+        fn trigger_pos(&self) -> Option<PositionRad> {
+            self._trigger_pos
+        }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            let pos = self.state.pos();
+            let reached = if self._dir.as_bool() { pos >= self.pos } else { pos <= self.pos };
+
+            if reached || self._forced.load(Relaxed) {
+                self._trigger_pos = Some(ctx.pos);
+                Some(InterruptReason::EndReached)
+            } else {
+                self._trigger_pos = None;
+                None
+            }
+        }
+    }
+
+    /// A simulated stepper motor using start-stop control, see [stepper]
+    pub type SimStepper = StepperMotor<StartStopBuilder, SimStepperController>;
+
+    /// Creates a stepper motor running on the `clock`, using the data of a [StepperConst::MOT_17HE15_1504S] motor and the
+    /// [StepperConfig::VOLT12_NO_OVERLOAD] configuration, returns the motor and the state of its controller
+    ///
+    /// ```rust
+    /// use core::time::Duration;
+    /// 
+    /// use syact::prelude::*;
+    /// use syact::sim::{self, SimStepper, VirtualClock};
+    ///
+    /// let clock = VirtualClock::new();
+    /// let (mut stepper, state) = sim::stepper::<StartStopBuilder>(&clock);
+    ///
+    /// stepper.drive_rel_blocking(Radians(10.0), Factor::MAX).unwrap();
+    ///
+    /// // The movement took a few virtual seconds, but returned instantly
+    /// assert!(clock.now() > Duration::from_millis(100));
+    /// assert!((state.pos() - PositionRad(10.0)).abs() < Radians(0.05));
+    /// ```
+    pub fn stepper<B : AdvancedStepperBuilder>(clock : &VirtualClock) -> (StepperMotor<B, SimStepperController>, Arc<SimStepperState>) {
+        let consts = StepperConst::MOT_17HE15_1504S;
+        let ctrl = SimStepperController::new(clock, consts.clone());
+        let state = ctrl.state();
+
+        let motor = StepperMotor::new_advanced(ctrl, consts, StepperConfig::VOLT12_NO_OVERLOAD)
+            .expect("The simulation parameters must be valid");
+
+        (motor, state)
+    }
+//
+
+// #############
+// #    PWM    #
+// #############
+    /// A simulated PWM output recording its duty cycle over the virtual time, e.g. for servos or DC motors
+    ///
+    /// Clones share the same output
+    #[derive(Clone, Debug)]
+    pub struct SimPwm {
+        clock : VirtualClock,
+        _duty : Arc<AtomicU16>,
+        trajectory : Trajectory<Factor>
+    }
+
+    impl SimPwm {
+        /// Creates a new output at zero duty
+        pub fn new(clock : &VirtualClock) -> Self {
+            Self {
+                clock: clock.clone(),
+                _duty: Arc::new(AtomicU16::new(0)),
+                trajectory: Trajectory::default()
+            }
+        }
+
+        /// The current duty cycle
+        pub fn duty(&self) -> Factor {
+            Factor::new(self._duty.load(Relaxed) as f32 / u16::MAX as f32)
+        }
+
+        /// The duty cycles recorded every time the output has been set
+        pub fn trajectory(&self) -> Trajectory<Factor> {
+            self.trajectory.clone()
+        }
+    }
+
+    impl ErrorType for SimPwm {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SetDutyCycle for SimPwm {
+        fn max_duty_cycle(&self) -> u16 {
+            u16::MAX
+        }
+
+        fn set_duty_cycle(&mut self, duty : u16) -> Result<(), Self::Error> {
+            self._duty.store(duty, Relaxed);
+            self.trajectory.push(self.clock.now(), self.duty());
+            Ok(())
+        }
+    }
+
+    /// Creates a servo running on the `clock`, returns the servo and its PWM output
+    ///
+    /// Ramped movements (see [MiniServo::with_delay](crate::sync::MiniServo::with_delay)) advance the clock instead of waiting
+    #[cfg(feature = "servo")]
+    pub fn servo(clock : &VirtualClock, consts : ServoConst) -> (MiniServo<SimPwm, VirtualClock>, SimPwm) {
+        let pwm = SimPwm::new(clock);
+        (MiniServo::new(consts, pwm.clone()).with_delay(clock.clone()), pwm)
+    }
+//
+
+// ############
+// #    DC    #
+// ############
+    /// The integration step of the simulated DC motor
+    const DC_TIME_STEP : f32 = 0.0001;
+
+    #[derive(Debug, Default)]
+    struct DcPlantState {
+        duty : f32,
+        dir : bool,
+        pos : f32,
+        velocity : f32,
+        time : Duration
+    }
+
+    /// A simulated DC gearmotor with a quadrature encoder, the velocity approaching the one of the duty cycle with a
+    /// first-order response
+    ///
+    /// The motor is integrated up to the current time of the clock every time it is accessed. Faults can be injected by
+    /// blocking the shaft or disconnecting the encoder.
+    #[derive(Debug)]
+    pub struct SimDcMotor {
+        /// The clock the motor is integrated with
+        pub clock : VirtualClock,
+        /// The velocity of the motor at full duty
+        pub velocity_max : RadPerSecond,
+        /// The time constant of the velocity response
+        pub time_const : Seconds,
+        /// The counts of the encoder per revolution
+        pub counts_per_rev : f32,
+
+        _blocked : AtomicBool,
+        _encoder_fault : AtomicBool,
+
+        state : Mutex<DcPlantState>,
+        trajectory : Trajectory<PositionRad>
+    }
+
+    impl SimDcMotor {
+        /// Creates a new simulated motor at standstill
+        pub fn new(clock : &VirtualClock, velocity_max : RadPerSecond, time_const : Seconds, counts_per_rev : f32) -> Arc<Self> {
+            Arc::new(Self {
+                clock: clock.clone(),
+                velocity_max,
+                time_const,
+                counts_per_rev,
+
+                _blocked: AtomicBool::new(false),
+                _encoder_fault: AtomicBool::new(false),
+
+                state: Mutex::new(DcPlantState { time: clock.now(), ..Default::default() }),
+                trajectory: Trajectory::default()
+            })
+        }
+
+        /// The actual position of the shaft
+        pub fn pos(&self) -> PositionRad {
+            PositionRad(self.update().pos)
+        }
+
+        /// The actual velocity of the shaft
+        pub fn velocity(&self) -> RadPerSecond {
+            RadPerSecond(self.update().velocity)
+        }
+
+        /// The positions recorded at every read of the encoder
+        pub fn trajectory(&self) -> Trajectory<PositionRad> {
+            self.trajectory.clone()
+        }
+
+        /// Blocks the shaft, e.g. to simulate a mechanical jam
+        pub fn set_blocked(&self, blocked : bool) {
+            self._blocked.store(blocked, Relaxed);
+        }
+
+        /// Disconnects the encoder, all reads fail until it is connected again
+        pub fn set_encoder_fault(&self, fault : bool) {
+            self._encoder_fault.store(fault, Relaxed);
+        }
+
+        /// Integrates the motor up to the current time of the clock
+        fn update(&self) -> std::sync::MutexGuard<'_, DcPlantState> {
+            let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+            let now = self.clock.now();
+            let time = now.saturating_sub(state.time).as_secs_f32();
+
+            if time > 0.0 {
+                let steps = (time / DC_TIME_STEP).ceil() as usize;
+                let dt = time / steps as f32;
+
+                for _ in 0 .. steps {
+                    if self._blocked.load(Relaxed) {
+                        state.velocity = 0.0;
+                        continue;
+                    }
+
+                    let target = state.duty * if state.dir { 1.0 } else { -1.0 } * self.velocity_max.0;
+                    state.velocity += (target - state.velocity) * dt / self.time_const.0.max(dt);
+                    state.pos += state.velocity * dt;
+                }
+
+                state.time = now;
+            }
+
+            state
+        }
+    }
+
+    /// The PWM output of a [SimDcMotor]
+    #[derive(Debug)]
+    pub struct SimDcPwm(pub Arc<SimDcMotor>);
+
+    impl ErrorType for SimDcPwm {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SetDutyCycle for SimDcPwm {
+        fn max_duty_cycle(&self) -> u16 {
+            u16::MAX
+        }
+
+        fn set_duty_cycle(&mut self, duty : u16) -> Result<(), Self::Error> {
+            self.0.update().duty = duty as f32 / u16::MAX as f32;
+            Ok(())
+        }
+    }
+
+    /// The direction output of a [SimDcMotor]
+    #[derive(Debug)]
+    pub struct SimDcDir(pub Arc<SimDcMotor>);
+
+    impl DigitalOutput for SimDcDir {
+        fn set(&mut self, state : bool) -> Result<(), IoError> {
+            self.0.update().dir = state;
+            Ok(())
+        }
+    }
+
+    /// The encoder of a [SimDcMotor]
+    #[derive(Debug)]
+    pub struct SimDcEncoder(pub Arc<SimDcMotor>);
+
+    impl Encoder for SimDcEncoder {
+        fn count(&mut self) -> Result<i64, IoError> {
+            if self.0._encoder_fault.load(Relaxed) {
+                return Err(IoError::PinError);
+            }
+
+            let pos = self.0.update().pos;
+            self.0.trajectory.push(self.0.clock.now(), PositionRad(pos));
+
+            Ok((pos / TAU * self.0.counts_per_rev).round() as i64)
+        }
+    }
+
+    /// Creates a position controlled DC motor running on the `clock`, with a motor reaching `velocity_max` at full duty and a
+    /// 2000 counts per revolution encoder, returns the controlled motor and the simulated one
+    pub fn dc_motor(clock : &VirtualClock, velocity_max : RadPerSecond) -> (PidDcMotor<SimDcPwm, SimDcEncoder, VirtualClock>, Arc<SimDcMotor>) {
+        const COUNTS_PER_REV : f32 = 2000.0;
+
+        let plant = SimDcMotor::new(clock, velocity_max, Seconds(0.02), COUNTS_PER_REV);
+
+        (
+            PidDcMotor::new(
+                SimDcPwm(plant.clone()),
+                Box::new(SimDcDir(plant.clone())),
+                SimDcEncoder(plant.clone()),
+                clock.clone(),
+                COUNTS_PER_REV,
+                velocity_max
+            ),
+            plant
+        )
+    }
+//
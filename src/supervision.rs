@@ -0,0 +1,21 @@
+// ###############
+// #    Items    #
+// ###############
+    pub use crate::{InterruptContext, Interruptor};
+    #[cfg(feature = "meas")]
+    pub use crate::meas::{EndStop, HardLimitSwitch, LoadMonitor, LoadStatus, MeasRetryPolicy, SimpleMeasParams, 
+        Recoverable, SwitchDiagnostics, SwitchRecovery};
+    pub use crate::report::{AxisEnvelope, EnvelopeRecorder, EnvelopeViolation, MotionEnvelope, MotionReport};
+    pub use crate::safety::{CommandDiagnostic, CommandSanitizer, EmergencyStop, OverspeedStatus, OverspeedSupervisor, SanitizerStats, 
+        ThermalFault, ThermalGuard};
+    #[cfg(feature = "meas")]
+    pub use crate::safety::{BrakeTest, BrakeTestError, BrakeTestParams};
+    pub use crate::sync::{PreMoveCheck, PreMoveChecks};
+//
+
+/// Interruptors, checks and guards watching over the movements
+pub mod prelude {
+    pub use super::{CommandSanitizer, EmergencyStop, InterruptContext, Interruptor, OverspeedSupervisor, PreMoveCheck, ThermalGuard};
+    #[cfg(feature = "meas")]
+    pub use super::{EndStop, MeasRetryPolicy, SimpleMeasParams};
+}
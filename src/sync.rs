@@ -1,26 +1,147 @@
 // Private imports
+#[cfg(feature = "alloc")]
+use core::future::Future;
+
+#[cfg(feature = "alloc")]
 use alloc::sync::Arc;
 
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "alloc")]
 use syunit::*;
 
+#[cfg(feature = "alloc")]
 use crate::ActuatorError;
 
 // ####################
 // #    SUBMODULES    #
 // ####################
+    #[cfg(feature = "alloc")]
+    mod checks;
+    #[cfg(feature = "alloc")]
+    pub use checks::{PreMoveCheck, PreMoveChecks};
+
+    #[cfg(feature = "alloc")]
+    mod chunk;
+    #[cfg(feature = "alloc")]
+    pub use chunk::{ChunkedMove, ChunkOutcome, ChunkProgress};
+
+    #[cfg(feature = "alloc")]
+    mod scale;
+    #[cfg(feature = "alloc")]
+    pub use scale::TimeScale;
+
+    #[cfg(feature = "alloc")]
+    mod simulate;
+    #[cfg(feature = "alloc")]
+    pub use simulate::SimulationSwitch;
+
+    /// Synchronization of the clocks of multiple controllers
+    #[cfg(feature = "alloc")]
+    pub mod clock;
+    #[cfg(feature = "alloc")]
+    pub use clock::{ClockSync, SharedClock};
+
+    #[cfg(feature = "alloc")]
+    mod dc;
+    #[cfg(feature = "alloc")]
+    pub use dc::{DcState, PidDcMotor, PidGains};
+
+    #[cfg(feature = "alloc")]
+    mod group;
+    #[cfg(feature = "alloc")]
+    pub use group::SyncActuatorGroup;
+
+    #[cfg(feature = "alloc")]
+    mod jog;
+    #[cfg(feature = "alloc")]
+    pub use jog::{HeldJog, HeldJogPhase, JogKey, JogPreset, SyncActuatorJog, JOG_KEY_POLL_INTERVAL};
+
+    #[cfg(feature = "alloc")]
+    mod motion;
+    #[cfg(feature = "alloc")]
+    pub use motion::{AsyncMotion, MotionFuture, MotionWorker};
+    #[cfg(feature = "alloc")]
+    pub(crate) use motion::Latch;
+
+    /// Coordination of 2D paths with tool lifts, e.g. for pen plotters
+    #[cfg(feature = "alloc")]
+    pub mod plotter;
+    #[cfg(feature = "alloc")]
+    pub use plotter::ZHopPlotter;
+
+    #[cfg(feature = "alloc")]
+    mod segment;
+    #[cfg(feature = "alloc")]
+    pub use segment::{Segment, SegmentEvent, SegmentQueue, StopPoint};
+
     /// Everything concerning servo-motors
+    #[cfg(feature = "servo")]
     pub mod servo;
-    pub use servo::MiniServo;
+    #[cfg(feature = "servo")]
+    pub use servo::{MiniServo, NoDelay};
 
     /// Stepper motors and their unique methods and traits
     pub mod stepper;
+    #[cfg(feature = "alloc")]
     pub use stepper::{StepperActuator, StepperMotor};
 //
 
 // ######################
 // #    SyncActuator    #
 // ######################
+    /// The mode an actuator is currently in, reported by its [SyncActuatorState]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    #[repr(u8)]
+    pub enum MotionMode {
+        /// The actuator stands still and does not hold its position
+        #[default]
+        Inactive,
+        /// The actuator stands still and holds its position
+        Holding,
+        /// The actuator drives with a constant velocity
+        ConstVelocity,
+        /// The actuator drives with a constant fraction of its maximum velocity
+        ConstFactor,
+        /// The actuator drives a fixed distance
+        FixedDistance,
+        /// The actuator is stopping
+        Stopping
+    }
+
+    impl MotionMode {
+        /// Converts the `u8` representation of the mode back, unknown values result in [MotionMode::Inactive]
+        pub fn from_u8(value : u8) -> Self {
+            match value {
+                1 => Self::Holding,
+                2 => Self::ConstVelocity,
+                3 => Self::ConstFactor,
+                4 => Self::FixedDistance,
+                5 => Self::Stopping,
+                _ => Self::Inactive
+            }
+        }
+    }
+
+    /// How an actuator treats movements that would exceed its position limits, see [SyncActuator::set_limit_behavior]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub enum LimitBehavior {
+        /// The movement is started unchanged and stopped with the stop ramp once a limit is crossed, the actuator comes to a 
+        /// standstill behind the limit
+        #[default]
+        Stop,
+        /// Movements are truncated at the limits and decelerate smoothly to stop at the boundary, continuous movements start 
+        /// their stop ramp early enough to stand still at the limit
+        DecelerateAndClamp
+    }
+
     /// The state of a `SyncActuator` is used to control the component while it is moving and to get data about the current movement
+    /// 
+    /// To observe a blocking movement from another thread, e.g. a user interface, see 
+    /// [SyncActuatorBlocking::drive_rel_blocking_with]
+    #[cfg(feature = "alloc")]
     pub trait SyncActuatorState<U : UnitSet = Rotary> {
         /// Returns the current absolute position of the actuator
         fn pos(&self) -> U::Position; 
@@ -28,12 +149,43 @@ use crate::ActuatorError;
         /// Returns whether the actuator is currently moving or not
         fn moving(&self) -> bool;
 
+        /// Returns the current [MotionMode] of the actuator
+        /// 
+        /// Actuators without mode tracking report [MotionMode::FixedDistance] while moving and [MotionMode::Inactive] otherwise
+        fn motion_mode(&self) -> MotionMode {
+            if self.moving() {
+                MotionMode::FixedDistance
+            } else {
+                MotionMode::Inactive
+            }
+        }
+
+        /// Predicts the position of the actuator at the time `now`, interpolating between the position updates
+        /// 
+        /// **Display only!** The prediction smoothes positions shown in user interfaces at low update rates, it must not be used
+        /// for any control purposes. Actuators without support for predictions return their current position.
+        fn predicted_pos(&self, now : U::Time) -> U::Position {
+            let _ = now;
+            self.pos()
+        }
+
+        /// Returns `true` if the movements of the actuator are currently simulated, no output is generated, see 
+        /// [SimulationSwitch]
+        fn simulated(&self) -> bool {
+            false
+        }
+
         // Actions
             /// Halt the actuator
             fn halt(&self);
 
             /// Interrupt the movement of the actuator
             fn interrupt(&self);
+
+            /// Signals that the commanding host is still alive, see [StepperActuator::set_heartbeat_timeout]
+            /// 
+            /// Actuators without a heartbeat supervision ignore the call
+            fn heartbeat(&self) { }
         // 
     }   
 
@@ -43,6 +195,7 @@ use crate::ActuatorError;
     /// 
     /// Components can have multiple layers, for example take a stepper motor with a geaerbox attached to it. The stepper motor and both combined will be a component, the later having 
     /// the stepper motor component defined as it's parent component. (See [Gear])
+    #[cfg(feature = "alloc")]
     pub trait SyncActuator<U : UnitSet = Rotary> {
         // Position & U::Velocity
             /// Returns the **absolute** position of the component in the components [Position unit](UnitSet::Position).
@@ -55,8 +208,8 @@ use crate::ActuatorError;
             /// 
             /// // Create a new linear_axis (implements SyncActuator)
             /// let mut linear_axis = LinearAxis::new_belt_axis(
-            ///     // Stepper Motor as subcomponent (also implements SyncActuator), `default()` function only possible in testing!
-            ///     Stepper::default(), 
+            ///     // Simulated stepper motor as subcomponent (also implements SyncActuator)
+            ///     Stepper::simulated(), 
             ///     Millimeters(0.5)    // The radius is set to 0.5, which means for each radian the motor moves, the linear_axis moves for 0.5 mm
             /// );    
             /// 
@@ -81,8 +234,8 @@ use crate::ActuatorError;
             /// 
             /// // Create a new linear_axis (implements SyncActuator)
             /// let mut linear_axis = LinearAxis::new_belt_axis(
-            ///     // Stepper Motor as subcomponent (also implements SyncActuator), `default()` function only possible in testing!
-            ///     Stepper::default(), 
+            ///     // Simulated stepper motor as subcomponent (also implements SyncActuator)
+            ///     Stepper::simulated(), 
             ///     Millimeters(0.5)    // The radius is set to 0.5, which means for each radian the motor moves, the linear_axis moves for 0.5 mm
             /// );    
             /// 
@@ -99,8 +252,8 @@ use crate::ActuatorError;
             /// ```rust
             /// use syact::prelude::*;
             /// 
-            /// // Create a new stepper motor, `default()` only available in testing! 
-            /// let mut stepper = Stepper::default();   
+            /// // Create a new simulated stepper motor
+            /// let mut stepper = Stepper::simulated();   
             /// 
             /// assert_eq!(stepper.velocity_max(), None);
             /// ```
@@ -112,6 +265,34 @@ use crate::ActuatorError;
             /// 
             /// Set to `None` if no limit is wished
             fn set_velocity_max(&mut self, velocity_opt : Option<U::Velocity>) -> Result<(), ActuatorError<U>>;
+
+            /// Minimum velocity the actuator moves with if specified by the user, otherwise will return `None`
+            /// 
+            /// Actuators without support for a minimum velocity always return `None`
+            fn velocity_min(&self) -> Option<U::Velocity> {
+                None
+            }
+
+            /// Set the minimum [U::Velocity], e.g. for axes that stall below a certain step rate
+            /// 
+            /// Movements never dwell below the minimum velocity, slower velocities are raised to it. Only the ramps accelerating
+            /// from or decelerating to a standstill pass through lower velocities.
+            /// 
+            /// ## Option
+            /// 
+            /// Set to `None` if no limit is wished
+            /// 
+            /// ## Errors
+            /// 
+            /// Returns [ActuatorError::VelocityTooHigh] if the minimum is above the maximum velocity set, setting a maximum 
+            /// velocity below the minimum fails with [ActuatorError::VelocityTooLow] in return. Actuators without support for 
+            /// a minimum velocity return [ActuatorError::InvalidVelocity] for every minimum given.
+            fn set_velocity_min(&mut self, velocity_opt : Option<U::Velocity>) -> Result<(), ActuatorError<U>> {
+                match velocity_opt {
+                    Some(velocity) => Err(ActuatorError::InvalidVelocity(velocity)),
+                    None => Ok(())
+                }
+            }
         // 
 
         // Acceleration
@@ -124,6 +305,30 @@ use crate::ActuatorError;
             /// 
             /// Set to `None` if no limit is wished
             fn set_acceleration_max(&mut self, acceleration_opt : Option<U::Acceleration>) -> Result<(), ActuatorError<U>>;
+
+            /// Maximum deceleration that will be allowed, if specified by the user with `set_deceleration_max`, always 
+            /// `None` for actuators without support for a separate deceleration
+            fn deceleration_max(&self) -> Option<U::Acceleration> {
+                None
+            }
+
+            /// Set the maximum allowed deceleration, used to ramp down fixed distance movements and to stop, e.g. for 
+            /// machines that can brake harder than they can accelerate
+            /// 
+            /// ## Option
+            /// 
+            /// Set to `None` to decelerate with the acceleration limit
+            /// 
+            /// ## Errors
+            /// 
+            /// Actuators without support for a separate deceleration return [ActuatorError::InvalidAcceleration] for every 
+            /// deceleration given.
+            fn set_deceleration_max(&mut self, deceleration_opt : Option<U::Acceleration>) -> Result<(), ActuatorError<U>> {
+                match deceleration_opt {
+                    Some(deceleration) => Err(ActuatorError::InvalidAcceleration(deceleration)),
+                    None => Ok(())
+                }
+            }
         // 
 
         // Jolt
@@ -170,7 +375,7 @@ use crate::ActuatorError;
             /// // Create a new gear bearing (implements SyncActuator)
             /// let mut gear = Gear::new(
             ///     // Stepper Motor as subcomponent (also implements SyncActuator)
-            ///     Stepper::default(), 
+            ///     Stepper::simulated(), 
             /// 0.5);    // Ratio is set to 0.5, which means for each radian the motor moves, the bearing moves for half a radian
             /// 
             /// gear.set_pos_limits(Some(LIM_MIN), Some(LIM_MAX));
@@ -216,7 +421,7 @@ use crate::ActuatorError;
             /// // Create a new gear bearing (implements SyncActuator)
             /// let mut gear = Gear::new(
             ///     // Stepper Motor as subcomponent (also implements SyncActuator)
-            ///     Stepper::default(), 
+            ///     Stepper::simulated(), 
             /// 0.5);    // Ratio is set to 0.5, which means for each radian the motor moves, the bearing moves for half a radian
             /// 
             /// gear.set_pos_limits(Some(LIM_MIN), Some(LIM_MAX));
@@ -256,7 +461,7 @@ use crate::ActuatorError;
             /// // Create a new gear bearing (implements SyncActuator)
             /// let mut gear = Gear::new(
             ///     // Stepper Motor as subcomponent (also implements SyncActuator)
-            ///     Stepper::default(), 
+            ///     Stepper::simulated(), 
             /// 0.5);    // Ratio is set to 0.5, which means for each radian the motor moves, the bearing moves for half a radian
             /// 
             /// gear.set_pos_limits(Some(LIM_MIN), Some(LIM_MAX));
@@ -278,6 +483,36 @@ use crate::ActuatorError;
             /// assert_eq!(gear.resolve_pos_limits_for_abs_pos(PositionRad(-4.0)), Radians(-1.0));   // Under the minimum, but less
             /// ```
             fn overwrite_pos_limits(&mut self, min : Option<U::Position>, max : Option<U::Position>);
+
+            /// The behavior of the actuator at its position limits, see [LimitBehavior]
+            fn limit_behavior(&self) -> LimitBehavior {
+                LimitBehavior::Stop
+            }
+
+            /// Sets the behavior of the actuator at its position limits, e.g. to clamp movements at the limits instead of 
+            /// stopping behind them
+            /// 
+            /// ```rust
+            /// use syact::prelude::*;
+            /// 
+            /// let mut stepper = Stepper::simulated();
+            /// stepper.set_pos_limits(None, Some(PositionRad(2.0)));
+            /// stepper.set_limit_behavior(LimitBehavior::DecelerateAndClamp).unwrap();
+            /// 
+            /// // The movement is truncated at the limit
+            /// stepper.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+            /// assert!((stepper.pos() - PositionRad(2.0)).abs() < Radians(0.05));
+            /// ```
+            /// 
+            /// The default implementation only accepts the behavior returned by [SyncActuator::limit_behavior] and returns 
+            /// [ActuatorError::InvalidConfig] otherwise
+            fn set_limit_behavior(&mut self, behavior : LimitBehavior) -> Result<(), ActuatorError<U>> {
+                if behavior == self.limit_behavior() {
+                    Ok(())
+                } else {
+                    Err(ActuatorError::InvalidConfig("The limit behavior is not supported by the actuator"))
+                }
+            }
         // 
     }
 //
@@ -287,6 +522,26 @@ use crate::ActuatorError;
 // #########################################
     // Movement
         /// Further defines a `SyncActuator`, extending it with blocking movement functions
+        /// 
+        /// # Observing blocking movements
+        /// 
+        /// A blocking movement borrows the component mutably until it is finished, so not even its position can be read in the
+        /// meantime. Clone the state handle *before* the movement instead, it is cheap and reports the position and mode of the 
+        /// component live while the movement is executed.
+        /// 
+        /// ```rust
+        /// use syact::prelude::*;
+        /// 
+        /// let mut stepper = Stepper::simulated();
+        /// 
+        /// stepper.drive_rel_blocking_with(Radians(2.0), Factor::MAX, |state| {
+        ///     // Hand the state to a user interface thread, which can read it while the movement blocks
+        ///     std::thread::spawn(move || {
+        ///         let _ = state.pos();
+        ///     });
+        /// }).unwrap();
+        /// ```
+        #[cfg(feature = "alloc")]
         pub trait SyncActuatorBlocking<U : UnitSet = Rotary> : SyncActuator<U> {
             // State
                 /// Returns a reference to the actuators `SyncActuatorState`
@@ -299,6 +554,15 @@ use crate::ActuatorError;
             /// Moves the component by the relative distance as fast as possible, blocks the script until the movement is finshed
             fn drive_rel_blocking(&mut self, rel_dist : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>>;
 
+            /// Moves the component by the relative distance like [SyncActuatorBlocking::drive_rel_blocking], but leaves the 
+            /// movement with the speed factor `exit` instead of stopping, so the next movement in the same direction is blended
+            /// into it, see [SegmentQueue::push_blended]
+            /// 
+            /// The default implementation stops at the end of the movement, as most actuators cannot blend movements
+            fn drive_rel_blocking_blended(&mut self, rel_dist : U::Distance, speed : Factor, _exit : Factor) -> Result<(), ActuatorError<U>> {
+                self.drive_rel_blocking(rel_dist, speed)
+            }
+
             /// Moves the component to the absolute position as fast as possible, blocks the script until the movement is finshed
             #[inline]
             fn drive_abs_blocking(&mut self, pos : U::Position, speed : Factor) -> Result<(), ActuatorError<U>> {
@@ -306,6 +570,32 @@ use crate::ActuatorError;
                 self.drive_rel_blocking(rel_dist, speed)
             }
 
+            // Observed movements
+                /// Moves the component by the relative distance like [SyncActuatorBlocking::drive_rel_blocking], handing a handle
+                /// to its state to the `observer` right before the movement is started
+                /// 
+                /// Unlike [SyncActuatorBlocking::clone_state], the handle can be sent to other threads. It reports the position in
+                /// the units of the component for the observed movement.
+                fn drive_rel_blocking_with<F>(&mut self, rel_dist : U::Distance, speed : Factor, observer : F) -> Result<(), ActuatorError<U>> 
+                where
+                    F : FnOnce(Arc<dyn SyncActuatorState<U> + Send + Sync>),
+                    U : 'static,
+                    Self : Sized;
+
+                /// Moves the component to the absolute position like [SyncActuatorBlocking::drive_abs_blocking], handing a handle
+                /// to its state to the `observer` right before the movement is started, see 
+                /// [SyncActuatorBlocking::drive_rel_blocking_with]
+                fn drive_abs_blocking_with<F>(&mut self, pos : U::Position, speed : Factor, observer : F) -> Result<(), ActuatorError<U>> 
+                where
+                    F : FnOnce(Arc<dyn SyncActuatorState<U> + Send + Sync>),
+                    U : 'static,
+                    Self : Sized
+                {
+                    let rel_dist = pos - self.pos();
+                    self.drive_rel_blocking_with(rel_dist, speed, observer)
+                }
+            // 
+
             /// Starts the movement process of the component in the given direction with a given `speed` factor
             fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError<U>>; 
 
@@ -314,6 +604,7 @@ use crate::ActuatorError;
         }
 
         /// Further defines a `SyncActuator`, extending it with non-blocking movement functions
+        #[cfg(feature = "alloc")]
         pub trait SyncActuatorNB<U : UnitSet = Rotary> : SyncActuator<U> {
             /// Moves the component by the relative distance as fast as possible, blocks the script until the movement is finshed
             fn drive_rel_nb(&mut self, rel_dist : U::Distance, speed : Factor) -> Result<(), ActuatorError<U>>;
@@ -324,5 +615,15 @@ use crate::ActuatorError;
                 self.drive_rel_nb(rel_dist, speed)
             }
         }
+
+        /// Future-based movement functions, the returned futures are awakened once the movement has finished, see [AsyncMotion]
+        #[cfg(feature = "alloc")]
+        pub trait SyncActuatorAsync<U : UnitSet = Rotary> {
+            /// Moves the component by the relative distance as fast as possible, resolves once the movement is finished
+            fn drive_rel(&mut self, rel_dist : U::Distance, speed : Factor) -> impl Future<Output = Result<(), ActuatorError<U>>>;
+
+            /// Moves the component to the absolute position as fast as possible, resolves once the movement is finished
+            fn drive_abs(&mut self, pos : U::Position, speed : Factor) -> impl Future<Output = Result<(), ActuatorError<U>>>;
+        }
     // 
 // 
\ No newline at end of file
@@ -0,0 +1,69 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use syunit::*;
+
+use crate::ActuatorError;
+
+/// A single pre-move check, returning the reason why the movement must not start as error
+pub type PreMoveCheck = Box<dyn FnMut() -> Result<(), &'static str> + Send>;
+
+/// A chain of named checks evaluated before a movement starts, e.g. "door closed", "air pressure ok" or "tool clamped"
+///
+/// The checks are evaluated in the order they have been added, the first failing check aborts the movement with
+/// [ActuatorError::PreMoveCheckFailed]. Checks are only evaluated when a movement starts, use interruptors to supervise
+/// conditions during movements.
+#[derive(Default)]
+pub struct PreMoveChecks {
+    checks : Vec<(&'static str, PreMoveCheck)>
+}
+
+impl PreMoveChecks {
+    /// Creates a new empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a `check` with the given `name` to the end of the chain, replacing any check with the same name
+    pub fn add(&mut self, name : &'static str, check : PreMoveCheck) {
+        self.remove(name);
+        self.checks.push((name, check));
+    }
+
+    /// Removes the check with the given `name`, returns `true` if the check existed
+    pub fn remove(&mut self, name : &'static str) -> bool {
+        let len = self.checks.len();
+        self.checks.retain(|(n, _)| *n != name);
+        self.checks.len() != len
+    }
+
+    /// The names of all checks in the chain
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.checks.iter().map(|(name, _)| *name)
+    }
+
+    /// The amount of checks in the chain
+    pub fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    /// Returns `true` if the chain contains no checks
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Evaluates all checks, returns the error of the first failing one
+    pub fn run<U : UnitSet>(&mut self) -> Result<(), ActuatorError<U>> {
+        for (name, check) in self.checks.iter_mut() {
+            check().map_err(|reason| ActuatorError::PreMoveCheckFailed(name, reason))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for PreMoveChecks {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
@@ -0,0 +1,193 @@
+use syunit::*;
+
+use crate::{ActuatorError, InterruptReason, Interruptible, SyncActuatorBlocking};
+
+/// The progress of a [ChunkedMove], reported at every checkpoint
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkProgress<U : UnitSet = Rotary> {
+    /// The amount of chunks completed
+    pub checkpoint : usize,
+    /// The total amount of chunks of the movement
+    pub chunks : usize,
+    /// The distance completed up to the last checkpoint, having the same sign as the total distance
+    pub done : U::Distance,
+    /// The total distance of the movement
+    pub total : U::Distance
+}
+
+impl<U : UnitSet> ChunkProgress<U> {
+    /// The completed fraction of the movement, ranging from `0.0` to `1.0`
+    pub fn fraction(&self) -> f32 {
+        if self.chunks == 0 {
+            1.0
+        } else {
+            self.checkpoint as f32 / self.chunks as f32
+        }
+    }
+
+    /// Returns `true` if all chunks have been completed
+    pub fn is_done(&self) -> bool {
+        self.checkpoint >= self.chunks
+    }
+}
+
+impl<U : UnitSet> PartialEq for ChunkProgress<U> {
+    fn eq(&self, other : &Self) -> bool {
+        (self.checkpoint == other.checkpoint) && (self.chunks == other.chunks)
+            && (self.done == other.done) && (self.total == other.total)
+    }
+}
+
+/// The way the execution of a [ChunkedMove] ended
+#[derive(Clone, Debug)]
+pub enum ChunkOutcome<U : UnitSet = Rotary> {
+    /// All chunks have been completed
+    Completed,
+    /// The checkpoint callback requested a pause, the movement stands still at the last checkpoint
+    Paused,
+    /// The running chunk has been halted, the next execution continues from the last checkpoint
+    Held,
+    /// The running chunk has been interrupted by an interruptor
+    /// - 0 - [InterruptReason]: The reason of the interrupt
+    Interrupted(InterruptReason),
+    /// The actuator failed to execute the running chunk
+    /// - 0 - [ActuatorError]: The error returned by the actuator
+    Failed(ActuatorError<U>)
+}
+
+/// A long fixed-distance movement split into checkpointed chunks
+///
+/// Instead of one single movement that either completes or is lost as a whole, the distance is driven in chunks of a fixed
+/// length. After every chunk the progress is reported to a callback, which can pause or abort the movement between two
+/// chunks.
+///
+/// # Resuming
+///
+/// The chunks are placed relative to the position the actuator had when the movement has been started first. If a chunk is
+/// halted, interrupted or fails, the next execution continues from the last completed checkpoint, driving the rest of the
+/// unfinished chunk to its original end. Call [ChunkedMove::reset] to discard the progress.
+#[derive(Clone, Debug)]
+pub struct ChunkedMove<U : UnitSet = Rotary> {
+    total : U::Distance,
+    chunk : U::Distance,
+    speed : Factor,
+
+    _origin : Option<U::Position>,
+    _checkpoint : usize
+}
+
+impl<U : UnitSet> ChunkedMove<U> {
+    /// Creates a new chunked movement over the relative distance `total`, each chunk covering the distance `chunk` at most
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the `chunk` distance is zero or not finite
+    pub fn new(total : U::Distance, chunk : U::Distance, speed : Factor) -> Self {
+        let chunk_f : f32 = chunk.into();
+
+        if !chunk_f.is_normal() {
+            panic!("The chunk distance of a chunked move must be finite and not zero! (given: {})", chunk_f);
+        }
+
+        Self {
+            total,
+            chunk: chunk.abs(),
+            speed,
+
+            _origin: None,
+            _checkpoint: 0
+        }
+    }
+
+    // Data
+        /// The total relative distance of the movement
+        pub fn total(&self) -> U::Distance {
+            self.total
+        }
+
+        /// The speed factor every chunk is driven with
+        pub fn speed(&self) -> Factor {
+            self.speed
+        }
+
+        /// The total amount of chunks
+        pub fn chunks(&self) -> usize {
+            let total : f32 = self.total.into();
+            let chunk : f32 = self.chunk.into();
+
+            let ratio = total.abs() / chunk;
+            let chunks = ratio as usize;
+
+            // Rounding up, the last chunk covers the rest of the distance
+            if (chunks as f32) < ratio { chunks + 1 } else { chunks }
+        }
+
+        /// The position the movement has been started at, `None` if it has not been started yet
+        pub fn origin(&self) -> Option<U::Position> {
+            self._origin
+        }
+
+        /// The progress of the movement at the last checkpoint
+        pub fn progress(&self) -> ChunkProgress<U> {
+            ChunkProgress {
+                checkpoint: self._checkpoint,
+                chunks: self.chunks(),
+                done: self.dist_at(self._checkpoint),
+                total: self.total
+            }
+        }
+
+        /// Returns `true` if all chunks have been completed
+        pub fn is_done(&self) -> bool {
+            self._checkpoint >= self.chunks()
+        }
+
+        /// Discards the progress, the next execution starts the whole movement again from the position of the actuator
+        pub fn reset(&mut self) {
+            self._origin = None;
+            self._checkpoint = 0;
+        }
+    //
+
+    /// The distance from the origin to the given `checkpoint`
+    fn dist_at(&self, checkpoint : usize) -> U::Distance {
+        let total : f32 = self.total.into();
+        let chunk : f32 = self.chunk.into();
+
+        U::Distance::from(total.signum() * (chunk * checkpoint as f32).min(total.abs()))
+    }
+
+    /// Executes the remaining chunks with the given `actuator`, calling `on_checkpoint` after every completed chunk
+    ///
+    /// Returning `false` from the callback pauses the movement at the checkpoint just reached, execute the movement again to
+    /// continue it. See the [ChunkedMove] docs on how interrupted chunks are resumed.
+    pub fn run<A, F>(&mut self, actuator : &mut A, mut on_checkpoint : F) -> ChunkOutcome<U>
+    where
+        A : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized,
+        F : FnMut(&ChunkProgress<U>) -> bool
+    {
+        let origin = *self._origin.get_or_insert_with(|| actuator.pos());
+
+        while !self.is_done() {
+            let target = origin + self.dist_at(self._checkpoint + 1);
+
+            if let Err(err) = actuator.drive_rel_blocking(target - actuator.pos(), self.speed) {
+                return ChunkOutcome::Failed(err);
+            }
+
+            match actuator.intr_reason() {
+                Some(InterruptReason::Halted) => return ChunkOutcome::Held,
+                Some(reason) => return ChunkOutcome::Interrupted(reason),
+                None => { }
+            }
+
+            self._checkpoint += 1;
+
+            if !on_checkpoint(&self.progress()) && !self.is_done() {
+                return ChunkOutcome::Paused;
+            }
+        }
+
+        ChunkOutcome::Completed
+    }
+}
@@ -0,0 +1,177 @@
+use alloc::collections::VecDeque;
+
+use embedded_hal::delay::DelayNs;
+use syunit::*;
+
+use crate::ActuatorError;
+
+/// A local, monotonic clock, e.g. the system timer of the controller
+pub trait LocalClock {
+    /// The current time of the clock
+    fn now(&mut self) -> Seconds;
+}
+
+/// The result of a single time exchange with the remote device
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockSample {
+    /// The offset of the shared (remote) clock relative to the local clock
+    pub offset : Seconds,
+    /// The round-trip delay of the exchange, excluding the processing time of the remote device
+    pub delay : Seconds
+}
+
+impl ClockSample {
+    /// Evaluates a time exchange the same way PTP does
+    /// - `t1`: Local time the request has been sent
+    /// - `t2`: Remote time the request has been received
+    /// - `t3`: Remote time the response has been sent
+    /// - `t4`: Local time the response has been received
+    ///
+    /// The offset is only exact if the delays of both directions are equal, the error is at most half of the round-trip delay
+    pub fn from_exchange(t1 : Seconds, t2 : Seconds, t3 : Seconds, t4 : Seconds) -> Self {
+        Self {
+            offset: Seconds(((t2.0 - t1.0) + (t3.0 - t4.0)) / 2.0),
+            delay: Seconds((t4.0 - t1.0) - (t3.0 - t2.0))
+        }
+    }
+
+    /// The maximum error of the offset, being half of the round-trip delay
+    pub fn uncertainty(&self) -> Seconds {
+        Seconds(self.delay.0.abs() / 2.0)
+    }
+}
+
+/// Estimates the offset between the local clock and the clock shared with a remote device, e.g. a second controller
+/// moving other axes of the same machine
+///
+/// The estimation keeps the last `window` samples and uses the one with the lowest round-trip delay, as it has the lowest
+/// uncertainty. The samples have to be taken periodically, as the clocks drift apart over time.
+#[derive(Clone, Debug)]
+pub struct ClockSync {
+    samples : VecDeque<ClockSample>,
+    window : usize
+}
+
+impl ClockSync {
+    /// Creates a new clock synchronization keeping the last `window` samples
+    pub fn new(window : usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            window: window.max(1)
+        }
+    }
+
+    /// Adds a sample, dropping the oldest one if the window is full
+    pub fn add_sample(&mut self, sample : ClockSample) {
+        if self.samples.len() >= self.window {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    /// Removes all samples, e.g. after the connection to the remote device has been lost
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns the sample with the lowest uncertainty, `None` if no samples have been taken yet
+    pub fn estimate(&self) -> Option<ClockSample> {
+        self.samples.iter().copied().reduce(|best, sample|
+            if sample.delay.0.abs() < best.delay.0.abs() { sample } else { best }
+        )
+    }
+
+    /// Converts a time of the shared clock into local time
+    pub fn to_local(&self, shared : Seconds) -> Option<Seconds> {
+        self.estimate().map(|est| Seconds(shared.0 - est.offset.0))
+    }
+
+    /// Converts a local time into the shared clock
+    pub fn to_shared(&self, local : Seconds) -> Option<Seconds> {
+        self.estimate().map(|est| Seconds(local.0 + est.offset.0))
+    }
+
+    /// Runs `count` time exchanges with the remote device and adds the samples
+    ///
+    /// The `exchange` closure sends a request over the protocol used to communicate with the remote device and returns the
+    /// remote times the request has been received and the response has been sent, or `None` if the exchange failed. Returns the
+    /// number of successful exchanges.
+    pub fn sync<C, F>(&mut self, clock : &mut C, mut exchange : F, count : usize) -> usize
+    where
+        C : LocalClock,
+        F : FnMut() -> Option<(Seconds, Seconds)>
+    {
+        let mut successful = 0;
+
+        for _ in 0 .. count {
+            let t1 = clock.now();
+
+            if let Some((t2, t3)) = exchange() {
+                let t4 = clock.now();
+
+                self.add_sample(ClockSample::from_exchange(t1, t2, t3, t4));
+                successful += 1;
+            }
+        }
+
+        successful
+    }
+}
+
+/// A [ClockSync] combined with the local clock and a delay, used to start movements at times given in the shared clock
+/// domain, see [SegmentQueue::run_next_synced](crate::sync::SegmentQueue::run_next_synced)
+pub struct SharedClock<C : LocalClock, D : DelayNs> {
+    /// The local clock
+    pub clock : C,
+    /// The delay used to wait for start times
+    pub delay : D,
+    /// The offset estimation
+    pub sync : ClockSync,
+    /// The maximum skew allowed between the devices, starts are refused if the uncertainty of the estimation or the delay of
+    /// the start exceeds it
+    pub max_skew : Seconds
+}
+
+impl<C : LocalClock, D : DelayNs> SharedClock<C, D> {
+    /// Creates a new shared clock with an empty [ClockSync] keeping the last 8 samples
+    pub fn new(clock : C, delay : D, max_skew : Seconds) -> Self {
+        Self {
+            clock,
+            delay,
+            sync: ClockSync::new(8),
+            max_skew
+        }
+    }
+
+    /// The current time of the shared clock, `None` if the clocks have not been synchronized yet
+    pub fn now(&mut self) -> Option<Seconds> {
+        let local = self.clock.now();
+        self.sync.to_shared(local)
+    }
+
+    /// Blocks until the shared clock reaches the `start` time
+    ///
+    /// Returns [ActuatorError::ClockSkewExceeded] if the clocks are not synchronized precisely enough and
+    /// [ActuatorError::StartTimeMissed] if the start time has passed by more than the maximum skew
+    pub fn wait_until<U : UnitSet>(&mut self, start : Seconds) -> Result<(), ActuatorError<U>> {
+        let estimate = self.sync.estimate()
+            .ok_or(ActuatorError::ClockSkewExceeded(Seconds::INFINITY))?;
+
+        if estimate.uncertainty() > self.max_skew {
+            return Err(ActuatorError::ClockSkewExceeded(estimate.uncertainty()));
+        }
+
+        let remaining = Seconds(start.0 - estimate.offset.0 - self.clock.now().0);
+
+        if remaining.0 < -self.max_skew.0 {
+            return Err(ActuatorError::StartTimeMissed(-remaining));
+        }
+
+        if remaining > Seconds::ZERO {
+            self.delay.delay_us((remaining.0 * 1_000_000.0) as u32);
+        }
+
+        Ok(())
+    }
+}
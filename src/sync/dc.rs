@@ -0,0 +1,549 @@
+use core::sync::atomic::{AtomicBool, AtomicU8};
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use atomic_float::AtomicF32;
+use embedded_hal::delay::DelayNs;
+use syunit::*;
+
+use crate::{ActuatorError, LimitBehavior, MotionMode, SyncActuator, SyncActuatorBlocking, SyncActuatorState};
+use crate::io::{DigitalOutput, Encoder, PwmOutput};
+use crate::math::kin;
+use crate::parent::{LayerPos, PositionLayers};
+use crate::report::Capabilities;
+
+/// The state of a [PidDcMotor], shared with other threads
+#[derive(Debug, Default)]
+pub struct DcState {
+    _pos : AtomicF32,
+    _moving : AtomicBool,
+    _mode : AtomicU8,
+    _halt : AtomicBool,
+    _interrupt : AtomicBool
+}
+
+impl DcState {
+    fn set_mode(&self, mode : MotionMode) {
+        self._mode.store(mode as u8, Relaxed);
+        self._moving.store(!matches!(mode, MotionMode::Inactive | MotionMode::Holding), Relaxed);
+    }
+}
+
+impl SyncActuatorState for DcState {
+    fn pos(&self) -> PositionRad {
+        PositionRad(self._pos.load(Relaxed))
+    }
+
+    fn moving(&self) -> bool {
+        self._moving.load(Relaxed)
+    }
+
+    fn motion_mode(&self) -> MotionMode {
+        MotionMode::from_u8(self._mode.load(Relaxed))
+    }
+
+    fn halt(&self) {
+        self._halt.store(true, Relaxed);
+    }
+
+    fn interrupt(&self) {
+        self._interrupt.store(true, Relaxed);
+    }
+}
+
+/// The gains of the position control of a [PidDcMotor], the output being the duty cycle of the motor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PidGains {
+    /// Duty cycle per radian of position error
+    pub p : f32,
+    /// Duty cycle per radian of position error and second
+    pub i : f32,
+    /// Duty cycle per radian per second of position error change
+    pub d : f32
+}
+
+impl Default for PidGains {
+    fn default() -> Self {
+        Self { p: 8.0, i: 20.0, d: 0.05 }
+    }
+}
+
+/// A brushed DC (gear-)motor with a quadrature encoder, positioned by a PID control loop
+///
+/// The motor is driven by a PWM output and a direction output, the position is read from the [Encoder]. Fixed distance 
+/// movements follow a trapezoidal reference generated from the velocity and acceleration limits, the PID loop corrects the 
+/// deviations from it on top of a velocity feed-forward. This way the motor implements [SyncActuator] and 
+/// [SyncActuatorBlocking] and can be used interchangeably with stepper motors.
+///
+/// # Control loop
+///
+/// The loop is executed by the thread calling the movement functions, waiting for the control `period` with the `delay` 
+/// between two iterations. A movement is finished once the reference has ended and the position error is within the 
+/// `tolerance`.
+///
+/// # Continuous movements
+///
+/// [SyncActuatorBlocking::drive_factor] and [SyncActuatorBlocking::drive_speed] drive the motor open-loop with the duty 
+/// cycle matching the velocity, call [PidDcMotor::update_pos] periodically to keep the position up to date.
+pub struct PidDcMotor<O : PwmOutput, E : Encoder, D : DelayNs> {
+    output : O,
+    dir_output : Box<dyn DigitalOutput + Send>,
+    encoder : E,
+    delay : D,
+
+    /// The velocity of the motor at full duty cycle without any load, used for the feed-forward
+    pub velocity_motor : RadPerSecond,
+    /// The gains of the position control
+    pub gains : PidGains,
+    /// The interval of the control loop
+    pub period : Seconds,
+    /// The position error allowed for a movement to count as finished
+    pub tolerance : Radians,
+    /// The time the motor may take to reach the `tolerance` after the reference has ended
+    pub settle_timeout : Seconds,
+
+    _count_angle : Radians,
+    _state : Arc<DcState>,
+    _offset : Radians,
+    _direction : Direction,
+
+    // Limits
+    _velocity_max : Option<RadPerSecond>,
+    _velocity_min : Option<RadPerSecond>,
+    _acceleration_max : Option<RadPerSecond2>,
+    _deceleration_max : Option<RadPerSecond2>,
+    _limit_min : Option<PositionRad>,
+    _limit_max : Option<PositionRad>
+}
+
+impl<O : PwmOutput, E : Encoder, D : DelayNs> PidDcMotor<O, E, D> {
+    /// Creates a new motor, the `encoder` delivering `counts_per_rev` counts per revolution of the shaft (quadrature 
+    /// evaluation included), the motor reaching `velocity_motor` at full duty cycle
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `counts_per_rev` is zero or not finite
+    pub fn new(output : O, dir_output : Box<dyn DigitalOutput + Send>, encoder : E, delay : D, counts_per_rev : f32, 
+        velocity_motor : RadPerSecond) -> Self 
+    {
+        if !counts_per_rev.is_normal() {
+            panic!("The counts per revolution of an encoder must be finite and not zero! (given: {})", counts_per_rev);
+        }
+
+        Self {
+            output,
+            dir_output,
+            encoder,
+            delay,
+
+            velocity_motor: velocity_motor.abs(),
+            gains: PidGains::default(),
+            period: Seconds(0.001),
+            tolerance: Radians(0.01),
+            settle_timeout: Seconds(0.5),
+
+            _count_angle: Radians(core::f32::consts::TAU / counts_per_rev.abs()),
+            _state: Arc::new(DcState::default()),
+            _offset: Radians::ZERO,
+            _direction: Direction::CW,
+
+            _velocity_max: None,
+            _velocity_min: None,
+            _acceleration_max: None,
+            _deceleration_max: None,
+            _limit_min: None,
+            _limit_max: None
+        }
+    }
+
+    // Data
+        /// The angle of a single encoder count
+        pub fn count_angle(&self) -> Radians {
+            self._count_angle
+        }
+
+        /// The direction of the last movement
+        pub fn direction(&self) -> Direction {
+            self._direction
+        }
+    //
+
+    /// Reads the encoder and updates the position of the motor, returns the position
+    pub fn update_pos(&mut self) -> Result<PositionRad, ActuatorError> {
+        let count = self.encoder.count().map_err(|_| ActuatorError::IOError)?;
+        let pos = PositionRad(self._count_angle.0 * count as f32) + self._offset;
+
+        self._state._pos.store(pos.0, Relaxed);
+        Ok(pos)
+    }
+
+    /// Switches the output off, the motor coasts to a standstill
+    pub fn stop(&mut self) -> Result<(), ActuatorError> {
+        self.output.set_duty(Factor::MIN).map_err(|_| ActuatorError::IOError)?;
+        self._state.set_mode(MotionMode::Inactive);
+        Ok(())
+    }
+
+    // Helpers
+        /// Drives the motor with the signed relative duty cycle `duty`, values outside of `-1.0 ..= 1.0` are clamped
+        fn apply(&mut self, duty : f32) -> Result<(), ActuatorError> {
+            let direction = Direction::from_bool(duty >= 0.0);
+
+            self.dir_output.set(direction.as_bool()).map_err(|_| ActuatorError::IOError)?;
+            self.output.set_duty(Factor::new(duty.abs().min(1.0))).map_err(|_| ActuatorError::IOError)
+        }
+
+        /// The highest velocity the motor may drive with
+        fn velocity_limit(&self) -> RadPerSecond {
+            self._velocity_max.map_or(self.velocity_motor, |max| max.min(self.velocity_motor))
+        }
+
+        fn wait_period(&mut self) {
+            self.delay.delay_us((self.period.0 * 1_000_000.0) as u32);
+        }
+
+        /// Executes the control loop until the reference over `rel_dist` has been followed
+        fn follow(&mut self, rel_dist : Radians, speed : Factor) -> Result<(), ActuatorError> {
+            let pos_start = self.update_pos()?;
+            let sign = if rel_dist.is_sign_negative() { -1.0 } else { 1.0 };
+            let dist = rel_dist.abs().0;
+
+            let mut velocity = self.velocity_limit() * speed.as_f32();
+            if let Some(min) = self._velocity_min {
+                velocity = velocity.max(min);
+            }
+
+            // Without limits the reference jumps to the velocity within a single period
+            let accel_default = velocity / self.period;
+            let accel = self._acceleration_max.unwrap_or(accel_default);
+            let decel = self._deceleration_max.or(self._acceleration_max).unwrap_or(accel_default);
+
+            let times = kin::travel_times::<Rotary>(rel_dist, velocity, accel, decel);
+            let (t_accel, t_decel, total) = (times.accel.0, times.decel.0, times.total().0);
+            let velocity_peak = times.velocity_peak.0;
+            let dist_accel = kin::accel_distance::<Rotary>(RadPerSecond::ZERO, times.velocity_peak, accel).0;
+
+            let mut time : f32 = 0.0;
+            let mut integral = 0.0;
+            let mut error_last : Option<f32> = None;
+            let mut end = total;
+            let mut dist_halt : Option<f32> = None;
+
+            self._direction = Direction::from_bool(sign > 0.0);
+            self._state.set_mode(MotionMode::FixedDistance);
+
+            loop {
+                if self._state._interrupt.swap(false, Relaxed) {
+                    return self.stop();
+                }
+
+                let pos = self.update_pos()?;
+
+                // A halt freezes the reference where the motor is, which is then held actively
+                if self._state._halt.swap(false, Relaxed) && (time < end) {
+                    end = time;
+                    dist_halt = Some((pos - pos_start).0 * sign);
+                    integral = 0.0;
+                    self._state.set_mode(MotionMode::Stopping);
+                }
+
+                let (ref_dist, ref_velocity) = if time >= end {
+                    (dist_halt.unwrap_or(dist), 0.0)
+                } else if time < t_accel {
+                    (accel.0 * time * time / 2.0, accel.0 * time)
+                } else if time < (total - t_decel) {
+                    (dist_accel + velocity_peak * (time - t_accel), velocity_peak)
+                } else {
+                    let left = total - time;
+                    (dist - decel.0 * left * left / 2.0, decel.0 * left)
+                };
+
+                let error = (pos_start.0 + ref_dist * sign) - pos.0;
+
+                if (time >= end) && (error.abs() <= self.tolerance.0) {
+                    return self.stop();
+                }
+
+                if time >= (end + self.settle_timeout.0) {
+                    self.stop()?;
+                    return Err(ActuatorError::SettleTimeout(self.settle_timeout));
+                }
+
+                let derivative = error_last.map_or(0.0, |last| (error - last) / self.period.0);
+                let feed_forward = ref_velocity * sign / self.velocity_motor.0;
+
+                let integral_next = integral + error * self.period.0;
+                let duty = feed_forward + self.gains.p * error + self.gains.i * integral_next + self.gains.d * derivative;
+
+                // The integral is frozen while the output saturates, preventing wind-up
+                if duty.abs() <= 1.0 {
+                    integral = integral_next;
+                }
+
+                self.apply(duty)?;
+                error_last = Some(error);
+
+                self.wait_period();
+                time += self.period.0;
+            }
+        }
+    //
+}
+
+// Actuator traits
+    impl<O : PwmOutput, E : Encoder, D : DelayNs> Capabilities for PidDcMotor<O, E, D> {
+        fn motion_modes(&self) -> Vec<MotionMode> {
+            vec![
+                MotionMode::Inactive, MotionMode::ConstVelocity, MotionMode::ConstFactor, 
+                MotionMode::FixedDistance, MotionMode::Stopping
+            ]
+        }
+    }
+
+    impl<O : PwmOutput, E : Encoder, D : DelayNs> PositionLayers for PidDcMotor<O, E, D> {
+        fn layer_count(&self) -> usize {
+            1
+        }
+
+        fn layer_name(&self, index : usize) -> Option<&'static str> {
+            (index == 0).then_some("motor")
+        }
+
+        fn layer_pos(&self, index : usize) -> Option<LayerPos> {
+            (index == 0).then(|| LayerPos::Rotary(self.pos()))
+        }
+    }
+
+    impl<O : PwmOutput, E : Encoder, D : DelayNs> SyncActuator for PidDcMotor<O, E, D> {
+        // Position
+            fn pos(&self) -> PositionRad {
+                self._state.pos()
+            }
+
+            fn overwrite_abs_pos(&mut self, pos : PositionRad) {
+                self._offset += pos - self.pos();
+                self._state._pos.store(pos.0, Relaxed);
+            }
+        //
+
+        // Velocity
+            fn velocity_max(&self) -> Option<RadPerSecond> {
+                self._velocity_max
+            }
+
+            fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+                if let Some(velocity) = velocity_opt {
+                    if !velocity.is_normal() {
+                        return Err(ActuatorError::InvalidVelocity(velocity));
+                    }
+
+                    if let Some(min) = self._velocity_min.filter(|min| velocity.abs() < *min) {
+                        return Err(ActuatorError::VelocityTooLow(velocity.abs(), min));
+                    }
+                }
+
+                self._velocity_max = velocity_opt.map(|velocity| velocity.abs());
+                Ok(())
+            }
+
+            fn velocity_min(&self) -> Option<RadPerSecond> {
+                self._velocity_min
+            }
+
+            fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+                if let Some(velocity) = velocity_opt {
+                    if !velocity.is_normal() {
+                        return Err(ActuatorError::InvalidVelocity(velocity));
+                    }
+
+                    if velocity.abs() > self.velocity_limit() {
+                        return Err(ActuatorError::VelocityTooHigh(velocity.abs(), self.velocity_limit()));
+                    }
+                }
+
+                self._velocity_min = velocity_opt.map(|velocity| velocity.abs());
+                Ok(())
+            }
+        //
+
+        // Acceleration & Deceleration
+            fn acceleration_max(&self) -> Option<RadPerSecond2> {
+                self._acceleration_max
+            }
+
+            fn set_acceleration_max(&mut self, acceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+                if let Some(acceleration) = acceleration_opt.filter(|acceleration| !acceleration.is_normal()) {
+                    return Err(ActuatorError::InvalidAcceleration(acceleration));
+                }
+
+                self._acceleration_max = acceleration_opt.map(|acceleration| acceleration.abs());
+                Ok(())
+            }
+
+            fn deceleration_max(&self) -> Option<RadPerSecond2> {
+                self._deceleration_max
+            }
+
+            fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+                if let Some(deceleration) = deceleration_opt.filter(|deceleration| !deceleration.is_normal()) {
+                    return Err(ActuatorError::InvalidAcceleration(deceleration));
+                }
+
+                self._deceleration_max = deceleration_opt.map(|deceleration| deceleration.abs());
+                Ok(())
+            }
+        //
+
+        // Jolt, the reference is a trapezoid
+            fn jolt_max(&self) -> Option<RadPerSecond3> {
+                None
+            }
+
+            fn set_jolt_max(&mut self, jolt_opt : Option<RadPerSecond3>) -> Result<(), ActuatorError> {
+                match jolt_opt {
+                    Some(jolt) => Err(ActuatorError::InvalidJolt(jolt)),
+                    None => Ok(())
+                }
+            }
+        //
+
+        // Position limits
+            fn limit_min(&self) -> Option<PositionRad> {
+                self._limit_min
+            }
+
+            fn limit_max(&self) -> Option<PositionRad> {
+                self._limit_max
+            }
+
+            fn resolve_pos_limits_for_abs_pos(&self, pos : PositionRad) -> Radians {
+                match (self._limit_min, self._limit_max) {
+                    (None, None) => Radians::NAN,
+                    (Some(min), _) if pos < min => pos - min,
+                    (_, Some(max)) if pos > max => pos - max,
+                    _ => Radians::ZERO
+                }
+            }
+
+            fn set_endpos(&mut self, overwrite_abs_pos : PositionRad) {
+                self.overwrite_abs_pos(overwrite_abs_pos);
+
+                let dir = self._direction.as_bool();
+
+                self.set_pos_limits(
+                    if dir { None } else { Some(overwrite_abs_pos) },
+                    if dir { Some(overwrite_abs_pos) } else { None }
+                )
+            }
+
+            fn set_pos_limits(&mut self, min : Option<PositionRad>, max : Option<PositionRad>) {
+                if let Some(min) = min {
+                    self._limit_min = Some(min);
+                }
+
+                if let Some(max) = max {
+                    self._limit_max = Some(max);
+                }
+            }
+
+            fn overwrite_pos_limits(&mut self, min : Option<PositionRad>, max : Option<PositionRad>) {
+                self._limit_min = min;
+                self._limit_max = max;
+            }
+
+            // The position control always truncates the movements at the limits
+            fn limit_behavior(&self) -> LimitBehavior {
+                LimitBehavior::DecelerateAndClamp
+            }
+        //
+    }
+
+    impl<O : PwmOutput, E : Encoder, D : DelayNs> SyncActuatorBlocking for PidDcMotor<O, E, D> {
+        // State
+            fn state(&self) -> &dyn SyncActuatorState {
+                self._state.as_ref()
+            }
+
+            fn clone_state(&self) -> Arc<dyn SyncActuatorState> {
+                self._state.clone()
+            }
+        //
+
+        /// Moves the motor by `rel_dist` with the position control, the movement ends early at the position limits
+        fn drive_rel_blocking(&mut self, rel_dist : Radians, speed : Factor) -> Result<(), ActuatorError> {
+            if !rel_dist.is_finite() {
+                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+            }
+
+            let pos = self.update_pos()?;
+            let overshoot = self.resolve_pos_limits_for_abs_pos(pos + rel_dist);
+            let rel_dist = if overshoot.is_finite() { rel_dist - overshoot } else { rel_dist };
+
+            // Discard requests from before the movement
+            self._state._halt.store(false, Relaxed);
+            self._state._interrupt.store(false, Relaxed);
+
+            self.follow(rel_dist, speed)
+        }
+
+        fn drive_rel_blocking_with<F>(&mut self, rel_dist : Radians, speed : Factor, observer : F) -> Result<(), ActuatorError> 
+        where
+            F : FnOnce(Arc<dyn SyncActuatorState + Send + Sync>)
+        {
+            observer(self._state.clone());
+            self.drive_rel_blocking(rel_dist, speed)
+        }
+
+        /// Drives the motor open-loop in the given `direction`, the duty cycle scaled to the velocity limit
+        fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError> {
+            let duty = speed.as_f32() * (self.velocity_limit() / self.velocity_motor);
+            self.apply(if direction.as_bool() { duty } else { -duty })?;
+
+            self._direction = direction;
+            self._state.set_mode(if speed == Factor::MIN { MotionMode::Inactive } else { MotionMode::ConstFactor });
+            Ok(())
+        }
+
+        /// Drives the motor open-loop with the duty cycle matching the `speed`
+        fn drive_speed(&mut self, speed : RadPerSecond) -> Result<(), ActuatorError> {
+            if !speed.is_finite() {
+                return Err(ActuatorError::InvalidVelocity(speed));
+            }
+
+            if speed.abs() > self.velocity_limit() {
+                return Err(ActuatorError::VelocityTooHigh(speed.abs(), self.velocity_limit()));
+            }
+
+            if speed == RadPerSecond::ZERO {
+                return self.stop();
+            }
+
+            if let Some(min) = self._velocity_min.filter(|min| speed.abs() < *min) {
+                return Err(ActuatorError::VelocityTooLow(speed.abs(), min));
+            }
+
+            self.apply(speed / self.velocity_motor)?;
+
+            self._direction = speed.get_direction();
+            self._state.set_mode(MotionMode::ConstVelocity);
+            Ok(())
+        }
+    }
+//
+
+impl<O : PwmOutput, E : Encoder, D : DelayNs> core::fmt::Debug for PidDcMotor<O, E, D> {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PidDcMotor")
+            .field("velocity_motor", &self.velocity_motor)
+            .field("gains", &self.gains)
+            .field("period", &self.period)
+            .field("tolerance", &self.tolerance)
+            .field("pos", &self.pos())
+            .field("mode", &self._state.motion_mode())
+            .finish()
+    }
+}
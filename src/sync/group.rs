@@ -0,0 +1,122 @@
+use syunit::*;
+
+use crate::ActuatorError;
+use crate::report::{ReadinessReport, SelfTest};
+use crate::sync::stepper::{drive_rel_interpolated, InterpolatedStepper};
+
+/// A fixed-size group of actuators, e.g. the axes of a machine
+///
+/// Implemented for arrays of actuators, custom structs can implement it by listing their axes in [SyncActuatorGroup::axes]
+/// and [SyncActuatorGroup::axes_mut].
+///
+/// ```rust
+/// use syact::prelude::*;
+/// use syact::sync::SyncActuatorGroup;
+///
+/// let mut group = [ Stepper::simulated(), Stepper::simulated() ];
+///
+/// // Both axes start and arrive at the same time
+/// group.drive_ptp_coordinated([ PositionRad(1.0), PositionRad(-0.5) ], Factor::MAX).unwrap();
+///
+/// assert!((group.pos()[0] - PositionRad(1.0)).abs() < Radians(0.01));
+/// assert!((group.pos()[1] - PositionRad(-0.5)).abs() < Radians(0.01));
+/// ```
+pub trait SyncActuatorGroup<T, const N : usize> {
+    /// References to all actuators of the group
+    fn axes(&self) -> [&T; N];
+
+    /// Mutable references to all actuators of the group
+    fn axes_mut(&mut self) -> [&mut T; N];
+
+    // Iteration
+        /// Calls `func` for every actuator of the group with its index, returning the results
+        fn for_each<'a, F, R>(&'a self, mut func : F) -> [R; N]
+        where
+            F : FnMut(&'a T, usize) -> R,
+            T : 'a
+        {
+            let mut index = 0;
+
+            self.axes().map(|axis| {
+                index += 1;
+                func(axis, index - 1)
+            })
+        }
+
+        /// Calls `func` for every actuator of the group with its index, returning the results
+        fn for_each_mut<F, R>(&mut self, mut func : F) -> [R; N]
+        where
+            F : FnMut(&mut T, usize) -> R
+        {
+            let mut index = 0;
+
+            self.axes_mut().map(|axis| {
+                index += 1;
+                func(axis, index - 1)
+            })
+        }
+
+        /// Calls `func` for every actuator of the group with its index, stopping at the first error
+        fn try_for_each_mut<F, E>(&mut self, mut func : F) -> Result<(), E>
+        where
+            F : FnMut(&mut T, usize) -> Result<(), E>
+        {
+            for (index, axis) in self.axes_mut().into_iter().enumerate() {
+                func(axis, index)?;
+            }
+
+            Ok(())
+        }
+    //
+
+    /// Runs the [SelfTest] of every axis without moving them, returning the readiness report of the group
+    ///
+    /// Further checks (brakes, tools, encoders, ...) can be added to the returned report, see [ReadinessReport]
+    fn self_test(&mut self) -> ReadinessReport
+    where
+        T : SelfTest
+    {
+        let mut report = ReadinessReport::new();
+        self.for_each_mut(|axis, index| report.check_actuator(index, axis));
+        report
+    }
+
+    // Movement
+        /// The positions of all axes of the group
+        fn pos(&self) -> [PositionRad; N]
+        where
+            T : InterpolatedStepper
+        {
+            self.for_each(|axis, _| axis.axis_pos())
+        }
+
+        /// Drives all axes to the absolute `targets` with a coordinated point-to-point movement
+        ///
+        /// The velocities of the axes are scaled with their [DefinedActuator::ptp_time_for_distance](crate::DefinedActuator)
+        /// so that all of them start and arrive at the same time, the slowest axis moves with the `speed` factor. The step
+        /// signals of all axes are generated by the calling thread, see [drive_rel_interpolated] for the errors
+        fn drive_ptp_coordinated(&mut self, targets : [PositionRad; N], speed : Factor) -> Result<(), ActuatorError>
+        where
+            T : InterpolatedStepper
+        {
+            let mut axes = self.axes_mut().map(|axis| axis as &mut dyn InterpolatedStepper);
+
+            let mut rel_dists = [Radians::ZERO; N];
+            for ((rel_dist, target), axis) in rel_dists.iter_mut().zip(targets).zip(axes.iter()) {
+                *rel_dist = target - axis.axis_pos();
+            }
+
+            drive_rel_interpolated(&mut axes, &rel_dists, speed)
+        }
+    //
+}
+
+impl<T, const N : usize> SyncActuatorGroup<T, N> for [T; N] {
+    fn axes(&self) -> [&T; N] {
+        self.each_ref()
+    }
+
+    fn axes_mut(&mut self) -> [&mut T; N] {
+        self.each_mut()
+    }
+}
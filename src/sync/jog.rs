@@ -0,0 +1,273 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+use embedded_hal::delay::DelayNs;
+use syunit::*;
+
+use crate::{ActuatorError, InterruptContext, InterruptReason, Interruptor, SyncActuatorBlocking};
+use crate::math::kin;
+
+/// The interval in which the key is checked while waiting between the increments of a held jog
+pub const JOG_KEY_POLL_INTERVAL : Seconds = Seconds(0.01);
+
+/// Presets for jogging an actuator manually, selectable at runtime for every jog
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JogPreset<U : UnitSet = Rotary> {
+    /// Small fixed increments for precise positioning
+    /// - 0 - `U::Distance`: The increment to move for each jog
+    Fine(U::Distance),
+    /// Continuous movement in velocity mode with a low acceleration
+    /// - 0 - `U::Velocity`: The jog velocity
+    /// - 1 - `U::Acceleration`: The acceleration used to start and stop the jog
+    Coarse(U::Velocity, U::Acceleration),
+    /// Continuous movement in velocity mode with a high acceleration
+    /// - 0 - `U::Velocity`: The jog velocity
+    /// - 1 - `U::Acceleration`: The acceleration used to start and stop the jog
+    Rapid(U::Velocity, U::Acceleration)
+}
+
+impl<U : UnitSet> JogPreset<U> {
+    /// The distance the actuator requires to come to a standstill when jogging with this preset
+    ///
+    /// Fine jogs move by their increment only, so the distance is always zero
+    pub fn stop_distance(&self) -> U::Distance {
+        match *self {
+            Self::Fine(_) => U::Distance::ZERO,
+            Self::Coarse(velocity, acceleration) | Self::Rapid(velocity, acceleration) => 
+                kin::stop_distance::<U>(velocity, acceleration)
+        }
+    }
+}
+
+/// The "key held" signal of a jog pendant or user interface, see [SyncActuatorJog::jog_held]
+///
+/// Clones of the key share the same state, so it can be pressed and released from another thread. The interruptor of the 
+/// key (see [JogKey::interruptor]) has to be added to the actuator once, it stops the continuous phase of a held jog when 
+/// the key is released.
+#[derive(Clone, Debug, Default)]
+pub struct JogKey {
+    _held : Arc<AtomicBool>,
+    _armed : Arc<AtomicBool>,
+    _attached : Arc<AtomicBool>
+}
+
+impl JogKey {
+    /// Creates a new key that is not held
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presses the key
+    pub fn press(&self) {
+        self._held.store(true, Relaxed);
+    }
+
+    /// Releases the key, a continuous jog decelerates to a standstill
+    pub fn release(&self) {
+        self._held.store(false, Relaxed);
+    }
+
+    /// Returns `true` if the key is held
+    pub fn is_held(&self) -> bool {
+        self._held.load(Relaxed)
+    }
+
+    /// Creates the interruptor stopping the continuous phase of a held jog once the key is released
+    pub fn interruptor<U : UnitSet>(&self) -> Box<dyn Interruptor<U> + Send> {
+        self._attached.store(true, Relaxed);
+        Box::new(self.clone())
+    }
+}
+
+impl<U : UnitSet> Interruptor<U> for JogKey {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    // The key only interrupts jogs, so no direction has to be blocked
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn kind(&self) -> &'static str {
+        "JogKey"
+    }
+
+    fn check(&mut self, _ctx : &InterruptContext<U>) -> Option<InterruptReason> {
+        if self._armed.load(Relaxed) && !self.is_held() {
+            Some(InterruptReason::Halted)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parameters of a held jog, see [SyncActuatorJog::jog_held]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeldJog<U : UnitSet = Rotary> {
+    /// The fine increment moved when the key is pressed and for every repeat
+    pub increment : U::Distance,
+    /// The time the key has to be held after the first increment until the increments start repeating
+    pub repeat_delay : Seconds,
+    /// The amount of repeated increments before the jog turns into a continuous movement
+    pub repeats : usize,
+    /// The pause before the first repeated increment, the pauses shrink with every repeat
+    pub repeat_interval : Seconds,
+    /// The velocity of the continuous movement
+    pub velocity : U::Velocity,
+    /// The acceleration used to speed up the continuous movement and to stop it once the key is released
+    pub acceleration : U::Acceleration
+}
+
+/// The last phase of a held jog reached before the key has been released
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeldJogPhase {
+    /// The key has been released before the increments started repeating, a single increment has been moved
+    Increment,
+    /// The key has been released while the increments were repeating
+    /// - 0 - `usize`: The amount of repeated increments moved
+    Repeat(usize),
+    /// The jog reached its continuous movement
+    Continuous
+}
+
+/// Waits up to `time` while the `key` is held, returns `true` if the key has been held for the whole time
+fn wait_held<D : DelayNs>(key : &JogKey, time : Seconds, delay : &mut D) -> bool {
+    let mut waited = Seconds::ZERO;
+
+    while waited < time {
+        if !key.is_held() {
+            return false;
+        }
+
+        let slice = JOG_KEY_POLL_INTERVAL.min(time - waited);
+        delay.delay_us((slice.0 * 1_000_000.0) as u32);
+        waited += slice;
+    }
+
+    key.is_held()
+}
+
+/// Extends every [SyncActuatorBlocking] with a jog function, the movements are validated against the position limits
+/// before they are started
+pub trait SyncActuatorJog<U : UnitSet = Rotary> : SyncActuatorBlocking<U> {
+    /// The distance the actuator can travel in the given `direction` before it reaches its limit, infinite if no limit is set
+    fn distance_to_limit(&self, direction : Direction) -> U::Distance {
+        let pos = self.pos();
+
+        let distance = if direction.as_bool() {
+            self.limit_max().map(|max| max - pos)
+        } else {
+            self.limit_min().map(|min| pos - min)
+        };
+
+        distance.unwrap_or(U::Distance::INFINITY)
+    }
+
+    /// Jogs the actuator in the given `direction` using the `preset`, blocks until the jog is finished
+    ///
+    /// - [JogPreset::Fine] moves the actuator by a single increment
+    /// - [JogPreset::Coarse] and [JogPreset::Rapid] move the actuator continuously until it is halted, interrupted or the
+    ///   position limit is reached. The limit in jog direction is temporarily reduced by the stop distance, so the actuator
+    ///   comes to a standstill before the actual limit.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [ActuatorError::StopDistanceExceedsLimit] if the increment or the stop distance does not fit between the
+    /// current position and the limit
+    fn jog(&mut self, preset : JogPreset<U>, direction : Direction) -> Result<(), ActuatorError<U>> {
+        let available = self.distance_to_limit(direction);
+
+        match preset {
+            JogPreset::Fine(increment) => {
+                let increment = increment.abs();
+
+                if increment > available {
+                    return Err(ActuatorError::StopDistanceExceedsLimit(increment, available));
+                }
+
+                self.drive_rel_blocking(if direction.as_bool() { increment } else { -increment }, Factor::MAX)
+            },
+            JogPreset::Coarse(velocity, acceleration) | JogPreset::Rapid(velocity, acceleration) => {
+                let stop_distance = preset.stop_distance();
+
+                if stop_distance > available {
+                    return Err(ActuatorError::StopDistanceExceedsLimit(stop_distance, available));
+                }
+
+                // Store the current settings, as they are modified for the jog
+                let acceleration_prev = self.acceleration_max();
+                let deceleration_prev = self.deceleration_max();
+                let limit_min = self.limit_min();
+                let limit_max = self.limit_max();
+
+                // The jog stops with the acceleration of the preset
+                self.set_acceleration_max(Some(acceleration.abs()))?;
+                self.set_deceleration_max(None)?;
+
+                if direction.as_bool() {
+                    self.overwrite_pos_limits(limit_min, limit_max.map(|max| max - stop_distance));
+                } else {
+                    self.overwrite_pos_limits(limit_min.map(|min| min + stop_distance), limit_max);
+                }
+
+                let result = self.drive_speed(if direction.as_bool() { velocity.abs() } else { -velocity.abs() });
+
+                // Restore the settings in every case
+                self.overwrite_pos_limits(limit_min, limit_max);
+                self.set_acceleration_max(acceleration_prev)?;
+                self.set_deceleration_max(deceleration_prev)?;
+
+                result
+            }
+        }
+    }
+
+    /// Jogs the actuator in the given `direction` like the key of a jog pendant, blocks until the `key` is released
+    /// 
+    /// 1. A single fine increment is moved, even if the key has already been released again (a tap)
+    /// 2. If the key is still held after the repeat delay, the increment is repeated with shrinking pauses
+    /// 3. After all repeats, the actuator accelerates into a continuous movement ([JogPreset::Coarse]) and decelerates to a 
+    ///    standstill once the key is released
+    /// 
+    /// The `delay` is used for the pauses between the increments, all movements are validated against the position limits 
+    /// like with [SyncActuatorJog::jog].
+    /// 
+    /// ## Errors
+    /// 
+    /// Returns [ActuatorError::InvalidConfig] if the interruptor of the key has never been created, as the continuous 
+    /// movement could not be stopped then
+    fn jog_held<D : DelayNs>(&mut self, key : &JogKey, params : &HeldJog<U>, direction : Direction, delay : &mut D) 
+        -> Result<HeldJogPhase, ActuatorError<U>> 
+    {
+        if !key._attached.load(Relaxed) {
+            return Err(ActuatorError::InvalidConfig("The interruptor of the jog key has not been added to the actuator"));
+        }
+
+        self.jog(JogPreset::Fine(params.increment), direction)?;
+
+        if !wait_held(key, params.repeat_delay, delay) {
+            return Ok(HeldJogPhase::Increment);
+        }
+
+        for i in 0 .. params.repeats {
+            self.jog(JogPreset::Fine(params.increment), direction)?;
+
+            // The pauses shrink linearly, so the repeats blend into the continuous movement
+            let pause = params.repeat_interval * (1.0 - (i + 1) as f32 / (params.repeats + 1) as f32);
+
+            if !wait_held(key, pause, delay) {
+                return Ok(HeldJogPhase::Repeat(i + 1));
+            }
+        }
+
+        key._armed.store(true, Relaxed);
+        let result = self.jog(JogPreset::Coarse(params.velocity, params.acceleration), direction);
+        key._armed.store(false, Relaxed);
+
+        result.map(|_| HeldJogPhase::Continuous)
+    }
+}
+
+impl<U : UnitSet, T : SyncActuatorBlocking<U> + ?Sized> SyncActuatorJog<U> for T { }
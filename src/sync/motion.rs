@@ -0,0 +1,196 @@
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use core::task::{Context, Poll, Waker};
+
+use alloc::sync::Arc;
+
+use syunit::*;
+
+use crate::{ActuatorError, SyncActuatorAsync, SyncActuatorBlocking, SyncActuatorState};
+
+/// Minimal spin lock guarding the data shared between an [AsyncMotion] and its [MotionWorker], the critical sections only
+/// copy a few values
+/// 
+/// Interrupts stay enabled while the lock is held, see [AsyncMotion::split] for the consequences
+pub(crate) struct Latch<T> {
+    _locked : AtomicBool,
+    data : UnsafeCell<T>
+}
+
+// The data is only accessed while the lock is held
+unsafe impl<T : Send> Sync for Latch<T> { }
+
+impl<T> Latch<T> {
+    pub(crate) fn new(data : T) -> Self {
+        Self { _locked: AtomicBool::new(false), data: UnsafeCell::new(data) }
+    }
+
+    pub(crate) fn with<R>(&self, f : impl FnOnce(&mut T) -> R) -> R {
+        while self._locked.compare_exchange_weak(false, true, Acquire, Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+
+        // Safety: The lock has been acquired above, no other reference to the data exists
+        let result = f(unsafe { &mut *self.data.get() });
+
+        self._locked.store(false, Release);
+        result
+    }
+}
+
+/// A movement handed from an [AsyncMotion] to its [MotionWorker]
+#[derive(Clone, Copy)]
+enum Command<U : UnitSet> {
+    Rel(U::Distance, Factor),
+    Abs(U::Position, Factor)
+}
+
+struct Link<U : UnitSet> {
+    command : Option<Command<U>>,
+    result : Option<Result<(), ActuatorError<U>>>,
+    waker : Option<Waker>,
+    busy : bool
+}
+
+/// The async handle of an actuator driven by a [MotionWorker], created with [AsyncMotion::split]
+///
+/// The movements started with [SyncActuatorAsync] are executed by the worker, which wakes the returned futures once the
+/// movements are done. No thread is blocked while awaiting a movement, the handle integrates with any executor.
+pub struct AsyncMotion<U : UnitSet = Rotary> {
+    link : Arc<Latch<Link<U>>>,
+    state : Arc<dyn SyncActuatorState<U>>
+}
+
+impl<U : UnitSet> AsyncMotion<U> {
+    /// Splits the `actuator` into an async handle and the worker executing its movements
+    ///
+    /// The worker has to be polled by the thread driving the actuator, see [MotionWorker::poll]
+    /// 
+    /// # Interrupts
+    /// 
+    /// The handle and the worker share their data with a spin lock that does not disable interrupts. Neither the worker nor 
+    /// the futures of the handle may be polled in interrupt context (e.g. by an interrupt executor), as an interrupt 
+    /// preempting the lock on the same core would spin forever. Run both in thread mode or on separate cores.
+    pub fn split<A : SyncActuatorBlocking<U>>(actuator : A) -> (Self, MotionWorker<A, U>) {
+        let link = Arc::new(Latch::new(Link { command: None, result: None, waker: None, busy: false }));
+
+        (
+            Self { link: link.clone(), state: actuator.clone_state() },
+            MotionWorker { actuator, link }
+        )
+    }
+
+    /// The shared state of the actuator, e.g. to halt or interrupt the movement currently awaited
+    pub fn state(&self) -> &dyn SyncActuatorState<U> {
+        self.state.as_ref()
+    }
+}
+
+impl<U : UnitSet> SyncActuatorAsync<U> for AsyncMotion<U> {
+    fn drive_rel(&mut self, rel_dist : U::Distance, speed : Factor) -> impl Future<Output = Result<(), ActuatorError<U>>> {
+        MotionFuture { link: &self.link, command: Command::Rel(rel_dist, speed), submitted: false }
+    }
+
+    fn drive_abs(&mut self, pos : U::Position, speed : Factor) -> impl Future<Output = Result<(), ActuatorError<U>>> {
+        MotionFuture { link: &self.link, command: Command::Abs(pos, speed), submitted: false }
+    }
+}
+
+/// The future of a movement started with an [AsyncMotion]
+///
+/// Dropping the future does not stop the movement, halt or interrupt the actuator with [AsyncMotion::state] instead. The next
+/// movement is not handed to the worker before the dropped one has been finished.
+#[must_use = "futures do nothing unless polled"]
+pub struct MotionFuture<'a, U : UnitSet> {
+    link : &'a Latch<Link<U>>,
+    command : Command<U>,
+    submitted : bool
+}
+
+// The future is never pinned structurally
+impl<U : UnitSet> Unpin for MotionFuture<'_, U> { }
+
+impl<U : UnitSet> Future for MotionFuture<'_, U> {
+    type Output = Result<(), ActuatorError<U>>;
+
+    fn poll(mut self : Pin<&mut Self>, cx : &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        this.link.with(|link| {
+            if this.submitted {
+                if let Some(result) = link.result.take() {
+                    return Poll::Ready(result);
+                }
+            } else if link.command.is_none() && !link.busy {
+                // Discard the result of a movement whose future has been dropped
+                link.result = None;
+                link.command = Some(this.command);
+                this.submitted = true;
+            }
+
+            link.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+    }
+}
+
+/// Executes the movements of an [AsyncMotion] with the blocking actuator, created with [AsyncMotion::split]
+pub struct MotionWorker<A : SyncActuatorBlocking<U>, U : UnitSet = Rotary> {
+    actuator : A,
+    link : Arc<Latch<Link<U>>>
+}
+
+impl<A : SyncActuatorBlocking<U>, U : UnitSet> MotionWorker<A, U> {
+    /// The actuator driven by the worker
+    pub fn actuator(&self) -> &A {
+        &self.actuator
+    }
+
+    /// The actuator driven by the worker, e.g. to change its configuration between two movements
+    pub fn actuator_mut(&mut self) -> &mut A {
+        &mut self.actuator
+    }
+
+    /// Returns `true` as long as the [AsyncMotion] handle exists
+    pub fn connected(&self) -> bool {
+        Arc::strong_count(&self.link) > 1
+    }
+
+    /// Executes the pending movement if there is one, blocking until it is finished and waking its future afterwards
+    ///
+    /// Returns `true` if a movement has been executed, call it in a loop on the thread driving the actuator
+    pub fn poll(&mut self) -> bool {
+        let Some(command) = self.link.with(|link| {
+            let command = link.command.take();
+            link.busy = command.is_some();
+            command
+        }) else {
+            return false;
+        };
+
+        let result = match command {
+            Command::Rel(rel_dist, speed) => self.actuator.drive_rel_blocking(rel_dist, speed),
+            Command::Abs(pos, speed) => self.actuator.drive_abs_blocking(pos, speed)
+        };
+
+        let waker = self.link.with(|link| {
+            link.result = Some(result);
+            link.busy = false;
+            link.waker.take()
+        });
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        true
+    }
+
+    /// Consumes the worker, returning the actuator
+    pub fn into_inner(self) -> A {
+        self.actuator
+    }
+}
@@ -0,0 +1,195 @@
+use alloc::collections::VecDeque;
+
+use embedded_hal::delay::DelayNs;
+use syunit::*;
+
+use crate::{ActuatorError, SyncActuatorBlocking};
+use crate::io::DigitalOutput;
+use crate::sync::{SegmentEvent, SegmentQueue};
+use crate::sync::stepper::{InterpolatedStepper, drive_rel_interpolated};
+
+// ####################
+// #    Tool lifts    #
+// ####################
+    /// A tool that can be lifted off and lowered onto the workpiece, e.g. a pen, a drag knife or the spindle of an engraver
+    pub trait ToolLift {
+        /// Lifts the tool (`up = true`) or lowers it (`up = false`), blocks until the movement is done
+        fn set_lifted(&mut self, up : bool) -> Result<(), ActuatorError>;
+    }
+
+    /// Lifts the tool with an auxiliary axis (Z-hop), driving it between two absolute positions
+    #[derive(Debug)]
+    pub struct AxisLift<A : SyncActuatorBlocking> {
+        /// The auxiliary axis
+        pub actuator : A,
+        /// The position of the axis with the tool lifted
+        pub pos_up : PositionRad,
+        /// The position of the axis with the tool lowered
+        pub pos_down : PositionRad,
+        /// The speed factor used for the hops
+        pub speed : Factor
+    }
+
+    impl<A : SyncActuatorBlocking> ToolLift for AxisLift<A> {
+        fn set_lifted(&mut self, up : bool) -> Result<(), ActuatorError> {
+            self.actuator.drive_abs_blocking(if up { self.pos_up } else { self.pos_down }, self.speed)
+        }
+    }
+
+    /// Lifts the tool with a digital output, e.g. the solenoid of a pen plotter
+    #[derive(Debug)]
+    pub struct OutputLift<O : DigitalOutput> {
+        /// The output controlling the lift
+        pub output : O,
+        /// The state of the output that lifts the tool
+        pub up_state : bool
+    }
+
+    impl<O : DigitalOutput> ToolLift for OutputLift<O> {
+        fn set_lifted(&mut self, up : bool) -> Result<(), ActuatorError> {
+            self.output.set(up == self.up_state).map_err(|_| ActuatorError::IOError)
+        }
+    }
+//
+
+/// A 2D movement of a plotter path, carrying a user tag
+/// 
+/// Stored as the tag of the segments in the [SegmentQueue] of a [ZHopPlotter], the distance of the queue segment being the 
+/// length of the movement
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathSegment<T> {
+    /// The relative distances of both axes
+    pub rel_dists : [Radians; 2],
+    /// Whether the tool is lowered (drawing) during the movement or lifted (travel move)
+    pub draw : bool,
+    /// The user tag reported back in the [SegmentEvent]
+    pub tag : T
+}
+
+/// Executes 2D paths with automatic tool lifts at the segment boundaries, the pattern used by every pen plotter or engraver
+///
+/// The path is stored in a [SegmentQueue]. Before each segment the tool is lowered or lifted if required, followed by the 
+/// `dwell` time, so the tool can settle. Travel moves are therefore executed with the tool lifted. Each executed segment is 
+/// reported with a [SegmentEvent] carrying the tag of the [PathSegment].
+pub struct ZHopPlotter<T, L : ToolLift, D : DelayNs> {
+    /// The tool lift used at the segment boundaries
+    pub lift : L,
+    /// The delay used for the dwell time
+    pub delay : D,
+    /// The time to wait after the tool has been lifted or lowered
+    pub dwell : Seconds,
+
+    path : SegmentQueue<PathSegment<T>>,
+    _lowered : bool
+}
+
+impl<T, L : ToolLift, D : DelayNs> ZHopPlotter<T, L, D> {
+    /// Creates a new plotter with an empty path, the tool is expected to be lifted
+    pub fn new(lift : L, delay : D, dwell : Seconds) -> Self {
+        Self {
+            lift,
+            delay,
+            dwell,
+
+            path: SegmentQueue::new(),
+            _lowered: false
+        }
+    }
+
+    /// Returns `true` if the tool is currently lowered
+    pub fn is_lowered(&self) -> bool {
+        self._lowered
+    }
+
+    // Path
+        /// Appends a drawing segment to the path, executed with the `speed` factor
+        pub fn draw(&mut self, rel_dists : [Radians; 2], speed : Factor, tag : T) {
+            self.push(PathSegment { rel_dists, draw: true, tag }, speed);
+        }
+
+        /// Appends a travel segment to the path, executed with the tool lifted and the `speed` factor
+        pub fn travel(&mut self, rel_dists : [Radians; 2], speed : Factor, tag : T) {
+            self.push(PathSegment { rel_dists, draw: false, tag }, speed);
+        }
+
+        fn push(&mut self, segment : PathSegment<T>, speed : Factor) {
+            let [ dist_a, dist_b ] = segment.rel_dists;
+            self.path.push(Radians(dist_a.0.hypot(dist_b.0)), speed, segment);
+        }
+
+        /// The queue storing the path
+        pub fn path(&self) -> &SegmentQueue<PathSegment<T>> {
+            &self.path
+        }
+
+        /// The amount of segments waiting
+        pub fn len(&self) -> usize {
+            self.path.len()
+        }
+
+        /// Returns `true` if no segments are waiting
+        pub fn is_empty(&self) -> bool {
+            self.path.is_empty()
+        }
+
+        /// Removes all segments waiting, returning them
+        pub fn clear(&mut self) -> VecDeque<PathSegment<T>> {
+            self.path.clear().into_iter().map(|segment| segment.tag).collect()
+        }
+    //
+
+    /// Lifts or lowers the tool if it is not in the required state already, waiting for the dwell time afterwards
+    pub fn set_lowered(&mut self, lowered : bool) -> Result<(), ActuatorError> {
+        Self::lower(&mut self.lift, &mut self.delay, self.dwell, &mut self._lowered, lowered)
+    }
+
+    fn lower(lift : &mut L, delay : &mut D, dwell : Seconds, state : &mut bool, lowered : bool) -> Result<(), ActuatorError> {
+        if lowered != *state {
+            lift.set_lifted(!lowered)?;
+            *state = lowered;
+
+            delay.delay_us((dwell.0 * 1_000_000.0) as u32);
+        }
+
+        Ok(())
+    }
+
+    /// Executes the next segment with the `axes`, returns `None` if the path is empty
+    pub fn run_next(&mut self, axes : [&mut dyn InterpolatedStepper; 2]) -> Option<SegmentEvent<T>> {
+        let Self { lift, delay, dwell, path, _lowered } = self;
+        let mut axes = axes;
+
+        let event = path.run_next_with(|segment| {
+            Self::lower(lift, delay, *dwell, _lowered, segment.tag.draw)?;
+            drive_rel_interpolated(&mut axes, &segment.tag.rel_dists, segment.speed)
+        })?;
+
+        Some(match event {
+            SegmentEvent::Completed(segment) => SegmentEvent::Completed(segment.tag),
+            SegmentEvent::Interrupted(segment, reason) => SegmentEvent::Interrupted(segment.tag, reason),
+            SegmentEvent::Failed(segment, err) => SegmentEvent::Failed(segment.tag, err),
+            SegmentEvent::Held(stop) => SegmentEvent::Held(stop)
+        })
+    }
+
+    /// Executes the whole path, reporting each segment with `on_event`, the tool is lifted at the end of the path
+    ///
+    /// Execution stops after the first segment that failed, the remaining segments stay in the path
+    pub fn run_all<F>(&mut self, axes : [&mut dyn InterpolatedStepper; 2], mut on_event : F) -> Result<(), ActuatorError>
+    where
+        F : FnMut(SegmentEvent<T>)
+    {
+        let [ axis_a, axis_b ] = axes;
+
+        while let Some(event) = self.run_next([ &mut *axis_a, &mut *axis_b ]) {
+            let completed = matches!(event, SegmentEvent::Completed(_));
+            on_event(event);
+
+            if !completed {
+                break;
+            }
+        }
+
+        self.set_lowered(false)
+    }
+}
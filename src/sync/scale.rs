@@ -0,0 +1,44 @@
+use alloc::sync::Arc;
+use core::sync::atomic::Ordering::Relaxed;
+
+use atomic_float::AtomicF32;
+use syunit::*;
+
+/// A shared time scale, slowing down the execution of all movements of the actuators it has been attached to, e.g. to verify 
+/// the first article of a new program at a quarter of the speed
+///
+/// The scale stretches the time between the steps, the path geometry is therefore preserved. As velocities scale linearly and 
+/// accelerations quadratically with the scale, all limits are kept at reduced speeds. The scale can be changed at any time, 
+/// actuators ramp moving axes to the new scale with their maximum acceleration.
+#[derive(Clone, Debug)]
+pub struct TimeScale {
+    _scale : Arc<AtomicF32>
+}
+
+impl TimeScale {
+    /// The smallest scale used, smaller scales (including zero) are raised to it, use a feed hold to stop movements
+    pub const MIN : f32 = 0.01;
+
+    /// Creates a new time scale with the given `scale`
+    pub fn new(scale : Factor) -> Self {
+        Self {
+            _scale: Arc::new(AtomicF32::new(scale.as_f32().max(Self::MIN)))
+        }
+    }
+
+    /// The current scale
+    pub fn get(&self) -> f32 {
+        self._scale.load(Relaxed)
+    }
+
+    /// Sets the scale, e.g. `Factor::new(0.25)` for a quarter of the speed
+    pub fn set(&self, scale : Factor) {
+        self._scale.store(scale.as_f32().max(Self::MIN), Relaxed);
+    }
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self::new(Factor::MAX)
+    }
+}
@@ -0,0 +1,283 @@
+use alloc::collections::VecDeque;
+
+use embedded_hal::delay::DelayNs;
+use syunit::*;
+
+use crate::{ActuatorError, InterruptReason, Interruptible, SyncActuatorBlocking};
+use crate::sync::clock::{LocalClock, SharedClock};
+
+/// A relative movement waiting in a [SegmentQueue], carrying a user tag
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment<T, U : UnitSet = Rotary> {
+    /// The relative distance to move
+    pub rel_dist : U::Distance,
+    /// The speed factor of the movement
+    pub speed : Factor,
+    /// The speed factor the segment is left with, blending it into the next segment without stopping, `Factor::MIN` to 
+    /// stop at the end of the segment, see [SegmentQueue::push_blended]
+    pub exit : Factor,
+    /// The time the segment has to be started at, given in the shared clock domain, see [SegmentQueue::run_next_synced]
+    pub start : Option<Seconds>,
+    /// The user tag reported back in the [SegmentEvent], e.g. a G-code line number or an order ID
+    pub tag : T
+}
+
+/// The exact point the execution of a [SegmentQueue] has been held at
+#[derive(Clone, Copy, Debug)]
+pub struct StopPoint<U : UnitSet = Rotary> {
+    /// The index of the held segment, counting all segments that have left the queue before
+    pub index : usize,
+    /// The distance travelled along the held segment, having the same sign as the distance of the segment
+    pub dist : U::Distance
+}
+
+impl<U : UnitSet> PartialEq for StopPoint<U> {
+    fn eq(&self, other : &Self) -> bool {
+        (self.index == other.index) && (self.dist == other.dist)
+    }
+}
+
+/// Events reported by a [SegmentQueue] once a segment has been executed
+#[derive(Clone, Debug)]
+pub enum SegmentEvent<T, U : UnitSet = Rotary> {
+    /// The segment has been completed
+    /// - 0 - `T`: The tag of the segment
+    Completed(T),
+    /// The segment has been interrupted by an interruptor
+    /// - 0 - `T`: The tag of the segment
+    /// - 1 - [InterruptReason]: The reason of the interrupt
+    Interrupted(T, InterruptReason),
+    /// The actuator failed to execute the segment
+    /// - 0 - `T`: The tag of the segment
+    /// - 1 - [ActuatorError]: The error returned by the actuator
+    Failed(T, ActuatorError<U>),
+    /// The segment has been halted (feed hold), it stays in the queue and is continued by the next execution
+    /// - 0 - [StopPoint]: The point the segment has been held at
+    Held(StopPoint<U>)
+}
+
+impl<T, U : UnitSet> SegmentEvent<T, U> {
+    /// The tag of the segment the event belongs to, `None` for held segments, as they stay in the queue
+    pub fn tag(&self) -> Option<&T> {
+        match self {
+            Self::Completed(tag) | Self::Interrupted(tag, _) | Self::Failed(tag, _) => Some(tag),
+            Self::Held(_) => None
+        }
+    }
+}
+
+/// A queue of tagged relative movements, executed one after another by an actuator
+///
+/// Every executed segment reports its tag back with a [SegmentEvent], so higher layers can correlate the completion of
+/// movements with their own bookkeeping.
+///
+/// # Feed hold
+///
+/// Halting the actuator with [SyncActuatorState::halt](crate::SyncActuatorState::halt) while a segment is executed decelerates
+/// it to a stop and holds the queue at the exact [StopPoint] reached. The held segment stays in the queue, the next execution
+/// continues it from the stop point, optionally after a [SegmentQueue::rewind].
+#[derive(Clone, Debug)]
+pub struct SegmentQueue<T, U : UnitSet = Rotary> {
+    segments : VecDeque<Segment<T, U>>,
+    _index : usize,
+    _stop : Option<StopPoint<U>>
+}
+
+impl<T, U : UnitSet> Default for SegmentQueue<T, U> {
+    fn default() -> Self {
+        Self { 
+            segments: VecDeque::new(),
+            _index: 0,
+            _stop: None
+        }
+    }
+}
+
+impl<T, U : UnitSet> SegmentQueue<T, U> {
+    /// Creates a new empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a segment to the end of the queue
+    pub fn push(&mut self, rel_dist : U::Distance, speed : Factor, tag : T) {
+        self.segments.push_back(Segment { rel_dist, speed, exit: Factor::MIN, start: None, tag });
+    }
+
+    /// Appends a segment to the end of the queue that is blended into the following segment, leaving it with the speed 
+    /// factor `exit` instead of stopping, e.g. the fast approach before a slow final segment
+    ///
+    /// The last segment of the queue always stops at its end. Actuators unable to blend movements stop between the segments, 
+    /// see [SyncActuatorBlocking::drive_rel_blocking_blended]
+    pub fn push_blended(&mut self, rel_dist : U::Distance, speed : Factor, exit : Factor, tag : T) {
+        self.segments.push_back(Segment { rel_dist, speed, exit, start: None, tag });
+    }
+
+    /// Appends a segment to the end of the queue that has to be started at the `start` time of the shared clock
+    pub fn push_at(&mut self, rel_dist : U::Distance, speed : Factor, start : Seconds, tag : T) {
+        self.segments.push_back(Segment { rel_dist, speed, exit: Factor::MIN, start: Some(start), tag });
+    }
+
+    /// The amount of segments waiting in the queue
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns `true` if no segments are waiting
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Removes all segments waiting in the queue, returning them, a held segment is returned with its full distance
+    pub fn clear(&mut self) -> VecDeque<Segment<T, U>> {
+        self._index += self.segments.len();
+        self._stop = None;
+
+        core::mem::take(&mut self.segments)
+    }
+
+    /// The index the next segment will be reported with in a [StopPoint]
+    pub fn index(&self) -> usize {
+        self._index
+    }
+
+    /// The point the queue has been held at, `None` if no segment is held
+    pub fn stop_point(&self) -> Option<StopPoint<U>> {
+        self._stop
+    }
+
+    /// Moves the `actuator` back along the held segment by the `overlap` distance, so the segment is continued slightly before
+    /// the point it has been held at, e.g. to avoid visible marks when lasering or milling
+    ///
+    /// The rewind is limited to the start of the held segment and uses its speed factor, returns the distance actually rewound. 
+    /// Does nothing if no segment is held, if the rewind itself is halted or interrupted the queue stays held at the point reached.
+    pub fn rewind<A>(&mut self, actuator : &mut A, overlap : U::Distance) -> Result<U::Distance, ActuatorError<U>>
+    where
+        A : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized
+    {
+        let (Some(mut stop), Some(segment)) = (self._stop, self.segments.front()) else {
+            return Ok(U::Distance::ZERO);
+        };
+
+        let dist : f32 = stop.dist.into();
+        let back = U::Distance::from(-dist.signum() * Into::<f32>::into(overlap).abs().min(dist.abs()));
+
+        let pos_start = actuator.pos();
+        let result = actuator.drive_rel_blocking(back, segment.speed);
+        actuator.intr_reason();
+
+        let rewound = actuator.pos() - pos_start;
+        stop.dist += rewound;
+        self._stop = Some(stop);
+
+        result.map(|_| -rewound)
+    }
+
+    /// Executes the next segment with the given `actuator`, returns `None` if the queue is empty
+    ///
+    /// Start times of the segments are ignored, use [SegmentQueue::run_next_synced] to execute scheduled segments
+    pub fn run_next<A>(&mut self, actuator : &mut A) -> Option<SegmentEvent<T, U>>
+    where
+        A : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized
+    {
+        self.execute(actuator)
+    }
+
+    /// Executes the next segment with the given `actuator`, waiting for its start time on the `clock` first
+    ///
+    /// Scheduled segments that cannot be started within the maximum skew of the clock fail with 
+    /// [ActuatorError::ClockSkewExceeded] or [ActuatorError::StartTimeMissed], segments without a start time and held segments 
+    /// are executed right away
+    pub fn run_next_synced<A, C, D>(&mut self, actuator : &mut A, clock : &mut SharedClock<C, D>) -> Option<SegmentEvent<T, U>>
+    where
+        A : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized,
+        C : LocalClock,
+        D : DelayNs
+    {
+        let start = self.segments.front()?.start;
+
+        if let (Some(start), None) = (start, self._stop) {
+            if let Err(err) = clock.wait_until(start) {
+                return self.pop().map(|segment| SegmentEvent::Failed(segment.tag, err));
+            }
+        }
+
+        self.execute(actuator)
+    }
+
+    /// Executes the next segment with the given `exec` function instead of a single actuator, returns `None` if the queue is 
+    /// empty
+    ///
+    /// Used for segments driving multiple actuators at once, e.g. with the distances of all axes carried in the tag, see 
+    /// [ZHopPlotter](crate::sync::plotter::ZHopPlotter). The execution cannot be held, the segment leaves the queue in any case
+    pub fn run_next_with<F>(&mut self, exec : F) -> Option<SegmentEvent<T, U>>
+    where
+        F : FnOnce(&Segment<T, U>) -> Result<(), ActuatorError<U>>
+    {
+        let result = exec(self.segments.front()?);
+        let segment = self.pop()?;
+
+        Some(match result {
+            Ok(()) => SegmentEvent::Completed(segment.tag),
+            Err(err) => SegmentEvent::Failed(segment.tag, err)
+        })
+    }
+
+    /// Removes the front segment, the next segment starts from its beginning
+    fn pop(&mut self) -> Option<Segment<T, U>> {
+        let segment = self.segments.pop_front()?;
+
+        self._index += 1;
+        self._stop = None;
+
+        Some(segment)
+    }
+
+    /// Executes the front segment, continuing it from the stop point if it has been held
+    fn execute<A>(&mut self, actuator : &mut A) -> Option<SegmentEvent<T, U>>
+    where
+        A : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized
+    {
+        let segment = self.segments.front()?;
+        let travelled = self._stop.map(|stop| stop.dist).unwrap_or(U::Distance::ZERO);
+
+        // The last segment has nothing to blend into
+        let exit = if self.segments.len() > 1 { segment.exit } else { Factor::MIN };
+
+        let pos_start = actuator.pos();
+        let result = actuator.drive_rel_blocking_blended(segment.rel_dist - travelled, segment.speed, exit);
+
+        Some(match result {
+            Ok(()) => match actuator.intr_reason() {
+                Some(InterruptReason::Halted) => {
+                    let stop = StopPoint { index: self._index, dist: travelled + (actuator.pos() - pos_start) };
+                    self._stop = Some(stop);
+
+                    SegmentEvent::Held(stop)
+                },
+                Some(reason) => SegmentEvent::Interrupted(self.pop()?.tag, reason),
+                None => SegmentEvent::Completed(self.pop()?.tag)
+            },
+            Err(err) => SegmentEvent::Failed(self.pop()?.tag, err)
+        })
+    }
+
+    /// Executes all segments, reporting each one with `on_event`
+    ///
+    /// Execution stops after the first segment that has not been completed, the remaining segments stay in the queue. Call it 
+    /// again to resume after a feed hold
+    pub fn run_all<A, F>(&mut self, actuator : &mut A, mut on_event : F)
+    where
+        A : SyncActuatorBlocking<U> + Interruptible<U> + ?Sized,
+        F : FnMut(SegmentEvent<T, U>)
+    {
+        while let Some(event) = self.run_next(actuator) {
+            let completed = matches!(event, SegmentEvent::Completed(_));
+            on_event(event);
+
+            if !completed {
+                break;
+            }
+        }
+    }
+}
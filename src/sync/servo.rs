@@ -1,7 +1,99 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use atomic_float::AtomicF32;
+use embedded_hal::delay::DelayNs;
 use embedded_hal::pwm::SetDutyCycle;
 use syunit::*;
 
+use crate::{ActuatorError, LimitBehavior, MotionMode, SyncActuator, SyncActuatorBlocking, SyncActuatorState};
 use crate::data::servo::ServoConst;
+use crate::parent::{LayerPos, PositionLayers};
+use crate::report::Capabilities;
+
+/// The state of a [MiniServo], shared with other threads
+#[derive(Debug)]
+pub struct ServoState {
+    _pos : AtomicF32,
+    _target : AtomicF32,
+    _active : AtomicBool,
+    _moving : AtomicBool,
+    _stopping : AtomicBool,
+
+    _halt : AtomicBool,
+    _interrupt : AtomicBool
+}
+
+impl ServoState {
+    /// The target of the ramp currently executed, `None` if the servo is not ramping
+    pub fn target(&self) -> Option<PositionRad> {
+        Some(PositionRad(self._target.load(Relaxed))).filter(|target| !target.0.is_nan())
+    }
+
+    /// The distance left to the target of the ramp currently executed, zero if the servo is not ramping
+    pub fn remaining(&self) -> Radians {
+        self.target().map_or(Radians::ZERO, |target| target - self.pos())
+    }
+}
+
+impl Default for ServoState {
+    fn default() -> Self {
+        Self {
+            _pos: AtomicF32::new(0.0),
+            _target: AtomicF32::new(f32::NAN),
+            _active: AtomicBool::new(false),
+            _moving: AtomicBool::new(false),
+            _stopping: AtomicBool::new(false),
+
+            _halt: AtomicBool::new(false),
+            _interrupt: AtomicBool::new(false)
+        }
+    }
+}
+
+impl SyncActuatorState for ServoState {
+    fn pos(&self) -> PositionRad {
+        PositionRad(self._pos.load(Relaxed))
+    }
+
+    // Only the ramps are tracked, the servo approaches each commanded position on its own
+    fn moving(&self) -> bool {
+        self._moving.load(Relaxed)
+    }
+
+    fn motion_mode(&self) -> MotionMode {
+        if self._stopping.load(Relaxed) {
+            MotionMode::Stopping
+        } else if self._moving.load(Relaxed) {
+            MotionMode::FixedDistance
+        } else if self._active.load(Relaxed) {
+            MotionMode::Holding
+        } else {
+            MotionMode::Inactive
+        }
+    }
+
+    // Only ramps can be halted or interrupted, commanded positions are approached by the servo itself
+    fn halt(&self) {
+        self._halt.store(true, Relaxed);
+    }
+
+    fn interrupt(&self) {
+        self._interrupt.store(true, Relaxed);
+    }
+}
+
+/// Placeholder for a servo without a delay attached, see [MiniServo::with_delay]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns : u32) { }
+}
 
 /// A basic servo motor with absolute position being controlled by a PWM signal
 /// 
@@ -9,12 +101,47 @@ use crate::data::servo::ServoConst;
 /// 
 /// In order to function correctly the servo has to be set up first!
 /// - No pins will be occupied until the setup function is called
+///
+/// # Calibration
+///
+/// The pulse widths at the endpoints and the angle between them differ from servo to servo, see
+/// [MiniServo::calibrate] and [ServoConst::with_calibration].
+///
+/// # Ramping
+///
+/// Without any further setup, movements are commanded instantly and the servo approaches the position with its own
+/// velocity. Once a delay has been attached with [MiniServo::with_delay], the commanded position is ramped towards the
+/// target in software, updating the pulse every PWM period. The ramps respect the `speed` factor and the velocity and
+/// acceleration limits of the servo. Ramps can also be driven without blocking, see [MiniServo::set_target] and
+/// [MiniServo::poll].
+///
+/// # Actuator traits
+///
+/// The servo implements [SyncActuator] and [SyncActuatorBlocking], so it can be mixed with other actuators. As the movement
+/// is executed by the servo itself, only the capabilities that are physically meaningful are supported:
+/// - Continuous movements drive the servo towards the limit (or endpoint) in the given direction
+/// - Jolts cannot be limited at all
 #[derive(Debug)]
-pub struct MiniServo<P : SetDutyCycle> {
-    /// The absolute position of the servo motor
-    pos : PositionRad,
+pub struct MiniServo<P : SetDutyCycle, D : DelayNs = NoDelay> {
     /// The constants of the servo motor (depending on type)
     _consts : ServoConst,
+    /// The state of the servo, storing the absolute position
+    _state : Arc<ServoState>,
+    /// Offset between the reported and the physical position, see [SyncActuator::overwrite_abs_pos]
+    _offset : Radians,
+
+    // Ramping
+    _delay : Option<D>,
+    _velocity : RadPerSecond,
+    _velocity_ramp : RadPerSecond,
+
+    // Limits
+    _velocity_max : Option<RadPerSecond>,
+    _velocity_min : Option<RadPerSecond>,
+    _acceleration_max : Option<RadPerSecond2>,
+    _deceleration_max : Option<RadPerSecond2>,
+    _limit_min : Option<PositionRad>,
+    _limit_max : Option<PositionRad>,
 
     /// The PWM output signal
     pwm : P
@@ -27,19 +154,84 @@ impl<P : SetDutyCycle> MiniServo<P> {
     /// 
     /// Before any use of movement functions you must call `start()` or `setup()` in order to set up all the pins
     pub fn new(consts : ServoConst, pwm : P) -> Self {
+        let state = ServoState::default();
+        state._pos.store(consts.default_pos().0, Relaxed);
+
         Self {
-            pos: consts.default_pos(),
             _consts: consts,
+            _state: Arc::new(state),
+            _offset: Radians::ZERO,
+
+            _delay: None,
+            _velocity: RadPerSecond::ZERO,
+            _velocity_ramp: RadPerSecond::ZERO,
+
+            _velocity_max: None,
+            _velocity_min: None,
+            _acceleration_max: None,
+            _deceleration_max: None,
+            _limit_min: None,
+            _limit_max: None,
 
             pwm
         }
     }
  
+    /// Attaches the `delay` used to wait between the pulse updates of blocking ramps, enabling the software ramping
+    pub fn with_delay<D : DelayNs>(self, delay : D) -> MiniServo<P, D> {
+        MiniServo {
+            _consts: self._consts,
+            _state: self._state,
+            _offset: self._offset,
+
+            _delay: Some(delay),
+            _velocity: self._velocity,
+            _velocity_ramp: self._velocity_ramp,
+
+            _velocity_max: self._velocity_max,
+            _velocity_min: self._velocity_min,
+            _acceleration_max: self._acceleration_max,
+            _deceleration_max: self._deceleration_max,
+            _limit_min: self._limit_min,
+            _limit_max: self._limit_max,
+
+            pwm: self.pwm
+        }
+    }
+}
+
+impl<P : SetDutyCycle, D : DelayNs> MiniServo<P, D> {
     /// Returns a reference to the `ServoConst` of the driver
     pub fn consts(&self) -> &ServoConst {
         &self._consts
     }
 
+    /// The shared state of the servo, also providing the progress of the ramps, see [ServoState::remaining]
+    pub fn servo_state(&self) -> Arc<ServoState> {
+        self._state.clone()
+    }
+
+    /// Returns a reference to the PWM output of the driver
+    pub fn pwm(&self) -> &P {
+        &self.pwm
+    }
+
+    /// Calibrates the servo with the pulse widths `pwm_min` and `pwm_max` measured at its endpoints and the angle
+    /// `position_max` between them, see [ServoConst::with_calibration] for the panics
+    ///
+    /// The servo keeps its relative position between the endpoints
+    pub fn calibrate(&mut self, pwm_min : Seconds, pwm_max : Seconds, position_max : PositionRad) -> Result<(), P::Error> {
+        let factor = self.factor();
+        self._consts = self._consts.clone().with_calibration(pwm_min, pwm_max, position_max);
+
+        if self._state._active.load(Relaxed) {
+            self.drive_factor_pos(factor)
+        } else {
+            self._state._pos.store((self._consts.position_max * factor.as_f32() + self._offset).0, Relaxed);
+            Ok(())
+        }
+    }
+
     // Drop
         /// Start the PWM signal and moves the servo to it's default position
         pub fn start(&mut self) -> Result<(), P::Error> {
@@ -48,14 +240,16 @@ impl<P : SetDutyCycle> MiniServo<P> {
 
         /// Stops the servo driver and the PWM signal, can be started again with `start()` if desired
         pub fn stop(&mut self) -> Result<(), P::Error> {
+            self.end_ramp();
+            self._state._active.store(false, Relaxed);
             self.pwm.set_duty_cycle_fully_off()
         }
     //  
 
     // Absolute position
-        /// Get the *aboslute* angle of the servo 
+        /// Get the *aboslute* angle of the servo, equal to [SyncActuator::pos]
         pub fn pos(&self) -> PositionRad {
-            self.pos
+            self._state.pos()
         }
 
         /// Set the absolute angle of the servo. Causes the servo to drive to this angle
@@ -64,21 +258,32 @@ impl<P : SetDutyCycle> MiniServo<P> {
         /// 
         /// Panics if the given 
         pub fn drive_abs(&mut self, pos : PositionRad) -> Result<(), P::Error> {
-            self.drive_factor_pos(Factor::try_new(pos / self._consts.position_max)
+            self.drive_factor_pos(Factor::try_new((pos - self._offset) / self._consts.position_max)
                 .expect("The given position is either invalid or out of range!"))
         }
     // 
     
     // Factor
-        /// Get the duty-cycle-percent of the motor (values `0.0` to `1.0`)
+        /// The position of the servo as factor of the range between its endpoints (values `0.0` to `1.0`)
         pub fn factor(&self) -> Factor {
             // Safe to use, as all operations altering the Factor are internal
-            unsafe { Factor::new_unchecked(self.pos / self._consts.position_max) }
+            unsafe { Factor::new_unchecked((self.pos() - self._offset) / self._consts.position_max) }
+        }
+
+        /// The pulse width currently commanded
+        pub fn pulse(&self) -> Seconds {
+            self._consts.pulse_for_factor(self.factor())
         }
 
-        /// Set the duty-cycle percent of the servo (value `0.0` to `1.0`). Causes the servo to adapt its position
+        /// Moves the servo to the `factor` of the range between its endpoints (value `0.0` to `1.0`), the pulse width is 
+        /// derived from the calibration, see [ServoConst::pulse_for_factor]
         pub fn drive_factor_pos(&mut self, factor : Factor) -> Result<(), P::Error> {
-            self.pwm.set_duty_cycle(factor.get_duty_for(self.pwm.max_duty_cycle()))
+            let duty = self._consts.duty_for_factor(factor);
+            self.pwm.set_duty_cycle(duty.get_duty_for(self.pwm.max_duty_cycle()))?;
+
+            self._state._pos.store((self._consts.position_max * factor.as_f32() + self._offset).0, Relaxed);
+            self._state._active.store(true, Relaxed);
+            Ok(())
         }
     // 
 
@@ -97,5 +302,360 @@ impl<P : SetDutyCycle> MiniServo<P> {
         pub fn goto_endpoint_max(&mut self) -> Result<(), P::Error> {
             self.drive_factor_pos(Factor::MAX)
         }
+
+        /// The reachable position range of the servo, being its endpoints restricted by the limits set
+        pub fn pos_range(&self) -> (PositionRad, PositionRad) {
+            let min = PositionRad::ZERO + self._offset;
+            let max = self._consts.position_max + self._offset;
+
+            (
+                self._limit_min.map(|limit| limit.max(min)).unwrap_or(min),
+                self._limit_max.map(|limit| limit.min(max)).unwrap_or(max)
+            )
+        }
+
+        /// Commands the servo directly to the `pos`, clamped to the reachable range
+        fn command_pos(&mut self, pos : PositionRad) -> Result<(), ActuatorError> {
+            let (min, max) = self.pos_range();
+            let pos = pos.max(min).min(max);
+
+            self.drive_factor_pos(Factor::new(((pos - self._offset) / self._consts.position_max).clamp(0.0, 1.0)))
+                .map_err(|_| ActuatorError::IOError)
+        }
+    //
+
+    // Ramping
+        /// The velocity ramps are driven with, reduced by the `speed` factor
+        fn velocity_ramp(&self, speed : Factor) -> RadPerSecond {
+            let velocity = self._velocity_max.map_or(self._consts.velocity_max, |max| max.min(self._consts.velocity_max))
+                * speed.as_f32();
+
+            self._velocity_min.map_or(velocity, |min| velocity.max(min))
+        }
+
+        fn end_ramp(&mut self) {
+            self._velocity = RadPerSecond::ZERO;
+            self._state._target.store(f32::NAN, Relaxed);
+            self._state._moving.store(false, Relaxed);
+            self._state._stopping.store(false, Relaxed);
+        }
+
+        /// Starts a ramp towards the `target` (clamped to the reachable range) with the velocity reduced by the `speed`
+        /// factor, the ramp is executed by calling [MiniServo::poll]
+        pub fn set_target(&mut self, target : PositionRad, speed : Factor) {
+            let (min, max) = self.pos_range();
+
+            // Discard requests from before the ramp
+            self._state._halt.store(false, Relaxed);
+            self._state._interrupt.store(false, Relaxed);
+
+            self._velocity_ramp = self.velocity_ramp(speed);
+            self._state._target.store(target.max(min).min(max).0, Relaxed);
+            self._state._moving.store(true, Relaxed);
+            self._state._stopping.store(false, Relaxed);
+        }
+
+        /// Advances the current ramp by the `elapsed` time, updating the pulse of the servo
+        ///
+        /// Returns `true` as long as the ramp has not reached its target
+        pub fn poll(&mut self, elapsed : Seconds) -> Result<bool, ActuatorError> {
+            let Some(mut target) = self._state.target() else {
+                return Ok(false);
+            };
+
+            let pos = self.pos();
+            let decel = self._deceleration_max.or(self._acceleration_max).unwrap_or(RadPerSecond2::INFINITY);
+
+            // Ramps without velocity (e.g. a zero speed factor) keep the servo where it is
+            if self._state._interrupt.swap(false, Relaxed) || !self._velocity_ramp.0.is_normal() {
+                self.end_ramp();
+                return Ok(false);
+            }
+
+            // A halt moves the target to the closest position the ramp can stop at
+            if self._state._halt.swap(false, Relaxed) {
+                let stop_dist = Radians(self._velocity.0 * self._velocity.0.abs() / (2.0 * decel.0));
+                let (min, max) = self.pos_range();
+
+                target = (pos + stop_dist).max(min).min(max);
+                self._state._target.store(target.0, Relaxed);
+                self._state._stopping.store(true, Relaxed);
+            }
+
+            let dist = target - pos;
+            let sign = if dist.is_sign_negative() { -1.0 } else { 1.0 };
+
+            // The velocity towards the target and the highest velocity allowing to stop in time after the next update
+            let velocity = self._velocity.0 * sign;
+            let velocity_brake = if decel.0.is_finite() {
+                let half_step = decel.0 * elapsed.0 / 2.0;
+                (half_step * half_step + 2.0 * decel.0 * dist.abs().0).sqrt() - half_step
+            } else {
+                f32::INFINITY
+            };
+            let velocity_allowed = self._velocity_ramp.0.min(velocity_brake);
+
+            let velocity_next = if velocity < velocity_allowed {
+                let accel = self._acceleration_max.unwrap_or(RadPerSecond2::INFINITY);
+                (velocity + accel.0 * elapsed.0).min(velocity_allowed)
+            } else {
+                (velocity - decel.0 * elapsed.0).max(velocity_allowed)
+            };
+
+            let step = velocity_next * elapsed.0;
+
+            if step >= dist.abs().0 {
+                self.command_pos(target)?;
+                self.end_ramp();
+                return Ok(false);
+            }
+
+            self._velocity = RadPerSecond(velocity_next * sign);
+            self.command_pos(pos + Radians(step * sign))?;
+            Ok(true)
+        }
+
+        /// Drives the servo to the `target`, ramping if a delay has been attached
+        fn approach(&mut self, target : PositionRad, speed : Factor) -> Result<(), ActuatorError> {
+            if self._delay.is_none() {
+                return self.command_pos(target);
+            }
+
+            let period = self._consts.period_time();
+            let period_ns = (period.0 * 1_000_000_000.0) as u32;
+
+            self.set_target(target, speed);
+
+            while self.poll(period)? {
+                if let Some(delay) = self._delay.as_mut() {
+                    delay.delay_ns(period_ns);
+                }
+            }
+
+            Ok(())
+        }
     //
-}
\ No newline at end of file
+}
+
+// Actuator traits
+    impl<P : SetDutyCycle, D : DelayNs> Capabilities for MiniServo<P, D> {
+        // Continuous movements are executed as movements to the limits
+        fn motion_modes(&self) -> Vec<MotionMode> {
+            vec![ MotionMode::Inactive, MotionMode::Holding, MotionMode::FixedDistance, MotionMode::Stopping ]
+        }
+    }
+
+    impl<P : SetDutyCycle, D : DelayNs> PositionLayers for MiniServo<P, D> {
+        fn layer_count(&self) -> usize {
+            1
+        }
+
+        fn layer_name(&self, index : usize) -> Option<&'static str> {
+            (index == 0).then_some("servo")
+        }
+
+        fn layer_pos(&self, index : usize) -> Option<LayerPos> {
+            (index == 0).then(|| LayerPos::Rotary(self.pos()))
+        }
+    }
+
+    impl<P : SetDutyCycle, D : DelayNs> SyncActuator for MiniServo<P, D> {
+        // Position
+            fn pos(&self) -> PositionRad {
+                self._state.pos()
+            }
+
+            fn overwrite_abs_pos(&mut self, pos : PositionRad) {
+                self._offset += pos - self.pos();
+                self._state._pos.store(pos.0, Relaxed);
+            }
+        //
+
+        // Velocity
+            fn velocity_max(&self) -> Option<RadPerSecond> {
+                self._velocity_max
+            }
+
+            fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+                if let Some(velocity) = velocity_opt {
+                    if !velocity.is_normal() {
+                        return Err(ActuatorError::InvalidVelocity(velocity));
+                    }
+
+                    if let Some(min) = self._velocity_min.filter(|min| velocity.abs() < *min) {
+                        return Err(ActuatorError::VelocityTooLow(velocity.abs(), min));
+                    }
+                }
+
+                self._velocity_max = velocity_opt.map(|velocity| velocity.abs());
+                Ok(())
+            }
+
+            fn velocity_min(&self) -> Option<RadPerSecond> {
+                self._velocity_min
+            }
+
+            fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+                if let Some(velocity) = velocity_opt {
+                    if !velocity.is_normal() {
+                        return Err(ActuatorError::InvalidVelocity(velocity));
+                    }
+
+                    if let Some(max) = self._velocity_max.filter(|max| velocity.abs() > *max) {
+                        return Err(ActuatorError::VelocityTooHigh(velocity.abs(), max));
+                    }
+                }
+
+                self._velocity_min = velocity_opt.map(|velocity| velocity.abs());
+                Ok(())
+            }
+        //
+
+        // Acceleration & Deceleration, applied to the software ramps
+            fn acceleration_max(&self) -> Option<RadPerSecond2> {
+                self._acceleration_max
+            }
+
+            fn set_acceleration_max(&mut self, acceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+                if let Some(acceleration) = acceleration_opt.filter(|acceleration| !acceleration.is_normal()) {
+                    return Err(ActuatorError::InvalidAcceleration(acceleration));
+                }
+
+                self._acceleration_max = acceleration_opt.map(|acceleration| acceleration.abs());
+                Ok(())
+            }
+
+            fn deceleration_max(&self) -> Option<RadPerSecond2> {
+                self._deceleration_max
+            }
+
+            fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+                if let Some(deceleration) = deceleration_opt.filter(|deceleration| !deceleration.is_normal()) {
+                    return Err(ActuatorError::InvalidAcceleration(deceleration));
+                }
+
+                self._deceleration_max = deceleration_opt.map(|deceleration| deceleration.abs());
+                Ok(())
+            }
+        //
+
+        // Jolt, cannot be limited as the servo regulates itself
+            fn jolt_max(&self) -> Option<RadPerSecond3> {
+                None
+            }
+
+            fn set_jolt_max(&mut self, jolt_opt : Option<RadPerSecond3>) -> Result<(), ActuatorError> {
+                match jolt_opt {
+                    Some(jolt) => Err(ActuatorError::InvalidJolt(jolt)),
+                    None => Ok(())
+                }
+            }
+        //
+
+        // Position limits
+            fn limit_min(&self) -> Option<PositionRad> {
+                self._limit_min
+            }
+
+            fn limit_max(&self) -> Option<PositionRad> {
+                self._limit_max
+            }
+
+            fn resolve_pos_limits_for_abs_pos(&self, pos : PositionRad) -> Radians {
+                match (self._limit_min, self._limit_max) {
+                    (None, None) => Radians::NAN,
+                    (Some(min), _) if pos < min => pos - min,
+                    (_, Some(max)) if pos > max => pos - max,
+                    _ => Radians::ZERO
+                }
+            }
+
+            fn set_endpos(&mut self, overwrite_abs_pos : PositionRad) {
+                // The servo has no movement direction, the endpoint is set on the side of the current position
+                let upper = (self.pos() - self._offset) > self._consts.default_pos();
+                self.overwrite_abs_pos(overwrite_abs_pos);
+
+                self.set_pos_limits(
+                    if upper { None } else { Some(overwrite_abs_pos) },
+                    if upper { Some(overwrite_abs_pos) } else { None }
+                )
+            }
+
+            fn set_pos_limits(&mut self, min : Option<PositionRad>, max : Option<PositionRad>) {
+                if let Some(min) = min {
+                    self._limit_min = Some(min);
+                }
+
+                if let Some(max) = max {
+                    self._limit_max = Some(max);
+                }
+            }
+
+            fn overwrite_pos_limits(&mut self, min : Option<PositionRad>, max : Option<PositionRad>) {
+                self._limit_min = min;
+                self._limit_max = max;
+            }
+
+            // The targets are always clamped to the limits
+            fn limit_behavior(&self) -> LimitBehavior {
+                LimitBehavior::DecelerateAndClamp
+            }
+        //
+    }
+
+    impl<P : SetDutyCycle, D : DelayNs> SyncActuatorBlocking for MiniServo<P, D> {
+        // State
+            fn state(&self) -> &dyn SyncActuatorState {
+                self._state.as_ref()
+            }
+
+            fn clone_state(&self) -> Arc<dyn SyncActuatorState> {
+                self._state.clone()
+            }
+        //
+
+        /// Moves the servo by `rel_dist`, the target is clamped to the reachable range of the servo
+        fn drive_rel_blocking(&mut self, rel_dist : Radians, speed : Factor) -> Result<(), ActuatorError> {
+            if !rel_dist.is_finite() {
+                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+            }
+
+            let target = self.pos() + rel_dist;
+            self.approach(target, speed)
+        }
+
+        fn drive_rel_blocking_with<F>(&mut self, rel_dist : Radians, speed : Factor, observer : F) -> Result<(), ActuatorError> 
+        where
+            F : FnOnce(Arc<dyn SyncActuatorState + Send + Sync>)
+        {
+            observer(self._state.clone());
+            self.drive_rel_blocking(rel_dist, speed)
+        }
+
+        /// Moves the servo to the limit (or endpoint) in the given `direction`
+        fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError> {
+            let (min, max) = self.pos_range();
+            let target = if direction.as_bool() { max } else { min };
+
+            self.approach(target, speed)
+        }
+
+        /// Moves the servo to the limit (or endpoint) in the direction of the `speed`, returns an error if the speed
+        /// exceeds the maximum velocity of the servo
+        fn drive_speed(&mut self, speed : RadPerSecond) -> Result<(), ActuatorError> {
+            let velocity_max = self._velocity_max.unwrap_or(RadPerSecond::INFINITY).min(self._consts.velocity_max);
+
+            if speed.abs() > velocity_max {
+                return Err(ActuatorError::VelocityTooHigh(speed.abs(), velocity_max));
+            }
+
+            // Without ramping the servo approaches the target with its own velocity, slower movements cannot be commanded
+            if let Some(min) = self._velocity_min.filter(|min| speed.abs() < *min) {
+                return Err(ActuatorError::VelocityTooLow(speed.abs(), min));
+            }
+
+            let factor = Factor::try_new(speed.abs() / velocity_max).unwrap_or(Factor::MAX);
+            self.drive_factor(factor, speed.get_direction())
+        }
+    }
+//
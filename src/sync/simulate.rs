@@ -0,0 +1,57 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::sync::Arc;
+use syunit::*;
+
+/// A machine-level switch to dry-run programs, shared by all actuators it is attached to
+///
+/// While the switch is active, all movements run through the full planning, queueing and state machinery, but the 
+/// controllers of the actuators are not touched at all: No step pulses, direction changes, enable or current changes are 
+/// emitted. The timing of the movements is reproduced with the delay function of the switch, see 
+/// [SimulationSwitch::with_delay]. The state of the actuators reports the simulated execution, see 
+/// [SyncActuatorState::simulated](crate::SyncActuatorState::simulated).
+///
+/// The switch is evaluated once at the start of every movement, changing it affects the next movement. Actuators restore 
+/// their real position before the first movement after the simulation.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationSwitch {
+    _active : Arc<AtomicBool>,
+    delay : Option<fn(Seconds)>
+}
+
+impl SimulationSwitch {
+    /// Creates a new switch with the given state, simulated movements are executed instantly
+    pub fn new(active : bool) -> Self {
+        Self {
+            _active: Arc::new(AtomicBool::new(active)),
+            delay: None
+        }
+    }
+
+    /// Creates a new switch with the given state, calling the `delay` function with the duration of every simulated step 
+    /// to run the movements in real time
+    pub fn with_delay(active : bool, delay : fn(Seconds)) -> Self {
+        Self {
+            _active: Arc::new(AtomicBool::new(active)),
+            delay: Some(delay)
+        }
+    }
+
+    /// Returns `true` if movements are simulated
+    pub fn is_active(&self) -> bool {
+        self._active.load(Relaxed)
+    }
+
+    /// Enables or disables the simulation for all following movements
+    pub fn set(&self, active : bool) {
+        self._active.store(active, Relaxed)
+    }
+
+    /// Waits for the given `time` in place of a controller, see [SimulationSwitch::with_delay]
+    pub fn wait(&self, time : Seconds) {
+        if let Some(delay) = self.delay {
+            delay(time);
+        }
+    }
+}
@@ -1,30 +1,76 @@
-use core::sync::atomic::AtomicBool;
+#[cfg(feature = "alloc")]
+use core::sync::atomic::{AtomicBool, AtomicU8};
+#[cfg(feature = "alloc")]
 use core::sync::atomic::Ordering::Relaxed;
 
+#[cfg(feature = "alloc")]
 use atomic_float::AtomicF32;
 use syunit::*;
 
-use crate::{ActuatorError, SyncActuatorState, SyncActuator};
+#[cfg(feature = "alloc")]
+use crate::{ActuatorError, MotionMode, SyncActuatorState, SyncActuator};
+#[cfg(feature = "alloc")]
 use crate::data::MicroSteps;
+#[cfg(feature = "alloc")]
+use crate::math::{AtomicFloat, Float, precise, reduced};
 
 // ####################
 // #    SUBMODULES    #
 // ####################
     #[doc = include_str!("../../documentation/sync/stepper/builder.md")]
     pub mod builder;
-    pub use builder::{DriveMode, StepperBuilder, StartStopBuilder, ComplexBuilder, SimpleStepperBuilder, AdvancedStepperBuilder};
+    pub use builder::{profile_hash, CommandPolicy, DriveMode, StepperBuilder, StartStopBuilder, SimpleStepperBuilder, AdvancedStepperBuilder, 
+        VelocityConstraint};
+    #[cfg(feature = "alloc")]
+    pub use builder::{CommandQueue, TrapezoidalBuilder};
+    #[cfg(feature = "complex-builder")]
+    pub use builder::{ComplexBuilder, LoadQuantization};
 
     mod ctrl;
-    pub use ctrl::StepperController;
+    pub use ctrl::{PulseController, PulseGenerator, StepperController};
 
+    #[cfg(feature = "alloc")]
+    mod group;
+    #[cfg(feature = "alloc")]
+    pub use group::{set_config_all, ConfigurableStepper};
+
+    #[cfg(feature = "alloc")]
+    mod interpolate;
+    #[cfg(feature = "alloc")]
+    pub use interpolate::{CompareAction, CompareCondition, GroupStopPolicy, InterpolatedStepper, PositionCompare, drive_rel_interpolated, 
+        drive_rel_interpolated_compare, drive_rel_interpolated_with, sync_speed_factors};
+
+    mod minimal;
+    pub use minimal::MinimalStepper;
+
+    #[cfg(feature = "alloc")]
+    mod stats;
+    #[cfg(feature = "alloc")]
+    pub use stats::{FaultKind, FaultRecord, StepperStats, STATS_FAULT_HISTORY};
+
+    #[cfg(feature = "alloc")]
+    mod stream;
+    #[cfg(feature = "alloc")]
+    pub use stream::{node_stream, StreamProducer, StreamConsumer, StreamMetrics};
+
+    #[cfg(feature = "alloc")]
     mod motor;
-    pub use motor::StepperMotor;
+    #[cfg(feature = "alloc")]
+    pub use motor::{InPositionCheck, StepperMotor};
+
+    #[cfg(feature = "alloc")]
+    mod sim;
+    #[cfg(feature = "alloc")]
+    pub use sim::{Disturbance, DisturbanceProfile, SimulatedAxis, SimulatedController, Stepper, TrapezoidalStepper};
+    #[cfg(feature = "complex-builder")]
+    pub use sim::ComplexStepper;
 // 
 
 // ################################
 // #    StepperActuator-Traits    #
 // ################################
     /// A component based on a stepper motor
+    #[cfg(feature = "alloc")]
     pub trait StepperActuator<U : UnitSet = Rotary> : SyncActuator<U> {
         // Microstepping
             /// The amount of microsteps in a full step
@@ -38,50 +84,233 @@ use crate::data::MicroSteps;
             /// The angular distance of a step considering microstepping
             fn step_dist(&self) -> U::Distance;
         // 
+
+        // Hold policy
+            /// The [HoldPolicy] that is applied once a movement has finished
+            fn hold_policy(&self) -> HoldPolicy;
+
+            /// Set the [HoldPolicy] that is applied once a movement has finished
+            fn set_hold_policy(&mut self, policy : HoldPolicy);
+        // 
+
+        // Heartbeat
+            /// The maximum interval between two heartbeats while driving in a continuous drive mode, `None` if no heartbeat is required
+            fn heartbeat_timeout(&self) -> Option<Seconds>;
+
+            /// Require a heartbeat (see [SyncActuatorState::heartbeat]) at least every `timeout` while driving in a continuous
+            /// drive mode ([DriveMode::ConstVelocity] or [DriveMode::ConstFactor]). If the heartbeat is missing, the actuator 
+            /// performs a controlled stop and latches [ActuatorError::HeartbeatLost]. 
+            /// 
+            /// Set to `None` to disable the heartbeat requirement
+            fn set_heartbeat_timeout(&mut self, timeout : Option<Seconds>);
+
+            /// Returns `true` if the heartbeat has been lost, continuous movements are refused until [StepperActuator::reset_heartbeat_lost] is called
+            fn heartbeat_lost(&self) -> bool;
+
+            /// Clears the latched heartbeat loss
+            fn reset_heartbeat_lost(&mut self);
+        // 
     }    
 // 
 
+// ####################
+// #    HoldPolicy    #
+// ####################
+    /// Defines what the motor does after a movement has finished and it stands still
+    /// 
+    /// The policy is executed automatically by the motor at the end of every movement, the driver is enabled with its full
+    /// current again once the next movement starts
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum HoldPolicy {
+        /// Keep the driver enabled with its full current, holding the position with maximum torque
+        #[default]
+        Hold,
+        /// Reduce the current of the driver to the given fraction of the full current
+        /// - 0 - [Factor]: The fraction of the full current to hold the position with
+        ReduceCurrent(Factor),
+        /// Disable the driver after the given timeout 
+        /// - 0 - [Seconds]: The time to keep holding the position before disabling, see [StepperController::disable_after]
+        DisableAfter(Seconds),
+        /// Disable the driver immediately, the motor will not hold its position
+        Disable
+    }
+// 
+
+// ######################
+// #    SettlePolicy    #
+// ######################
+    /// Defines how a motor waits for the mechanics to settle after a movement, before the blocking movement functions return
+    /// and the next movement can start
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum SettlePolicy {
+        /// Return as soon as the last step has been generated
+        #[default]
+        None,
+        /// Wait a fixed time after the last step, e.g. for vibrations to decay
+        /// - 0 - [Seconds]: The time to wait
+        Time(Seconds),
+        /// Wait until the in-position check of the motor is satisfied, see [StepperMotor::set_in_position]
+        /// - 0 - [Seconds]: The maximum time to wait, [ActuatorError::SettleTimeout] is returned afterwards
+        InPosition(Seconds)
+    }
+
+    /// The interval in which the in-position check is polled while settling
+    pub const SETTLE_POLL_INTERVAL : Seconds = Seconds(0.001);
+// 
+
+// ##################
+// #    StopMode    #
+// ##################
+    /// Defines how a motor stops once an interruptor fires, a halt is requested with [SyncActuatorState::halt] or an interrupt
+    /// with [SyncActuatorState::interrupt], see [StepperMotor::set_stop_mode]
+    /// 
+    /// Stops at the end of a movement, at position limits or by [StepperMotor::stop_before] always use the ramp of the builder
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub enum StopMode {
+        /// Switch the builder into [DriveMode::Stop], the builder ramps down with its deceleration limit. Builders without 
+        /// ramps, like the [StartStopBuilder], stop immediately
+        #[default]
+        Builder,
+        /// Stop with the step currently prepared, regardless of the velocity
+        Immediate,
+        /// Ramp down with the given deceleration along the speed levels of the builder, builders without ramps are ramped down
+        /// by the motor
+        /// - 0 - [RadPerSecond2]: The deceleration of the stop ramp
+        DecelRamp(RadPerSecond2)
+    }
+// 
+
+// #####################
+// #    MoveOutcome    #
+// #####################
+    /// The outcome of a relative movement, see [StepperMotor::drive_rel_checked]
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum MoveOutcome {
+        /// The movement has been executed
+        Executed,
+        /// The movement has been smaller than the minimum move and is added to the next movement
+        /// - 0 - [Radians]: The total distance waiting to be executed
+        Coalesced(Radians),
+        /// The movement has been skipped, as it rounds to zero steps
+        Skipped
+    }
+// 
+
 // ######################
 // #    StepperState    #
 // ######################
     /// The state of a stepper motor, whether it is driving etc.
+    #[cfg(feature = "alloc")]
     pub struct StepperState {
         /// Atomic `Radians`
-        _abs_pos : AtomicF32,
+        _abs_pos : AtomicFloat,
         _moving : AtomicBool,
+        _motion_mode : AtomicU8,
 
         should_halt : AtomicBool,
-        should_interrupt : AtomicBool
+        should_interrupt : AtomicBool,
+
+        _heartbeat : AtomicBool,
+        _simulated : AtomicBool,
+
+        // Prediction
+        _last_step : AtomicFloat,
+        _step_time : AtomicF32,
+        _step_angle : AtomicF32
     }
 
+    #[cfg(feature = "alloc")]
     impl StepperState {
         /// Creates a new `StepperState`
         pub fn new() -> Self {
             StepperState {
-                _abs_pos: AtomicF32::new(Radians::ZERO.0),
+                _abs_pos: AtomicFloat::new(0.0),
                 _moving: AtomicBool::new(false),
+                _motion_mode: AtomicU8::new(MotionMode::Inactive as u8),
 
                 should_halt : AtomicBool::new(false),
-                should_interrupt : AtomicBool::new(false)
+                should_interrupt : AtomicBool::new(false),
+
+                _heartbeat : AtomicBool::new(false),
+                _simulated : AtomicBool::new(false),
+
+                _last_step: AtomicFloat::new(Float::NAN),
+                _step_time: AtomicF32::new(0.0),
+                _step_angle: AtomicF32::new(0.0)
             }
         }
+
+        /// The absolute position in radians with the full precision of the bookkeeping, see [math](crate::math)
+        pub fn pos_precise(&self) -> Float {
+            self._abs_pos.load(Relaxed)
+        }
+
+        /// Records a step for the position prediction, `step_angle` is negative for steps in `CCW` direction
+        pub(crate) fn record_step(&self, now : Seconds, step_time : Seconds, step_angle : Radians) {
+            self._step_time.store(step_time.0, Relaxed);
+            self._step_angle.store(step_angle.0, Relaxed);
+            self._last_step.store(precise(now.0), Relaxed);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Default for StepperState {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 
+    #[cfg(feature = "alloc")]
     impl SyncActuatorState<Rotary> for StepperState {
         fn pos(&self) -> PositionRad {
-            PositionRad(self._abs_pos.load(Relaxed))
+            PositionRad(reduced(self._abs_pos.load(Relaxed)))
         }
 
         fn moving(&self) -> bool {
             self._moving.load(Relaxed)
         }
 
+        fn motion_mode(&self) -> MotionMode {
+            MotionMode::from_u8(self._motion_mode.load(Relaxed))
+        }
+
+        fn simulated(&self) -> bool {
+            self._simulated.load(Relaxed)
+        }
+
+        /// Interpolates between the steps using the duration of the last step, the prediction never exceeds a single step
+        /// 
+        /// Requires a clock to be set with [StepperMotor::set_clock](crate::sync::stepper::StepperMotor::set_clock), `now` must 
+        /// be given in the time base of that clock
+        fn predicted_pos(&self, now : Seconds) -> PositionRad {
+            let pos = self.pos();
+            let last_step = self._last_step.load(Relaxed);
+            let step_time = self._step_time.load(Relaxed);
+
+            if !self.moving() || last_step.is_nan() || (step_time <= 0.0) {
+                return pos;
+            }
+
+            let progress = (reduced(precise(now.0) - last_step) / step_time).clamp(0.0, 1.0);
+            pos + Radians(self._step_angle.load(Relaxed) * progress)
+        }
+
+        /// Decelerates the current movement to a stop, reporting [InterruptReason::Halted](crate::InterruptReason::Halted)
+        /// 
+        /// A halt requested while the motor stands still stops the next movement right away
         fn halt(&self) {
             self.should_halt.store(true, Relaxed);
         }
 
+        /// Stops the current movement with the stop ramp, reporting [InterruptReason::Error](crate::InterruptReason::Error)
+        /// 
+        /// An interrupt requested while the motor stands still stops the next movement right away
         fn interrupt(&self) {
             self.should_interrupt.store(true, Relaxed);
         }
+
+        fn heartbeat(&self) {
+            self._heartbeat.store(true, Relaxed);
+        }
     }
 // 
\ No newline at end of file
@@ -1,21 +1,40 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use syunit::*;
 use syunit::metric::*;
 
 use crate::{StepperConst, StepperConfig, ActuatorError};
 use crate::data::{ActuatorVars, MicroSteps};
+#[cfg(feature = "alloc")]
+use crate::data::JoltTable;
 use crate::sync::stepper::StepperController;
 
 // ####################
 // #    SUBMODULES    #
 // ####################
+    #[cfg(feature = "complex-builder")]
     mod complex;
-    pub use complex::ComplexBuilder;
+    #[cfg(feature = "complex-builder")]
+    pub use complex::{ComplexBuilder, LoadQuantization};
 
+    #[cfg(feature = "complex-builder")]
     mod free;
+    #[cfg(feature = "complex-builder")]
     pub use free::FreeBuilder;
 
+    mod queue;
+    pub use queue::CommandPolicy;
+    #[cfg(feature = "alloc")]
+    pub use queue::CommandQueue;
+
     mod start_stop;
     pub use start_stop::StartStopBuilder;
+
+    #[cfg(feature = "alloc")]
+    mod trapezoidal;
+    #[cfg(feature = "alloc")]
+    pub use trapezoidal::TrapezoidalBuilder;
 //
 
 // Constants
@@ -23,6 +42,92 @@ use crate::sync::stepper::StepperController;
     pub const DEFAULT_MAX_SPEED_LEVEL : usize = 10;
 // 
 
+// Deceleration helpers
+    /// The highest velocity the motor can come to a standstill from within the given amount of `steps`
+    #[cfg(feature = "complex-builder")]
+    pub(crate) fn velocity_for_stop(step_angle : Radians, deceleration : RadPerSecond2, steps : u64) -> RadPerSecond {
+        RadPerSecond((2.0 * deceleration.0 * step_angle.0 * steps as f32).sqrt())
+    }
+
+    /// The amount of steps required to come to a standstill from the given `velocity`, rounded down so the first step of the
+    /// ramp does not increase the velocity
+    #[cfg(feature = "complex-builder")]
+    pub(crate) fn steps_for_stop(step_angle : Radians, deceleration : RadPerSecond2, velocity : RadPerSecond) -> u64 {
+        (velocity.0 * velocity.0 / (2.0 * deceleration.0 * step_angle.0)) as u64
+    }
+// 
+
+// Distance helpers
+    /// Converts the relative distance `rel_dist` of a [DriveMode::FixedDistance] into the absolute number of steps and the 
+    /// direction of the movement
+    /// 
+    /// The distance is rounded to the closest number of steps, carrying residues over is left to the motor (see 
+    /// [RoundingPolicy](crate::data::RoundingPolicy))
+    pub(crate) fn steps_for_distance(rel_dist : Radians, step_angle : Radians) -> (u64, Direction) {
+        let steps = (rel_dist / step_angle).round() as i64;
+        (steps.unsigned_abs(), if steps < 0 { Direction::CCW } else { Direction::CW })
+    }
+//
+
+// Velocity helpers
+    /// Raises the target velocity `vel_tar` of a drive mode to the minimum velocity, standstill is kept
+    pub(crate) fn raise_to_min(vel_tar : RadPerSecond, velocity_min : Option<RadPerSecond>) -> RadPerSecond {
+        match velocity_min {
+            Some(min) if vel_tar > RadPerSecond::ZERO => vel_tar.max(min),
+            _ => vel_tar
+        }
+    }
+
+    /// Validates a new minimum velocity against the maximum velocity of a builder
+    pub(crate) fn check_velocity_min(velocity_opt : Option<RadPerSecond>, velocity_max : Option<RadPerSecond>) 
+        -> Result<Option<RadPerSecond>, ActuatorError> 
+    {
+        let Some(velocity) = velocity_opt else {
+            return Ok(None);
+        };
+
+        if !velocity.is_normal() {
+            return Err(ActuatorError::InvalidVelocity(velocity));
+        }
+
+        match velocity_max {
+            Some(max) if velocity.abs() > max => Err(ActuatorError::VelocityTooHigh(velocity.abs(), max)),
+            _ => Ok(Some(velocity.abs()))
+        }
+    }
+
+    /// Validates a new maximum `velocity` against the minimum velocity of a builder
+    pub(crate) fn check_velocity_max(velocity : RadPerSecond, velocity_min : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+        match velocity_min {
+            Some(min) if velocity.abs() < min => Err(ActuatorError::VelocityTooLow(velocity.abs(), min)),
+            _ => Ok(())
+        }
+    }
+
+    /// Checks if the minimum velocity can be reached by a builder, given the velocity currently possible
+    pub(crate) fn check_velocity_possible(velocity_min : Option<RadPerSecond>, velocity_possible : RadPerSecond) -> Result<(), ActuatorError> {
+        match velocity_min {
+            Some(min) if min > velocity_possible => Err(ActuatorError::VelocityTooHigh(min, velocity_possible)),
+            _ => Ok(())
+        }
+    }
+// 
+
+// Profiles
+    /// A 64 bit FNV-1a hash over the bit patterns of all step intervals of a `profile`, see [StepperBuilder::render_profile]
+    /// 
+    /// Builders are deterministic (see [StepperBuilder]), so the hash of a profile rendered on the target can be compared 
+    /// against one recorded on the same kind of target during the validation of the machine.
+    pub fn profile_hash(profile : &[Seconds]) -> u64 {
+        const FNV_OFFSET : u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME : u64 = 0x0000_0100_0000_01b3;
+
+        profile.iter()
+            .flat_map(|time| time.0.to_bits().to_le_bytes())
+            .fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+    }
+// 
+
 /// The drive-mode of the stepper motor
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum DriveMode {
@@ -34,8 +139,13 @@ pub enum DriveMode {
     /// - 1 - `Direction`: The driving direction
     ConstFactor(Factor, Direction),
     /// Driving a fixed distance
+    /// 
+    /// All builders round the distance to the closest number of steps, the sub-step residue is carried over by the motor 
+    /// according to its [RoundingPolicy](crate::data::RoundingPolicy). Movements rounding to zero steps (e.g. zero-distance 
+    /// or sub-step movements) are no-ops, the builder keeps its current drive mode and direction.
     /// - 0 - `Radians`: The relative distance to drive
-    /// - 1 - [RadPerSecond]: The exit velocity of the movement
+    /// - 1 - [RadPerSecond]: The exit velocity of the movement, the next movement in the same direction continues from it. 
+    ///   The `ComplexBuilder` and the `FreeBuilder` ignore it and stop at the end of the movement
     /// - 2 - [Factor]: Factor of maximum possible speed
     FixedDistance(Radians, RadPerSecond, Factor),
     /// Motor is stopping
@@ -44,7 +154,30 @@ pub enum DriveMode {
     Inactive
 }
 
+/// The constraint currently limiting the maximum velocity of a builder, see [StepperBuilder::velocity_constraint]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VelocityConstraint {
+    /// The back-EMF model of the motor, including the margin of the configuration (see [VelocityMargin](crate::data::VelocityMargin))
+    BackEmf,
+    /// The velocity limit set by the user
+    UserCap,
+    /// The start-stop velocity of the motor, which can be reached without a ramp
+    StartStop,
+    /// The acceleration ramp of the builder, e.g. limited by the acceleration or jolt limits or the available torque
+    Ramp
+}
+
 /// A stepperbuilder creates stepper motor curves
+/// 
+/// # Determinism
+/// 
+/// Builders do not use any randomness, clocks or other state outside of their inputs. The same constants, configuration, 
+/// limits, loads and sequence of drive modes always produce a bit-identical sequence of step intervals on the same platform
+/// with the same math library. Across platforms the profiles only match as far as the math library does: the basic 
+/// arithmetic and `sqrt` are exactly rounded by IEEE 754, but functions like `powf` and `cbrt` (used for torque curves and 
+/// jolt limits) come from the libm of the target and may differ in the last bit. Changes of the generated profiles are 
+/// caught by the conformance tests of the crate, use [profile_hash] to compare profiles rendered on a target against ones
+/// recorded on the same kind of target.
 pub trait StepperBuilder : Iterator<Item = Seconds> {
     // Getters
         /// The current step angle in radians
@@ -72,6 +205,32 @@ pub trait StepperBuilder : Iterator<Item = Seconds> {
         /// 
         /// Set to `None` if no limit is wished
         fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError>;
+
+        /// Minimum velocity allowed by the user if specified, builders without support for it always return `None`
+        fn velocity_min(&self) -> Option<RadPerSecond> {
+            None
+        }
+
+        /// Set the minimum [RadPerSecond], the velocities of all drive modes are raised to it, see 
+        /// [SyncActuator::set_velocity_min](crate::SyncActuator::set_velocity_min)
+        /// 
+        /// ## Option
+        /// 
+        /// Set to `None` if no limit is wished, builders without support for it reject every minimum with 
+        /// [ActuatorError::InvalidVelocity]
+        fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+            match velocity_opt {
+                Some(velocity) => Err(ActuatorError::InvalidVelocity(velocity)),
+                None => Ok(())
+            }
+        }
+
+        /// The maximum velocity that is currently possible, the speed factors of the drive modes refer to it
+        /// 
+        /// The default implementation returns the velocity limit of the user, or zero if none is set
+        fn velocity_possible(&self) -> RadPerSecond {
+            self.velocity_max().unwrap_or(RadPerSecond::ZERO)
+        }
     // 
 
     // Acceleration
@@ -84,6 +243,18 @@ pub trait StepperBuilder : Iterator<Item = Seconds> {
         /// 
         /// Set to `None` if no limit is wished
         fn set_acceleration_max(&mut self, acceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError>;
+
+        /// Maximum deceleration used to ramp down, if specified by the user with `set_deceleration_max`, the builder ramps 
+        /// down with the acceleration limit otherwise
+        fn deceleration_max(&self) -> Option<RadPerSecond2>;
+
+        /// Set the maximum allowed deceleration in [RadPerSecond2], used for the ramp-down phase of fixed distance movements
+        /// and for stops
+        /// 
+        /// ## Option
+        /// 
+        /// Set to `None` to ramp down with the acceleration limit
+        fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError>;
     // 
 
     // Jolt
@@ -96,6 +267,23 @@ pub trait StepperBuilder : Iterator<Item = Seconds> {
         /// 
         /// Set to `None` if no limit is wished
         fn set_jolt_max(&mut self, jolt_opt : Option<RadPerSecond3>) -> Result<(), ActuatorError>;
+
+        /// The [JoltTable] with velocity-dependent jolt limits, if set
+        /// 
+        /// Builders without speed levels only use the global jolt limit and never have a table
+        #[cfg(feature = "alloc")]
+        fn jolt_table(&self) -> Option<&JoltTable> {
+            None
+        }
+
+        /// Set the [JoltTable] with velocity-dependent jolt limits, the global jolt limit applies to velocities above all 
+        /// bands of the table
+        /// 
+        /// Builders without speed levels ignore the table
+        #[cfg(feature = "alloc")]
+        fn set_jolt_table(&mut self, _table_opt : Option<JoltTable>) -> Result<(), ActuatorError> {
+            Ok(())
+        }
     // 
 
     // Regulation
@@ -105,6 +293,89 @@ pub trait StepperBuilder : Iterator<Item = Seconds> {
         /// Sets the drive mode
         fn set_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError>;
     //   
+
+    // Commands
+        /// The [CommandPolicy] used for commands that arrive while the builder is ramping down
+        /// 
+        /// Builders that can stop instantly never queue commands and always return the default policy
+        fn command_policy(&self) -> CommandPolicy {
+            CommandPolicy::default()
+        }
+
+        /// Sets the [CommandPolicy] used for commands that arrive while the builder is ramping down
+        fn set_command_policy(&mut self, _policy : CommandPolicy) { }
+
+        /// The amount of commands waiting for the stop ramp to finish
+        fn queued_commands(&self) -> usize {
+            0
+        }
+
+        /// Starts the next queued command once the builder has come to a standstill, returns `true` if a command has been started
+        fn resume_queued<C : StepperController>(&mut self, _ctrl : &mut C) -> Result<bool, ActuatorError> {
+            Ok(false)
+        }
+    //
+
+    // Stops
+        /// Ramps down along the speed levels of the builder with the given `deceleration` instead of its own deceleration 
+        /// limit, called after switching into [DriveMode::Stop]. The deceleration applies until the builder stands still
+        /// 
+        /// Returns `false` if the builder has no ramps to follow (e.g. the [StartStopBuilder](crate::sync::stepper::StartStopBuilder)),
+        /// the caller has to ramp down on its own then
+        fn stop_with_deceleration(&mut self, _deceleration : RadPerSecond2) -> bool {
+            false
+        }
+
+        /// Drops the ramp currently executed, the builder stands still afterwards. Commands queued during a stop ramp are 
+        /// kept and started by [StepperBuilder::resume_queued]
+        fn reset_ramp(&mut self);
+    //
+
+    // Diagnostics
+        /// The constraint currently limiting the maximum velocity of the builder, useful to find out why an axis does not 
+        /// reach the expected speed. Returns `None` if the builder does not report it
+        fn velocity_constraint(&self) -> Option<VelocityConstraint> {
+            None
+        }
+    //
+
+    // Profiles
+        /// Renders all step intervals of a movement by the relative distance `rel_dist` with the given `speed` factor, 
+        /// without touching any controller. Useful for shipping profiles to external pulse generators or verifying them.
+        /// 
+        /// All nodes of the movement are consumed afterwards, the direction of the builder is set to the direction of the movement.
+        #[cfg(feature = "alloc")]
+        fn render_profile(&mut self, rel_dist : Radians, speed : Factor) -> Result<Vec<Seconds>, ActuatorError> 
+        where
+            Self : Sized
+        {
+            self.set_drive_mode(DriveMode::FixedDistance(rel_dist, RadPerSecond::ZERO, speed), &mut ProfileController::default())?;
+            Ok(self.by_ref().collect())
+        }
+    // 
+}
+
+/// A controller that does nothing but storing its direction, used to render profiles and to run simulated movements
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+pub(crate) struct ProfileController {
+    direction : Direction
+}
+
+#[cfg(feature = "alloc")]
+impl StepperController for ProfileController {
+    fn step(&mut self, _time : Seconds) -> Result<(), ActuatorError> {
+        Ok(())
+    }
+
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+        self.direction = dir;
+        Ok(())
+    }
 }
 
 // Extension Traits
@@ -146,6 +417,9 @@ pub trait AdvancedStepperBuilder : StepperBuilder {
 
         /// Setting the overload current for more torque output
         fn set_overload_curret(&mut self, current : Option<f32>) -> Result<(), ActuatorError>;
+
+        /// Setting the current configured on the driver, if it is below the rated current of the motor
+        fn set_drive_current(&mut self, current : Option<f32>) -> Result<(), ActuatorError>;
     //
 
     // Loads
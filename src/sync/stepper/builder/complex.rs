@@ -4,11 +4,58 @@ use syunit::*;
 use syunit::metric::*;
 
 use crate::{DefinedActuator, StepperConst, StepperConfig};
-use crate::data::{ActuatorVars, MicroSteps};
+use crate::data::{ActuatorVars, JoltTable, MicroSteps};
 use crate::sync::stepper::StepperController;
 use crate::sync::stepper::builder::AdvancedStepperBuilder;
 
-use super::{DriveMode, StepperBuilder, ActuatorError, DEFAULT_MAX_SPEED_LEVEL};
+use super::{CommandPolicy, CommandQueue, DriveMode, StepperBuilder, ActuatorError, VelocityConstraint, DEFAULT_MAX_SPEED_LEVEL, 
+    check_velocity_max, check_velocity_min, check_velocity_possible, raise_to_min, steps_for_distance, steps_for_stop, velocity_for_stop};
+
+/// Quantization of the loads applied to a [ComplexBuilder], see [ComplexBuilder::set_load_quantization]
+/// 
+/// The speed levels are computed for the loads rounded to multiples of the given steps. Loads rounded to the same values
+/// share their speed levels, so the levels are only recomputed if a load changes by more than one step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadQuantization {
+    /// The step of both the general and the directional load force
+    pub force : NewtonMeters,
+    /// The step of the load inertia
+    pub inertia : KgMeter2,
+    /// The maximum amount of speed level tables kept for previously applied loads
+    pub cache_size : usize
+}
+
+impl LoadQuantization {
+    /// The quantized loads, all of them are rounded towards the larger load, the directional force away from zero
+    fn key(&self, vars : &ActuatorVars) -> [i64; 3] {
+        let force_dir = vars.force_load_dir.0 / self.force.0;
+
+        [
+            (vars.force_load_gen.0 / self.force.0).ceil() as i64,
+            (force_dir.abs().ceil() * force_dir.signum()) as i64,
+            (vars.inertia_load.0 / self.inertia.0).ceil() as i64
+        ]
+    }
+
+    /// The loads the speed levels of the given `key` are computed for
+    fn vars(&self, key : [i64; 3], vars : &ActuatorVars) -> ActuatorVars {
+        ActuatorVars {
+            force_load_gen: self.force * key[0] as f32,
+            force_load_dir: self.force * key[1] as f32,
+            inertia_load: self.inertia * key[2] as f32,
+            lim: vars.lim.clone()
+        }
+    }
+}
+
+/// The speed levels of a [ComplexBuilder] computed for one set of loads
+#[derive(Clone, Debug, Default)]
+struct SpeedLevels {
+    speed_levels : Vec<RadPerSecond>,
+    time_sums : Vec<Seconds>,
+    times : Vec<Seconds>,
+    last_accel : RadPerSecond2
+}
 
 /// ########################
 /// #    ComplexBuilder    #
@@ -27,8 +74,11 @@ pub struct ComplexBuilder {
 
     // Limits
     _velocity_max : Option<RadPerSecond>,
+    _velocity_min : Option<RadPerSecond>,
     _acceleration_max : Option<RadPerSecond2>,
+    _deceleration_max : Option<RadPerSecond2>,
     _jolt_max : Option<RadPerSecond3>,
+    _jolt_table : Option<JoltTable>,
 
     // Cache
     last_accel : RadPerSecond2,
@@ -38,7 +88,7 @@ pub struct ComplexBuilder {
 
     // Modes
     mode : DriveMode,
-    cached_mode : Option<DriveMode>,
+    commands : CommandQueue,
 
     // Speed levels
     speed_levels : Vec<RadPerSecond>,
@@ -46,14 +96,76 @@ pub struct ComplexBuilder {
     times : Vec<Seconds>,
     max_speed_level : Option<usize>,
     current_speed_level : usize,
+    /// Steps left of a stop ramp with a separate deceleration limit
+    stop_steps : u64,
+    /// Deceleration of the stop ramp requested with [StepperBuilder::stop_with_deceleration]
+    stop_deceleration : Option<RadPerSecond2>,
 
     distance : u64,
-    distance_counter : u64
+    distance_counter : u64,
+
+    // Load quantization
+    _load_quantization : Option<LoadQuantization>,
+    /// The quantized loads of the current speed levels, `None` if computed for the exact loads
+    levels_key : Option<[i64; 3]>,
+    level_cache : Vec<([i64; 3], SpeedLevels)>
 }
 
 impl ComplexBuilder {
     /// Updates the builders speed levels and times considering loads etc.
+    /// 
+    /// Discards all speed levels cached for previous loads, see [ComplexBuilder::set_load_quantization]
     pub fn update(&mut self) -> Result<(), ActuatorError> {
+        self.level_cache.clear();
+        self.levels_key = None;
+        self.update_loads()
+    }
+
+    /// Updates the speed levels after the loads have changed, reusing the levels of the quantized loads if possible
+    fn update_loads(&mut self) -> Result<(), ActuatorError> {
+        let Some(quant) = self._load_quantization else {
+            let levels = self.compute_levels(&self._vars)?;
+            self.set_levels(levels);
+            return Ok(());
+        };
+
+        let key = quant.key(&self._vars);
+
+        if self.levels_key == Some(key) {
+            return Ok(());
+        }
+
+        let levels = match self.level_cache.iter().position(|(cached, _)| *cached == key) {
+            Some(index) => self.level_cache.remove(index).1,
+            None => self.compute_levels(&quant.vars(key, &self._vars))?
+        };
+
+        let previous = self.set_levels(levels);
+
+        // The levels replaced are kept as the most recent ones
+        if let Some(key_prev) = self.levels_key.replace(key) {
+            self.level_cache.push((key_prev, previous));
+
+            if self.level_cache.len() > quant.cache_size {
+                self.level_cache.remove(0);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the current speed levels, returning the previous ones
+    fn set_levels(&mut self, levels : SpeedLevels) -> SpeedLevels {
+        SpeedLevels {
+            speed_levels: core::mem::replace(&mut self.speed_levels, levels.speed_levels),
+            time_sums: core::mem::replace(&mut self.time_sums, levels.time_sums),
+            times: core::mem::replace(&mut self.times, levels.times),
+            last_accel: core::mem::replace(&mut self.last_accel, levels.last_accel)
+        }
+    }
+
+    /// Computes the speed levels for the given loads
+    fn compute_levels(&self, vars : &ActuatorVars) -> Result<SpeedLevels, ActuatorError> {
         // Store relevant values
         let max_speed_level = self.max_speed_level.unwrap_or(DEFAULT_MAX_SPEED_LEVEL);
         let velocity_cap = self.velocity_cap();
@@ -64,21 +176,23 @@ impl ComplexBuilder {
         let mut times : Vec<Seconds> = Vec::new();
 
         let mut velocity_current = RadPerSecond::ZERO;
+        // The speed levels start at standstill
+        let mut last_accel = RadPerSecond2::ZERO;
 
         // Iterate to max speed level or until the cap is reached
         for _ in 0 .. max_speed_level {
-            let mut accel_possible = self.acceleration_possible(velocity_current)?;
+            let mut accel_possible = self.acceleration_for(vars, velocity_current)?;
 
             // Do without jolt first
             let ( mut move_time, _ ) = sykin::kin2::time_for_distance::<Rotary>(self.step_angle(), velocity_current, accel_possible);
 
             // Consider maximum jolt if set
-            if let Some(jolt_max) = self.jolt_max() {
+            if let Some(jolt_max) = self.jolt_at(velocity_current) {
                 // Only correct if the acceleration has exeeded the jolt value
-                if ((accel_possible - self.last_accel) / move_time) > jolt_max {
+                if ((accel_possible - last_accel) / move_time) > jolt_max {
                     // Heavy calculation of a cubic formula
-                    move_time = sykin::kin3::time_for_distance::<Rotary>(self.step_angle(), velocity_current, self.last_accel, jolt_max);
-                    accel_possible = self.last_accel + jolt_max * move_time;
+                    move_time = sykin::kin3::time_for_distance::<Rotary>(self.step_angle(), velocity_current, last_accel, jolt_max);
+                    accel_possible = last_accel + jolt_max * move_time;
                 }
             }
 
@@ -88,7 +202,7 @@ impl ComplexBuilder {
             // If the velocity is greater than the cap, recalc values, store them, and break the loop
             if velocity_current > velocity_cap {
                 // Correcting movetime and storing correct velocity
-                move_time = 2.0 * self.step_angle() / (*self.speed_levels.last().unwrap_or(&RadPerSecond::ZERO) + velocity_cap);
+                move_time = 2.0 * self.step_angle() / (*speed_levels.last().unwrap_or(&RadPerSecond::ZERO) + velocity_cap);
                 velocity_current = velocity_cap;
 
                 // Push to speed levels
@@ -104,21 +218,118 @@ impl ComplexBuilder {
             time_sums.push(*time_sums.last().unwrap_or(&Seconds::ZERO) + move_time);
             times.push(move_time);
 
-            self.last_accel = accel_possible;
+            last_accel = accel_possible;
         }
 
-        // Update class values
-        self.speed_levels = speed_levels;
-        self.times = times;
-        self.time_sums = time_sums;
-
-        Ok(())
+        Ok(SpeedLevels { speed_levels, time_sums, times, last_accel })
     }
 
-    /// Stops the builder with the given drivemode
+    // Load quantization
+        /// The quantization of the loads, `None` if the speed levels are computed for the exact loads
+        pub fn load_quantization(&self) -> Option<LoadQuantization> {
+            self._load_quantization
+        }
+
+        /// Sets the quantization of the loads, speeding up frequent small load changes (e.g. gravity compensation)
+        /// 
+        /// Applying a load only recomputes the speed levels if the quantized loads change, the levels of previously applied
+        /// loads are cached. All loads are rounded towards the larger load, so the motor is never driven faster than the
+        /// actual loads allow. `None` computes the speed levels for the exact loads on every change (default)
+        /// 
+        /// Returns [ActuatorError::InvalidConfig] if one of the steps is not positive and finite
+        pub fn set_load_quantization(&mut self, quant_opt : Option<LoadQuantization>) -> Result<(), ActuatorError> {
+            if let Some(quant) = quant_opt {
+                if !(quant.force.0.is_normal() && (quant.force.0 > 0.0) && quant.inertia.0.is_normal() && (quant.inertia.0 > 0.0)) {
+                    return Err(ActuatorError::InvalidConfig("The steps of a load quantization must be positive and finite"));
+                }
+            }
+
+            self._load_quantization = quant_opt;
+            self.update()
+        }
+
+        /// The amount of speed level tables cached for previously applied loads
+        pub fn cached_levels(&self) -> usize {
+            self.level_cache.len()
+        }
+    //
+
+    /// Stops the builder, the given drivemode is queued and executed once the builder has come to a standstill
     pub fn stop_with_mode(&mut self, mode : DriveMode) {
         self.mode = DriveMode::Stop;
-        self.cached_mode = Some(mode);
+        self.commands.push(mode);
+    }
+
+    /// Moves on to the next queued command once a continuous movement has reached its target velocity
+    /// 
+    /// Commands in the same direction are taken over directly, all others require the builder to stop first
+    fn advance_queue(&mut self) {
+        let same_dir = match self.commands.peek() {
+            Some(DriveMode::ConstVelocity(velocity)) => velocity.get_direction() == self._dir,
+            Some(DriveMode::ConstFactor(_, dir)) => *dir == self._dir,
+            Some(_) => false,
+            None => return
+        };
+
+        if same_dir {
+            if let Some(mode) = self.commands.pop() {
+                self.mode = mode;
+            }
+        } else {
+            self.mode = DriveMode::Stop;
+        }
+    }
+
+    /// Applies the drive mode directly, continuous movements in the opposite direction stop the builder first
+    fn apply_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
+        let (steps, dir) = match mode {
+            DriveMode::FixedDistance(rel_dist, _, _) => steps_for_distance(rel_dist, self._step_angle),
+            _ => (0, self._dir)
+        };
+
+        match mode {
+            DriveMode::ConstVelocity(velocity) => {
+                let dir = velocity.get_direction();
+
+                if (self.current_speed_level > 0) & (dir != self._dir) {
+                    // Turn around motor
+                    self.stop_with_mode(mode);
+                    return Ok(());
+                } 
+                
+                self._dir = dir;
+                ctrl.set_dir(dir)?;
+            },
+            DriveMode::ConstFactor(_, dir) => {
+                if (self.current_speed_level > 0) & (dir != self._dir) {
+                    // Turn around motor
+                    self.stop_with_mode(mode);
+                    return Ok(());
+                } 
+                
+                self._dir = dir;
+                ctrl.set_dir(dir)?;
+            },
+            DriveMode::FixedDistance(_, _, _) if steps == 0 => {
+                // Sub-step movements are no-ops
+                return Ok(());
+            },
+            DriveMode::FixedDistance(_, _, _) => {
+                if steps < self.current_speed_level as u64 {
+                    return Err(ActuatorError::InvaldRelativeDistance(self.step_angle()))
+                }
+
+                self.distance = steps;
+                self.distance_counter = 0;
+
+                self._dir = dir;
+                ctrl.set_dir(dir)?;
+            },
+            _ => { }
+        };
+
+        self.mode = mode;
+        Ok(())
     }
 
     /// Moves the builder towards the next speed-level closer to the desired velocity `vel_tar`
@@ -145,6 +356,39 @@ impl ComplexBuilder {
         }
     }
 
+    /// Limits the velocity `vel` to the ramp-down with the given `deceleration`, so the motor comes to a standstill within
+    /// the `remaining` steps. Once the ramp-down has started, the speed level follows it until the standstill
+    fn ramp_down(&mut self, vel : RadPerSecond, deceleration : RadPerSecond2, remaining : u64) -> RadPerSecond {
+        let vel_stop = velocity_for_stop(self.step_angle(), deceleration, remaining);
+
+        if vel > vel_stop {
+            self.current_speed_level = self.speed_levels.iter().take_while(|&&level| level <= vel_stop).count();
+            self.stop_steps = remaining - 1;
+            vel_stop
+        } else {
+            vel
+        }
+    }
+
+    /// The next step of a stop ramp with the given `deceleration`
+    fn stop_step(&mut self, deceleration : RadPerSecond2) -> RadPerSecond {
+        if self.current_speed_level == 0 {
+            return RadPerSecond::ZERO;
+        }
+
+        if self.stop_steps == 0 {
+            self.stop_steps = steps_for_stop(self.step_angle(), deceleration, self.velocity_current()).max(1);
+        }
+
+        let vel = self.ramp_down(RadPerSecond::INFINITY, deceleration, self.stop_steps);
+
+        if self.stop_steps == 0 {
+            self.current_speed_level = 0;
+        }
+
+        vel
+    }
+
     // RadPerSecond
         /// The current velocity of the builder
         pub fn velocity_current(&self) -> RadPerSecond {
@@ -156,10 +400,11 @@ impl ComplexBuilder {
         /// Returns the cap velocity
         /// - Is either the cap velocity given by the user
         /// - Or the maximum recommended velocity for a stepper motor
+        /// 
         /// depends on which is lower
         pub fn velocity_cap(&self) -> RadPerSecond {
             self._velocity_max.unwrap_or(RadPerSecond::INFINITY)
-                .min(self.consts().velocity_max(self.config().voltage))
+                .min(self.consts().velocity_max_with_margin(self.config()))
         }
 
         /// The maximum velocity that is currently possible, defined by numerous factors like maximum jolt, acceleration, velocity and start-stop mechanics
@@ -170,10 +415,25 @@ impl ComplexBuilder {
         }
     // 
 
+    // RadPerSecond3
+        /// The jolt limit at the given `velocity`, taken from the [JoltTable] if the velocity lies in one of its bands, the 
+        /// global jolt limit is used otherwise
+        pub fn jolt_at(&self, velocity : RadPerSecond) -> Option<RadPerSecond3> {
+            self._jolt_table.as_ref()
+                .and_then(|table| table.jolt_at(velocity))
+                .or(self._jolt_max)
+        }
+    // 
+
     // RadPerSecond2
         /// Returns the maximum acceleration possible by the motor or allowed by to user, depending on which one is lower
         pub fn acceleration_possible(&self, velocity_current : RadPerSecond) -> Result<RadPerSecond2, ActuatorError> {
-            self.consts().acceleration_max_for_velocity(self.vars(), self.config(), velocity_current, self.direction())
+            self.acceleration_for(self.vars(), velocity_current)
+        }
+
+        /// The maximum acceleration possible with the given loads
+        fn acceleration_for(&self, vars : &ActuatorVars, velocity_current : RadPerSecond) -> Result<RadPerSecond2, ActuatorError> {
+            self.consts().acceleration_max_for_velocity_microsteps(vars, self.config(), velocity_current, self.direction(), self.microsteps())
                 .ok_or(ActuatorError::Overload)
                 .map(|accel| accel.min(self.acceleration_max().unwrap_or(RadPerSecond2::INFINITY)))
         }
@@ -185,26 +445,73 @@ impl Iterator for ComplexBuilder {
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut vel_opt = match self.mode {
-            DriveMode::ConstVelocity(velocity ) => self.goto_velocity(velocity).ok(),
-            DriveMode::ConstFactor(factor, _) => self.goto_velocity(self.velocity_possible() * factor).ok(),
+            DriveMode::ConstVelocity(velocity) => {
+                let vel_tar = raise_to_min(velocity.abs(), self._velocity_min);
+                let vel_opt = self.goto_velocity(vel_tar).ok();
+
+                if vel_opt == Some(vel_tar) {
+                    self.advance_queue();
+                }
+
+                vel_opt
+            },
+            DriveMode::ConstFactor(factor, _) => {
+                let vel_tar = raise_to_min(self.velocity_possible() * factor, self._velocity_min);
+                let vel_opt = self.goto_velocity(vel_tar).ok();
+
+                if vel_opt == Some(vel_tar) {
+                    self.advance_queue();
+                }
+
+                vel_opt
+            },
             DriveMode::FixedDistance(_, _, factor) => {
                 self.distance_counter += 1;
 
                 // Special case with only one node
                 if (self.distance == 1) & (self.distance_counter == 1) {
-                    return self.times.first().map(|v| *v);
+                    return self.times.first().copied();
                 }
 
-                if ((self.distance_counter + self.current_speed_level as u64) == self.distance) & ((self.distance % 2) == 1) {
+                if let Some(deceleration) = self._deceleration_max {
+                    // Every step is limited to the velocity the motor is still able to stop from in the remaining distance
+                    if self.distance_counter > self.distance {
+                        self.current_speed_level = 0;
+                        None
+                    } else {
+                        let remaining = self.distance - self.distance_counter + 1;
+
+                        if self.stop_steps > 0 {
+                            Some(self.ramp_down(RadPerSecond::INFINITY, deceleration, remaining))
+                        } else {
+                            self.goto_velocity(raise_to_min(self.velocity_possible() * factor, self._velocity_min)).ok()
+                                .map(|vel| self.ramp_down(vel, deceleration, remaining))
+                        }
+                    }
+                } else if ((self.distance_counter + self.current_speed_level as u64) == self.distance) & ((self.distance % 2) == 1) {
                     Some(self.speed_levels[self.current_speed_level.saturating_sub(1)])
                 } else if (self.distance_counter + self.current_speed_level as u64) > self.distance {
                     self.goto_velocity(RadPerSecond::ZERO).ok()
                 } else {
-                    self.goto_velocity(self.velocity_possible() * factor).ok()
+                    self.goto_velocity(raise_to_min(self.velocity_possible() * factor, self._velocity_min)).ok()
                 }
             },
             DriveMode::Stop => {
-                self.goto_velocity(RadPerSecond::ZERO).ok()
+                let vel_opt = match self.stop_deceleration.or(self._deceleration_max) {
+                    Some(deceleration) => Some(self.stop_step(deceleration)),
+                    None => self.goto_velocity(RadPerSecond::ZERO).ok()
+                };
+
+                // Standstill reached, the builder stays in stop mode if commands are waiting for `resume_queued()`
+                if vel_opt.unwrap_or(RadPerSecond::ZERO) == RadPerSecond::ZERO {
+                    self.stop_deceleration = None;
+
+                    if self.commands.is_empty() {
+                        self.mode = DriveMode::Inactive;
+                    }
+                }
+
+                vel_opt
             },
             DriveMode::Inactive => None
         };
@@ -215,6 +522,11 @@ impl Iterator for ComplexBuilder {
             }
         }
 
+        // Fixed distance movements are finished once no further step is required
+        if vel_opt.is_none() && matches!(self.mode, DriveMode::FixedDistance(_, _, _)) {
+            self.mode = DriveMode::Inactive;
+        }
+
         vel_opt.map(|vel| self._consts.step_time(vel, self._microsteps))
     }
 }
@@ -251,6 +563,7 @@ impl StepperBuilder for ComplexBuilder {
         fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
             if let Some(velocity) = velocity_opt {
                 if velocity.is_normal() {
+                    check_velocity_max(velocity, self._velocity_min)?;
                     self._velocity_max = Some(velocity.abs()); 
                     self.update()
                 } else {
@@ -261,6 +574,20 @@ impl StepperBuilder for ComplexBuilder {
                 Ok(())
             }
         }
+
+        #[inline]
+        fn velocity_min(&self) -> Option<RadPerSecond> {
+            self._velocity_min
+        }
+
+        fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+            self._velocity_min = check_velocity_min(velocity_opt, self._velocity_max)?;
+            Ok(())
+        }
+
+        fn velocity_possible(&self) -> RadPerSecond {
+            ComplexBuilder::velocity_possible(self)
+        }
     //
 
     // RadPerSecond2
@@ -282,6 +609,25 @@ impl StepperBuilder for ComplexBuilder {
                 Ok(())
             }
         }
+
+        #[inline]
+        fn deceleration_max(&self) -> Option<RadPerSecond2> {
+            self._deceleration_max
+        }
+
+        fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+            if let Some(deceleration) = deceleration_opt {
+                if deceleration.is_normal() {
+                    self._deceleration_max = Some(deceleration.abs()); 
+                    self.update()
+                } else {
+                    Err(ActuatorError::InvalidAcceleration(deceleration))
+                }
+            } else {
+                self._deceleration_max = None;
+                Ok(())
+            }
+        }
     // 
 
     // RadPerSecond3 
@@ -303,6 +649,15 @@ impl StepperBuilder for ComplexBuilder {
                 Ok(())
             }
         }
+
+        fn jolt_table(&self) -> Option<&JoltTable> {
+            self._jolt_table.as_ref()
+        }
+
+        fn set_jolt_table(&mut self, table_opt : Option<JoltTable>) -> Result<(), ActuatorError> {
+            self._jolt_table = table_opt;
+            self.update()
+        }
     // 
 
     fn drive_mode(&self) -> &DriveMode {
@@ -310,57 +665,89 @@ impl StepperBuilder for ComplexBuilder {
     }
 
     fn set_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
-        match mode {
-            DriveMode::ConstVelocity(mut velocity) => {
-                let dir = velocity.get_direction();
-                velocity = velocity.abs();
-
-                if velocity > self.velocity_possible() {
-                    return Err(ActuatorError::VelocityTooHigh(velocity, self.velocity_possible()))
-                } 
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive) {
+            check_velocity_possible(self._velocity_min, self.velocity_possible())?;
+        }
 
-                if self.mode != DriveMode::Inactive {
-                    // Turn around motor
-                    self.stop_with_mode(mode.clone());
-                } else {
-                    self._dir = dir;
-                    ctrl.set_dir(dir)?;
-                }
+        match mode {
+            DriveMode::ConstVelocity(velocity) | DriveMode::FixedDistance(_, velocity, _) if velocity.abs() > self.velocity_possible() => {
+                return Err(ActuatorError::VelocityTooHigh(velocity.abs(), self.velocity_possible()))
             },
-            DriveMode::ConstFactor(_, dir) => {
-                if (self.mode != DriveMode::Inactive) & (dir != self._dir) {
-                    // Turn around motor
-                    self.stop_with_mode(mode.clone());
-                } else {
-                    self._dir = dir;
-                    ctrl.set_dir(dir)?;
-                }
+            DriveMode::Stop | DriveMode::Inactive => {
+                // Stopping discards all commands waiting and the deceleration of the last stop
+                self.commands.clear();
+                self.stop_deceleration = None;
             },
-            DriveMode::FixedDistance(rel_dist, velocity_exit, _) => {
-                if velocity_exit > self.velocity_possible() {
-                    return Err(ActuatorError::VelocityTooHigh(velocity_exit, self.velocity_possible()))
-                }
+            _ => { }
+        }
 
-                self.distance = self._consts.steps_from_angle_abs(rel_dist, self._microsteps);
-                self.distance_counter = 0;
+        // Commands arriving during a stop ramp or while other commands are waiting are queued
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive) 
+            & (((self.mode == DriveMode::Stop) & (self.current_speed_level > 0)) | !self.commands.is_empty()) 
+        {
+            self.commands.push(mode);
+            return Ok(());
+        }
 
-                if self.distance < self.current_speed_level as u64 {
-                    return Err(ActuatorError::InvaldRelativeDistance(self.step_angle()))
-                }
+        self.apply_drive_mode(mode, ctrl)
+    }
+    
+    // Commands
+        fn command_policy(&self) -> CommandPolicy {
+            self.commands.policy()
+        }
 
-                if rel_dist >= Radians::ZERO {
-                    self._dir = Direction::CW;
-                } else {
-                    self._dir = Direction::CCW;
-                }
-                ctrl.set_dir(self._dir)?;
-            },
-            _ => { }
-        };
+        fn set_command_policy(&mut self, policy : CommandPolicy) {
+            self.commands.set_policy(policy)
+        }
 
-        self.mode = mode;
-        Ok(())
-    }
+        fn queued_commands(&self) -> usize {
+            self.commands.len()
+        }
+
+        fn resume_queued<C : StepperController>(&mut self, ctrl : &mut C) -> Result<bool, ActuatorError> {
+            if self.current_speed_level > 0 {
+                return Ok(false);
+            }
+
+            if let Some(mode) = self.commands.pop() {
+                self.apply_drive_mode(mode, ctrl)?;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+    //
+
+    // Stops
+        fn stop_with_deceleration(&mut self, deceleration : RadPerSecond2) -> bool {
+            self.stop_deceleration = Some(deceleration);
+            self.stop_steps = 0;
+            true
+        }
+
+        fn reset_ramp(&mut self) {
+            self.current_speed_level = 0;
+            self.stop_steps = 0;
+            self.stop_deceleration = None;
+
+            self.mode = if self.commands.is_empty() { DriveMode::Inactive } else { DriveMode::Stop };
+        }
+    //
+
+    // Diagnostics
+        fn velocity_constraint(&self) -> Option<VelocityConstraint> {
+            let velocity = self.velocity_possible();
+
+            Some(if self._velocity_max == Some(velocity) {
+                VelocityConstraint::UserCap
+            } else if self.consts().velocity_max_with_margin(self.config()) == velocity {
+                VelocityConstraint::BackEmf
+            } else {
+                VelocityConstraint::Ramp
+            })
+        }
+    //
 }
 
 impl AdvancedStepperBuilder for ComplexBuilder {
@@ -376,8 +763,11 @@ impl AdvancedStepperBuilder for ComplexBuilder {
 
                 // Limits
                 _velocity_max: None,
+                _velocity_min: None,
                 _acceleration_max: None,
+                _deceleration_max: None,
                 _jolt_max: None,
+                _jolt_table: None,
 
                 _microsteps: MicroSteps::default(),
 
@@ -388,13 +778,19 @@ impl AdvancedStepperBuilder for ComplexBuilder {
                 _dir: Direction::default(),
 
                 mode: DriveMode::Inactive,
-                cached_mode: None,
+                commands: CommandQueue::default(),
 
                 time_sums: Vec::new(),
                 times: Vec::new(),
                 speed_levels: Vec::new(),
                 max_speed_level: None,
                 current_speed_level: 0,
+                stop_steps: 0,
+                stop_deceleration: None,
+
+                _load_quantization: None,
+                levels_key: None,
+                level_cache: Vec::new(),
 
                 _consts: consts
             };
@@ -429,22 +825,27 @@ impl AdvancedStepperBuilder for ComplexBuilder {
             self._config.overload_current = current;
             self.update()
         }
+
+        fn set_drive_current(&mut self, current : Option<f32>) -> Result<(), ActuatorError> {
+            self._config.drive_current = current;
+            self.update()
+        }
     // 
 
     // Loads
         fn apply_gen_force(&mut self, force : NewtonMeters) -> Result<(), ActuatorError> {
             self._vars.force_load_gen = force;
-            self.update()
+            self.update_loads()
         }
 
         fn apply_dir_force(&mut self, force : NewtonMeters) -> Result<(), ActuatorError> {
             self._vars.force_load_dir = force;
-            self.update()
+            self.update_loads()
         }
         
         fn apply_inertia(&mut self, inertia : KgMeter2) -> Result<(), ActuatorError> {
             self._vars.inertia_load = inertia;
-            self.update()
+            self.update_loads()
         }
     // 
 }
@@ -3,10 +3,11 @@ use alloc::vec::Vec;
 use syunit::*;
 
 use crate::StepperConst;
-use crate::data::MicroSteps;
+use crate::data::{JoltTable, MicroSteps};
 use crate::sync::stepper::StepperController;
 
-use super::{DriveMode, StepperBuilder, ActuatorError, DEFAULT_MAX_SPEED_LEVEL};
+use super::{CommandPolicy, CommandQueue, DriveMode, StepperBuilder, ActuatorError, DEFAULT_MAX_SPEED_LEVEL, 
+    check_velocity_max, check_velocity_min, check_velocity_possible, raise_to_min, steps_for_distance, steps_for_stop, velocity_for_stop};
 
 /// ########################
 /// #    FreeBuilder    #
@@ -23,8 +24,11 @@ pub struct FreeBuilder {
 
     // Limits
     _velocity_max : Option<RadPerSecond>,
+    _velocity_min : Option<RadPerSecond>,
     _acceleration_max : Option<RadPerSecond2>,
+    _deceleration_max : Option<RadPerSecond2>,
     _jolt_max : Option<RadPerSecond3>,
+    _jolt_table : Option<JoltTable>,
 
     // Cache
     last_accel : RadPerSecond2,
@@ -34,7 +38,7 @@ pub struct FreeBuilder {
 
     // Modes
     mode : DriveMode,
-    cached_mode : Option<DriveMode>,
+    commands : CommandQueue,
 
     // Speed levels
     speed_levels : Vec<RadPerSecond>,
@@ -42,12 +46,54 @@ pub struct FreeBuilder {
     times : Vec<Seconds>,
     max_speed_level : Option<usize>,
     current_speed_level : usize,
+    /// Steps left of a stop ramp with a separate deceleration limit
+    stop_steps : u64,
+    /// Deceleration of the stop ramp requested with [StepperBuilder::stop_with_deceleration]
+    stop_deceleration : Option<RadPerSecond2>,
 
     distance : u64,
     distance_counter : u64
 }
 
 impl FreeBuilder {
+    /// Creates a new builder for the motor with the given `consts`, the speed levels are defined by the `velocity_max` and
+    /// the `acceleration_max` only, no model of the motor is used
+    pub fn new(consts : StepperConst, velocity_max : RadPerSecond, acceleration_max : RadPerSecond2) -> Result<Self, ActuatorError> {
+        let mut _self = Self {
+            _velocity_max: Some(velocity_max),
+            _velocity_min: None,
+            _acceleration_max: Some(acceleration_max),
+            _deceleration_max: None,
+            _jolt_max: None,
+            _jolt_table: None,
+
+            last_accel: RadPerSecond2::ZERO,
+            _microsteps: MicroSteps::default(),
+            _step_angle: consts.step_angle(MicroSteps::default()),
+            _dir: Direction::default(),
+
+            mode: DriveMode::Inactive,
+            commands: CommandQueue::default(),
+
+            speed_levels: Vec::new(),
+            time_sums: Vec::new(),
+            times: Vec::new(),
+            max_speed_level: None,
+            current_speed_level: 0,
+            stop_steps: 0,
+            stop_deceleration: None,
+
+            distance: 0,
+            distance_counter: 0,
+
+            consts
+        };
+
+        _self.update()?;
+
+        Ok(_self)
+    }
+
     /// Updates the builders speed levels and times considering loads etc.
     pub fn update(&mut self) -> Result<(), ActuatorError> {
         // Store relevant values
@@ -60,6 +106,8 @@ impl FreeBuilder {
         let mut times : Vec<Seconds> = Vec::new();
 
         let mut velocity_current = RadPerSecond::ZERO;
+        // The speed levels start at standstill
+        self.last_accel = RadPerSecond2::ZERO;
 
         // Iterate to max speed level or until the cap is reached
         for _ in 0 .. max_speed_level {
@@ -69,7 +117,7 @@ impl FreeBuilder {
             let ( mut move_time, _ ) = sykin::kin2::time_for_distance::<Rotary>(self.step_angle(), velocity_current, accel_possible);
 
             // Consider maximum jolt if set
-            if let Some(jolt_max) = self.jolt_max() {
+            if let Some(jolt_max) = self.jolt_at(velocity_current) {
                 // Only correct if the acceleration has exeeded the jolt value
                 if ((accel_possible - self.last_accel) / move_time) > jolt_max {
                     // Heavy calculation of a cubic formula
@@ -111,10 +159,82 @@ impl FreeBuilder {
         Ok(())
     }
 
-    /// Stops the builder with the given drivemode
+    /// Stops the builder, the given drivemode is queued and executed once the builder has come to a standstill
     pub fn stop_with_mode(&mut self, mode : DriveMode) {
         self.mode = DriveMode::Stop;
-        self.cached_mode = Some(mode);
+        self.commands.push(mode);
+    }
+
+    /// Moves on to the next queued command once a continuous movement has reached its target velocity
+    /// 
+    /// Commands in the same direction are taken over directly, all others require the builder to stop first
+    fn advance_queue(&mut self) {
+        let same_dir = match self.commands.peek() {
+            Some(DriveMode::ConstVelocity(velocity)) => velocity.get_direction() == self._dir,
+            Some(DriveMode::ConstFactor(_, dir)) => *dir == self._dir,
+            Some(_) => false,
+            None => return
+        };
+
+        if same_dir {
+            if let Some(mode) = self.commands.pop() {
+                self.mode = mode;
+            }
+        } else {
+            self.mode = DriveMode::Stop;
+        }
+    }
+
+    /// Applies the drive mode directly, continuous movements in the opposite direction stop the builder first
+    fn apply_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
+        let (steps, dir) = match mode {
+            DriveMode::FixedDistance(rel_dist, _, _) => steps_for_distance(rel_dist, self._step_angle),
+            _ => (0, self._dir)
+        };
+
+        match mode {
+            DriveMode::ConstVelocity(velocity) => {
+                let dir = velocity.get_direction();
+
+                if (self.current_speed_level > 0) & (dir != self._dir) {
+                    // Turn around motor
+                    self.stop_with_mode(mode);
+                    return Ok(());
+                } 
+                
+                self._dir = dir;
+                ctrl.set_dir(dir)?;
+            },
+            DriveMode::ConstFactor(_, dir) => {
+                if (self.current_speed_level > 0) & (dir != self._dir) {
+                    // Turn around motor
+                    self.stop_with_mode(mode);
+                    return Ok(());
+                } 
+                
+                self._dir = dir;
+                ctrl.set_dir(dir)?;
+            },
+            DriveMode::FixedDistance(_, _, _) if steps == 0 => {
+                // Sub-step movements are no-ops
+                return Ok(());
+            },
+            DriveMode::FixedDistance(_, _, _) => {
+                if steps < self.current_speed_level as u64 {
+                    return Err(ActuatorError::InvaldRelativeDistance(self.step_angle()))
+                }
+
+                self.distance = steps;
+                self.distance_counter = 0;
+
+                self._dir = dir;
+                ctrl.set_dir(dir)?;
+            },
+            _ => { }
+        };
+
+        self.mode = mode;
+        Ok(())
     }
 
     /// Moves the builder towards the next speed-level closer to the desired velocity `vel_tar`
@@ -141,6 +261,39 @@ impl FreeBuilder {
         }
     }
 
+    /// Limits the velocity `vel` to the ramp-down with the given `deceleration`, so the motor comes to a standstill within
+    /// the `remaining` steps. Once the ramp-down has started, the speed level follows it until the standstill
+    fn ramp_down(&mut self, vel : RadPerSecond, deceleration : RadPerSecond2, remaining : u64) -> RadPerSecond {
+        let vel_stop = velocity_for_stop(self.step_angle(), deceleration, remaining);
+
+        if vel > vel_stop {
+            self.current_speed_level = self.speed_levels.iter().take_while(|&&level| level <= vel_stop).count();
+            self.stop_steps = remaining - 1;
+            vel_stop
+        } else {
+            vel
+        }
+    }
+
+    /// The next step of a stop ramp with the given `deceleration`
+    fn stop_step(&mut self, deceleration : RadPerSecond2) -> RadPerSecond {
+        if self.current_speed_level == 0 {
+            return RadPerSecond::ZERO;
+        }
+
+        if self.stop_steps == 0 {
+            self.stop_steps = steps_for_stop(self.step_angle(), deceleration, self.velocity_current()).max(1);
+        }
+
+        let vel = self.ramp_down(RadPerSecond::INFINITY, deceleration, self.stop_steps);
+
+        if self.stop_steps == 0 {
+            self.current_speed_level = 0;
+        }
+
+        vel
+    }
+
     // RadPerSecond
         /// The current velocity of the builder
         pub fn velocity_current(&self) -> RadPerSecond {
@@ -152,6 +305,7 @@ impl FreeBuilder {
         /// Returns the cap velocity
         /// - Is either the cap velocity given by the user
         /// - Or the maximum recommended velocity for a stepper motor
+        /// 
         /// depends on which is lower
         pub fn velocity_cap(&self) -> RadPerSecond {
             self._velocity_max.unwrap_or(RadPerSecond::INFINITY)
@@ -165,6 +319,16 @@ impl FreeBuilder {
         }
     // 
 
+    // RadPerSecond3
+        /// The jolt limit at the given `velocity`, taken from the [JoltTable] if the velocity lies in one of its bands, the 
+        /// global jolt limit is used otherwise
+        pub fn jolt_at(&self, velocity : RadPerSecond) -> Option<RadPerSecond3> {
+            self._jolt_table.as_ref()
+                .and_then(|table| table.jolt_at(velocity))
+                .or(self._jolt_max)
+        }
+    // 
+
     // RadPerSecond2
         /// Returns the maximum acceleration possible by the motor or allowed by to user, depending on which one is lower
         pub fn acceleration_possible(&self) -> RadPerSecond2 {
@@ -178,26 +342,73 @@ impl Iterator for FreeBuilder {
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut vel_opt = match self.mode {
-            DriveMode::ConstVelocity(velocity ) => self.goto_velocity(velocity).ok(),
-            DriveMode::ConstFactor(factor, _) => self.goto_velocity(self.velocity_possible() * factor).ok(),
+            DriveMode::ConstVelocity(velocity) => {
+                let vel_tar = raise_to_min(velocity.abs(), self._velocity_min);
+                let vel_opt = self.goto_velocity(vel_tar).ok();
+
+                if vel_opt == Some(vel_tar) {
+                    self.advance_queue();
+                }
+
+                vel_opt
+            },
+            DriveMode::ConstFactor(factor, _) => {
+                let vel_tar = raise_to_min(self.velocity_possible() * factor, self._velocity_min);
+                let vel_opt = self.goto_velocity(vel_tar).ok();
+
+                if vel_opt == Some(vel_tar) {
+                    self.advance_queue();
+                }
+
+                vel_opt
+            },
             DriveMode::FixedDistance(_, _, factor) => {
                 self.distance_counter += 1;
 
                 // Special case with only one node
                 if (self.distance == 1) & (self.distance_counter == 1) {
-                    return self.times.first().map(|v| *v);
+                    return self.times.first().copied();
                 }
 
-                if ((self.distance_counter + self.current_speed_level as u64) == self.distance) & ((self.distance % 2) == 1) {
+                if let Some(deceleration) = self._deceleration_max {
+                    // Every step is limited to the velocity the motor is still able to stop from in the remaining distance
+                    if self.distance_counter > self.distance {
+                        self.current_speed_level = 0;
+                        None
+                    } else {
+                        let remaining = self.distance - self.distance_counter + 1;
+
+                        if self.stop_steps > 0 {
+                            Some(self.ramp_down(RadPerSecond::INFINITY, deceleration, remaining))
+                        } else {
+                            self.goto_velocity(raise_to_min(self.velocity_possible() * factor, self._velocity_min)).ok()
+                                .map(|vel| self.ramp_down(vel, deceleration, remaining))
+                        }
+                    }
+                } else if ((self.distance_counter + self.current_speed_level as u64) == self.distance) & ((self.distance % 2) == 1) {
                     Some(self.speed_levels[self.current_speed_level.saturating_sub(1)])
                 } else if (self.distance_counter + self.current_speed_level as u64) > self.distance {
                     self.goto_velocity(RadPerSecond::ZERO).ok()
                 } else {
-                    self.goto_velocity(self.velocity_possible() * factor).ok()
+                    self.goto_velocity(raise_to_min(self.velocity_possible() * factor, self._velocity_min)).ok()
                 }
             },
             DriveMode::Stop => {
-                self.goto_velocity(RadPerSecond::ZERO).ok()
+                let vel_opt = match self.stop_deceleration.or(self._deceleration_max) {
+                    Some(deceleration) => Some(self.stop_step(deceleration)),
+                    None => self.goto_velocity(RadPerSecond::ZERO).ok()
+                };
+
+                // Standstill reached, the builder stays in stop mode if commands are waiting for `resume_queued()`
+                if vel_opt.unwrap_or(RadPerSecond::ZERO) == RadPerSecond::ZERO {
+                    self.stop_deceleration = None;
+
+                    if self.commands.is_empty() {
+                        self.mode = DriveMode::Inactive;
+                    }
+                }
+
+                vel_opt
             },
             DriveMode::Inactive => None
         };
@@ -208,6 +419,11 @@ impl Iterator for FreeBuilder {
             }
         }
 
+        // Fixed distance movements are finished once no further step is required
+        if vel_opt.is_none() && matches!(self.mode, DriveMode::FixedDistance(_, _, _)) {
+            self.mode = DriveMode::Inactive;
+        }
+
         vel_opt.map(|vel| self.consts.step_time(vel, self._microsteps))
     }
 }
@@ -244,6 +460,7 @@ impl StepperBuilder for FreeBuilder {
         fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
             if let Some(velocity) = velocity_opt {
                 if velocity.is_normal() {
+                    check_velocity_max(velocity, self._velocity_min)?;
                     self._velocity_max = Some(velocity.abs()); 
                     self.update()
                 } else {
@@ -254,6 +471,20 @@ impl StepperBuilder for FreeBuilder {
                 Ok(())
             }
         }
+
+        #[inline]
+        fn velocity_min(&self) -> Option<RadPerSecond> {
+            self._velocity_min
+        }
+
+        fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+            self._velocity_min = check_velocity_min(velocity_opt, self._velocity_max)?;
+            Ok(())
+        }
+
+        fn velocity_possible(&self) -> RadPerSecond {
+            FreeBuilder::velocity_possible(self)
+        }
     //
 
     // RadPerSecond2
@@ -275,6 +506,25 @@ impl StepperBuilder for FreeBuilder {
                 Ok(())
             }
         }
+
+        #[inline]
+        fn deceleration_max(&self) -> Option<RadPerSecond2> {
+            self._deceleration_max
+        }
+
+        fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+            if let Some(deceleration) = deceleration_opt {
+                if deceleration.is_normal() {
+                    self._deceleration_max = Some(deceleration.abs()); 
+                    self.update()
+                } else {
+                    Err(ActuatorError::InvalidAcceleration(deceleration))
+                }
+            } else {
+                self._deceleration_max = None;
+                Ok(())
+            }
+        }
     // 
 
     // RadPerSecond3 
@@ -296,6 +546,15 @@ impl StepperBuilder for FreeBuilder {
                 Ok(())
             }
         }
+
+        fn jolt_table(&self) -> Option<&JoltTable> {
+            self._jolt_table.as_ref()
+        }
+
+        fn set_jolt_table(&mut self, table_opt : Option<JoltTable>) -> Result<(), ActuatorError> {
+            self._jolt_table = table_opt;
+            self.update()
+        }
     // 
 
     fn drive_mode(&self) -> &DriveMode {
@@ -303,55 +562,73 @@ impl StepperBuilder for FreeBuilder {
     }
 
     fn set_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
-        match mode {
-            DriveMode::ConstVelocity(mut velocity) => {
-                let dir = velocity.get_direction();
-                velocity = velocity.abs();
-
-                if velocity > self.velocity_possible() {
-                    return Err(ActuatorError::VelocityTooHigh(velocity, self.velocity_possible()))
-                } 
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive) {
+            check_velocity_possible(self._velocity_min, self.velocity_possible())?;
+        }
 
-                if self.mode != DriveMode::Inactive {
-                    // Turn around motor
-                    self.stop_with_mode(mode.clone());
-                } else {
-                    self._dir = dir;
-                    ctrl.set_dir(dir)?;
-                }
+        match mode {
+            DriveMode::ConstVelocity(velocity) | DriveMode::FixedDistance(_, velocity, _) if velocity.abs() > self.velocity_possible() => {
+                return Err(ActuatorError::VelocityTooHigh(velocity.abs(), self.velocity_possible()))
             },
-            DriveMode::ConstFactor(_, dir) => {
-                if (self.mode != DriveMode::Inactive) & (dir != self._dir) {
-                    // Turn around motor
-                    self.stop_with_mode(mode.clone());
-                } else {
-                    self._dir = dir;
-                    ctrl.set_dir(dir)?;
-                }
+            DriveMode::Stop | DriveMode::Inactive => {
+                // Stopping discards all commands waiting and the deceleration of the last stop
+                self.commands.clear();
+                self.stop_deceleration = None;
             },
-            DriveMode::FixedDistance(rel_dist, velocity_exit, _) => {
-                if velocity_exit > self.velocity_possible() {
-                    return Err(ActuatorError::VelocityTooHigh(velocity_exit, self.velocity_possible()))
-                }
+            _ => { }
+        }
 
-                self.distance = self.consts.steps_from_angle_abs(rel_dist, self._microsteps);
-                self.distance_counter = 0;
+        // Commands arriving during a stop ramp or while other commands are waiting are queued
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive) 
+            & (((self.mode == DriveMode::Stop) & (self.current_speed_level > 0)) | !self.commands.is_empty()) 
+        {
+            self.commands.push(mode);
+            return Ok(());
+        }
 
-                if self.distance < self.current_speed_level as u64 {
-                    return Err(ActuatorError::InvaldRelativeDistance(self.step_angle()))
-                }
+        self.apply_drive_mode(mode, ctrl)
+    }
+    
+    // Commands
+        fn command_policy(&self) -> CommandPolicy {
+            self.commands.policy()
+        }
 
-                if rel_dist >= Radians::ZERO {
-                    self._dir = Direction::CW;
-                } else {
-                    self._dir = Direction::CCW;
-                }
-                ctrl.set_dir(self._dir)?;
-            },
-            _ => { }
-        };
+        fn set_command_policy(&mut self, policy : CommandPolicy) {
+            self.commands.set_policy(policy)
+        }
 
-        self.mode = mode;
-        Ok(())
-    }
+        fn queued_commands(&self) -> usize {
+            self.commands.len()
+        }
+
+        fn resume_queued<C : StepperController>(&mut self, ctrl : &mut C) -> Result<bool, ActuatorError> {
+            if self.current_speed_level > 0 {
+                return Ok(false);
+            }
+
+            if let Some(mode) = self.commands.pop() {
+                self.apply_drive_mode(mode, ctrl)?;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+    //
+
+    // Stops
+        fn stop_with_deceleration(&mut self, deceleration : RadPerSecond2) -> bool {
+            self.stop_deceleration = Some(deceleration);
+            self.stop_steps = 0;
+            true
+        }
+
+        fn reset_ramp(&mut self) {
+            self.current_speed_level = 0;
+            self.stop_steps = 0;
+            self.stop_deceleration = None;
+
+            self.mode = if self.commands.is_empty() { DriveMode::Inactive } else { DriveMode::Stop };
+        }
+    //
 }
\ No newline at end of file
@@ -0,0 +1,107 @@
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+
+#[cfg(feature = "alloc")]
+use super::DriveMode;
+
+/// Defines how a builder handles commands that arrive while the motor has to ramp down first, e.g. a direction flip
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommandPolicy {
+    /// Only the latest command is kept, it replaces any command waiting for the stop ramp to finish
+    #[default]
+    LatestWins,
+    /// Commands are executed in the order they arrived, continuous commands are held until their target velocity has
+    /// been reached before the next command is executed
+    /// - 0 - `usize`: The capacity of the queue, if it is full the latest command replaces the last one waiting
+    Queue(usize)
+}
+
+/// The commands waiting for the stop ramp of a builder to finish
+///
+/// # Replacement rules
+///
+/// - [DriveMode::Stop] and [DriveMode::Inactive] are never queued, setting them discards all waiting commands
+/// - [CommandPolicy::LatestWins]: A new command replaces the waiting one
+/// - [CommandPolicy::Queue]: A new command is appended, if the queue is full it replaces the last command instead, so the
+///   latest command is always executed
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct CommandQueue {
+    policy : CommandPolicy,
+    commands : VecDeque<DriveMode>
+}
+
+#[cfg(feature = "alloc")]
+impl CommandQueue {
+    /// Creates a new empty queue with the given `policy`
+    pub fn new(policy : CommandPolicy) -> Self {
+        Self {
+            policy,
+            commands: VecDeque::new()
+        }
+    }
+
+    /// The policy of the queue
+    pub fn policy(&self) -> CommandPolicy {
+        self.policy
+    }
+
+    /// Sets the policy of the queue, commands exceeding the new capacity are dropped starting with the oldest
+    pub fn set_policy(&mut self, policy : CommandPolicy) {
+        self.policy = policy;
+
+        while self.commands.len() > self.capacity() {
+            self.commands.pop_front();
+        }
+    }
+
+    /// The maximum amount of commands waiting
+    pub fn capacity(&self) -> usize {
+        match self.policy {
+            CommandPolicy::LatestWins => 1,
+            CommandPolicy::Queue(capacity) => capacity.max(1)
+        }
+    }
+
+    /// Adds a command following the replacement rules of the queue, returns the command that has been replaced if there is one
+    pub fn push(&mut self, mode : DriveMode) -> Option<DriveMode> {
+        if matches!(mode, DriveMode::Stop | DriveMode::Inactive) {
+            self.commands.clear();
+            return None;
+        }
+
+        let replaced = if self.commands.len() >= self.capacity() {
+            self.commands.pop_back()
+        } else {
+            None
+        };
+
+        self.commands.push_back(mode);
+        replaced
+    }
+
+    /// The next command to execute
+    pub fn peek(&self) -> Option<&DriveMode> {
+        self.commands.front()
+    }
+
+    /// Removes the next command from the queue and returns it
+    pub fn pop(&mut self) -> Option<DriveMode> {
+        self.commands.pop_front()
+    }
+
+    /// Discards all waiting commands
+    pub fn clear(&mut self) {
+        self.commands.clear()
+    }
+
+    /// The amount of commands waiting
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns `true` if no commands are waiting
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
@@ -6,7 +6,8 @@ use crate::sync::stepper::StepperController;
 use crate::sync::stepper::builder::AdvancedStepperBuilder;
 use crate::data::{ActuatorVars, MicroSteps};
 
-use super::{DriveMode, StepperBuilder, ActuatorError};
+use super::{DriveMode, StepperBuilder, ActuatorError, VelocityConstraint, check_velocity_max, check_velocity_min, 
+    check_velocity_possible, raise_to_min, steps_for_distance};
 
 
 /// ##########################
@@ -32,7 +33,9 @@ pub struct StartStopBuilder {
 
     // Limits
     _velocity_max : Option<RadPerSecond>,
+    _velocity_min : Option<RadPerSecond>,
     _acceleration_max : Option<RadPerSecond2>,
+    _deceleration_max : Option<RadPerSecond2>,
     _jolt_max : Option<RadPerSecond3>,
 
     _microsteps : MicroSteps,   
@@ -61,8 +64,10 @@ impl StartStopBuilder {
         /// 
         /// Returns `None` if no maximum jolt is defined
         pub fn acceleration_by_max_jolt(&self) -> Option<RadPerSecond2> {
+            // Acceleration reached after the first step when ramping up with the jolt only, written out to keep the
+            // minimal motion path free of `sykin`
             self.jolt_max().map(|jolt_max| {
-                sykin::kin3::acceleration_for_distance_only_jolt::<Rotary>(self.step_angle(), jolt_max)
+                RadPerSecond2((6.0 * self.step_angle().0 * jolt_max.0 * jolt_max.0).cbrt())
             })
         }
 
@@ -74,9 +79,11 @@ impl StartStopBuilder {
     // 
 
     // RadPerSecond helpers
-        /// The maximum velocity that can be reached with the specified acceleration (will result in infinity if no limits are set)
+        /// The maximum velocity that can be reached with the specified acceleration and stopped from with the specified
+        /// deceleration (will result in infinity if no limits are set)
         pub fn velocity_by_max_acceleration(&self) -> RadPerSecond {
-            sykin::kin2::velocity_for_distance_no_vel0::<Rotary>(self.step_angle(), self.acceleration_allowed())
+            let acceleration = self.acceleration_allowed().min(self._deceleration_max.unwrap_or(RadPerSecond2::INFINITY));
+            RadPerSecond((2.0 * self.step_angle().0 * acceleration.0).sqrt())
         }
 
         /// The maximum velocity that is currently possible, defined by numerous factors like maximum jolt, acceleration, velocity and start-stop mechanics
@@ -96,8 +103,8 @@ impl Iterator for StartStopBuilder {
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.mode {
-            // Contant velocity just yields the velocity, the check if the velocity is possible already happened in `set_drive_mode()`
-            DriveMode::ConstVelocity(velocity ) => Some(velocity),
+            // Contant velocity just yields the velocity (the direction has been set already), the check if the velocity is possible already happened in `set_drive_mode()`
+            DriveMode::ConstVelocity(velocity ) => Some(velocity.abs()),
             // Drive a constant factor of the maximum possible velocity
             DriveMode::ConstFactor(factor, _) => Some(self.velocity_possible() * factor),
             // Continue driving until the distance is reached
@@ -118,7 +125,7 @@ impl Iterator for StartStopBuilder {
             },
             // Inactive, no more nodes needed
             DriveMode::Inactive => None
-        }.map(|velocity | self._consts.step_time(velocity , self._microsteps))
+        }.map(|velocity| self._consts.step_time(raise_to_min(velocity, self._velocity_min), self._microsteps))
     }
 }
 
@@ -154,6 +161,7 @@ impl StepperBuilder for StartStopBuilder {
         fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
             if let Some(velocity) = velocity_opt {
                 if velocity.is_normal() {
+                    check_velocity_max(velocity, self._velocity_min)?;
                     self._velocity_max = Some(velocity.abs()); 
                     self.update_start_stop()
                 } else {
@@ -164,6 +172,20 @@ impl StepperBuilder for StartStopBuilder {
                 Ok(())
             }
         }
+
+        #[inline]
+        fn velocity_min(&self) -> Option<RadPerSecond> {
+            self._velocity_min
+        }
+
+        fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+            self._velocity_min = check_velocity_min(velocity_opt, self._velocity_max)?;
+            Ok(())
+        }
+
+        fn velocity_possible(&self) -> RadPerSecond {
+            StartStopBuilder::velocity_possible(self)
+        }
     //
 
     // RadPerSecond2
@@ -185,6 +207,25 @@ impl StepperBuilder for StartStopBuilder {
                 Ok(())
             }
         }
+
+        #[inline]
+        fn deceleration_max(&self) -> Option<RadPerSecond2> {
+            self._deceleration_max
+        }
+
+        fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+            if let Some(deceleration) = deceleration_opt {
+                if deceleration.is_normal() {
+                    self._deceleration_max = Some(deceleration.abs()); 
+                    self.update_start_stop()
+                } else {
+                    Err(ActuatorError::InvalidAcceleration(deceleration))
+                }
+            } else {
+                self._deceleration_max = None;
+                Ok(())
+            }
+        }
     // 
 
     // RadPerSecond3 
@@ -214,6 +255,10 @@ impl StepperBuilder for StartStopBuilder {
     }
 
     fn set_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive) {
+            check_velocity_possible(self._velocity_min, self.velocity_possible())?;
+        }
+
         match mode {
             // Driving with a constant velocity, check if the velocity is possible, return error if it is not
             DriveMode::ConstVelocity(mut velocity) => {
@@ -238,15 +283,18 @@ impl StepperBuilder for StartStopBuilder {
                     return Err(ActuatorError::VelocityTooHigh(velocity_exit, self.velocity_possible()))
                 }
 
-                self.distance = self._consts.steps_from_angle_abs(rel_dist, self._microsteps);
-                self.distance_counter = 0;
+                let (steps, dir) = steps_for_distance(rel_dist, self._step_angle);
 
-                if rel_dist >= Radians::ZERO {
-                    self._direction = Direction::CW;
-                } else {
-                    self._direction = Direction::CCW;
+                // Sub-step movements are no-ops
+                if steps == 0 {
+                    return Ok(());
                 }
-                ctrl.set_dir(self._direction)?;
+
+                self.distance = steps;
+                self.distance_counter = 0;
+
+                self._direction = dir;
+                ctrl.set_dir(dir)?;
             },
             _ => { }
         };
@@ -254,6 +302,23 @@ impl StepperBuilder for StartStopBuilder {
         self.mode = mode;
         Ok(())
     }
+
+    // The builder stops instantly and never queues commands
+    fn reset_ramp(&mut self) {
+        self.mode = DriveMode::Inactive;
+    }
+
+    fn velocity_constraint(&self) -> Option<VelocityConstraint> {
+        let velocity = self.velocity_possible();
+
+        Some(if self._velocity_max == Some(velocity) {
+            VelocityConstraint::UserCap
+        } else if self.velocity_start_stop == velocity {
+            VelocityConstraint::StartStop
+        } else {
+            VelocityConstraint::Ramp
+        })
+    }
 }
 
 // Extension traits
@@ -270,7 +335,9 @@ impl StepperBuilder for StartStopBuilder {
                     velocity_start_stop: RadPerSecond::INFINITY,
     
                     _velocity_max: None,
+                    _velocity_min: None,
                     _acceleration_max: None,
+                    _deceleration_max: None,
                     _jolt_max: None,
     
                     _step_angle: consts.step_angle(MicroSteps::default()),
@@ -315,6 +382,11 @@ impl StepperBuilder for StartStopBuilder {
                 self._config.overload_current = current;
                 self.update_start_stop()        // Overload current affects start stop velocity, recalculate
             }
+
+            fn set_drive_current(&mut self, current : Option<f32>) -> Result<(), ActuatorError> {
+                self._config.drive_current = current;
+                self.update_start_stop()        // Drive current affects start stop velocity, recalculate
+            }
         //
 
         // Loads
@@ -339,7 +411,7 @@ impl StepperBuilder for StartStopBuilder {
 // Math
     impl DefinedActuator for StartStopBuilder {
         fn ptp_time_for_distance(&self, abs_pos_0 : PositionRad, abs_pos_t : PositionRad) -> Seconds {
-            (abs_pos_t - abs_pos_0) / self.velocity_possible()
+            (abs_pos_t - abs_pos_0).abs() / self.velocity_possible()
         }
     }
 // 
\ No newline at end of file
@@ -0,0 +1,628 @@
+use syunit::*;
+use syunit::metric::*;
+
+use crate::{StepperConst, StepperConfig, DefinedActuator};
+use crate::data::{ActuatorVars, MicroSteps};
+use crate::math::kin;
+use crate::sync::stepper::StepperController;
+use crate::sync::stepper::builder::AdvancedStepperBuilder;
+
+use super::{CommandPolicy, CommandQueue, DriveMode, StepperBuilder, ActuatorError, VelocityConstraint, check_velocity_max, 
+    check_velocity_min, check_velocity_possible, raise_to_min, steps_for_distance};
+
+/// ###########################
+/// #    TrapezoidalBuilder    #
+/// ###########################
+///
+/// A builder that ramps purely with the velocity, acceleration and jolt limits given by the user
+///
+/// - Predictable movements, e.g. "move 100 mm at 50 mm/s with 200 mm/s² acceleration"
+/// - S-curve ramps if a jolt limit is set
+/// - Requires an acceleration limit
+///
+/// The torque model of the motor is ignored unless enabled with [TrapezoidalBuilder::set_torque_model], the limits then
+/// additionally get capped by the torque and back-EMF of the motor like for the [ComplexBuilder](super::ComplexBuilder).
+///
+/// The ramps are calculated step by step with constant acceleration. A jolt limit rounds the ramps towards a target
+/// velocity, fixed distance movements ramp down along a jolt-limited stop curve.
+#[derive(Debug)]
+pub struct TrapezoidalBuilder {
+    // Data
+    _consts : StepperConst,
+    _vars : ActuatorVars,
+    _config : StepperConfig,
+
+    // Limits
+    _velocity_max : Option<RadPerSecond>,
+    _velocity_min : Option<RadPerSecond>,
+    _acceleration_max : Option<RadPerSecond2>,
+    _deceleration_max : Option<RadPerSecond2>,
+    _jolt_max : Option<RadPerSecond3>,
+    _torque_model : bool,
+
+    // State
+    _velocity : RadPerSecond,
+    _acceleration : RadPerSecond2,
+    _microsteps : MicroSteps,
+    _step_angle : Radians,
+    _direction : Direction,
+
+    // Modes
+    mode : DriveMode,
+    commands : CommandQueue,
+    /// Deceleration of the stop ramp requested with [StepperBuilder::stop_with_deceleration]
+    stop_deceleration : Option<RadPerSecond2>,
+
+    // Step counters
+    distance : u64,
+    distance_counter : u64
+}
+
+impl TrapezoidalBuilder {
+    // Torque model
+        /// Returns `true` if the limits are additionally capped by the torque model of the motor
+        pub fn torque_model(&self) -> bool {
+            self._torque_model
+        }
+
+        /// Enables or disables the torque model of the motor, disabled by default
+        pub fn set_torque_model(&mut self, enabled : bool) {
+            self._torque_model = enabled;
+        }
+    //
+
+    // RadPerSecond
+        /// The current velocity of the builder
+        pub fn velocity_current(&self) -> RadPerSecond {
+            self._velocity
+        }
+
+        /// The maximum velocity that is currently possible, the velocity limit of the user or the maximum velocity of the
+        /// motor if no limit is set. The latter always caps the velocity if the torque model is enabled
+        pub fn velocity_possible(&self) -> RadPerSecond {
+            let velocity_motor = self._consts.velocity_max_with_margin(&self._config);
+
+            match self._velocity_max {
+                Some(velocity) if self._torque_model => velocity.min(velocity_motor),
+                Some(velocity) => velocity,
+                None => velocity_motor
+            }
+        }
+
+        /// The highest velocity the motor can come to a standstill from within the given amount of `steps` with the given
+        /// `deceleration`, considering the jolt limit
+        pub fn velocity_for_stop(&self, deceleration : RadPerSecond2, steps : u64) -> RadPerSecond {
+            let dist = self._step_angle.0 * steps as f32;
+            let decel = deceleration.0;
+
+            RadPerSecond(if let Some(jolt) = self._jolt_max.map(|jolt| jolt.0) {
+                if dist <= decel * decel * decel / (jolt * jolt) {
+                    // The deceleration limit is never reached, dist = v * sqrt(v / jolt)
+                    (dist * jolt.sqrt()).powf(2.0 / 3.0)
+                } else {
+                    // dist = v / 2 * (v / decel + decel / jolt)
+                    let offset = decel * decel / jolt;
+                    (-offset + (offset * offset + 8.0 * decel * dist).sqrt()) / 2.0
+                }
+            } else {
+                (2.0 * decel * dist).sqrt()
+            })
+        }
+    //
+
+    // RadPerSecond2
+        /// The acceleration allowed at the given `velocity`, returns an error if no acceleration limit is set and the torque
+        /// model is disabled
+        pub fn acceleration_allowed(&self, velocity : RadPerSecond) -> Result<RadPerSecond2, ActuatorError> {
+            self.limit_with_torque(self._acceleration_max, velocity)
+        }
+
+        /// The deceleration allowed at the given `velocity`, the acceleration limit is used if no deceleration limit is set. 
+        /// Stops requested with [StepperBuilder::stop_with_deceleration] use their own deceleration
+        pub fn deceleration_allowed(&self, velocity : RadPerSecond) -> Result<RadPerSecond2, ActuatorError> {
+            self.limit_with_torque(self.stop_deceleration.or(self._deceleration_max).or(self._acceleration_max), velocity)
+        }
+
+        fn limit_with_torque(&self, limit : Option<RadPerSecond2>, velocity : RadPerSecond) -> Result<RadPerSecond2, ActuatorError> {
+            if self._torque_model {
+                let torque = self._consts.acceleration_max_for_velocity_microsteps(&self._vars, &self._config, velocity, self._direction, self._microsteps)
+                    .ok_or(ActuatorError::Overload)?;
+
+                Ok(limit.map_or(torque, |limit| limit.min(torque)))
+            } else {
+                limit.ok_or(ActuatorError::InvalidConfig("The TrapezoidalBuilder requires an acceleration limit if the torque model is disabled"))
+            }
+        }
+    //
+
+    /// Calculates the time of the next step, changing the velocity towards `vel_tar`. For fixed distance movements
+    /// `remaining` is the amount of steps left after this one
+    fn step_towards(&mut self, vel_tar : RadPerSecond, remaining : Option<u64>) -> Result<Seconds, ActuatorError> {
+        let step_angle = self._step_angle;
+        let vel = self._velocity;
+        let accelerating = vel_tar > vel;
+
+        let mut accel = if accelerating {
+            self.acceleration_allowed(vel)?
+        } else {
+            self.deceleration_allowed(vel)?
+        };
+
+        if let Some(jolt) = self._jolt_max {
+            // Round the end of the ramp, the acceleration has to come down to zero when the target velocity is reached
+            accel = accel.min(RadPerSecond2((2.0 * jolt.0 * (vel_tar - vel).abs().0).sqrt()));
+        }
+
+        let sign = if accelerating { 1.0 } else { -1.0 };
+        let (mut vel_next, time) = const_accel_step(step_angle, vel, accel * sign, vel_tar);
+
+        if let Some(jolt) = self._jolt_max {
+            // Round the start of the ramp, the acceleration in the direction of the velocity change grows with the jolt
+            let accel_prev = self._acceleration * sign;
+
+            if (accel - accel_prev) > jolt * time {
+                let accel_jolt = (accel_prev + jolt * time).max(RadPerSecond2::ZERO);
+                vel_next = const_accel_step(step_angle, vel, accel_jolt * sign, vel_tar).0;
+            }
+        }
+
+        if let Some(remaining) = remaining {
+            // Blended movements only slow down to their exit velocity
+            let vel_exit = match self.mode {
+                DriveMode::FixedDistance(_, vel_exit, _) => vel_exit.abs(),
+                _ => RadPerSecond::ZERO
+            };
+            let vel_stop = self.velocity_for_stop(self.deceleration_allowed(vel)?, remaining);
+
+            vel_next = vel_next.min(RadPerSecond((vel_stop.0 * vel_stop.0 + vel_exit.0 * vel_exit.0).sqrt()));
+        }
+
+        let time = if (vel + vel_next) > RadPerSecond::ZERO {
+            2.0 * step_angle / (vel + vel_next)
+        } else {
+            // Single step movement starting and ending at a standstill
+            Seconds(2.0 * (step_angle.0 / accel.0).sqrt())
+        };
+
+        self._acceleration = (vel_next - vel) / time;
+        self._velocity = vel_next;
+
+        Ok(time)
+    }
+
+    /// Stops the builder, the given drivemode is queued and executed once the builder has come to a standstill
+    pub fn stop_with_mode(&mut self, mode : DriveMode) {
+        self.mode = DriveMode::Stop;
+        self.commands.push(mode);
+    }
+
+    /// Moves on to the next queued command once a continuous movement has reached its target velocity
+    ///
+    /// Commands in the same direction are taken over directly, all others require the builder to stop first
+    fn advance_queue(&mut self) {
+        let same_dir = match self.commands.peek() {
+            Some(DriveMode::ConstVelocity(velocity)) => velocity.get_direction() == self._direction,
+            Some(DriveMode::ConstFactor(_, dir)) => *dir == self._direction,
+            Some(_) => false,
+            None => return
+        };
+
+        if same_dir {
+            if let Some(mode) = self.commands.pop() {
+                self.mode = mode;
+            }
+        } else {
+            self.mode = DriveMode::Stop;
+        }
+    }
+
+    /// Applies the drive mode directly, movements in the opposite direction stop the builder first
+    fn apply_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
+        let mut dir = match mode {
+            DriveMode::ConstVelocity(velocity) => velocity.get_direction(),
+            DriveMode::ConstFactor(_, dir) => dir,
+            DriveMode::FixedDistance(rel_dist, _, _) => if rel_dist >= Radians::ZERO { Direction::CW } else { Direction::CCW },
+            _ => {
+                self.mode = mode;
+                return Ok(());
+            }
+        };
+
+        if (self._velocity > RadPerSecond::ZERO) & (dir != self._direction) {
+            // Turn around motor
+            self.stop_with_mode(mode);
+            return Ok(());
+        }
+
+        if let DriveMode::FixedDistance(rel_dist, _, _) = mode {
+            let steps;
+            (steps, dir) = steps_for_distance(rel_dist, self._step_angle);
+
+            // Sub-step movements are no-ops
+            if steps == 0 {
+                return Ok(());
+            }
+
+            // The movement must leave enough room to ramp down from the current velocity
+            if self.velocity_for_stop(self.deceleration_allowed(self._velocity)?, steps) < self._velocity {
+                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+            }
+
+            self.distance = steps;
+            self.distance_counter = 0;
+        }
+
+        self._direction = dir;
+        ctrl.set_dir(dir)?;
+
+        self.mode = mode;
+        Ok(())
+    }
+}
+
+/// The velocity after moving by `step_angle` from `vel` with the constant `accel`, capped at the target velocity `vel_tar`,
+/// and the time required
+fn const_accel_step(step_angle : Radians, vel : RadPerSecond, accel : RadPerSecond2, vel_tar : RadPerSecond) -> (RadPerSecond, Seconds) {
+    let vel_next = RadPerSecond((vel.0 * vel.0 + 2.0 * accel.0 * step_angle.0).max(0.0).sqrt());
+
+    let vel_next = if accel >= RadPerSecond2::ZERO {
+        vel_next.min(vel_tar)
+    } else {
+        vel_next.max(vel_tar)
+    };
+
+    (vel_next, 2.0 * step_angle / (vel + vel_next))
+}
+
+impl Iterator for TrapezoidalBuilder {
+    type Item = Seconds;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (vel_tar, remaining) = match self.mode {
+            DriveMode::ConstVelocity(velocity) => (velocity.abs(), None),
+            DriveMode::ConstFactor(factor, _) => (self.velocity_possible() * factor, None),
+            DriveMode::FixedDistance(_, vel_exit, factor) => {
+                if self.distance_counter >= self.distance {
+                    // Blended movements keep their exit velocity for the next movement
+                    self.mode = DriveMode::Inactive;
+                    self._velocity = self._velocity.min(vel_exit.abs());
+                    self._acceleration = RadPerSecond2::ZERO;
+                    return None;
+                }
+
+                self.distance_counter += 1;
+                (self.velocity_possible() * factor, Some(self.distance - self.distance_counter))
+            },
+            DriveMode::Stop => {
+                // Standstill reached, the builder stays in stop mode if commands are waiting for `resume_queued()`
+                if self._velocity == RadPerSecond::ZERO {
+                    if self.commands.is_empty() {
+                        self.mode = DriveMode::Inactive;
+                    }
+
+                    self._acceleration = RadPerSecond2::ZERO;
+                    self.stop_deceleration = None;
+                    return None;
+                }
+
+                (RadPerSecond::ZERO, None)
+            },
+            DriveMode::Inactive => return None
+        };
+
+        let vel_tar = raise_to_min(vel_tar, self._velocity_min);
+        let time = self.step_towards(vel_tar, remaining).ok()?;
+
+        if matches!(self.mode, DriveMode::ConstVelocity(_) | DriveMode::ConstFactor(_, _)) & (self._velocity == vel_tar) {
+            self.advance_queue();
+        }
+
+        time.is_finite().then_some(time)
+    }
+}
+
+impl StepperBuilder for TrapezoidalBuilder {
+    // Getters
+        fn microsteps(&self) -> MicroSteps {
+            self._microsteps
+        }
+
+        fn step_angle(&self) -> Radians {
+            self._step_angle
+        }
+
+        fn direction(&self) -> Direction {
+            self._direction
+        }
+    //
+
+    // Setters
+        fn set_microsteps(&mut self, microsteps : MicroSteps) -> Result<(), ActuatorError> {
+            self._step_angle = self._consts.step_angle(microsteps);
+            self._microsteps = microsteps;
+            Ok(())
+        }
+    //
+
+    // RadPerSecond
+        #[inline]
+        fn velocity_max(&self) -> Option<RadPerSecond> {
+            self._velocity_max
+        }
+
+        fn set_velocity_max(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+            if let Some(velocity) = velocity_opt {
+                if velocity.is_normal() {
+                    check_velocity_max(velocity, self._velocity_min)?;
+                    self._velocity_max = Some(velocity.abs());
+                } else {
+                    return Err(ActuatorError::InvalidVelocity(velocity))
+                }
+            } else {
+                self._velocity_max = None;
+            }
+
+            Ok(())
+        }
+
+        #[inline]
+        fn velocity_min(&self) -> Option<RadPerSecond> {
+            self._velocity_min
+        }
+
+        fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+            self._velocity_min = check_velocity_min(velocity_opt, self._velocity_max)?;
+            Ok(())
+        }
+
+        fn velocity_possible(&self) -> RadPerSecond {
+            TrapezoidalBuilder::velocity_possible(self)
+        }
+    //
+
+    // RadPerSecond2
+        #[inline]
+        fn acceleration_max(&self) -> Option<RadPerSecond2> {
+            self._acceleration_max
+        }
+
+        fn set_acceleration_max(&mut self, acceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+            if let Some(acceleration) = acceleration_opt {
+                if acceleration.is_normal() {
+                    self._acceleration_max = Some(acceleration.abs());
+                } else {
+                    return Err(ActuatorError::InvalidAcceleration(acceleration))
+                }
+            } else {
+                self._acceleration_max = None;
+            }
+
+            Ok(())
+        }
+
+        #[inline]
+        fn deceleration_max(&self) -> Option<RadPerSecond2> {
+            self._deceleration_max
+        }
+
+        fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+            if let Some(deceleration) = deceleration_opt {
+                if deceleration.is_normal() {
+                    self._deceleration_max = Some(deceleration.abs());
+                } else {
+                    return Err(ActuatorError::InvalidAcceleration(deceleration))
+                }
+            } else {
+                self._deceleration_max = None;
+            }
+
+            Ok(())
+        }
+    //
+
+    // RadPerSecond3
+        #[inline]
+        fn jolt_max(&self) -> Option<RadPerSecond3> {
+            self._jolt_max
+        }
+
+        fn set_jolt_max(&mut self, jolt_opt : Option<RadPerSecond3>) -> Result<(), ActuatorError> {
+            if let Some(jolt) = jolt_opt {
+                if jolt.is_normal() {
+                    self._jolt_max = Some(jolt.abs());
+                } else {
+                    return Err(ActuatorError::InvalidJolt(jolt))
+                }
+            } else {
+                self._jolt_max = None;
+            }
+
+            Ok(())
+        }
+    //
+
+    #[inline]
+    fn drive_mode(&self) -> &DriveMode {
+        &self.mode
+    }
+
+    fn set_drive_mode<C : StepperController>(&mut self, mode : DriveMode, ctrl : &mut C) -> Result<(), ActuatorError> {
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive) {
+            check_velocity_possible(self._velocity_min, self.velocity_possible())?;
+        }
+
+        match mode {
+            DriveMode::ConstVelocity(velocity) | DriveMode::FixedDistance(_, velocity, _) if velocity.abs() > self.velocity_possible() => {
+                return Err(ActuatorError::VelocityTooHigh(velocity.abs(), self.velocity_possible()))
+            },
+            DriveMode::Stop | DriveMode::Inactive => {
+                // Stopping discards all commands waiting and the deceleration of the last stop
+                self.commands.clear();
+                self.stop_deceleration = None;
+            },
+            _ => {
+                // Fail early if the builder is not able to ramp
+                self.acceleration_allowed(self._velocity)?;
+            }
+        }
+
+        // Commands arriving during a stop ramp or while other commands are waiting are queued
+        if !matches!(mode, DriveMode::Stop | DriveMode::Inactive)
+            & (((self.mode == DriveMode::Stop) & (self._velocity > RadPerSecond::ZERO)) | !self.commands.is_empty())
+        {
+            self.commands.push(mode);
+            return Ok(());
+        }
+
+        self.apply_drive_mode(mode, ctrl)
+    }
+
+    // Commands
+        fn command_policy(&self) -> CommandPolicy {
+            self.commands.policy()
+        }
+
+        fn set_command_policy(&mut self, policy : CommandPolicy) {
+            self.commands.set_policy(policy)
+        }
+
+        fn queued_commands(&self) -> usize {
+            self.commands.len()
+        }
+
+        fn resume_queued<C : StepperController>(&mut self, ctrl : &mut C) -> Result<bool, ActuatorError> {
+            if self._velocity > RadPerSecond::ZERO {
+                return Ok(false);
+            }
+
+            if let Some(mode) = self.commands.pop() {
+                self.apply_drive_mode(mode, ctrl)?;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+    //
+
+    // Stops
+        fn stop_with_deceleration(&mut self, deceleration : RadPerSecond2) -> bool {
+            self.stop_deceleration = Some(deceleration);
+            true
+        }
+
+        fn reset_ramp(&mut self) {
+            self._velocity = RadPerSecond::ZERO;
+            self._acceleration = RadPerSecond2::ZERO;
+            self.stop_deceleration = None;
+
+            self.mode = if self.commands.is_empty() { DriveMode::Inactive } else { DriveMode::Stop };
+        }
+    //
+
+    fn velocity_constraint(&self) -> Option<VelocityConstraint> {
+        Some(if self._velocity_max == Some(self.velocity_possible()) {
+            VelocityConstraint::UserCap
+        } else {
+            VelocityConstraint::BackEmf
+        })
+    }
+}
+
+// Extension traits
+    impl AdvancedStepperBuilder for TrapezoidalBuilder {
+        // General constructors
+            fn new(consts : StepperConst, config : StepperConfig) -> Result<Self, ActuatorError>
+            where
+                Self: Sized
+            {
+                Ok(Self {
+                    _vars: ActuatorVars::ZERO,
+                    _config: config,
+
+                    _velocity_max: None,
+                    _velocity_min: None,
+                    _acceleration_max: None,
+                    _deceleration_max: None,
+                    _jolt_max: None,
+                    _torque_model: false,
+
+                    _velocity: RadPerSecond::ZERO,
+                    _acceleration: RadPerSecond2::ZERO,
+                    _step_angle: consts.step_angle(MicroSteps::default()),
+                    _direction: Direction::default(),
+                    _microsteps: MicroSteps::default(),
+
+                    mode: DriveMode::Inactive,
+                    commands: CommandQueue::default(),
+                    stop_deceleration: None,
+
+                    distance: 0,
+                    distance_counter: 0,
+
+                    _consts: consts
+                })
+            }
+        //
+
+        // Getters
+            fn consts(&self) -> &StepperConst {
+                &self._consts
+            }
+
+            fn vars(&self) -> &ActuatorVars {
+                &self._vars
+            }
+
+            fn config(&self) -> &StepperConfig {
+                &self._config
+            }
+        //
+
+        // Setters
+            fn set_config(&mut self, config : StepperConfig) -> Result<(), ActuatorError> {
+                self._config = config;
+                Ok(())
+            }
+
+            fn set_overload_curret(&mut self, current : Option<f32>) -> Result<(), ActuatorError> {
+                self._config.overload_current = current;
+                Ok(())
+            }
+
+            fn set_drive_current(&mut self, current : Option<f32>) -> Result<(), ActuatorError> {
+                self._config.drive_current = current;
+                Ok(())
+            }
+        //
+
+        // Loads
+            fn apply_gen_force(&mut self, force : NewtonMeters) -> Result<(), ActuatorError> {
+                self._vars.force_load_gen = force;
+                Ok(())
+            }
+
+            fn apply_dir_force(&mut self, force : NewtonMeters) -> Result<(), ActuatorError> {
+                self._vars.force_load_dir = force;
+                Ok(())
+            }
+
+            fn apply_inertia(&mut self, inertia : KgMeter2) -> Result<(), ActuatorError> {
+                self._vars.inertia_load = inertia;
+                Ok(())
+            }
+        //
+    }
+//
+
+// Math
+    impl DefinedActuator for TrapezoidalBuilder {
+        /// The time of a trapezoidal movement between the positions, the jolt limit is not considered
+        fn ptp_time_for_distance(&self, abs_pos_0 : PositionRad, abs_pos_t : PositionRad) -> Seconds {
+            match (self.acceleration_allowed(RadPerSecond::ZERO), self.deceleration_allowed(RadPerSecond::ZERO)) {
+                (Ok(acceleration), Ok(deceleration)) =>
+                    kin::travel_times::<Rotary>(abs_pos_t - abs_pos_0, self.velocity_possible(), acceleration, deceleration).total(),
+                _ => Seconds::INFINITY
+            }
+        }
+    }
+//
@@ -1,4 +1,7 @@
+use embedded_hal::digital::{OutputPin, PinState};
+
 use crate::ActuatorError;
+use crate::data::{DriverLimit, DriverLimits, MicroSteps};
 use syunit::*;
 
 /// A controller for the logics of a stepper motor
@@ -6,9 +9,191 @@ pub trait StepperController {
     /// Initializes a step with the given `time`, this function will set the pin to `HIGH` 
     fn step(&mut self, time : Seconds) -> Result<(), ActuatorError<Rotary>>;
 
+    /// Generates a burst of steps, each one taking the corresponding interval of `intervals`
+    /// 
+    /// Backends able to output whole pulse trains (DMA, RMT peripherals, ring buffers) should override this method, as it 
+    /// removes the overhead of a call per step. The default implementation calls [StepperController::step] for every interval.
+    fn step_batch(&mut self, intervals : &[Seconds]) -> Result<(), ActuatorError<Rotary>> {
+        for interval in intervals {
+            self.step(*interval)?;
+        }
+
+        Ok(())
+    }
+
     /// The movement direction of the motor
     fn direction(&self) -> Direction;
 
     /// Sets the direction of the motor
     fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError<Rotary>>;
-}
\ No newline at end of file
+
+    /// The capabilities of the driver, the motor checks its setup and all movements against them
+    /// 
+    /// The default implementation returns `None`, meaning the driver is not checked
+    fn driver_limits(&self) -> Option<&DriverLimits> {
+        None
+    }
+
+    /// Sets the microsteps of the driver, e.g. with its mode pins
+    /// 
+    /// Called by the motor whenever its microsteps change, the default implementation does nothing
+    fn set_microsteps(&mut self, _microsteps : MicroSteps) -> Result<(), ActuatorError<Rotary>> {
+        Ok(())
+    }
+
+    // Hold policy
+        /// Enables or disables the driver of the motor
+        /// 
+        /// Used to execute a [HoldPolicy](crate::sync::stepper::HoldPolicy), the default implementation does nothing
+        fn set_enabled(&mut self, _enabled : bool) -> Result<(), ActuatorError<Rotary>> {
+            Ok(())
+        }
+
+        /// Sets the current of the driver to the given fraction of its full current
+        /// 
+        /// Used to execute a [HoldPolicy](crate::sync::stepper::HoldPolicy), the default implementation does nothing
+        fn set_current(&mut self, _current : Factor) -> Result<(), ActuatorError<Rotary>> {
+            Ok(())
+        }
+
+        /// Disables the driver once the given `timeout` has passed without a new movement
+        /// 
+        /// Controllers without a way to schedule the disable use the default implementation, which disables the driver immediately
+        fn disable_after(&mut self, _timeout : Seconds) -> Result<(), ActuatorError<Rotary>> {
+            self.set_enabled(false)
+        }
+    // 
+
+    /// Waits for the given `time` without generating any steps, used to execute a 
+    /// [SettlePolicy](crate::sync::stepper::SettlePolicy)
+    /// 
+    /// Controllers keeping the timing of the steps should override this method, the default implementation returns 
+    /// immediately
+    fn wait(&mut self, _time : Seconds) -> Result<(), ActuatorError<Rotary>> {
+        Ok(())
+    }
+
+    /// Stops all step generation and de-asserts the output pins of the controller
+    /// 
+    /// Called by the motor when it gets dropped, the default implementation does nothing
+    fn stop(&mut self) -> Result<(), ActuatorError<Rotary>> {
+        Ok(())
+    }
+}
+
+/// A hardware pulse generator creating the step signal of a [PulseController], e.g. a hardware PWM channel, an RMT peripheral
+/// or a timer interrupt feeding a pin
+///
+/// Unlike a bitbanged step signal, the pulses are timed by the hardware, so the signal does not jitter if the CPU is under load
+pub trait PulseGenerator {
+    /// Generates a single pulse, the next pulse starting after the `period`. Blocks until the pulse has been generated
+    fn pulse(&mut self, period : Seconds) -> Result<(), ActuatorError<Rotary>>;
+
+    /// Generates a train of pulses, one for each of the `periods`
+    ///
+    /// Generators able to queue whole pulse trains (DMA, RMT, ring buffers) should override this method, the default 
+    /// implementation calls [PulseGenerator::pulse] for every period
+    fn pulse_train(&mut self, periods : &[Seconds]) -> Result<(), ActuatorError<Rotary>> {
+        for period in periods {
+            self.pulse(*period)?;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the output `LOW` for the given `time`, blocking until it has passed. Used to time the settling of the motor, see 
+    /// [StepperController::wait]
+    fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError<Rotary>>;
+
+    /// The highest pulse frequency the generator can create, `None` if it is not limited
+    fn frequency_max(&self) -> Option<Hertz> {
+        None
+    }
+
+    /// Stops the generation of pulses immediately, leaving the output `LOW`
+    fn stop(&mut self) -> Result<(), ActuatorError<Rotary>>;
+}
+
+/// A [StepperController] generating the step signal with a [PulseGenerator], the direction being set with a regular output pin
+pub struct PulseController<G : PulseGenerator, DIR : OutputPin> {
+    generator : G,
+    pin_dir : DIR,
+
+    direction : Direction,
+    driver_limits : Option<DriverLimits>
+}
+
+impl<G : PulseGenerator, DIR : OutputPin> PulseController<G, DIR> {
+    /// Creates a new controller generating the steps with the `generator`
+    pub fn new(generator : G, pin_dir : DIR) -> Self {
+        Self {
+            generator,
+            pin_dir,
+
+            direction: Direction::default(),
+            driver_limits: None
+        }
+    }
+
+    /// Attaches the limits of the driver connected to the controller, see [StepperController::driver_limits]
+    pub fn with_driver_limits(mut self, limits : DriverLimits) -> Self {
+        self.driver_limits = Some(limits);
+        self
+    }
+
+    /// The pulse generator of the controller
+    pub fn generator(&self) -> &G {
+        &self.generator
+    }
+
+    /// The pulse generator of the controller
+    pub fn generator_mut(&mut self) -> &mut G {
+        &mut self.generator
+    }
+
+    /// Checks the `period` against the highest frequency of the generator
+    fn check_period(&self, period : Seconds) -> Result<(), ActuatorError<Rotary>> {
+        match self.generator.frequency_max() {
+            Some(max) if (1.0 / period) > max => Err(ActuatorError::DriverLimitExceeded(DriverLimit::StepFrequency(1.0 / period, max))),
+            _ => Ok(())
+        }
+    }
+}
+
+impl<G : PulseGenerator, DIR : OutputPin> StepperController for PulseController<G, DIR> {
+    fn step(&mut self, time : Seconds) -> Result<(), ActuatorError<Rotary>> {
+        self.check_period(time)?;
+        self.generator.pulse(time)
+    }
+
+    fn step_batch(&mut self, intervals : &[Seconds]) -> Result<(), ActuatorError<Rotary>> {
+        // The shortest interval has the highest frequency
+        if let Some(shortest) = intervals.iter().copied().reduce(Seconds::min) {
+            self.check_period(shortest)?;
+        }
+
+        self.generator.pulse_train(intervals)
+    }
+
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError<Rotary>> {
+        self.pin_dir.set_state(PinState::from(dir.as_bool())).map_err(|_| ActuatorError::IOError)?;
+        self.direction = dir;
+        Ok(())
+    }
+
+    fn driver_limits(&self) -> Option<&DriverLimits> {
+        self.driver_limits.as_ref()
+    }
+
+    fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError<Rotary>> {
+        self.generator.wait(time)
+    }
+
+    fn stop(&mut self) -> Result<(), ActuatorError<Rotary>> {
+        self.generator.stop()
+    }
+}
@@ -0,0 +1,114 @@
+use alloc::vec::Vec;
+
+use crate::ActuatorError;
+use crate::data::{ActuatorVars, StepperConfig, StepperConst, StepperSnapshot};
+use crate::sync::stepper::{StepperController, StepperMotor};
+use crate::sync::stepper::builder::AdvancedStepperBuilder;
+
+/// A stepper actuator whose [StepperConfig] can be changed, so the configuration of a whole group can be set at once with
+/// [set_config_all]
+pub trait ConfigurableStepper {
+    /// The constants of the motor, used to check the configurations
+    fn consts(&self) -> &StepperConst;
+
+    /// The current configuration of the motor
+    fn config(&self) -> &StepperConfig;
+
+    /// Sets the configuration of the motor
+    fn set_config(&mut self, config : StepperConfig) -> Result<(), ActuatorError>;
+
+    /// The loads and limits applied to the motor, the default returns [ActuatorVars::ZERO] for motors without any
+    fn vars(&self) -> &ActuatorVars {
+        &ActuatorVars::ZERO
+    }
+
+    // Snapshots
+        /// An owned copy of the constants of the motor
+        fn consts_snapshot(&self) -> StepperConst {
+            self.consts().clone()
+        }
+
+        /// An owned copy of the configuration of the motor
+        fn config_snapshot(&self) -> StepperConfig {
+            self.config().clone()
+        }
+
+        /// An owned copy of the loads and limits applied to the motor
+        fn vars_snapshot(&self) -> ActuatorVars {
+            self.vars().clone()
+        }
+
+        /// An owned copy of all data of the motor, see [StepperSnapshot]
+        fn snapshot(&self) -> StepperSnapshot {
+            StepperSnapshot {
+                consts: self.consts_snapshot(),
+                config: self.config_snapshot(),
+                vars: self.vars_snapshot()
+            }
+        }
+    //
+}
+
+impl<B : AdvancedStepperBuilder, C : StepperController> ConfigurableStepper for StepperMotor<B, C> {
+    fn consts(&self) -> &StepperConst {
+        StepperMotor::consts(self)
+    }
+
+    fn config(&self) -> &StepperConfig {
+        StepperMotor::config(self)
+    }
+
+    fn set_config(&mut self, config : StepperConfig) -> Result<(), ActuatorError> {
+        StepperMotor::set_config(self, config)
+    }
+
+    fn vars(&self) -> &ActuatorVars {
+        StepperMotor::vars(self)
+    }
+}
+
+/// Sets the `config` on all `axes` of a group, except for the axes listed in `overrides` with their index, which get 
+/// their own configuration instead (e.g. a higher current for the axis carrying the most load)
+/// 
+/// All configurations are checked against the constants of their axis first (see [StepperConfig::check]), if any of them
+/// is invalid no axis is changed. Errors are reported for every axis at once, together with the index of the axis.
+pub fn set_config_all(axes : &mut [&mut dyn ConfigurableStepper], config : &StepperConfig, overrides : &[(usize, StepperConfig)]) 
+    -> Result<(), Vec<(usize, ActuatorError)>> 
+{
+    let mut errors = Vec::new();
+
+    for &(index, _) in overrides {
+        if index >= axes.len() {
+            errors.push((index, ActuatorError::InvalidConfig("No axis with the index of the override exists")));
+        }
+    }
+
+    let configs : Vec<&StepperConfig> = (0 .. axes.len()).map(|index| 
+        overrides.iter()
+            .rfind(|(i, _)| *i == index)
+            .map_or(config, |(_, config)| config)
+    ).collect();
+
+    for (index, (axis, config)) in axes.iter().zip(configs.iter()).enumerate() {
+        if let Err(reason) = config.check(axis.consts()) {
+            errors.push((index, ActuatorError::InvalidConfig(reason)));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // Failing axes restore their previous configuration on their own
+    for (index, (axis, config)) in axes.iter_mut().zip(configs).enumerate() {
+        if let Err(err) = axis.set_config(config.clone()) {
+            errors.push((index, err));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,313 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use syunit::*;
+
+use crate::{ActuatorError, DefinedActuator, InterruptReason};
+
+/// A stepper actuator that exposes its step stream, so it can be driven by [drive_rel_interpolated] together with other
+/// actuators from one single loop
+///
+/// The methods are called in the order `begin_rel` -> (`next_node` -> `step_node`)* -> `end_movement`, where the next node
+/// is requested one step in advance. Drive mode changes caused by interruptors or limits therefore take effect one step later
+/// than with [StepperMotor::handle_builder](crate::sync::stepper::StepperMotor::handle_builder)
+pub trait InterpolatedStepper : DefinedActuator {
+    /// The current absolute position of the axis, used to evaluate [PositionCompare] conditions
+    /// 
+    /// Axes without position tracking return `NaN`, conditions referring to them are never satisfied
+    fn axis_pos(&self) -> PositionRad {
+        PositionRad::NAN
+    }
+
+    /// Evaluates the pre-move checks of the actuator, called for all axes before any of them begins moving
+    fn check_premove(&mut self) -> Result<(), ActuatorError> {
+        Ok(())
+    }
+
+    /// Prepares a relative movement by the distance `rel_dist` with the speed factor `speed_f`, no steps are generated yet
+    fn begin_rel(&mut self, rel_dist : Radians, speed_f : Factor) -> Result<(), ActuatorError>;
+
+    /// Returns the time interval of the next step of the movement, `None` if the movement is done
+    fn next_node(&mut self) -> Option<Seconds>;
+
+    /// Checks interruptors and limits, then generates a single step signal that takes exactly `step_time`
+    ///
+    /// The `step_time` can differ from the interval returned by [InterpolatedStepper::next_node], as the time is shared
+    /// with the steps of the other actuators
+    fn step_node(&mut self, step_time : Seconds) -> Result<(), ActuatorError>;
+
+    /// Finishes the movement, applies hold policies etc.
+    fn end_movement(&mut self) -> Result<(), ActuatorError>;
+
+    /// Returns the reason if the current movement has been interrupted, without resetting it
+    fn interrupted(&self) -> Option<InterruptReason> {
+        None
+    }
+
+    /// Starts a controlled stop of the current movement, used to stop all axes of a group, see [GroupStopPolicy]
+    fn begin_stop(&mut self) -> Result<(), ActuatorError> {
+        Ok(())
+    }
+}
+
+/// Defines how the axes of a group react if one of them gets interrupted, see [drive_rel_interpolated_with]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GroupStopPolicy {
+    /// Only the interrupted axis stops, all other axes complete their movements
+    #[default]
+    Independent,
+    /// All axes start a controlled stop within the same step as the interrupted axis, the movement fails with 
+    /// [ActuatorError::AxisInterrupted]
+    /// 
+    /// The axes decelerate with their own acceleration limits, so the path can deviate from the interpolated one during the
+    /// stop ramp
+    StopAll
+}
+
+/// A condition on the position of a single axis of a group, see [PositionCompare]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareCondition {
+    /// The position of the axis is greater than or equal to the given position
+    AtLeast(PositionRad),
+    /// The position of the axis is smaller than or equal to the given position
+    AtMost(PositionRad),
+    /// The axis has reached or passed the given position during the movement, no matter the direction
+    Reached(PositionRad)
+}
+
+/// The action of a [PositionCompare], called with the positions of all axes of the group
+pub type CompareAction = Box<dyn FnMut(&[PositionRad]) + Send>;
+
+/// A composite position condition across the axes of a group, firing its action within the step that satisfies it
+/// 
+/// All conditions have to be satisfied at once (e.g. X at least 100 AND Y at least 50), they are evaluated after every
+/// step of the interpolated schedule. The action fires once per movement with the positions of all axes, when the 
+/// conditions become satisfied.
+pub struct PositionCompare {
+    conditions : Vec<(usize, CompareCondition)>,
+    action : CompareAction,
+
+    _starts : Vec<PositionRad>,
+    _satisfied : bool,
+    _fired : usize
+}
+
+impl PositionCompare {
+    /// Creates a new compare without any conditions, calling `action` with the positions of all axes once it fires
+    pub fn new(action : CompareAction) -> Self {
+        Self {
+            conditions: Vec::new(),
+            action,
+
+            _starts: Vec::new(),
+            _satisfied: false,
+            _fired: 0
+        }
+    }
+
+    /// Adds a `condition` for the axis with the given `index`
+    pub fn with(mut self, index : usize, condition : CompareCondition) -> Self {
+        self.conditions.push((index, condition));
+        self
+    }
+
+    /// The conditions of the compare, together with the index of their axis
+    pub fn conditions(&self) -> &[(usize, CompareCondition)] {
+        &self.conditions
+    }
+
+    /// The amount of movements the compare has fired in
+    pub fn fired(&self) -> usize {
+        self._fired
+    }
+
+    /// Prepares the compare for a new movement starting at the positions of the `axes`
+    fn arm(&mut self, axes : &[&mut dyn InterpolatedStepper]) {
+        self._starts = axes.iter().map(|axis| axis.axis_pos()).collect();
+        self._satisfied = false;
+    }
+
+    /// Evaluates the conditions, firing the action if they are satisfied for the first time in this movement
+    fn evaluate(&mut self, axes : &[&mut dyn InterpolatedStepper]) {
+        if self._satisfied {
+            return;
+        }
+
+        let starts = &self._starts;
+        let satisfied = self.conditions.iter().all(|&(index, condition)| {
+            let pos = axes[index].axis_pos();
+
+            match condition {
+                CompareCondition::AtLeast(target) => pos >= target,
+                CompareCondition::AtMost(target) => pos <= target,
+                CompareCondition::Reached(target) => (pos == target) | ((pos > target) != (starts[index] > target))
+            }
+        });
+
+        if satisfied {
+            let positions : Vec<PositionRad> = axes.iter().map(|axis| axis.axis_pos()).collect();
+
+            self._satisfied = true;
+            self._fired += 1;
+            (self.action)(&positions);
+        }
+    }
+}
+
+impl core::fmt::Debug for PositionCompare {
+    fn fmt(&self, f : &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PositionCompare")
+            .field("conditions", &self.conditions)
+            .field("fired", &self._fired)
+            .finish()
+    }
+}
+
+/// Drives all `axes` by their corresponding relative distance `rel_dists` with a shared, interpolated step schedule
+///
+/// The speed factors of the axes are scaled so that all movements take the same time, the slowest axis moves with `speed_f`.
+/// The step streams of all axes are then merged into one time-ordered schedule and executed by the calling thread,
+/// guaranteeing the relative order of the steps (steps at the same time are executed in the order of the `axes`).
+///
+/// ## Errors
+///
+/// The pre-move checks of all axes are evaluated first, no axis starts moving if any of them fails. If an axis fails to start, 
+/// the axes started before are finished again. Afterwards the first error of any axis aborts the whole movement, all axes are 
+/// finished regardless and the first error is returned
+///
+/// ## Panics
+///
+/// Panics if the amount of axes and distances do not match
+pub fn drive_rel_interpolated(axes : &mut [&mut dyn InterpolatedStepper], rel_dists : &[Radians], speed_f : Factor) -> Result<(), ActuatorError> {
+    drive_rel_interpolated_with(axes, rel_dists, speed_f, GroupStopPolicy::Independent)
+}
+
+/// Like [drive_rel_interpolated], with the given `policy` defining the reaction of the group to an interrupted axis
+///
+/// With [GroupStopPolicy::StopAll], the interrupt of any axis stops the whole group and returns 
+/// [ActuatorError::AxisInterrupted] with the index of the first interrupted axis. The interrupt reason stays available on the 
+/// axis itself.
+pub fn drive_rel_interpolated_with(axes : &mut [&mut dyn InterpolatedStepper], rel_dists : &[Radians], speed_f : Factor, 
+    policy : GroupStopPolicy) -> Result<(), ActuatorError> 
+{
+    drive_rel_interpolated_compare(axes, rel_dists, speed_f, policy, &mut [])
+}
+
+/// Like [drive_rel_interpolated_with], evaluating the position `compares` after every step of the schedule
+/// 
+/// ## Panics
+/// 
+/// Panics if the amount of axes and distances do not match or if a condition refers to an axis that does not exist
+pub fn drive_rel_interpolated_compare(axes : &mut [&mut dyn InterpolatedStepper], rel_dists : &[Radians], speed_f : Factor, 
+    policy : GroupStopPolicy, compares : &mut [PositionCompare]) -> Result<(), ActuatorError> 
+{
+    assert_eq!(axes.len(), rel_dists.len(), "The amount of axes and distances must be equal");
+
+    let times : Vec<Seconds> = axes.iter().zip(rel_dists)
+        .map(|(axis, rel_dist)| axis.ptp_time_for_distance(PositionRad::ZERO, PositionRad::ZERO + *rel_dist))
+        .collect();
+
+    // A single failing check aborts the movement of the whole group
+    for axis in axes.iter_mut() {
+        axis.check_premove()?;
+    }
+
+    for (index, factor) in sync_speed_factors(&times, speed_f).into_iter().enumerate() {
+        if let Err(err) = axes[index].begin_rel(rel_dists[index], factor) {
+            // Finish the axes started already, the error of the failing axis takes priority
+            let _ = end_movements(&mut axes[..index]);
+            return Err(err);
+        }
+    }
+
+    for compare in compares.iter_mut() {
+        assert!(compare.conditions.iter().all(|(index, _)| *index < axes.len()), "A condition refers to an axis that does not exist");
+        compare.arm(axes);
+    }
+
+    let result = execute_schedule(axes, policy, compares);
+
+    // Finish all axes, even if an error occured
+    let end_result = end_movements(axes);
+
+    result.and(end_result)
+}
+
+/// Ends the movement of all `axes`, returns the first error after all axes have been finished
+fn end_movements(axes : &mut [&mut dyn InterpolatedStepper]) -> Result<(), ActuatorError> {
+    let mut result = Ok(());
+
+    for axis in axes.iter_mut() {
+        let end_result = axis.end_movement();
+        result = result.and(end_result);
+    }
+
+    result
+}
+
+/// The speed factors that scale the movements of a group to the slowest axis, given the point-to-point `times` of all axes 
+/// at full speed. Used by [drive_rel_interpolated], can be used to plan the movements of a group in advance
+pub fn sync_speed_factors(times : &[Seconds], speed_f : Factor) -> Vec<Factor> {
+    let time_max = times.iter().copied().fold(Seconds::ZERO, Seconds::max);
+
+    times.iter().map(|&time| if time_max > Seconds::ZERO {
+        Factor::try_new(speed_f.as_f32() * (time / time_max)).unwrap_or(speed_f)
+    } else {
+        speed_f
+    }).collect()
+}
+
+/// Merges the step streams of all axes and executes them
+fn execute_schedule(axes : &mut [&mut dyn InterpolatedStepper], policy : GroupStopPolicy, compares : &mut [PositionCompare]) 
+    -> Result<(), ActuatorError> 
+{
+    // The first axis interrupted and the reason, once the group has been stopped
+    let mut interrupt : Option<(usize, InterruptReason)> = None;
+
+    // The pending step of each axis, with its point in time and the interval to the following step
+    let mut pending : Vec<Option<(Seconds, Seconds)>> = axes.iter_mut()
+        .map(|axis| axis.next_node().map(|node| (Seconds::ZERO, node)))
+        .collect();
+
+    // Always execute the earliest pending step
+    while let Some((index, (time, interval))) = next_pending(&pending) {
+        // Look one node ahead, as the step has to last until the next step of any axis is due
+        pending[index] = axes[index].next_node().map(|node| (time + interval, node));
+
+        let time_next = next_pending(&pending).map_or(time + interval, |(_, (t, _))| t);
+
+        axes[index].step_node(time_next - time)?;
+
+        for compare in compares.iter_mut() {
+            compare.evaluate(axes);
+        }
+
+        if (policy == GroupStopPolicy::StopAll) && interrupt.is_none() {
+            if let Some(reason) = axes[index].interrupted() {
+                interrupt = Some((index, reason));
+
+                // The interrupted axis is stopping already
+                for (i, axis) in axes.iter_mut().enumerate() {
+                    if i != index {
+                        axis.begin_stop()?;
+                    }
+                }
+            }
+        }
+    }
+
+    match interrupt {
+        Some((index, reason)) => Err(ActuatorError::AxisInterrupted(index, reason)),
+        None => Ok(())
+    }
+}
+
+/// Returns the index and the pending step of the axis that has to step next
+fn next_pending(pending : &[Option<(Seconds, Seconds)>]) -> Option<(usize, (Seconds, Seconds))> {
+    pending.iter().enumerate()
+        .filter_map(|(i, p)| p.map(|p| (i, p)))
+        .fold(None, |min : Option<(usize, (Seconds, Seconds))>, cur| match min {
+            Some(m) if m.1.0 <= cur.1.0 => Some(m),
+            _ => Some(cur)
+        })
+}
@@ -0,0 +1,108 @@
+use syunit::*;
+
+use crate::ActuatorError;
+use crate::math::{Float, precise, reduced};
+use crate::sync::stepper::{DriveMode, StepperBuilder, StepperController};
+
+/// ########################
+/// #    MinimalStepper    #
+/// ########################
+///
+/// The minimal motion path of the crate, a stepper motor consisting of nothing but a builder, a controller and the absolute
+/// position of the motor
+///
+/// - No heap allocations, available without the `alloc` feature
+/// - No interruptors, limits, statistics, hold or settle policies
+/// - Blocks the calling thread for the whole movement
+///
+/// Meant for small microcontrollers (e.g. a Cortex-M0 with 32 KB of flash), see the `examples/cortex-m0` project. Use the
+/// [StepperMotor](crate::sync::stepper::StepperMotor) wherever an allocator is available.
+#[derive(Debug)]
+pub struct MinimalStepper<B : StepperBuilder, C : StepperController> {
+    builder : B,
+    ctrl : C,
+
+    /// Absolute position in radians
+    _abs_pos : Float
+}
+
+impl<B : StepperBuilder, C : StepperController> MinimalStepper<B, C> {
+    /// Creates a new minimal stepper out of the `builder` and the `ctrl`, starting at the absolute position `0`
+    pub fn new(builder : B, ctrl : C) -> Self {
+        Self {
+            builder,
+            ctrl,
+
+            _abs_pos: 0.0
+        }
+    }
+
+    // Parts
+        /// The builder creating the step intervals of the motor
+        pub fn builder(&self) -> &B {
+            &self.builder
+        }
+
+        /// The builder creating the step intervals of the motor
+        pub fn builder_mut(&mut self) -> &mut B {
+            &mut self.builder
+        }
+
+        /// The controller generating the steps
+        pub fn ctrl(&self) -> &C {
+            &self.ctrl
+        }
+
+        /// The controller generating the steps
+        pub fn ctrl_mut(&mut self) -> &mut C {
+            &mut self.ctrl
+        }
+
+        /// Splits the stepper into its builder and controller
+        pub fn into_parts(self) -> (B, C) {
+            (self.builder, self.ctrl)
+        }
+    //
+
+    // Position
+        /// The absolute position of the motor
+        pub fn pos(&self) -> PositionRad {
+            PositionRad(reduced(self._abs_pos))
+        }
+
+        /// Overwrites the absolute position of the motor, e.g. after a homing sequence
+        pub fn set_pos(&mut self, pos : PositionRad) {
+            self._abs_pos = precise(pos.0);
+        }
+    //
+
+    // Movements
+        /// Moves the motor by the relative distance `rel_dist` with the factor `speed`, blocks until the movement is done
+        pub fn drive_rel(&mut self, rel_dist : Radians, speed : Factor) -> Result<(), ActuatorError> {
+            if !rel_dist.is_finite() {
+                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+            }
+
+            self.builder.set_drive_mode(DriveMode::FixedDistance(rel_dist, RadPerSecond::ZERO, speed), &mut self.ctrl)?;
+
+            while let Some(step_time) = self.builder.next() {
+                self.ctrl.step(step_time)?;
+
+                let step_angle = precise(self.builder.step_angle().0);
+
+                if self.builder.direction().as_bool() {
+                    self._abs_pos += step_angle;
+                } else {
+                    self._abs_pos -= step_angle;
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Moves the motor to the absolute position `pos` with the factor `speed`, blocks until the movement is done
+        pub fn drive_abs(&mut self, pos : PositionRad, speed : Factor) -> Result<(), ActuatorError> {
+            self.drive_rel(pos - self.pos(), speed)
+        }
+    //
+}
@@ -7,105 +7,1274 @@ use alloc::vec::Vec;
 use syunit::*;
 use syunit::metric::*;
 
-use crate::{SyncActuator, SyncActuatorBlocking, InterruptReason, Interruptible, Interruptor, AdvancedActuator, DefinedActuator};
-use crate::data::{StepperConfig, StepperConst, MicroSteps}; 
-use crate::sync::{ActuatorError, SyncActuatorState};
-use crate::sync::stepper::{StepperActuator, StepperController, StepperBuilder, DriveMode, StepperState};
-use crate::sync::stepper::builder::{AdvancedStepperBuilder, SimpleStepperBuilder};
+use crate::{logging, LimitBehavior, MotionMode, SyncActuator, SyncActuatorBlocking, InterruptContext, InterruptReason, Interruptible, Interruptor, AdvancedActuator, DefinedActuator};
+use crate::data::{ActuatorVars, JoltTable, StepperConfig, StepperConst, MicroSteps, RoundingPolicy}; 
+use crate::math::{Float, precise, reduced};
+use crate::math::kin::{self, RampProfile, RampSteps};
+use crate::parent::{LayerPos, PositionLayers};
+use crate::report::{Capabilities, CheckKind, ReadinessCheck, SelfTest};
+use crate::sync::{ActuatorError, PreMoveCheck, PreMoveChecks, SegmentEvent, SegmentQueue, SimulationSwitch, SyncActuatorState, TimeScale};
+use crate::sync::stepper::{StepperActuator, StepperController, StepperBuilder, CommandPolicy, DriveMode, HoldPolicy, MoveOutcome, 
+    SettlePolicy, StepperState, StopMode, InterpolatedStepper, VelocityConstraint, SETTLE_POLL_INTERVAL};
+use crate::sync::stepper::builder::{AdvancedStepperBuilder, ProfileController, SimpleStepperBuilder, TrapezoidalBuilder};
+use crate::sync::stepper::{FaultKind, StepperStats};
 
 /// A stepper motor
 /// 
 /// Controlled by two pins, one giving information about the direction, the other about the step signal (PWM)
+/// 
+/// # Drop and panic-safety
+/// 
+/// If the motor is dropped while it is still moving (e.g. because a panic unwinds through [StepperMotor::handle_builder]), 
+/// the motor commands a controlled stop by switching the builder into [DriveMode::Stop] and generating the remaining 
+/// ramp-down steps. Afterwards [StepperController::stop] is called in every case, so no pins are left asserted.
+/// 
+/// Errors occuring during this process are ignored, as there is no way to report them. A panic inside the controller while 
+/// already unwinding will abort the program.
 pub struct StepperMotor<B : StepperBuilder, C : StepperController> {
     builder : B,
     ctrl : C, 
 
-    _state: Arc<StepperState>,
+    _state: Arc<StepperState>,
+
+    // Limits
+    _limit_min : Option<PositionRad>,
+    _limit_max : Option<PositionRad>,
+    _limit_behavior : LimitBehavior,
+
+    // Hold policy
+    _hold_policy : HoldPolicy,
+
+    // Settling
+    _settle_policy : SettlePolicy,
+    in_position : Option<InPositionCheck>,
+
+    // Rounding
+    _rounding : RoundingPolicy,
+    _step_remainder : f32,
+
+    // Dead-band
+    _min_move : Option<Radians>,
+    _pending_dist : Radians,
+
+    // Stopping
+    _velocity : RadPerSecond,
+    _stop_mode : StopMode,
+    /// The velocity of the stop ramp of [StopMode::DecelRamp], if the motor ramps down on its own
+    _stop_ramp : Option<RadPerSecond>,
+    _stop_before : Option<PositionRad>,
+    /// The current movement is blended into the next one, see [SyncActuatorBlocking::drive_rel_blocking_blended]
+    _blending : bool,
+
+    // Ramp profiles
+    _profile : Option<RampSteps>,
+    _profile_time : Seconds,
+
+    // Position prediction
+    _clock : Option<fn() -> Seconds>,
+
+    // Heartbeat
+    _heartbeat_timeout : Option<Seconds>,
+    _heartbeat_elapsed : Seconds,
+    _heartbeat_lost : bool,
+
+    // Statistics
+    _stats : StepperStats,
+
+    // Configuration profiles
+    config_profiles : Vec<(&'static str, StepperConfig)>,
+    _config_profile : Option<&'static str>,
+
+    // Motion mode
+    mode_listener : Option<Box<dyn FnMut(MotionMode, MotionMode) + Send>>,
+
+    // Pre-move checks
+    premove_checks : PreMoveChecks,
+
+    // Time scale
+    _time_scale : Option<TimeScale>,
+    _scale : f32,
+
+    // Simulation
+    _simulation : Option<SimulationSwitch>,
+    _real_pos : Option<Float>,
+
+    // Step batching
+    _step_batch : usize,
+    _batch : Vec<Seconds>,
+
+    // Interrupters
+    interruptors : Vec<Box<dyn Interruptor<Rotary> + Send>>,
+    _intr_reason : Option<InterruptReason>,
+    _intr_pos : Option<PositionRad>
+}
+
+/// A check reporting if the motor is in position, called with the position commanded to the motor, e.g. comparing it 
+/// against an encoder or reading an in-position signal of a servo drive, see [StepperMotor::set_in_position]
+pub type InPositionCheck = Box<dyn FnMut(PositionRad) -> bool + Send>;
+
+// Inits
+impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {   
+    /// Creates the motor out of the `builder` and the `ctrl`, all other settings start with their defaults. Used by all 
+    /// constructors, so no field can be missed by one of them
+    fn from_parts(builder : B, ctrl : C) -> Self {
+        Self {
+            builder,
+            ctrl,
+
+            _state : Arc::new(StepperState::new()),
+
+            _limit_min: None,
+            _limit_max: None,
+            _limit_behavior: LimitBehavior::default(),
+
+            _hold_policy: HoldPolicy::default(),
+
+            _settle_policy: SettlePolicy::default(),
+            in_position: None,
+
+            _rounding: RoundingPolicy::default(),
+            _step_remainder: 0.0,
+
+            _min_move: None,
+            _pending_dist: Radians::ZERO,
+
+            _velocity: RadPerSecond::ZERO,
+            _stop_mode: StopMode::default(),
+            _stop_ramp: None,
+            _stop_before: None,
+            _blending: false,
+
+            _profile: None,
+            _profile_time: Seconds::ZERO,
+
+            _clock: None,
+
+            _heartbeat_timeout: None,
+            _heartbeat_elapsed: Seconds::ZERO,
+            _heartbeat_lost: false,
+
+            _stats: StepperStats::new(),
+
+            config_profiles: Vec::new(),
+            _config_profile: None,
+
+            mode_listener: None,
+
+            premove_checks: PreMoveChecks::new(),
+
+            _time_scale: None,
+            _scale: 1.0,
+
+            _simulation: None,
+            _real_pos: None,
+
+            _step_batch: 1,
+            _batch: Vec::new(),
+
+            interruptors : Vec::new(),
+            _intr_reason: None,
+            _intr_pos: None
+        }
+    }
+
+    /// ######################################
+    /// #    StepperMotor::handle_builder    #
+    /// ######################################
+    ///
+    /// Main driving algorithm for stepper motors, handles the builder until no nodes are left anymore
+    /// 
+    /// ## Thread
+    /// 
+    /// Blocks the current thread and creates the step signals until the builder is finished
+    pub fn handle_builder(&mut self) -> Result<(), ActuatorError> {
+        let heartbeat_lost_prev = self._heartbeat_lost;
+        let blending = core::mem::take(&mut self._blending);
+
+        if let Err(err) = self.begin_movement() {
+            self.abort_movement();
+            return Err(err);
+        }
+        
+        // Steps prepared already have to be generated, even if an error occured
+        let result = self.drive_nodes();
+        let flushed = self.flush_batch();
+
+        if let Err(err) = result.and(flushed) {
+            self.abort_movement();
+            return Err(err);
+        }
+
+        // Blended movements continue right away, unless they have been stopped early
+        let blending = blending && self._intr_reason.is_none();
+
+        self.finish_movement(blending)?;
+
+        if !blending {
+            self.settle()?;
+        }
+
+        // Report the loss of the heartbeat if it occured during this movement
+        if self._heartbeat_lost && !heartbeat_lost_prev {
+            self._stats.record_fault(FaultKind::HeartbeatLost, self.pos());
+            return Err(ActuatorError::HeartbeatLost);
+        }
+
+        Ok(())
+    }
+
+    /// Iterates through the builder until no nodes are left, continuing with commands queued during stop ramps
+    fn drive_nodes(&mut self) -> Result<(), ActuatorError> {
+        loop {
+            while let Some(node) = self.pull_node() {
+                let node = self.profile_node(node);
+
+                if self._step_batch > 1 {
+                    self.batch_node(node)?;
+                } else {
+                    self.process_node(node)?;
+                }
+            }
+
+            // Drive mode changes can change the direction, the steps of the old direction have to be made before
+            self.flush_batch()?;
+
+            let resumed = if self.simulating() {
+                self.builder.resume_queued(&mut ProfileController::default())?
+            } else {
+                self.builder.resume_queued(&mut self.ctrl)?
+            };
+
+            if !resumed {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The next node of the builder, or of the stop ramp of [StopMode::DecelRamp] for builders without ramps
+    fn pull_node(&mut self) -> Option<Seconds> {
+        let Some(velocity) = self._stop_ramp else {
+            return self.builder.next();
+        };
+
+        let step_angle = self.builder.step_angle();
+        let deceleration = match self._stop_mode {
+            StopMode::DecelRamp(deceleration) => deceleration,
+            _ => RadPerSecond2::INFINITY
+        };
+
+        let vel_sq = velocity.0 * velocity.0 - 2.0 * deceleration.0 * step_angle.0;
+
+        if vel_sq <= 0.0 {
+            // The builder has been waiting in stop mode during the ramp
+            self._stop_ramp = None;
+            self.builder.reset_ramp();
+
+            return None;
+        }
+
+        let vel_next = RadPerSecond(vel_sq.sqrt());
+        self._stop_ramp = Some(vel_next);
+
+        Some(2.0 * step_angle / (velocity + vel_next))
+    }
+
+    /// Applies the [StopMode] after a stop has been requested by an interruptor, a halt or an interrupt, `velocity` being the
+    /// velocity of the step currently prepared
+    fn apply_stop_mode(&mut self, velocity : RadPerSecond) {
+        match self._stop_mode {
+            StopMode::Builder => { },
+            // Only the ramp is dropped, commands queued for after the stop are kept
+            StopMode::Immediate => self.builder.reset_ramp(),
+            StopMode::DecelRamp(deceleration) => {
+                // Builders without ramps are ramped down by the motor
+                if !self.builder.stop_with_deceleration(deceleration) {
+                    self._stop_ramp = Some(velocity);
+                }
+            }
+        }
+    }
+
+    /// Replaces the step time `node` of the builder with the one of the active ramp profile, see [StepperMotor::drive_with_profile]
+    /// 
+    /// Once the builder leaves the movement (e.g. stopping because of an interruptor), its stop ramp is used, but never 
+    /// faster than the last step of the profile.
+    fn profile_node(&mut self, node : Seconds) -> Seconds {
+        let Some(profile) = self._profile.as_mut() else {
+            return node;
+        };
+
+        if matches!(self.builder.drive_mode(), DriveMode::FixedDistance(_, _, _)) {
+            if let Some(time) = profile.next() {
+                self._profile_time = time;
+                return time;
+            }
+        }
+
+        node.max(self._profile_time)
+    }
+
+    /// Prepares the motor and its controller for a new movement
+    fn begin_movement(&mut self) -> Result<(), ActuatorError> {
+        // Update the movement variable of the state
+        self._state._moving.store(true, Relaxed);
+
+        // Interrupt reasons refer to the last movement only, a reason that has not been read with `intr_reason()` is 
+        // discarded, otherwise it would be reported for this movement (e.g. stopping a whole group, see 
+        // `InterpolatedStepper::interrupted`)
+        self._intr_reason = None;
+        self._stop_ramp = None;
+        self._intr_pos = None;
+
+        for intr in self.interruptors.iter_mut() {
+            intr.begin_movement();
+        }
+
+        self._stats.begin_move();
+
+        // The host has just commanded the movement, restart the heartbeat interval
+        self._state._heartbeat.store(false, Relaxed);
+        self._heartbeat_elapsed = Seconds::ZERO;
+
+        // The motor stands still, so a new time scale can be applied without ramping
+        self._scale = self.time_scale_target();
+
+        // Make sure the driver is enabled with full current, as the hold policy might have changed it
+        if !self.simulating() {
+            self.ctrl.set_enabled(true)?;
+            self.ctrl.set_current(Factor::MAX)?;
+        }
+
+        self.update_motion_mode();
+        Ok(())
+    }
+
+    /// Checks the interruptors, generates a single step signal taking the given `step_time` and updates the position 
+    fn process_node(&mut self, step_time : Seconds) -> Result<(), ActuatorError> {
+        let (step_time, direction) = self.prepare_node(step_time)?;
+
+        // Make step and return error if occured
+        if self.simulating() {
+            self.simulate_wait(step_time);
+        } else {
+            self.ctrl.step(step_time)?;
+        }
+
+        self.complete_node(step_time, direction)
+    }
+
+    /// Like [StepperMotor::process_node], but adds the step to the current batch, generating the whole batch once it is full
+    fn batch_node(&mut self, step_time : Seconds) -> Result<(), ActuatorError> {
+        let (step_time, direction) = self.prepare_node(step_time)?;
+
+        self._batch.push(step_time);
+        self.complete_node(step_time, direction)?;
+
+        if self._batch.len() >= self._step_batch {
+            self.flush_batch()?;
+        }
+
+        Ok(())
+    }
+
+    /// Generates all steps waiting in the current batch
+    fn flush_batch(&mut self) -> Result<(), ActuatorError> {
+        if self._batch.is_empty() {
+            return Ok(());
+        }
+
+        let result = if self.simulating() {
+            self.simulate_wait(self._batch.iter().fold(Seconds::ZERO, |sum, time| sum + *time));
+            Ok(())
+        } else {
+            self.ctrl.step_batch(&self._batch)
+        };
+
+        self._batch.clear();
+        result
+    }
+
+    /// Checks if the driver can move the motor with the given `velocity`, see [StepperController::driver_limits]
+    fn check_driver_velocity(&self, velocity : RadPerSecond) -> Result<(), ActuatorError> {
+        match self.ctrl.driver_limits() {
+            Some(limits) => limits.check_velocity(velocity, self.builder.step_angle()).map_err(ActuatorError::DriverLimitExceeded),
+            None => Ok(())
+        }
+    }
+
+    /// Scales the `step_time` and checks the interruptors, returns the scaled step time and the direction of the step
+    fn prepare_node(&mut self, step_time : Seconds) -> Result<(Seconds, Direction), ActuatorError> {
+        // Velocity of the unscaled node, stop ramps are scaled like the nodes of the builder
+        let velocity_node = self.builder.step_angle() / step_time;
+        let step_time = self.scale_step_time(step_time);
+
+        // Never command a step rate the driver cannot follow, movements are validated before their first step, so steps 
+        // exceeding the limit afterwards are slowed down instead of aborting the movement halfway
+        let step_time = match self.ctrl.driver_limits() {
+            Some(limits) => step_time.max(limits.step_time_min()),
+            None => step_time
+        };
+
+        // Get the current direction of the motor (builder)
+        let direction = self.builder.direction();
+        // Get the current drive mode of the motor (builder)
+        let drive_mode = self.builder.drive_mode();
+
+        let velocity = self.builder.step_angle() / step_time;
+
+        // The stop ramp of a stop request is not checked again
+        if self._stop_ramp.is_some() {
+            return Ok((step_time, direction));
+        }
+
+        let mut stop_requested = false;
+
+        // Check all interruptors if the motor is not stopping already
+        if *drive_mode != DriveMode::Stop {
+            // Snapshot taken before any interruptor is executed, so all of them see the same state
+            let ctx = InterruptContext {
+                pos: self._state.pos(),
+                velocity: if direction.as_bool() { velocity } else { -velocity },
+                direction,
+                mode: self._state.motion_mode(),
+                interval: step_time,
+                // `_velocity` still holds the velocity of the last step
+                last_interval: if self._velocity == RadPerSecond::ZERO {
+                    Seconds::ZERO
+                } else {
+                    self.builder.step_angle() / self._velocity.abs()
+                }
+            };
+
+            for intr in self.interruptors.iter_mut() {
+                // Check if the direction is right
+                if let Some(i_dir) = intr.dir() {
+                    if i_dir != direction {
+                        // If the interruptors checking-direction does not match the current direction, 
+                        //     the loop skips to the next interruptor
+                        continue;
+                    }
+                }
+
+                // Checks if the interruptor has been triggered
+                if let Some(reason) = intr.check(&ctx) {
+                    logging::log_info!("Movement interrupted at {} rad, reason: {}", ctx.pos.0, logging::reason_name(reason));
+
+                    intr.set_temp_dir(Some(direction));
+                    self._intr_reason.replace(reason);
+                    self._intr_pos = Some(intr.trigger_pos().unwrap_or(ctx.pos));
+                    
+                    // `apply_drive_mode` cannot be used while the interruptors are borrowed
+                    if self._state.simulated() {
+                        self.builder.set_drive_mode(DriveMode::Stop, &mut ProfileController::default())?;
+                    } else {
+                        self.builder.set_drive_mode(DriveMode::Stop, &mut self.ctrl)?;
+                    }
+                    stop_requested = true;
+                } else {
+                    // Clear temporary direction
+                    intr.set_temp_dir(None);
+                }
+            }
+        }
+
+        // Decelerate to a stop if a halt has been requested, e.g. a feed hold
+        if (*self.builder.drive_mode() != DriveMode::Stop) && self._state.should_halt.swap(false, Relaxed) {
+            logging::log_info!("Movement halted at {} rad", self._state.pos().0);
+
+            self._intr_reason.replace(InterruptReason::Halted);
+            self.apply_drive_mode(DriveMode::Stop)?;
+            stop_requested = true;
+        }
+
+        // Stop if an interrupt has been requested, e.g. by an emergency stop
+        if (*self.builder.drive_mode() != DriveMode::Stop) && self._state.should_interrupt.swap(false, Relaxed) {
+            logging::log_info!("Movement interrupted at {} rad, reason: {}", self._state.pos().0, logging::reason_name(InterruptReason::Error));
+
+            self._intr_reason.replace(InterruptReason::Error);
+            self.apply_drive_mode(DriveMode::Stop)?;
+            stop_requested = true;
+        }
+
+        if stop_requested {
+            self.apply_stop_mode(velocity_node);
+            return Ok((step_time, direction));
+        }
+
+        self.check_stop_before(velocity, direction)?;
+        self.check_soft_limits(velocity, direction)?;
+
+        Ok((step_time, direction))
+    }
+
+    /// Starts the stop ramp if waiting any longer would move the motor past the position set with [StepperMotor::stop_before]
+    fn check_stop_before(&mut self, velocity : RadPerSecond, direction : Direction) -> Result<(), ActuatorError> {
+        let Some(target) = self._stop_before else {
+            return Ok(());
+        };
+
+        if *self.builder.drive_mode() == DriveMode::Stop {
+            return Ok(());
+        }
+
+        // Distance left along the direction of movement, negative values mean the motor moves away from the target
+        let remaining = if direction.as_bool() { target - self.pos() } else { self.pos() - target };
+
+        if remaining < Radians::ZERO {
+            return Ok(());
+        }
+
+        // The step currently prepared is still made, another one is reserved for the velocity gained while accelerating
+        let stop_distance = kin::actuator_stop_distance(self, velocity).unwrap_or(Radians::ZERO);
+
+        if remaining < (stop_distance + self.builder.step_angle() * 2.0) {
+            logging::log_info!("Stopping at {} rad before the target {} rad", self.pos().0, target.0);
+
+            self._stop_before = None;
+            self.apply_drive_mode(DriveMode::Stop)?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts the stop ramp of continuous movements early enough to stand still at the position limit in the direction of 
+    /// movement, only if the limits are clamped (see [LimitBehavior::DecelerateAndClamp])
+    fn check_soft_limits(&mut self, velocity : RadPerSecond, direction : Direction) -> Result<(), ActuatorError> {
+        if self._limit_behavior != LimitBehavior::DecelerateAndClamp {
+            return Ok(());
+        }
+
+        // Fixed distances are truncated before they are started
+        if matches!(self.builder.drive_mode(), DriveMode::Stop | DriveMode::FixedDistance(_, _, _)) {
+            return Ok(());
+        }
+
+        let Some(limit) = (if direction.as_bool() { self.limit_max() } else { self.limit_min() }) else {
+            return Ok(());
+        };
+
+        let remaining = if direction.as_bool() { limit - self.pos() } else { self.pos() - limit };
+
+        // Same reserve as for the stop before a target, see [StepperMotor::check_stop_before]
+        let stop_distance = kin::actuator_stop_distance(self, velocity).unwrap_or(Radians::ZERO);
+
+        if remaining < (stop_distance + self.builder.step_angle() * 2.0) {
+            logging::log_info!("Decelerating at {} rad to stop at the limit {} rad", self.pos().0, limit.0);
+            self.apply_drive_mode(DriveMode::Stop)?;
+        }
+
+        Ok(())
+    }
+
+    /// Truncates the relative distance `rel_dist` at the position limits if they are clamped, see 
+    /// [LimitBehavior::DecelerateAndClamp]
+    fn clamp_to_limits(&self, rel_dist : Radians) -> Radians {
+        if self._limit_behavior != LimitBehavior::DecelerateAndClamp {
+            return rel_dist;
+        }
+
+        let overshoot = self.resolve_pos_limits_for_abs_pos(self.pos() + rel_dist);
+
+        // No limits set
+        if !overshoot.is_finite() {
+            return rel_dist;
+        }
+
+        let clamped = rel_dist - overshoot;
+
+        // Never reverse the movement, e.g. if the motor already stands behind the limit
+        if (clamped.0 * rel_dist.0) < 0.0 {
+            Radians::ZERO
+        } else {
+            clamped
+        }
+    }
+
+    /// Updates the motion mode, the heartbeat supervision and the position after a step has been made
+    fn complete_node(&mut self, step_time : Seconds, direction : Direction) -> Result<(), ActuatorError> {
+        self.update_motion_mode();
+        self._stats.record_step(step_time);
+
+        let velocity = self.builder.step_angle() / step_time;
+        self._velocity = if direction.as_bool() { velocity } else { -velocity };
+
+        // Stop continuous movements if the heartbeat of the host is missing
+        if let Some(timeout) = self._heartbeat_timeout {
+            if matches!(self.builder.drive_mode(), DriveMode::ConstVelocity(_) | DriveMode::ConstFactor(_, _)) {
+                if self._state._heartbeat.swap(false, Relaxed) {
+                    self._heartbeat_elapsed = Seconds::ZERO;
+                } else {
+                    self._heartbeat_elapsed += step_time;
+                }
+
+                if self._heartbeat_elapsed > timeout {
+                    logging::log_warn!("Host heartbeat lost for {} s, stopping the motor", self._heartbeat_elapsed.0);
+
+                    self._heartbeat_lost = true;
+                    self.apply_drive_mode(DriveMode::Stop)?;
+                }
+            }
+        }
+
+        // Check if the pos value exeeds any limits, stop the movement if it does
+        if direction.as_bool() {
+            self._state._abs_pos.fetch_add(precise(self.builder.step_angle().0), Relaxed);
+
+            if self.pos() > self.limit_max().unwrap_or(PositionRad::INFINITY) {
+                self.log_limit_reached();
+                self.apply_drive_mode(DriveMode::Stop)?;
+            } 
+        } else {
+            self._state._abs_pos.fetch_sub(precise(self.builder.step_angle().0), Relaxed);
+
+            if self.pos() < self.limit_min().unwrap_or(PositionRad::NEG_INFINITY) {
+                self.log_limit_reached();
+                self.apply_drive_mode(DriveMode::Stop)?;
+            } 
+        }
+
+        if let Some(clock) = self._clock {
+            let step_angle = self.builder.step_angle();
+            self._state.record_step(clock(), step_time, if direction.as_bool() { step_angle } else { -step_angle });
+        }
+
+        Ok(())
+    }
+
+    /// The scale of the attached [TimeScale], `1.0` if none is attached
+    fn time_scale_target(&self) -> f32 {
+        self._time_scale.as_ref().map_or(1.0, TimeScale::get)
+    }
+
+    /// Stretches the `step_time` with the time scale, ramping the scale towards its target within the acceleration limit
+    fn scale_step_time(&mut self, step_time : Seconds) -> Seconds {
+        let target = self.time_scale_target();
+
+        if self._scale != target {
+            // Changing the scale by `ds` changes the velocity by `v * ds` within the scaled step time 
+            let velocity = self.builder.step_angle() / step_time;
+            let ds_max = self.acceleration_max().map_or(f32::INFINITY, |accel| 
+                accel.0 * (step_time.0 / self._scale) / velocity.0
+            );
+
+            self._scale += (target - self._scale).clamp(-ds_max, ds_max);
+        }
+
+        step_time / self._scale
+    }
+
+    /// Logs that a position limit has been reached, only once per stop
+    fn log_limit_reached(&self) {
+        if *self.builder.drive_mode() != DriveMode::Stop {
+            logging::log_warn!("Position limit reached at {} rad, stopping the motor", self.pos().0);
+        }
+    }
+
+    /// Marks the movement as finished and applies the hold policy, unless the movement is `blending` into the next one
+    fn finish_movement(&mut self, blending : bool) -> Result<(), ActuatorError> {
+        // No movement anymore
+        self._state._moving.store(false, Relaxed);
+        self._velocity = RadPerSecond::ZERO;
+
+        // The stop target only applies to a single movement
+        self._stop_before = None;
+        self._profile = None;
+
+        self._stats.end_move(self.builder.step_angle(), self._intr_reason, self.pos());
+
+        if !blending {
+            self.apply_hold_policy()?;
+        }
+        self.update_motion_mode();
+        Ok(())
+    }
+
+    /// Cleans up after a movement that failed, the motor is not moving anymore and the remaining nodes of the builder are
+    /// discarded. The steps of the failed movement are counted as well
+    fn abort_movement(&mut self) {
+        self._state._moving.store(false, Relaxed);
+        self._velocity = RadPerSecond::ZERO;
+
+        self._stop_before = None;
+        self._profile = None;
+        self._stop_ramp = None;
+        self._batch.clear();
+        self.builder.reset_ramp();
+
+        self._stats.end_move(self.builder.step_angle(), self._intr_reason, self.pos());
+        self._stats.record_fault(FaultKind::MovementFailed, self.pos());
+
+        self.update_motion_mode();
+    }
+
+    /// Updates the [MotionMode] of the state, notifying the listener if it changed
+    fn update_motion_mode(&mut self) {
+        let mode = if self._state.moving() {
+            match self.builder.drive_mode() {
+                DriveMode::ConstVelocity(_) => MotionMode::ConstVelocity,
+                DriveMode::ConstFactor(_, _) => MotionMode::ConstFactor,
+                DriveMode::FixedDistance(_, _, _) => MotionMode::FixedDistance,
+                // The builder becomes inactive with the last node
+                DriveMode::Stop | DriveMode::Inactive => MotionMode::Stopping
+            }
+        } else if matches!(self._hold_policy, HoldPolicy::Disable) {
+            MotionMode::Inactive
+        } else {
+            MotionMode::Holding
+        };
+
+        let mode_prev = MotionMode::from_u8(self._state._motion_mode.swap(mode as u8, Relaxed));
+
+        if mode != mode_prev {
+            logging::log_debug!("Motion mode changed: {} -> {}", logging::mode_name(mode_prev), logging::mode_name(mode));
+
+            if let Some(listener) = self.mode_listener.as_mut() {
+                listener(mode_prev, mode);
+            }
+        }
+    }
+
+    /// Returns `true` if the current movement is simulated
+    fn simulating(&self) -> bool {
+        self._state.simulated()
+    }
+
+    /// Sets the drive `mode` of the builder, simulated movements do not touch the controller
+    /// 
+    /// The simulation switch is evaluated with every mode starting a movement, independent of the state of the last one
+    fn apply_drive_mode(&mut self, mode : DriveMode) -> Result<(), ActuatorError> {
+        if mode != DriveMode::Stop {
+            self.update_simulation();
+        }
+
+        if self.simulating() {
+            self.builder.set_drive_mode(mode, &mut ProfileController::default())
+        } else {
+            self.builder.set_drive_mode(mode, &mut self.ctrl)
+        }
+    }
+
+    /// Waits for the given `time` in place of the controller while simulating, see [SimulationSwitch::with_delay]
+    fn simulate_wait(&self, time : Seconds) {
+        if let Some(switch) = self._simulation.as_ref() {
+            switch.wait(time);
+        }
+    }
+
+    /// Evaluates the simulation switch at the start of a movement, saving the real position when entering the simulation 
+    /// and restoring it when leaving it
+    fn update_simulation(&mut self) {
+        let simulate = self._simulation.as_ref().is_some_and(SimulationSwitch::is_active);
+
+        if simulate {
+            if self._real_pos.is_none() {
+                logging::log_info!("Simulating movements, real position {} rad", self.pos().0);
+                self._real_pos = Some(self.pos_precise());
+            }
+        } else if let Some(pos) = self._real_pos.take() {
+            logging::log_info!("Simulation finished, restoring real position {} rad", reduced(pos));
+            self._state._abs_pos.store(pos, Relaxed);
+        }
+
+        self._state._simulated.store(simulate, Relaxed);
+    }
+
+    /// The absolute position of the motor with the full precision of the bookkeeping, see [math](crate::math)
+    pub fn pos_precise(&self) -> Float {
+        self._state.pos_precise()
+    }
+
+    /// Sets the `clock` used to timestamp the steps, enabling position predictions with [SyncActuatorState::predicted_pos]
+    /// 
+    /// The clock is called by the thread driving the motor after every step, so it must return quickly
+    pub fn set_clock(&mut self, clock : Option<fn() -> Seconds>) {
+        self._clock = clock;
+    }
+
+    // Statistics
+        /// The lifetime [StepperStats] of the motor
+        /// 
+        /// ```rust
+        /// use syact::prelude::*;
+        /// 
+        /// let mut stepper = Stepper::simulated();
+        /// stepper.drive_rel_blocking(Radians(4.0 * core::f32::consts::PI), Factor::MAX).unwrap();
+        /// 
+        /// assert_eq!(stepper.stats().moves, 1);
+        /// assert_eq!(stepper.stats().steps, 400);
+        /// assert!((stepper.stats().revolutions - 2.0).abs() < 1e-3);
+        /// ```
+        pub fn stats(&self) -> &StepperStats {
+            &self._stats
+        }
+
+        /// Restores the `stats` stored before, e.g. after a restart of the machine, the counts of the motor continue from 
+        /// the values given
+        pub fn restore_stats(&mut self, stats : StepperStats) {
+            self._stats = stats;
+        }
+
+        /// Resets all statistics and clears the fault history, e.g. after the motor has been replaced
+        pub fn reset_stats(&mut self) {
+            self._stats = StepperStats::new();
+        }
+    //
+
+    // Simulation
+        /// Attaches a shared [SimulationSwitch] to the motor, `None` detaches it
+        /// 
+        /// While the switch is active, movements are executed without touching the controller, their timing is reproduced 
+        /// by the switch, see [SimulationSwitch::with_delay]. The real position of the motor is restored at the start of the first movement after the simulation, use 
+        /// [StepperMotor::real_pos] to get it while simulating.
+        pub fn set_simulation_switch(&mut self, switch : Option<SimulationSwitch>) {
+            self._simulation = switch;
+        }
+
+        /// The [SimulationSwitch] attached to the motor
+        pub fn simulation_switch(&self) -> Option<&SimulationSwitch> {
+            self._simulation.as_ref()
+        }
+
+        /// The real position of the motor, differs from the position while movements are simulated
+        pub fn real_pos(&self) -> PositionRad {
+            self._real_pos.map_or(self.pos(), |pos| PositionRad(reduced(pos)))
+        }
+    // 
+
+    // Step batching
+        /// The amount of steps generated with a single call to [StepperController::step_batch], see 
+        /// [StepperMotor::set_step_batch]
+        pub fn step_batch(&self) -> usize {
+            self._step_batch
+        }
+
+        /// Sets the amount of steps generated with a single call to [StepperController::step_batch], `1` (the default) 
+        /// generates every step on its own
+        /// 
+        /// Batching reduces the overhead per step for controllers that output whole pulse trains, allowing high step rates.
+        /// The steps of a batch are prepared before they are generated, interruptors are therefore checked and the position 
+        /// is updated up to `size` steps early, delaying the reaction to interrupts and halts by the duration of a batch.
+        pub fn set_step_batch(&mut self, size : usize) {
+            self._step_batch = size.max(1);
+            self._batch.reserve(self._step_batch);
+        }
+    // 
+
+    // Time scale
+        /// Attaches a shared [TimeScale] to the motor, all following movements are executed at the scale, `None` detaches it
+        pub fn set_time_scale(&mut self, time_scale : Option<TimeScale>) {
+            self._time_scale = time_scale;
+        }
+
+        /// The [TimeScale] attached to the motor
+        pub fn time_scale(&self) -> Option<&TimeScale> {
+            self._time_scale.as_ref()
+        }
+    // 
+
+    // Pre-move checks
+        /// Adds a pre-move `check` with the given `name`, evaluated before every movement of the motor, see [PreMoveChecks]
+        pub fn add_premove_check(&mut self, name : &'static str, check : PreMoveCheck) {
+            self.premove_checks.add(name, check);
+        }
+
+        /// Removes the pre-move check with the given `name`, returns `true` if the check existed
+        pub fn remove_premove_check(&mut self, name : &'static str) -> bool {
+            self.premove_checks.remove(name)
+        }
+
+        /// The pre-move checks of the motor
+        pub fn premove_checks(&self) -> &PreMoveChecks {
+            &self.premove_checks
+        }
+    // 
+
+    /// Set a listener that is called with the previous and the new [MotionMode] every time the mode of the motor changes
+    /// 
+    /// The listener is executed by the thread driving the motor, so it must return quickly
+    pub fn set_mode_listener(&mut self, listener : Option<Box<dyn FnMut(MotionMode, MotionMode) + Send>>) {
+        self.mode_listener = listener;
+    }
+
+    /// Waits for the motor to settle according to the [SettlePolicy], called automatically after every movement
+    fn settle(&mut self) -> Result<(), ActuatorError> {
+        // Simulated movements only reproduce the settle time, the motor has not moved
+        if self.simulating() {
+            if let SettlePolicy::Time(time) = self._settle_policy {
+                self.simulate_wait(time);
+            }
+
+            return Ok(());
+        }
+
+        match self._settle_policy {
+            SettlePolicy::None => Ok(()),
+            SettlePolicy::Time(time) => self.ctrl.wait(time),
+            SettlePolicy::InPosition(timeout) => {
+                let pos = self.pos();
+                let mut waited = Seconds::ZERO;
+
+                // Without a check the motor counts as being in position
+                while let Some(check) = self.in_position.as_mut() {
+                    if check(pos) {
+                        break;
+                    }
+
+                    if waited >= timeout {
+                        logging::log_warn!("Motor did not settle at {} rad within {} s", pos.0, timeout.0);
+                        return Err(ActuatorError::SettleTimeout(waited));
+                    }
+
+                    self.ctrl.wait(SETTLE_POLL_INTERVAL)?;
+                    waited += SETTLE_POLL_INTERVAL;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Executes the current [HoldPolicy] of the motor, called automatically after every movement
+    fn apply_hold_policy(&mut self) -> Result<(), ActuatorError> {
+        if self.simulating() {
+            return Ok(());
+        }
+
+        match self._hold_policy {
+            HoldPolicy::Hold => Ok(()),
+            HoldPolicy::ReduceCurrent(current) => self.ctrl.set_current(current),
+            HoldPolicy::DisableAfter(timeout) => self.ctrl.disable_after(timeout),
+            HoldPolicy::Disable => self.ctrl.set_enabled(false)
+        }
+    }
+
+    /// Returns the current movement direction
+    pub fn direction(&self) -> Direction {
+        self.builder.direction()
+    }
+
+    // Dead-band
+        /// The minimum distance of a movement, `None` if every movement is executed
+        pub fn min_move(&self) -> Option<Radians> {
+            self._min_move
+        }
+
+        /// Set the minimum distance of a movement, smaller movements are coalesced with the following ones until they
+        /// exceed the minimum. Clears the distance of all movements coalesced so far.
+        pub fn set_min_move(&mut self, min_move : Option<Radians>) {
+            self._min_move = min_move.map(|dist| dist.abs());
+            self._pending_dist = Radians::ZERO;
+        }
+
+        /// The distance of the coalesced movements that will be added to the next movement
+        pub fn pending_dist(&self) -> Radians {
+            self._pending_dist
+        }
+    // 
+
+    // Settling
+        /// The [SettlePolicy] executed after every movement
+        pub fn settle_policy(&self) -> SettlePolicy {
+            self._settle_policy
+        }
+
+        /// Set the [SettlePolicy] executed after every movement, before the blocking movement functions return
+        pub fn set_settle_policy(&mut self, policy : SettlePolicy) {
+            self._settle_policy = policy;
+        }
+
+        /// Set the check used by [SettlePolicy::InPosition], `None` removes it and the motor counts as being in position 
+        /// right after the last step
+        /// 
+        /// The check is called with the commanded position, e.g. `|pos| (encoder() - pos).abs() < window` for an encoder 
+        /// window
+        pub fn set_in_position(&mut self, check : Option<InPositionCheck>) {
+            self.in_position = check;
+        }
+    //
+
+    // Stopping
+        /// The velocity of the last step made, positive values mean CW movement, zero if the motor is not moving
+        pub fn velocity(&self) -> RadPerSecond {
+            self._velocity
+        }
+
+        /// The distance the motor requires to stop from its current velocity with the current deceleration limit, see 
+        /// [kin::actuator_stop_distance]
+        /// 
+        /// Returns `None` if neither a deceleration nor an acceleration limit is set, the stop ramp is not known then
+        pub fn stop_distance(&self) -> Option<Radians> {
+            kin::actuator_stop_distance(self, self._velocity).map(|dist| dist.abs())
+        }
+
+        /// The [StopMode] used for stops requested by interruptors, halts and interrupts
+        pub fn stop_mode(&self) -> StopMode {
+            self._stop_mode
+        }
+
+        /// Set the [StopMode] used for stops requested by interruptors, halts and interrupts
+        /// 
+        /// ```rust
+        /// use syact::prelude::*;
+        /// 
+        /// let mut stepper = Stepper::simulated();
+        /// 
+        /// // Ramp down smoothly instead of stopping instantly like the start-stop builder does
+        /// stepper.set_stop_mode(StopMode::DecelRamp(RadPerSecond2(200.0))).unwrap();
+        /// assert_eq!(stepper.stop_mode(), StopMode::DecelRamp(RadPerSecond2(200.0)));
+        /// ```
+        /// 
+        /// Returns [ActuatorError::InvalidAcceleration] if the deceleration of [StopMode::DecelRamp] is not positive and finite
+        pub fn set_stop_mode(&mut self, mode : StopMode) -> Result<(), ActuatorError> {
+            if let StopMode::DecelRamp(deceleration) = mode {
+                if !deceleration.is_normal() || (deceleration <= RadPerSecond2::ZERO) {
+                    return Err(ActuatorError::InvalidAcceleration(deceleration));
+                }
+            }
+
+            self._stop_mode = mode;
+            Ok(())
+        }
 
-    // Limits
-    _limit_min : Option<PositionRad>,
-    _limit_max : Option<PositionRad>,
+        /// The position the next movement stops before, see [StepperMotor::stop_before]
+        pub fn stop_before_pos(&self) -> Option<PositionRad> {
+            self._stop_before
+        }
 
-    // Interrupters
-    interruptors : Vec<Box<dyn Interruptor<Rotary> + Send>>,
-    _intr_reason : Option<InterruptReason>,
-}
+        /// Makes the next movement stop before the given position `pos`, triggering a controlled stop at the last possible
+        /// moment, `None` clears the position
+        /// 
+        /// Useful for approach moves, e.g. driving with [SyncActuatorBlocking::drive_factor] towards a sensor that normally
+        /// interrupts the movement, while making sure the motor never passes the end of the travel. The position is only 
+        /// watched while moving towards it and is cleared once the movement has finished.
+        /// 
+        /// Returns [ActuatorError::InvalidAcceleration] if neither a deceleration nor an acceleration limit is set, as the 
+        /// stop distance is not known then
+        pub fn stop_before(&mut self, pos : Option<PositionRad>) -> Result<(), ActuatorError> {
+            if pos.is_some() && self.deceleration_max().or(self.acceleration_max()).is_none() {
+                return Err(ActuatorError::InvalidAcceleration(RadPerSecond2::ZERO));
+            }
 
-// Inits
-impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {   
-    /// ######################################
-    /// #    StepperMotor::handle_builder    #
-    /// ######################################
-    ///
-    /// Main driving algorithm for stepper motors, handles the builder until no nodes are left anymore
-    /// 
-    /// ## Thread
-    /// 
-    /// Blocks the current thread and creates the step signals until the builder is finished
-    pub fn handle_builder(&mut self) -> Result<(), ActuatorError> {
-        // Update the movement variable of the state
-        self._state._moving.store(false, Relaxed);
-        
-        // Iterate through the builder until no nodes are left
-        while let Some(node) = self.builder.next() {
-            // Get the current direction of the motor (builder)
-            let direction = self.builder.direction();
-            // Get the current drive mode of the motor (builder)
-            let drive_mode = self.builder.drive_mode();
-
-            // Check all interruptors if the motor is not stopping already
-            if *drive_mode != DriveMode::Stop {
-                for intr in self.interruptors.iter_mut() {
-                    // Check if the direction is right
-                    if let Some(i_dir) = intr.dir() {
-                        if i_dir != direction {
-                            // If the interruptors checking-direction does not match the current direction, 
-                            //     the loop skips to the next interruptor
-                            continue;
-                        }
-                    }
+            self._stop_before = pos;
+            Ok(())
+        }
+    //
 
-                    // Checks if the interruptor has been triggered
-                    if let Some(reason) = intr.check(self._state.pos()) {
-                        intr.set_temp_dir(Some(direction));
-                        self._intr_reason.replace(reason);
-                        
-                        self.builder.set_drive_mode(DriveMode::Stop, &mut self.ctrl)?; 
-                    } else {
-                        // Clear temporary direction
-                        intr.set_temp_dir(None);
-                    }
+    // Jolt
+        /// The [JoltTable] of the builder, if set
+        pub fn jolt_table(&self) -> Option<&JoltTable> {
+            self.builder.jolt_table()
+        }
+
+        /// Set the [JoltTable] of the builder, allowing higher jolts at low velocities, see [StepperBuilder::set_jolt_table]
+        pub fn set_jolt_table(&mut self, table_opt : Option<JoltTable>) -> Result<(), ActuatorError> {
+            self.builder.set_jolt_table(table_opt)
+        }
+    //
+
+    // Diagnostics
+        /// The constraint currently limiting the maximum velocity of the motor, see [StepperBuilder::velocity_constraint]
+        pub fn velocity_constraint(&self) -> Option<VelocityConstraint> {
+            self.builder.velocity_constraint()
+        }
+    //
+
+    // Commands
+        /// The [CommandPolicy] of the builder, used for commands arriving while the motor is ramping down
+        pub fn command_policy(&self) -> CommandPolicy {
+            self.builder.command_policy()
+        }
+
+        /// Set the [CommandPolicy] of the builder, used for commands arriving while the motor is ramping down
+        pub fn set_command_policy(&mut self, policy : CommandPolicy) {
+            self.builder.set_command_policy(policy)
+        }
+    //
+
+    // Rounding
+        /// The [RoundingPolicy] used to convert relative distances into steps
+        pub fn rounding_policy(&self) -> RoundingPolicy {
+            self._rounding
+        }
+
+        /// Set the [RoundingPolicy] used to convert relative distances into steps, clears the sub-step residue
+        pub fn set_rounding_policy(&mut self, policy : RoundingPolicy) {
+            self._rounding = policy;
+            self._step_remainder = 0.0;
+        }
+
+        /// Moves the motor by the relative distance `rel_dist`, like [SyncActuatorBlocking::drive_rel_blocking], but reports
+        /// whether the movement has actually been executed
+        /// 
+        /// - Movements smaller than the minimum move (see [StepperMotor::set_min_move]) are coalesced with the following ones
+        /// - Movements that round to zero steps are skipped, no step signals are generated
+        pub fn drive_rel_checked(&mut self, rel_dist : Radians, speed_f : Factor) -> Result<MoveOutcome, ActuatorError> {
+            self.drive_rel_exit(rel_dist, speed_f, Factor::MIN)
+        }
+
+        /// Moves the motor by the relative distance `rel_dist` like [StepperMotor::drive_rel_checked], leaving the movement 
+        /// with the speed factor `exit`
+        fn drive_rel_exit(&mut self, rel_dist : Radians, speed_f : Factor, exit : Factor) -> Result<MoveOutcome, ActuatorError> {
+            if !rel_dist.is_finite() {
+                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+            }
+
+            let rel_dist = rel_dist + self._pending_dist;
+
+            if let Some(min_move) = self._min_move {
+                if rel_dist.abs() < min_move {
+                    self._pending_dist = rel_dist;
+                    return Ok(MoveOutcome::Coalesced(rel_dist));
                 }
             }
 
-            // Make step and return error if occured
-            self.ctrl.step(node)?;
+            self._pending_dist = Radians::ZERO;
 
-            // Check if the pos value exeeds any limits, stop the movement if it does
-            if direction.as_bool() {
-                self._state._abs_pos.fetch_add(self.builder.step_angle().0, Relaxed);
+            let rel_dist = self.clamp_to_limits(rel_dist);
+            let rel_dist = self.round_rel_dist(rel_dist);
 
-                if self.pos() > self.limit_max().unwrap_or(PositionRad::INFINITY) {
-                    self.builder.set_drive_mode(DriveMode::Stop, &mut self.ctrl)?;
-                } 
-            } else {
-                self._state._abs_pos.fetch_sub(self.builder.step_angle().0, Relaxed);
+            if rel_dist == Radians::ZERO {
+                return Ok(MoveOutcome::Skipped);
+            }
+
+            self.premove_checks.run()?;
+
+            // Reject movements the driver cannot follow before the first step
+            self.check_driver_velocity(self.builder.velocity_possible() * speed_f)?;
+
+            // Set drive mode, return mapped error if one occurs
+            let velocity_exit = self.builder.velocity_possible() * exit;
+
+            self.apply_drive_mode(DriveMode::FixedDistance(rel_dist, velocity_exit, speed_f))?;
+            self._blending = velocity_exit > RadPerSecond::ZERO;
+            self.handle_builder()?;
+
+            Ok(MoveOutcome::Executed)
+        }
+
+        /// Moves the motor by the relative distance `rel_dist` with the factor `speed`, blending into the slower `creep_speed` 
+        /// for the final `creep_dist` of the movement, e.g. for docking or tool changes. Blocks until the movement is done
+        /// 
+        /// The approach and the creep segment are executed as two blended segments of a [SegmentQueue], the approach slows 
+        /// down to the creep speed along the deceleration ramp of the builder. Returns [ActuatorError::InvalidAcceleration] if 
+        /// neither a deceleration nor an acceleration limit is set, as the approach could not slow down then.
+        pub fn drive_rel_approach(&mut self, rel_dist : Radians, speed : Factor, creep_dist : Radians, creep_speed : Factor) 
+            -> Result<(), ActuatorError> 
+        {
+            if !rel_dist.is_finite() {
+                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+            }
+
+            if !creep_dist.is_finite() || (creep_dist.abs() > rel_dist.abs()) {
+                return Err(ActuatorError::InvaldRelativeDistance(creep_dist));
+            }
+
+            if self.deceleration_max().or(self.acceleration_max()).is_none() {
+                return Err(ActuatorError::InvalidAcceleration(RadPerSecond2::ZERO));
+            }
+
+            // The creep segment points in the direction of the movement
+            let creep_dist = if rel_dist < Radians::ZERO { -creep_dist.abs() } else { creep_dist.abs() };
 
-                if self.pos() < self.limit_min().unwrap_or(PositionRad::NEG_INFINITY) {
-                    self.builder.set_drive_mode(DriveMode::Stop, &mut self.ctrl)?;
-                } 
+            let mut segments = SegmentQueue::new();
+            segments.push_blended(rel_dist - creep_dist, speed, creep_speed, ());
+            segments.push(creep_dist, creep_speed, ());
+
+            while let Some(event) = segments.run_next(self) {
+                match event {
+                    SegmentEvent::Completed(_) => { },
+                    // The queue takes the reason of the interrupt, it is reported by the motor again
+                    SegmentEvent::Interrupted(_, reason) => {
+                        self._intr_reason = Some(reason);
+                        return Ok(());
+                    },
+                    SegmentEvent::Held(_) => {
+                        self._intr_reason = Some(InterruptReason::Halted);
+                        return Ok(());
+                    },
+                    SegmentEvent::Failed(_, err) => return Err(err)
+                }
             }
+
+            Ok(())
         }
 
-        // No movement anymore
-        self._state._moving.store(false, Relaxed);
+        /// Moves the motor along the trapezoidal `profile`, bypassing the force and inertia modelling of the builder. Blocks 
+        /// until the movement is done
+        /// 
+        /// The velocity limit of the motor still applies, faster profiles return [ActuatorError::VelocityTooHigh]. Interruptors, 
+        /// halts and position limits stop the motor with the stop ramp of the builder.
+        /// 
+        /// ```rust
+        /// use syact::prelude::*;
+        /// 
+        /// let mut stepper = Stepper::simulated();
+        /// 
+        /// stepper.drive_with_profile(&RampProfile::new(Radians(10.0), RadPerSecond(20.0), RadPerSecond2(100.0))).unwrap();
+        /// assert!((stepper.pos() - PositionRad(10.0)).abs() < Radians(0.05));
+        /// ```
+        pub fn drive_with_profile(&mut self, profile : &RampProfile) -> Result<(), ActuatorError> {
+            profile.check()?;
 
-        Ok(())
-    }
+            if let Some(velocity_max) = self.velocity_max() {
+                if profile.velocity_max > velocity_max {
+                    return Err(ActuatorError::VelocityTooHigh(profile.velocity_max, velocity_max));
+                }
+            }
 
-    /// Returns the current movement direction
-    pub fn direction(&self) -> Direction {
-        self.builder.direction()
+            self.check_driver_velocity(profile.velocity_max)?;
+            let rel_dist = self.round_rel_dist(self.clamp_to_limits(profile.distance));
+
+            if rel_dist == Radians::ZERO {
+                return Ok(());
+            }
+
+            self.premove_checks.run()?;
+
+            self._profile = Some(RampProfile { distance: rel_dist, ..*profile }.step_times(self.builder.step_angle()));
+            self._profile_time = Seconds::ZERO;
+
+            let result = self.apply_drive_mode(DriveMode::FixedDistance(rel_dist, RadPerSecond::ZERO, Factor::MAX))
+                .and_then(|_| self.handle_builder());
+
+            // The profile only applies to this movement, even if it failed
+            self._profile = None;
+            result
+        }
+
+        /// Rounds the relative distance `rel_dist` to a whole number of steps according to the rounding policy
+        fn round_rel_dist(&mut self, rel_dist : Radians) -> Radians {
+            let step_angle = self.builder.step_angle();
+            let steps = self._rounding.apply(rel_dist / step_angle, &mut self._step_remainder);
+
+            step_angle * (steps as f32)
+        }
+    // 
+}
+
+impl<B : StepperBuilder, C : StepperController> Drop for StepperMotor<B, C> {
+    fn drop(&mut self) {
+        // Ramp down if the motor has been dropped mid-movement, simulated movements have no ramp to generate
+        if self._state.moving() && !self.simulating() {
+            logging::log_warn!("Motor dropped while moving at {} rad, ramping down", self.pos().0);
+
+            if self.builder.set_drive_mode(DriveMode::Stop, &mut self.ctrl).is_ok() {
+                for node in self.builder.by_ref() {
+                    if self.ctrl.step(node).is_err() {
+                        logging::log_error!("Step failed while ramping down a dropped motor");
+                        break;
+                    }
+                }
+            } else {
+                logging::log_error!("Failed to ramp down a dropped motor");
+            }
+        }
+
+        // De-assert all pins, errors cannot be handled anymore, so they are only logged
+        if self.ctrl.stop().is_err() {
+            logging::log_error!("Failed to stop the controller of a dropped motor");
+        }
+        self._state._moving.store(false, Relaxed);
     }
 }
 
@@ -121,7 +1290,7 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
 
             #[inline]
             fn overwrite_abs_pos(&mut self, pos : PositionRad) {
-                self._state._abs_pos.store(pos.0, Relaxed);
+                self._state._abs_pos.store(precise(pos.0), Relaxed);
             }
         //
 
@@ -136,6 +1305,16 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
                 self.builder.set_velocity_max(velocity_opt)?;
                 Ok(())
             }
+
+            #[inline]
+            fn velocity_min(&self) -> Option<RadPerSecond> {
+                self.builder.velocity_min()
+            }
+
+            #[inline]
+            fn set_velocity_min(&mut self, velocity_opt : Option<RadPerSecond>) -> Result<(), ActuatorError> {
+                self.builder.set_velocity_min(velocity_opt)
+            }
         //
 
         // Acceleration
@@ -148,6 +1327,16 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
                 self.builder.set_acceleration_max(acceleration_opt)?;
                 Ok(())
             }
+
+            #[inline]
+            fn deceleration_max(&self) -> Option<RadPerSecond2> {
+                self.builder.deceleration_max()
+            }
+
+            fn set_deceleration_max(&mut self, deceleration_opt : Option<RadPerSecond2>) -> Result<(), ActuatorError> {
+                self.builder.set_deceleration_max(deceleration_opt)?;
+                Ok(())
+            }
         //
 
         // Jolt
@@ -228,6 +1417,15 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
                     if dir { Some(overwrite_abs_pos) } else { None }
                 )
             }
+
+            fn limit_behavior(&self) -> LimitBehavior {
+                self._limit_behavior
+            }
+
+            fn set_limit_behavior(&mut self, behavior : LimitBehavior) -> Result<(), ActuatorError> {
+                self._limit_behavior = behavior;
+                Ok(())
+            }
         //
     }
 
@@ -243,24 +1441,44 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
         // 
 
         fn drive_rel_blocking(&mut self, rel_dist : Radians, speed_f : Factor) -> Result<(), ActuatorError> {
-            if !rel_dist.is_finite() {
-                return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
-            }
+            self.drive_rel_checked(rel_dist, speed_f).map(|_| ())
+        }
 
-            // Set drive mode, return mapped error if one occurs
-            self.builder.set_drive_mode(DriveMode::FixedDistance(rel_dist, RadPerSecond::ZERO, speed_f), &mut self.ctrl)?;
-            self.handle_builder()
+        fn drive_rel_blocking_blended(&mut self, rel_dist : Radians, speed_f : Factor, exit : Factor) -> Result<(), ActuatorError> {
+            self.drive_rel_exit(rel_dist, speed_f, exit).map(|_| ())
+        }
+
+        fn drive_rel_blocking_with<F>(&mut self, rel_dist : Radians, speed_f : Factor, observer : F) -> Result<(), ActuatorError> 
+        where
+            F : FnOnce(Arc<dyn SyncActuatorState + Send + Sync>)
+        {
+            observer(self._state.clone());
+            self.drive_rel_blocking(rel_dist, speed_f)
         }
 
         fn drive_factor(&mut self, speed : Factor, direction : Direction) -> Result<(), ActuatorError> {
+            if self._heartbeat_lost {
+                return Err(ActuatorError::HeartbeatLost);
+            }
+
+            self.check_driver_velocity(self.builder.velocity_possible() * speed)?;
+            self.premove_checks.run()?;
+
             // Set drive mode, return mapped error if one occurs
-            self.builder.set_drive_mode(DriveMode::ConstFactor(speed, direction), &mut self.ctrl)?;
+            self.apply_drive_mode(DriveMode::ConstFactor(speed, direction))?;
             self.handle_builder()
         }
     
         fn drive_speed(&mut self, speed : RadPerSecond) -> Result<(), ActuatorError> {
+            if self._heartbeat_lost {
+                return Err(ActuatorError::HeartbeatLost);
+            }
+
+            self.check_driver_velocity(speed)?;
+            self.premove_checks.run()?;
+
             // Set drive mode, return mapped error if one occurs
-            self.builder.set_drive_mode(DriveMode::ConstVelocity(speed), &mut self.ctrl)?;
+            self.apply_drive_mode(DriveMode::ConstVelocity(speed))?;
             self.handle_builder()
         }
     }
@@ -272,39 +1490,116 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
     impl<B : SimpleStepperBuilder, C : StepperController> StepperMotor<B, C> {
         /// Creates a new stepper motor with the given controller `ctrl` 
         pub fn new_simple(ctrl : C) -> Result<Self, ActuatorError> {
-            Ok(Self {
-                builder: B::new()?,
-                ctrl,
-
-                _state : Arc::new(StepperState::new()),
+            let motor = Self::from_parts(B::new()?, ctrl);
 
-                _limit_min: None,
-                _limit_max: None,
+            if let Some(limits) = motor.ctrl.driver_limits() {
+                limits.check_microsteps(motor.builder.microsteps()).map_err(ActuatorError::DriverLimitExceeded)?;
+            }
 
-                interruptors : Vec::new(),
-                _intr_reason: None
-            })
+            Ok(motor)
         }
     }
 
     impl<B : AdvancedStepperBuilder, C : StepperController> StepperMotor<B, C> {
         /// Creates a new stepper motor with the given constants `consts` and configuration `config`
         pub fn new_advanced(ctrl : C, consts : StepperConst, config : StepperConfig) -> Result<Self, ActuatorError> {
-            Ok(Self {
-                builder: B::new(consts, config)?,
-                ctrl,
-
-                _state : Arc::new(StepperState::new()),
+            let motor = Self::from_parts(B::new(consts, config)?, ctrl);
 
-                _limit_min: None,
-                _limit_max: None,
+            if let Some(limits) = motor.ctrl.driver_limits() {
+                limits.check(motor.builder.consts(), motor.builder.config(), motor.builder.microsteps())
+                    .map_err(ActuatorError::DriverLimitExceeded)?;
+            }
 
-                interruptors : Vec::new(),
-                _intr_reason: None
-            })
+            Ok(motor)
         }
     }
 
+    impl<B : AdvancedStepperBuilder, C : StepperController> StepperMotor<B, C> {
+        // Configuration
+            /// The constants of the motor
+            pub fn consts(&self) -> &StepperConst {
+                self.builder.consts()
+            }
+
+            /// The current configuration of the motor
+            pub fn config(&self) -> &StepperConfig {
+                self.builder.config()
+            }
+
+            /// The loads and limits applied to the motor
+            pub fn vars(&self) -> &ActuatorVars {
+                self.builder.vars()
+            }
+
+            /// Checks if the driver can deliver the current required by the `config`, see [StepperController::driver_limits]
+            fn check_driver_current(&self, config : &StepperConfig) -> Result<(), ActuatorError> {
+                match self.ctrl.driver_limits() {
+                    Some(limits) => limits.check_current(self.builder.consts(), config).map_err(ActuatorError::DriverLimitExceeded),
+                    None => Ok(())
+                }
+            }
+
+            /// Sets the configuration of the motor after checking it against the constants of the motor and the limits of 
+            /// the driver, see [StepperConfig::check]
+            /// 
+            /// The builder is recalculated with the new configuration, if this fails the previous configuration is restored.
+            /// Selecting a configuration directly leaves the current configuration profile.
+            pub fn set_config(&mut self, config : StepperConfig) -> Result<(), ActuatorError> {
+                config.check(self.builder.consts()).map_err(ActuatorError::InvalidConfig)?;
+                self.check_driver_current(&config)?;
+
+                let config_prev = self.builder.config().clone();
+
+                if let Err(err) = self.builder.set_config(config) {
+                    self.builder.set_config(config_prev)?;
+                    return Err(err);
+                }
+
+                self._config_profile = None;
+                Ok(())
+            }
+        // 
+
+        // Configuration profiles
+            /// Registers a named [StepperConfig] profile (e.g. "precise" with a low current or "rapid" with an overload current),
+            /// overwriting any profile with the same name
+            pub fn add_config_profile(&mut self, name : &'static str, config : StepperConfig) {
+                if let Some(profile) = self.config_profiles.iter_mut().find(|(n, _)| *n == name) {
+                    profile.1 = config;
+                } else {
+                    self.config_profiles.push((name, config));
+                }
+            }
+
+            /// The name of the currently active configuration profile, `None` if none has been selected yet
+            pub fn config_profile(&self) -> Option<&'static str> {
+                self._config_profile
+            }
+
+            /// Switches to the configuration profile with the given `name`
+            /// 
+            /// As movements borrow the motor mutably, the switch always happens between two movements. The builder 
+            /// is recalculated with the new configuration, if this fails the previous configuration is restored.
+            pub fn select_config_profile(&mut self, name : &str) -> Result<(), ActuatorError> {
+                let (name, config) = self.config_profiles.iter()
+                    .find(|(n, _)| *n == name)
+                    .cloned()
+                    .ok_or(ActuatorError::UnknownConfigProfile)?;
+
+                self.check_driver_current(&config)?;
+                let config_prev = self.builder.config().clone();
+
+                if let Err(err) = self.builder.set_config(config) {
+                    self.builder.set_config(config_prev)?;
+                    return Err(err);
+                }
+
+                self._config_profile = Some(name);
+                Ok(())
+            }
+        // 
+    }
+
     impl<B : AdvancedStepperBuilder, C : StepperController> AdvancedActuator for StepperMotor<B, C> {
         // Loads
             fn force_gen(&self) -> NewtonMeters {
@@ -336,6 +1631,19 @@ impl<B : StepperBuilder, C : StepperController> StepperMotor<B, C> {
             }
         //
     }
+
+    impl<C : StepperController> StepperMotor<TrapezoidalBuilder, C> {
+        /// Returns `true` if the limits of the builder are additionally capped by the torque model of the motor, see 
+        /// [TrapezoidalBuilder::set_torque_model]
+        pub fn torque_model(&self) -> bool {
+            self.builder.torque_model()
+        }
+
+        /// Enables or disables the torque model of the builder, see [TrapezoidalBuilder::set_torque_model]
+        pub fn set_torque_model(&mut self, enabled : bool) {
+            self.builder.set_torque_model(enabled)
+        }
+    }
 // 
 
 impl<B : StepperBuilder, C : StepperController> StepperActuator for StepperMotor<B, C> 
@@ -348,14 +1656,92 @@ where
         }
 
         fn set_microsteps(&mut self, microsteps : MicroSteps) -> Result<(), ActuatorError> {
-            self.builder.set_microsteps(microsteps)
-            
+            if let Some(limits) = self.ctrl.driver_limits() {
+                limits.check_microsteps(microsteps).map_err(ActuatorError::DriverLimitExceeded)?;
+            }
+
+            self.builder.set_microsteps(microsteps)?;
+            self.ctrl.set_microsteps(microsteps)
         }
     //
 
     fn step_dist(&self) -> Radians {
         self.builder.step_angle()
     }
+
+    // Hold policy
+        fn hold_policy(&self) -> HoldPolicy {
+            self._hold_policy
+        }
+
+        fn set_hold_policy(&mut self, policy : HoldPolicy) {
+            self._hold_policy = policy;
+        }
+    // 
+
+    // Heartbeat
+        fn heartbeat_timeout(&self) -> Option<Seconds> {
+            self._heartbeat_timeout
+        }
+
+        fn set_heartbeat_timeout(&mut self, timeout : Option<Seconds>) {
+            self._heartbeat_timeout = timeout;
+        }
+
+        fn heartbeat_lost(&self) -> bool {
+            self._heartbeat_lost
+        }
+
+        fn reset_heartbeat_lost(&mut self) {
+            self._heartbeat_lost = false;
+        }
+    // 
+}
+
+impl<B : StepperBuilder, C : StepperController> Capabilities for StepperMotor<B, C> {
+    fn sensors(&self) -> Vec<&'static str> {
+        self.interruptors.iter().map(|intr| intr.kind()).collect()
+    }
+}
+
+impl<B : StepperBuilder, C : StepperController> PositionLayers for StepperMotor<B, C> {
+    fn layer_count(&self) -> usize {
+        1
+    }
+
+    fn layer_name(&self, index : usize) -> Option<&'static str> {
+        (index == 0).then_some("motor")
+    }
+
+    fn layer_pos(&self, index : usize) -> Option<LayerPos> {
+        (index == 0).then(|| LayerPos::Rotary(self.pos()))
+    }
+}
+
+impl<B : AdvancedStepperBuilder, C : StepperController> SelfTest for StepperMotor<B, C> {
+    fn self_test(&mut self, axis : Option<usize>) -> Vec<ReadinessCheck> {
+        let mut checks = Vec::new();
+
+        // Setting the current direction again does not change the output, but requires the controller to respond
+        let dir = self.ctrl.direction();
+        checks.push(ReadinessCheck::new(axis, CheckKind::Controller, "StepperController",
+            self.ctrl.set_dir(dir).map_err(|_| "the controller does not respond")));
+
+        let mut config = self.builder.config().check(self.builder.consts());
+
+        if let (Ok(()), Some(limits)) = (config, self.ctrl.driver_limits()) {
+            config = limits.check(self.builder.consts(), self.builder.config(), self.builder.microsteps())
+                .map_err(|_| "the configuration exceeds the limits of the driver");
+        }
+
+        checks.push(ReadinessCheck::new(axis, CheckKind::Config, "StepperConfig", config));
+
+        for intr in self.interruptors.iter_mut() {
+            checks.push(ReadinessCheck::new(axis, CheckKind::Interruptor, intr.kind(), intr.probe()));
+        }
+
+        checks
+    }
 }
 
 impl<B : StepperBuilder, C : StepperController> Interruptible for StepperMotor<B, C> {
@@ -364,9 +1750,25 @@ impl<B : StepperBuilder, C : StepperController> Interruptible for StepperMotor<B
             self.interruptors.push(interruptor);
         }
 
+        fn remove_interruptors(&mut self, kind : &str) -> usize {
+            let len = self.interruptors.len();
+            self.interruptors.retain(|intr| intr.kind() != kind);
+            len - self.interruptors.len()
+        }
+
+        fn remove_interruptors_of(&mut self, kind : &str, source : usize) -> usize {
+            let len = self.interruptors.len();
+            self.interruptors.retain(|intr| (intr.kind() != kind) || (intr.source() != Some(source)));
+            len - self.interruptors.len()
+        }
+
         fn intr_reason(&mut self) -> Option<InterruptReason> {
             // Return the value and replace it with `None`
-            core::mem::replace(&mut self._intr_reason, None)
+            self._intr_reason.take()
+        }
+
+        fn intr_pos(&self) -> Option<PositionRad> {
+            self._intr_pos
         }
     // 
 }
@@ -378,4 +1780,66 @@ where
     fn ptp_time_for_distance(&self, abs_pos_0 : PositionRad, abs_pos_t : PositionRad) -> Seconds {
         self.builder.ptp_time_for_distance(abs_pos_0, abs_pos_t)
     }
+}
+
+impl<B : StepperBuilder, C : StepperController> InterpolatedStepper for StepperMotor<B, C> 
+where
+    B : DefinedActuator
+{
+    fn axis_pos(&self) -> PositionRad {
+        self.pos()
+    }
+
+    fn check_premove(&mut self) -> Result<(), ActuatorError> {
+        self.premove_checks.run()
+    }
+
+    fn begin_rel(&mut self, rel_dist : Radians, speed_f : Factor) -> Result<(), ActuatorError> {
+        if !rel_dist.is_finite() {
+            return Err(ActuatorError::InvaldRelativeDistance(rel_dist));
+        }
+
+        self.check_driver_velocity(self.builder.velocity_possible() * speed_f)?;
+        let rel_dist = self.round_rel_dist(rel_dist);
+
+        self.apply_drive_mode(DriveMode::FixedDistance(rel_dist, RadPerSecond::ZERO, speed_f))?;
+
+        if let Err(err) = self.begin_movement() {
+            self.abort_movement();
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    fn next_node(&mut self) -> Option<Seconds> {
+        self.pull_node()
+    }
+
+    fn step_node(&mut self, step_time : Seconds) -> Result<(), ActuatorError> {
+        self.process_node(step_time)
+    }
+
+    fn end_movement(&mut self) -> Result<(), ActuatorError> {
+        // Movements of groups end early if any step failed, the builder has nodes left then
+        if *self.builder.drive_mode() != DriveMode::Inactive {
+            self.abort_movement();
+            return Ok(());
+        }
+
+        self.finish_movement(false)?;
+        self.settle()
+    }
+
+    fn interrupted(&self) -> Option<InterruptReason> {
+        self._intr_reason
+    }
+
+    fn begin_stop(&mut self) -> Result<(), ActuatorError> {
+        if *self.builder.drive_mode() == DriveMode::Inactive {
+            return Ok(());
+        }
+
+        self.apply_drive_mode(DriveMode::Stop)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,264 @@
+use core::f32::consts::PI;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering::Relaxed;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use syunit::*;
+use syunit::metric::*;
+
+use crate::ActuatorError;
+use crate::data::{DriverLimits, StepperConfig, StepperConst};
+use crate::math::{AtomicFloat, precise, reduced};
+use crate::sync::stepper::{AdvancedStepperBuilder, StartStopBuilder, StepperController, StepperMotor, TrapezoidalBuilder};
+#[cfg(feature = "complex-builder")]
+use crate::sync::stepper::ComplexBuilder;
+
+// ######################
+// #    Disturbances    #
+// ######################
+    /// A load disturbance acting on a simulated axis
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Disturbance {
+        /// Friction torque opposing every movement, starting once the simulated time reaches `at`
+        FrictionStep {
+            /// The simulated time the friction starts at
+            at : Seconds,
+            /// The friction torque
+            torque : NewtonMeters
+        },
+        /// Sinusoidal torque ripple over the position of the axis, opposing movements in `CW` direction on the first half of 
+        /// each period and assisting them on the other
+        TorqueRipple {
+            /// The amplitude of the ripple
+            amplitude : NewtonMeters,
+            /// The distance of one period
+            period : Radians
+        },
+        /// The axis cannot pass the position, e.g. because a part jammed
+        Jam {
+            /// The position of the jam
+            pos : PositionRad
+        }
+    }
+
+    /// Programmable load disturbances of a [SimulatedController], used to test closed-loop logic, interruptors and fault 
+    /// handling without a real machine
+    ///
+    /// Every step is checked against the sum of all disturbances: if the load exceeds the torque available, the step is lost 
+    /// and the actual position of the [SimulatedAxis] falls behind the commanded one.
+    #[derive(Clone, Debug)]
+    pub struct DisturbanceProfile {
+        /// The step angle of the simulated motor, used to track the actual position
+        pub step_angle : Radians,
+        /// The torque the motor is able to deliver
+        pub torque_available : NewtonMeters,
+        /// The disturbances acting on the axis
+        pub disturbances : Vec<Disturbance>,
+        /// Lost steps return [ActuatorError::Overload] if `true`, like drivers with stall detection do, otherwise they are lost
+        /// silently, like with simple drivers
+        pub report_stall : bool
+    }
+
+    impl DisturbanceProfile {
+        /// Creates a new profile without any disturbances, lost steps are not reported
+        pub fn new(step_angle : Radians, torque_available : NewtonMeters) -> Self {
+            Self {
+                step_angle,
+                torque_available,
+                disturbances: Vec::new(),
+                report_stall: false
+            }
+        }
+
+        /// Adds a disturbance to the profile
+        pub fn with(mut self, disturbance : Disturbance) -> Self {
+            self.disturbances.push(disturbance);
+            self
+        }
+
+        /// Returns `true` if a step from the actual position `pos` in the given `direction` is lost at the simulated time 
+        /// `elapsed`
+        pub fn step_lost(&self, pos : PositionRad, direction : Direction, elapsed : Seconds) -> bool {
+            let step = if direction.as_bool() { self.step_angle } else { -self.step_angle };
+            let next = pos + step;
+            let mut load = NewtonMeters::ZERO;
+
+            for disturbance in self.disturbances.iter() {
+                match *disturbance {
+                    Disturbance::FrictionStep { at, torque } => if elapsed >= at {
+                        load += torque.abs();
+                    },
+                    Disturbance::TorqueRipple { amplitude, period } => {
+                        let ripple = amplitude * (2.0 * PI * pos.0 / period.0).sin();
+                        load += if direction.as_bool() { ripple } else { -ripple };
+                    },
+                    Disturbance::Jam { pos: jam } => if ((pos < jam) && (next >= jam)) || ((pos > jam) && (next <= jam)) {
+                        return true;
+                    }
+                }
+            }
+
+            load > self.torque_available
+        }
+    }
+
+    /// The actual state of an axis driven by a [SimulatedController], shared with other threads 
+    ///
+    /// Can be used as simulated encoder for closed-loop logic and supervisors
+    #[derive(Debug, Default)]
+    pub struct SimulatedAxis {
+        _pos : AtomicFloat,
+        _lost_steps : AtomicU64
+    }
+
+    impl SimulatedAxis {
+        /// The actual position of the axis, starting at zero
+        pub fn pos(&self) -> PositionRad {
+            PositionRad(reduced(self._pos.load(Relaxed)))
+        }
+
+        /// The amount of steps lost because of disturbances
+        pub fn lost_steps(&self) -> u64 {
+            self._lost_steps.load(Relaxed)
+        }
+    }
+//
+
+/// A software-only stepper controller, useful for examples, tutorials and tests without any hardware
+///
+/// By default the steps are executed instantly and only the simulated time is accumulated. A `delay` function can be added
+/// to run the simulation in real time, a [DisturbanceProfile] to simulate loads acting on the axis.
+#[derive(Clone, Debug, Default)]
+pub struct SimulatedController {
+    _dir : Direction,
+    _steps : u64,
+    _elapsed : Seconds,
+
+    delay : Option<fn(Seconds)>,
+    disturbances : Option<DisturbanceProfile>,
+    driver_limits : Option<DriverLimits>,
+    _axis : Arc<SimulatedAxis>
+}
+
+impl SimulatedController {
+    /// Creates a new simulated controller, executing all steps instantly
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new simulated controller, that calls the `delay` function with the duration of every step
+    pub fn with_delay(delay : fn(Seconds)) -> Self {
+        Self {
+            delay: Some(delay),
+            ..Self::default()
+        }
+    }
+
+    /// Adds the disturbance `profile` to the controller
+    pub fn with_disturbances(mut self, profile : DisturbanceProfile) -> Self {
+        self.disturbances = Some(profile);
+        self
+    }
+
+    /// Adds the limits of a simulated driver to the controller, see [StepperController::driver_limits]
+    pub fn with_driver_limits(mut self, limits : DriverLimits) -> Self {
+        self.driver_limits = Some(limits);
+        self
+    }
+
+    /// The actual state of the simulated axis, only tracked if a [DisturbanceProfile] has been added
+    pub fn axis(&self) -> Arc<SimulatedAxis> {
+        self._axis.clone()
+    }
+
+    /// The amount of steps generated since the controller has been created
+    pub fn steps(&self) -> u64 {
+        self._steps
+    }
+
+    /// The simulated time the generated steps took
+    pub fn elapsed(&self) -> Seconds {
+        self._elapsed
+    }
+}
+
+impl StepperController for SimulatedController {
+    fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+        if let Some(delay) = self.delay {
+            delay(time);
+        }
+
+        self._steps += 1;
+        self._elapsed += time;
+
+        if let Some(profile) = &self.disturbances {
+            let pos = self._axis.pos();
+
+            if profile.step_lost(pos, self._dir, self._elapsed) {
+                self._axis._lost_steps.fetch_add(1, Relaxed);
+
+                if profile.report_stall {
+                    return Err(ActuatorError::Overload);
+                }
+            } else {
+                let step = if self._dir.as_bool() { profile.step_angle } else { -profile.step_angle };
+                self._axis._pos.fetch_add(precise(step.0), Relaxed);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn direction(&self) -> Direction {
+        self._dir
+    }
+
+    fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+        self._dir = dir;
+        Ok(())
+    }
+
+    fn driver_limits(&self) -> Option<&DriverLimits> {
+        self.driver_limits.as_ref()
+    }
+
+    fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+        if let Some(delay) = self.delay {
+            delay(time);
+        }
+
+        self._elapsed += time;
+        Ok(())
+    }
+}
+
+/// Simulated stepper motor using start-stop control, see [StepperMotor::simulated]
+pub type Stepper = StepperMotor<StartStopBuilder, SimulatedController>;
+/// Simulated stepper motor using trapezoidal ramps with user-defined limits, see [StepperMotor::simulated]
+pub type TrapezoidalStepper = StepperMotor<TrapezoidalBuilder, SimulatedController>;
+/// Simulated stepper motor using complex control, see [StepperMotor::simulated]
+#[cfg(feature = "complex-builder")]
+pub type ComplexStepper = StepperMotor<ComplexBuilder, SimulatedController>;
+
+impl<B : AdvancedStepperBuilder> StepperMotor<B, SimulatedController> {
+    /// Creates a fully functional, software-only stepper motor, using the data of a [StepperConst::MOT_17HE15_1504S]
+    /// motor and the [StepperConfig::VOLT12_NO_OVERLOAD] configuration
+    ///
+    /// All movements are executed instantly, use [StepperMotor::new_advanced] with [SimulatedController::with_delay] to
+    /// run them in real time
+    ///
+    /// ```rust
+    /// use syact::prelude::*;
+    ///
+    /// let mut stepper = Stepper::simulated();
+    /// stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    ///
+    /// assert!((stepper.pos() - PositionRad(1.0)).abs() < Radians(0.05));
+    /// ```
+    pub fn simulated() -> Self {
+        Self::new_advanced(SimulatedController::new(), StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD)
+            .expect("The simulation parameters must be valid")
+    }
+}
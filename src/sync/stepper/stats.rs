@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use syunit::*;
+
+use crate::InterruptReason;
+
+/// The maximum number of faults kept in the history of [StepperStats], older faults are dropped
+pub const STATS_FAULT_HISTORY : usize = 32;
+
+/// The kind of a fault recorded by [StepperStats]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FaultKind {
+    /// The movement has been interrupted because of an overload or an error, reported by an interruptor or an interrupt
+    /// - 0 - [InterruptReason]: The reason of the interrupt
+    Interrupted(InterruptReason),
+    /// The heartbeat of the host has been lost, see [StepperMotor::set_heartbeat_timeout](super::StepperMotor)
+    HeartbeatLost,
+    /// The movement failed with an error, e.g. of the controller or because of the limits of the driver
+    MovementFailed
+}
+
+/// A fault in the history of [StepperStats]
+///
+/// The faults are timestamped with the operating time of the motor, not the wall-clock time, as the motor has no clock on 
+/// its own. Store the wall-clock time along with the statistics to map the faults onto calendar dates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FaultRecord {
+    /// The operating time of the motor when the fault occured (the time spent moving, not the wall-clock time), see 
+    /// [StepperStats::operating_time]
+    pub time : Seconds,
+    /// The position of the motor when the fault occured
+    pub pos : PositionRad,
+    /// The kind of the fault
+    pub kind : FaultKind
+}
+
+/// Lifetime statistics of a stepper motor for maintenance planning, collected with every step
+///
+/// The statistics can be stored with serde and restored after a restart with [StepperMotor::restore_stats](super::StepperMotor),
+/// so the counts continue over the whole life of the motor.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StepperStats {
+    /// The total number of steps, counting microsteps
+    pub steps : u64,
+    /// The total number of revolutions
+    pub revolutions : f64,
+    /// The total time spent moving in seconds
+    pub run_time : f64,
+    /// The number of movements started
+    pub moves : u64,
+    /// The number of movements halted on request, see [SyncActuatorState::halt](crate::SyncActuatorState::halt)
+    pub stops : u64,
+    /// The number of movements interrupted, e.g. by end switches, overloads or errors
+    pub interrupts : u64,
+    /// The most recent faults, oldest first, limited to [STATS_FAULT_HISTORY] entries
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub faults : Vec<FaultRecord>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _move_steps : u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _move_time : Seconds
+}
+
+impl StepperStats {
+    /// Creates new statistics with all counts zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The total operating time of the motor, including the movement currently executed
+    pub fn operating_time(&self) -> Seconds {
+        Seconds((self.run_time + self._move_time.0 as f64) as f32)
+    }
+
+    /// Adds the `fault` to the history, dropping the oldest fault if the history is full
+    pub fn push_fault(&mut self, fault : FaultRecord) {
+        if self.faults.len() >= STATS_FAULT_HISTORY {
+            self.faults.remove(0);
+        }
+
+        self.faults.push(fault);
+    }
+
+    // Recording
+        /// Starts the statistics of a new movement
+        pub(crate) fn begin_move(&mut self) {
+            self.moves += 1;
+            self._move_steps = 0;
+            self._move_time = Seconds::ZERO;
+        }
+
+        /// Records a single step taking `step_time`
+        #[inline]
+        pub(crate) fn record_step(&mut self, step_time : Seconds) {
+            self._move_steps += 1;
+            self._move_time += step_time;
+        }
+
+        /// Adds the movement to the totals, the steps having the angle `step_angle`
+        pub(crate) fn end_move(&mut self, step_angle : Radians, intr : Option<InterruptReason>, pos : PositionRad) {
+            self.steps += self._move_steps;
+            self.revolutions += self._move_steps as f64 * step_angle.0 as f64 / core::f64::consts::TAU;
+            self.run_time += self._move_time.0 as f64;
+
+            self._move_steps = 0;
+            self._move_time = Seconds::ZERO;
+
+            match intr {
+                Some(InterruptReason::Halted) => self.stops += 1,
+                Some(reason) => {
+                    self.interrupts += 1;
+
+                    if matches!(reason, InterruptReason::Overload | InterruptReason::Error) {
+                        self.record_fault(FaultKind::Interrupted(reason), pos);
+                    }
+                },
+                None => { }
+            }
+        }
+
+        /// Records a fault of the `kind` at the position `pos` at the current operating time
+        pub(crate) fn record_fault(&mut self, kind : FaultKind, pos : PositionRad) {
+            self.push_fault(FaultRecord { time: self.operating_time(), pos, kind });
+        }
+    //
+}
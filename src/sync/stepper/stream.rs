@@ -0,0 +1,217 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use atomic_float::AtomicF32;
+use syunit::*;
+
+use crate::{logging, ActuatorError};
+use crate::sync::stepper::StepperController;
+
+/// Creates a new bounded node stream with the given `capacity`, returning both of its ends
+///
+/// The producer fills the stream up to the `high_watermark` and should be called again once the fill level dropped below the
+/// `low_watermark` (see [StreamConsumer::needs_refill]), so the computation of the nodes can run on a different core than
+/// the emission of the step signals.
+///
+/// ## Panics
+///
+/// Panics if the capacity is zero or the watermarks are not ordered like `low_watermark <= high_watermark <= capacity`
+pub fn node_stream(capacity : usize, low_watermark : usize, high_watermark : usize) -> (StreamProducer, StreamConsumer) {
+    assert!(capacity > 0, "The capacity of a node stream must be greater than zero");
+    assert!(low_watermark <= high_watermark && high_watermark <= capacity, "The watermarks must be ordered like low <= high <= capacity");
+
+    let shared = Arc::new(NodeStream {
+        // One slot is always kept free to distinguish a full from an empty buffer
+        slots: (0 ..= capacity).map(|_| AtomicF32::new(0.0)).collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        finished: AtomicBool::new(false),
+
+        low_watermark,
+        high_watermark,
+
+        starvations: AtomicUsize::new(0),
+        overflows: AtomicUsize::new(0),
+        fill_min: AtomicUsize::new(usize::MAX)
+    });
+
+    ( StreamProducer { stream: shared.clone() }, StreamConsumer { stream: shared, starving: false } )
+}
+
+/// Metrics of a node stream, helpful to tune the capacity and the watermarks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamMetrics {
+    /// Amount of times the stream ran empty while it was not finished yet
+    pub starvations : usize,
+    /// Amount of nodes that could not be pushed, as the stream was full
+    pub overflows : usize,
+    /// The lowest fill level the consumer has seen before taking a node
+    pub fill_min : usize
+}
+
+/// Single-producer single-consumer ring buffer shared by both ends of the stream
+struct NodeStream {
+    slots : Vec<AtomicF32>,
+    head : AtomicUsize,
+    tail : AtomicUsize,
+    finished : AtomicBool,
+
+    low_watermark : usize,
+    high_watermark : usize,
+
+    starvations : AtomicUsize,
+    overflows : AtomicUsize,
+    fill_min : AtomicUsize
+}
+
+impl NodeStream {
+    fn len(&self) -> usize {
+        let head = self.head.load(Acquire);
+        let tail = self.tail.load(Acquire);
+
+        (tail + self.slots.len() - head) % self.slots.len()
+    }
+
+    fn metrics(&self) -> StreamMetrics {
+        let fill_min = self.fill_min.load(Relaxed);
+
+        StreamMetrics {
+            starvations: self.starvations.load(Relaxed),
+            overflows: self.overflows.load(Relaxed),
+            fill_min: if fill_min == usize::MAX { 0 } else { fill_min }
+        }
+    }
+}
+
+/// The producing end of a node stream, usually fed by a [StepperBuilder](crate::sync::stepper::StepperBuilder)
+pub struct StreamProducer {
+    stream : Arc<NodeStream>
+}
+
+impl StreamProducer {
+    /// Pushes a single node into the stream, returns the node back if the stream is full
+    pub fn push(&mut self, node : Seconds) -> Result<(), Seconds> {
+        let tail = self.stream.tail.load(Relaxed);
+        let next = (tail + 1) % self.stream.slots.len();
+
+        if next == self.stream.head.load(Acquire) {
+            let overflows = self.stream.overflows.fetch_add(1, Relaxed) + 1;
+
+            if logging::rate_limited(overflows) {
+                logging::log_warn!("Node stream overflow, {} nodes rejected in total", overflows);
+            }
+
+            return Err(node);
+        }
+
+        self.stream.slots[tail].store(node.0, Relaxed);
+        self.stream.tail.store(next, Release);
+        Ok(())
+    }
+
+    /// Takes nodes out of the given iterator until the high watermark is reached, returns the amount of nodes pushed
+    ///
+    /// Marks the stream as finished once the iterator has no nodes left
+    pub fn fill<I : Iterator<Item = Seconds>>(&mut self, nodes : &mut I) -> usize {
+        let mut count = 0;
+
+        while self.stream.len() < self.stream.high_watermark {
+            if let Some(node) = nodes.next() {
+                if self.push(node).is_err() {
+                    break;
+                }
+
+                count += 1;
+            } else {
+                self.finish();
+                break;
+            }
+        }
+
+        count
+    }
+
+    /// Marks the stream as finished, no more nodes will be pushed
+    pub fn finish(&mut self) {
+        self.stream.finished.store(true, Release);
+    }
+
+    /// Returns `true` if the fill level of the stream is below the low watermark and the stream should be refilled
+    pub fn needs_refill(&self) -> bool {
+        !self.stream.finished.load(Acquire) && (self.stream.len() < self.stream.low_watermark)
+    }
+
+    /// The metrics of the stream
+    pub fn metrics(&self) -> StreamMetrics {
+        self.stream.metrics()
+    }
+}
+
+/// The consuming end of a node stream, emitting the step signals
+pub struct StreamConsumer {
+    stream : Arc<NodeStream>,
+    starving : bool
+}
+
+impl StreamConsumer {
+    /// Takes the next node out of the stream, returns `None` if the stream is currently empty
+    pub fn pop(&mut self) -> Option<Seconds> {
+        let head = self.stream.head.load(Relaxed);
+        self.stream.fill_min.fetch_min(self.stream.len(), Relaxed);
+
+        if head == self.stream.tail.load(Acquire) {
+            // Only count the start of a starvation, not every failed request
+            if !self.starving && !self.stream.finished.load(Acquire) {
+                let starvations = self.stream.starvations.fetch_add(1, Relaxed) + 1;
+                self.starving = true;
+
+                if logging::rate_limited(starvations) {
+                    logging::log_warn!("Node stream starved, {} step-timing overruns in total", starvations);
+                }
+            }
+
+            return None;
+        }
+
+        self.starving = false;
+
+        let node = Seconds(self.stream.slots[head].load(Relaxed));
+        self.stream.head.store((head + 1) % self.stream.slots.len(), Release);
+        Some(node)
+    }
+
+    /// Returns `true` if the stream has been finished and all nodes have been taken out
+    pub fn is_finished(&self) -> bool {
+        self.stream.finished.load(Acquire) && (self.stream.len() == 0)
+    }
+
+    /// Returns `true` if the fill level of the stream is below the low watermark and the stream should be refilled
+    pub fn needs_refill(&self) -> bool {
+        !self.stream.finished.load(Acquire) && (self.stream.len() < self.stream.low_watermark)
+    }
+
+    /// Emits all nodes of the stream with the given controller until the stream is finished
+    ///
+    /// ## Thread
+    ///
+    /// Blocks the current thread, busy-waits if the stream starves
+    pub fn emit<C : StepperController>(&mut self, ctrl : &mut C) -> Result<(), ActuatorError> {
+        loop {
+            if let Some(node) = self.pop() {
+                ctrl.step(node)?;
+            } else if self.is_finished() {
+                return Ok(());
+            } else {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    /// The metrics of the stream
+    pub fn metrics(&self) -> StreamMetrics {
+        self.stream.metrics()
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::{Arc, Mutex};
+
+use crate::asyn::PwmDevice;
+use crate::io::{IoError, PwmOutput};
+use crate::prelude::*;
+
+/// A simulated pump, losing a part of its velocity to the load
+#[derive(Default)]
+struct SimPump {
+    duty : f32,
+    velocity : f32
+}
+
+impl SimPump {
+    const VELOCITY_MAX : f32 = 100.0;
+    const EFFICIENCY : f32 = 0.8;
+    const TIME_CONSTANT : f32 = 0.2;
+
+    fn update(&mut self, elapsed : Seconds) {
+        let velocity_tar = self.duty * Self::VELOCITY_MAX * Self::EFFICIENCY;
+        self.velocity += (velocity_tar - self.velocity) * (elapsed.0 / Self::TIME_CONSTANT).min(1.0);
+    }
+}
+
+struct SimPumpOutput(Arc<Mutex<SimPump>>);
+
+impl PwmOutput for SimPumpOutput {
+    fn set_duty(&mut self, duty : Factor) -> Result<(), IoError> {
+        self.0.lock().unwrap().duty = duty.as_f32();
+        Ok(())
+    }
+}
+
+#[test]
+fn pwm_device() {
+    const DT : Seconds = Seconds(0.01);
+
+    let pump = Arc::new(Mutex::new(SimPump::default()));
+    let tach_pump = pump.clone();
+
+    let run = |device : &mut PwmDevice<SimPumpOutput>, time : Seconds| {
+        for _ in 0 .. (time / DT) as usize {
+            pump.lock().unwrap().update(DT);
+            device.poll(DT).unwrap();
+        }
+    };
+
+    // Open-loop, the load is not compensated
+    let mut device = PwmDevice::new(SimPumpOutput(pump.clone()), RadPerSecond(SimPump::VELOCITY_MAX));
+    device.drive_speed(RadPerSecond(50.0)).unwrap();
+    assert_eq!(device.duty(), Factor::HALF);
+    assert_eq!(device.target(), None);
+
+    run(&mut device, Seconds(2.0));
+    assert!((pump.lock().unwrap().velocity - 40.0).abs() < 0.5);
+
+    // Only CW is possible without a direction output
+    assert!(matches!(device.drive_factor(Factor::HALF, Direction::CCW), Err(ActuatorError::InvalidVelocity(_))));
+    assert!(matches!(device.drive_speed(RadPerSecond(150.0)), Err(ActuatorError::VelocityTooHigh(_, _))));
+
+    // Closed-loop, the velocity is held despite the load
+    let mut device = device.with_tach(Box::new(move || Some(RadPerSecond(tach_pump.lock().unwrap().velocity))));
+    device.drive_speed(RadPerSecond(50.0)).unwrap();
+    assert_eq!(device.target(), Some(RadPerSecond(50.0)));
+
+    run(&mut device, Seconds(5.0));
+    assert!((pump.lock().unwrap().velocity - 50.0).abs() < 1.0);
+    assert!(device.duty() > Factor::new(0.6));
+
+    device.stop().unwrap();
+    assert_eq!(device.target(), None);
+    assert_eq!(pump.lock().unwrap().duty, 0.0);
+}
@@ -0,0 +1,460 @@
+use alloc::sync::Arc;
+
+#[cfg(feature = "meas")]
+use crate::ConveyorRegistration;
+use crate::data::CalibrationTable;
+#[cfg(feature = "meas")]
+use crate::meas::RegistrationLatch;
+use crate::prelude::*;
+
+#[test]
+#[cfg(feature = "meas")]
+fn conveyor_registration() {
+    let latch = Arc::new(RegistrationLatch::new());
+
+    let mut conveyor = Conveyor::new(Stepper::default(), Millimeters(10.0));
+    conveyor.set_registration(Some(ConveyorRegistration {
+        latch: latch.clone(),
+        mark_pitch: Millimeters(100.0),
+        correction_max: Millimeters(5.0)
+    }));
+
+    // No mark detected yet
+    assert!(conveyor.apply_registration().is_none());
+
+    // Belt slipped by 2 mm
+    conveyor.overwrite_abs_pos(PositionMM(102.0));
+    latch.latch(conveyor.pos_for_child(PositionMM(102.0)));
+
+    let correction = conveyor.apply_registration().unwrap();
+    assert!((correction - Millimeters(-2.0)).abs() < Millimeters(0.01));
+    assert!((conveyor.pos() - PositionMM(100.0)).abs() < Millimeters(0.1));
+
+    // Corrections are limited
+    conveyor.overwrite_abs_pos(PositionMM(220.0));
+    latch.latch(conveyor.pos_for_child(PositionMM(220.0)));
+
+    let correction = conveyor.apply_registration().unwrap();
+    assert!((correction - Millimeters(-5.0)).abs() < Millimeters(0.01));
+}
+
+#[test]
+fn linear_axis_calibration() {
+    let mut axis = LinearAxis::new_spindle_axis(Stepper::default(), Millimeters(8.0));
+    axis.calibration = Some(CalibrationTable::new(vec![ (0.0, 0.0), (100.0, 1.0) ]));
+
+    // The actual position is reported, the child is commanded to the nominal one
+    let child_pos = axis.pos_for_child(PositionMM(101.0));
+    assert!((child_pos * axis.ratio() - PositionMM(100.0)).abs() < Millimeters(0.001));
+    assert!((axis.pos_for_parent(child_pos) - PositionMM(101.0)).abs() < Millimeters(0.001));
+
+    axis.overwrite_abs_pos(PositionMM(101.0));
+    assert!((axis.pos() - PositionMM(101.0)).abs() < Millimeters(0.05));
+}
+
+#[test]
+fn mechanics_ratios() {
+    // 12 to 48 teeth, the actuator turns four times as far as the output
+    let mut gear = Gear::from_teeth(Stepper::simulated(), 12, 48);
+    gear.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((gear.actuator.pos() - PositionRad(4.0)).abs() <= gear.actuator.step_dist());
+
+    // One revolution per 40 mm of travel
+    let axis = LinearAxis::from_pulley(Stepper::simulated(), 20, Millimeters(2.0));
+    assert!((axis.pos_for_child(PositionMM(40.0)) - PositionRad(2.0 * core::f32::consts::PI)).abs() < Radians(1e-4));
+
+    // One revolution per 8 mm of travel
+    let axis = LinearAxis::from_screw(Stepper::simulated(), Millimeters(8.0));
+    assert!((axis.pos_for_child(PositionMM(8.0)) - PositionRad(2.0 * core::f32::consts::PI)).abs() < Radians(1e-4));
+}
+
+#[test]
+fn gantry_coupling() {
+    // Simulated mechanism in millimeters, side b lags behind because of a one-sided load and is dragged along by side a
+    fn actual(pos_a : PositionRad, pos_b : PositionRad) -> (f32, f32) {
+        (5.0 * pos_a.0, 5.0 * (0.9 * pos_b.0 + 0.05 * pos_a.0))
+    }
+
+    let mut gantry = Gantry::new(Stepper::default(), Stepper::default());
+    let state_a = gantry.actuator_a.clone_state();
+    let state_b = gantry.actuator_b.clone_state();
+
+    gantry.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    let (a, b) = actual(state_a.pos(), state_b.pos());
+    assert!((a - b).abs() > 0.5);
+
+    let coupling = gantry.calibrate(Radians(0.5), Factor::HALF, || Some(actual(state_a.pos(), state_b.pos()))).unwrap();
+    assert!(coupling.is_some());
+    assert_eq!(gantry.coupling, coupling);
+
+    // The racking caused by the movement is reduced to a few steps
+    let (a_start, b_start) = actual(state_a.pos(), state_b.pos());
+    gantry.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    let (a, b) = actual(state_a.pos(), state_b.pos());
+
+    assert!(((a - a_start) - (b - b_start)).abs() < 0.1);
+}
+
+#[test]
+fn machine_report() {
+    use crate::{EmergencyStop, Interruptible, MotionMode};
+    use crate::io::IoRegistry;
+    use crate::report::MachineReport;
+
+    let mut axis = LinearAxis::new_belt_axis(Stepper::simulated(), Millimeters(10.0));
+    axis.set_pos_limits(Some(PositionMM(0.0)), Some(PositionMM(300.0)));
+    axis.set_velocity_max(Some(MMPerSecond(100.0))).unwrap();
+    axis.add_interruptor(Box::new(EmergencyStop::new()));
+
+    let mut io = IoRegistry::new();
+    io.add_output("spindle", Box::new(NoPin));
+
+    let mut report = MachineReport::new("router");
+    report.add_axis("x", &axis);
+    report.add_io(&io);
+
+    let axis_report = &report.axes[0];
+    assert_eq!(axis_report.unit, "mm");
+    assert_eq!((axis_report.limit_min, axis_report.limit_max), (Some(0.0), Some(300.0)));
+    assert!((axis_report.velocity_max.unwrap() - 100.0).abs() < 1e-3);
+    assert!(axis_report.modes.contains(&MotionMode::ConstVelocity));
+    assert_eq!(axis_report.sensors, vec![ "EmergencyStop".to_string() ]);
+    assert_eq!(report.outputs, vec![ "spindle".to_string() ]);
+}
+
+#[test]
+fn readiness_report() {
+    use crate::{EmergencyStop, InterruptContext, InterruptReason, Interruptible, Interruptor};
+    use crate::report::CheckKind;
+    use crate::sync::SyncActuatorGroup;
+
+    /// Switch with a broken wire
+    struct BrokenSwitch;
+
+    impl Interruptor for BrokenSwitch {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, _ctx : &InterruptContext) -> Option<InterruptReason> {
+            None
+        }
+
+        fn kind(&self) -> &'static str {
+            "BrokenSwitch"
+        }
+
+        fn probe(&mut self) -> Result<(), &'static str> {
+            Err("no signal")
+        }
+    }
+
+    let mut group = [ Stepper::simulated(), Stepper::simulated() ];
+    group[0].add_interruptor(Box::new(EmergencyStop::new()));
+
+    let mut report = group.self_test();
+    assert!(report.ready());
+    assert_eq!(report.checks.iter().filter(|check| check.kind == CheckKind::Controller).count(), 2);
+    assert_eq!(report.checks.iter().filter(|check| check.kind == CheckKind::Interruptor).count(), 1);
+
+    report.check(CheckKind::Brake, "z-brake", Ok(()));
+    assert!(report.ready());
+
+    group[1].add_interruptor(Box::new(BrokenSwitch));
+    let mut report = group.self_test();
+    report.check(CheckKind::Tool, "spindle", Err("not connected"));
+
+    assert!(!report.ready());
+
+    let failures : Vec<_> = report.failures().map(|check| (check.axis, check.name.as_str())).collect();
+    assert_eq!(failures, vec![ (Some(1), "BrokenSwitch"), (None, "spindle") ]);
+}
+
+#[test]
+fn motion_report() {
+    use crate::Interruptible;
+    use crate::report::{EnvelopeRecorder, EnvelopeViolation, MotionReport};
+
+    let recorder = EnvelopeRecorder::new();
+    let envelope = recorder.envelope();
+
+    let mut stepper = TrapezoidalStepper::simulated();
+    stepper.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(3.0)));
+    stepper.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+    stepper.add_interruptor(Box::new(recorder));
+
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    stepper.drive_rel_blocking(Radians(-2.5), Factor::HALF).unwrap();
+
+    let mut report = MotionReport::new("turntable");
+    report.add_axis("table", &envelope, &stepper);
+
+    let axis = &report.axes[0];
+    assert!(axis.samples > 0);
+    assert!((axis.pos_min.unwrap() + 0.5).abs() <= stepper.step_dist().0 * 1.5, "Min: {:?}", axis.pos_min);
+    assert!((axis.pos_max.unwrap() - 2.0).abs() <= stepper.step_dist().0 * 1.5, "Max: {:?}", axis.pos_max);
+    assert!(axis.velocity_peak > 0.0);
+    assert!((axis.acceleration_peak - 100.0).abs() < 5.0, "Acceleration: {}", axis.acceleration_peak);
+    assert!(report.within_limits(0.05), "Violations: {:?}", axis.violations(0.05));
+
+    // Declaring tighter limits afterwards reveals the violations of the run
+    stepper.set_pos_limits(Some(PositionRad(0.0)), Some(PositionRad(3.0)));
+    stepper.set_velocity_max(Some(RadPerSecond(axis.velocity_peak * 0.5))).unwrap();
+
+    let axis = crate::report::AxisEnvelope::from_recording("table", &envelope, &stepper);
+    assert_eq!(axis.violations(0.05), vec![ EnvelopeViolation::LimitMin, EnvelopeViolation::Velocity ]);
+
+    envelope.reset();
+    assert_eq!((envelope.samples(), envelope.pos_min()), (0, None));
+}
+
+/// Output pin without any effect
+struct NoPin;
+
+impl embedded_hal::digital::ErrorType for NoPin {
+    type Error = core::convert::Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for NoPin {
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+#[cfg(feature = "meas")]
+fn gantry_squaring() {
+    use crate::{InterruptContext, InterruptReason, Interruptible, Interruptor};
+
+    /// Virtual end switch at the given position
+    struct Switch(f32);
+
+    impl Interruptor for Switch {
+        fn dir(&self) -> Option<Direction> {
+            Some(Direction::CW)
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            (ctx.pos.0 > self.0).then_some(InterruptReason::EndReached)
+        }
+    }
+
+    /// Gantry with the switch of side b mounted 0.2 rad further away, side b starts `offset_b` ahead
+    fn gantry(offset_b : f32) -> Gantry<Stepper, Stepper> {
+        let mut gantry = Gantry::new(Stepper::simulated(), Stepper::simulated());
+        gantry.actuator_a.add_interruptor(Box::new(Switch(1.0)));
+        gantry.actuator_b.add_interruptor(Box::new(Switch(1.2)));
+        gantry.actuator_b.overwrite_abs_pos(PositionRad(offset_b));
+        gantry
+    }
+
+    let step_dist = Stepper::simulated().step_dist();
+
+    // Commissioning, starting square
+    let squaring = gantry(0.0).square(Radians(2.0), Factor::HALF).unwrap();
+    assert!((squaring.skew - Radians(0.2)).abs() <= step_dist, "Skew: {}", squaring.skew);
+
+    // Homing a racked gantry with the stored squaring
+    let mut racked = gantry(0.1);
+    racked.squaring = Some(squaring);
+
+    let (travel_a, travel_b) = racked.home(Radians(2.0), Factor::HALF, PositionRad::ZERO).unwrap();
+    assert!((travel_a - (travel_b + Radians(0.1))).abs() <= step_dist * 2.0, "Travel a: {}, Travel b: {}", travel_a, travel_b);
+    assert_eq!(racked.pos(), PositionRad::ZERO);
+
+    // A missing switch fails the procedure
+    let mut missing = Gantry::new(Stepper::simulated(), Stepper::simulated());
+    missing.actuator_a.add_interruptor(Box::new(Switch(1.0)));
+    assert!(matches!(missing.square(Radians(2.0), Factor::HALF), Err(crate::meas::SimpleMeasError::NoInterrupt)));
+}
+
+#[test]
+fn indexing_table() {
+    use core::f32::consts::PI;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::io::{DigitalInput, DigitalOutput, IoError};
+
+    struct Lock(Arc<AtomicBool>);
+
+    impl DigitalOutput for Lock {
+        fn set(&mut self, state : bool) -> Result<(), IoError> {
+            self.0.store(state, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    struct InPosition(Arc<AtomicBool>);
+
+    impl DigitalInput for InPosition {
+        fn read(&mut self) -> Result<bool, IoError> {
+            Ok(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    let locked = Arc::new(AtomicBool::new(true));
+    let in_position = Arc::new(AtomicBool::new(true));
+    let tolerance = Radians(0.01);
+
+    let mut table = IndexingTable::evenly_spaced(Stepper::simulated(), &[ "load", "drill", "tap", "unload" ]);
+    table.lock = Some(Box::new(Lock(locked.clone())));
+    table.in_position = Some(Box::new(InPosition(in_position.clone())));
+
+    assert_eq!(table.station_index("unload"), Some(3));
+    assert_eq!(table.current_station(), Some(0));
+
+    // Shortest way wraps around backwards
+    table.goto_station(3, Factor::MAX).unwrap();
+    assert!((table.actuator.pos() - PositionRad(-PI / 2.0)).abs() < tolerance);
+    assert_eq!(table.current_station(), Some(3));
+    assert!(locked.load(Ordering::Relaxed));
+
+    // Only clockwise rotation allowed
+    table.direction = Some(Direction::CW);
+    table.goto_station(2, Factor::MAX).unwrap();
+    assert!((table.actuator.pos() - PositionRad(PI)).abs() < tolerance);
+    assert_eq!(table.current_station(), Some(2));
+
+    // Station not confirmed by the sensor
+    in_position.store(false, Ordering::Relaxed);
+    assert!(matches!(table.goto_station(1, Factor::MAX), Err(ActuatorError::StationNotReached(1))));
+}
+
+#[test]
+fn gear_train() {
+    let mut train = GearTrain::new(Stepper::simulated(), vec![
+        GearStage::new(0.2).with_efficiency(0.95).with_inertia(KgMeter2(0.000_2)),
+        GearStage::new(0.5).with_efficiency(0.8).with_inertia(KgMeter2(0.001))
+    ]);
+
+    assert!((train.ratio_total() - 0.1).abs() < 1e-6);
+    assert!((train.efficiency_total() - 0.76).abs() < 1e-6);
+    assert_eq!(PositionRad(10.0), train.pos_for_child(PositionRad(1.0)));
+
+    // Reflected inertias
+    assert!((train.inertia_stages() - KgMeter2(0.000_2 * 0.04 + 0.001 * 0.01)).abs() < KgMeter2(1e-9));
+    assert!((train.inertia_for_motor(KgMeter2(2.0)) - KgMeter2(0.000_018 + 0.02)).abs() < KgMeter2(1e-8));
+
+    // Losses increase the torque required from the motor
+    assert!((train.torque_for_motor(NewtonMeters(7.6)) - NewtonMeters(1.0)).abs() < NewtonMeters(1e-5));
+
+    let diag = train.diagnostics(NewtonMeters(4.0), RadPerSecond(1.0));
+    assert_eq!(diag.len(), 2);
+    assert!((diag[0].velocity - RadPerSecond(2.0)).abs() < RadPerSecond(1e-5));
+    assert!((diag[0].torque - NewtonMeters(2.5)).abs() < NewtonMeters(1e-5));
+    assert!((diag[1].torque - NewtonMeters(4.0)).abs() < NewtonMeters(1e-5));
+    assert!((diag[1].torque_loss - NewtonMeters(1.0)).abs() < NewtonMeters(1e-5));
+
+    train.apply_load_inertia(KgMeter2(2.0)).unwrap();
+    assert!((train.child().inertia() - KgMeter2(0.020_018)).abs() < KgMeter2(1e-8));
+
+    train.apply_load_forces(NewtonMeters(0.76), NewtonMeters::ZERO).unwrap();
+    assert!((train.child().force_gen() - NewtonMeters(0.1)).abs() < NewtonMeters(1e-5));
+}
+
+#[test]
+fn position_layers() {
+    let mut axis = LinearAxis::new_belt_axis(Gear::new(Stepper::simulated(), 0.5), Millimeters(10.0));
+    axis.overwrite_abs_pos(PositionMM(20.0));
+
+    assert_eq!(axis.layer_count(), 3);
+    assert_eq!(axis.layer_name(1), Some("gear"));
+    assert_eq!(axis.layer_name(3), None);
+    assert_eq!(axis.layer_pos(3), None);
+
+    // Every layer is converted with the ratios of the layers above it
+    assert!((axis.layer_pos(0).unwrap().linear().unwrap() - PositionMM(20.0)).abs() < Millimeters(1e-4));
+    assert!((axis.layer_pos_by_name("gear").unwrap().rotary().unwrap() - PositionRad(2.0)).abs() < Radians(1e-4));
+    assert!((axis.layer_pos_by_name("motor").unwrap().value() - 4.0).abs() < 1e-4);
+    assert_eq!(axis.layer_pos_by_name("spindle"), None);
+
+    let names : Vec<_> = axis.layer_positions().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec![ "axis", "gear", "motor" ]);
+}
+
+#[test]
+fn backlash_compensation() {
+    let mut comp = BacklashCompensator::new(Stepper::simulated(), Radians(0.2));
+    let state = comp.clone_state();
+
+    // The first movement is not compensated, it only defines the direction
+    comp.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert_eq!(comp.engaged_dir(), Some(Direction::CW));
+    assert!((comp.actuator.pos() - PositionRad(2.0)).abs() < Radians(0.05));
+
+    // Reversing inserts the backlash
+    comp.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+    assert_eq!(comp.engaged_dir(), Some(Direction::CCW));
+    assert!((comp.pos() - PositionRad(1.0)).abs() < Radians(0.05));
+    assert!((comp.actuator.pos() - PositionRad(0.8)).abs() < Radians(0.05));
+
+    // Continuing in the same direction does not
+    comp.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+    assert!((comp.pos() - PositionRad(0.5)).abs() < Radians(0.05));
+    assert!((comp.actuator.pos() - PositionRad(0.3)).abs() < Radians(0.05));
+
+    comp.drive_abs_blocking(PositionRad(1.5), Factor::MAX).unwrap();
+    assert!((comp.pos() - PositionRad(1.5)).abs() < Radians(0.05));
+    assert!((comp.actuator.pos() - PositionRad(1.5)).abs() < Radians(0.05));
+
+    // The shared state reports the position of the load
+    assert_eq!(state.pos(), comp.pos());
+    assert_eq!(comp.layer_name(0), Some("backlash"));
+    assert_eq!(comp.layer_count(), 2);
+
+    // Continuous movements take up the backlash first and end at the limits of the child
+    let mut stepper = TrapezoidalStepper::simulated();
+    stepper.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    stepper.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(2.0)));
+    stepper.set_limit_behavior(LimitBehavior::DecelerateAndClamp).unwrap();
+
+    let mut comp = BacklashCompensator::new(stepper, Radians(0.2));
+
+    // The first movement is tracked with the commanded direction
+    comp.drive_factor(Factor::MAX, Direction::CW).unwrap();
+    assert_eq!(comp.engaged_dir(), Some(Direction::CW));
+    assert!((comp.pos() - PositionRad(2.0)).abs() < Radians(0.05));
+
+    // The backlash is taken up with a separate movement of the child
+    let moves = comp.actuator.stats().moves;
+    comp.drive_factor(Factor::MAX, Direction::CCW).unwrap();
+    assert_eq!(comp.actuator.stats().moves, moves + 2);
+    assert_eq!(comp.engaged_dir(), Some(Direction::CCW));
+    assert!((comp.actuator.pos() - PositionRad(-1.0)).abs() < Radians(0.05));
+    assert!((comp.pos() - PositionRad(-0.8)).abs() < Radians(0.05));
+
+    comp.drive_speed(RadPerSecond(5.0)).unwrap();
+    assert_eq!(comp.engaged_dir(), Some(Direction::CW));
+    assert!((comp.pos() - PositionRad(2.0)).abs() < Radians(0.05));
+}
+
+#[test]
+fn linear_axis_load_model() {
+    let mut axis = LinearAxis::ball_screw(Stepper::simulated(), Millimeters(5.0), 0.5);
+
+    // The torque model is reversible
+    let torque = axis.torque_for_load(Newtons(200.0));
+    assert!((axis.load_max_for_torque(torque) - Newtons(200.0)).abs() < Newtons(1e-3));
+
+    // Losses double the torque and inertia required compared to an ideal screw
+    let ideal = LinearAxis::from_screw(Stepper::simulated(), Millimeters(5.0));
+    assert!((torque - ideal.torque_for_load(Newtons(200.0)) * 2.0).abs() < NewtonMeters(1e-5));
+
+    axis.apply_gen_force(Newtons(200.0)).unwrap();
+    axis.apply_inertia(Kilogramms(10.0)).unwrap();
+
+    assert!((axis.actuator.force_gen() - torque).abs() < NewtonMeters(1e-5));
+    // The carriage mass acts on the effective radius of the screw
+    let radius = 0.005 / (2.0 * core::f32::consts::PI);
+    assert!((axis.actuator.inertia() - KgMeter2(10.0 * radius * radius / 0.5)).abs() < KgMeter2(1e-8));
+}
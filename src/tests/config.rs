@@ -0,0 +1,90 @@
+use crate::config::{ActuatorSpec, ConfigError, ControllerRegistry, ControllerSpec, LinearAxisSpec, PresetOr, StepperSpec};
+use crate::prelude::*;
+
+fn registry() -> ControllerRegistry<SimulatedController> {
+    ControllerRegistry::new().with("sim", |spec| {
+        // The simulated controller takes a step and a direction pin, just like the real ones
+        if spec.pins.len() != 2 {
+            return Err(ConfigError::Controller(spec.kind.clone(), "expected two pins".into()));
+        }
+
+        Ok(SimulatedController::new())
+    })
+}
+
+#[test]
+fn config_stepper() {
+    let spec : StepperSpec = serde_json::from_str(r#"{
+        "controller": { "kind": "sim", "pins": [ 17, 26 ] },
+        "consts": { 
+            "default_current": 2.0, "inductance": 0.004, "resistance": 1.5, "number_steps": 400, 
+            "torque_stall": 0.5, "inertia_motor": 0.00001 
+        },
+        "config": "VOLT24_NO_OVERLOAD",
+        "microsteps": 8,
+        "limits": { "pos_min": -1.0, "pos_max": 5.0, "velocity_max": 10.0 }
+    }"#).unwrap();
+
+    let mut stepper : Stepper = spec.build(&registry()).unwrap();
+
+    assert_eq!(stepper.consts().number_steps, 400);
+    assert_eq!(stepper.microsteps(), MicroSteps::from(8));
+    assert_eq!(stepper.limit_min(), Some(PositionRad(-1.0)));
+    assert_eq!(stepper.limit_max(), Some(PositionRad(5.0)));
+    assert_eq!(stepper.velocity_max(), Some(RadPerSecond(10.0)));
+
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(1.0)).abs() < Radians(0.05));
+}
+
+#[test]
+fn config_errors() {
+    let stepper = |controller : &str, consts : &str| StepperSpec::<StartStopBuilder>::new(
+        serde_json::from_str::<ControllerSpec>(controller).unwrap(), 
+        PresetOr::Preset(consts.into()), 
+        PresetOr::Preset("VOLT12_NO_OVERLOAD".into())
+    );
+
+    let result = stepper(r#"{ "kind": "gpio", "pins": [ 1, 2 ] }"#, "MOT_17HE15_1504S").build(&registry());
+    assert!(matches!(result, Err(ConfigError::UnknownController(kind)) if kind == "gpio"));
+
+    let result = stepper(r#"{ "kind": "sim" }"#, "MOT_17HE15_1504S").build(&registry());
+    assert!(matches!(result, Err(ConfigError::Controller(_, _))));
+
+    let result = stepper(r#"{ "kind": "sim", "pins": [ 1, 2 ] }"#, "MOT_42").build(&registry());
+    assert!(matches!(result, Err(ConfigError::UnknownPreset(name)) if name == "MOT_42"));
+
+    // Errors of the actuators are reported as well
+    let mut spec = stepper(r#"{ "kind": "sim", "pins": [ 1, 2 ] }"#, "MOT_17HE15_1504S");
+    spec.limits.velocity_max = Some(0.0);
+    assert!(matches!(spec.build(&registry()), Err(ConfigError::Actuator(_))));
+
+    let axis : LinearAxisSpec<StepperSpec> = serde_json::from_str(r#"{
+        "effective_radius": 5.0,
+        "efficiency": 1.5,
+        "actuator": { "controller": { "kind": "sim", "pins": [ 1, 2 ] }, "consts": "MOT_17HE15_1504S", "config": "VOLT12_NO_OVERLOAD" }
+    }"#).unwrap();
+    assert!(matches!(axis.build(&registry()), Err(ConfigError::InvalidValue("efficiency"))));
+}
+
+#[test]
+fn config_groups() {
+    use crate::sync::SyncActuatorGroup;
+
+    let specs : Vec<StepperSpec> = serde_json::from_str(r#"[
+        { "controller": { "kind": "sim", "pins": [ 1, 2 ] }, "consts": "MOT_17HE15_1504S", "config": "VOLT12_NO_OVERLOAD" },
+        { "controller": { "kind": "sim", "pins": [ 3, 4 ] }, "consts": "MOT_23HS45_4204S", "config": "VOLT48_NO_OVERLOAD" },
+        { "controller": { "kind": "sim", "pins": [ 5, 6 ] }, "consts": "MOT_17HE15_1504S", "config": "VOLT24_NO_OVERLOAD" }
+    ]"#).unwrap();
+
+    let steppers : Vec<Stepper> = specs.build(&registry()).unwrap();
+    assert_eq!(steppers.len(), 3);
+    assert_eq!(steppers[1].consts(), &StepperConst::MOT_23HS45_4204S);
+
+    // Arrays build into groups
+    let specs : [StepperSpec; 3] = specs.try_into().unwrap();
+    let mut group : [Stepper; 3] = specs.build(&registry()).unwrap();
+
+    group.drive_ptp_coordinated([ PositionRad(1.0), PositionRad(2.0), PositionRad(-1.0) ], Factor::MAX).unwrap();
+    assert!((group.pos()[1] - PositionRad(2.0)).abs() < Radians(0.05));
+}
@@ -1,4 +1,4 @@
-use crate::data::StepperConst;
+use crate::data::{CalibrationTable, CouplingMatrix, StepperConst};
 
 #[test]
 #[ignore = "Value display, run manually ... "]
@@ -9,4 +9,82 @@ fn basic_plot() {
     
     println!("Stepper-Data");
     println!("- U::Velocity-Max: {}", consts.velocity_max(u));
-}
\ No newline at end of file
+}
+
+#[test]
+fn microstep_torque_derating() {
+    use crate::data::{ActuatorVars, MicroSteps, StepperConfig};
+    use syunit::*;
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let config = StepperConfig::VOLT12_NO_OVERLOAD;
+    let vars = ActuatorVars::ZERO;
+
+    let accel_full = consts.acceleration_max_for_velocity(&vars, &config, RadPerSecond(10.0), Direction::CW).unwrap();
+    assert_eq!(consts.acceleration_max_for_velocity_microsteps(&vars, &config, RadPerSecond(10.0), Direction::CW, MicroSteps::from(1)), Some(accel_full));
+
+    let accel_micro = consts.acceleration_max_for_velocity_microsteps(&vars, &config, RadPerSecond(10.0), Direction::CW, MicroSteps::from(16)).unwrap();
+    assert!(accel_micro < accel_full);
+
+    // The derating fades out with rising speed
+    let velocity_max = consts.velocity_max(config.voltage);
+    assert!((consts.torque_factor_microsteps(MicroSteps::from(16), RadPerSecond::ZERO, &config) - core::f32::consts::FRAC_1_SQRT_2).abs() < 0.001);
+    assert!(consts.torque_factor_microsteps(MicroSteps::from(16), velocity_max * 0.5, &config) 
+        > consts.torque_factor_microsteps(MicroSteps::from(16), RadPerSecond(10.0), &config));
+    assert!((consts.torque_factor_microsteps(MicroSteps::from(16), velocity_max, &config) - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn drive_current_torque_scaling() {
+    use crate::data::StepperConfig;
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let mut config = StepperConfig::VOLT12_NO_OVERLOAD;
+
+    config.drive_current = Some(consts.default_current / 2.0);
+    assert_eq!(consts.torque_overload(config.current()), consts.torque_stall / 2.0);
+
+    // Overload current takes priority
+    config.overload_current = Some(consts.default_current * 2.0);
+    assert_eq!(consts.torque_overload(config.current()), consts.torque_stall * 2.0);
+}
+
+#[test]
+fn calibration_table() {
+    let table = CalibrationTable::parse("
+        # position, error
+        0.0, 0.0
+        10.0; 0.2
+        20.0  0.1
+    ").unwrap();
+
+    assert_eq!(table.points().len(), 3);
+    assert_eq!(CalibrationTable::parse("0.0, 0.0\n1.0").unwrap_err().line, 2);
+
+    // Measured points are hit exactly, outside the table the closest error is used
+    assert_eq!(table.error_at(10.0), 0.2);
+    assert_eq!(table.error_at(-5.0), 0.0);
+    assert_eq!(table.error_at(30.0), 0.1);
+
+    // Smooth in between and invertible
+    let error = table.error_at(5.0);
+    assert!((0.0 .. 0.2).contains(&error));
+    assert!((table.nominal_for_actual(table.actual_for_nominal(15.0)) - 15.0).abs() < 1e-4);
+}
+
+#[test]
+fn coupling_matrix() {
+    assert_eq!(CouplingMatrix::from_response((2.0, 0.0), (0.0, 2.0)), Some(CouplingMatrix::IDENTITY));
+    assert_eq!(CouplingMatrix::from_response((1.0, 1.0), (1.0, 1.0)), None);
+
+    // Side b lags behind and is dragged along by side a
+    let response_a = (1.0, 0.1);
+    let response_b = (0.0, 0.8);
+    let coupling = CouplingMatrix::from_response(response_a, response_b).unwrap();
+
+    let (a, b) = coupling.apply(1.0, 1.0);
+    let actual_a = response_a.0 * a + response_b.0 * b;
+    let actual_b = response_a.1 * a + response_b.1 * b;
+
+    assert!((actual_a - actual_b).abs() < 1e-5);
+}
@@ -0,0 +1,101 @@
+use core::convert::Infallible;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+
+use crate::io::{IoError, IoRegistry};
+use crate::prelude::*;
+
+/// A simulated pin, outputs and inputs sharing the same state are connected 
+struct SimPin(Arc<AtomicBool>);
+
+impl ErrorType for SimPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for SimPin {
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl InputPin for SimPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.0.load(Ordering::Relaxed))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.0.load(Ordering::Relaxed))
+    }
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns : u32) { }
+}
+
+#[test]
+fn io_registry() {
+    let gripper = Arc::new(AtomicBool::new(false));
+
+    let mut io = IoRegistry::new();
+    io.add_output("gripper", Box::new(SimPin(gripper.clone())));
+    io.add_input("gripper_closed", Box::new(SimPin(gripper.clone())));
+
+    assert_eq!(io.set_output("valve", true), Err(IoError::UnknownChannel));
+    assert_eq!(io.wait_for_input("gripper_closed", true, Seconds(0.01), &mut NoDelay), Err(IoError::Timeout));
+
+    io.set_output("gripper", true).unwrap();
+    assert!(io.read_input("gripper_closed").unwrap());
+    io.wait_for_input("gripper_closed", true, Seconds(0.01), &mut NoDelay).unwrap();
+}
+
+#[test]
+fn cooling_fan() {
+    use crate::io::{CoolingFan, OnOffOutput};
+
+    let fan_pin = Arc::new(AtomicBool::new(false));
+
+    let mut stepper = Stepper::simulated();
+    let mut fan = CoolingFan::new(OnOffOutput(SimPin(fan_pin.clone())), RadPerSecond(10.0));
+    fan.time_constant = Seconds(1.0);
+    fan.run_on = Seconds(2.0);
+    fan.add_axis(stepper.clone_state());
+
+    // Idle axes do not start the fan
+    for _ in 0 .. 10 {
+        assert_eq!(fan.poll(Seconds(0.1)).unwrap(), Factor::MIN);
+    }
+
+    // Fast movements start the fan
+    for _ in 0 .. 10 {
+        stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+        fan.poll(Seconds(0.1)).unwrap();
+    }
+
+    assert!(fan.running());
+    assert!(fan_pin.load(Ordering::Relaxed));
+
+    // Fan keeps running for the run-on time after the activity has dropped
+    let mut idle = Seconds::ZERO;
+    while fan.running() {
+        fan.poll(Seconds(0.1)).unwrap();
+        idle += Seconds(0.1);
+
+        assert!(idle < Seconds(10.0));
+    }
+
+    assert!(fan.activity() < fan.activity_off);
+    assert!(idle >= fan.run_on);
+    assert!(!fan_pin.load(Ordering::Relaxed));
+}
@@ -0,0 +1,59 @@
+use crate::math::kin;
+use crate::prelude::*;
+
+#[test]
+fn kinematics() {
+    // Triangular profile, the maximum velocity is never reached
+    let times = kin::travel_times::<Rotary>(Radians(1.0), RadPerSecond(100.0), RadPerSecond2(50.0), RadPerSecond2(50.0));
+    assert_eq!(times.cruise, Seconds::ZERO);
+    assert!((times.velocity_peak - RadPerSecond(50f32.sqrt())).abs() < RadPerSecond(1e-4));
+    assert!(kin::can_stop_within::<Rotary>(times.velocity_peak, RadPerSecond2(50.0), Radians(0.5)));
+    assert!(!kin::can_stop_within::<Rotary>(times.velocity_peak, RadPerSecond2(50.0), Radians(0.49)));
+
+    assert_eq!(kin::accel_distance::<Rotary>(RadPerSecond(10.0), RadPerSecond(20.0), RadPerSecond2(100.0)), Radians(1.5));
+
+    // Actuators use their deceleration limit if set
+    let mut stepper = Stepper::simulated();
+    assert_eq!(kin::actuator_stop_distance(&stepper, RadPerSecond(10.0)), None);
+
+    stepper.set_acceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+    assert_eq!(kin::actuator_stop_distance(&stepper, RadPerSecond(10.0)), Some(Radians(1.0)));
+
+    stepper.set_deceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+    assert_eq!(kin::actuator_stop_distance(&stepper, RadPerSecond(-10.0)), Some(Radians(0.5)));
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn kinematics_match_builder() {
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let step_angle = consts.step_angle(MicroSteps::default());
+
+    let mut builder = ComplexBuilder::new(consts, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    builder.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    builder.set_deceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+
+    let nodes = builder.render_profile(Radians(20.0), Factor::MAX).unwrap();
+    let velocity = builder.velocity_possible();
+
+    // The ramp-down of the builder covers the stop distance
+    let ramp_down = nodes.windows(2).rev().take_while(|w| w[1] > w[0]).count() + 1;
+    let stop_distance = kin::stop_distance::<Rotary>(velocity, RadPerSecond2(50.0));
+
+    assert!((step_angle * ramp_down as f32 - stop_distance).abs() <= step_angle * 2.0);
+}
+
+#[test]
+fn ramp_profile_steps() {
+    let profile = RampProfile::<Rotary>::new(Radians(2.0), RadPerSecond(10.0), RadPerSecond2(50.0)).with_deceleration(RadPerSecond2(100.0));
+    let steps : Vec<Seconds> = profile.step_times(Radians(0.01)).collect();
+
+    assert_eq!(steps.len(), 200);
+    assert!((steps.iter().fold(Seconds::ZERO, |sum, &time| sum + time) - profile.times().total()).abs() < Seconds(1e-4));
+
+    // Accelerating, cruising and decelerating
+    assert!(steps[0] > steps[1]);
+    assert!((steps[100] - Seconds(0.001)).abs() < Seconds(1e-5));
+    assert!(steps[199] > steps[198]);
+}
@@ -0,0 +1,475 @@
+use core::convert::Infallible;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use embedded_hal::digital::{ErrorType, InputPin};
+
+use crate::{InterruptContext, Interruptible, Interruptor, InterruptReason, MotionMode};
+use crate::meas::{Filter, HardLimitSwitch, Iir, LoadMonitor, MeasRetryPolicy, MovingAverage, Recoverable, SimpleMeasError, move_off_switch, 
+    take_simple_meas};
+use crate::prelude::*;
+
+struct SimSwitch(Arc<AtomicBool>);
+
+impl ErrorType for SimSwitch {
+    type Error = Infallible;
+}
+
+impl InputPin for SimSwitch {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.0.load(Ordering::Relaxed))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.0.load(Ordering::Relaxed))
+    }
+}
+
+fn ctx(direction : Direction) -> InterruptContext {
+    InterruptContext {
+        pos: PositionRad::ZERO,
+        velocity: RadPerSecond::ZERO,
+        direction,
+        mode: MotionMode::ConstVelocity,
+        interval: Seconds(0.001),
+        last_interval: Seconds(0.001)
+    }
+}
+
+#[test]
+fn endstop_diagnostics() {
+    let pin = Arc::new(AtomicBool::new(false));
+    let mut endstop = EndStop::new(true, Some(Direction::CW), SimSwitch(pin.clone()))
+        .with_diagnostics(2, 3);
+    let diag = endstop.diagnostics();
+
+    // Monitored switches are checked in both directions
+    assert_eq!(endstop.dir(), None);
+    assert_eq!(endstop.check(&ctx(Direction::CW)), None);
+
+    // Single sample activation is filtered
+    pin.store(true, Ordering::Relaxed);
+    assert_eq!(endstop.check(&ctx(Direction::CW)), None);
+    pin.store(false, Ordering::Relaxed);
+    assert_eq!(endstop.check(&ctx(Direction::CW)), None);
+    assert_eq!((diag.edges(), diag.glitches()), (1, 1));
+
+    // Activations while moving away never interrupt
+    pin.store(true, Ordering::Relaxed);
+    assert_eq!(endstop.check(&ctx(Direction::CCW)), None);
+    assert_eq!(endstop.check(&ctx(Direction::CCW)), None);
+    assert_eq!(diag.implausible(), 1);
+    assert!(!diag.fault());
+
+    pin.store(false, Ordering::Relaxed);
+    endstop.check(&ctx(Direction::CCW));
+    pin.store(true, Ordering::Relaxed);
+    endstop.check(&ctx(Direction::CCW));
+    assert!(diag.fault());
+
+    // Real activations still stop the movement
+    pin.store(false, Ordering::Relaxed);
+    endstop.check(&ctx(Direction::CW));
+    pin.store(true, Ordering::Relaxed);
+    assert_eq!(endstop.check(&ctx(Direction::CW)), None);
+    assert_eq!(endstop.check(&ctx(Direction::CW)), Some(InterruptReason::EndReached));
+    assert_eq!(diag.edges(), 4);
+
+    diag.reset();
+    assert!(!diag.fault());
+}
+
+/// A switch flag that is active between two positions, missing the given amount of passes
+struct FlakySwitch {
+    missed : usize,
+    skipping : Option<bool>
+}
+
+impl Interruptor for FlakySwitch {
+    fn dir(&self) -> Option<Direction> {
+        Some(Direction::CW)
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        if (ctx.pos < PositionRad(1.0)) || (ctx.pos > PositionRad(1.2)) {
+            self.skipping = None;
+            return None;
+        }
+
+        // Decide once per pass whether the switch is missed
+        let skipping = *self.skipping.get_or_insert_with(|| {
+            let skip = self.missed > 0;
+            self.missed = self.missed.saturating_sub(1);
+            skip
+        });
+
+        (!skipping).then_some(InterruptReason::EndReached)
+    }
+}
+
+#[test]
+fn homing_retries() {
+    let mut params = SimpleMeasParams::default();
+    params.max_dist = Radians(2.0);
+    params.meas_speed = Factor::HALF;
+
+    // Single-shot measurement reports the missing switch
+    let mut stepper = Stepper::simulated()
+        .add_interruptor_inline(Box::new(FlakySwitch { missed: 1, skipping: None }));
+    assert!(matches!(take_simple_meas(&mut stepper, &params, Factor::MAX), Err(SimpleMeasError::NoInterrupt)));
+
+    // Searching backwards on retry finds the switch again
+    let mut stepper = Stepper::simulated()
+        .add_interruptor_inline(Box::new(FlakySwitch { missed: 1, skipping: None }));
+    params.retry = MeasRetryPolicy { retries: 2, retry_speed: Factor::HALF, search_window: Some(Radians(3.0)) };
+    let values = take_simple_meas(&mut stepper, &params, Factor::MAX).unwrap();
+    assert!((values.position_avg - PositionRad(1.0)).abs() < Radians(0.05));
+
+    // Position limits stop the search before the switch is found
+    let mut stepper = Stepper::simulated()
+        .add_interruptor_inline(Box::new(FlakySwitch { missed: 0, skipping: None }));
+    stepper.set_pos_limits(None, Some(PositionRad(0.5)));
+    params.retry = MeasRetryPolicy { retries: 1, ..Default::default() };
+
+    match take_simple_meas(&mut stepper, &params, Factor::MAX) {
+        Err(SimpleMeasError::RetriesExhausted(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert!(errors.iter().all(|err| matches!(err, SimpleMeasError::TravelExceeded)));
+        },
+        other => panic!("Unexpected result: {:?}", other.map(|values| values.position_avg))
+    }
+}
+
+#[test]
+fn filters() {
+    use syunit::metric::Newtons;
+
+    let mut average = MovingAverage::new(4);
+    assert_eq!(average.value(), None);
+
+    for force in [ 1.0, 3.0, 1.0, 3.0 ] {
+        average.update(Newtons(force));
+    }
+
+    assert_eq!(average.value(), Some(Newtons(2.0)));
+    // The oldest sample leaves the window
+    assert_eq!(average.update(Newtons(5.0)), Newtons(3.0));
+
+    let mut iir = Iir::new(Factor::HALF);
+    assert_eq!(iir.update(RadPerSecond(4.0)), RadPerSecond(4.0));
+    assert_eq!(iir.update(RadPerSecond(0.0)), RadPerSecond(2.0));
+    assert_eq!(iir.update(RadPerSecond(0.0)), RadPerSecond(1.0));
+
+    iir.reset();
+    assert_eq!(iir.value(), None);
+
+    // A time constant equal to the sample time weights the new sample with one half
+    assert_eq!(Iir::<Newtons>::from_time_constant(Seconds(0.01), Seconds(0.01)).alpha, Factor::HALF);
+}
+
+/// Switch at 1.0 rad behind a drive train with backlash, the load lags behind the motor by half of the backlash
+struct BacklashSwitch {
+    half_backlash : f32
+}
+
+impl Interruptor for BacklashSwitch {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        let triggered = if ctx.direction.as_bool() {
+            (ctx.pos.0 - self.half_backlash) >= 1.0
+        } else {
+            (ctx.pos.0 + self.half_backlash) <= 1.0
+        };
+
+        triggered.then_some(InterruptReason::EndReached)
+    }
+}
+
+#[test]
+fn homing_backlash() {
+    /// Homes from both sides, returns the shift of the position frame caused by each homing
+    fn home_both_sides(half_backlash : f32, backlash : Option<Radians>) -> Vec<Radians> {
+        let mut params = SimpleMeasParams::default();
+        params.meas_speed = Factor::HALF;
+        params.homing_offset = Radians(0.5);
+        params.backlash = backlash;
+
+        [ (0.0, 2.0), (2.0, -2.0) ].into_iter().map(|(start, max_dist)| {
+            let mut stepper = Stepper::simulated();
+            stepper.add_interruptor(Box::new(BacklashSwitch { half_backlash }));
+            stepper.overwrite_abs_pos(PositionRad(start));
+
+            params.max_dist = Radians(max_dist);
+            let values = take_simple_meas(&mut stepper, &params, Factor::MAX).unwrap();
+
+            assert_eq!(values.backlash_comp, Radians(half_backlash.copysign(max_dist)));
+            values.position_avg - (stepper.pos() - values.correction)
+        }).collect()
+    }
+
+    // The compensated axis gets the same references as an axis without any backlash, apart from step quantization
+    let step_dist = Stepper::simulated().step_dist();
+    let ideal = home_both_sides(0.0, None);
+    let compensated = home_both_sides(0.1, Some(Radians(0.2)));
+
+    for (ideal, compensated) in ideal.into_iter().zip(compensated) {
+        assert!((ideal - compensated).abs() <= step_dist, "Ideal: {}, Compensated: {}", ideal, compensated);
+        assert!((compensated - Radians(0.5)).abs() < Radians(0.1), "Compensated: {}", compensated);
+    }
+}
+
+/// Drives the pin of a switch mounted at 1.0 rad, the pin can be stuck to simulate a defective switch
+struct SwitchMount {
+    pin : Arc<AtomicBool>,
+    stuck : Arc<AtomicBool>
+}
+
+impl Interruptor for SwitchMount {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        self.pin.store((ctx.pos >= PositionRad(1.0)) || self.stuck.load(Ordering::Relaxed), Ordering::Relaxed);
+        None
+    }
+}
+
+#[test]
+fn hard_limit_switch() {
+    let pin = Arc::new(AtomicBool::new(false));
+    let stuck = Arc::new(AtomicBool::new(false));
+
+    let switch = HardLimitSwitch::new(true, Direction::CW, SimSwitch(pin.clone()));
+    let recovery = switch.recovery();
+
+    let mut stepper = Stepper::simulated();
+    stepper.add_interruptor(Box::new(SwitchMount { pin, stuck: stuck.clone() }));
+    stepper.add_interruptor(Box::new(switch));
+    stepper.set_pos_limits(Some(PositionRad(-0.5)), Some(PositionRad(3.0)));
+
+    // The switch stops the movement within the soft limits and latches
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert_eq!(recovery.latched(), Some(Direction::CW));
+    assert!((stepper.pos() - PositionRad(1.0)).abs() < Radians(0.05));
+    assert!((recovery.pos() - 1.0).abs() < 0.05);
+
+    // Normal movements are refused by the switch itself, even away from it
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert!((stepper.pos() - pos).abs() < stepper.step_dist() * 1.5);
+
+    // A defective switch stays active, the latch is kept
+    stuck.store(true, Ordering::Relaxed);
+    assert!(matches!(move_off_switch(&mut stepper, &recovery, Radians(-0.2), Radians(0.1), Factor::HALF), Err(SimpleMeasError::SwitchStuck)));
+    assert_eq!(recovery.latched(), Some(Direction::CW));
+
+    // Moving off the switch clears the latch and restores the soft limits
+    stuck.store(false, Ordering::Relaxed);
+    stepper.overwrite_abs_pos(PositionRad(1.1));
+    let pos_release = move_off_switch(&mut stepper, &recovery, Radians(-1.0), Radians(0.1), Factor::HALF).unwrap();
+
+    assert!((pos_release - PositionRad(1.0)).abs() <= stepper.step_dist() * 2.0, "Release: {}", pos_release);
+    assert_eq!(recovery.latched(), None);
+    assert!(!recovery.active());
+    assert_eq!(stepper.limit_max(), Some(PositionRad(3.0)));
+
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+    assert!(stepper.pos() < PositionRad(0.5));
+}
+
+#[test]
+fn move_off_switch_recovery() {
+    let pin = Arc::new(AtomicBool::new(false));
+    let stuck = Arc::new(AtomicBool::new(false));
+
+    let endstop = Recoverable::new(EndStop::new(true, None, SimSwitch(pin.clone())));
+    let recovery = endstop.recovery();
+
+    let mut stepper = Stepper::simulated();
+    stepper.add_interruptor(Box::new(SwitchMount { pin, stuck: stuck.clone() }));
+    stepper.add_interruptor(Box::new(endstop));
+    stepper.set_pos_limits(Some(PositionRad(-0.5)), Some(PositionRad(1.2)));
+
+    // Started on the switch, which blocks both directions
+    stepper.overwrite_abs_pos(PositionRad(1.5));
+    stepper.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert!((stepper.pos() - PositionRad(1.5)).abs() <= stepper.step_dist() * 1.5);
+
+    // A stuck switch is reported, the state is restored
+    stuck.store(true, Ordering::Relaxed);
+    assert!(matches!(move_off_switch(&mut stepper, &recovery, Radians(-0.2), Radians(0.2), Factor::HALF), Err(SimpleMeasError::SwitchStuck)));
+    assert_eq!(recovery.recovering(), None);
+    assert_eq!(stepper.limit_max(), Some(PositionRad(1.2)));
+
+    // Moving off stops at the release point, followed by the margin
+    stuck.store(false, Ordering::Relaxed);
+    let pos_release = move_off_switch(&mut stepper, &recovery, Radians(-2.0), Radians(0.2), Factor::HALF).unwrap();
+
+    assert!((pos_release - PositionRad(1.0)).abs() <= stepper.step_dist() * 2.0, "Release: {}", pos_release);
+    assert!((stepper.pos() - (pos_release - Radians(0.2))).abs() <= stepper.step_dist());
+    assert!(!recovery.active());
+    assert_eq!((stepper.limit_min(), stepper.limit_max()), (Some(PositionRad(-0.5)), Some(PositionRad(1.2))));
+
+    // The switch protects the component again
+    stepper.set_pos_limits(None, None);
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert!((stepper.pos() - PositionRad(1.0)).abs() <= stepper.step_dist() * 2.0);
+}
+
+#[test]
+fn endstop_latency_compensation() {
+    let pin = Arc::new(AtomicBool::new(false));
+    let mut endstop = EndStop::new(true, Some(Direction::CW), SimSwitch(pin.clone()))
+        .with_latency(Seconds(0.005));
+
+    let moving = InterruptContext { pos: PositionRad(1.0), velocity: RadPerSecond(2.0), last_interval: Seconds(0.01), ..ctx(Direction::CW) };
+    assert_eq!(endstop.check(&moving), None);
+    assert_eq!(endstop.trigger_pos(), None);
+
+    // Moved back by half of the check interval and the latency
+    pin.store(true, Ordering::Relaxed);
+    assert_eq!(endstop.check(&moving), Some(InterruptReason::EndReached));
+    assert!((endstop.trigger_pos().unwrap() - PositionRad(0.98)).abs() < Radians(1e-4));
+
+    pin.store(false, Ordering::Relaxed);
+    endstop.check(&moving);
+    assert_eq!(endstop.trigger_pos(), None);
+
+    // Homing references the trigger position instead of the position the stepper stopped at
+    let pin = Arc::new(AtomicBool::new(false));
+    let mut stepper = Stepper::simulated();
+    stepper.add_interruptor(Box::new(SwitchMount { pin: pin.clone(), stuck: Arc::new(AtomicBool::new(false)) }));
+    stepper.add_interruptor(Box::new(EndStop::new(true, Some(Direction::CW), SimSwitch(pin))));
+
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert!(stepper.intr_pos().unwrap() <= stepper.pos());
+
+    let mut params = SimpleMeasParams::default();
+    params.max_dist = Radians(2.0);
+    params.meas_speed = Factor::HALF;
+
+    stepper.overwrite_abs_pos(PositionRad::ZERO);
+    let values = take_simple_meas(&mut stepper, &params, Factor::MAX).unwrap();
+    assert!((values.position_avg - PositionRad(1.0)).abs() <= stepper.step_dist(), "Average: {}", values.position_avg);
+
+    // Parents convert the trigger position of their child
+    let pin = Arc::new(AtomicBool::new(false));
+    let mut gear = Gear::new(Stepper::simulated(), 0.5);
+    gear.add_interruptor(Box::new(SwitchMount { pin: pin.clone(), stuck: Arc::new(AtomicBool::new(false)) }));
+    gear.add_interruptor(Box::new(EndStop::new(true, Some(Direction::CW), SimSwitch(pin))));
+
+    gear.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert_eq!(gear.intr_reason(), Some(InterruptReason::EndReached));
+    assert_eq!(gear.intr_pos(), gear.child().intr_pos().map(|pos| pos * 0.5));
+    assert!(gear.intr_pos().unwrap() <= gear.pos());
+}
+
+#[test]
+fn load_monitor() {
+    use atomic_float::AtomicF32;
+
+    let measured = Arc::new(AtomicF32::new(0.0));
+    let encoder = measured.clone();
+
+    let mut monitor : LoadMonitor = LoadMonitor::new(Box::new(move || Some(RadPerSecond(encoder.load(Ordering::Relaxed)))));
+    let status = monitor.status();
+
+    // Trapezoidal move, friction slows the acceleration down and speeds the deceleration up by the given fraction
+    let mut run_move = |friction : f32| {
+        let profile = (1 ..= 10).chain((1 .. 10).rev()).map(|v| v as f32);
+        let mut speed_cmd = 0.0;
+        let mut speed_meas = 0.0;
+
+        for speed in profile {
+            let delta = speed - speed_cmd;
+            speed_meas += delta * if delta > 0.0 { 1.0 - friction } else { 1.0 + friction };
+            speed_cmd = speed;
+
+            measured.store(speed_meas, Ordering::Relaxed);
+            assert_eq!(monitor.check(&InterruptContext { velocity: RadPerSecond(speed), ..ctx(Direction::CW) }), None);
+        }
+    };
+
+    run_move(0.0);
+    run_move(0.2);
+    assert_eq!(status.moves(), 1);
+    assert!((status.margin() - 1.0).abs() < 0.01);
+
+    // The last move is evaluated manually
+    let margin = status.finish_move().unwrap();
+    assert!((margin - 0.8).abs() < 0.01);
+    assert_eq!(status.moves(), 2);
+    assert!(status.average() < 1.0);
+    assert!(status.trend() < 0.0);
+
+    // Nothing left to evaluate
+    assert_eq!(status.finish_move(), None);
+}
+
+#[test]
+fn sensorless_endstop() {
+    use std::sync::atomic::AtomicUsize;
+
+    use atomic_float::AtomicF32;
+
+    use crate::meas::SensorlessEndstop;
+
+    let load = Arc::new(AtomicF32::new(400.0));
+    let driver = load.clone();
+
+    // StallGuard-like value, falling with increasing load
+    let mut endstop = SensorlessEndstop::new(100.0, Some(Direction::CW), Box::new(move || Some(driver.load(Ordering::Relaxed))))
+        .inverted()
+        .with_samples(2)
+        .with_velocity_min(RadPerSecond(5.0));
+
+    let moving = |pos : f32| InterruptContext { pos: PositionRad(pos), velocity: RadPerSecond(10.0), ..ctx(Direction::CW) };
+
+    assert_eq!(endstop.dir(), Some(Direction::CW));
+    assert_eq!(endstop.check(&moving(0.0)), None);
+
+    // Load peaks during the acceleration are ignored
+    load.store(50.0, Ordering::Relaxed);
+    assert_eq!(endstop.check(&InterruptContext { velocity: RadPerSecond(2.0), ..ctx(Direction::CW) }), None);
+
+    // Single peaks are filtered
+    assert_eq!(endstop.check(&moving(1.0)), None);
+    load.store(300.0, Ordering::Relaxed);
+    assert_eq!(endstop.check(&moving(1.1)), None);
+
+    // Stall at the mechanical end
+    load.store(20.0, Ordering::Relaxed);
+    assert_eq!(endstop.check(&moving(1.2)), None);
+    assert_eq!(endstop.check(&moving(1.21)), Some(InterruptReason::EndReached));
+    assert!((endstop.trigger_pos().unwrap() - PositionRad(1.2)).abs() < Radians(0.01));
+
+    // Homing move of a machine without switches, the load rises once the carriage hits the end after 100 checks
+    let checks = Arc::new(AtomicUsize::new(0));
+    let counter = checks.clone();
+
+    let mut stepper = Stepper::simulated().add_interruptor_inline(Box::new(
+        SensorlessEndstop::new(1.0, Some(Direction::CW), Box::new(move || {
+            Some(if counter.fetch_add(1, Ordering::Relaxed) >= 100 { 2.0 } else { 0.5 })
+        }))
+    ));
+
+    stepper.drive_rel_blocking(Radians(100.0), Factor::HALF).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert!(stepper.pos() < PositionRad(100.0));
+}
@@ -7,9 +7,33 @@
 // ####################
     mod sync;
     #[allow(unused)]
-    pub use sync::{Stepper, ComplexStepper, SimulatedController};
+    pub use sync::realtime_controller;
+
+    mod asyn;
+
+    mod comps;
+
+    #[cfg(feature = "serde")]
+    mod config;
 
     mod data;
+
+    mod io;
+
+    mod math;
+
+    #[cfg(feature = "meas")]
+    mod meas;
+
+    mod path;
+
+    mod recipes;
+
+    mod record;
+
+    mod safety;
+
+    mod sim;
 // 
 
 // ####################
@@ -0,0 +1,41 @@
+use syunit::prelude::*;
+
+use crate::devices::prelude::*;
+use crate::groups::prelude::*;
+use crate::motion::SegmentEvent;
+use crate::motion::prelude::*;
+
+#[test]
+fn linear_path_planner() {
+    let mut group = [
+        LinearAxis::from_screw(Stepper::simulated(), Millimeters(8.0)),
+        LinearAxis::from_screw(Stepper::simulated(), Millimeters(8.0))
+    ];
+    let tolerance = Millimeters(0.05);
+
+    let mut planner = LinearPathPlanner::new(Factor::MAX);
+    planner.push([ PositionMM(10.0), PositionMM(0.0) ], 1_u32);
+    planner.push_feed([ PositionMM(10.0), PositionMM(5.0) ], MMPerSecond(0.5), 2);
+    planner.push([ PositionMM(0.0), PositionMM(0.0) ], 3);
+
+    // The first movement only moves X
+    let planned = planner.plan_next(&group).unwrap();
+    assert!((planned.length - Millimeters(10.0)).abs() < tolerance);
+    assert!((planned.rel_dists[0] - Radians(10.0 / 8.0 * 2.0 * core::f32::consts::PI)).abs() < Radians(1e-3));
+    assert_eq!(planned.rel_dists[1], Radians::ZERO);
+    assert_eq!(planned.speed, Factor::MAX);
+
+    assert!(matches!(planner.run_next(&mut group), Some(SegmentEvent::Completed(1))));
+
+    // The low feed rate slows down the second movement
+    let planned = planner.plan_next(&group).unwrap();
+    assert!((planned.length - Millimeters(5.0)).abs() < tolerance);
+    assert!(planned.speed < Factor::MAX);
+
+    let mut tags = Vec::new();
+    planner.run_all(&mut group, |event| tags.extend(event.tag().copied())).unwrap();
+
+    assert_eq!(tags, [ 2, 3 ]);
+    assert!(planner.is_empty());
+    assert!(group.iter().all(|axis| (axis.pos() - PositionMM::ZERO).abs() < tolerance));
+}
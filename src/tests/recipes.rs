@@ -0,0 +1,47 @@
+use embedded_hal::delay::DelayNs;
+
+use crate::prelude::*;
+use crate::recipes::{StageProgress, StagedSchedule};
+
+/// Accumulates the time waited instead of waiting
+#[derive(Default)]
+struct DwellCounter(u64);
+
+impl DelayNs for DwellCounter {
+    fn delay_ns(&mut self, ns : u32) {
+        self.0 += ns as u64;
+    }
+}
+
+#[test]
+fn staged_schedule() {
+    let mut schedule = StagedSchedule::new();
+    schedule.add_stage(Radians(1.0), Factor::new(0.25), Seconds(0.5));
+    schedule.add_stage(Radians(2.0), Factor::MAX, Seconds::ZERO);
+    schedule.add_stage(Radians(-0.5), Factor::HALF, Seconds(1.0));
+
+    assert_eq!(schedule.total_dist(), Radians(2.5));
+
+    let mut stepper = Stepper::simulated();
+    let step_dist = stepper.step_dist();
+    let mut delay = DwellCounter::default();
+    let mut progress : Vec<StageProgress> = Vec::new();
+
+    schedule.run(&mut stepper, &mut delay, |p| progress.push(p)).unwrap();
+
+    assert_eq!(progress.len(), 3);
+    assert!(progress[2].is_last());
+    assert!((progress[0].pos - PositionRad(1.0)).abs() <= step_dist);
+    assert!((progress[1].pos - PositionRad(3.0)).abs() <= step_dist);
+    assert!((stepper.pos() - PositionRad(2.5)).abs() <= step_dist);
+
+    // Dwell times of all stages
+    assert_eq!(delay.0, 1_500_000_000);
+
+    // Failing stages stop the schedule
+    stepper.add_premove_check("guard", Box::new(|| Err("guard open")));
+    progress.clear();
+
+    assert!(matches!(schedule.run(&mut stepper, &mut delay, |p| progress.push(p)), Err(ActuatorError::PreMoveCheckFailed("guard", _))));
+    assert!(progress.is_empty());
+}
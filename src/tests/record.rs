@@ -0,0 +1,96 @@
+use crate::prelude::*;
+use crate::record::{TrajectoryPlayer, TrajectoryRecorder, TrajectorySample};
+
+#[test]
+fn trajectory_recording() {
+    let mut recorder = TrajectoryRecorder::new(Stepper::simulated());
+    recorder.drive_rel_blocking(Radians(1.0), Factor::HALF).unwrap();
+
+    // Every step is sampled, plus the end of the movement
+    let steps = (Radians(1.0) / recorder.actuator().step_dist()).round() as usize;
+    assert_eq!(recorder.samples().len(), steps + 1);
+
+    let samples = recorder.samples();
+    assert!(samples.windows(2).all(|w| (w[0].time < w[1].time) && (w[0].pos <= w[1].pos)));
+    assert_eq!(samples.last().unwrap().pos, recorder.actuator().pos());
+    assert_eq!(samples.last().unwrap().velocity, RadPerSecond::ZERO);
+
+    // Decimated samples, the time continues over movements
+    let time = samples.last().unwrap().time;
+    recorder.clear();
+    recorder.set_sample_interval(Seconds(0.05));
+    recorder.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+
+    let samples = recorder.samples();
+    assert!(samples.len() < steps / 4);
+    assert!(samples.windows(2).all(|w| (w[1].time - w[0].time) <= Seconds(0.06)));
+    assert!(samples[0].time < time);
+    assert!((samples.last().unwrap().pos - PositionRad::ZERO).abs() < Radians(0.01));
+}
+
+#[test]
+fn trajectory_recording_composed() {
+    let mut recorder = TrajectoryRecorder::new(Gear::new(Stepper::simulated(), 0.5)).with_sample_interval(Seconds(0.02));
+    recorder.drive_abs_blocking(PositionRad(2.0), Factor::MAX).unwrap();
+
+    // The samples are given at the output of the gear, not at the motor
+    let samples = recorder.samples();
+    assert!(samples.iter().all(|sample| (PositionRad::ZERO <= sample.pos) && (sample.pos <= PositionRad(2.01))));
+    assert!((samples.last().unwrap().pos - PositionRad(2.0)).abs() < Radians(0.01));
+
+    // The velocities are halved compared to the motor
+    let mut motor = TrajectoryRecorder::new(Stepper::simulated());
+    motor.drive_abs_blocking(PositionRad(4.0), Factor::MAX).unwrap();
+
+    let velocity_max = |samples : &[TrajectorySample]| samples.iter()
+        .map(|sample| sample.velocity)
+        .fold(RadPerSecond::ZERO, RadPerSecond::max);
+    assert!((velocity_max(samples) * 2.0 - velocity_max(motor.samples())).abs() < RadPerSecond(0.5));
+}
+
+#[test]
+fn trajectory_playback() {
+    let mut recorder = TrajectoryRecorder::new(Stepper::simulated()).with_sample_interval(Seconds(0.02));
+    recorder.drive_rel_blocking(Radians(2.0), Factor::HALF).unwrap();
+    recorder.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+
+    let player = recorder.player();
+    assert!(player.duration() > Seconds::ZERO);
+
+    let mut stepper = Stepper::simulated();
+    player.play(&mut stepper, Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(1.5)).abs() < Radians(0.05));
+
+    // Relative to the current position
+    player.play_rel(&mut stepper, Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(3.0)).abs() < Radians(0.05));
+
+    // Nothing to replay
+    TrajectoryPlayer::new(Vec::new()).play(&mut stepper, Factor::MAX).unwrap();
+}
+
+#[test]
+fn trajectory_playback_streamed() {
+    let trapezoidal = || {
+        let mut stepper = TrapezoidalStepper::simulated();
+        stepper.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+        stepper.set_acceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+        stepper
+    };
+
+    let mut recorder = TrajectoryRecorder::new(trapezoidal()).with_sample_interval(Seconds(0.1));
+    recorder.drive_rel_blocking(Radians(6.0), Factor::MAX).unwrap();
+    let player = recorder.player();
+
+    // The samples are blended into each other, the replay takes about as long as the recording instead of ramping down 
+    // at every sample
+    let mut stepper = trapezoidal();
+    player.play_rel(&mut stepper, Factor::MAX).unwrap();
+
+    assert!((stepper.pos() - PositionRad(6.0)).abs() < Radians(0.05));
+    assert!(stepper.stats().run_time < (player.duration().0 * 1.4) as f64);
+
+    // The probe is removed with the recorder
+    let mut stepper = recorder.into_inner();
+    assert_eq!(stepper.remove_interruptors("TrajectoryRecorder"), 0);
+}
@@ -0,0 +1,293 @@
+use core::convert::Infallible;
+use core::time::Duration;
+
+use embedded_hal::digital::{ErrorType, OutputPin};
+
+use crate::{InterruptContext, Interruptible, Interruptor, InterruptReason};
+#[cfg(feature = "meas")]
+use crate::meas::Measurable;
+use crate::prelude::*;
+#[cfg(feature = "meas")]
+use crate::safety::{BrakeTest, BrakeTestError, BrakeTestParams};
+use crate::safety::{CommandDiagnostic, CommandSanitizer, EmergencyStop, OverspeedSupervisor, 
+    SanitizerStats, ThermalFault, ThermalGuard};
+use crate::sim::{self, VirtualClock};
+
+#[test]
+fn thermal_guard() {
+    let estop = EmergencyStop::new();
+    let mut temp = Some(200.0);
+
+    {
+        let mut guard = ThermalGuard::new(|| temp, estop.clone(), 20.0, 250.0, Seconds(1.0));
+
+        // In range
+        assert_eq!(guard.poll(Seconds(0.5)), None);
+        assert!(!estop.is_triggered());
+    }
+
+    temp = Some(260.0);
+    let mut guard = ThermalGuard::new(|| temp, estop.clone(), 20.0, 250.0, Seconds(1.0));
+
+    // Grace period
+    assert_eq!(guard.poll(Seconds(0.6)), None);
+    assert_eq!(guard.poll(Seconds(0.6)), Some(ThermalFault::OverTemperature(260.0)));
+    assert!(estop.is_triggered());
+
+    // Motion halts on the triggered emergency stop
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(estop.interruptor());
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!(stepper.pos() < PositionRad(0.1));
+
+    // Sensor faults trip immediately
+    estop.reset();
+    let mut guard = ThermalGuard::new(|| None, estop.clone(), 20.0, 250.0, Seconds(1.0));
+    assert_eq!(guard.poll(Seconds(0.0)), Some(ThermalFault::SensorFault));
+    assert!(estop.is_triggered());
+}
+
+#[test]
+fn emergency_stop_registered() {
+    use crate::sync::SyncActuatorGroup;
+
+    /// Triggers the emergency stop once the virtual time has passed `time`, like a button pressed during the movement
+    struct TriggerAt {
+        estop : EmergencyStop,
+        clock : VirtualClock,
+        time : Option<Duration>
+    }
+
+    impl Interruptor for TriggerAt {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, _ctx : &InterruptContext) -> Option<InterruptReason> {
+            if self.time.is_some_and(|time| self.clock.now() >= time) {
+                self.time = None;
+                self.estop.trigger();
+            }
+
+            None
+        }
+    }
+
+    let estop = EmergencyStop::new();
+    let clock = VirtualClock::new();
+
+    let (mut stepper, _) = sim::stepper::<StartStopBuilder>(&clock);
+    let mut group = [ sim::stepper::<StartStopBuilder>(&VirtualClock::new()).0, sim::stepper::<StartStopBuilder>(&VirtualClock::new()).0 ];
+
+    stepper.add_interruptor(Box::new(TriggerAt { estop: estop.clone(), clock: clock.clone(), time: Some(Duration::from_millis(100)) }));
+    estop.register(&mut stepper);
+    estop.register_group(&mut group);
+    assert_eq!(estop.registered(), 3);
+
+    // Triggered while the movement is in progress
+    stepper.drive_rel_blocking(Radians(20.0), Factor::MAX).unwrap();
+    assert!(estop.is_triggered());
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!(stepper.pos() < PositionRad(19.0));
+
+    // New movements are stopped while the emergency stop is triggered
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!((stepper.pos() - pos).abs() < stepper.step_dist() * 1.5);
+
+    group.drive_ptp_coordinated([ PositionRad(20.0), PositionRad(-20.0) ], Factor::MAX).unwrap();
+    assert!((group[0].pos() - PositionRad::ZERO).abs() < Radians(0.1));
+    assert!((group[1].pos() - PositionRad::ZERO).abs() < Radians(0.1));
+
+    // Unregistered actuators move again, even though the emergency stop is still triggered
+    assert!(estop.unregister(&mut stepper));
+    assert!(!estop.unregister(&mut stepper));
+    estop.unregister_group(&mut group);
+    assert_eq!(estop.registered(), 0);
+
+    group.drive_ptp_coordinated([ PositionRad(1.0), PositionRad(-1.0) ], Factor::MAX).unwrap();
+    assert!((group[0].pos() - PositionRad(1.0)).abs() <= group[0].step_dist());
+
+    // Registered actuators are not affected once the emergency stop is reset
+    estop.register(&mut stepper);
+    estop.reset();
+
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), None);
+    assert!(((stepper.pos() - pos) - Radians(0.5)).abs() <= stepper.step_dist());
+}
+
+#[test]
+fn emergency_stop_unregister_own() {
+    let door = EmergencyStop::new();
+    let button = EmergencyStop::new();
+
+    let (mut stepper, _) = sim::stepper::<StartStopBuilder>(&VirtualClock::new());
+    door.register(&mut stepper);
+    button.register(&mut stepper);
+
+    // Unregistering the door keeps the button registered
+    assert!(door.unregister(&mut stepper));
+    assert!(!door.unregister(&mut stepper));
+    assert_eq!(door.registered(), 0);
+    assert_eq!(button.registered(), 1);
+
+    door.trigger();
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), None);
+
+    button.trigger();
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!((stepper.pos() - pos).abs() < stepper.step_dist() * 1.5);
+
+    // Clones share the identity of the emergency stop
+    assert!(button.clone().unregister(&mut stepper));
+    assert_eq!(button.registered(), 0);
+}
+
+#[test]
+#[cfg(feature = "meas")]
+fn brake_test() {
+    struct BrakePin {
+        engaged : bool
+    }
+
+    impl ErrorType for BrakePin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for BrakePin {
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.engaged = true;
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.engaged = false;
+            Ok(())
+        }
+    }
+
+    /// Encoder returning the given sequence of positions
+    struct Encoder(Vec<PositionRad>);
+
+    impl Measurable<PositionRad> for Encoder {
+        type Error = ();
+
+        fn measure(&mut self) -> Result<PositionRad, Self::Error> {
+            self.0.pop().ok_or(())
+        }
+    }
+
+    let mut stepper = Stepper::default();
+    let mut brake = BrakePin { engaged: false };
+    let mut test = BrakeTest::new(BrakeTestParams {
+        test_dist: Radians(0.1),
+        speed: Factor::HALF,
+        tolerance: Radians(0.01),
+        engaged_state: true
+    });
+
+    // Brake holds
+    test.run(&mut stepper, &mut brake, &mut Encoder(vec![ PositionRad(0.005), PositionRad::ZERO ])).unwrap();
+    assert!(brake.engaged);
+    assert!((stepper.pos() - PositionRad::ZERO).abs() < stepper.step_dist());
+
+    // Brake slips, the fault is latched
+    assert!(matches!(
+        test.run(&mut stepper, &mut brake, &mut Encoder(vec![ PositionRad(0.1), PositionRad::ZERO ])), 
+        Err(BrakeTestError::Slipped(_))
+    ));
+    assert!(test.fault().is_some());
+    assert!(matches!(test.run(&mut stepper, &mut brake, &mut Encoder(vec![ PositionRad::ZERO; 2 ])), Err(BrakeTestError::Slipped(_))));
+
+    test.reset();
+    test.run(&mut stepper, &mut brake, &mut Encoder(vec![ PositionRad::ZERO; 2 ])).unwrap();
+}
+
+#[test]
+fn overspeed_supervisor() {
+    // Commanded velocity, the ceiling is way below the velocity of the movement
+    let estop = EmergencyStop::new();
+    let supervisor = OverspeedSupervisor::new(RadPerSecond(1.0), 3).with_estop(estop.clone());
+    let status = supervisor.status();
+
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(Box::new(supervisor));
+
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!(status.tripped());
+    assert!(!status.measured());
+    assert!(status.velocity() > 1.0);
+    assert!(estop.is_triggered());
+
+    // Latched until reset
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!((stepper.pos() - pos).abs() < Radians(0.1));
+
+    status.reset();
+    assert!(!status.tripped());
+
+    // Measured velocity, runaway after 10 checks
+    let mut checks = 0;
+    let supervisor = OverspeedSupervisor::new(RadPerSecond(1000.0), 3)
+        .with_encoder(Box::new(move || {
+            checks += 1;
+            Some(RadPerSecond(if checks > 10 { 2000.0 } else { 10.0 }))
+        }));
+    let status = supervisor.status();
+
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(Box::new(supervisor));
+
+    stepper.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::Error));
+    assert!(status.tripped());
+    assert!(status.measured());
+    assert_eq!(status.velocity(), 2000.0);
+}
+
+#[test]
+fn command_sanitizer() {
+    let mut stepper = Stepper::simulated();
+    let mut sanitizer = CommandSanitizer::<Rotary>::new(Seconds(0.1))
+        .with_dist_max(Radians(1.0))
+        .with_velocity_max(RadPerSecond(10.0))
+        .with_velocity_deadband(RadPerSecond(0.5));
+
+    // Invalid and excessive distances
+    assert_eq!(sanitizer.drive_rel(&mut stepper, Radians(f32::NAN), Factor::MAX).unwrap(), CommandDiagnostic::Rejected("NaN value"));
+    assert_eq!(sanitizer.drive_rel(&mut stepper, Radians(f32::NEG_INFINITY), Factor::MAX).unwrap(), CommandDiagnostic::Clamped);
+    assert!((stepper.pos() - PositionRad(-1.0)).abs() <= stepper.step_dist());
+
+    // Rate limit
+    sanitizer.poll(Seconds(0.05));
+    assert!(matches!(sanitizer.drive_rel(&mut stepper, Radians(0.5), Factor::MAX).unwrap(), CommandDiagnostic::RateLimited(_)));
+    sanitizer.poll(Seconds(0.05));
+    assert_eq!(sanitizer.drive_rel(&mut stepper, Radians(0.5), Factor::MAX).unwrap(), CommandDiagnostic::Forwarded);
+    assert!((stepper.pos() - PositionRad(-0.5)).abs() <= stepper.step_dist());
+
+    // Velocity updates
+    sanitizer.poll(Seconds(0.1));
+    assert_eq!(sanitizer.sanitize_velocity(RadPerSecond(20.0)), Ok((RadPerSecond(10.0), CommandDiagnostic::Clamped)));
+    sanitizer.poll(Seconds(0.1));
+    assert_eq!(sanitizer.sanitize_velocity(RadPerSecond(9.8)), Err(CommandDiagnostic::Coalesced));
+    assert!(sanitizer.sanitize_velocity(RadPerSecond(5.0)).is_ok());
+    
+    // Stops pass the rate limit, but are coalesced if redundant
+    assert_eq!(sanitizer.sanitize_velocity(RadPerSecond::ZERO), Ok((RadPerSecond::ZERO, CommandDiagnostic::Forwarded)));
+    assert_eq!(sanitizer.sanitize_velocity(RadPerSecond::ZERO), Err(CommandDiagnostic::Coalesced));
+
+    assert_eq!(sanitizer.stats(), SanitizerStats { forwarded: 3, clamped: 2, coalesced: 2, rate_limited: 1, rejected: 1 });
+}
@@ -0,0 +1,115 @@
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+
+use embedded_hal::delay::DelayNs;
+
+use crate::prelude::*;
+use crate::sim::{self, SimEndStop, SimFault, VirtualClock};
+
+#[test]
+fn virtual_clock() {
+    let clock = VirtualClock::new();
+    let mut delay = clock.clone();
+
+    clock.advance(Seconds(1.5));
+    delay.delay_ms(500);
+    clock.advance(Seconds(-1.0));
+
+    assert_eq!(clock.now(), Duration::from_secs(2));
+}
+
+#[test]
+fn sim_stepper_trajectory() {
+    let clock = VirtualClock::new();
+    let (mut stepper, state) = sim::stepper::<StartStopBuilder>(&clock);
+
+    stepper.drive_rel_blocking(Radians(10.0), Factor::MAX).unwrap();
+
+    let trajectory = state.trajectory();
+    assert_eq!(trajectory.len() as u64, state.steps());
+    assert!((state.pos() - PositionRad(10.0)).abs() < Radians(0.05));
+    assert_eq!(state.pos(), state.commanded_pos());
+
+    // The trajectory is recorded in virtual time and moves monotonically
+    let points = trajectory.points();
+    assert!(points.windows(2).all(|w| (w[0].0 <= w[1].0) && (w[0].1 < w[1].1)));
+    assert_eq!(trajectory.last().unwrap().0, clock.now());
+    assert!(clock.now() > Duration::from_millis(100));
+
+    // The positions follow the microsteps of the motor
+    stepper.set_microsteps(MicroSteps::from(8)).unwrap();
+    assert_eq!(state.step_angle(), stepper.step_dist());
+
+    stepper.drive_rel_blocking(Radians(-5.0), Factor::MAX).unwrap();
+    assert!((state.pos() - PositionRad(5.0)).abs() < Radians(0.05));
+    assert!((state.pos() - stepper.pos()).abs() < state.step_angle());
+}
+
+#[test]
+fn sim_stepper_faults() {
+    let clock = VirtualClock::new();
+    let (mut stepper, state) = sim::stepper::<StartStopBuilder>(&clock);
+
+    state.inject(SimFault::MissedSteps { after: 10, count: 5 });
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+
+    assert_eq!(state.lost_steps(), 5);
+    assert!(((state.commanded_pos() - state.pos()) - state.step_angle() * 5.0).abs() < Radians(1e-4));
+
+    // A stall aborts the movement
+    state.clear_faults();
+    state.inject(SimFault::Stall { after: state.steps() + 3 });
+    assert!(matches!(stepper.drive_rel_blocking(Radians(2.0), Factor::MAX), Err(ActuatorError::Overload)));
+}
+
+#[test]
+fn sim_endstop() {
+    let clock = VirtualClock::new();
+    let (mut stepper, state) = sim::stepper::<StartStopBuilder>(&clock);
+
+    let endstop = SimEndStop::new(state.clone(), PositionRad(1.0), Direction::CW);
+    let forced = endstop.forced();
+    stepper.add_interruptor(Box::new(endstop));
+
+    stepper.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+    assert!((state.pos() - PositionRad(1.0)).abs() < state.step_angle() * 2.0);
+
+    // Moving away from the switch
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+    let pos = state.pos();
+
+    // A forced trigger stops the next movement immediately
+    forced.store(true, Ordering::Relaxed);
+    stepper.drive_rel_blocking(Radians(0.3), Factor::MAX).unwrap();
+    assert!((state.pos() - pos).abs() < state.step_angle() * 2.0);
+}
+
+#[cfg(feature = "servo")]
+#[test]
+fn sim_servo() {
+    use crate::data::servo::ServoConst;
+
+    let clock = VirtualClock::new();
+    let (mut servo, pwm) = sim::servo(&clock, ServoConst::MG996R);
+
+    servo.drive_abs_blocking(PositionRad(1.0), Factor::HALF).unwrap();
+
+    assert!(clock.now() > Duration::ZERO);
+    assert!(pwm.trajectory().len() > 1);
+    assert_eq!(pwm.trajectory().last().unwrap().1, pwm.duty());
+}
+
+#[test]
+fn sim_dc_motor() {
+    let clock = VirtualClock::new();
+    let (mut motor, plant) = sim::dc_motor(&clock, RadPerSecond(20.0));
+
+    motor.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+    assert!((plant.pos() - PositionRad(5.0)).abs() < Radians(0.02));
+    assert!(clock.now() > Duration::from_millis(250));
+    assert!(!plant.trajectory().is_empty());
+
+    // A disconnected encoder fails the movement
+    plant.set_encoder_fault(true);
+    assert!(motor.drive_rel_blocking(Radians(1.0), Factor::MAX).is_err());
+}
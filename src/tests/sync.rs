@@ -1,2 +1,49 @@
+mod clock;
+
+mod dc;
+
+mod motion;
+
+#[cfg(feature = "servo")]
+mod servo;
+
 mod stepper;
-pub use stepper::{Stepper, ComplexStepper, SimulatedController};
\ No newline at end of file
+pub use stepper::realtime_controller;
+
+#[test]
+fn actuator_state_defaults() {
+    use core::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+    use crate::{MotionMode, SyncActuatorState};
+    use crate::prelude::*;
+
+    /// A state implementing only the required methods
+    #[derive(Default)]
+    struct MinimalState {
+        moving : AtomicBool
+    }
+
+    impl SyncActuatorState for MinimalState {
+        fn pos(&self) -> PositionRad {
+            PositionRad(1.0)
+        }
+
+        fn moving(&self) -> bool {
+            self.moving.load(Relaxed)
+        }
+
+        fn halt(&self) { }
+
+        fn interrupt(&self) { }
+    }
+
+    let state = MinimalState::default();
+    state.heartbeat();
+
+    assert_eq!(state.predicted_pos(Seconds(10.0)), PositionRad(1.0));
+    assert_eq!(state.motion_mode(), MotionMode::Inactive);
+    assert!(!state.simulated());
+
+    state.moving.store(true, Relaxed);
+    assert_eq!(state.motion_mode(), MotionMode::FixedDistance);
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use embedded_hal::delay::DelayNs;
+
+use crate::prelude::*;
+use crate::sync::{ClockSync, SegmentEvent, SegmentQueue, SharedClock};
+use crate::sync::clock::{ClockSample, LocalClock};
+
+/// Simulated time, advanced by the delay
+#[derive(Clone)]
+struct SimTime(Rc<Cell<f32>>);
+
+impl LocalClock for SimTime {
+    fn now(&mut self) -> Seconds {
+        Seconds(self.0.get())
+    }
+}
+
+impl DelayNs for SimTime {
+    fn delay_ns(&mut self, ns : u32) {
+        self.0.set(self.0.get() + ns as f32 * 1e-9);
+    }
+}
+
+#[test]
+fn clock_sync() {
+    // Remote clock is 5 seconds ahead, 10 ms delay in each direction, 2 ms processing time
+    let sample = ClockSample::from_exchange(Seconds(10.0), Seconds(15.01), Seconds(15.012), Seconds(10.022));
+    assert!((sample.offset - Seconds(5.0)).abs() < Seconds(1e-4));
+    assert!((sample.delay - Seconds(0.02)).abs() < Seconds(1e-4));
+
+    let time = SimTime(Rc::new(Cell::new(100.0)));
+    let mut clock = SharedClock::new(time.clone(), time.clone(), Seconds(0.005));
+
+    let mut stepper = Stepper::simulated();
+    let mut queue = SegmentQueue::new();
+    queue.push_at(Radians(1.0), Factor::MAX, Seconds(106.0), 0);
+    queue.push_at(Radians(1.0), Factor::MAX, Seconds(90.0), 1);
+
+    // Unsynchronized clocks refuse scheduled starts
+    assert!(matches!(queue.run_next_synced(&mut stepper, &mut clock), Some(SegmentEvent::Failed(0, ActuatorError::ClockSkewExceeded(_)))));
+    queue.push_at(Radians(1.0), Factor::MAX, Seconds(106.0), 2);
+
+    // Exchanges with asymmetric delays, the fastest one is used
+    let mut sync = ClockSync::new(4);
+    let mut exchange_time = time.clone();
+    let delays = [ 0.05, 0.004, 0.02 ];
+    let mut i = 0;
+    let synced = sync.sync(&mut time.clone(), || {
+        let delay = delays[i];
+        i += 1;
+
+        exchange_time.delay_ms((delay * 1000.0) as u32);
+        let remote = exchange_time.now() + Seconds(5.0);
+        exchange_time.delay_ms((delay * 1000.0) as u32);
+
+        Some((remote, remote))
+    }, 3);
+
+    assert_eq!(synced, 3);
+    assert!((sync.estimate().unwrap().delay - Seconds(0.008)).abs() < Seconds(1e-3));
+    clock.sync = sync;
+
+    // Segment 1 has been scheduled in the past
+    assert!(matches!(queue.run_next_synced(&mut stepper, &mut clock), Some(SegmentEvent::Failed(1, ActuatorError::StartTimeMissed(_)))));
+
+    // Segment 2 waits for its start time
+    assert!(matches!(queue.run_next_synced(&mut stepper, &mut clock), Some(SegmentEvent::Completed(2))));
+    assert!((clock.now().unwrap() - Seconds(106.0)).abs() < Seconds(0.005));
+}
@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+
+use embedded_hal::delay::DelayNs;
+
+use crate::MotionMode;
+use crate::io::{DigitalOutput, Encoder, IoError, PwmOutput};
+use crate::prelude::*;
+use crate::sync::PidDcMotor;
+
+const COUNTS_PER_REV : f32 = 2000.0;
+const VELOCITY_MOTOR : RadPerSecond = RadPerSecond(20.0);
+/// The time constant of the velocity response of the simulated motor
+const TIME_CONST : f32 = 0.02;
+
+/// A simulated gearmotor, the velocity approaching the one of the duty cycle with a first-order response
+#[derive(Debug, Default)]
+struct Plant {
+    duty : f32,
+    dir : bool,
+    pos : f32,
+    velocity : f32
+}
+
+impl Plant {
+    fn advance(&mut self, time : f32) {
+        let steps = (time / 0.0001).ceil().max(1.0) as usize;
+        let dt = time / steps as f32;
+
+        for _ in 0 .. steps {
+            let target = self.duty * if self.dir { 1.0 } else { -1.0 } * VELOCITY_MOTOR.0;
+            self.velocity += (target - self.velocity) * dt / TIME_CONST;
+            self.pos += self.velocity * dt;
+        }
+    }
+}
+
+struct TestPwm(Arc<Mutex<Plant>>);
+
+impl PwmOutput for TestPwm {
+    fn set_duty(&mut self, duty : Factor) -> Result<(), IoError> {
+        self.0.lock().unwrap().duty = duty.as_f32();
+        Ok(())
+    }
+}
+
+struct TestDir(Arc<Mutex<Plant>>);
+
+impl DigitalOutput for TestDir {
+    fn set(&mut self, state : bool) -> Result<(), IoError> {
+        self.0.lock().unwrap().dir = state;
+        Ok(())
+    }
+}
+
+struct TestEncoder(Arc<Mutex<Plant>>);
+
+impl Encoder for TestEncoder {
+    fn count(&mut self) -> Result<i64, IoError> {
+        let pos = self.0.lock().unwrap().pos;
+        Ok((pos / core::f32::consts::TAU * COUNTS_PER_REV).round() as i64)
+    }
+}
+
+/// Advances the simulation instead of waiting
+struct TestDelay(Arc<Mutex<Plant>>);
+
+impl DelayNs for TestDelay {
+    fn delay_ns(&mut self, ns : u32) {
+        self.0.lock().unwrap().advance(ns as f32 / 1_000_000_000.0);
+    }
+}
+
+fn motor() -> (PidDcMotor<TestPwm, TestEncoder, TestDelay>, Arc<Mutex<Plant>>) {
+    let plant = Arc::new(Mutex::new(Plant::default()));
+
+    (
+        PidDcMotor::new(
+            TestPwm(plant.clone()), 
+            Box::new(TestDir(plant.clone())), 
+            TestEncoder(plant.clone()), 
+            TestDelay(plant.clone()), 
+            COUNTS_PER_REV, 
+            VELOCITY_MOTOR
+        ),
+        plant
+    )
+}
+
+#[test]
+fn dc_motor_position_control() {
+    let (mut motor, plant) = motor();
+    motor.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+
+    motor.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+    assert!((motor.pos() - PositionRad(5.0)).abs() <= motor.tolerance);
+    assert!((plant.lock().unwrap().pos - 5.0).abs() < 0.02);
+    assert_eq!(motor.state().motion_mode(), MotionMode::Inactive);
+
+    motor.drive_abs_blocking(PositionRad(-2.0), Factor::HALF).unwrap();
+    assert!((motor.pos() - PositionRad(-2.0)).abs() <= motor.tolerance);
+    assert_eq!(motor.direction(), Direction::CCW);
+
+    // Without an acceleration limit
+    motor.set_acceleration_max(None).unwrap();
+    motor.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((motor.pos() - PositionRad(-1.0)).abs() <= motor.tolerance);
+}
+
+#[test]
+fn dc_motor_limits() {
+    let (mut motor, _) = motor();
+    motor.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(2.0)));
+
+    motor.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+    assert!((motor.pos() - PositionRad(2.0)).abs() <= motor.tolerance);
+
+    assert!(motor.set_jolt_max(Some(RadPerSecond3(10.0))).is_err());
+    assert!(motor.set_velocity_min(Some(RadPerSecond(50.0))).is_err());
+
+    // Offsets are kept by the encoder position
+    motor.overwrite_abs_pos(PositionRad(10.0));
+    motor.update_pos().unwrap();
+    assert!((motor.pos() - PositionRad(10.0)).abs() < Radians(0.01));
+}
+
+#[test]
+fn dc_motor_settle_timeout() {
+    let (mut motor, _) = motor();
+    motor.gains.p = 0.0;
+    motor.gains.i = 0.0;
+    motor.gains.d = 0.0;
+    motor.velocity_motor = RadPerSecond(40.0);
+
+    // The feed-forward alone only reaches half the distance
+    assert!(matches!(motor.drive_rel_blocking(Radians(2.0), Factor::MAX), Err(ActuatorError::SettleTimeout(_))));
+    assert_eq!(motor.state().motion_mode(), MotionMode::Inactive);
+}
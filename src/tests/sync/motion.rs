@@ -0,0 +1,56 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::prelude::*;
+use crate::sync::AsyncMotion;
+
+/// Wakes the thread blocking on a future
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self : Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls the `future` until it is ready, parking the thread in between
+fn block_on<F : Future>(future : F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        std::thread::park();
+    }
+}
+
+#[test]
+fn async_motion() {
+    let (mut motion, mut worker) = AsyncMotion::split(Stepper::default());
+
+    let handle = std::thread::spawn(move || {
+        while worker.connected() {
+            if !worker.poll() {
+                std::thread::yield_now();
+            }
+        }
+
+        worker.into_inner()
+    });
+
+    block_on(motion.drive_rel(Radians(0.5), Factor::MAX)).unwrap();
+    assert!((motion.state().pos() - PositionRad(0.5)).abs() < Radians(0.01));
+
+    block_on(motion.drive_abs(PositionRad(-0.25), Factor::MAX)).unwrap();
+    assert!((motion.state().pos() - PositionRad(-0.25)).abs() < Radians(0.01));
+
+    // Dropping the handle stops the worker
+    drop(motion);
+    let stepper = handle.join().unwrap();
+    assert!((stepper.pos() - PositionRad(-0.25)).abs() < Radians(0.01));
+}
@@ -0,0 +1,142 @@
+use core::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::pwm::{ErrorType, SetDutyCycle};
+
+use crate::MotionMode;
+use crate::data::servo::ServoConst;
+use crate::prelude::*;
+use crate::sync::MiniServo;
+
+/// PWM output storing its duty cycle
+struct TestPwm(u16);
+
+impl ErrorType for TestPwm {
+    type Error = Infallible;
+}
+
+impl SetDutyCycle for TestPwm {
+    fn max_duty_cycle(&self) -> u16 {
+        1000
+    }
+
+    fn set_duty_cycle(&mut self, duty : u16) -> Result<(), Self::Error> {
+        self.0 = duty;
+        Ok(())
+    }
+}
+
+#[test]
+fn servo_actuator_parity() {
+    let mut servo = MiniServo::new(ServoConst::MG996R, TestPwm(0));
+    let state = servo.clone_state();
+    assert_eq!(state.motion_mode(), MotionMode::Inactive);
+
+    servo.start().unwrap();
+    assert_eq!(state.motion_mode(), MotionMode::Holding);
+    assert!((servo.pos() - ServoConst::MG996R.default_pos()).abs() < Radians(0.01));
+
+    // Relative movements update the position
+    servo.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert!((state.pos() - ServoConst::MG996R.default_pos() - Radians(0.5)).abs() < Radians(0.01));
+
+    // Limits clamp the movements
+    servo.set_pos_limits(Some(PositionRad(0.5)), Some(PositionRad(2.0)));
+    servo.drive_factor(Factor::MAX, Direction::CW).unwrap();
+    assert!((servo.pos() - PositionRad(2.0)).abs() < Radians(0.01));
+    servo.drive_rel_blocking(Radians(-10.0), Factor::MAX).unwrap();
+    assert!((servo.pos() - PositionRad(0.5)).abs() < Radians(0.01));
+    assert_eq!(servo.resolve_pos_limits_for_abs_pos(PositionRad(2.5)), Radians(0.5));
+
+    // Velocity and jolt
+    assert!(matches!(servo.drive_speed(RadPerSecond(100.0)), Err(ActuatorError::VelocityTooHigh(_, _))));
+    servo.drive_speed(RadPerSecond(-1.0)).unwrap();
+    assert!(servo.set_jolt_max(Some(RadPerSecond3(10.0))).is_err());
+    assert_eq!(servo.jolt_max(), None);
+
+    // Overwriting the position shifts the reported positions
+    servo.overwrite_abs_pos(PositionRad(10.0));
+    servo.overwrite_pos_limits(None, None);
+    servo.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((servo.pos() - PositionRad(11.0)).abs() < Radians(0.01));
+
+    servo.stop().unwrap();
+    assert_eq!(state.motion_mode(), MotionMode::Inactive);
+}
+
+/// Records the time waited
+#[derive(Clone, Default)]
+struct SimTime(Arc<Mutex<f32>>);
+
+impl DelayNs for SimTime {
+    fn delay_ns(&mut self, ns : u32) {
+        *self.0.lock().unwrap() += ns as f32 / 1_000_000_000.0;
+    }
+}
+
+#[test]
+fn servo_calibration() {
+    let mut servo = MiniServo::new(ServoConst::MG996R, TestPwm(0));
+    servo.start().unwrap();
+
+    // 1.5 ms of a 20 ms period
+    assert_eq!(servo.pwm().0, 75);
+
+    servo.calibrate(Seconds(0.001), Seconds(0.002), PositionRad(2.0)).unwrap();
+    assert!((servo.pos() - PositionRad(1.0)).abs() < Radians(0.001));
+    assert!((servo.pulse() - Seconds(0.0015)).abs() < Seconds(1e-6));
+
+    servo.drive_abs_blocking(PositionRad(2.0), Factor::MAX).unwrap();
+    assert!((servo.pulse() - Seconds(0.002)).abs() < Seconds(1e-6));
+    assert_eq!(servo.pwm().0, 100);
+}
+
+#[test]
+fn servo_ramping() {
+    let time = SimTime::default();
+    let mut servo = MiniServo::new(ServoConst::MG996R, TestPwm(0)).with_delay(time.clone());
+    let state = servo.clone_state();
+    servo.start().unwrap();
+
+    // Velocity ramp without acceleration limit
+    servo.set_velocity_max(Some(RadPerSecond(1.0))).unwrap();
+    servo.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((servo.pos() - ServoConst::MG996R.default_pos() - Radians(1.0)).abs() < Radians(0.001));
+    assert!((*time.0.lock().unwrap() - 1.0).abs() < 0.05);
+    assert!(!state.moving());
+
+    // The speed factor slows down the ramp
+    *time.0.lock().unwrap() = 0.0;
+    servo.drive_rel_blocking(Radians(-1.0), Factor::HALF).unwrap();
+    assert!((*time.0.lock().unwrap() - 2.0).abs() < 0.05);
+
+    // Trapezoidal ramp, 1 s accelerating and decelerating each
+    *time.0.lock().unwrap() = 0.0;
+    servo.set_acceleration_max(Some(RadPerSecond2(1.0))).unwrap();
+    servo.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((*time.0.lock().unwrap() - 2.0).abs() < 0.1);
+
+    // Polled ramps report their progress
+    servo.set_target(ServoConst::MG996R.default_pos(), Factor::MAX);
+    assert!(state.moving());
+    assert_eq!(state.motion_mode(), MotionMode::FixedDistance);
+
+    for _ in 0 .. 25 {
+        assert!(servo.poll(Seconds(0.02)).unwrap());
+    }
+
+    assert!(servo.pos() < ServoConst::MG996R.default_pos() + Radians(1.0));
+    assert!(servo.pos() > ServoConst::MG996R.default_pos());
+    assert!((servo.servo_state().remaining() - (ServoConst::MG996R.default_pos() - servo.pos())).abs() < Radians(1e-6));
+
+    // A halt decelerates the ramp
+    state.halt();
+    assert!(servo.poll(Seconds(0.02)).unwrap());
+    assert_eq!(state.motion_mode(), MotionMode::Stopping);
+
+    while servo.poll(Seconds(0.02)).unwrap() { }
+
+    assert!(servo.pos() > ServoConst::MG996R.default_pos() + Radians(0.5));
+    assert_eq!(state.motion_mode(), MotionMode::Holding);
+}
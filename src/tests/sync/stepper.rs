@@ -1,5 +1,7 @@
 use std::time::Instant;
 
+use crate::{InterruptContext, Interruptible, Interruptor, InterruptReason, MotionMode};
+use crate::motion::node_stream;
 use crate::prelude::*;
 use crate::tests::PARAM_TIME_ACCURACY;
 
@@ -8,30 +10,28 @@ use crate::tests::PARAM_TIME_ACCURACY;
 // ####################
     mod builder;
 
+    mod conformance;
+
     pub mod ctrl;
-    pub use ctrl::SimulatedController;
+    pub use ctrl::realtime_controller;
 //
 
 // #######################
 // #    Stepper-Types    #
 // #######################
-    /// Helper stepper type for simulated stepper motors using Start-Stop control and simulated controllers
-    pub type Stepper = StepperMotor<StartStopBuilder, SimulatedController>;
-    /// Helper stepper type for simulated stepper motors using complex control and simulated controllers
-    pub type ComplexStepper = StepperMotor<ComplexBuilder, SimulatedController>;
-
     impl Default for Stepper {
         fn default() -> Self {
-            Self::new_advanced(SimulatedController::new(), StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
+            Self::new_advanced(realtime_controller(), StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
         }
     }
 
+    #[cfg(feature = "complex-builder")]
     impl Default for ComplexStepper {
         fn default() -> Self {
-            Self::new_advanced(SimulatedController::new(), StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
+            Self::new_advanced(realtime_controller(), StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
         }
     }
-// 
+//
 
 #[test]
 fn stepper_move_fixed_dist() {
@@ -64,4 +64,1723 @@ fn abs_pos_distance() {
     dbg!(stepper.pos());
     stepper.drive_abs_blocking(PositionRad(10.0), Factor::MAX).unwrap();
     dbg!(stepper.pos());
-}
\ No newline at end of file
+}
+#[test]
+fn stepper_drop_mid_move() {
+    /// Interruptor that panics on its first check, simulating a crash of the control thread
+    struct PanicInterruptor;
+
+    impl Interruptor for PanicInterruptor {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, _ctx : &InterruptContext) -> Option<InterruptReason> {
+            panic!("Simulated crash")
+        }
+    }
+
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(Box::new(PanicInterruptor));
+
+    let state = stepper.clone_state();
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        stepper.drive_rel_blocking(Radians(10.0), Factor::MAX)
+    }));
+
+    assert!(result.is_err());
+    assert!(!state.moving(), "Motor must not be flagged as moving after being dropped");
+}
+
+#[test]
+fn stepper_failed_move() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Controller failing with every step after the given amount of steps
+    struct FailingController {
+        steps_left : Arc<AtomicUsize>,
+        ctrl : SimulatedController
+    }
+
+    impl StepperController for FailingController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            if self.steps_left.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |steps| steps.checked_sub(1)).is_err() {
+                return Err(ActuatorError::IOError);
+            }
+
+            self.ctrl.step(time)
+        }
+
+        fn direction(&self) -> Direction {
+            self.ctrl.direction()
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.ctrl.set_dir(dir)
+        }
+    }
+
+    let failing = |steps_left : &Arc<AtomicUsize>| StepperMotor::<StartStopBuilder, _>::new_advanced(
+        FailingController { steps_left: steps_left.clone(), ctrl: SimulatedController::new() }, 
+        StepperConst::MOT_17HE15_1504S, 
+        StepperConfig::VOLT12_NO_OVERLOAD
+    ).unwrap();
+
+    let steps_left = Arc::new(AtomicUsize::new(50));
+    let mut stepper = failing(&steps_left);
+
+    // The motor stands still after the failed movement
+    assert!(matches!(stepper.drive_rel_blocking(Radians(10.0), Factor::MAX), Err(ActuatorError::IOError)));
+    assert!(!stepper.state().moving());
+    assert_eq!(stepper.state().motion_mode(), MotionMode::Holding);
+    assert!((stepper.pos() - PositionRad::ZERO - stepper.step_dist() * 50.0).abs() < stepper.step_dist());
+
+    // Following movements start from scratch
+    steps_left.store(usize::MAX, Ordering::Relaxed);
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!(((stepper.pos() - pos) - Radians(1.0)).abs() <= stepper.step_dist());
+
+    // Interpolated movements
+    steps_left.store(50, Ordering::Relaxed);
+    let mut stepper_b = Stepper::simulated();
+
+    assert!(matches!(
+        drive_rel_interpolated(&mut [ &mut stepper, &mut stepper_b ], &[ Radians(10.0), Radians(10.0) ], Factor::MAX),
+        Err(ActuatorError::IOError)
+    ));
+    assert!(!stepper.state().moving() && !stepper_b.state().moving());
+    assert_eq!(stepper.state().motion_mode(), MotionMode::Holding);
+    assert_eq!(stepper_b.state().motion_mode(), MotionMode::Holding);
+
+    steps_left.store(usize::MAX, Ordering::Relaxed);
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!(((stepper.pos() - pos) - Radians(1.0)).abs() <= stepper.step_dist());
+}
+
+#[test]
+fn stepper_hold_policy() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Controller that only keeps track of whether it is enabled
+    struct EnableController {
+        enabled : Arc<AtomicBool>,
+        ctrl : SimulatedController
+    }
+
+    impl StepperController for EnableController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            self.ctrl.step(time)
+        }
+
+        fn direction(&self) -> Direction {
+            self.ctrl.direction()
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.ctrl.set_dir(dir)
+        }
+
+        fn set_enabled(&mut self, enabled : bool) -> Result<(), ActuatorError> {
+            self.enabled.store(enabled, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    let enabled = Arc::new(AtomicBool::new(false));
+    let mut stepper = StepperMotor::<StartStopBuilder, _>::new_advanced(
+        EnableController { enabled: enabled.clone(), ctrl: SimulatedController::new() }, 
+        StepperConst::MOT_17HE15_1504S, 
+        StepperConfig::VOLT12_NO_OVERLOAD
+    ).unwrap();
+
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert!(enabled.load(Ordering::Relaxed), "The default policy must keep the driver enabled");
+
+    stepper.set_hold_policy(HoldPolicy::Disable);
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert!(!enabled.load(Ordering::Relaxed), "The driver must be disabled after the movement");
+}
+
+#[test]
+fn stepper_config_profiles() {
+    let mut stepper = Stepper::default();
+
+    stepper.add_config_profile("precise", StepperConfig::VOLT12_NO_OVERLOAD);
+    stepper.add_config_profile("rapid", StepperConfig::new(24.0, Some(2.0)));
+
+    assert_eq!(stepper.config_profile(), None);
+    assert!(matches!(stepper.select_config_profile("unknown"), Err(ActuatorError::UnknownConfigProfile)));
+
+    stepper.select_config_profile("rapid").unwrap();
+    assert_eq!(stepper.config_profile(), Some("rapid"));
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+
+    stepper.select_config_profile("precise").unwrap();
+    assert_eq!(stepper.config_profile(), Some("precise"));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn stepper_stats() {
+    use crate::sync::stepper::{FaultKind, StepperStats, STATS_FAULT_HISTORY};
+
+    /// Reports an overload once the actuator passes 1.0 rad
+    struct OverloadSensor;
+
+    impl Interruptor for OverloadSensor {
+        fn dir(&self) -> Option<Direction> {
+            Some(Direction::CW)
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            (ctx.pos > PositionRad(1.0)).then_some(InterruptReason::Overload)
+        }
+    }
+
+    let mut stepper = Stepper::simulated();
+    let step_count = |dist : Radians, step : Radians| (dist / step).round() as u64;
+    let step = stepper.step_dist();
+
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+
+    assert_eq!(stepper.stats().moves, 2);
+    assert_eq!(stepper.stats().steps, 2 * step_count(Radians(0.5), step));
+    assert!(stepper.stats().run_time > 0.0);
+    assert!(stepper.stats().faults.is_empty());
+
+    // Overloads are counted as interrupts and recorded as faults
+    stepper.add_interruptor(Box::new(OverloadSensor));
+    stepper.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+
+    let stats = stepper.stats().clone();
+    assert_eq!(stats.interrupts, 1);
+    assert_eq!(stats.faults.len(), 1);
+    assert_eq!(stats.faults[0].kind, FaultKind::Interrupted(InterruptReason::Overload));
+    assert!((stats.faults[0].pos - PositionRad(1.0)).abs() < Radians(0.05));
+    assert!((stats.operating_time().0 as f64 - stats.run_time).abs() < 1e-6);
+
+    // The statistics survive a restart of the machine
+    let stored = serde_json::to_string(&stats).unwrap();
+
+    let mut stepper = Stepper::simulated();
+    stepper.restore_stats(serde_json::from_str::<StepperStats>(&stored).unwrap());
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+
+    assert_eq!(stepper.stats().moves, stats.moves + 1);
+    assert_eq!(stepper.stats().steps, stats.steps + step_count(Radians(0.5), step));
+    assert_eq!(stepper.stats().faults, stats.faults);
+
+    // The fault history is limited
+    let mut stats = StepperStats::new();
+
+    for _ in 0 .. (STATS_FAULT_HISTORY + 5) {
+        stats.push_fault(crate::sync::stepper::FaultRecord { time: Seconds::ZERO, pos: PositionRad::ZERO, kind: FaultKind::MovementFailed });
+    }
+
+    assert_eq!(stats.faults.len(), STATS_FAULT_HISTORY);
+
+    stepper.reset_stats();
+    assert_eq!(stepper.stats(), &StepperStats::new());
+}
+
+#[test]
+fn stepper_interpolated() {
+    let mut stepper_a = Stepper::default();
+    let mut stepper_b = Stepper::default();
+
+    let time = stepper_a.ptp_time_for_distance(PositionRad::ZERO, PositionRad(2.0));
+
+    let inst = Instant::now();
+    drive_rel_interpolated(&mut [ &mut stepper_a, &mut stepper_b ], &[ Radians(2.0), Radians(-1.0) ], Factor::MAX).unwrap();
+    let elapsed = inst.elapsed().as_secs_f32();
+
+    assert!((stepper_a.pos() - PositionRad(2.0)).abs() <= stepper_a.step_dist());
+    assert!((stepper_b.pos() - PositionRad(-1.0)).abs() <= stepper_b.step_dist());
+    assert!(!stepper_a.state().moving() && !stepper_b.state().moving());
+
+    // Both movements run in parallel, so the whole movement takes as long as the longest one 
+    assert!((elapsed - time.0).abs() < time.0 * PARAM_TIME_ACCURACY, "Elapsed: {}, Expected: {}", elapsed, time);
+}
+
+#[test]
+fn stepper_interpolated_start_error() {
+    let mut stepper_a = Stepper::simulated();
+    let mut stepper_b = Stepper::simulated();
+
+    // The second axis fails to start, the first one must be finished again
+    assert!(matches!(
+        drive_rel_interpolated(&mut [ &mut stepper_a, &mut stepper_b ], &[ Radians(1.0), Radians(f32::NAN) ], Factor::MAX),
+        Err(ActuatorError::InvaldRelativeDistance(_))
+    ));
+    assert!(!stepper_a.state().moving() && !stepper_b.state().moving());
+    assert_eq!(stepper_a.pos(), PositionRad::ZERO);
+
+    stepper_a.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((stepper_a.pos() - PositionRad(1.0)).abs() <= stepper_a.step_dist());
+}
+
+#[test]
+fn stepper_ptp_coordinated() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::sync::SyncActuatorGroup;
+
+    /// Records the time of the last step of an axis
+    struct LastStep(Arc<Mutex<Option<Instant>>>);
+
+    impl Interruptor for LastStep {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, _ctx : &InterruptContext) -> Option<InterruptReason> {
+            *self.0.lock().unwrap() = Some(Instant::now());
+            None
+        }
+    }
+
+    let last_step = Arc::new(Mutex::new(None));
+
+    let mut group = [ Stepper::default(), Stepper::default() ];
+    group[1].add_interruptor(Box::new(LastStep(last_step.clone())));
+
+    let inst = Instant::now();
+    group.drive_ptp_coordinated([ PositionRad(2.0), PositionRad(-0.5) ], Factor::MAX).unwrap();
+    let elapsed = inst.elapsed().as_secs_f32();
+
+    let pos = group.pos();
+    assert!((pos[0] - PositionRad(2.0)).abs() <= group[0].step_dist());
+    assert!((pos[1] - PositionRad(-0.5)).abs() <= group[1].step_dist());
+
+    // The shorter movement is slowed down, so both axes arrive at the same time
+    let arrival = last_step.lock().unwrap().unwrap().duration_since(inst).as_secs_f32();
+    assert!(arrival > elapsed * 0.6, "Arrival: {}, Elapsed: {}", arrival, elapsed);
+}
+
+#[test]
+fn stepper_node_stream() {
+    let mut builder = StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    let nodes = builder.render_profile(Radians(2.0), Factor::MAX).unwrap();
+    let count = nodes.len();
+
+    let (mut producer, mut consumer) = node_stream(16, 4, 12);
+
+    let handle = std::thread::spawn(move || {
+        let mut nodes = nodes.into_iter();
+        let mut pushed = 0;
+
+        while nodes.len() > 0 {
+            pushed += producer.fill(&mut nodes);
+
+            while !producer.needs_refill() && (nodes.len() > 0) {
+                std::thread::yield_now();
+            }
+        }
+
+        producer.finish();
+        pushed
+    });
+
+    consumer.emit(&mut SimulatedController::new()).unwrap();
+
+    assert_eq!(handle.join().unwrap(), count);
+    assert!(consumer.is_finished());
+    assert_eq!(consumer.metrics().overflows, 0);
+}
+
+#[test]
+fn stepper_jog_presets() {
+    let mut stepper = Stepper::default();
+    stepper.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(1.0)));
+
+    // Fine increments
+    stepper.jog(JogPreset::Fine(Radians(0.5)), Direction::CW).unwrap();
+    assert!((stepper.pos() - PositionRad(0.5)).abs() <= stepper.step_dist());
+
+    assert!(matches!(
+        stepper.jog(JogPreset::Fine(Radians(1.0)), Direction::CW), 
+        Err(ActuatorError::StopDistanceExceedsLimit(_, _))
+    ));
+
+    // The stop distance of this preset is 0.5 rad, which does not fit anymore
+    let coarse : JogPreset = JogPreset::Coarse(RadPerSecond(10.0), RadPerSecond2(100.0));
+    assert!((coarse.stop_distance() - Radians(0.5)).abs() < Radians(0.001));
+    assert!(matches!(stepper.jog(coarse, Direction::CW), Err(ActuatorError::StopDistanceExceedsLimit(_, _))));
+
+    // Continuous jog until the reduced limit has been reached
+    stepper.jog(JogPreset::Rapid(RadPerSecond(5.0), RadPerSecond2(1000.0)), Direction::CCW).unwrap();
+    assert!(stepper.pos() >= (PositionRad(-1.0) - stepper.step_dist()));
+    assert_eq!(stepper.limit_min(), Some(PositionRad(-1.0)));
+    assert_eq!(stepper.acceleration_max(), None);
+}
+
+#[test]
+fn stepper_jog_held() {
+    use embedded_hal::delay::DelayNs;
+    use crate::math::kin;
+    use crate::sync::{HeldJog, HeldJogPhase, JogKey};
+
+    /// Releases the key once the given amount of time has been waited
+    struct ReleaseAfter(JogKey, Seconds);
+
+    impl DelayNs for ReleaseAfter {
+        fn delay_ns(&mut self, ns : u32) {
+            self.1 -= Seconds(ns as f32 / 1e9);
+
+            if self.1 <= Seconds::ZERO {
+                self.0.release();
+            }
+        }
+    }
+
+    /// Releases the key once the actuator passes the given position
+    struct ReleaseAt(JogKey, PositionRad);
+
+    impl Interruptor for ReleaseAt {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            if ctx.pos > self.1 {
+                self.0.release();
+            }
+
+            None
+        }
+    }
+
+    let params = HeldJog {
+        increment: Radians(0.1),
+        repeat_delay: Seconds(0.3),
+        repeats: 4,
+        repeat_interval: Seconds(0.2),
+        velocity: RadPerSecond(0.5),
+        acceleration: RadPerSecond2(10.0)
+    };
+
+    let key = JogKey::new();
+    let mut stepper = Stepper::simulated();
+
+    assert!(matches!(
+        stepper.jog_held(&key, &params, Direction::CW, &mut ReleaseAfter(key.clone(), Seconds::ZERO)), 
+        Err(ActuatorError::InvalidConfig(_))
+    ));
+
+    stepper.add_interruptor(key.interruptor());
+
+    // A tap moves a single increment
+    assert_eq!(stepper.jog_held(&key, &params, Direction::CW, &mut ReleaseAfter(key.clone(), Seconds::ZERO)).unwrap(), HeldJogPhase::Increment);
+    assert!((stepper.pos() - PositionRad(0.1)).abs() <= stepper.step_dist());
+
+    // Released during the second pause, after the repeat delay of 0.3 s and the first pause of 0.16 s
+    key.press();
+    assert_eq!(stepper.jog_held(&key, &params, Direction::CW, &mut ReleaseAfter(key.clone(), Seconds(0.5))).unwrap(), HeldJogPhase::Repeat(2));
+    assert!((stepper.pos() - PositionRad(0.4)).abs() <= stepper.step_dist());
+
+    // Held into the continuous movement, decelerating after the release
+    key.press();
+    stepper.add_interruptor(Box::new(ReleaseAt(key.clone(), PositionRad(1.0))));
+
+    assert_eq!(stepper.jog_held(&key, &params, Direction::CW, &mut ReleaseAfter(key.clone(), Seconds(10.0))).unwrap(), HeldJogPhase::Continuous);
+    assert!(stepper.pos() > PositionRad(1.0));
+    assert!(stepper.pos() < PositionRad(1.0) + kin::stop_distance::<Rotary>(params.velocity, params.acceleration) + Radians(0.1));
+    assert_eq!(stepper.acceleration_max(), None);
+}
+
+#[test]
+fn stepper_heartbeat() {
+    let mut stepper = Stepper::default();
+    stepper.set_heartbeat_timeout(Some(Seconds(0.05)));
+
+    // No heartbeat is sent, the motor has to stop on its own
+    assert!(matches!(stepper.drive_factor(Factor::HALF, Direction::CW), Err(ActuatorError::HeartbeatLost)));
+    assert!(stepper.heartbeat_lost());
+    assert!(!stepper.state().moving());
+
+    // Continuous movements are refused while the loss is latched, fixed distances are still allowed
+    assert!(matches!(stepper.drive_speed(RadPerSecond(1.0)), Err(ActuatorError::HeartbeatLost)));
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+
+    stepper.reset_heartbeat_lost();
+    assert!(!stepper.heartbeat_lost());
+}
+
+#[test]
+fn stepper_segment_queue() {
+    use crate::sync::{SegmentEvent, SegmentQueue};
+
+    /// Virtual end switch at 1.0 rad
+    struct EndSwitch;
+
+    impl Interruptor for EndSwitch {
+        fn dir(&self) -> Option<Direction> {
+            Some(Direction::CW)
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            (ctx.pos > PositionRad(1.0)).then_some(InterruptReason::EndReached)
+        }
+    }
+
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(Box::new(EndSwitch));
+
+    let mut queue = SegmentQueue::new();
+    queue.push(Radians(0.5), Factor::MAX, 10_u32);
+    queue.push(Radians(0.2), Factor::MAX, 11);
+    queue.push(Radians(1.0), Factor::MAX, 12);     // Runs into the end switch
+    queue.push(Radians(-0.5), Factor::MAX, 13);
+
+    let mut events = Vec::new();
+    queue.run_all(&mut stepper, |event| events.push(event));
+
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], SegmentEvent::Completed(10)));
+    assert!(matches!(events[1], SegmentEvent::Completed(11)));
+    assert!(matches!(events[2], SegmentEvent::Interrupted(12, InterruptReason::EndReached)));
+
+    // The remaining segment stays in the queue
+    assert_eq!(queue.len(), 1);
+}
+
+#[test]
+fn stepper_chunked_move() {
+    use crate::sync::{ChunkedMove, ChunkOutcome};
+
+    /// Interrupts the movement once after passing 1.2 rad
+    struct Glitch(bool);
+
+    impl Interruptor for Glitch {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            if !self.0 && (ctx.pos > PositionRad(1.2)) {
+                self.0 = true;
+                return Some(InterruptReason::Error);
+            }
+
+            None
+        }
+    }
+
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(Box::new(Glitch(false)));
+    let tolerance = stepper.step_dist() * 1.5;
+
+    let mut chunked = ChunkedMove::new(Radians(2.0), Radians(0.5), Factor::MAX);
+    assert_eq!(chunked.chunks(), 4);
+
+    // The third chunk is interrupted, the progress stays at the last checkpoint
+    let mut checkpoints = Vec::new();
+    assert!(matches!(chunked.run(&mut stepper, |progress| { checkpoints.push(progress.checkpoint); true }), 
+        ChunkOutcome::Interrupted(InterruptReason::Error)));
+    assert_eq!(checkpoints, [ 1, 2 ]);
+    assert_eq!(chunked.progress().done, Radians(1.0));
+
+    // Pausing after the next checkpoint
+    assert!(matches!(chunked.run(&mut stepper, |_| false), ChunkOutcome::Paused));
+    assert!((stepper.pos() - PositionRad(1.5)).abs() < tolerance);
+    assert_eq!(chunked.progress().checkpoint, 3);
+
+    // Resuming completes the movement at its original target
+    assert!(matches!(chunked.run(&mut stepper, |_| true), ChunkOutcome::Completed));
+    assert!(chunked.is_done());
+    assert!((stepper.pos() - PositionRad(2.0)).abs() < tolerance);
+}
+
+#[test]
+fn stepper_rounding_policy() {
+    let mut stepper = Stepper::default();
+    let sub_step = stepper.step_dist() * 0.4;
+
+    // Sub-step movements are lost when rounding
+    for _ in 0 .. 10 {
+        stepper.drive_rel_blocking(sub_step, Factor::MAX).unwrap();
+    }
+    assert_eq!(stepper.pos(), PositionRad::ZERO);
+
+    // The residue is carried over when accumulating
+    stepper.set_rounding_policy(RoundingPolicy::Accumulate);
+    for _ in 0 .. 10 {
+        stepper.drive_rel_blocking(sub_step, Factor::MAX).unwrap();
+    }
+    assert!((stepper.pos() - PositionRad::ZERO - stepper.step_dist() * 4.0).abs() < stepper.step_dist() * 0.01);
+
+    // Floor only drives full steps
+    stepper.set_rounding_policy(RoundingPolicy::Floor);
+    stepper.drive_rel_blocking(stepper.step_dist() * 1.9, Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad::ZERO - stepper.step_dist() * 5.0).abs() < stepper.step_dist() * 0.01);
+}
+
+#[test]
+fn stepper_min_move() {
+    let mut stepper = Stepper::default();
+    let step = stepper.step_dist();
+
+    // Sub-step movements are skipped
+    assert_eq!(stepper.drive_rel_checked(step * 0.2, Factor::MAX).unwrap(), MoveOutcome::Skipped);
+
+    // Small movements are coalesced until they exceed the minimum
+    stepper.set_min_move(Some(step * 3.0));
+    assert_eq!(stepper.drive_rel_checked(step * 2.0, Factor::MAX).unwrap(), MoveOutcome::Coalesced(step * 2.0));
+    assert_eq!(stepper.pos(), PositionRad::ZERO);
+
+    assert_eq!(stepper.drive_rel_checked(step * 2.0, Factor::MAX).unwrap(), MoveOutcome::Executed);
+    assert!((stepper.pos() - PositionRad::ZERO - step * 4.0).abs() < step * 0.01);
+    assert_eq!(stepper.pending_dist(), Radians::ZERO);
+}
+
+#[test]
+fn stepper_motion_mode() {
+    let transitions = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let transitions_listener = transitions.clone();
+
+    let mut stepper = Stepper::default();
+    stepper.set_mode_listener(Some(Box::new(move |prev, mode| transitions_listener.lock().unwrap().push((prev, mode)))));
+    assert_eq!(stepper.state().motion_mode(), MotionMode::Inactive);
+
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert_eq!(stepper.state().motion_mode(), MotionMode::Holding);
+
+    stepper.set_hold_policy(HoldPolicy::Disable);
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert_eq!(stepper.state().motion_mode(), MotionMode::Inactive);
+
+    assert_eq!(*transitions.lock().unwrap(), vec![
+        (MotionMode::Inactive, MotionMode::FixedDistance),
+        (MotionMode::FixedDistance, MotionMode::Holding),
+        (MotionMode::Holding, MotionMode::FixedDistance),
+        (MotionMode::FixedDistance, MotionMode::Inactive)
+    ]);
+}
+
+#[test]
+fn stepper_interrupt_context() {
+    use std::sync::{Arc, Mutex};
+
+    /// Stores every context it is checked with, interrupts once the actuator passes -0.5 rad
+    struct ContextRecorder(Arc<Mutex<Vec<InterruptContext>>>);
+
+    impl Interruptor for ContextRecorder {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            self.0.lock().unwrap().push(*ctx);
+            (ctx.pos < PositionRad(-0.5)).then_some(InterruptReason::EndReached)
+        }
+    }
+
+    let contexts = Arc::new(Mutex::new(Vec::new()));
+
+    let mut stepper = Stepper::default();
+    stepper.add_interruptor(Box::new(ContextRecorder(contexts.clone())));
+    stepper.drive_rel_blocking(Radians(-1.0), Factor::HALF).unwrap();
+
+    assert_eq!(stepper.intr_reason(), Some(InterruptReason::EndReached));
+    assert!(stepper.pos() < PositionRad(-0.5));
+    assert!(stepper.pos() > PositionRad(-1.0));
+
+    let contexts = contexts.lock().unwrap();
+    assert!(!contexts.is_empty());
+
+    for ctx in contexts.iter() {
+        assert_eq!(ctx.direction, Direction::CCW);
+        assert_eq!(ctx.mode, MotionMode::FixedDistance);
+        assert!(ctx.velocity < RadPerSecond::ZERO);
+    }
+}
+
+#[test]
+fn stepper_zhop_plotter() {
+    use embedded_hal::delay::DelayNs;
+    use crate::sync::SegmentEvent;
+    use crate::sync::plotter::{AxisLift, ZHopPlotter};
+
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _ns : u32) { }
+    }
+
+    let mut axis_x = Stepper::simulated();
+    let mut axis_y = Stepper::simulated();
+
+    let lift = AxisLift { actuator: Stepper::simulated(), pos_up: PositionRad(1.0), pos_down: PositionRad::ZERO, speed: Factor::MAX };
+    let mut plotter = ZHopPlotter::new(lift, NoDelay, Seconds(0.05));
+
+    plotter.travel([ Radians(2.0), Radians(1.0) ], Factor::MAX, 0);
+    plotter.draw([ Radians(1.0), Radians(-1.0) ], Factor::HALF, 1);
+    plotter.draw([ Radians(-1.0), Radians(0.5) ], Factor::HALF, 2);
+    assert_eq!(plotter.len(), 3);
+
+    // The tool has to be lifted for the first travel move
+    plotter.lift.actuator.drive_abs_blocking(PositionRad(1.0), Factor::MAX).unwrap();
+
+    let mut events = Vec::new();
+    plotter.run_all([ &mut axis_x, &mut axis_y ], |event| events.push(event)).unwrap();
+
+    assert!(matches!(events.as_slice(), [ SegmentEvent::Completed(0), SegmentEvent::Completed(1), SegmentEvent::Completed(2) ]));
+    assert!(plotter.is_empty());
+    assert_eq!(plotter.path().index(), 3);
+
+    // Tool is lifted again at the end of the path
+    assert!(!plotter.is_lowered());
+    assert!((plotter.lift.actuator.pos() - PositionRad(1.0)).abs() <= plotter.lift.actuator.step_dist());
+
+    assert!((axis_x.pos() - PositionRad(2.0)).abs() <= axis_x.step_dist());
+    assert!((axis_y.pos() - PositionRad(0.5)).abs() <= axis_y.step_dist());
+}
+
+#[test]
+fn stepper_predicted_pos() {
+    use std::sync::OnceLock;
+
+    static START : OnceLock<Instant> = OnceLock::new();
+
+    fn clock() -> Seconds {
+        Seconds(START.get_or_init(Instant::now).elapsed().as_secs_f32())
+    }
+
+    let mut stepper = Stepper::default();
+    let state = stepper.clone_state();
+
+    // Without a clock, the current position is returned
+    assert_eq!(state.predicted_pos(clock()), stepper.pos());
+
+    stepper.set_clock(Some(clock));
+    let step_angle = stepper.step_dist();
+
+    let handle = std::thread::spawn(move || {
+        stepper.drive_rel_blocking(Radians(2.0), Factor::new(0.2)).unwrap();
+        stepper
+    });
+
+    let mut interpolated = 0;
+
+    while !handle.is_finished() {
+        let pos = state.pos();
+        let predicted = state.predicted_pos(clock());
+
+        // Predictions never exceed a single step in movement direction (a step may happen between the two reads)
+        assert!(predicted >= pos, "Predicted: {}, Position: {}", predicted, pos);
+        assert!(predicted - pos <= step_angle * 2.0, "Predicted: {}, Position: {}", predicted, pos);
+
+        if (predicted - pos) > Radians::ZERO {
+            interpolated += 1;
+        }
+    }
+
+    let stepper = handle.join().unwrap();
+    assert!(interpolated > 0);
+    assert_eq!(state.predicted_pos(clock()), stepper.pos());
+}
+
+#[test]
+fn stepper_sim_disturbances() {
+    use crate::sync::stepper::{Disturbance, DisturbanceProfile, SimulatedController};
+
+    let step_angle = Stepper::simulated().step_dist();
+    let simulated = |profile : DisturbanceProfile| {
+        let ctrl = SimulatedController::new().with_disturbances(profile);
+        let axis = ctrl.axis();
+
+        (Stepper::new_advanced(ctrl, StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap(), axis)
+    };
+
+    // Undisturbed axes follow the commanded position
+    let (mut stepper, axis) = simulated(DisturbanceProfile::new(step_angle, NewtonMeters(0.4)));
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert!((axis.pos() - stepper.pos()).abs() < Radians(1e-3));
+    assert_eq!(axis.lost_steps(), 0);
+
+    // Jams silently lose all steps beyond them
+    let (mut stepper, axis) = simulated(DisturbanceProfile::new(step_angle, NewtonMeters(0.4))
+        .with(Disturbance::Jam { pos: PositionRad(1.0) }));
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    assert!((axis.pos() - PositionRad(1.0)).abs() <= step_angle);
+    assert!(axis.lost_steps() > 0);
+
+    // The axis can still be moved away from the jam
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+    assert!((axis.pos() - PositionRad(0.5)).abs() <= step_angle);
+
+    // Drivers with stall detection report lost steps
+    let mut profile = DisturbanceProfile::new(step_angle, NewtonMeters(0.4))
+        .with(Disturbance::FrictionStep { at: Seconds::ZERO, torque: NewtonMeters(0.3) })
+        .with(Disturbance::TorqueRipple { amplitude: NewtonMeters(0.2), period: Radians(1.0) });
+    profile.report_stall = true;
+
+    let (mut stepper, axis) = simulated(profile);
+    assert!(matches!(stepper.drive_rel_blocking(Radians(2.0), Factor::MAX), Err(ActuatorError::Overload)));
+    assert_eq!(axis.lost_steps(), 1);
+    // Friction and ripple exceed the torque available once the ripple reaches half its amplitude
+    assert!((axis.pos() - PositionRad(1.0 / 12.0)).abs() <= step_angle, "Position: {}", axis.pos());
+}
+
+#[test]
+fn stepper_precise_pos() {
+    use crate::math::reduced;
+
+    let mut stepper = Stepper::simulated();
+    stepper.overwrite_abs_pos(PositionRad(2000.0));
+    stepper.drive_rel_blocking(Radians(100.0), Factor::MAX).unwrap();
+
+    assert_eq!(stepper.pos(), PositionRad(reduced(stepper.pos_precise())));
+
+    // Far away from zero, the `f32` bookkeeping accumulates errors of several steps
+    #[cfg(feature = "f64")]
+    {
+        use crate::math::precise;
+        assert!((stepper.pos_precise() - 2100.0).abs() <= precise(stepper.step_dist().0), "Position: {}", stepper.pos_precise());
+    }
+}
+
+#[test]
+fn stepper_premove_checks() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let door_closed = Arc::new(AtomicBool::new(false));
+    let door = door_closed.clone();
+
+    let mut stepper_a = Stepper::simulated();
+    let mut stepper_b = Stepper::simulated();
+
+    stepper_a.add_premove_check("door closed", Box::new(move || 
+        if door.load(Ordering::Relaxed) { Ok(()) } else { Err("door is open") }
+    ));
+
+    assert!(matches!(
+        stepper_a.drive_rel_blocking(Radians(1.0), Factor::MAX), 
+        Err(ActuatorError::PreMoveCheckFailed("door closed", "door is open"))
+    ));
+    assert_eq!(stepper_a.pos(), PositionRad::ZERO);
+
+    // The failing check of one axis keeps all axes of the group still
+    assert!(drive_rel_interpolated(&mut [ &mut stepper_b, &mut stepper_a ], &[ Radians(1.0), Radians(1.0) ], Factor::MAX).is_err());
+    assert_eq!(stepper_b.pos(), PositionRad::ZERO);
+
+    door_closed.store(true, Ordering::Relaxed);
+    stepper_a.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((stepper_a.pos() - PositionRad(1.0)).abs() <= stepper_a.step_dist());
+
+    assert!(stepper_a.remove_premove_check("door closed"));
+    assert!(stepper_a.premove_checks().is_empty());
+}
+
+#[test]
+fn stepper_feed_hold() {
+    use crate::sync::{SegmentEvent, SegmentQueue};
+
+    let mut stepper = Stepper::default();
+    let state = stepper.clone_state();
+
+    let mut queue = SegmentQueue::new();
+    queue.push(Radians(1.0), Factor::new(0.5), 0_u32);
+    queue.push(Radians(0.5), Factor::MAX, 1);
+
+    let handle = std::thread::spawn(move || {
+        let mut events = Vec::new();
+        queue.run_all(&mut stepper, |event| events.push(event));
+        (stepper, queue, events)
+    });
+
+    while state.pos() < PositionRad(0.5) {
+        std::thread::yield_now();
+    }
+
+    state.halt();
+    let (mut stepper, mut queue, events) = handle.join().unwrap();
+
+    // The motor decelerates to a stop, the held segment stays in the queue
+    let stop = queue.stop_point().unwrap();
+    assert!(matches!(events.as_slice(), [ SegmentEvent::Held(point) ] if *point == stop));
+    assert_eq!(events[0].tag(), None);
+    assert_eq!(stop.index, 0);
+    assert!((stop.dist.0 - stepper.pos().0).abs() < 1e-4, "Stop: {:?}, Position: {}", stop, stepper.pos());
+    assert!((stop.dist > Radians(0.5)) && (stop.dist < Radians(1.0)), "Stop: {:?}", stop);
+    assert_eq!(queue.len(), 2);
+
+    // Rewind by an overlap and resume
+    let rewound = queue.rewind(&mut stepper, Radians(0.2)).unwrap();
+    assert!((rewound - Radians(0.2)).abs() <= stepper.step_dist());
+    assert!((queue.stop_point().unwrap().dist - (stop.dist - rewound)).abs() < Radians(1e-4));
+
+    let mut events = Vec::new();
+    queue.run_all(&mut stepper, |event| events.push(event));
+
+    assert!(matches!(events.as_slice(), [ SegmentEvent::Completed(0), SegmentEvent::Completed(1) ]));
+    assert!((stepper.pos() - PositionRad(1.5)).abs() <= stepper.step_dist());
+    assert_eq!(queue.stop_point(), None);
+    assert_eq!(queue.index(), 2);
+}
+
+#[test]
+fn stepper_time_scale() {
+    use crate::sync::TimeScale;
+
+    let scale = TimeScale::new(Factor::MAX);
+
+    let mut stepper = Stepper::default();
+    stepper.set_time_scale(Some(scale.clone()));
+
+    let inst = Instant::now();
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+    let elapsed_full = inst.elapsed().as_secs_f32();
+
+    scale.set(Factor::HALF);
+
+    let inst = Instant::now();
+    stepper.drive_rel_blocking(Radians(-2.0), Factor::MAX).unwrap();
+    let elapsed_half = inst.elapsed().as_secs_f32();
+
+    // Same path, twice the time
+    assert!((stepper.pos() - PositionRad::ZERO).abs() <= stepper.step_dist());
+    assert!(((elapsed_half / elapsed_full) - 2.0).abs() < 0.3, "Full: {}, Half: {}", elapsed_full, elapsed_half);
+
+    let time = stepper.ptp_time_for_distance(PositionRad::ZERO, PositionRad(1.0));
+
+    // The scale is changed while the motor is moving
+    let handle = std::thread::spawn(move || {
+        stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+        stepper
+    });
+
+    std::thread::sleep(std::time::Duration::from_secs_f32(time.0 / 2.0));
+    scale.set(Factor::MAX);
+
+    let stepper = handle.join().unwrap();
+    assert!((stepper.pos() - PositionRad(1.0)).abs() <= stepper.step_dist());
+}
+
+#[test]
+fn stepper_group_stop() {
+    use crate::sync::stepper::{GroupStopPolicy, drive_rel_interpolated_with};
+
+    /// Virtual end switch at 0.5 rad
+    struct EndSwitch;
+
+    impl Interruptor for EndSwitch {
+        fn dir(&self) -> Option<Direction> {
+            Some(Direction::CW)
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            (ctx.pos > PositionRad(0.5)).then_some(InterruptReason::EndReached)
+        }
+    }
+
+    // Independent axes, the second axis completes its movement
+    let mut stepper_a = Stepper::simulated();
+    let mut stepper_b = Stepper::simulated();
+    stepper_b.add_interruptor(Box::new(EndSwitch));
+
+    drive_rel_interpolated_with(&mut [ &mut stepper_a, &mut stepper_b ], &[ Radians(-2.0), Radians(2.0) ], Factor::MAX, 
+        GroupStopPolicy::Independent).unwrap();
+
+    assert!((stepper_a.pos() - PositionRad(-2.0)).abs() <= stepper_a.step_dist());
+    assert!(stepper_b.pos() < PositionRad(1.0));
+
+    // Stopping the whole group
+    let mut stepper_a = Stepper::simulated();
+    let mut stepper_b = Stepper::simulated();
+    stepper_b.add_interruptor(Box::new(EndSwitch));
+
+    let result = drive_rel_interpolated_with(&mut [ &mut stepper_a, &mut stepper_b ], &[ Radians(-2.0), Radians(2.0) ], Factor::MAX, 
+        GroupStopPolicy::StopAll);
+
+    assert!(matches!(result, Err(ActuatorError::AxisInterrupted(1, InterruptReason::EndReached))));
+    assert!(stepper_a.pos() > PositionRad(-1.0), "Position: {}", stepper_a.pos());
+    assert!(!stepper_a.state().moving() && !stepper_b.state().moving());
+}
+
+#[test]
+fn stepper_step_batch() {
+    use std::sync::{Arc, Mutex};
+
+    /// Controller recording the size of each batch
+    struct BatchController {
+        batches : Arc<Mutex<Vec<usize>>>,
+        ctrl : SimulatedController
+    }
+
+    impl StepperController for BatchController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            self.step_batch(&[ time ])
+        }
+
+        fn step_batch(&mut self, intervals : &[Seconds]) -> Result<(), ActuatorError> {
+            self.batches.lock().unwrap().push(intervals.len());
+            intervals.iter().try_for_each(|interval| self.ctrl.step(*interval))
+        }
+
+        fn direction(&self) -> Direction {
+            self.ctrl.direction()
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.ctrl.set_dir(dir)
+        }
+    }
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let mut stepper = StepperMotor::<StartStopBuilder, _>::new_advanced(
+        BatchController { batches: batches.clone(), ctrl: SimulatedController::new() }, 
+        StepperConst::MOT_17HE15_1504S, 
+        StepperConfig::VOLT12_NO_OVERLOAD
+    ).unwrap();
+
+    stepper.set_step_batch(16);
+    stepper.drive_rel_blocking(Radians(2.0), Factor::MAX).unwrap();
+
+    let batches = batches.lock().unwrap();
+    let steps : usize = batches.iter().sum();
+
+    // All steps are generated in full batches, except for the last one
+    assert!((stepper.pos() - PositionRad(2.0)).abs() <= stepper.step_dist());
+    assert_eq!(steps, (2.0 / stepper.step_dist().0).round() as usize);
+    assert!(batches[.. batches.len() - 1].iter().all(|size| *size == 16), "Batches: {:?}", batches);
+}
+
+#[test]
+fn stepper_stop_before() {
+    let mut stepper = Stepper::simulated();
+
+    // The stop distance is unknown without any limits
+    assert!(matches!(stepper.stop_before(Some(PositionRad(2.0))), Err(ActuatorError::InvalidAcceleration(_))));
+
+    stepper.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    stepper.set_deceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+    assert_eq!(stepper.stop_distance(), Some(Radians::ZERO));
+
+    // Approach move without a sensor triggering, the motor has to stop on its own
+    stepper.stop_before(Some(PositionRad(2.0))).unwrap();
+    stepper.drive_factor(Factor::MAX, Direction::CW).unwrap();
+
+    assert!(stepper.pos() <= PositionRad(2.0));
+    assert!(stepper.pos() >= (PositionRad(2.0) - stepper.step_dist() * 4.0));
+    assert_eq!(stepper.stop_before_pos(), None);
+    assert_eq!(stepper.velocity(), RadPerSecond::ZERO);
+
+    // Targets behind the motor are ignored
+    stepper.stop_before(Some(PositionRad(3.0))).unwrap();
+    stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(1.5)).abs() <= stepper.step_dist() * 4.0);
+}
+
+#[test]
+fn stepper_soft_limits() {
+    let mut stepper = TrapezoidalStepper::simulated();
+    stepper.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    stepper.set_deceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+    stepper.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(2.0)));
+
+    // By default the motor is stopped behind the limit
+    assert_eq!(stepper.limit_behavior(), LimitBehavior::Stop);
+    stepper.drive_factor(Factor::MAX, Direction::CW).unwrap();
+    assert!(stepper.pos() > PositionRad(2.5));
+
+    stepper.overwrite_abs_pos(PositionRad::ZERO);
+    stepper.set_limit_behavior(LimitBehavior::DecelerateAndClamp).unwrap();
+
+    // Fixed distances are truncated at the limits
+    stepper.drive_rel_blocking(Radians(5.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(2.0)).abs() < stepper.step_dist());
+
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(2.0)).abs() < stepper.step_dist());
+
+    stepper.drive_abs_blocking(PositionRad(-3.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(-1.0)).abs() < stepper.step_dist());
+
+    // Continuous movements decelerate to stand still at the limit
+    stepper.drive_factor(Factor::MAX, Direction::CW).unwrap();
+    assert!(stepper.pos() <= PositionRad(2.0));
+    assert!(stepper.pos() >= (PositionRad(2.0) - stepper.step_dist() * 4.0));
+    assert_eq!(stepper.velocity(), RadPerSecond::ZERO);
+
+    // Composed actuators forward the behavior
+    let gear = Gear::new(Stepper::simulated(), 0.5);
+    assert_eq!(gear.limit_behavior(), LimitBehavior::Stop);
+}
+
+#[test]
+fn stepper_approach() {
+    /// Records the velocity of every step
+    struct VelocityLog(std::sync::Arc<std::sync::Mutex<Vec<RadPerSecond>>>);
+
+    impl Interruptor for VelocityLog {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            self.0.lock().unwrap().push(ctx.velocity);
+            None
+        }
+    }
+
+    let mut stepper = Stepper::simulated();
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    assert!(matches!(
+        stepper.drive_rel_approach(Radians(4.0), Factor::MAX, Radians(0.5), Factor::new(0.1)), 
+        Err(ActuatorError::InvalidAcceleration(_))
+    ));
+
+    stepper.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+
+    assert!(matches!(
+        stepper.drive_rel_approach(Radians(0.5), Factor::MAX, Radians(1.0), Factor::new(0.1)), 
+        Err(ActuatorError::InvaldRelativeDistance(_))
+    ));
+
+    stepper.add_interruptor(Box::new(VelocityLog(log.clone())));
+    stepper.drive_rel_approach(Radians(4.0), Factor::MAX, Radians(0.5), Factor::new(0.1)).unwrap();
+    assert!((stepper.pos() - PositionRad(4.0)).abs() <= stepper.step_dist());
+
+    // The final segment is driven at the creep speed, the transition is blended without stopping
+    let log = log.lock().unwrap();
+    let creep_steps = (Radians(0.5) / stepper.step_dist()) as usize;
+    let fastest = log.iter().fold(RadPerSecond::ZERO, |max, &vel| max.max(vel));
+
+    assert!(log[(log.len() - creep_steps) ..].iter().all(|&vel| vel < fastest * 0.2));
+
+    let transition = log.len() - creep_steps;
+    assert!(log[(transition - 10) .. transition].iter().all(|&vel| vel > fastest * 0.05));
+}
+
+#[test]
+fn stepper_approach_blended() {
+    let mut stepper = TrapezoidalStepper::simulated();
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    stepper.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    stepper.add_interruptor(Box::new(VelocityLog(log.clone())));
+
+    stepper.drive_rel_approach(Radians(-6.0), Factor::MAX, Radians(1.0), Factor::new(0.1)).unwrap();
+    assert!((stepper.pos() - PositionRad(-6.0)).abs() <= stepper.step_dist());
+
+    // The approach ramps down to the creep speed, the motor never stops in between
+    let log = log.lock().unwrap();
+    let creep_steps = (Radians(1.0) / stepper.step_dist()) as usize;
+    let transition = log.len() - creep_steps;
+
+    assert!(log[transition .. (log.len() - 10)].iter().all(|&vel| (vel.abs() - RadPerSecond(2.0)).abs() < RadPerSecond(0.2)));
+    assert!(log[(transition - 20) .. transition].iter().all(|&vel| vel.abs() >= RadPerSecond(1.8)));
+}
+
+#[test]
+fn stepper_group_config() {
+    use crate::sync::stepper::set_config_all;
+
+    let mut stepper_a = Stepper::simulated();
+    let mut stepper_b = Stepper::simulated();
+    let mut stepper_c = Stepper::simulated();
+
+    let boost = StepperConfig { drive_current: Some(1.0), ..StepperConfig::VOLT24_NO_OVERLOAD };
+    set_config_all(&mut [ &mut stepper_a, &mut stepper_b, &mut stepper_c ], &StepperConfig::VOLT24_NO_OVERLOAD, &[ (1, boost.clone()) ]).unwrap();
+
+    assert_eq!(stepper_a.config().voltage, 24.0);
+    assert_eq!(stepper_b.config().drive_current, Some(1.0));
+    assert_eq!(stepper_c.config().drive_current, None);
+
+    // All invalid configurations are reported, no axis is changed
+    let too_high = StepperConfig { drive_current: Some(10.0), ..StepperConfig::VOLT48_NO_OVERLOAD };
+    let errors = set_config_all(&mut [ &mut stepper_a, &mut stepper_b, &mut stepper_c ], &StepperConfig::new(0.0, None), 
+        &[ (1, StepperConfig::VOLT48_NO_OVERLOAD), (2, too_high), (5, StepperConfig::VOLT48_NO_OVERLOAD) ]).unwrap_err();
+
+    let indices : Vec<usize> = errors.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indices, [ 5, 0, 2 ]);
+    assert!(errors.iter().all(|(_, err)| matches!(err, ActuatorError::InvalidConfig(_))));
+    assert_eq!(stepper_b.config().drive_current, Some(1.0));
+    assert_eq!(stepper_b.config().voltage, 24.0);
+}
+
+#[test]
+fn stepper_snapshots() {
+    use crate::sync::stepper::ConfigurableStepper;
+
+    let mut stepper = Stepper::simulated();
+    stepper.set_config(StepperConfig::VOLT24_NO_OVERLOAD).unwrap();
+    stepper.apply_gen_force(NewtonMeters(0.05)).unwrap();
+    stepper.set_pos_limits(Some(PositionRad(-1.0)), Some(PositionRad(1.0)));
+
+    let snapshot = stepper.snapshot();
+
+    // The snapshot is owned and can be read by other threads while the motor moves
+    let monitor = std::thread::spawn(move || snapshot);
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    let snapshot = monitor.join().unwrap();
+
+    assert_eq!(snapshot.consts, stepper.consts_snapshot());
+    assert_eq!(snapshot.config.voltage, 24.0);
+    assert_eq!(snapshot.vars.force_load_gen, NewtonMeters(0.05));
+    assert_eq!(stepper.vars_snapshot().force_load_gen, snapshot.vars.force_load_gen);
+    assert_eq!(stepper.config_snapshot().voltage, snapshot.config.voltage);
+}
+
+#[test]
+fn stepper_settling() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::sync::stepper::SettlePolicy;
+
+    // Fixed settle time
+    let mut stepper = Stepper::default();
+    stepper.set_settle_policy(SettlePolicy::Time(Seconds(0.1)));
+
+    let inst = Instant::now();
+    stepper.drive_rel_blocking(Radians(0.1), Factor::MAX).unwrap();
+    assert!(inst.elapsed().as_secs_f32() >= 0.1);
+
+    // Encoder lagging behind for a few polls
+    let polls = Arc::new(AtomicUsize::new(0));
+    let polls_check = polls.clone();
+
+    let mut stepper = Stepper::simulated();
+    stepper.set_settle_policy(SettlePolicy::InPosition(Seconds(0.05)));
+    stepper.set_in_position(Some(Box::new(move |pos| {
+        let encoder = pos - Radians(0.1) / (polls_check.fetch_add(1, Ordering::Relaxed) as f32 + 1.0);
+        (encoder - pos).abs() < Radians(0.02)
+    })));
+
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert_eq!(polls.load(Ordering::Relaxed), 5);
+
+    // Never in position
+    stepper.set_in_position(Some(Box::new(|_| false)));
+    assert!(matches!(stepper.drive_rel_blocking(Radians(1.0), Factor::MAX), Err(ActuatorError::SettleTimeout(_))));
+}
+
+#[test]
+fn stepper_position_compare() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::sync::stepper::{CompareCondition, GroupStopPolicy, PositionCompare, drive_rel_interpolated_compare};
+
+    let mut stepper_x = Stepper::simulated();
+    let mut stepper_y = Stepper::simulated();
+    let step_dist = stepper_x.step_dist();
+
+    let triggers = Arc::new(Mutex::new(Vec::new()));
+    let triggers_action = triggers.clone();
+
+    // Measurement once X reaches 1 while Y is below -0.5
+    let mut compares = [
+        PositionCompare::new(Box::new(move |positions| triggers_action.lock().unwrap().push(positions.to_vec())))
+            .with(0, CompareCondition::Reached(PositionRad(1.0)))
+            .with(1, CompareCondition::AtMost(PositionRad(-0.5)))
+    ];
+
+    drive_rel_interpolated_compare(&mut [ &mut stepper_x, &mut stepper_y ], &[ Radians(2.0), Radians(-1.0) ], Factor::MAX, 
+        GroupStopPolicy::Independent, &mut compares).unwrap();
+
+    // Fired once, within the step satisfying both conditions
+    let positions = triggers.lock().unwrap()[0].clone();
+    assert_eq!(compares[0].fired(), 1);
+    assert!((positions[0] - PositionRad(1.0)).abs() <= step_dist);
+    assert!(positions[1] <= PositionRad(-0.5));
+
+    // Moving back, Y leaves the window before X reaches its position again
+    drive_rel_interpolated_compare(&mut [ &mut stepper_x, &mut stepper_y ], &[ Radians(-2.0), Radians(1.5) ], Factor::MAX, 
+        GroupStopPolicy::Independent, &mut compares).unwrap();
+
+    assert_eq!(compares[0].fired(), 1);
+
+    // Only Y is compared, the start position is taken from every movement
+    compares[0] = PositionCompare::new(Box::new(|_| { })).with(1, CompareCondition::Reached(PositionRad(-0.5)));
+
+    for rel_dist in [ Radians(-1.5), Radians(1.5) ] {
+        drive_rel_interpolated_compare(&mut [ &mut stepper_x, &mut stepper_y ], &[ Radians::ZERO, rel_dist ], Factor::MAX, 
+            GroupStopPolicy::Independent, &mut compares).unwrap();
+    }
+
+    assert_eq!(compares[0].fired(), 2);
+    assert_eq!(triggers.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn stepper_simulation_switch() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::sync::SimulationSwitch;
+
+    /// Controller counting every call that would touch a pin
+    struct IoController {
+        calls : Arc<AtomicUsize>,
+        ctrl : SimulatedController
+    }
+
+    impl StepperController for IoController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.ctrl.step(time)
+        }
+
+        fn direction(&self) -> Direction {
+            self.ctrl.direction()
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.ctrl.set_dir(dir)
+        }
+
+        fn set_enabled(&mut self, _enabled : bool) -> Result<(), ActuatorError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.ctrl.wait(time)
+        }
+    }
+
+    let switch = SimulationSwitch::with_delay(false, |time| spin_sleep::sleep(time.into()));
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let mut stepper = StepperMotor::<StartStopBuilder, _>::new_advanced(
+        IoController { calls: calls.clone(), ctrl: realtime_controller() }, 
+        StepperConst::MOT_17HE15_1504S, 
+        StepperConfig::VOLT12_NO_OVERLOAD
+    ).unwrap();
+    stepper.set_simulation_switch(Some(switch.clone()));
+    stepper.set_settle_policy(SettlePolicy::Time(Seconds(0.01)));
+    let state = stepper.clone_state();
+
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+    assert!(!state.simulated());
+
+    // Dry-run with the real timing, the controller is not touched at all
+    switch.set(true);
+
+    let calls_real = calls.load(Ordering::Relaxed);
+    let time = stepper.ptp_time_for_distance(PositionRad::ZERO, PositionRad(1.0)) + Seconds(0.01);
+    let inst = Instant::now();
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    let elapsed = inst.elapsed().as_secs_f32();
+
+    assert!(state.simulated());
+    assert_eq!(calls.load(Ordering::Relaxed), calls_real);
+    assert!(((elapsed / time.0) - 1.0).abs() < 0.3, "Elapsed: {}, Expected: {}", elapsed, time);
+    assert!((stepper.pos() - PositionRad(1.5)).abs() <= stepper.step_dist());
+    assert!((stepper.real_pos() - PositionRad(0.5)).abs() <= stepper.step_dist());
+
+    // The real position is restored with the next real movement
+    switch.set(false);
+    stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+
+    assert!(!state.simulated());
+    assert!((stepper.pos() - PositionRad(1.0)).abs() <= stepper.step_dist());
+}
+
+#[test]
+fn stepper_simulation_switch_after_failure() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::sync::SimulationSwitch;
+
+    /// Controller counting the real pulses, failing after the 50th step
+    struct FailingController {
+        pulses : Arc<AtomicUsize>,
+        ctrl : SimulatedController
+    }
+
+    impl StepperController for FailingController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            if self.pulses.load(Ordering::Relaxed) >= 50 {
+                return Err(ActuatorError::IOError);
+            }
+
+            self.pulses.fetch_add(1, Ordering::Relaxed);
+            self.ctrl.step(time)
+        }
+
+        fn direction(&self) -> Direction {
+            self.ctrl.direction()
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.ctrl.set_dir(dir)
+        }
+    }
+
+    let switch = SimulationSwitch::new(false);
+    let pulses = Arc::new(AtomicUsize::new(0));
+
+    let mut stepper = StepperMotor::<StartStopBuilder, _>::new_advanced(
+        FailingController { pulses: pulses.clone(), ctrl: SimulatedController::new() }, 
+        StepperConst::MOT_17HE15_1504S, 
+        StepperConfig::VOLT12_NO_OVERLOAD
+    ).unwrap();
+    stepper.set_simulation_switch(Some(switch.clone()));
+
+    assert!(matches!(stepper.drive_rel_blocking(Radians(10.0), Factor::MAX), Err(ActuatorError::IOError)));
+    assert_eq!(pulses.load(Ordering::Relaxed), 50);
+
+    // The switch is evaluated with the next movement, even though the last one failed
+    switch.set(true);
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+
+    assert!(stepper.state().simulated());
+    assert_eq!(pulses.load(Ordering::Relaxed), 50);
+}
+
+/// Records the velocity of every step
+struct VelocityLog(std::sync::Arc<std::sync::Mutex<Vec<RadPerSecond>>>);
+
+impl Interruptor for VelocityLog {
+    fn dir(&self) -> Option<Direction> {
+        None
+    }
+
+    fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+    fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+        self.0.lock().unwrap().push(ctx.velocity);
+        None
+    }
+}
+
+#[test]
+fn stepper_ramp_profile() {
+    let mut stepper = Stepper::simulated();
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    assert!(matches!(
+        stepper.drive_with_profile(&RampProfile::new(Radians(4.0), RadPerSecond(20.0), RadPerSecond2::ZERO)), 
+        Err(ActuatorError::InvalidAcceleration(_))
+    ));
+
+    stepper.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+
+    assert!(matches!(
+        stepper.drive_with_profile(&RampProfile::new(Radians(4.0), RadPerSecond(20.0), RadPerSecond2(100.0))), 
+        Err(ActuatorError::VelocityTooHigh(_, _))
+    ));
+
+    stepper.set_velocity_max(None).unwrap();
+    stepper.add_interruptor(Box::new(VelocityLog(log.clone())));
+
+    stepper.drive_with_profile(&RampProfile::new(-Radians(4.0), RadPerSecond(20.0), RadPerSecond2(100.0))).unwrap();
+    assert!((stepper.pos() - PositionRad(-4.0)).abs() <= stepper.step_dist());
+
+    // The profile is driven as given, cruising at its velocity in the middle and ramping down at the end
+    let log = log.lock().unwrap();
+    let middle = log[log.len() / 2];
+
+    assert!((middle + RadPerSecond(20.0)).abs() < RadPerSecond(0.5), "Velocity: {}", middle);
+    assert!(log.iter().all(|&vel| vel.abs() < RadPerSecond(20.5)));
+    assert!(log.last().unwrap().abs() < RadPerSecond(5.0));
+}
+
+#[test]
+fn stepper_trapezoidal() {
+    let mut stepper = TrapezoidalStepper::simulated();
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    stepper.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+    stepper.add_interruptor(Box::new(VelocityLog(log.clone())));
+
+    stepper.drive_rel_blocking(Radians(8.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(8.0)).abs() <= stepper.step_dist());
+
+    // Cruising with the user velocity, way above the start-stop velocity of the motor
+    {
+        let log = log.lock().unwrap();
+        let middle = log[log.len() / 2];
+
+        assert!((middle - RadPerSecond(20.0)).abs() < RadPerSecond(0.5), "Velocity: {}", middle);
+        assert!(log.last().unwrap().abs() < RadPerSecond(5.0));
+    }
+
+    stepper.drive_rel_blocking(Radians(-8.0), Factor::HALF).unwrap();
+    assert!((stepper.pos() - PositionRad::ZERO).abs() <= stepper.step_dist());
+    assert!(!stepper.torque_model());
+}
+
+#[test]
+fn stepper_driver_limits() {
+    use crate::data::{DriverLimit, DriverLimits};
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let config = StepperConfig::VOLT12_NO_OVERLOAD;
+
+    // The rated current of the motor is too high for the driver
+    let ctrl = SimulatedController::new().with_driver_limits(DriverLimits::new(1.0, MicroSteps::from(4), Hertz(50.0)));
+    assert!(matches!(
+        Stepper::new_advanced(ctrl, consts.clone(), config.clone()), 
+        Err(ActuatorError::DriverLimitExceeded(DriverLimit::Current(_, _)))
+    ));
+
+    let ctrl = SimulatedController::new().with_driver_limits(DriverLimits::new(2.0, MicroSteps::from(4), Hertz(50.0)));
+    let mut stepper = Stepper::new_advanced(ctrl, consts, config).unwrap();
+
+    assert!(matches!(stepper.set_microsteps(MicroSteps::from(8)), Err(ActuatorError::DriverLimitExceeded(DriverLimit::MicroSteps(_, _)))));
+    assert!(matches!(
+        stepper.set_config(StepperConfig::new(12.0, Some(3.0))), 
+        Err(ActuatorError::DriverLimitExceeded(DriverLimit::Current(_, _)))
+    ));
+
+    // Movements requiring step rates above 50 Hz are rejected before their first step
+    assert!(matches!(
+        stepper.drive_with_profile(&RampProfile::new(Radians(1.0), RadPerSecond(10.0), RadPerSecond2(100.0))), 
+        Err(ActuatorError::DriverLimitExceeded(DriverLimit::StepFrequency(_, _)))
+    ));
+    assert!(matches!(stepper.drive_rel_blocking(Radians(1.0), Factor::MAX), Err(ActuatorError::DriverLimitExceeded(DriverLimit::StepFrequency(_, _)))));
+    assert!(matches!(stepper.drive_factor(Factor::MAX, Direction::CW), Err(ActuatorError::DriverLimitExceeded(DriverLimit::StepFrequency(_, _)))));
+    assert_eq!(stepper.pos(), PositionRad::ZERO);
+
+    stepper.set_velocity_max(Some(RadPerSecond(1.0))).unwrap();
+    stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(1.0)).abs() <= stepper.step_dist());
+
+}
+
+#[test]
+fn stepper_driver_limits_during_move() {
+    use crate::data::DriverLimits;
+
+    /// Driver reducing its step frequency after the given amount of steps, e.g. because of thermal derating
+    struct DeratingController {
+        steps_left : usize,
+        limits : DriverLimits,
+        derated : DriverLimits,
+        ctrl : SimulatedController
+    }
+
+    impl StepperController for DeratingController {
+        fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            if self.steps_left == 0 {
+                self.limits = self.derated;
+            } else {
+                self.steps_left -= 1;
+            }
+
+            self.ctrl.step(time)
+        }
+
+        fn direction(&self) -> Direction {
+            self.ctrl.direction()
+        }
+
+        fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
+            self.ctrl.set_dir(dir)
+        }
+
+        fn driver_limits(&self) -> Option<&DriverLimits> {
+            Some(&self.limits)
+        }
+    }
+
+    let derated = DriverLimits::new(2.0, MicroSteps::from(4), Hertz(100.0));
+    let ctrl = DeratingController { 
+        steps_left: 50, 
+        limits: DriverLimits::new(2.0, MicroSteps::from(4), Hertz(500.0)), 
+        derated, 
+        ctrl: SimulatedController::new() 
+    };
+
+    let mut stepper = StepperMotor::<StartStopBuilder, _>::new_advanced(ctrl, StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    stepper.add_interruptor(Box::new(VelocityLog(log.clone())));
+    stepper.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+
+    // The movement is finished with the step rate of the driver instead of being aborted
+    stepper.drive_rel_blocking(Radians(10.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(10.0)).abs() <= stepper.step_dist());
+
+    let velocity_derated = derated.velocity_max(stepper.step_dist());
+    assert!(log.lock().unwrap()[60 ..].iter().all(|velocity| velocity.abs() <= velocity_derated * 1.001));
+}
+
+#[test]
+fn stepper_observed_move() {
+    use std::sync::{Arc, Mutex};
+    use core::time::Duration;
+
+    use crate::SyncActuatorState;
+    use crate::sim::{self, VirtualClock};
+
+    type Observed = Arc<Mutex<Option<Arc<dyn SyncActuatorState + Send + Sync>>>>;
+
+    /// Reads the observed state with every step, like a user interface polling it during the movement
+    struct StateProbe {
+        observed : Observed,
+        clock : VirtualClock,
+        positions : Arc<Mutex<Vec<(Duration, PositionRad)>>>
+    }
+
+    impl Interruptor for StateProbe {
+        fn dir(&self) -> Option<Direction> {
+            None
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, _ctx : &InterruptContext) -> Option<InterruptReason> {
+            if let Some(state) = self.observed.lock().unwrap().as_ref() {
+                self.positions.lock().unwrap().push((self.clock.now(), state.pos()));
+            }
+
+            None
+        }
+    }
+
+    let clock = VirtualClock::new();
+    let (stepper, _) = sim::stepper::<StartStopBuilder>(&clock);
+    let mut gear = Gear::new(stepper, 0.5);
+
+    let observed : Observed = Arc::new(Mutex::new(None));
+    let positions = Arc::new(Mutex::new(Vec::new()));
+
+    gear.add_interruptor(Box::new(StateProbe { observed: observed.clone(), clock: clock.clone(), positions: positions.clone() }));
+    gear.drive_rel_blocking_with(Radians(1.0), Factor::MAX, |state| {
+        *observed.lock().unwrap() = Some(state);
+    }).unwrap();
+
+    // The positions are observed at the output of the gear while the movement blocks
+    let positions = positions.lock().unwrap();
+    assert!(positions.iter().any(|(_, pos)| (PositionRad(0.1) < *pos) && (*pos < PositionRad(0.9))));
+    assert!(positions.windows(2).all(|w| (w[0].0 < w[1].0) && (w[0].1 <= w[1].1)));
+
+    // The handle can be sent to other threads
+    let state = observed.lock().unwrap().take().unwrap();
+    let end = std::thread::spawn(move || state.pos()).join().unwrap();
+    assert!((end - PositionRad(1.0)).abs() < Radians(0.01));
+}
+
+#[test]
+fn stepper_stop_mode() {
+    use std::sync::{Arc, Mutex};
+
+    /// Virtual end switch at 1.0 rad, stores the velocity it triggered at
+    struct EndSwitch(Arc<Mutex<Option<RadPerSecond>>>);
+
+    impl Interruptor for EndSwitch {
+        fn dir(&self) -> Option<Direction> {
+            Some(Direction::CW)
+        }
+
+        fn set_temp_dir(&mut self, _dir_opt : Option<Direction>) { }
+
+        fn check(&mut self, ctx : &InterruptContext) -> Option<InterruptReason> {
+            (ctx.pos > PositionRad(1.0)).then(|| {
+                self.0.lock().unwrap().replace(ctx.velocity);
+                InterruptReason::EndReached
+            })
+        }
+    }
+
+    let run = |mode : StopMode| {
+        let trigger = Arc::new(Mutex::new(None));
+
+        let mut stepper = Stepper::simulated();
+        stepper.set_stop_mode(mode).unwrap();
+        stepper.add_interruptor(Box::new(EndSwitch(trigger.clone())));
+        stepper.drive_rel_blocking(Radians(10.0), Factor::MAX).unwrap();
+
+        let velocity = trigger.lock().unwrap().unwrap();
+        (stepper.pos() - PositionRad(1.0), velocity)
+    };
+
+    let (dist_builder, _) = run(StopMode::Builder);
+    let (dist_immediate, _) = run(StopMode::Immediate);
+    let (dist_ramp, velocity) = run(StopMode::DecelRamp(RadPerSecond2(200.0)));
+
+    // The start-stop builder stops immediately as well
+    assert!(dist_builder < Radians(0.05));
+    assert!(dist_immediate < Radians(0.05));
+
+    // The ramp takes v² / (2a) to come to a standstill
+    let dist_expected = Radians(velocity.0 * velocity.0 / 400.0);
+    assert!(dist_expected > Radians(0.1));
+    assert!((dist_ramp - dist_expected).abs() < Radians(0.05), "{dist_ramp:?} vs {dist_expected:?}");
+
+    // Builders with ramps ramp down themselves, with the deceleration of the stop mode instead of their own
+    let trigger = Arc::new(Mutex::new(None));
+
+    let mut stepper = TrapezoidalStepper::simulated();
+    stepper.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    stepper.set_acceleration_max(Some(RadPerSecond2(2000.0))).unwrap();
+    stepper.set_stop_mode(StopMode::DecelRamp(RadPerSecond2(200.0))).unwrap();
+    stepper.add_interruptor(Box::new(EndSwitch(trigger.clone())));
+    stepper.drive_rel_blocking(Radians(10.0), Factor::MAX).unwrap();
+
+    let velocity = trigger.lock().unwrap().unwrap();
+    let dist_ramp = stepper.pos() - PositionRad(1.0);
+    let dist_expected = Radians(velocity.0 * velocity.0 / 400.0);
+    assert!((dist_ramp - dist_expected).abs() < Radians(0.05), "{dist_ramp:?} vs {dist_expected:?}");
+
+    // The builder stands still afterwards and accepts new movements
+    let pos = stepper.pos();
+    stepper.drive_rel_blocking(Radians(-1.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - (pos - Radians(1.0))).abs() < stepper.step_dist());
+
+    // Invalid decelerations are rejected
+    let mut stepper = Stepper::simulated();
+    assert!(stepper.set_stop_mode(StopMode::DecelRamp(RadPerSecond2(-1.0))).is_err());
+    assert!(stepper.set_stop_mode(StopMode::DecelRamp(RadPerSecond2::INFINITY)).is_err());
+    assert_eq!(stepper.stop_mode(), StopMode::Builder);
+}
+
+#[test]
+fn minimal_stepper() {
+    let builder = StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    let mut stepper = MinimalStepper::new(builder, SimulatedController::default());
+
+    stepper.drive_rel(Radians(2.0), Factor::MAX).unwrap();
+    assert!((stepper.pos() - PositionRad(2.0)).abs() < stepper.builder().step_angle());
+
+    stepper.drive_abs(PositionRad(-1.0), Factor::HALF).unwrap();
+    assert!((stepper.pos() - PositionRad(-1.0)).abs() < stepper.builder().step_angle());
+    assert_eq!(stepper.ctrl().direction(), Direction::CCW);
+
+    // Steps of both movements, each rounded to full steps
+    let step_angle = stepper.builder().step_angle();
+    let (_, ctrl) = stepper.into_parts();
+    assert!(ctrl.steps().abs_diff(((Radians(2.0) + Radians(3.0)) / step_angle).round() as u64) <= 1);
+
+    assert!(MinimalStepper::new(StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap(), 
+        SimulatedController::default()).drive_rel(Radians::NAN, Factor::MAX).is_err());
+}
@@ -11,6 +11,223 @@ fn simple_builder() {
 }
 
 
+#[test]
+#[cfg(feature = "complex-builder")]
+fn render_profile() {
+    const DISTANCE : Radians = Radians(2.0);
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let steps = consts.steps_from_angle_abs(DISTANCE, MicroSteps::default()) as usize;
+
+    let mut builder = StartStopBuilder::new(consts.clone(), StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    assert_eq!(builder.render_profile(DISTANCE, Factor::MAX).unwrap().len(), steps);
+    assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+
+    let mut builder = ComplexBuilder::new(consts, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    assert_eq!(builder.render_profile(-DISTANCE, Factor::MAX).unwrap().len(), steps);
+    assert_eq!(builder.direction(), Direction::CCW);
+}
+
+#[test]
+fn start_stop_const_velocity_ccw() {
+    let mut ctrl = SimulatedController::new();
+    let mut builder = StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+
+    // The direction is set on the controller, the step times stay positive
+    builder.set_drive_mode(DriveMode::ConstVelocity(RadPerSecond(-5.0)), &mut ctrl).unwrap();
+    assert_eq!(ctrl.direction(), Direction::CCW);
+
+    let node = builder.next().unwrap();
+    assert!(node > Seconds::ZERO);
+    assert!((node - StepperConst::MOT_17HE15_1504S.step_time(RadPerSecond(5.0), MicroSteps::default())).abs() < Seconds(1e-6));
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn deceleration_limit() {
+    const DISTANCE : Radians = Radians(20.0);
+
+    /// Amount of steps at the start of the profile with decreasing step times and at the end with increasing ones
+    fn ramps(nodes : &[Seconds]) -> (usize, usize) {
+        let up = nodes.windows(2).take_while(|w| w[1] < w[0]).count();
+        let down = nodes.windows(2).rev().take_while(|w| w[1] > w[0]).count();
+        (up, down)
+    }
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let steps = consts.steps_from_angle_abs(DISTANCE, MicroSteps::default()) as usize;
+
+    let mut builder = ComplexBuilder::new(consts, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    builder.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+
+    // Symmetric profile by default
+    let nodes = builder.render_profile(DISTANCE, Factor::MAX).unwrap();
+    let (up, down) = ramps(&nodes);
+    assert_eq!(nodes.len(), steps);
+    assert!(up.abs_diff(down) <= 1);
+
+    // Braking harder
+    builder.set_deceleration_max(Some(RadPerSecond2(800.0))).unwrap();
+    assert_eq!(builder.deceleration_max(), Some(RadPerSecond2(800.0)));
+
+    let nodes = builder.render_profile(DISTANCE, Factor::MAX).unwrap();
+    let (up_hard, down_hard) = ramps(&nodes);
+    assert_eq!(nodes.len(), steps);
+    assert_eq!(up_hard, up);
+    assert!(down_hard < down);
+
+    // Braking softer, e.g. for fragile payloads
+    builder.set_deceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+
+    let nodes = builder.render_profile(DISTANCE, Factor::MAX).unwrap();
+    let (_, down_soft) = ramps(&nodes);
+    assert_eq!(nodes.len(), steps);
+    assert!(down_soft > down);
+
+    // Stops use the deceleration limit too
+    let mut ctrl = SimulatedController::new();
+    let velocity = builder.velocity_possible();
+    builder.set_drive_mode(DriveMode::ConstVelocity(velocity), &mut ctrl).unwrap();
+    builder.by_ref().take(100).for_each(drop);
+    builder.set_drive_mode(DriveMode::Stop, &mut ctrl).unwrap();
+
+    let stop : Vec<Seconds> = builder.by_ref().collect();
+    assert!(stop.len() > down);
+    assert!(stop.windows(2).all(|w| w[1] >= w[0]));
+    assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn jolt_table() {
+    use crate::data::JoltTable;
+
+    const DISTANCE : Radians = Radians(1.0);
+
+    let table = JoltTable::new(vec![ (RadPerSecond(20.0), RadPerSecond3(2_000.0)), (RadPerSecond(5.0), RadPerSecond3(5_000.0)) ]);
+    assert_eq!(table.jolt_at(RadPerSecond(-2.0)), Some(RadPerSecond3(5_000.0)));
+    assert_eq!(table.jolt_at(RadPerSecond(10.0)), Some(RadPerSecond3(2_000.0)));
+    assert_eq!(table.jolt_at(RadPerSecond(30.0)), None);
+
+    let mut builder = ComplexBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    builder.set_jolt_max(Some(RadPerSecond3(500.0))).unwrap();
+
+    let time_global : f32 = builder.render_profile(DISTANCE, Factor::MAX).unwrap().into_iter().map(|t| t.0).sum();
+
+    // Snappier small moves
+    builder.set_jolt_table(Some(table)).unwrap();
+    assert_eq!(builder.jolt_at(RadPerSecond(30.0)), Some(RadPerSecond3(500.0)));
+
+    let time_table : f32 = builder.render_profile(DISTANCE, Factor::MAX).unwrap().into_iter().map(|t| t.0).sum();
+    assert!(time_table < time_global);
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn complex_builder_direction_flips() {
+    let mut ctrl = SimulatedController::new();
+    let mut builder = ComplexBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    let velocity = builder.velocity_possible() * 0.5;
+
+    // Latest wins, only the last direction flip is executed
+    builder.set_drive_mode(DriveMode::ConstVelocity(velocity), &mut ctrl).unwrap();
+    builder.by_ref().take(50).for_each(drop);
+
+    for vel in [ -velocity, velocity, -velocity ] {
+        builder.set_drive_mode(DriveMode::ConstVelocity(vel), &mut ctrl).unwrap();
+    }
+
+    assert_eq!(*builder.drive_mode(), DriveMode::Stop);
+    assert_eq!(builder.queued_commands(), 1);
+
+    // The direction must not change during the stop ramp
+    builder.by_ref().for_each(drop);
+    assert_eq!(ctrl.direction(), Direction::CW);
+
+    assert!(builder.resume_queued(&mut ctrl).unwrap());
+    assert_eq!(*builder.drive_mode(), DriveMode::ConstVelocity(-velocity));
+    assert_eq!(ctrl.direction(), Direction::CCW);
+
+    // A stop discards all waiting commands
+    builder.set_drive_mode(DriveMode::ConstVelocity(velocity), &mut ctrl).unwrap();
+    builder.set_drive_mode(DriveMode::Stop, &mut ctrl).unwrap();
+    builder.by_ref().for_each(drop);
+
+    assert!(!builder.resume_queued(&mut ctrl).unwrap());
+    assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+
+    // Queue, every flip is executed in order
+    builder.set_command_policy(CommandPolicy::Queue(4));
+    builder.set_drive_mode(DriveMode::ConstVelocity(velocity), &mut ctrl).unwrap();
+    builder.by_ref().take(50).for_each(drop);
+
+    for vel in [ -velocity, velocity, -velocity ] {
+        builder.set_drive_mode(DriveMode::ConstVelocity(vel), &mut ctrl).unwrap();
+    }
+
+    assert_eq!(builder.queued_commands(), 3);
+
+    for dir in [ Direction::CCW, Direction::CW, Direction::CCW ] {
+        builder.by_ref().take(1000).for_each(drop);
+        assert!(builder.resume_queued(&mut ctrl).unwrap());
+        assert_eq!(ctrl.direction(), dir);
+    }
+
+    assert_eq!(builder.queued_commands(), 0);
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn complex_builder_stop_ramps() {
+    let mut ctrl = SimulatedController::new();
+    let mut builder = ComplexBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    let velocity = builder.velocity_possible() * 0.5;
+
+    let mut stop_ramp = |builder : &mut ComplexBuilder, deceleration : Option<RadPerSecond2>| {
+        builder.set_drive_mode(DriveMode::ConstVelocity(velocity), &mut ctrl).unwrap();
+        builder.by_ref().take(200).for_each(drop);
+
+        // The direction flip waits for the stop ramp
+        builder.set_drive_mode(DriveMode::ConstVelocity(-velocity), &mut ctrl).unwrap();
+        assert_eq!(*builder.drive_mode(), DriveMode::Stop);
+
+        if let Some(deceleration) = deceleration {
+            assert!(builder.stop_with_deceleration(deceleration));
+        }
+
+        let steps = builder.by_ref().count();
+
+        // The command queued during the stop is kept
+        assert_eq!(builder.queued_commands(), 1);
+        assert!(builder.resume_queued(&mut ctrl).unwrap());
+        assert_eq!(*builder.drive_mode(), DriveMode::ConstVelocity(-velocity));
+        builder.set_drive_mode(DriveMode::Stop, &mut ctrl).unwrap();
+        builder.by_ref().for_each(drop);
+
+        steps
+    };
+
+    // A gentle deceleration takes longer than the builder ramping down on its own
+    let steps_builder = stop_ramp(&mut builder, None);
+    let steps_gentle = stop_ramp(&mut builder, Some(RadPerSecond2(50.0)));
+    assert!(steps_gentle > steps_builder);
+
+    // The deceleration only applies to a single stop
+    assert_eq!(stop_ramp(&mut builder, None), steps_builder);
+
+    // Resetting the ramp stops instantly, but keeps the commands waiting
+    builder.set_drive_mode(DriveMode::ConstVelocity(velocity), &mut ctrl).unwrap();
+    builder.by_ref().take(200).for_each(drop);
+    builder.set_drive_mode(DriveMode::ConstVelocity(-velocity), &mut ctrl).unwrap();
+
+    builder.reset_ramp();
+    assert_eq!(builder.queued_commands(), 1);
+    assert!(builder.resume_queued(&mut ctrl).unwrap());
+    assert_eq!(*builder.drive_mode(), DriveMode::ConstVelocity(-velocity));
+}
+
 // #[test]
 // #[ignore = "Value display, run manually ... "]
 // fn complex_builder() {
@@ -66,4 +283,236 @@ fn simple_builder() {
 //     }
 
 //     println!("Simple: Pred: {}, true: {}", pred, time_sum);
-// }
\ No newline at end of file
+// }
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn velocity_constraints() {
+    let consts = StepperConst::MOT_17HE15_1504S;
+
+    let mut builder = StartStopBuilder::new(consts.clone(), StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    assert_eq!(builder.velocity_constraint(), Some(VelocityConstraint::StartStop));
+
+    builder.set_velocity_max(Some(RadPerSecond(1.0))).unwrap();
+    assert_eq!(builder.velocity_constraint(), Some(VelocityConstraint::UserCap));
+
+    // A calibrated ceiling below the velocity of the model
+    let config = StepperConfig { velocity_margin: VelocityMargin::Ceiling(RadPerSecond(10.0)), ..StepperConfig::VOLT12_NO_OVERLOAD };
+    let mut builder = ComplexBuilder::new(consts.clone(), config).unwrap();
+
+    assert_eq!(builder.velocity_possible(), RadPerSecond(10.0));
+    assert_eq!(builder.velocity_constraint(), Some(VelocityConstraint::BackEmf));
+
+    builder.set_velocity_max(Some(RadPerSecond(5.0))).unwrap();
+    assert_eq!(builder.velocity_constraint(), Some(VelocityConstraint::UserCap));
+
+    // A percentage margin reduces the cap of the model
+    let config = StepperConfig { velocity_margin: VelocityMargin::Percent(20.0), ..StepperConfig::VOLT12_NO_OVERLOAD };
+    let builder = ComplexBuilder::new(consts.clone(), config.clone()).unwrap();
+
+    assert!((builder.velocity_cap() - consts.velocity_max(12.0) * 0.8).abs() < RadPerSecond(1e-4));
+    assert_eq!(consts.velocity_max_with_margin(&config), builder.velocity_cap());
+
+    assert!(StepperConfig { velocity_margin: VelocityMargin::Percent(120.0), ..StepperConfig::VOLT12_NO_OVERLOAD }.check(&consts).is_err());
+    assert!(StepperConfig { velocity_margin: VelocityMargin::Absolute(RadPerSecond(-1.0)), ..StepperConfig::VOLT12_NO_OVERLOAD }.check(&consts).is_err());
+}
+
+#[test]
+fn trapezoidal_builder() {
+    const DISTANCE : Radians = Radians(20.0);
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let steps = consts.steps_from_angle_abs(DISTANCE, MicroSteps::default()) as usize;
+    let total = |nodes : &[Seconds]| nodes.iter().fold(Seconds::ZERO, |sum, &node| sum + node);
+
+    let mut builder = TrapezoidalBuilder::new(consts.clone(), StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+
+    // An acceleration limit is required without the torque model
+    assert!(matches!(builder.render_profile(DISTANCE, Factor::MAX), Err(ActuatorError::InvalidConfig(_))));
+
+    builder.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+    assert_eq!(builder.velocity_constraint(), Some(VelocityConstraint::UserCap));
+
+    // The profile matches the trapezoid of the limits
+    let nodes = builder.render_profile(DISTANCE, Factor::MAX).unwrap();
+    let expected = builder.ptp_time_for_distance(PositionRad::ZERO, PositionRad::ZERO + DISTANCE);
+    let fastest = nodes.iter().fold(Seconds::INFINITY, |min, &node| min.min(node));
+
+    assert_eq!(nodes.len(), steps);
+    assert!((total(&nodes) - expected).abs() < Seconds(0.02), "Time: {}, Expected: {}", total(&nodes), expected);
+    assert!((fastest - builder.step_angle() / RadPerSecond(10.0)).abs() < Seconds(1e-5));
+    assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+
+    // Braking harder shortens the movement
+    builder.set_deceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    let nodes_hard = builder.render_profile(-DISTANCE, Factor::MAX).unwrap();
+
+    assert_eq!(nodes_hard.len(), steps);
+    assert!((total(&nodes) - total(&nodes_hard) - Seconds(0.075)).abs() < Seconds(0.01));
+
+    // The jolt limit rounds the ramps, making the movement slightly longer
+    builder.set_deceleration_max(None).unwrap();
+    builder.set_jolt_max(Some(RadPerSecond3(500.0))).unwrap();
+    let nodes_jolt = builder.render_profile(DISTANCE, Factor::MAX).unwrap();
+
+    assert_eq!(nodes_jolt.len(), steps);
+    assert!(nodes_jolt[0] > nodes[0]);
+    assert!(total(&nodes_jolt) > total(&nodes));
+    assert!(total(&nodes_jolt) < total(&nodes) + Seconds(0.2));
+
+    // The torque model caps the velocity of the user
+    builder.set_velocity_max(Some(RadPerSecond(1000.0))).unwrap();
+    assert_eq!(builder.velocity_possible(), RadPerSecond(1000.0));
+
+    builder.set_torque_model(true);
+    assert_eq!(builder.velocity_possible(), consts.velocity_max_with_margin(&StepperConfig::VOLT12_NO_OVERLOAD));
+    assert_eq!(builder.velocity_constraint(), Some(VelocityConstraint::BackEmf));
+
+    // Short movements reach their target as well
+    builder.set_torque_model(false);
+    assert_eq!(builder.render_profile(builder.step_angle(), Factor::MAX).unwrap().len(), 1);
+    assert_eq!(builder.render_profile(builder.step_angle() * 3.0, Factor::MAX).unwrap().len(), 3);
+}
+
+#[test]
+fn velocity_min() {
+    const DISTANCE : Radians = Radians(20.0);
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+
+    // Start-stop movements are raised to the minimum as a whole
+    let mut builder = StartStopBuilder::new(consts.clone(), StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    let velocity_min = builder.velocity_possible() * 0.5;
+    builder.set_velocity_min(Some(velocity_min)).unwrap();
+
+    let nodes = builder.render_profile(DISTANCE, Factor::new(0.1)).unwrap();
+    assert!(nodes.iter().all(|&node| (node - builder.step_angle() / velocity_min).abs() < Seconds(1e-6)));
+
+    // Validation against the maximum velocity
+    let mut builder = TrapezoidalBuilder::new(consts, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    builder.set_velocity_max(Some(RadPerSecond(10.0))).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(50.0))).unwrap();
+
+    assert!(matches!(builder.set_velocity_min(Some(RadPerSecond(20.0))), Err(ActuatorError::VelocityTooHigh(_, _))));
+    builder.set_velocity_min(Some(RadPerSecond(5.0))).unwrap();
+    assert!(matches!(builder.set_velocity_max(Some(RadPerSecond(2.0))), Err(ActuatorError::VelocityTooLow(_, _))));
+    assert_eq!(builder.velocity_max(), Some(RadPerSecond(10.0)));
+
+    // Ramps start and end at standstill, the movement cruises with the minimum velocity instead of the requested one
+    let nodes = builder.render_profile(DISTANCE, Factor::new(0.1)).unwrap();
+    let cruise = builder.step_angle() / RadPerSecond(5.0);
+
+    assert!(nodes[0] > cruise);
+    assert!(nodes[nodes.len() - 1] > cruise);
+    assert!((nodes[nodes.len() / 2] - cruise).abs() < Seconds(1e-5));
+    assert!(nodes.iter().all(|&node| node > cruise * 0.99));
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn load_quantization() {
+    use crate::sync::stepper::LoadQuantization;
+
+    const DISTANCE : Radians = Radians(0.2);
+
+    let time = |builder : &ComplexBuilder| builder.ptp_time_for_distance(PositionRad::ZERO, PositionRad::ZERO + DISTANCE);
+
+    let mut exact = ComplexBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    exact.apply_inertia(KgMeter2(0.001)).unwrap();
+    let time_exact = time(&exact);
+
+    let mut builder = ComplexBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+    builder.set_load_quantization(Some(LoadQuantization { 
+        force: NewtonMeters(0.05), 
+        inertia: KgMeter2(0.000_5), 
+        cache_size: 4 
+    })).unwrap();
+
+    // Loads within one step share the speed levels of the rounded up load
+    builder.apply_inertia(KgMeter2(0.000_6)).unwrap();
+    assert_eq!(builder.cached_levels(), 1);
+    assert_eq!(time(&builder), time_exact);
+
+    builder.apply_inertia(KgMeter2(0.000_9)).unwrap();
+    assert_eq!(builder.cached_levels(), 1);
+    assert_eq!(time(&builder), time_exact);
+
+    // Larger changes switch the levels, the previous ones are cached
+    builder.apply_inertia(KgMeter2(0.002)).unwrap();
+    assert_eq!(builder.cached_levels(), 2);
+    assert!(time(&builder) > time_exact);
+
+    builder.apply_inertia(KgMeter2(0.000_8)).unwrap();
+    assert_eq!(builder.cached_levels(), 2);
+    assert_eq!(time(&builder), time_exact);
+
+    // Directional loads are rounded away from zero in both directions
+    exact.apply_dir_force(NewtonMeters(-0.1)).unwrap();
+    builder.apply_dir_force(NewtonMeters(-0.06)).unwrap();
+    assert_eq!(time(&builder), time(&exact));
+
+    exact.apply_dir_force(NewtonMeters::ZERO).unwrap();
+    builder.apply_dir_force(NewtonMeters::ZERO).unwrap();
+    assert_eq!(time(&builder), time_exact);
+
+    // Invalid steps are rejected, keeping the current quantization
+    let quant = builder.load_quantization();
+    assert!(builder.set_load_quantization(Some(LoadQuantization { force: NewtonMeters(0.0), inertia: KgMeter2(0.001), cache_size: 4 })).is_err());
+    assert_eq!(builder.load_quantization(), quant);
+
+    // Other changes discard the cache
+    builder.set_velocity_max(Some(RadPerSecond(50.0))).unwrap();
+    assert_eq!(builder.cached_levels(), 0);
+
+    builder.set_load_quantization(None).unwrap();
+    builder.apply_inertia(KgMeter2(0.001)).unwrap();
+    exact.set_velocity_max(Some(RadPerSecond(50.0))).unwrap();
+    assert_eq!(time(&builder), time(&exact));
+}
+
+#[test]
+#[cfg(feature = "complex-builder")]
+fn sub_step_moves() {
+    use crate::sync::stepper::builder::FreeBuilder;
+
+    /// Zero-distance and sub-step movements are no-ops in every builder, the residue is carried over by the motor only
+    fn check<B : StepperBuilder>(mut builder : B) {
+        let step = builder.step_angle();
+
+        assert_eq!(builder.render_profile(step * -3.0, Factor::MAX).unwrap().len(), 3);
+        assert_eq!(builder.direction(), Direction::CCW);
+        assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+
+        // No steps, the direction is kept
+        assert!(builder.render_profile(Radians::ZERO, Factor::MAX).unwrap().is_empty());
+        assert!(builder.render_profile(step * 0.4, Factor::MAX).unwrap().is_empty());
+        assert_eq!(builder.direction(), Direction::CCW);
+        assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+
+        // Distances are rounded to the closest step
+        assert!(builder.render_profile(step * 0.4, Factor::MAX).unwrap().is_empty());
+        assert_eq!(builder.render_profile(step * 0.6, Factor::MAX).unwrap().len(), 1);
+        assert_eq!(builder.direction(), Direction::CW);
+
+        assert_eq!(builder.render_profile(step * -2.4, Factor::MAX).unwrap().len(), 2);
+        assert_eq!(builder.direction(), Direction::CCW);
+        assert_eq!(*builder.drive_mode(), DriveMode::Inactive);
+    }
+
+    let consts = StepperConst::MOT_17HE15_1504S;
+    let config = StepperConfig::VOLT12_NO_OVERLOAD;
+
+    check(StartStopBuilder::new(consts.clone(), config.clone()).unwrap());
+    check(ComplexBuilder::new(consts.clone(), config.clone()).unwrap());
+
+    let mut builder = ComplexBuilder::new(consts.clone(), config.clone()).unwrap();
+    builder.set_deceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+    check(builder);
+
+    check(FreeBuilder::new(consts.clone(), RadPerSecond(50.0), RadPerSecond2(1000.0)).unwrap());
+
+    let mut builder = TrapezoidalBuilder::new(consts, config).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+    check(builder);
+}
@@ -0,0 +1,72 @@
+//! Conformance tests of the step profiles generated by the builders
+//! 
+//! The hashes are recorded reference values, any change of them changes the motion of existing machines. Update them only
+//! for intentional changes of the profiles and mention it in the changelog. Targets with a different libm may produce 
+//! different hashes for the profiles using `powf` or `cbrt`, see the determinism notes of [StepperBuilder].
+
+use crate::prelude::*;
+use crate::data::JoltTable;
+use crate::sync::stepper::profile_hash;
+
+/// Renders the profiles of all scenarios for the given builder
+fn render<B : StepperBuilder>(builder : &mut B) -> Vec<Vec<Seconds>> {
+    [
+        (Radians(2.0), Factor::MAX),
+        (Radians(-0.5), Factor::HALF),
+        (Radians(10.0), Factor::new(0.8))
+    ].into_iter().map(|(rel_dist, speed)| builder.render_profile(rel_dist, speed).unwrap()).collect()
+}
+
+fn start_stop() -> StartStopBuilder {
+    StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
+}
+
+#[cfg(feature = "complex-builder")]
+fn complex() -> ComplexBuilder {
+    ComplexBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
+}
+
+#[cfg(feature = "complex-builder")]
+fn complex_limited() -> ComplexBuilder {
+    let mut builder = complex();
+    builder.set_velocity_max(Some(RadPerSecond(20.0))).unwrap();
+    builder.set_acceleration_max(Some(RadPerSecond2(200.0))).unwrap();
+    builder.set_deceleration_max(Some(RadPerSecond2(100.0))).unwrap();
+    builder.set_jolt_max(Some(RadPerSecond3(5000.0))).unwrap();
+    builder.set_jolt_table(Some(JoltTable::new(vec![ (RadPerSecond(5.0), RadPerSecond3(20000.0)) ]))).unwrap();
+    builder
+}
+
+fn hashes(profiles : &[Vec<Seconds>]) -> Vec<u64> {
+    profiles.iter().map(|profile| profile_hash(profile)).collect()
+}
+
+#[test]
+fn profile_hash_values() {
+    assert_eq!(profile_hash(&[]), 0xcbf2_9ce4_8422_2325);
+    assert_ne!(profile_hash(&[ Seconds(0.001), Seconds(0.002) ]), profile_hash(&[ Seconds(0.002), Seconds(0.001) ]));
+}
+
+#[test]
+fn builders_replay() {
+    // Rendering the same inputs twice, with a fresh and with a reused builder
+    let mut reused = start_stop();
+    assert_eq!(render(&mut start_stop()), render(&mut reused));
+    assert_eq!(render(&mut start_stop()), render(&mut reused));
+
+    #[cfg(feature = "complex-builder")] {
+        let mut reused = complex_limited();
+        assert_eq!(render(&mut complex_limited()), render(&mut reused));
+        assert_eq!(render(&mut complex_limited()), render(&mut reused));
+    }
+}
+
+#[test]
+fn builders_conformance() {
+    assert_eq!(hashes(&render(&mut start_stop())), [ 0x6d0c6b7f91a56225, 0xfb6fb03066ccd665, 0x5d0d7a766f07806d ]);
+
+    #[cfg(feature = "complex-builder")] {
+        assert_eq!(hashes(&render(&mut complex())), [ 0x8c6c210a1aa371c5, 0x8fb92b59e29797a5, 0x88ef8dfd47e382cd ]);
+        assert_eq!(hashes(&render(&mut complex_limited())), [ 0xca4041b50f8ffb10, 0x8b59ce0a3efa5879, 0x100d39291204966a ]);
+    }
+}
@@ -1,33 +1,103 @@
-use syunit::*;
-
 use crate::prelude::*;
 
-/// A simulated controller that does nothing
-pub struct SimulatedController {
-    _dir : Direction
+/// Creates a simulated controller that sleeps for the duration of every step, so the movements take real time
+pub fn realtime_controller() -> SimulatedController {
+    SimulatedController::with_delay(|time| spin_sleep::sleep(time.into()))
 }
 
-impl SimulatedController {
-    /// Creates a new simulated controller
-    pub fn new() -> Self {
-        Self {
-            _dir: Direction::default()
+mod pulse {
+    use core::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    use embedded_hal::digital::{ErrorType, OutputPin};
+
+    use crate::data::DriverLimit;
+    use crate::prelude::*;
+    use crate::sync::stepper::{PulseController, PulseGenerator, StepperMotor};
+
+    /// Records all pulses generated
+    #[derive(Default)]
+    struct TestGenerator {
+        periods : Arc<Mutex<Vec<Seconds>>>,
+        frequency_max : Option<Hertz>,
+        waited : Arc<Mutex<Seconds>>,
+        stopped : bool
+    }
+
+    impl PulseGenerator for TestGenerator {
+        fn pulse(&mut self, period : Seconds) -> Result<(), ActuatorError> {
+            self.periods.lock().unwrap().push(period);
+            Ok(())
+        }
+
+        fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+            *self.waited.lock().unwrap() += time;
+            Ok(())
+        }
+
+        fn frequency_max(&self) -> Option<Hertz> {
+            self.frequency_max
+        }
+
+        fn stop(&mut self) -> Result<(), ActuatorError> {
+            self.stopped = true;
+            Ok(())
         }
     }
-}
 
-impl StepperController for SimulatedController {
-    fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
-        spin_sleep::sleep(time.into());
-        Ok(())
+    struct TestPin(Arc<Mutex<bool>>);
+
+    impl ErrorType for TestPin {
+        type Error = Infallible;
     }
 
-    fn direction(&self) -> Direction {
-        self._dir
+    impl OutputPin for TestPin {
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            *self.0.lock().unwrap() = false;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            *self.0.lock().unwrap() = true;
+            Ok(())
+        }
     }
 
-    fn set_dir(&mut self, dir : Direction) -> Result<(), ActuatorError> {
-        self._dir = dir;
-        Ok(())
+    #[test]
+    fn pulse_controller() {
+        let periods = Arc::new(Mutex::new(Vec::new()));
+        let waited = Arc::new(Mutex::new(Seconds::ZERO));
+        let dir = Arc::new(Mutex::new(false));
+
+        let ctrl = PulseController::new(TestGenerator { periods: periods.clone(), waited: waited.clone(), ..Default::default() }, 
+            TestPin(dir.clone()));
+        let mut stepper = StepperMotor::<StartStopBuilder, _>::new_advanced(ctrl, StepperConst::MOT_17HE15_1504S, 
+            StepperConfig::VOLT12_NO_OVERLOAD).unwrap();
+
+        stepper.drive_rel_blocking(Radians(1.0), Factor::MAX).unwrap();
+
+        // Every step of the movement is a pulse of the generator
+        let steps = (Radians(1.0) / stepper.step_dist()).round() as usize;
+        assert_eq!(periods.lock().unwrap().len(), steps);
+        assert!(*dir.lock().unwrap());
+
+        stepper.drive_rel_blocking(Radians(-0.5), Factor::MAX).unwrap();
+        assert!(!*dir.lock().unwrap());
+
+        // The settle time is timed by the generator
+        stepper.set_settle_policy(SettlePolicy::Time(Seconds(0.05)));
+        stepper.drive_rel_blocking(Radians(0.5), Factor::MAX).unwrap();
+        assert_eq!(*waited.lock().unwrap(), Seconds(0.05));
+
+        // Step rates above the maximum frequency of the generator are refused
+        let mut ctrl = PulseController::new(TestGenerator { frequency_max: Some(Hertz(1000.0)), ..Default::default() }, 
+            TestPin(dir.clone()));
+
+        assert!(ctrl.step(Seconds(0.01)).is_ok());
+        assert!(matches!(ctrl.step(Seconds(0.0001)), Err(ActuatorError::DriverLimitExceeded(DriverLimit::StepFrequency(_, _)))));
+        assert!(ctrl.step_batch(&[ Seconds(0.01), Seconds(0.0005) ]).is_err());
+
+        ctrl.stop().unwrap();
+        assert!(ctrl.generator().stopped);
     }
-}
\ No newline at end of file
+}
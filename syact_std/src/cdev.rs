@@ -0,0 +1,85 @@
+//! Pins based on the Linux GPIO character device (`/dev/gpiochipN`), usable on any Linux SBC (BeagleBone, Orange Pi, industrial boards ...)
+
+use std::path::Path;
+
+use embedded_hal::digital::{ErrorKind, ErrorType, InputPin, OutputPin};
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+
+use crate::GenericPWMController;
+
+/// The consumer label the lines are requested with
+pub const CONSUMER : &str = "syact";
+
+/// Error of a [CdevPin], wrapping the error of the `gpio-cdev` crate
+#[derive(Debug)]
+pub struct CdevError(pub gpio_cdev::Error);
+
+impl embedded_hal::digital::Error for CdevError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl From<gpio_cdev::Error> for CdevError {
+    fn from(value: gpio_cdev::Error) -> Self {
+        Self(value)
+    }
+}
+
+/// A single GPIO line requested from a GPIO character device
+pub struct CdevPin {
+    handle : LineHandle
+}
+
+impl CdevPin {
+    /// Requests the line with the given `offset` of the `chip` as an output, initially set to `LOW`
+    pub fn new_output(chip : &mut Chip, offset : u32) -> Result<Self, CdevError> {
+        let handle = chip.get_line(offset)?.request(LineRequestFlags::OUTPUT, 0, CONSUMER)?;
+        Ok(Self { handle })
+    }
+
+    /// Requests the line with the given `offset` of the `chip` as an input
+    pub fn new_input(chip : &mut Chip, offset : u32) -> Result<Self, CdevError> {
+        let handle = chip.get_line(offset)?.request(LineRequestFlags::INPUT, 0, CONSUMER)?;
+        Ok(Self { handle })
+    }
+}
+
+impl ErrorType for CdevPin {
+    type Error = CdevError;
+}
+
+impl InputPin for CdevPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.handle.get_value()? != 0)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.handle.get_value()? == 0)
+    }
+}
+
+impl OutputPin for CdevPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(self.handle.set_value(0)?)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(self.handle.set_value(1)?)
+    }
+}
+
+impl GenericPWMController<CdevPin, CdevPin> {
+    /// Creates a new controller using the lines `offset_dir` and `offset_step` of the GPIO character device at `chip_path` 
+    /// (e.g. `/dev/gpiochip0`)
+    /// 
+    /// The step signal is generated with the same timing as for any other pins
+    pub fn new_cdev<P : AsRef<Path>>(chip_path : P, offset_dir : u32, offset_step : u32) -> Result<Self, CdevError> {
+        let mut chip = Chip::new(chip_path)?;
+
+        Ok(Self::new(
+            CdevPin::new_output(&mut chip, offset_dir)?,
+            CdevPin::new_output(&mut chip, offset_step)?
+        ))
+    }
+}
@@ -0,0 +1,149 @@
+//! Step generation on ESP32 chips using the RMT peripheral
+//! 
+//! A software loop cannot generate stable step signals above ~100 kHz on these chips, the RMT peripheral however generates
+//! the pulse train in hardware. The step intervals of a builder are converted into RMT symbols on the fly, the driver
+//! refills its memory blocks from the interval iterator while the signal is being transmitted.
+
+use esp_idf_hal::rmt::{PinState, Pulse, PulseTicks, Symbol, TxRmtDriver};
+
+use syact::ActuatorError;
+use syact::sync::stepper::{PulseController, PulseGenerator, StepperBuilder};
+use syact::units::*;
+
+/// The maximum number of ticks a single RMT pulse can last
+const PULSE_TICKS_MAX : u32 = 32767;
+
+fn pulse(state : PinState, ticks : u32) -> Pulse {
+    // Safe to unwrap, all tick values are clamped to `PULSE_TICKS_MAX` before
+    Pulse::new(state, PulseTicks::new(ticks as u16).unwrap())
+}
+
+/// Converts an iterator of step intervals into RMT symbols
+/// 
+/// Each step consists of a `HIGH` pulse lasting half of the interval, followed by a `LOW` pulse. Intervals exceeding the
+/// maximum length of a symbol are extended with additional `LOW` symbols. 
+pub struct RmtPulseTrain<I : Iterator<Item = Seconds>> {
+    intervals : I,
+    ticks_hz : f32,
+
+    /// Remaining `LOW` ticks of the current step that did not fit into the previous symbol
+    low_rest : u32
+}
+
+impl<I : Iterator<Item = Seconds>> RmtPulseTrain<I> {
+    /// Creates a new pulse train from the given `intervals` for an RMT channel running with `ticks_hz`
+    pub fn new(intervals : I, ticks_hz : u32) -> Self {
+        Self {
+            intervals,
+            ticks_hz: ticks_hz as f32,
+            low_rest: 0
+        }
+    }
+}
+
+impl<I : Iterator<Item = Seconds>> Iterator for RmtPulseTrain<I> {
+    type Item = Symbol;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Continue the `LOW` part of the last step, zero length pulses would end the transmission
+        if self.low_rest > 1 {
+            let total = self.low_rest.min(2 * PULSE_TICKS_MAX);
+            self.low_rest -= total;
+
+            return Some(Symbol::new(pulse(PinState::Low, total / 2), pulse(PinState::Low, total - total / 2)));
+        }
+
+        let ticks = (self.intervals.next()?.0 * self.ticks_hz) as u32;
+
+        let high = (ticks / 2).clamp(1, PULSE_TICKS_MAX);
+        let low_total = ticks.saturating_sub(high).max(1);
+        let low = low_total.min(PULSE_TICKS_MAX);
+
+        self.low_rest = low_total - low;
+
+        Some(Symbol::new(pulse(PinState::High, high), pulse(PinState::Low, low)))
+    }
+}
+
+/// A [PulseGenerator] creating the step signal with the RMT peripheral of an ESP32, see [RmtController]
+pub struct RmtGenerator<'d> {
+    tx : TxRmtDriver<'d>
+}
+
+impl<'d> RmtGenerator<'d> {
+    /// Creates a new generator using the RMT driver `tx` for the step signal
+    pub fn new(tx : TxRmtDriver<'d>) -> Self {
+        Self { tx }
+    }
+
+    /// The frequency of the ticks of the RMT channel
+    fn ticks_hz(&self) -> Result<u32, ActuatorError> {
+        Ok(u32::from(self.tx.counter_clock().map_err(|_| ActuatorError::IOError)?))
+    }
+
+    /// Transmits all remaining step intervals of the `builder` in hardware, blocking until the transmission has finished
+    /// 
+    /// The drive mode of the builder has to be set beforehand, using the [RmtController] of this generator. Calls 
+    /// `on_finish` with the number of steps made once the move has been completed, the number is also returned.
+    pub fn drive<B, F>(&mut self, builder : &mut B, on_finish : F) -> Result<u64, ActuatorError> 
+    where
+        B : StepperBuilder + Send,
+        F : FnOnce(u64)
+    {
+        let ticks_hz = self.ticks_hz()?;
+        let mut steps = 0;
+
+        let intervals = builder.by_ref().inspect(|_| steps += 1);
+        self.tx.start_iter_blocking(RmtPulseTrain::new(intervals, ticks_hz))
+            .map_err(|_| ActuatorError::IOError)?;
+
+        on_finish(steps);
+        Ok(steps)
+    }
+}
+
+impl<'d> PulseGenerator for RmtGenerator<'d> {
+    /// Transmits a single step, used when the controller is driven step by step by a `StepperMotor`
+    fn pulse(&mut self, period : Seconds) -> Result<(), ActuatorError> {
+        self.pulse_train(&[ period ])
+    }
+
+    /// Transmits a whole burst of steps with a single RMT transmission, see `StepperMotor::set_step_batch`
+    fn pulse_train(&mut self, periods : &[Seconds]) -> Result<(), ActuatorError> {
+        let ticks_hz = self.ticks_hz()?;
+
+        self.tx.start_iter_blocking(RmtPulseTrain::new(periods.iter().copied(), ticks_hz))
+            .map_err(|_| ActuatorError::IOError)
+    }
+
+    /// Transmits `LOW` symbols for the given `time`, so the wait is timed by the RMT peripheral like the steps
+    fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+        let mut rest = (time.0 * self.ticks_hz()? as f32) as u32;
+
+        // Zero length pulses would end the transmission, see `RmtPulseTrain`
+        let symbols = core::iter::from_fn(move || (rest > 1).then(|| {
+            let total = rest.min(2 * PULSE_TICKS_MAX);
+            rest -= total;
+
+            Symbol::new(pulse(PinState::Low, total / 2), pulse(PinState::Low, total - total / 2))
+        }));
+
+        self.tx.start_iter_blocking(symbols).map_err(|_| ActuatorError::IOError)
+    }
+
+    fn stop(&mut self) -> Result<(), ActuatorError> {
+        self.tx.stop().map_err(|_| ActuatorError::IOError)
+    }
+}
+
+/// A stepper controller generating the step signal with the RMT peripheral of an ESP32, the direction pin being a regular
+/// output pin
+/// 
+/// ```rust,ignore
+/// let mut ctrl = RmtController::new(RmtGenerator::new(tx), pin_dir);
+/// 
+/// // Streams a whole movement of the builder in a single transmission
+/// builder.set_drive_mode(DriveMode::FixedDistance(Radians(10.0), RadPerSecond::ZERO, Factor::MAX), &mut ctrl)?;
+/// ctrl.generator_mut().drive(&mut builder, |steps| println!("{} steps made", steps))?;
+/// ```
+pub type RmtController<'d, DIR> = PulseController<RmtGenerator<'d>, DIR>;
@@ -4,14 +4,28 @@
 use embedded_hal::digital::{OutputPin, PinState};
 
 use syact::ActuatorError;
+use syact::data::DriverLimits;
 use syact::sync::stepper::StepperController;
 use syact::units::*;
 
+#[cfg(feature = "gpio-cdev")]
+pub mod cdev;
+
+#[cfg(feature = "esp")]
+pub mod esp;
+
+/// Plot data of planned group trajectories
+pub mod plot;
+
+/// Hardware pulse generation with the PWM channels of the Linux sysfs
+pub mod sysfs_pwm;
+
 pub struct GenericPWMController<DIR : OutputPin, STEP : OutputPin> {
     pin_dir : DIR,
     pin_step : STEP,
 
     direction : Direction,
+    driver_limits : Option<DriverLimits>
 }
 
 impl<DIR : OutputPin, STEP : OutputPin> GenericPWMController<DIR, STEP> {
@@ -20,9 +34,29 @@ impl<DIR : OutputPin, STEP : OutputPin> GenericPWMController<DIR, STEP> {
             pin_dir,
             pin_step,
 
-            direction: Direction::default()
+            direction: Direction::default(),
+            driver_limits: None
         }
     }
+
+    /// Attaches the limits of the driver connected to the pins, see [StepperController::driver_limits]
+    pub fn with_driver_limits(mut self, limits : DriverLimits) -> Self {
+        self.driver_limits = Some(limits);
+        self
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<DIR, STEP> GenericPWMController<syact::compat::CompatOutput02<DIR>, syact::compat::CompatOutput02<STEP>> 
+where
+    DIR : embedded_hal_02::digital::v2::OutputPin,
+    STEP : embedded_hal_02::digital::v2::OutputPin,
+    DIR::Error : core::fmt::Debug,
+    STEP::Error : core::fmt::Debug
+{
+    pub fn new_02(pin_dir : DIR, pin_step : STEP) -> Self {
+        Self::new(syact::compat::CompatOutput02::new(pin_dir), syact::compat::CompatOutput02::new(pin_step))
+    }
 }
 
 impl<DIR : OutputPin, STEP : OutputPin> StepperController for GenericPWMController<DIR, STEP> {
@@ -34,6 +68,10 @@ impl<DIR : OutputPin, STEP : OutputPin> StepperController for GenericPWMControll
         self.pin_dir.set_state(PinState::from(dir.as_bool())).map_err(|_| ActuatorError::IOError)
     }
 
+    fn driver_limits(&self) -> Option<&DriverLimits> {
+        self.driver_limits.as_ref()
+    }
+
     fn step(&mut self, time : Seconds) -> Result<(), ActuatorError> {
         self.pin_step.set_high().map_err(|_| ActuatorError::IOError)?;
         spin_sleep::sleep((time / 2.0).into());
@@ -41,4 +79,13 @@ impl<DIR : OutputPin, STEP : OutputPin> StepperController for GenericPWMControll
         spin_sleep::sleep((time / 2.0).into());
         Ok(())
     }
+
+    fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+        spin_sleep::sleep(time.into());
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), ActuatorError> {
+        self.pin_step.set_low().map_err(|_| ActuatorError::IOError)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,211 @@
+//! Plot data of planned group trajectories, helpful to verify synchronization and profiles before running the hardware
+//!
+//! ```rust
+//! use syact::prelude::*;
+//! use syact_std::plot::GroupTrajectory;
+//!
+//! let mut builders = [
+//!     StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap(),
+//!     StartStopBuilder::new(StepperConst::MOT_17HE15_1504S, StepperConfig::VOLT12_NO_OVERLOAD).unwrap()
+//! ];
+//!
+//! let traj = GroupTrajectory::plan(&mut builders, &[ Radians(2.0), Radians(1.0) ], Factor::MAX).unwrap();
+//!
+//! // Both axes finish at about the same time
+//! assert!((traj.axes[0].duration() - traj.axes[1].duration()).abs() < Seconds(0.05));
+//!
+//! let csv = traj.to_csv(Seconds(0.01));
+//! assert!(csv.starts_with("time,axis0_pos,axis0_vel,axis1_pos,axis1_vel"));
+//!
+//! let svg = traj.to_svg_xy(0, 1, 400, 400);
+//! assert!(svg.starts_with("<svg"));
+//! ```
+
+use std::fmt::Write;
+
+use syact::{ActuatorError, DefinedActuator};
+use syact::sync::stepper::{sync_speed_factors, StepperBuilder};
+use syact::units::*;
+
+/// A single step of an axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectorySample {
+    /// The time at which the step is finished
+    pub time : Seconds,
+    /// The position after the step
+    pub pos : PositionRad,
+    /// The velocity during the step
+    pub velocity : RadPerSecond
+}
+
+/// The planned steps of a single axis
+#[derive(Clone, Debug, Default)]
+pub struct AxisTrajectory {
+    /// The name of the axis, used for the column headers of the CSV export
+    pub name : String,
+    /// All steps of the axis, the movement starts at zero time and position
+    pub samples : Vec<TrajectorySample>
+}
+
+impl AxisTrajectory {
+    /// Creates the trajectory of an axis from the step intervals `nodes`, each step moving the axis by `step_dist`
+    /// (negative for movements in the negative direction)
+    pub fn from_nodes<S : Into<String>>(name : S, step_dist : Radians, nodes : &[Seconds]) -> Self {
+        let mut time = Seconds::ZERO;
+        let mut pos = PositionRad::ZERO;
+
+        let samples = nodes.iter().map(|&node| {
+            time += node;
+            pos += step_dist;
+
+            TrajectorySample { time, pos, velocity: step_dist / node }
+        }).collect();
+
+        Self { name: name.into(), samples }
+    }
+
+    /// The time until the last step is finished
+    pub fn duration(&self) -> Seconds {
+        self.samples.last().map_or(Seconds::ZERO, |sample| sample.time)
+    }
+
+    /// The position and velocity at the given `time`, the position is held between the steps
+    pub fn state_at(&self, time : Seconds) -> (PositionRad, RadPerSecond) {
+        // Index of the step in progress at the given time
+        let index = self.samples.partition_point(|sample| sample.time <= time);
+
+        let pos = index.checked_sub(1).map_or(PositionRad::ZERO, |i| self.samples[i].pos);
+        let velocity = self.samples.get(index).map_or(RadPerSecond::ZERO, |sample| sample.velocity);
+
+        (pos, velocity)
+    }
+}
+
+/// The planned trajectories of all axes of a group, exportable as CSV plot data and SVG path plots
+#[derive(Clone, Debug, Default)]
+pub struct GroupTrajectory {
+    /// The trajectories of the axes
+    pub axes : Vec<AxisTrajectory>
+}
+
+impl GroupTrajectory {
+    /// Creates an empty group trajectory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the trajectory of another axis
+    pub fn with_axis(mut self, axis : AxisTrajectory) -> Self {
+        self.axes.push(axis);
+        self
+    }
+
+    /// Plans the movement of a group by `rel_dists` with the builders of the axes, scaling the speeds to the slowest axis
+    /// like [drive_rel_interpolated](syact::sync::stepper::drive_rel_interpolated) does. The axes are named `axis0`, `axis1` ...
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the amount of builders and distances do not match
+    pub fn plan<B : StepperBuilder + DefinedActuator>(builders : &mut [B], rel_dists : &[Radians], speed_f : Factor)
+        -> Result<Self, ActuatorError>
+    {
+        assert_eq!(builders.len(), rel_dists.len(), "The amount of builders and distances must be equal");
+
+        let times : Vec<Seconds> = builders.iter().zip(rel_dists)
+            .map(|(builder, rel_dist)| builder.ptp_time_for_distance(PositionRad::ZERO, PositionRad::ZERO + *rel_dist))
+            .collect();
+
+        let mut traj = Self::new();
+
+        for (i, ((builder, rel_dist), factor)) in builders.iter_mut().zip(rel_dists).zip(sync_speed_factors(&times, speed_f)).enumerate() {
+            let nodes = builder.render_profile(*rel_dist, factor)?;
+            let step_dist = if rel_dist.0 < 0.0 { -builder.step_angle() } else { builder.step_angle() };
+
+            traj.axes.push(AxisTrajectory::from_nodes(format!("axis{}", i), step_dist, &nodes));
+        }
+
+        Ok(traj)
+    }
+
+    /// The time until the last axis has finished
+    pub fn duration(&self) -> Seconds {
+        self.axes.iter().map(AxisTrajectory::duration).fold(Seconds::ZERO, Seconds::max)
+    }
+
+    /// Exports the position and velocity of all axes over time as CSV, sampled every `sample_time`
+    ///
+    /// The columns are `time`, followed by `<name>_pos` and `<name>_vel` for each axis
+    pub fn to_csv(&self, sample_time : Seconds) -> String {
+        let mut csv = String::from("time");
+
+        for axis in &self.axes {
+            write!(csv, ",{}_pos,{}_vel", axis.name, axis.name).unwrap();
+        }
+
+        csv.push('\n');
+
+        let samples = (self.duration() / sample_time).ceil() as usize;
+
+        for i in 0 ..= samples {
+            let time = sample_time * i as f32;
+            write!(csv, "{}", time.0).unwrap();
+
+            for axis in &self.axes {
+                let (pos, velocity) = axis.state_at(time);
+                write!(csv, ",{},{}", pos.0, velocity.0).unwrap();
+            }
+
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Renders the path of the axis `x` against the axis `y` as an SVG image with the given size in pixels
+    ///
+    /// Both axes are scaled equally, so the path keeps its proportions. The positions of both axes are merged by time, each
+    /// step of any axis adds a point to the path.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if one of the axis indices is out of range
+    pub fn to_svg_xy(&self, x : usize, y : usize, width : u32, height : u32) -> String {
+        let (axis_x, axis_y) = (&self.axes[x], &self.axes[y]);
+
+        // Merge the steps of both axes by time
+        let mut times : Vec<Seconds> = axis_x.samples.iter().chain(&axis_y.samples).map(|sample| sample.time).collect();
+        times.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let points : Vec<(f32, f32)> = core::iter::once(Seconds::ZERO).chain(times)
+            .map(|time| (axis_x.state_at(time).0.0, axis_y.state_at(time).0.0))
+            .collect();
+
+        let (min_x, max_x) = points.iter().fold((0.0f32, 0.0f32), |(min, max), p| (min.min(p.0), max.max(p.0)));
+        let (min_y, max_y) = points.iter().fold((0.0f32, 0.0f32), |(min, max), p| (min.min(p.1), max.max(p.1)));
+
+        // Keep a margin of 5 % on each side
+        let margin = 0.05 * width.min(height) as f32;
+        let scale = ((width as f32 - 2.0 * margin) / (max_x - min_x).max(f32::EPSILON))
+            .min((height as f32 - 2.0 * margin) / (max_y - min_y).max(f32::EPSILON));
+
+        let mut svg = String::new();
+        write!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            width, height, width, height).unwrap();
+        write!(svg, "<polyline fill=\"none\" stroke=\"black\" stroke-width=\"1\" points=\"").unwrap();
+
+        for (i, (px, py)) in points.iter().enumerate() {
+            // SVG coordinates grow downwards
+            let sx = margin + (px - min_x) * scale;
+            let sy = height as f32 - margin - (py - min_y) * scale;
+
+            if i > 0 {
+                svg.push(' ');
+            }
+
+            write!(svg, "{:.2},{:.2}", sx, sy).unwrap();
+        }
+
+        svg.push_str("\"/></svg>");
+        svg
+    }
+}
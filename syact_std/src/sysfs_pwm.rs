@@ -0,0 +1,159 @@
+//! Pulse generation with the hardware PWM channels exposed by Linux in `/sys/class/pwm`, e.g. the PWM0/PWM1 channels of a 
+//! Raspberry Pi (enabled with the `pwm-2chan` overlay)
+//! 
+//! The pulses are created by the PWM peripheral, so their width and spacing do not jitter with the load of the CPU. Linux 
+//! cannot count the pulses of a PWM channel though, the channel runs continuously for the whole pulse train and is 
+//! disabled once the train has been timed out. Use step batches (see `StepperMotor::set_step_batch`) to generate whole 
+//! trains, single pulses are limited by the time it takes to enable and disable the channel.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use syact::ActuatorError;
+use syact::sync::stepper::{PulseController, PulseGenerator};
+use syact::units::*;
+
+/// The root of the PWM chips in the sysfs
+pub const SYSFS_PWM : &str = "/sys/class/pwm";
+
+/// A hardware PWM channel of the Linux sysfs used as [PulseGenerator]
+pub struct SysfsPwm {
+    path : PathBuf,
+
+    period_ns : u64,
+    frequency_max : Option<Hertz>
+}
+
+impl SysfsPwm {
+    /// Opens the `channel` of the PWM chip `chip` (`/sys/class/pwm/pwmchip<chip>/pwm<channel>`), exporting the channel if it 
+    /// has not been exported yet
+    pub fn new(chip : u32, channel : u32) -> io::Result<Self> {
+        let chip_path = Path::new(SYSFS_PWM).join(format!("pwmchip{}", chip));
+        let path = chip_path.join(format!("pwm{}", channel));
+
+        if !path.exists() {
+            fs::write(chip_path.join("export"), channel.to_string())?;
+        }
+
+        Self::from_path(path)
+    }
+
+    /// Uses the already exported PWM channel at the given `path`, the channel is disabled initially
+    pub fn from_path<P : Into<PathBuf>>(path : P) -> io::Result<Self> {
+        let mut pwm = Self {
+            path: path.into(),
+
+            period_ns: 0,
+            frequency_max: None
+        };
+
+        pwm.set_enabled(false)?;
+        Ok(pwm)
+    }
+
+    /// Limits the frequency of the pulses generated, see [PulseGenerator::frequency_max]
+    pub fn with_frequency_max(mut self, frequency_max : Hertz) -> Self {
+        self.frequency_max = Some(frequency_max);
+        self
+    }
+
+    /// The path of the channel in the sysfs
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn write(&self, attribute : &str, value : u64) -> io::Result<()> {
+        fs::write(self.path.join(attribute), value.to_string())
+    }
+
+    /// Enables or disables the output of the channel
+    pub fn set_enabled(&mut self, enabled : bool) -> io::Result<()> {
+        self.write("enable", enabled as u64)
+    }
+
+    /// Sets the `period` of the channel with a duty cycle of 50 %
+    pub fn set_period(&mut self, period : Seconds) -> io::Result<()> {
+        let period_ns = (period.0 * 1_000_000_000.0) as u64;
+
+        if period_ns == self.period_ns {
+            return Ok(());
+        }
+
+        // The duty cycle must never exceed the period, the order of the writes depends on the change
+        if period_ns < self.period_ns {
+            self.write("duty_cycle", period_ns / 2)?;
+            self.write("period", period_ns)?;
+        } else {
+            self.write("period", period_ns)?;
+            self.write("duty_cycle", period_ns / 2)?;
+        }
+
+        self.period_ns = period_ns;
+        Ok(())
+    }
+}
+
+impl PulseGenerator for SysfsPwm {
+    /// Runs the channel for one `period`, the pulse being `HIGH` for the first half of it
+    fn pulse(&mut self, period : Seconds) -> Result<(), ActuatorError> {
+        self.pulse_train(&[ period ])
+    }
+
+    /// Runs the channel continuously for the sum of all `periods`, each pulse being `HIGH` for the first half of its period
+    /// 
+    /// The period of the next pulse is written while the current one is running, the PWM peripheral takes it over once the 
+    /// current period has ended
+    fn pulse_train(&mut self, periods : &[Seconds]) -> Result<(), ActuatorError> {
+        let Some(first) = periods.first() else {
+            return Ok(());
+        };
+
+        self.set_period(*first).map_err(|_| ActuatorError::IOError)?;
+        self.set_enabled(true).map_err(|_| ActuatorError::IOError)?;
+
+        // The end of each period is timed from the start of the train, so delays of the writes do not add up
+        let mut deadline = Instant::now();
+
+        for (index, period) in periods.iter().enumerate() {
+            deadline += Duration::from_nanos((period.0 * 1_000_000_000.0) as u64);
+
+            if let Some(next) = periods.get(index + 1) {
+                self.set_period(*next).map_err(|_| ActuatorError::IOError)?;
+            }
+
+            spin_sleep::sleep(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        self.set_enabled(false).map_err(|_| ActuatorError::IOError)
+    }
+
+    /// Keeps the channel disabled for the given `time`
+    fn wait(&mut self, time : Seconds) -> Result<(), ActuatorError> {
+        self.set_enabled(false).map_err(|_| ActuatorError::IOError)?;
+        spin_sleep::sleep(time.into());
+        Ok(())
+    }
+
+    fn frequency_max(&self) -> Option<Hertz> {
+        self.frequency_max
+    }
+
+    fn stop(&mut self) -> Result<(), ActuatorError> {
+        self.set_enabled(false).map_err(|_| ActuatorError::IOError)
+    }
+}
+
+/// A stepper controller generating the steps with a hardware PWM channel of the Linux sysfs
+/// 
+/// ```rust,no_run
+/// use syact::sync::stepper::PulseController;
+/// use syact_std::sysfs_pwm::{SysfsPwm, SysfsPwmController};
+/// 
+/// fn controller<DIR : embedded_hal::digital::OutputPin>(pin_dir : DIR) -> std::io::Result<SysfsPwmController<DIR>> {
+///     // PWM0 of a Raspberry Pi
+///     Ok(PulseController::new(SysfsPwm::new(0, 0)?, pin_dir))
+/// }
+/// ```
+pub type SysfsPwmController<DIR> = PulseController<SysfsPwm, DIR>;